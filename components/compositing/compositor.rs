@@ -30,10 +30,13 @@ use num_traits::FromPrimitive;
 #[cfg(feature = "gl")]
 use pixels::PixelFormat;
 use profile_traits::time::{self as profile_time, profile, ProfilerCategory};
-use script_traits::CompositorEvent::{MouseButtonEvent, MouseMoveEvent, TouchEvent, WheelEvent};
+use script_traits::CompositorEvent::{
+    DragEvent, MouseButtonEvent, MouseMoveEvent, PenEvent, TouchEvent, WheelEvent,
+};
 use script_traits::{AnimationState, AnimationTickType, LayoutControlMsg};
+use script_traits::{DragData, DragEventType};
 use script_traits::{
-    MouseButton, MouseEventType, ScrollState, TouchEventType, TouchId, WheelDelta,
+    MouseButton, MouseEventType, PenState, ScrollState, TouchEventType, TouchId, WheelDelta,
 };
 use script_traits::{UntrustedNodeAddress, WindowSizeData, WindowSizeType};
 use servo_geometry::{DeviceIndependentPixel, FramebufferUintLength};
@@ -118,6 +121,9 @@ pub struct IOCompositor<Window: WindowMethods + ?Sized> {
     /// Tracks details about each active pipeline that the compositor knows about.
     pipeline_details: HashMap<PipelineId, PipelineDetails>,
 
+    /// The pipeline that a drag from the host system is currently over, if any.
+    drag_pipeline: Option<PipelineId>,
+
     /// The scene scale, to allow for zooming and high-resolution painting.
     scale: Scale<f32, LayerPixel, DevicePixel>,
 
@@ -304,6 +310,7 @@ impl<Window: WindowMethods + ?Sized> IOCompositor<Window> {
             port: state.receiver,
             root_pipeline: None,
             pipeline_details: HashMap::new(),
+            drag_pipeline: None,
             scale: Scale::new(1.0),
             composition_request: CompositionRequest::NoCompositingNecessary,
             touch_handler: TouchHandler::new(),
@@ -972,6 +979,38 @@ impl<Window: WindowMethods + ?Sized> IOCompositor<Window> {
         }
     }
 
+    fn send_pen_event(&self, event_type: TouchEventType, point: DevicePoint, pen_state: PenState) {
+        let results = self.hit_test_at_point(point);
+        if let Some(item) = results.items.first() {
+            let event = PenEvent(
+                event_type,
+                item.point_in_viewport.to_untyped(),
+                Some(UntrustedNodeAddress(item.tag.0 as *const c_void)),
+                pen_state,
+            );
+            let pipeline_id = PipelineId::from_webrender(item.pipeline);
+            let msg = ConstellationMsg::ForwardEvent(pipeline_id, event);
+            if let Err(e) = self.constellation_chan.send(msg) {
+                warn!("Sending event to constellation failed ({:?}).", e);
+            }
+        }
+    }
+
+    fn send_drag_event(
+        &self,
+        pipeline_id: PipelineId,
+        event_type: DragEventType,
+        point: euclid::default::Point2D<f32>,
+        node_address: Option<UntrustedNodeAddress>,
+        data: DragData,
+    ) {
+        let event = DragEvent(event_type, point, node_address, data);
+        let msg = ConstellationMsg::ForwardEvent(pipeline_id, event);
+        if let Err(e) = self.constellation_chan.send(msg) {
+            warn!("Sending event to constellation failed ({:?}).", e);
+        }
+    }
+
     pub fn send_wheel_event(&mut self, delta: WheelDelta, point: DevicePoint) {
         let results = self.hit_test_at_point(point);
         if let Some(item) = results.items.first() {
@@ -1045,6 +1084,75 @@ impl<Window: WindowMethods + ?Sized> IOCompositor<Window> {
         self.send_touch_event(TouchEventType::Cancel, identifier, point);
     }
 
+    pub fn on_pen_event(
+        &mut self,
+        event_type: TouchEventType,
+        location: DevicePoint,
+        pen_state: PenState,
+    ) {
+        self.send_pen_event(event_type, location, pen_state);
+    }
+
+    pub fn on_drag_event(
+        &mut self,
+        event_type: DragEventType,
+        location: DevicePoint,
+        data: DragData,
+    ) {
+        let results = self.hit_test_at_point(location);
+        let item = match results.items.first() {
+            Some(item) if event_type != DragEventType::Leave => item,
+            _ => {
+                // The drag left the window, or is over something that isn't content.
+                if let Some(pipeline_id) = self.drag_pipeline.take() {
+                    self.send_drag_event(
+                        pipeline_id,
+                        DragEventType::Leave,
+                        location.to_untyped(),
+                        None,
+                        data,
+                    );
+                }
+                return;
+            },
+        };
+
+        let point = item.point_in_viewport.to_untyped();
+        let node_address = Some(UntrustedNodeAddress(item.tag.0 as *const c_void));
+        let pipeline_id = PipelineId::from_webrender(item.pipeline);
+
+        // Each document tracks its own drag session, so moving between documents
+        // ends the drag in the old one and starts it in the new one.
+        if self.drag_pipeline != Some(pipeline_id) {
+            if let Some(old_pipeline_id) = self.drag_pipeline.take() {
+                self.send_drag_event(
+                    old_pipeline_id,
+                    DragEventType::Leave,
+                    point,
+                    None,
+                    data.clone(),
+                );
+            }
+            if event_type != DragEventType::Drop {
+                self.send_drag_event(
+                    pipeline_id,
+                    DragEventType::Enter,
+                    point,
+                    node_address,
+                    data.clone(),
+                );
+            }
+        }
+
+        self.drag_pipeline = match event_type {
+            DragEventType::Drop => None,
+            _ => Some(pipeline_id),
+        };
+        if event_type != DragEventType::Enter {
+            self.send_drag_event(pipeline_id, event_type, point, node_address, data);
+        }
+    }
+
     /// <http://w3c.github.io/touch-events/#mouse-events>
     fn simulate_mouse_click(&mut self, p: DevicePoint) {
         let button = MouseButton::Left;
@@ -11,13 +11,13 @@ pub use crate::compositor::CompositingReason;
 pub use crate::compositor::IOCompositor;
 pub use crate::compositor::ShutdownState;
 pub use crate::compositor_thread::CompositorProxy;
-use embedder_traits::Cursor;
+use embedder_traits::{Cursor, DownloadAction, DownloadId};
 use gfx_traits::Epoch;
 use ipc_channel::ipc::IpcSender;
 use keyboard_types::KeyboardEvent;
 use msg::constellation_msg::PipelineId;
 use msg::constellation_msg::TopLevelBrowsingContextId;
-use msg::constellation_msg::{BrowsingContextId, TraversalDirection};
+use msg::constellation_msg::{BrowsingContextId, BrowsingSessionId, TraversalDirection};
 use script_traits::{
     AnimationTickType, LogEntry, WebDriverCommandMsg, WindowSizeData, WindowSizeType,
 };
@@ -89,8 +89,8 @@ pub enum ConstellationMsg {
     Reload(TopLevelBrowsingContextId),
     /// A log entry, with the top-level browsing context id and thread name
     LogEntry(Option<TopLevelBrowsingContextId>, Option<String>, LogEntry),
-    /// Create a new top level browsing context.
-    NewBrowser(ServoUrl, TopLevelBrowsingContextId),
+    /// Create a new top level browsing context, in the given browsing session.
+    NewBrowser(ServoUrl, TopLevelBrowsingContextId, BrowsingSessionId),
     /// Close a top level browsing context.
     CloseBrowser(TopLevelBrowsingContextId),
     /// Panic a top level browsing context.
@@ -113,6 +113,8 @@ pub enum ConstellationMsg {
     ChangeBrowserVisibility(TopLevelBrowsingContextId, bool),
     /// Virtual keyboard was dismissed
     IMEDismissed,
+    /// Pause, resume or cancel a download.
+    DownloadAction(DownloadId, DownloadAction),
 }
 
 impl fmt::Debug for ConstellationMsg {
@@ -146,6 +148,7 @@ impl fmt::Debug for ConstellationMsg {
             ChangeBrowserVisibility(..) => "ChangeBrowserVisibility",
             IMEDismissed => "IMEDismissed",
             ClearCache => "ClearCache",
+            DownloadAction(..) => "DownloadAction",
         };
         write!(formatter, "ConstellationMsg::{}", variant)
     }
@@ -4,7 +4,7 @@
 
 use self::TouchState::*;
 use euclid::{Point2D, Scale, Vector2D};
-use script_traits::{EventResult, TouchId};
+use script_traits::{EventResult, TouchActions, TouchId};
 use style_traits::DevicePixel;
 
 /// Minimum number of `DeviceIndependentPixel` to begin touch scrolling.
@@ -13,6 +13,8 @@ const TOUCH_PAN_MIN_SCREEN_PX: f32 = 20.0;
 pub struct TouchHandler {
     pub state: TouchState,
     pub active_touch_points: Vec<TouchPoint>,
+    /// The default actions allowed by the `touch-action` of the current touch sequence's target.
+    pub allowed_touch_actions: TouchActions,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -73,6 +75,7 @@ impl TouchHandler {
         TouchHandler {
             state: Nothing,
             active_touch_points: Vec::new(),
+            allowed_touch_actions: TouchActions::all(),
         }
     }
 
@@ -101,22 +104,35 @@ impl TouchHandler {
 
         let action = match self.state {
             Touching => {
-                let delta = point - old_point;
+                let delta = self.allowed_pan(point - old_point);
 
                 if delta.x.abs() > TOUCH_PAN_MIN_SCREEN_PX ||
                     delta.y.abs() > TOUCH_PAN_MIN_SCREEN_PX
                 {
                     self.state = Panning;
                     TouchAction::Scroll(delta)
+                } else if !self
+                    .allowed_touch_actions
+                    .intersects(TouchActions::PAN_X | TouchActions::PAN_Y)
+                {
+                    // Content handles this gesture, so it needs to see the touch moving.
+                    TouchAction::DispatchEvent
                 } else {
                     TouchAction::NoAction
                 }
             },
             Panning => {
-                let delta = point - old_point;
+                let delta = self.allowed_pan(point - old_point);
                 TouchAction::Scroll(delta)
             },
             DefaultPrevented => TouchAction::DispatchEvent,
+            Pinching
+                if !self
+                    .allowed_touch_actions
+                    .contains(TouchActions::PINCH_ZOOM) =>
+            {
+                TouchAction::NoAction
+            },
             Pinching => {
                 let (d0, c0) = self.pinch_distance_and_center();
                 self.active_touch_points[idx].point = point;
@@ -203,15 +219,34 @@ impl TouchHandler {
         if let WaitingForScript = self.state {
             self.state = match result {
                 EventResult::DefaultPrevented => DefaultPrevented,
-                EventResult::DefaultAllowed => match self.touch_count() {
-                    1 => Touching,
-                    2 => Pinching,
-                    _ => MultiTouch,
+                EventResult::DefaultAllowed(allowed_touch_actions) => {
+                    self.allowed_touch_actions = allowed_touch_actions;
+                    match self.touch_count() {
+                        1 => Touching,
+                        2 => Pinching,
+                        _ => MultiTouch,
+                    }
                 },
             }
         }
     }
 
+    /// Removes the components of a pan that the current `touch-action` doesn't allow.
+    fn allowed_pan(&self, delta: Vector2D<f32, DevicePixel>) -> Vector2D<f32, DevicePixel> {
+        Vector2D::new(
+            if self.allowed_touch_actions.contains(TouchActions::PAN_X) {
+                delta.x
+            } else {
+                0.
+            },
+            if self.allowed_touch_actions.contains(TouchActions::PAN_Y) {
+                delta.y
+            } else {
+                0.
+            },
+        )
+    }
+
     fn touch_count(&self) -> usize {
         self.active_touch_points.len()
     }
@@ -4,11 +4,15 @@
 
 //! Abstract windowing methods. The concrete implementations of these can be found in `platform/`.
 
-use embedder_traits::{EmbedderProxy, EventLoopWaker};
+use embedder_traits::{DownloadAction, DownloadId, EmbedderProxy, EventLoopWaker};
 use euclid::Scale;
 use keyboard_types::KeyboardEvent;
-use msg::constellation_msg::{PipelineId, TopLevelBrowsingContextId, TraversalDirection};
-use script_traits::{MediaSessionActionType, MouseButton, TouchEventType, TouchId, WheelDelta};
+use msg::constellation_msg::{BrowsingSessionId, PipelineId};
+use msg::constellation_msg::{TopLevelBrowsingContextId, TraversalDirection};
+use script_traits::{DragData, DragEventType};
+use script_traits::{
+    MediaSessionActionType, MouseButton, PenState, TouchEventType, TouchId, WheelDelta,
+};
 use servo_geometry::DeviceIndependentPixel;
 use servo_media::player::context::{GlApi, GlContext, NativeDisplay};
 use servo_url::ServoUrl;
@@ -61,6 +65,10 @@ pub enum WindowEvent {
     MouseWindowMoveEventClass(DevicePoint),
     /// Touch event: type, identifier, point
     Touch(TouchEventType, TouchId, DevicePoint),
+    /// Pen event: type, point, pressure and orientation of the pen
+    Pen(TouchEventType, DevicePoint, PenState),
+    /// Sent when data from the host system is dragged over, or dropped onto, the window.
+    Drag(DragEventType, DevicePoint, DragData),
     /// Sent when user moves the mouse wheel.
     Wheel(WheelDelta, DevicePoint),
     /// Sent when the user scrolls. The first point is the delta and the second point is the
@@ -82,8 +90,8 @@ pub enum WindowEvent {
     Keyboard(KeyboardEvent),
     /// Sent when Ctr+R/Apple+R is called to reload the current page.
     Reload(TopLevelBrowsingContextId),
-    /// Create a new top level browsing context
-    NewBrowser(ServoUrl, TopLevelBrowsingContextId),
+    /// Create a new top level browsing context, in the given browsing session.
+    NewBrowser(ServoUrl, TopLevelBrowsingContextId, BrowsingSessionId),
     /// Close a top level browsing context
     CloseBrowser(TopLevelBrowsingContextId),
     /// Panic a top level browsing context.
@@ -106,6 +114,8 @@ pub enum WindowEvent {
     ChangeBrowserVisibility(TopLevelBrowsingContextId, bool),
     /// Virtual keyboard was dismissed
     IMEDismissed,
+    /// Pause, resume or cancel a download.
+    DownloadAction(DownloadId, DownloadAction),
 }
 
 impl Debug for WindowEvent {
@@ -120,6 +130,8 @@ impl Debug for WindowEvent {
             WindowEvent::MouseWindowEventClass(..) => write!(f, "Mouse"),
             WindowEvent::MouseWindowMoveEventClass(..) => write!(f, "MouseMove"),
             WindowEvent::Touch(..) => write!(f, "Touch"),
+            WindowEvent::Pen(..) => write!(f, "Pen"),
+            WindowEvent::Drag(..) => write!(f, "Drag"),
             WindowEvent::Wheel(..) => write!(f, "Wheel"),
             WindowEvent::Scroll(..) => write!(f, "Scroll"),
             WindowEvent::Zoom(..) => write!(f, "Zoom"),
@@ -140,6 +152,7 @@ impl Debug for WindowEvent {
             WindowEvent::ChangeBrowserVisibility(..) => write!(f, "ChangeBrowserVisibility"),
             WindowEvent::IMEDismissed => write!(f, "IMEDismissed"),
             WindowEvent::ClearCache => write!(f, "ClearCache"),
+            WindowEvent::DownloadAction(..) => write!(f, "DownloadAction"),
         }
     }
 }
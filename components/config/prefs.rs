@@ -139,12 +139,18 @@ mod gen {
                         enabled: bool,
                     }
                 },
+                caches: {
+                    enabled: bool,
+                },
                 canvas_capture: {
                     enabled: bool,
                 },
                 canvas_text: {
                     enabled: bool,
                 },
+                clipboard: {
+                    enabled: bool,
+                },
                 composition_event: {
                     #[serde(rename = "dom.compositionevent.enabled")]
                     enabled: bool,
@@ -157,6 +163,9 @@ mod gen {
                     dblclick_timeout: i64,
                     dblclick_dist: i64,
                 },
+                drag_and_drop: {
+                    enabled: bool,
+                },
                 forcetouch: {
                     enabled: bool,
                 },
@@ -169,6 +178,15 @@ mod gen {
                 imagebitmap: {
                     enabled: bool,
                 },
+                indexeddb: {
+                    enabled: bool,
+                },
+                intersection_observer: {
+                    enabled: bool,
+                },
+                lazy_loading: {
+                    root_margin: String,
+                },
                 microdata: {
                     testing: {
                         enabled: bool,
@@ -192,6 +210,12 @@ mod gen {
                         allowed_in_nonsecure_contexts: bool,
                     }
                 },
+                pointer_events: {
+                    enabled: bool,
+                },
+                resize_observer: {
+                    enabled: bool,
+                },
                 script: {
                     asynch: bool,
                 },
@@ -475,6 +499,9 @@ mod gen {
                 },
                 mime: {
                     sniff: bool,
+                },
+                mixed_content: {
+                    upgrade: bool,
                 }
             },
             session_history: {
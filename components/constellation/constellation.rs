@@ -94,7 +94,8 @@ use crate::browsingcontext::{
     AllBrowsingContextsIterator, BrowsingContext, FullyActiveBrowsingContextsIterator,
 };
 use crate::event_loop::EventLoop;
-use crate::network_listener::NetworkListener;
+use crate::lock_manager::LockManager;
+use crate::network_listener::{is_secure_context, NetworkListener};
 use crate::pipeline::{InitialPipelineState, Pipeline};
 use crate::serviceworker::ServiceWorkerUnprivilegedContent;
 use crate::session_history::{
@@ -137,16 +138,20 @@ use msg::constellation_msg::{
     PipelineNamespaceId, PipelineNamespaceRequest, TraversalDirection,
 };
 use msg::constellation_msg::{
-    BrowsingContextGroupId, BrowsingContextId, HistoryStateId, PipelineId,
-    TopLevelBrowsingContextId,
+    BrowsingContextGroupId, BrowsingContextId, BrowsingSessionId, HistoryStateId, LockClientId,
+    PipelineId, SharedWorkerId, TopLevelBrowsingContextId,
 };
+use net::storage_thread::StorageThreadFactory;
+use net_traits::filemanager_thread::FileManagerThreadMsg;
 use net_traits::pub_domains::reg_host;
 use net_traits::request::{Referrer, RequestBuilder};
 use net_traits::storage_thread::{StorageThreadMsg, StorageType};
-use net_traits::{self, FetchResponseMsg, IpcSend, ResourceThreads};
+use net_traits::{self, FetchMetadata, FetchResponseMsg, IpcSend, Metadata, ResourceThreads};
+use net_traits::{embedder_policy_from_headers, opener_policy_from_headers};
+use net_traits::{CrossOriginEmbedderPolicy, CrossOriginOpenerPolicy};
 use profile_traits::mem;
 use profile_traits::time;
-use script_traits::CompositorEvent::{MouseButtonEvent, MouseMoveEvent};
+use script_traits::CompositorEvent::{DragEvent, MouseButtonEvent, MouseMoveEvent};
 use script_traits::{webdriver_msg, LogEntry, ScriptToConstellationChan, ServiceWorkerMsg};
 use script_traits::{
     AnimationState, AnimationTickType, AuxiliaryBrowsingContextLoadInfo, BroadcastMsg,
@@ -154,6 +159,7 @@ use script_traits::{
 };
 use script_traits::{ConstellationControlMsg, DiscardBrowsingContext};
 use script_traits::{DocumentActivity, DocumentState, LayoutControlMsg, LoadData, LoadOrigin};
+use script_traits::{DragEventType, MediaSessionActionType, MouseEventType};
 use script_traits::{HistoryEntryReplacement, IFrameSizeMsg, WindowSizeData, WindowSizeType};
 use script_traits::{
     IFrameLoadInfo, IFrameLoadInfoWithData, IFrameSandboxState, TimerSchedulerMsg,
@@ -162,7 +168,7 @@ use script_traits::{
     Job, LayoutMsg as FromLayoutMsg, ScriptMsg as FromScriptMsg, ScriptThreadFactory,
     ServiceWorkerManagerFactory,
 };
-use script_traits::{MediaSessionActionType, MouseEventType};
+use script_traits::{LockManagerMsg, SharedWorkerMsg};
 use script_traits::{MessagePortMsg, PortMessageTask, StructuredSerializedData};
 use script_traits::{SWManagerMsg, SWManagerSenders, UpdatePipelineIdReason, WebDriverCommandMsg};
 use serde::{Deserialize, Serialize};
@@ -173,6 +179,7 @@ use servo_url::{Host, ImmutableOrigin, ServoUrl};
 use std::borrow::{Cow, ToOwned};
 use std::collections::hash_map::Entry;
 use std::collections::{HashMap, HashSet, VecDeque};
+use std::iter;
 use std::marker::PhantomData;
 use std::mem::replace;
 use std::process;
@@ -222,6 +229,19 @@ struct MessagePortInfo {
     entangled_with: Option<MessagePortId>,
 }
 
+/// Info related to a shared worker tracked by the constellation.
+struct SharedWorkerInfo {
+    /// The id of the worker.
+    id: SharedWorkerId,
+
+    /// The sender of messages to the worker.
+    sender: IpcSender<SharedWorkerMsg>,
+
+    /// The pipelines whose documents are connected to the worker.
+    /// <https://html.spec.whatwg.org/multipage/#the-worker's-owners>
+    clients: HashSet<PipelineId>,
+}
+
 /// Webrender related objects required by WebGPU threads
 struct WebrenderWGPU {
     /// Webrender API.
@@ -244,6 +264,9 @@ struct Browser {
 
     /// The joint session history for this browser.
     session_history: JointSessionHistory,
+
+    /// The browsing session this browser belongs to.
+    session_id: BrowsingSessionId,
 }
 
 /// A browsing context group.
@@ -355,6 +378,11 @@ pub struct Constellation<Message, LTF, STF, SWF> {
     /// browsing.
     private_resource_threads: ResourceThreads,
 
+    /// Channels to the resource-related threads of each private browsing session.
+    /// Dropping them discards all the state of the session, once the pipelines
+    /// of the session are gone as well.
+    private_session_resource_threads: HashMap<BrowsingSessionId, ResourceThreads>,
+
     /// A channel for the constellation to send messages to the font
     /// cache thread.
     font_cache_thread: FontCacheThread,
@@ -367,8 +395,8 @@ pub struct Constellation<Message, LTF, STF, SWF> {
     /// bluetooth thread.
     bluetooth_thread: IpcSender<BluetoothRequest>,
 
-    /// A map of origin to sender to a Service worker manager.
-    sw_managers: HashMap<ImmutableOrigin, IpcSender<ServiceWorkerMsg>>,
+    /// A map of browsing session and origin to sender to a Service worker manager.
+    sw_managers: HashMap<(BrowsingSessionId, ImmutableOrigin), IpcSender<ServiceWorkerMsg>>,
 
     /// An IPC channel for Service Worker Manager threads to send
     /// messages to the constellation.  This is the SW Manager thread's
@@ -420,8 +448,18 @@ pub struct Constellation<Message, LTF, STF, SWF> {
     /// A map of broadcast routers to their IPC sender.
     broadcast_routers: HashMap<BroadcastChannelRouterId, IpcSender<BroadcastMsg>>,
 
-    /// A map of origin to a map of channel-name to a list of relevant routers.
-    broadcast_channels: HashMap<ImmutableOrigin, HashMap<String, Vec<BroadcastChannelRouterId>>>,
+    /// A map of browsing session and origin to a map of channel-name to a list of relevant routers.
+    broadcast_channels: HashMap<
+        (BrowsingSessionId, ImmutableOrigin),
+        HashMap<String, Vec<BroadcastChannelRouterId>>,
+    >,
+
+    /// A map of browsing session, origin, script url and name to a running shared worker.
+    shared_workers:
+        HashMap<(BrowsingSessionId, ImmutableOrigin, ServoUrl, String), SharedWorkerInfo>,
+
+    /// The locks held and requested through the Web Locks API.
+    lock_manager: LockManager,
 
     /// The set of all the pipelines in the browser.  (See the `pipeline` module
     /// for more details.)
@@ -872,6 +910,7 @@ where
                     bluetooth_thread: state.bluetooth_thread,
                     public_resource_threads: state.public_resource_threads,
                     private_resource_threads: state.private_resource_threads,
+                    private_session_resource_threads: HashMap::new(),
                     font_cache_thread: state.font_cache_thread,
                     sw_managers: Default::default(),
                     swmanager_receiver: swmanager_receiver,
@@ -882,6 +921,8 @@ where
                     message_port_routers: HashMap::new(),
                     broadcast_routers: HashMap::new(),
                     broadcast_channels: HashMap::new(),
+                    shared_workers: HashMap::new(),
+                    lock_manager: LockManager::new(),
                     pipelines: HashMap::new(),
                     browsing_contexts: HashMap::new(),
                     pending_changes: vec![],
@@ -1128,10 +1169,11 @@ where
             },
         };
 
-        let resource_threads = if is_private {
-            self.private_resource_threads.clone()
-        } else {
-            self.public_resource_threads.clone()
+        let session_id = self.browsing_session_id(top_level_browsing_context_id);
+        let resource_threads = match self.private_session_resource_threads.get(&session_id) {
+            Some(resource_threads) => resource_threads.clone(),
+            None if is_private => self.private_resource_threads.clone(),
+            None => self.public_resource_threads.clone(),
         };
 
         let result = Pipeline::spawn::<Message, LTF, STF>(InitialPipelineState {
@@ -1206,6 +1248,40 @@ where
         self.pipelines.insert(pipeline_id, pipeline.pipeline);
     }
 
+    /// The browsing session of a top-level browsing context.
+    fn browsing_session_id(
+        &self,
+        top_level_browsing_context_id: TopLevelBrowsingContextId,
+    ) -> BrowsingSessionId {
+        self.browsers
+            .get(&top_level_browsing_context_id)
+            .map(|browser| browser.session_id)
+            .unwrap_or_default()
+    }
+
+    /// The browsing session of the browser a pipeline belongs to.
+    fn pipeline_browsing_session_id(&self, pipeline_id: PipelineId) -> BrowsingSessionId {
+        self.pipelines
+            .get(&pipeline_id)
+            .map(|pipeline| self.browsing_session_id(pipeline.top_level_browsing_context_id))
+            .unwrap_or_default()
+    }
+
+    /// The resource threads used by a pipeline.
+    fn pipeline_resource_threads(&self, pipeline_id: PipelineId) -> Option<&ResourceThreads> {
+        let pipeline = self.pipelines.get(&pipeline_id)?;
+        let session_id = self.browsing_session_id(pipeline.top_level_browsing_context_id);
+        if let Some(resource_threads) = self.private_session_resource_threads.get(&session_id) {
+            return Some(resource_threads);
+        }
+        let browsing_context = self.browsing_contexts.get(&pipeline.browsing_context_id)?;
+        if browsing_context.is_private {
+            Some(&self.private_resource_threads)
+        } else {
+            Some(&self.public_resource_threads)
+        }
+    }
+
     /// Get an iterator for the fully active browsing contexts in a subtree.
     fn fully_active_descendant_browsing_contexts_iter(
         &self,
@@ -1425,6 +1501,13 @@ where
 
     fn handle_request_from_network_listener(&mut self, message: (PipelineId, FetchResponseMsg)) {
         let (id, message_) = message;
+        if let FetchResponseMsg::ProcessResponse(Ok(ref fetch_metadata)) = message_ {
+            let metadata = match *fetch_metadata {
+                FetchMetadata::Filtered { ref unsafe_, .. } => unsafe_,
+                FetchMetadata::Unfiltered(ref metadata) => metadata,
+            };
+            self.handle_navigation_response_policies(id, metadata);
+        }
         let result = match self.pipelines.get(&id) {
             Some(pipeline) => {
                 let msg = ConstellationControlMsg::NavigationResponse(id, message_);
@@ -1439,6 +1522,171 @@ where
         }
     }
 
+    /// Record the embedder and opener policies of the document that is about to be
+    /// loaded in a pipeline, and move its top-level browsing context into a new
+    /// browsing context group if the opener policies require it.
+    /// <https://html.spec.whatwg.org/multipage/#browsing-context-group-switches-due-to-cross-origin-opener-policy>
+    fn handle_navigation_response_policies(
+        &mut self,
+        pipeline_id: PipelineId,
+        metadata: &Metadata,
+    ) {
+        let is_secure_context =
+            is_secure_context(&metadata.final_url, &self.ancestor_origins(pipeline_id));
+        let (embedder_policy, opener_policy) = match metadata.headers {
+            Some(ref headers) => (
+                embedder_policy_from_headers(headers, is_secure_context),
+                opener_policy_from_headers(headers, is_secure_context),
+            ),
+            None => Default::default(),
+        };
+        let (browsing_context_id, top_level_browsing_context_id, opener) =
+            match self.pipelines.get_mut(&pipeline_id) {
+                Some(pipeline) => {
+                    pipeline.embedder_policy = embedder_policy;
+                    pipeline.opener_policy = opener_policy;
+                    (
+                        pipeline.browsing_context_id,
+                        pipeline.top_level_browsing_context_id,
+                        pipeline.opener,
+                    )
+                },
+                None => return,
+            };
+
+        // Opener policies only apply to top-level browsing contexts.
+        if browsing_context_id != BrowsingContextId::from(top_level_browsing_context_id) {
+            return;
+        }
+
+        let active_pipeline = self
+            .browsing_contexts
+            .get(&browsing_context_id)
+            .and_then(|browsing_context| self.pipelines.get(&browsing_context.pipeline_id))
+            .filter(|active_pipeline| active_pipeline.id != pipeline_id);
+        let is_initial_about_blank = active_pipeline.map_or(true, |active_pipeline| {
+            active_pipeline.url.as_str() == "about:blank"
+        });
+        let (active_origin, active_opener_policy) = match active_pipeline {
+            Some(active_pipeline) if !is_initial_about_blank => {
+                (active_pipeline.url.origin(), active_pipeline.opener_policy)
+            },
+            // The initial about:blank document inherits the policy of its opener.
+            _ => match opener
+                .and_then(|opener| self.browsing_contexts.get(&opener))
+                .and_then(|opener| self.pipelines.get(&opener.pipeline_id))
+            {
+                Some(opener) => (opener.url.origin(), opener.opener_policy),
+                None => (
+                    ImmutableOrigin::new_opaque(),
+                    CrossOriginOpenerPolicy::UnsafeNone,
+                ),
+            },
+        };
+
+        // https://html.spec.whatwg.org/multipage/#check-browsing-context-group-switch-coop-value
+        let requires_switch = match (active_opener_policy, opener_policy) {
+            // Step 1.
+            (CrossOriginOpenerPolicy::UnsafeNone, CrossOriginOpenerPolicy::UnsafeNone) => false,
+            // Step 2.
+            (
+                CrossOriginOpenerPolicy::SameOriginAllowPopups,
+                CrossOriginOpenerPolicy::UnsafeNone,
+            ) if is_initial_about_blank => false,
+            // Step 3.
+            (active_opener_policy, opener_policy)
+                if active_opener_policy == opener_policy &&
+                    active_origin == metadata.final_url.origin() =>
+            {
+                false
+            },
+            // Step 4.
+            _ => true,
+        };
+        if requires_switch {
+            self.switch_browsing_context_group(pipeline_id, top_level_browsing_context_id);
+        }
+    }
+
+    /// Move a top-level browsing context into a new browsing context group,
+    /// severing any opener relationship with the browsing contexts it leaves behind.
+    fn switch_browsing_context_group(
+        &mut self,
+        pipeline_id: PipelineId,
+        top_level_browsing_context_id: TopLevelBrowsingContextId,
+    ) {
+        let browsing_context_id = BrowsingContextId::from(top_level_browsing_context_id);
+        let (host, event_loop, opener) = match self.pipelines.get(&pipeline_id) {
+            Some(pipeline) => (
+                reg_host(&pipeline.url),
+                Rc::downgrade(&pipeline.event_loop),
+                pipeline.opener,
+            ),
+            None => return,
+        };
+        let is_alone_in_group = self.browsing_context_group_set.values().any(|bc_group| {
+            bc_group.top_level_browsing_context_set.len() == 1 &&
+                bc_group
+                    .top_level_browsing_context_set
+                    .contains(&top_level_browsing_context_id)
+        });
+        if is_alone_in_group && opener.is_none() {
+            return;
+        }
+
+        debug!(
+            "Moving {} into a new browsing context group.",
+            top_level_browsing_context_id
+        );
+        for bc_group in self.browsing_context_group_set.values_mut() {
+            bc_group
+                .top_level_browsing_context_set
+                .remove(&top_level_browsing_context_id);
+        }
+        // https://html.spec.whatwg.org/multipage/#creating-a-new-browsing-context-group
+        let mut new_bc_group: BrowsingContextGroup = Default::default();
+        let new_bc_group_id = self.next_browsing_context_group_id();
+        new_bc_group
+            .top_level_browsing_context_set
+            .insert(top_level_browsing_context_id);
+        if let Some(host) = host {
+            new_bc_group.event_loops.insert(host, event_loop);
+        }
+        self.browsing_context_group_set
+            .insert(new_bc_group_id, new_bc_group);
+        if let Some(browsing_context) = self.browsing_contexts.get_mut(&browsing_context_id) {
+            browsing_context.bc_group_id = new_bc_group_id;
+        }
+
+        // The documents of this browsing context, including the one being loaded, no
+        // longer have an opener, and documents opened by this browsing context no
+        // longer have it as their opener.
+        let disowned: Vec<PipelineId> =
+            self.pipelines
+                .values()
+                .filter(|pipeline| {
+                    (pipeline.browsing_context_id == browsing_context_id &&
+                        pipeline.opener.is_some()) ||
+                        pipeline.opener == Some(browsing_context_id)
+                })
+                .map(|pipeline| pipeline.id)
+                .collect();
+        for id in disowned {
+            let result = match self.pipelines.get_mut(&id) {
+                Some(pipeline) => {
+                    pipeline.opener = None;
+                    pipeline
+                        .event_loop
+                        .send(ConstellationControlMsg::DisownOpener(id))
+                },
+                None => continue,
+            };
+            if let Err(e) = result {
+                self.handle_send_error(id, e);
+            }
+        }
+    }
+
     fn handle_request_from_swmanager(&mut self, message: SWManagerMsg) {
         match message {
             SWManagerMsg::PostMessageToClient => {
@@ -1531,6 +1779,20 @@ where
                 self.public_resource_threads.clear_cache();
                 self.private_resource_threads.clear_cache();
             },
+            FromCompositorMsg::DownloadAction(id, action) => {
+                // Each browsing session has downloads of its own, and download ids are
+                // unique across sessions, so only the session that owns the download
+                // acts on it.
+                let resource_threads = iter::once(&self.public_resource_threads)
+                    .chain(iter::once(&self.private_resource_threads))
+                    .chain(self.private_session_resource_threads.values());
+                for resource_threads in resource_threads {
+                    let msg = net_traits::CoreResourceMsg::DownloadAction(id, action);
+                    if let Err(e) = resource_threads.send(msg) {
+                        warn!("Sending download action to resource thread failed ({}).", e);
+                    }
+                }
+            },
             // Load a new page from a typed url
             // If there is already a pending page (self.pending_changes), it will not be overridden;
             // However, if the id is not encompassed by another change, it will be.
@@ -1576,8 +1838,12 @@ where
             },
             // Create a new top level browsing context. Will use response_chan to return
             // the browsing context id.
-            FromCompositorMsg::NewBrowser(url, top_level_browsing_context_id) => {
-                self.handle_new_top_level_browsing_context(url, top_level_browsing_context_id);
+            FromCompositorMsg::NewBrowser(url, top_level_browsing_context_id, session_id) => {
+                self.handle_new_top_level_browsing_context(
+                    url,
+                    top_level_browsing_context_id,
+                    session_id,
+                );
             },
             // Close a top level browsing context.
             FromCompositorMsg::CloseBrowser(top_level_browsing_context_id) => {
@@ -1655,6 +1921,11 @@ where
             content, source_pipeline_id
         );
 
+        // A shared worker can outlive the pipeline that started it.
+        if let FromScriptMsg::SharedWorkerClosed(worker_id) = content {
+            return self.handle_shared_worker_closed(worker_id);
+        }
+
         let source_top_ctx_id = match self
             .pipelines
             .get(&source_pipeline_id)
@@ -1813,6 +2084,14 @@ where
             FromScriptMsg::Focus => {
                 self.handle_focus_msg(source_pipeline_id);
             },
+            FromScriptMsg::FullscreenChanged(fullscreen) => {
+                self.handle_fullscreen_changed_msg(source_pipeline_id, fullscreen);
+            },
+            FromScriptMsg::ExitFullscreen(browsing_context_id) => {
+                let browsing_context_id = browsing_context_id
+                    .unwrap_or_else(|| BrowsingContextId::from(source_top_ctx_id));
+                self.switch_fullscreen_mode(browsing_context_id);
+            },
             FromScriptMsg::VisibilityChangeComplete(is_visible) => {
                 self.handle_visibility_change_complete(source_pipeline_id, is_visible);
             },
@@ -1888,13 +2167,53 @@ where
             FromScriptMsg::ScheduleJob(job) => {
                 self.handle_schedule_serviceworker_job(source_pipeline_id, job);
             },
+            FromScriptMsg::ConnectSharedWorker {
+                script_url,
+                name,
+                origin,
+                data,
+            } => {
+                self.handle_connect_shared_worker(
+                    source_pipeline_id,
+                    script_url,
+                    name,
+                    origin,
+                    data,
+                );
+            },
+            FromScriptMsg::SharedWorkerClosed(_) => {
+                unreachable!("Shared worker closures are handled regardless of their pipeline.")
+            },
+            FromScriptMsg::NewLockClient(client_id, ipc_sender, origin) => {
+                self.handle_new_lock_client(source_pipeline_id, client_id, ipc_sender, origin);
+            },
+            FromScriptMsg::RemoveLockClient(client_id) => {
+                self.lock_manager
+                    .remove_client(source_pipeline_id, client_id);
+            },
+            FromScriptMsg::RequestLock(request) => {
+                self.lock_manager.request(source_pipeline_id, request);
+            },
+            FromScriptMsg::ReleaseLock(client_id, id) => {
+                self.lock_manager.release(source_pipeline_id, client_id, id);
+            },
+            FromScriptMsg::QueryLocks(client_id) => {
+                self.lock_manager.query(source_pipeline_id, client_id);
+            },
             FromScriptMsg::ForwardDOMMessage(msg_vec, scope_url) => {
-                if let Some(mgr) = self.sw_managers.get(&scope_url.origin()) {
+                let session_id = self.pipeline_browsing_session_id(source_pipeline_id);
+                if let Some(mgr) = self.sw_managers.get(&(session_id, scope_url.origin())) {
                     let _ = mgr.send(ServiceWorkerMsg::ForwardDOMMessage(msg_vec, scope_url));
                 } else {
                     warn!("Unable to forward DOMMessage for postMessage call");
                 }
             },
+            FromScriptMsg::SetNavigationPreload(scope_url, state) => {
+                let session_id = self.pipeline_browsing_session_id(source_pipeline_id);
+                if let Some(mgr) = self.sw_managers.get(&(session_id, scope_url.origin())) {
+                    let _ = mgr.send(ServiceWorkerMsg::SetNavigationPreload(scope_url, state));
+                }
+            },
             FromScriptMsg::BroadcastStorageEvent(storage, url, key, old_value, new_value) => {
                 self.handle_broadcast_storage_event(
                     source_pipeline_id,
@@ -1984,7 +2303,11 @@ where
                 "Attempt to schedule broadcast from an origin not matching the origin of the msg."
             );
         }
-        if let Some(channels) = self.broadcast_channels.get(&message.origin) {
+        let session_id = self.pipeline_browsing_session_id(pipeline_id);
+        if let Some(channels) = self
+            .broadcast_channels
+            .get(&(session_id, message.origin.clone()))
+        {
             let routers = match channels.get(&message.channel_name) {
                 Some(routers) => routers,
                 None => return warn!("Broadcast to channel name without active routers."),
@@ -2026,7 +2349,11 @@ where
         {
             return warn!("Attempt to remove channel name from an unexpected origin.");
         }
-        if let Some(channels) = self.broadcast_channels.get_mut(&origin) {
+        let session_id = self.pipeline_browsing_session_id(pipeline_id);
+        if let Some(channels) = self
+            .broadcast_channels
+            .get_mut(&(session_id, origin.clone()))
+        {
             let is_empty = if let Some(routers) = channels.get_mut(&channel_name) {
                 routers.retain(|router| router != &router_id);
                 routers.is_empty()
@@ -2061,9 +2388,10 @@ where
         {
             return warn!("Attempt to add channel name from an unexpected origin.");
         }
+        let session_id = self.pipeline_browsing_session_id(pipeline_id);
         let channels = self
             .broadcast_channels
-            .entry(origin)
+            .entry((session_id, origin))
             .or_insert_with(HashMap::new);
 
         let routers = channels.entry(channel_name).or_insert_with(Vec::new);
@@ -2578,7 +2906,7 @@ where
     /// <https://w3c.github.io/ServiceWorker/#dfn-job-queue>
     ///
     /// The Job Queue is essentially the channel to a SW manager,
-    /// which are scoped per origin.
+    /// which are scoped per browsing session and origin.
     fn handle_schedule_serviceworker_job(&mut self, pipeline_id: PipelineId, job: Job) {
         let origin = job.scope_url.origin();
 
@@ -2591,15 +2919,22 @@ where
             );
         }
 
+        let session_id = self.pipeline_browsing_session_id(pipeline_id);
+        let resource_sender: IpcSender<net_traits::CoreResourceMsg> =
+            match self.private_session_resource_threads.get(&session_id) {
+                Some(resource_threads) => resource_threads.sender(),
+                None => self.public_resource_threads.sender(),
+            };
+
         // This match is equivalent to Entry.or_insert_with but allows for early return.
-        let sw_manager = match self.sw_managers.entry(origin.clone()) {
+        let sw_manager = match self.sw_managers.entry((session_id, origin.clone())) {
             Entry::Occupied(entry) => entry.into_mut(),
             Entry::Vacant(entry) => {
                 let (own_sender, receiver) = ipc::channel().expect("Failed to create IPC channel!");
 
                 let sw_senders = SWManagerSenders {
                     swmanager_sender: self.swmanager_sender.clone(),
-                    resource_sender: self.public_resource_threads.sender(),
+                    resource_sender,
                     own_sender: own_sender.clone(),
                     receiver,
                 };
@@ -2618,6 +2953,119 @@ where
         let _ = sw_manager.send(ServiceWorkerMsg::ScheduleJob(job));
     }
 
+    /// <https://html.spec.whatwg.org/multipage/#dom-sharedworker>
+    ///
+    /// Shared workers are scoped per browsing session, origin, url and name.
+    fn handle_connect_shared_worker(
+        &mut self,
+        pipeline_id: PipelineId,
+        script_url: ServoUrl,
+        name: String,
+        origin: ImmutableOrigin,
+        data: StructuredSerializedData,
+    ) {
+        if self
+            .check_origin_against_pipeline(&pipeline_id, &origin)
+            .is_err()
+        {
+            return warn!(
+                "Attempt to connect to a shared worker from an origin not matching the origin of the pipeline."
+            );
+        }
+        if script_url.origin() != origin {
+            return warn!("Attempt to connect to a cross-origin shared worker.");
+        }
+
+        let session_id = self.pipeline_browsing_session_id(pipeline_id);
+
+        // This match is equivalent to Entry.or_insert_with but allows for early return.
+        let shared_worker =
+            match self
+                .shared_workers
+                .entry((session_id, origin, script_url.clone(), name.clone()))
+            {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    // The worker runs in the event loop of the first document connecting to it.
+                    let event_loop = match self.pipelines.get(&pipeline_id) {
+                        Some(pipeline) => pipeline.event_loop.clone(),
+                        None => {
+                            return warn!(
+                                "Pipeline {} closed before starting a shared worker.",
+                                pipeline_id
+                            )
+                        },
+                    };
+                    let (sender, receiver) = match ipc::channel() {
+                        Ok(channel) => channel,
+                        Err(e) => return warn!("Failed to create shared worker channel ({}).", e),
+                    };
+                    let worker_id = SharedWorkerId::new();
+                    let msg = ConstellationControlMsg::SpawnSharedWorker {
+                        pipeline_id,
+                        worker_id,
+                        script_url,
+                        name,
+                        receiver,
+                    };
+                    if let Err(e) = event_loop.send(msg) {
+                        return warn!("Failed to spawn shared worker ({}).", e);
+                    }
+                    entry.insert(SharedWorkerInfo {
+                        id: worker_id,
+                        sender,
+                        clients: HashSet::new(),
+                    })
+                },
+            };
+        shared_worker.clients.insert(pipeline_id);
+        if let Err(e) = shared_worker.sender.send(SharedWorkerMsg::Connect(data)) {
+            warn!("Failed to connect to shared worker ({}).", e);
+        }
+    }
+
+    /// A shared worker closed itself, or was shut down.
+    fn handle_shared_worker_closed(&mut self, worker_id: SharedWorkerId) {
+        self.shared_workers
+            .retain(|_, shared_worker| shared_worker.id != worker_id);
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#the-worker's-lifetime>
+    ///
+    /// Shut down the shared workers whose last client was the given pipeline.
+    fn remove_shared_worker_client(&mut self, pipeline_id: PipelineId) {
+        self.shared_workers.retain(|_, shared_worker| {
+            if !shared_worker.clients.remove(&pipeline_id) || !shared_worker.clients.is_empty() {
+                return true;
+            }
+            if let Err(e) = shared_worker.sender.send(SharedWorkerMsg::Exit) {
+                warn!("Exit shared worker failed ({})", e);
+            }
+            false
+        });
+    }
+
+    /// Register a lock client with the lock manager of its browsing session and origin.
+    fn handle_new_lock_client(
+        &mut self,
+        pipeline_id: PipelineId,
+        client_id: LockClientId,
+        ipc_sender: IpcSender<LockManagerMsg>,
+        origin: ImmutableOrigin,
+    ) {
+        if self
+            .check_origin_against_pipeline(&pipeline_id, &origin)
+            .is_err()
+        {
+            return warn!(
+                "Attempt to add a lock client from an origin not matching the origin of the pipeline."
+            );
+        }
+        let session_id = self.pipeline_browsing_session_id(pipeline_id);
+        self.lock_manager
+            .add_client(client_id, ipc_sender, pipeline_id, (session_id, origin));
+    }
+
     fn handle_broadcast_storage_event(
         &self,
         pipeline_id: PipelineId,
@@ -2628,8 +3076,13 @@ where
         new_value: Option<String>,
     ) {
         let origin = url.origin();
+        // Storage is not shared between browsing sessions, and neither are its events.
+        let session_id = self.pipeline_browsing_session_id(pipeline_id);
         for pipeline in self.pipelines.values() {
-            if (pipeline.id != pipeline_id) && (pipeline.url.origin() == origin) {
+            if (pipeline.id != pipeline_id) &&
+                (pipeline.url.origin() == origin) &&
+                self.browsing_session_id(pipeline.top_level_browsing_context_id) == session_id
+            {
                 let msg = ConstellationControlMsg::DispatchStorageEvent(
                     pipeline.id,
                     storage,
@@ -2770,6 +3223,13 @@ where
             }
         }
 
+        debug!("Exiting shared workers.");
+        for (_, shared_worker) in self.shared_workers.drain() {
+            if let Err(e) = shared_worker.sender.send(SharedWorkerMsg::Exit) {
+                warn!("Exit shared worker failed ({})", e);
+            }
+        }
+
         debug!("Exiting Canvas Paint thread.");
         if let Err(e) = self.canvas_chan.send(ConstellationCanvasMsg::Exit) {
             warn!("Exit Canvas Paint thread failed ({})", e);
@@ -2835,6 +3295,8 @@ where
     fn handle_pipeline_exited(&mut self, pipeline_id: PipelineId) {
         debug!("Pipeline {:?} exited.", pipeline_id);
         self.pipelines.remove(&pipeline_id);
+        self.remove_shared_worker_client(pipeline_id);
+        self.lock_manager.remove_pipeline(pipeline_id);
     }
 
     fn handle_send_error(&mut self, pipeline_id: PipelineId, err: IpcError) {
@@ -2992,6 +3454,26 @@ where
             self.pressed_mouse_buttons = 0;
         }
 
+        // Files dragged in from the host system can only be read by the document
+        // that they are dragged over or dropped onto.
+        let granted_files = match event {
+            DragEvent(DragEventType::Enter, _, _, ref data) |
+            DragEvent(DragEventType::Drop, _, _, ref data) => data.files.clone(),
+            _ => vec![],
+        };
+        if !granted_files.is_empty() {
+            let msg = net_traits::CoreResourceMsg::ToFileManager(
+                FileManagerThreadMsg::GrantDroppedFiles(granted_files),
+            );
+            let result = match self.pipeline_resource_threads(destination_pipeline_id) {
+                Some(resource_threads) => resource_threads.send(msg),
+                None => return,
+            };
+            if let Err(e) = result {
+                warn!("Granting dropped files failed ({}).", e);
+            }
+        }
+
         let msg = ConstellationControlMsg::SendEvent(destination_pipeline_id, event);
         let result = match self.pipelines.get(&destination_pipeline_id) {
             None => {
@@ -3012,6 +3494,7 @@ where
         &mut self,
         url: ServoUrl,
         top_level_browsing_context_id: TopLevelBrowsingContextId,
+        session_id: BrowsingSessionId,
     ) {
         let window_size = self.window_size.initial_viewport;
         let pipeline_id = PipelineId::new();
@@ -3029,9 +3512,13 @@ where
             None,
         );
         let sandbox = IFrameSandboxState::IFrameUnsandboxed;
-        let is_private = false;
+        let is_private = session_id.is_private();
         let is_visible = true;
 
+        if is_private {
+            self.start_private_session(session_id);
+        }
+
         // Register this new top-level browsing context id as a browser and set
         // its focused browsing context to be itself.
         self.browsers.insert(
@@ -3039,6 +3526,7 @@ where
             Browser {
                 focused_browsing_context_id: browsing_context_id,
                 session_history: JointSessionHistory::new(),
+                session_id,
             },
         );
 
@@ -3083,7 +3571,9 @@ where
     ) {
         let browsing_context_id = BrowsingContextId::from(top_level_browsing_context_id);
         self.close_browsing_context(browsing_context_id, ExitPipelineMode::Normal);
-        self.browsers.remove(&top_level_browsing_context_id);
+        if let Some(browser) = self.browsers.remove(&top_level_browsing_context_id) {
+            self.maybe_end_private_session(browser.session_id);
+        }
         if self.active_browser_id == Some(top_level_browsing_context_id) {
             self.active_browser_id = None;
         }
@@ -3099,6 +3589,61 @@ where
             .remove(&browsing_context.bc_group_id);
     }
 
+    /// Spawn the resource-related threads of a private browsing session, if they don't exist yet.
+    /// The state of these threads is only kept in memory, apart from any other session.
+    fn start_private_session(&mut self, session_id: BrowsingSessionId) {
+        if self
+            .private_session_resource_threads
+            .contains_key(&session_id)
+        {
+            return;
+        }
+        debug!("Starting private browsing session {:?}.", session_id);
+        let (core_sender, core_receiver) = ipc::channel().expect("Failed to create IPC channel!");
+        let msg = net_traits::CoreResourceMsg::NewPrivateSession(core_receiver);
+        if let Err(e) = self.public_resource_threads.send(msg) {
+            return warn!("Starting private browsing session failed ({})", e);
+        }
+        let storage_sender: IpcSender<StorageThreadMsg> = StorageThreadFactory::new(None);
+        self.private_session_resource_threads.insert(
+            session_id,
+            ResourceThreads::new(core_sender, storage_sender),
+        );
+    }
+
+    /// Discard the state of a private browsing session once its last browser has closed.
+    fn maybe_end_private_session(&mut self, session_id: BrowsingSessionId) {
+        if !session_id.is_private() ||
+            self.browsers
+                .values()
+                .any(|browser| browser.session_id == session_id)
+        {
+            return;
+        }
+        debug!("Ending private browsing session {:?}.", session_id);
+
+        // The service workers of the session hold on to its resource threads.
+        let sw_managers: Vec<_> = self
+            .sw_managers
+            .keys()
+            .filter(|(id, _)| *id == session_id)
+            .cloned()
+            .collect();
+        for key in sw_managers {
+            if let Some(mgr) = self.sw_managers.remove(&key) {
+                if let Err(e) = mgr.send(ServiceWorkerMsg::Exit) {
+                    warn!("Exit service worker manager failed ({})", e);
+                }
+            }
+        }
+        self.broadcast_channels
+            .retain(|(id, _), _| *id != session_id);
+
+        // The resource threads of the session discard their state
+        // once the last sender to them has been dropped.
+        self.private_session_resource_threads.remove(&session_id);
+    }
+
     fn handle_iframe_size_msg(&mut self, iframe_sizes: Vec<IFrameSizeMsg>) {
         for IFrameSizeMsg { data, type_ } in iframe_sizes {
             let window_size = WindowSizeData {
@@ -3156,9 +3701,17 @@ where
         request_builder: RequestBuilder,
         cancel_chan: IpcReceiver<()>,
     ) {
+        let parent_embedder_policy = self
+            .pipelines
+            .get(&id)
+            .and_then(|pipeline| self.parent_pipeline_id(pipeline.browsing_context_id))
+            .and_then(|parent_pipeline_id| self.pipelines.get(&parent_pipeline_id))
+            .map(|parent| parent.embedder_policy);
         let listener = NetworkListener::new(
             request_builder,
             id,
+            self.ancestor_origins(id),
+            parent_embedder_policy,
             self.public_resource_threads.clone(),
             self.network_listener_sender.clone(),
         );
@@ -3166,6 +3719,40 @@ where
         listener.initiate_fetch(Some(cancel_chan));
     }
 
+    /// The origins of the documents of all ancestors of the browsing context
+    /// containing `pipeline_id`, from the parent up to the top-level one.
+    /// Used to enforce the CSP `frame-ancestors` directive on navigation responses.
+    fn ancestor_origins(&self, pipeline_id: PipelineId) -> Vec<ImmutableOrigin> {
+        let mut origins = vec![];
+        let mut browsing_context_id = match self.pipelines.get(&pipeline_id) {
+            Some(pipeline) => pipeline.browsing_context_id,
+            None => return origins,
+        };
+        while let Some(parent_pipeline_id) = self.parent_pipeline_id(browsing_context_id) {
+            let parent = match self.pipelines.get(&parent_pipeline_id) {
+                Some(parent) => parent,
+                None => break,
+            };
+            origins.push(parent.url.origin());
+            browsing_context_id = parent.browsing_context_id;
+        }
+        origins
+    }
+
+    /// The parent pipeline of a browsing context, including nested browsing
+    /// contexts whose first document has not been activated yet.
+    fn parent_pipeline_id(&self, browsing_context_id: BrowsingContextId) -> Option<PipelineId> {
+        match self.browsing_contexts.get(&browsing_context_id) {
+            Some(browsing_context) => browsing_context.parent_pipeline_id,
+            None => self
+                .pending_changes
+                .iter()
+                .find(|change| change.browsing_context_id == browsing_context_id)
+                .and_then(|change| change.new_browsing_context_info.as_ref())
+                .and_then(|info| info.parent_pipeline_id),
+        }
+    }
+
     // The script thread associated with pipeline_id has loaded a URL in an
     // iframe via script. This will result in a new pipeline being spawned and
     // a child being added to the parent browsing context. This message is never
@@ -3380,11 +3967,14 @@ where
 
         assert!(!self.pipelines.contains_key(&new_pipeline_id));
         self.pipelines.insert(new_pipeline_id, pipeline);
+        // An auxiliary browsing context shares the browsing session of its opener.
+        let session_id = self.pipeline_browsing_session_id(opener_pipeline_id);
         self.browsers.insert(
             new_top_level_browsing_context_id,
             Browser {
                 focused_browsing_context_id: new_browsing_context_id,
                 session_history: JointSessionHistory::new(),
+                session_id,
             },
         );
 
@@ -4322,6 +4912,46 @@ where
         self.focus_parent_pipeline(parent_browsing_context_id);
     }
 
+    /// Makes the iframe that a pipeline is in enter or leave fullscreen in the document of
+    /// its parent, which passes it on to its own parent if that changes its fullscreen
+    /// state too.
+    fn handle_fullscreen_changed_msg(&mut self, pipeline_id: PipelineId, fullscreen: bool) {
+        let browsing_context_id = match self.pipelines.get(&pipeline_id) {
+            Some(pipeline) => pipeline.browsing_context_id,
+            None => return warn!("Pipeline {:?} fullscreen after closure.", pipeline_id),
+        };
+        let parent_pipeline_id = match self.browsing_contexts.get(&browsing_context_id) {
+            Some(ctx) => ctx.parent_pipeline_id,
+            None => {
+                return warn!(
+                    "Browsing context {:?} fullscreen after closure.",
+                    browsing_context_id
+                );
+            },
+        };
+        let parent_pipeline_id = match parent_pipeline_id {
+            Some(parent_id) => parent_id,
+            None => return,
+        };
+        let msg = ConstellationControlMsg::SetIFrameFullscreen(
+            parent_pipeline_id,
+            browsing_context_id,
+            fullscreen,
+        );
+        let result = match self.pipelines.get(&parent_pipeline_id) {
+            Some(pipeline) => pipeline.event_loop.send(msg),
+            None => {
+                return warn!(
+                    "Pipeline {:?} fullscreen after closure.",
+                    parent_pipeline_id
+                )
+            },
+        };
+        if let Err(e) = result {
+            self.handle_send_error(parent_pipeline_id, e);
+        }
+    }
+
     fn handle_remove_iframe_msg(
         &mut self,
         browsing_context_id: BrowsingContextId,
@@ -5239,22 +5869,29 @@ where
         }
     }
 
-    // Handle switching from fullscreen mode
+    // Handle switching from fullscreen mode, which exits fullscreen in the documents of
+    // the browsing context and of all of its descendants.
     fn switch_fullscreen_mode(&mut self, browsing_context_id: BrowsingContextId) {
-        if let Some(browsing_context) = self.browsing_contexts.get(&browsing_context_id) {
-            let pipeline_id = browsing_context.pipeline_id;
-            let pipeline = match self.pipelines.get(&pipeline_id) {
+        let pipeline_ids: Vec<PipelineId> = self
+            .fully_active_descendant_browsing_contexts_iter(browsing_context_id)
+            .map(|browsing_context| browsing_context.pipeline_id)
+            .collect();
+        for pipeline_id in pipeline_ids {
+            let result = match self.pipelines.get(&pipeline_id) {
                 None => {
-                    return warn!(
+                    warn!(
                         "Pipeline {:?} switched from fullscreen mode after closing.",
                         pipeline_id
-                    )
+                    );
+                    continue;
                 },
-                Some(pipeline) => pipeline,
+                Some(pipeline) => pipeline
+                    .event_loop
+                    .send(ConstellationControlMsg::ExitFullScreen(pipeline_id)),
             };
-            let _ = pipeline
-                .event_loop
-                .send(ConstellationControlMsg::ExitFullScreen(pipeline.id));
+            if let Err(e) = result {
+                self.handle_send_error(pipeline_id, e);
+            }
         }
     }
 
@@ -5479,6 +6116,7 @@ where
             .or_insert_with(|| Browser {
                 focused_browsing_context_id: BrowsingContextId::from(top_level_id),
                 session_history: JointSessionHistory::new(),
+                session_id: BrowsingSessionId::Default,
             })
             .session_history
     }
@@ -14,6 +14,7 @@ extern crate serde;
 mod browsingcontext;
 mod constellation;
 mod event_loop;
+mod lock_manager;
 mod network_listener;
 mod pipeline;
 mod sandboxing;
@@ -0,0 +1,297 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! The lock manager backing the Web Locks API.
+//!
+//! Locks are scoped per browsing session and origin, and shared by all the
+//! windows and workers of that origin, whichever event loop they run in.
+//! <https://w3c.github.io/web-locks/#lock-managers>
+
+use ipc_channel::ipc::IpcSender;
+use msg::constellation_msg::{BrowsingSessionId, LockClientId, PipelineId};
+use script_traits::{LockInfo, LockManagerMsg, LockManagerSnapshot, LockMode, LockRequest};
+use servo_url::ImmutableOrigin;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+
+/// A window or worker using the lock manager of its origin.
+struct LockClient {
+    /// The sender used to answer the requests of the client.
+    sender: IpcSender<LockManagerMsg>,
+    /// The pipeline the client belongs to.
+    pipeline_id: PipelineId,
+    /// The browsing session and origin of the client.
+    key: (BrowsingSessionId, ImmutableOrigin),
+}
+
+/// The held locks and the lock request queues of an origin.
+#[derive(Default)]
+struct OriginLockManager {
+    /// <https://w3c.github.io/web-locks/#lock-manager-held-lock-set>
+    held: Vec<LockRequest>,
+    /// <https://w3c.github.io/web-locks/#lock-request-queue-map>
+    queues: HashMap<String, VecDeque<LockRequest>>,
+}
+
+impl OriginLockManager {
+    /// <https://w3c.github.io/web-locks/#lock-request-grantable>, for the
+    /// request at the front of its queue.
+    fn is_grantable(&self, request: &LockRequest) -> bool {
+        match request.mode {
+            LockMode::Exclusive => !self.held.iter().any(|lock| lock.name == request.name),
+            LockMode::Shared => !self
+                .held
+                .iter()
+                .any(|lock| lock.name == request.name && lock.mode == LockMode::Exclusive),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.held.is_empty() && self.queues.is_empty()
+    }
+}
+
+pub struct LockManager {
+    /// The registered lock clients.
+    clients: HashMap<LockClientId, LockClient>,
+    /// The lock managers, per browsing session and origin.
+    managers: HashMap<(BrowsingSessionId, ImmutableOrigin), OriginLockManager>,
+}
+
+impl LockManager {
+    pub fn new() -> LockManager {
+        LockManager {
+            clients: HashMap::new(),
+            managers: HashMap::new(),
+        }
+    }
+
+    /// Register a new lock client, for the manager of its browsing session and origin.
+    pub fn add_client(
+        &mut self,
+        client_id: LockClientId,
+        sender: IpcSender<LockManagerMsg>,
+        pipeline_id: PipelineId,
+        key: (BrowsingSessionId, ImmutableOrigin),
+    ) {
+        self.clients.insert(
+            client_id,
+            LockClient {
+                sender,
+                pipeline_id,
+                key,
+            },
+        );
+    }
+
+    /// Forget a lock client, releasing its held locks and aborting its pending requests.
+    pub fn remove_client(&mut self, pipeline_id: PipelineId, client_id: LockClientId) {
+        let key = match self.clients.entry(client_id) {
+            Entry::Occupied(entry) => {
+                if entry.get().pipeline_id != pipeline_id {
+                    return warn!("Attempt to remove a lock client of another pipeline.");
+                }
+                entry.remove().key
+            },
+            Entry::Vacant(_) => return warn!("Attempt to remove an unknown lock client."),
+        };
+        let manager = match self.managers.get_mut(&key) {
+            Some(manager) => manager,
+            None => return,
+        };
+        let mut names = vec![];
+        manager.held.retain(|lock| {
+            if lock.client_id != client_id {
+                return true;
+            }
+            names.push(lock.name.clone());
+            false
+        });
+        for (name, queue) in manager.queues.iter_mut() {
+            let length = queue.len();
+            queue.retain(|request| request.client_id != client_id);
+            if queue.len() != length {
+                names.push(name.clone());
+            }
+        }
+        for name in names {
+            self.process_queue(&key, &name);
+        }
+        self.remove_manager_if_empty(&key);
+    }
+
+    /// Forget the lock clients of a pipeline that exited.
+    pub fn remove_pipeline(&mut self, pipeline_id: PipelineId) {
+        let client_ids: Vec<LockClientId> = self
+            .clients
+            .iter()
+            .filter(|(_, client)| client.pipeline_id == pipeline_id)
+            .map(|(client_id, _)| *client_id)
+            .collect();
+        for client_id in client_ids {
+            self.remove_client(pipeline_id, client_id);
+        }
+    }
+
+    /// <https://w3c.github.io/web-locks/#request-a-lock>
+    pub fn request(&mut self, pipeline_id: PipelineId, request: LockRequest) {
+        let key = match self.client_key(pipeline_id, request.client_id) {
+            Some(key) => key,
+            None => return warn!("Lock requested by an unknown lock client."),
+        };
+        let name = request.name.clone();
+        let manager = self.managers.entry(key.clone()).or_default();
+
+        if request.steal {
+            // Step 7.1, release the held locks with the same name.
+            let mut stolen = vec![];
+            manager.held.retain(|lock| {
+                if lock.name != request.name {
+                    return true;
+                }
+                stolen.push((lock.client_id, lock.id));
+                false
+            });
+            for (client_id, id) in stolen {
+                if let Some(client) = self.clients.get(&client_id) {
+                    let _ = client.sender.send(LockManagerMsg::Stolen(id));
+                }
+            }
+            // Step 7.2, the request goes first in the queue.
+            manager
+                .queues
+                .entry(name.clone())
+                .or_default()
+                .push_front(request);
+        } else {
+            // Step 8.
+            let queue_is_empty = manager
+                .queues
+                .get(&request.name)
+                .map_or(true, |queue| queue.is_empty());
+            if request.if_available && !(queue_is_empty && manager.is_grantable(&request)) {
+                if let Some(client) = self.clients.get(&request.client_id) {
+                    let _ = client.sender.send(LockManagerMsg::NotGranted(request.id));
+                }
+                return self.remove_manager_if_empty(&key);
+            }
+            // Step 9.
+            manager
+                .queues
+                .entry(name.clone())
+                .or_default()
+                .push_back(request);
+        }
+
+        // Step 11.
+        self.process_queue(&key, &name);
+    }
+
+    /// <https://w3c.github.io/web-locks/#release-the-lock>, or
+    /// <https://w3c.github.io/web-locks/#abort-the-request> if the request is still pending.
+    pub fn release(&mut self, pipeline_id: PipelineId, client_id: LockClientId, id: u64) {
+        let key = match self.client_key(pipeline_id, client_id) {
+            Some(key) => key,
+            None => return warn!("Lock released by an unknown lock client."),
+        };
+        let manager = match self.managers.get_mut(&key) {
+            Some(manager) => manager,
+            None => return,
+        };
+        let is_request = |lock: &LockRequest| lock.client_id == client_id && lock.id == id;
+        let name = if let Some(index) = manager.held.iter().position(is_request) {
+            manager.held.remove(index).name
+        } else {
+            let pending = manager.queues.iter_mut().find_map(|(name, queue)| {
+                queue
+                    .iter()
+                    .position(is_request)
+                    .and_then(|index| queue.remove(index))
+                    .map(|_| name.clone())
+            });
+            match pending {
+                Some(name) => name,
+                // The lock was stolen, or its request was not granted.
+                None => return,
+            }
+        };
+        self.process_queue(&key, &name);
+        self.remove_manager_if_empty(&key);
+    }
+
+    /// <https://w3c.github.io/web-locks/#snapshot-the-lock-state>
+    pub fn query(&self, pipeline_id: PipelineId, client_id: LockClientId) {
+        let client = match self.clients.get(&client_id) {
+            Some(client) if client.pipeline_id == pipeline_id => client,
+            _ => return warn!("Locks queried by an unknown lock client."),
+        };
+        let mut snapshot = LockManagerSnapshot::default();
+        if let Some(manager) = self.managers.get(&client.key) {
+            snapshot.held = manager.held.iter().map(lock_info).collect();
+            snapshot.pending = manager
+                .queues
+                .values()
+                .flat_map(|queue| queue.iter().map(lock_info))
+                .collect();
+        }
+        let _ = client.sender.send(LockManagerMsg::Snapshot(snapshot));
+    }
+
+    fn client_key(
+        &self,
+        pipeline_id: PipelineId,
+        client_id: LockClientId,
+    ) -> Option<(BrowsingSessionId, ImmutableOrigin)> {
+        self.clients
+            .get(&client_id)
+            .filter(|client| client.pipeline_id == pipeline_id)
+            .map(|client| client.key.clone())
+    }
+
+    /// <https://w3c.github.io/web-locks/#process-the-lock-request-queue>
+    fn process_queue(&mut self, key: &(BrowsingSessionId, ImmutableOrigin), name: &str) {
+        let manager = match self.managers.get_mut(key) {
+            Some(manager) => manager,
+            None => return,
+        };
+        loop {
+            let request = match manager.queues.get(name).and_then(|queue| queue.front()) {
+                Some(request) if manager.is_grantable(request) => request.clone(),
+                _ => break,
+            };
+            if let Some(queue) = manager.queues.get_mut(name) {
+                queue.pop_front();
+            }
+            if let Some(client) = self.clients.get(&request.client_id) {
+                let _ = client.sender.send(LockManagerMsg::Granted(request.id));
+            }
+            manager.held.push(request);
+        }
+        if manager
+            .queues
+            .get(name)
+            .map_or(false, |queue| queue.is_empty())
+        {
+            manager.queues.remove(name);
+        }
+    }
+
+    fn remove_manager_if_empty(&mut self, key: &(BrowsingSessionId, ImmutableOrigin)) {
+        if self
+            .managers
+            .get(key)
+            .map_or(false, |manager| manager.is_empty())
+        {
+            self.managers.remove(key);
+        }
+    }
+}
+
+fn lock_info(request: &LockRequest) -> LockInfo {
+    LockInfo {
+        name: request.name.clone(),
+        mode: request.mode,
+        client_id: request.client_id.to_string(),
+    }
+}
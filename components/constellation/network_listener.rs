@@ -11,16 +11,25 @@ use http::HeaderMap;
 use ipc_channel::ipc;
 use ipc_channel::router::ROUTER;
 use msg::constellation_msg::PipelineId;
+use net::csp_directives::should_navigation_response_be_blocked_by_frame_ancestors;
 use net::http_loader::{set_default_accept, set_default_accept_language};
 use net_traits::request::{Destination, Referrer, RequestBuilder};
 use net_traits::response::ResponseInit;
-use net_traits::{CoreResourceMsg, FetchChannels, FetchMetadata, FetchResponseMsg};
+use net_traits::{csp_list_from_headers, embedder_policy_from_headers, CrossOriginEmbedderPolicy};
+use net_traits::{CoreResourceMsg, FetchChannels, FetchMetadata, FetchResponseMsg, Metadata};
 use net_traits::{IpcSend, NetworkError, ResourceThreads};
+use servo_url::{ImmutableOrigin, ServoUrl};
 
 pub struct NetworkListener {
     res_init: Option<ResponseInit>,
     request_builder: RequestBuilder,
     pipeline_id: PipelineId,
+    /// The origins of the ancestors of the browsing context being navigated,
+    /// checked against the `frame-ancestors` directive of the response.
+    ancestor_origins: Vec<ImmutableOrigin>,
+    /// The embedder policy of the parent document, if the browsing context
+    /// being navigated is a nested one.
+    parent_embedder_policy: Option<CrossOriginEmbedderPolicy>,
     resource_threads: ResourceThreads,
     sender: Sender<(PipelineId, FetchResponseMsg)>,
     should_send: bool,
@@ -30,6 +39,8 @@ impl NetworkListener {
     pub fn new(
         request_builder: RequestBuilder,
         pipeline_id: PipelineId,
+        ancestor_origins: Vec<ImmutableOrigin>,
+        parent_embedder_policy: Option<CrossOriginEmbedderPolicy>,
         resource_threads: ResourceThreads,
         sender: Sender<(PipelineId, FetchResponseMsg)>,
     ) -> NetworkListener {
@@ -37,6 +48,8 @@ impl NetworkListener {
             res_init: None,
             request_builder,
             pipeline_id,
+            ancestor_origins,
+            parent_embedder_policy,
             resource_threads,
             sender,
             should_send: false,
@@ -52,6 +65,8 @@ impl NetworkListener {
             resource_threads: self.resource_threads.clone(),
             sender: self.sender.clone(),
             pipeline_id: self.pipeline_id.clone(),
+            ancestor_origins: self.ancestor_origins.clone(),
+            parent_embedder_policy: self.parent_embedder_policy,
             should_send: false,
         };
 
@@ -68,7 +83,7 @@ impl NetworkListener {
 
                 CoreResourceMsg::Fetch(
                     listener.request_builder.clone(),
-                    FetchChannels::ResponseMsg(ipc_sender, cancel_chan),
+                    FetchChannels::ResponseMsg(ipc_sender, cancel_chan, None),
                 )
             },
         };
@@ -144,6 +159,28 @@ impl NetworkListener {
                         // Ideally the Fetch code would handle manual redirects on its own
                         self.initiate_fetch(None);
                     }
+                    _ if self.is_blocked_by_frame_ancestors(metadata) => {
+                        warn!(
+                            "Refused to display {} in a frame because of frame-ancestors",
+                            metadata.final_url
+                        );
+                        self.should_send = true;
+                        self.send(FetchResponseMsg::ProcessResponse(Err(
+                            NetworkError::Internal("Blocked by frame-ancestors".into()),
+                        )));
+                    },
+                    _ if self.is_blocked_by_embedder_policy(metadata) => {
+                        warn!(
+                            "Refused to display {} in a frame because of Cross-Origin-Embedder-Policy",
+                            metadata.final_url
+                        );
+                        self.should_send = true;
+                        self.send(FetchResponseMsg::ProcessResponse(Err(
+                            NetworkError::Internal(
+                                "Blocked by Cross-Origin-Embedder-Policy".into(),
+                            ),
+                        )));
+                    },
                     _ => {
                         // Response should be processed by script thread.
                         self.should_send = true;
@@ -158,6 +195,49 @@ impl NetworkListener {
         };
     }
 
+    /// <https://www.w3.org/TR/CSP/#frame-ancestors-navigation-response>
+    fn is_blocked_by_frame_ancestors(&self, metadata: &Metadata) -> bool {
+        // Top-level navigations are never subject to frame-ancestors.
+        if self.ancestor_origins.is_empty() {
+            return false;
+        }
+        let csp_list = match metadata
+            .headers
+            .as_ref()
+            .and_then(|headers| csp_list_from_headers(headers))
+        {
+            Some(csp_list) => csp_list,
+            None => return false,
+        };
+        should_navigation_response_be_blocked_by_frame_ancestors(
+            &csp_list,
+            &metadata.final_url,
+            &self.ancestor_origins,
+        )
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#check-a-navigation-response's-adherence-to-its-embedder-policy>
+    fn is_blocked_by_embedder_policy(&self, metadata: &Metadata) -> bool {
+        // Step 1.
+        let parent_embedder_policy = match self.parent_embedder_policy {
+            Some(policy) => policy,
+            None => return false,
+        };
+        // Documents with a local scheme inherit the embedder policy of their parent.
+        if metadata.final_url.is_local_scheme() {
+            return false;
+        }
+        let is_secure_context = is_secure_context(&metadata.final_url, &self.ancestor_origins);
+        let embedder_policy = metadata
+            .headers
+            .as_ref()
+            .map(|headers| embedder_policy_from_headers(headers, is_secure_context))
+            .unwrap_or_default();
+        // Steps 2-5.
+        parent_embedder_policy.is_compatible_with_cross_origin_isolation() &&
+            !embedder_policy.is_compatible_with_cross_origin_isolation()
+    }
+
     fn send(&mut self, msg: FetchResponseMsg) {
         if self.should_send {
             if let Err(e) = self.sender.send((self.pipeline_id, msg)) {
@@ -169,3 +249,14 @@ impl NetworkListener {
         }
     }
 }
+
+/// Whether a document loaded from `url`, in a browsing context whose ancestors have
+/// `ancestor_origins`, is a secure context: the document and all its ancestors must
+/// have been delivered over potentially trustworthy URLs.
+/// <https://html.spec.whatwg.org/multipage/#secure-context>
+pub fn is_secure_context(url: &ServoUrl, ancestor_origins: &[ImmutableOrigin]) -> bool {
+    url.is_potentially_trustworthy() &&
+        ancestor_origins
+            .iter()
+            .all(|origin| origin.is_potentially_trustworthy())
+}
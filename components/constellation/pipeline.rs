@@ -30,7 +30,7 @@ use msg::constellation_msg::{
 };
 use net::image_cache::ImageCacheImpl;
 use net_traits::image_cache::ImageCache;
-use net_traits::ResourceThreads;
+use net_traits::{CrossOriginEmbedderPolicy, CrossOriginOpenerPolicy, ResourceThreads};
 use profile_traits::mem as profile_mem;
 use profile_traits::time;
 use script_traits::{
@@ -100,6 +100,12 @@ pub struct Pipeline {
 
     /// The title of this pipeline's document.
     pub title: String,
+
+    /// The embedder policy of this pipeline's document.
+    pub embedder_policy: CrossOriginEmbedderPolicy,
+
+    /// The cross-origin opener policy of this pipeline's document.
+    pub opener_policy: CrossOriginOpenerPolicy,
 }
 
 /// Initial setup data needed to construct a pipeline.
@@ -383,6 +389,8 @@ impl Pipeline {
             history_states: HashSet::new(),
             completely_loaded: false,
             title: String::new(),
+            embedder_policy: CrossOriginEmbedderPolicy::UnsafeNone,
+            opener_policy: CrossOriginOpenerPolicy::UnsafeNone,
         };
 
         pipeline.notify_visibility(is_visible);
@@ -19,6 +19,7 @@ use keyboard_types::KeyboardEvent;
 use msg::constellation_msg::{InputMethodType, PipelineId, TopLevelBrowsingContextId};
 use servo_url::ServoUrl;
 use std::fmt::{Debug, Error, Formatter};
+use std::path::PathBuf;
 use webrender_api::units::{DeviceIntPoint, DeviceIntRect, DeviceIntSize};
 
 pub use webxr_api::MainThreadWaker as EventLoopWaker;
@@ -169,9 +170,9 @@ pub enum EmbedderMsg {
     /// Sends an unconsumed key event back to the embedder.
     Keyboard(KeyboardEvent),
     /// Gets system clipboard contents
-    GetClipboardContents(IpcSender<String>),
+    GetClipboardContents(IpcSender<ClipboardData>),
     /// Sets system clipboard contents
-    SetClipboardContents(String),
+    SetClipboardContents(ClipboardData),
     /// Changes the cursor.
     SetCursor(Cursor),
     /// A favicon was detected
@@ -209,6 +210,18 @@ pub enum EmbedderMsg {
     MediaSessionEvent(MediaSessionEvent),
     /// Report the status of Devtools Server with a token that can be used to bypass the permission prompt.
     OnDevtoolsStarted(Result<u16, ()>, String),
+    /// Ask the embedder where a download should be saved, given a suggested file name.
+    /// Replying with `None` cancels the download.
+    SelectDownloadPath(String, IpcSender<Option<PathBuf>>),
+    /// A download has started writing to the given path.
+    DownloadStarted(DownloadId, ServoUrl, PathBuf),
+    /// Progress of a download: the number of bytes received so far and,
+    /// if it is known, the total number of bytes.
+    DownloadProgress(DownloadId, u64, Option<u64>),
+    /// A download was paused, resumed, completed, cancelled or failed.
+    DownloadStatusChanged(DownloadId, DownloadStatus),
+    /// The security state of the page changed, e.g. because it loaded mixed content.
+    SecurityStateChanged(SecurityState),
 }
 
 impl Debug for EmbedderMsg {
@@ -245,6 +258,11 @@ impl Debug for EmbedderMsg {
             EmbedderMsg::MediaSessionEvent(..) => write!(f, "MediaSessionEvent"),
             EmbedderMsg::OnDevtoolsStarted(..) => write!(f, "OnDevtoolsStarted"),
             EmbedderMsg::ShowContextMenu(..) => write!(f, "ShowContextMenu"),
+            EmbedderMsg::SelectDownloadPath(..) => write!(f, "SelectDownloadPath"),
+            EmbedderMsg::DownloadStarted(..) => write!(f, "DownloadStarted"),
+            EmbedderMsg::DownloadProgress(..) => write!(f, "DownloadProgress"),
+            EmbedderMsg::DownloadStatusChanged(..) => write!(f, "DownloadStatusChanged"),
+            EmbedderMsg::SecurityStateChanged(..) => write!(f, "SecurityStateChanged"),
         }
     }
 }
@@ -316,6 +334,57 @@ pub enum MediaSessionEvent {
     SetPositionState(MediaPositionState),
 }
 
+/// Identifies a download handled by the network stack.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct DownloadId(pub u64);
+
+/// The state of a download.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum DownloadStatus {
+    /// The response body is being written to disk.
+    InProgress,
+    /// The download was paused by the embedder.
+    Paused,
+    /// The whole response body was written to disk.
+    Complete,
+    /// The download was cancelled, and any partial file removed.
+    Cancelled,
+    /// The download failed, for the given reason.
+    Failed(String),
+}
+
+impl DownloadStatus {
+    /// Whether the download can no longer make progress.
+    pub fn is_finished(&self) -> bool {
+        match *self {
+            DownloadStatus::InProgress | DownloadStatus::Paused => false,
+            DownloadStatus::Complete | DownloadStatus::Cancelled | DownloadStatus::Failed(_) => {
+                true
+            },
+        }
+    }
+}
+
+/// Actions the embedder can take on an ongoing download.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum DownloadAction {
+    Pause,
+    Resume,
+    Cancel,
+}
+
+/// The security state of a page, which the embedder can use to choose a lock icon.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum SecurityState {
+    /// The page was not loaded over a secure connection.
+    Insecure,
+    /// The page and all of its subresources were loaded over secure connections.
+    Secure,
+    /// The page was loaded over a secure connection, but displays insecure
+    /// (optionally-blockable) mixed content.
+    MixedContent,
+}
+
 /// Enum with variants that match the DOM PermissionName enum
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub enum PermissionName {
@@ -330,6 +399,48 @@ pub enum PermissionName {
     BackgroundSync,
     Bluetooth,
     PersistentStorage,
+    ClipboardRead,
+    ClipboardWrite,
+}
+
+/// One representation of the contents of the system clipboard.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ClipboardRepresentation {
+    /// The MIME type of the data, such as `text/plain` or `image/png`.
+    pub mime_type: String,
+    pub data: Vec<u8>,
+}
+
+/// The contents of the system clipboard, as representations of the same data in
+/// different formats, in order of preference.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ClipboardData {
+    pub representations: Vec<ClipboardRepresentation>,
+}
+
+impl ClipboardData {
+    pub fn from_text(text: String) -> ClipboardData {
+        ClipboardData {
+            representations: vec![ClipboardRepresentation {
+                mime_type: "text/plain".to_owned(),
+                data: text.into_bytes(),
+            }],
+        }
+    }
+
+    /// The data of the representation with this MIME type, if any.
+    pub fn get(&self, mime_type: &str) -> Option<&[u8]> {
+        self.representations
+            .iter()
+            .find(|representation| representation.mime_type.eq_ignore_ascii_case(mime_type))
+            .map(|representation| &*representation.data)
+    }
+
+    /// The plain text representation of the contents, if any.
+    pub fn text(&self) -> Option<String> {
+        self.get("text/plain")
+            .map(|data| String::from_utf8_lossy(data).into_owned())
+    }
 }
 
 /// Information required to display a permission prompt
@@ -20,6 +20,8 @@ use euclid::Size2D as TypedSize2D;
 use ipc_channel::ipc::IpcSender;
 use msg::constellation_msg::PipelineId;
 use script_layout_interface::rpc::TextIndexResponse;
+use script_layout_interface::rpc::{BoxSizes, BoxSizesResponse};
+use script_layout_interface::rpc::{CaretRectResponse, TextPositionResponse};
 use script_layout_interface::rpc::{ContentBoxResponse, ContentBoxesResponse, LayoutRPC};
 use script_layout_interface::rpc::{NodeGeometryResponse, NodeScrollIdResponse};
 use script_layout_interface::rpc::{OffsetParentResponse, ResolvedStyleResponse};
@@ -100,6 +102,15 @@ pub struct LayoutThreadData {
 
     /// A queued response for the viewport dimensions for a given browsing context.
     pub inner_window_dimensions_response: Option<TypedSize2D<f32, CSSPixel>>,
+
+    /// A queued response for the box sizes of a node.
+    pub box_sizes_response: Option<BoxSizes>,
+
+    /// A queued response for the caret position closest to a point.
+    pub text_position_response: Option<(UntrustedNodeAddress, usize)>,
+
+    /// A queued response for the rectangle of a caret.
+    pub caret_rect_response: Option<Rect<Au>>,
 }
 
 pub struct LayoutRPCImpl(pub Arc<Mutex<LayoutThreadData>>);
@@ -209,6 +220,24 @@ impl LayoutRPC for LayoutRPCImpl {
         let rw_data = rw_data.lock().unwrap();
         rw_data.inner_window_dimensions_response.clone()
     }
+
+    fn box_sizes(&self) -> BoxSizesResponse {
+        let &LayoutRPCImpl(ref rw_data) = self;
+        let rw_data = rw_data.lock().unwrap();
+        BoxSizesResponse(rw_data.box_sizes_response)
+    }
+
+    fn text_position(&self) -> TextPositionResponse {
+        let &LayoutRPCImpl(ref rw_data) = self;
+        let rw_data = rw_data.lock().unwrap();
+        TextPositionResponse(rw_data.text_position_response)
+    }
+
+    fn caret_rect(&self) -> CaretRectResponse {
+        let &LayoutRPCImpl(ref rw_data) = self;
+        let rw_data = rw_data.lock().unwrap();
+        CaretRectResponse(rw_data.caret_rect_response)
+    }
 }
 
 struct UnioningFragmentBorderBoxIterator {
@@ -391,6 +420,15 @@ pub fn process_content_box_request(
     iterator.rect
 }
 
+pub fn process_box_sizes_request(
+    requested_node: OpaqueNode,
+    layout_root: &mut dyn Flow,
+) -> Option<BoxSizes> {
+    let mut iterator = BoxSizesIterator::new(requested_node);
+    sequential::iterate_through_flow_tree_fragment_border_boxes(layout_root, &mut iterator);
+    iterator.box_sizes
+}
+
 pub fn process_content_boxes_request(
     requested_node: OpaqueNode,
     layout_root: &mut dyn Flow,
@@ -402,6 +440,59 @@ pub fn process_content_boxes_request(
     iterator.rects
 }
 
+// TODO: Carets of editing hosts aren't supported by this layout engine.
+pub fn process_text_position_request(
+    _requested_node: OpaqueNode,
+    _point: Point2D<Au>,
+) -> Option<(UntrustedNodeAddress, usize)> {
+    None
+}
+
+pub fn process_caret_rect_request(_requested_node: OpaqueNode, _offset: usize) -> Option<Rect<Au>> {
+    None
+}
+
+struct BoxSizesIterator {
+    node_address: OpaqueNode,
+    box_sizes: Option<BoxSizes>,
+}
+
+impl BoxSizesIterator {
+    fn new(node_address: OpaqueNode) -> BoxSizesIterator {
+        BoxSizesIterator {
+            node_address: node_address,
+            box_sizes: None,
+        }
+    }
+}
+
+impl FragmentBorderBoxIterator for BoxSizesIterator {
+    fn process(&mut self, fragment: &Fragment, _: i32, border_box: &Rect<Au>) {
+        if self.box_sizes.is_some() {
+            return;
+        }
+        let writing_mode = fragment.style.writing_mode;
+        let border = fragment.border_width().to_physical(writing_mode);
+        let border_padding = fragment.border_padding.to_physical(writing_mode);
+        let content_box = border_box.inner_rect(border_padding);
+        self.box_sizes = Some(BoxSizes {
+            content_box: Rect::new(
+                Point2D::new(
+                    border_padding.left - border.left,
+                    border_padding.top - border.top,
+                ),
+                content_box.size,
+            ),
+            border_box: border_box.size,
+            vertical: writing_mode.is_vertical(),
+        });
+    }
+
+    fn should_process(&mut self, fragment: &Fragment) -> bool {
+        fragment.node == self.node_address
+    }
+}
+
 struct FragmentClientRectQueryIterator {
     node_address: OpaqueNode,
     client_rect: Rect<i32>,
@@ -39,11 +39,24 @@ pub struct WebRenderImageInfo {
 type ItemTag = (u64, u16);
 type HitInfo = Option<ItemTag>;
 
+/// The caret of the focused editing host, at a position in a text node, or at the start
+/// of an element that has no text.
+#[derive(Clone, Copy)]
+pub struct Caret {
+    pub node: OpaqueNode,
+    /// The offset of the caret in bytes in the text of the node.
+    pub offset: usize,
+}
+
 pub struct DisplayListBuilder<'a> {
     /// The current SpatialId and ClipId information for this `DisplayListBuilder`.
     current_space_and_clip: wr::SpaceAndClipInfo,
 
     element_for_canvas_background: OpaqueNode,
+
+    /// The caret to paint, if any.
+    caret: Option<Caret>,
+
     pub context: &'a LayoutContext<'a>,
     pub wr: wr::DisplayListBuilder,
 
@@ -59,10 +72,12 @@ impl<'a> DisplayListBuilder<'a> {
         pipeline_id: wr::PipelineId,
         context: &'a LayoutContext,
         fragment_tree: &crate::FragmentTree,
+        caret: Option<Caret>,
     ) -> Self {
         Self {
             current_space_and_clip: wr::SpaceAndClipInfo::root_scroll(pipeline_id),
             element_for_canvas_background: fragment_tree.canvas_background.from_element,
+            caret,
             is_contentful: false,
             context,
             wr: wr::DisplayListBuilder::new(pipeline_id, fragment_tree.scrollable_overflow()),
@@ -85,6 +100,18 @@ impl<'a> DisplayListBuilder<'a> {
             flags: style.get_webrender_primitive_flags(),
         }
     }
+
+    /// Paints the caret as a line of the text color at the start of `rect`, a line box of
+    /// text in `style`.
+    fn build_caret(&mut self, rect: &PhysicalRect<Length>, style: &ComputedValues) {
+        // The caret is painted once, in the first fragment that contains it.
+        self.caret = None;
+        let mut rect = *rect;
+        rect.size.width = Length::new(1.);
+        let common = self.common_properties(rect.to_webrender(), style);
+        self.wr
+            .push_rect(&common, rect.to_webrender(), rgba(style.clone_color()));
+    }
 }
 
 impl Fragment {
@@ -204,6 +231,21 @@ impl Fragment {
             rect.size.height = font_metrics.strikeout_size;
             self.build_display_list_for_text_decoration(fragment, builder, &rect, color);
         }
+
+        // Caret.
+        //
+        // FIXME: Caret offsets are in the text of the node, and fragments only know
+        // offsets in their text run. These are only the same for the first text node of
+        // a run and when its white space isn't collapsed.
+        let caret_advance = builder
+            .caret
+            .filter(|caret| fragment.tag == Tag::Node(caret.node))
+            .and_then(|caret| fragment.advance_for_text_offset(caret.offset));
+        if let Some(advance) = caret_advance {
+            let mut rect = rect;
+            rect.origin.x += advance;
+            builder.build_caret(&rect, &fragment.parent_style);
+        }
     }
 
     fn build_display_list_for_text_decoration(
@@ -348,6 +390,24 @@ impl<'a> BuilderForBoxFragment<'a> {
         self.build_hit_test(builder);
         self.build_background(builder);
         self.build_border(builder);
+        self.build_caret(builder);
+    }
+
+    /// Paints the caret at the start of the content box of an element without text, like
+    /// an empty editing host.
+    fn build_caret(&mut self, builder: &mut DisplayListBuilder) {
+        let style = &self.fragment.style;
+        match builder.caret {
+            Some(caret) if self.fragment.tag == Tag::Node(caret.node) => {},
+            _ => return,
+        }
+        let mut rect = self
+            .fragment
+            .content_rect
+            .to_physical(style.writing_mode, self.containing_block)
+            .translate(self.containing_block.origin.to_vector());
+        rect.size.height = style.get_font().font_size.size.0 * 1.2;
+        builder.build_caret(&rect, style);
     }
 
     fn build_hit_test(&self, builder: &mut DisplayListBuilder) {
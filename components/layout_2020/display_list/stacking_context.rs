@@ -6,10 +6,11 @@ use crate::cell::ArcRefCell;
 use crate::display_list::conversions::ToWebRender;
 use crate::display_list::DisplayListBuilder;
 use crate::fragments::{
-    AbsoluteOrFixedPositionedFragment, AnonymousFragment, BoxFragment, Fragment,
+    AbsoluteOrFixedPositionedFragment, AnonymousFragment, BoxFragment, Fragment, Tag,
 };
 use crate::geom::PhysicalRect;
 use crate::style_ext::ComputedValuesExt;
+use embedder_traits::Cursor;
 use euclid::default::Rect;
 use servo_arc::Arc as ServoArc;
 use std::cmp::Ordering;
@@ -18,6 +19,7 @@ use style::computed_values::float::T as ComputedFloat;
 use style::computed_values::mix_blend_mode::T as ComputedMixBlendMode;
 use style::computed_values::overflow_x::T as ComputedOverflow;
 use style::computed_values::position::T as ComputedPosition;
+use style::dom::OpaqueNode;
 use style::properties::ComputedValues;
 use style::values::computed::ClipRectOrAuto;
 use style::values::computed::Length;
@@ -70,6 +72,10 @@ pub(crate) struct StackingContextBuilder<'a> {
     nearest_reference_frame: wr::SpatialId,
 
     wr: &'a mut wr::DisplayListBuilder,
+
+    /// The stacking contexts established by elements in the top layer, which are taken
+    /// out of their parent stacking contexts to be painted above everything else.
+    top_layer_stacking_contexts: Vec<TopLayerStackingContext>,
 }
 
 impl<'a> StackingContextBuilder<'a> {
@@ -78,6 +84,7 @@ impl<'a> StackingContextBuilder<'a> {
             current_space_and_clip: wr::SpaceAndClipInfo::root_scroll(wr.pipeline_id),
             nearest_reference_frame: wr::SpatialId::root_reference_frame(wr.pipeline_id),
             wr,
+            top_layer_stacking_contexts: vec![],
         }
     }
 
@@ -117,6 +124,68 @@ impl StackingContextFragment {
     }
 }
 
+/// An element of the document's top layer, which layout receives from script in top
+/// layer order.
+///
+/// <https://fullscreen.spec.whatwg.org/#top-layer>
+pub struct TopLayerElement {
+    /// The node of the element.
+    pub node: OpaqueNode,
+
+    /// The style of the element's `::backdrop` pseudo-element, if it has one.
+    pub backdrop_style: Option<ServoArc<ComputedValues>>,
+}
+
+/// The stacking context established by an element in the top layer, painted above the
+/// rest of the document and over the element's `::backdrop`.
+pub(crate) struct TopLayerStackingContext {
+    tag: Tag,
+
+    /// The style of the `::backdrop` and the rectangle of the viewport that it covers.
+    backdrop: Option<(ServoArc<ComputedValues>, PhysicalRect<Length>)>,
+
+    stacking_context: StackingContext,
+}
+
+impl TopLayerStackingContext {
+    fn build_display_list(&self, builder: &mut DisplayListBuilder) {
+        if let Some((style, rect)) = &self.backdrop {
+            self.build_backdrop_display_list(builder, style, rect);
+        }
+        self.stacking_context.build_display_list(builder);
+    }
+
+    /// <https://fullscreen.spec.whatwg.org/#::backdrop-pseudo-element>
+    fn build_backdrop_display_list(
+        &self,
+        builder: &mut DisplayListBuilder,
+        style: &ComputedValues,
+        rect: &PhysicalRect<Length>,
+    ) {
+        // The backdrop is fixed to the viewport, whatever the scroll position.
+        let pipeline_id = builder.wr.pipeline_id;
+        builder.current_space_and_clip = wr::SpaceAndClipInfo::root_scroll(pipeline_id);
+        builder.current_space_and_clip.spatial_id =
+            wr::SpatialId::root_reference_frame(pipeline_id);
+
+        let rect = rect.to_webrender();
+        let mut common = builder.common_properties(rect, style);
+        let background_color = style.resolve_color(style.get_background().background_color);
+        if background_color.alpha > 0 {
+            builder
+                .wr
+                .push_rect(&common, rect, super::rgba(background_color));
+        }
+
+        // Hits on the backdrop are hits on its element, so that nothing beneath it can be
+        // hit.
+        common.hit_info = super::hit_info(style, self.tag, Cursor::Default);
+        if common.hit_info.is_some() {
+            builder.wr.push_hit_test(&common);
+        }
+    }
+}
+
 #[derive(Clone, Copy, Eq, PartialEq)]
 pub(crate) enum StackingContextType {
     Real,
@@ -145,6 +214,10 @@ pub(crate) struct StackingContext {
 
     /// All float pseudo stacking context children of this stacking context.
     float_stacking_contexts: Vec<StackingContext>,
+
+    /// The stacking contexts of elements in the top layer, in top layer order. Only the
+    /// root stacking context has any.
+    top_layer: Vec<TopLayerStackingContext>,
 }
 
 impl StackingContext {
@@ -160,6 +233,7 @@ impl StackingContext {
             fragments: vec![],
             stacking_contexts: vec![],
             float_stacking_contexts: vec![],
+            top_layer: vec![],
         }
     }
 
@@ -171,7 +245,39 @@ impl StackingContext {
             fragments: vec![],
             stacking_contexts: vec![],
             float_stacking_contexts: vec![],
+            top_layer: vec![],
+        }
+    }
+
+    /// Takes the stacking contexts that the builder set aside for elements in the top
+    /// layer, and places them in this root stacking context in the order of `top_layer`.
+    pub(crate) fn set_top_layer(
+        &mut self,
+        builder: &mut StackingContextBuilder,
+        top_layer: &[TopLayerElement],
+        viewport: &PhysicalRect<Length>,
+    ) {
+        let mut unordered = mem::replace(&mut builder.top_layer_stacking_contexts, vec![]);
+        for element in top_layer {
+            let index = match unordered
+                .iter()
+                .position(|context| context.tag == Tag::Node(element.node))
+            {
+                Some(index) => index,
+                None => continue,
+            };
+            let mut context = unordered.remove(index);
+            context.backdrop = element
+                .backdrop_style
+                .clone()
+                .map(|style| (style, *viewport));
+            self.top_layer.push(context);
         }
+
+        // Styles may place an element in the top layer before layout hears about it from
+        // script, in which case it goes below the others, without a backdrop.
+        unordered.append(&mut self.top_layer);
+        self.top_layer = unordered;
     }
 
     fn z_index(&self) -> i32 {
@@ -424,6 +530,12 @@ impl StackingContext {
             child_context.build_display_list(builder);
         }
 
+        // Elements in the top layer are painted last, above everything else.
+        // https://fullscreen.spec.whatwg.org/#top-layer
+        for top_layer_context in &self.top_layer {
+            top_layer_context.build_display_list(builder);
+        }
+
         if pushed_context {
             builder.wr.pop_stacking_context();
         }
@@ -642,9 +754,19 @@ impl BoxFragment {
         }
 
         child_stacking_context.sort();
-        parent_stacking_context
-            .stacking_contexts
-            .push(child_stacking_context);
+        if self.style.in_top_layer() {
+            builder
+                .top_layer_stacking_contexts
+                .push(TopLayerStackingContext {
+                    tag: self.tag,
+                    backdrop: None,
+                    stacking_context: child_stacking_context,
+                });
+        } else {
+            parent_stacking_context
+                .stacking_contexts
+                .push(child_stacking_context);
+        }
         parent_stacking_context
             .stacking_contexts
             .append(&mut stolen_children);
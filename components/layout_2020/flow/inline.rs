@@ -754,6 +754,7 @@ impl TextRun {
         let mut runs = runs.iter();
         loop {
             let mut glyphs = vec![];
+            let mut text_offset = None;
             let mut advance_width = Length::zero();
             let mut last_break_opportunity = None;
             let mut force_line_break = false;
@@ -773,6 +774,9 @@ impl TextRun {
                         // to attempt to re-layout the most recent glyphs on a new line.
                         if let Some((len, width, iter)) = last_break_opportunity.take() {
                             glyphs.truncate(len);
+                            if len == 0 {
+                                text_offset = None;
+                            }
                             advance_width = width;
                             runs = iter;
                         }
@@ -796,6 +800,7 @@ impl TextRun {
                             break;
                         }
                     }
+                    text_offset.get_or_insert(run.range.begin().to_usize());
                     glyphs.push(run.glyph_store.clone());
                     advance_width += Length::from(run.glyph_store.total_advance());
                 } else {
@@ -832,6 +837,7 @@ impl TextRun {
                     font_metrics,
                     font_key,
                     glyphs,
+                    text_offset: text_offset.unwrap_or(0),
                     text_decoration_line: ifc.current_nesting_level.text_decoration_line,
                 }));
             // If this line is being broken because of a trailing newline, we can't ignore it.
@@ -6,7 +6,7 @@ use crate::cell::ArcRefCell;
 use crate::context::LayoutContext;
 use crate::display_list::stacking_context::{
     ContainingBlock, ContainingBlockInfo, StackingContext, StackingContextBuildMode,
-    StackingContextBuilder,
+    StackingContextBuilder, TopLayerElement,
 };
 use crate::dom_traversal::{iter_child_nodes, Contents, NodeAndStyleInfo, NodeExt};
 use crate::element_data::LayoutBox;
@@ -31,6 +31,7 @@ use atomic_refcell::AtomicRef;
 use euclid::default::{Point2D, Rect, Size2D};
 use fxhash::FxHashSet;
 use gfx_traits::print_tree::PrintTree;
+use script_layout_interface::rpc::BoxSizes;
 use script_layout_interface::wrapper_traits::LayoutNode;
 use script_layout_interface::{LayoutElementType, LayoutNodeType};
 use servo_arc::Arc;
@@ -39,6 +40,7 @@ use style::dom::OpaqueNode;
 use style::properties::ComputedValues;
 use style::selector_parser::PseudoElement;
 use style::values::computed::Length;
+use style::Zero;
 use style_traits::CSSPixel;
 
 #[derive(Serialize)]
@@ -385,7 +387,11 @@ impl BoxTree {
 }
 
 impl FragmentTree {
-    pub fn build_display_list(&self, builder: &mut crate::display_list::DisplayListBuilder) {
+    pub fn build_display_list(
+        &self,
+        builder: &mut crate::display_list::DisplayListBuilder,
+        top_layer: &[TopLayerElement],
+    ) {
         let mut stacking_context = StackingContext::create_root(&builder.wr);
         {
             let mut stacking_context_builder = StackingContextBuilder::new(&mut builder.wr);
@@ -407,6 +413,12 @@ impl FragmentTree {
                     StackingContextBuildMode::SkipHoisted,
                 );
             }
+
+            stacking_context.set_top_layer(
+                &mut stacking_context_builder,
+                top_layer,
+                &self.initial_containing_block,
+            );
         }
 
         stacking_context.sort();
@@ -536,6 +548,147 @@ impl FragmentTree {
         })
         .unwrap_or_else(Rect::zero)
     }
+
+    pub fn get_box_sizes_for_node(&self, requested_node: OpaqueNode) -> Option<BoxSizes> {
+        let to_au = |length: Length| Au::from_f32_px(length.px());
+        self.find(|fragment, containing_block| {
+            let fragment = match fragment {
+                Fragment::Box(fragment) if fragment.tag.node() == requested_node => fragment,
+                Fragment::AbsoluteOrFixedPositioned(_) |
+                Fragment::Box(_) |
+                Fragment::Text(_) |
+                Fragment::Image(_) |
+                Fragment::Anonymous(_) => return None,
+            };
+
+            let writing_mode = fragment.style.writing_mode;
+            let content_rect = fragment
+                .content_rect
+                .to_physical(writing_mode, &containing_block);
+            let padding_rect = fragment
+                .padding_rect()
+                .to_physical(writing_mode, &containing_block);
+            let border_rect = fragment
+                .border_rect()
+                .to_physical(writing_mode, &containing_block);
+            Some(BoxSizes {
+                content_box: Rect::new(
+                    Point2D::new(
+                        to_au(content_rect.origin.x - padding_rect.origin.x),
+                        to_au(content_rect.origin.y - padding_rect.origin.y),
+                    ),
+                    Size2D::new(
+                        to_au(content_rect.size.width),
+                        to_au(content_rect.size.height),
+                    ),
+                ),
+                border_box: Size2D::new(
+                    to_au(border_rect.size.width),
+                    to_au(border_rect.size.height),
+                ),
+                vertical: writing_mode.is_vertical(),
+            })
+        })
+    }
+
+    /// Like `find`, but only processes the first box of `node` and the fragments inside it.
+    fn find_in_node<T>(
+        &self,
+        node: OpaqueNode,
+        mut process_func: impl FnMut(&Fragment, &PhysicalRect<Length>) -> Option<T>,
+    ) -> Option<T> {
+        self.find(|fragment, containing_block| match fragment {
+            Fragment::Box(box_fragment) if box_fragment.tag == Tag::Node(node) => {
+                Some(fragment.find(containing_block, &mut process_func))
+            },
+            _ => None,
+        })
+        .flatten()
+    }
+
+    /// Returns the caret position closest to `point` in the text of `requested_node` and
+    /// its descendants, as a text node and an offset in bytes in its text. Lines are
+    /// matched first, then the position within the line.
+    pub fn get_text_position_for_point(
+        &self,
+        requested_node: OpaqueNode,
+        point: Point2D<Au>,
+    ) -> Option<(OpaqueNode, usize)> {
+        let point = PhysicalPoint::new(Length::from(point.x), Length::from(point.y));
+        let distance_to = |min: Length, max: Length, position: Length| {
+            if position < min {
+                min - position
+            } else if position > max {
+                position - max
+            } else {
+                Length::zero()
+            }
+        };
+
+        let mut closest = None;
+        self.find_in_node(requested_node, |fragment, containing_block| {
+            let (fragment, node) = match fragment {
+                Fragment::Text(fragment) => match fragment.tag {
+                    Tag::Node(node) => (fragment, node),
+                    Tag::BeforePseudo(_) | Tag::AfterPseudo(_) => return None::<()>,
+                },
+                _ => return None,
+            };
+            let rect = fragment
+                .rect
+                .to_physical(fragment.parent_style.writing_mode, containing_block)
+                .translate(containing_block.origin.to_vector());
+            let distance = (
+                distance_to(rect.min_y(), rect.max_y(), point.y),
+                distance_to(rect.min_x(), rect.max_x(), point.x),
+            );
+            if closest.map_or(true, |(closest_distance, _, _)| distance < closest_distance) {
+                let advance = (point.x - rect.min_x())
+                    .max(Length::zero())
+                    .min(rect.size.width);
+                closest = Some((distance, node, fragment.text_offset_for_advance(advance)));
+            }
+            None
+        });
+        closest.map(|(_, node, offset)| (node, offset))
+    }
+
+    /// Returns the rectangle of a caret at `offset`, in bytes, in the text of
+    /// `requested_node`, or at the start of its content box if it is an element.
+    pub fn get_caret_rect(&self, requested_node: OpaqueNode, offset: usize) -> Option<Rect<Au>> {
+        let tag_to_find = Tag::Node(requested_node);
+        let rect = self.find(|fragment, containing_block| {
+            let (mut rect, height) = match fragment {
+                Fragment::Text(fragment) if fragment.tag == tag_to_find => {
+                    let advance = fragment.advance_for_text_offset(offset)?;
+                    let mut rect = fragment
+                        .rect
+                        .to_physical(fragment.parent_style.writing_mode, containing_block);
+                    rect.origin.x += advance;
+                    let height = rect.size.height;
+                    (rect, height)
+                },
+                Fragment::Box(fragment) if fragment.tag == tag_to_find => {
+                    let style = &fragment.style;
+                    let rect = fragment
+                        .content_rect
+                        .to_physical(style.writing_mode, containing_block);
+                    (rect, style.get_font().font_size.size.0 * 1.2)
+                },
+                _ => return None,
+            };
+            rect.size = PhysicalSize::new(Length::zero(), height);
+            Some(rect.translate(containing_block.origin.to_vector()))
+        })?;
+
+        Some(Rect::new(
+            Point2D::new(
+                Au::from_f32_px(rect.origin.x.px()),
+                Au::from_f32_px(rect.origin.y.px()),
+            ),
+            Size2D::new(Au(0), Au::from_f32_px(rect.size.height.px())),
+        ))
+    }
 }
 
 /// https://drafts.csswg.org/css-backgrounds/#root-background
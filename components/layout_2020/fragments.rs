@@ -9,10 +9,12 @@ use crate::geom::{PhysicalPoint, PhysicalRect};
 #[cfg(debug_assertions)]
 use crate::layout_debug;
 use crate::positioned::HoistedSharedFragment;
+use app_units::Au;
 use gfx::font::FontMetrics as GfxFontMetrics;
 use gfx::text::glyph::GlyphStore;
 use gfx_traits::print_tree::PrintTree;
-use gfx_traits::{combine_id_with_fragment_type, FragmentType};
+use gfx_traits::{combine_id_with_fragment_type, ByteIndex, FragmentType};
+use range::Range as ServoRange;
 #[cfg(not(debug_assertions))]
 use serde::ser::{Serialize, Serializer};
 use servo_arc::Arc as ServoArc;
@@ -160,6 +162,9 @@ pub(crate) struct TextFragment {
     #[serde(skip_serializing)]
     pub font_key: FontInstanceKey,
     pub glyphs: Vec<Arc<GlyphStore>>,
+    /// The offset in bytes of the first glyph of this fragment in the text of its
+    /// text run.
+    pub text_offset: usize,
     /// A flag that represents the _used_ value of the text-decoration property.
     pub text_decoration_line: TextDecorationLine,
 }
@@ -447,6 +452,55 @@ impl TextFragment {
                 .sum::<isize>()
         ));
     }
+
+    /// Returns the distance from the start of this fragment to the caret position at
+    /// `offset`, a byte offset in the text of its text run, or `None` if that position
+    /// isn't in this fragment.
+    pub fn advance_for_text_offset(&self, offset: usize) -> Option<Length> {
+        let mut remaining = offset.checked_sub(self.text_offset)?;
+        let mut advance = Au(0);
+        for glyph_store in &self.glyphs {
+            let len = glyph_store.len().to_usize();
+            if remaining <= len {
+                let range = ServoRange::new(ByteIndex(0), ByteIndex(remaining as isize));
+                advance += glyph_store.advance_for_byte_range(&range, Au(0));
+                return Some(advance.into());
+            }
+            remaining -= len;
+            advance += glyph_store.total_advance();
+        }
+        None
+    }
+
+    /// Returns the caret position closest to `advance` from the start of this fragment,
+    /// as a byte offset in the text of its text run.
+    pub fn text_offset_for_advance(&self, advance: Length) -> usize {
+        let mut remaining = Au::from_f32_px(advance.px());
+        let mut offset = self.text_offset;
+        for glyph_store in &self.glyphs {
+            let len = glyph_store.len().to_usize();
+            if remaining >= glyph_store.total_advance() {
+                remaining -= glyph_store.total_advance();
+                offset += len;
+                continue;
+            }
+            let mut previous_advance = Au(0);
+            for index in 1..=len {
+                let range = ServoRange::new(ByteIndex(0), ByteIndex(index as isize));
+                let advance = glyph_store.advance_for_byte_range(&range, Au(0));
+                if advance >= remaining {
+                    // Snap to the nearest side of the glyph under the position.
+                    if remaining - previous_advance < advance - remaining {
+                        return offset + index - 1;
+                    }
+                    return offset + index;
+                }
+                previous_advance = advance;
+            }
+            return offset + len;
+        }
+        offset
+    }
 }
 
 impl ImageFragment {
@@ -6,6 +6,7 @@
 use crate::context::LayoutContext;
 use crate::flow::FragmentTree;
 use crate::fragments::{Fragment, Tag};
+use crate::opaque_node::OpaqueNodeMethods;
 use app_units::Au;
 use euclid::default::{Point2D, Rect};
 use euclid::Size2D;
@@ -13,6 +14,8 @@ use euclid::Vector2D;
 use ipc_channel::ipc::IpcSender;
 use msg::constellation_msg::PipelineId;
 use script_layout_interface::rpc::TextIndexResponse;
+use script_layout_interface::rpc::{BoxSizes, BoxSizesResponse};
+use script_layout_interface::rpc::{CaretRectResponse, TextPositionResponse};
 use script_layout_interface::rpc::{ContentBoxResponse, ContentBoxesResponse, LayoutRPC};
 use script_layout_interface::rpc::{NodeGeometryResponse, NodeScrollIdResponse};
 use script_layout_interface::rpc::{OffsetParentResponse, ResolvedStyleResponse};
@@ -87,6 +90,15 @@ pub struct LayoutThreadData {
 
     /// A queued response for the viewport dimensions for a given browsing context.
     pub inner_window_dimensions_response: Option<Size2D<f32, CSSPixel>>,
+
+    /// A queued response for the box sizes of a node.
+    pub box_sizes_response: Option<BoxSizes>,
+
+    /// A queued response for the caret position closest to a point.
+    pub text_position_response: Option<(UntrustedNodeAddress, usize)>,
+
+    /// A queued response for the rectangle of a caret.
+    pub caret_rect_response: Option<Rect<Au>>,
 }
 
 pub struct LayoutRPCImpl(pub Arc<Mutex<LayoutThreadData>>);
@@ -173,6 +185,24 @@ impl LayoutRPC for LayoutRPCImpl {
         let rw_data = rw_data.lock().unwrap();
         rw_data.inner_window_dimensions_response.clone()
     }
+
+    fn box_sizes(&self) -> BoxSizesResponse {
+        let &LayoutRPCImpl(ref rw_data) = self;
+        let rw_data = rw_data.lock().unwrap();
+        BoxSizesResponse(rw_data.box_sizes_response)
+    }
+
+    fn text_position(&self) -> TextPositionResponse {
+        let &LayoutRPCImpl(ref rw_data) = self;
+        let rw_data = rw_data.lock().unwrap();
+        TextPositionResponse(rw_data.text_position_response)
+    }
+
+    fn caret_rect(&self) -> CaretRectResponse {
+        let &LayoutRPCImpl(ref rw_data) = self;
+        let rw_data = rw_data.lock().unwrap();
+        CaretRectResponse(rw_data.caret_rect_response)
+    }
 }
 
 pub fn process_content_box_request(
@@ -182,6 +212,30 @@ pub fn process_content_box_request(
     Some(fragment_tree?.get_content_box_for_node(requested_node))
 }
 
+pub fn process_box_sizes_request(
+    requested_node: OpaqueNode,
+    fragment_tree: Option<Arc<FragmentTree>>,
+) -> Option<BoxSizes> {
+    fragment_tree?.get_box_sizes_for_node(requested_node)
+}
+
+pub fn process_text_position_request(
+    requested_node: OpaqueNode,
+    point: Point2D<Au>,
+    fragment_tree: Option<Arc<FragmentTree>>,
+) -> Option<(UntrustedNodeAddress, usize)> {
+    let (node, offset) = fragment_tree?.get_text_position_for_point(requested_node, point)?;
+    Some((node.to_untrusted_node_address(), offset))
+}
+
+pub fn process_caret_rect_request(
+    requested_node: OpaqueNode,
+    offset: usize,
+    fragment_tree: Option<Arc<FragmentTree>>,
+) -> Option<Rect<Au>> {
+    fragment_tree?.get_caret_rect(requested_node, offset)
+}
+
 pub fn process_content_boxes_request(_requested_node: OpaqueNode) -> Vec<Rect<Au>> {
     vec![]
 }
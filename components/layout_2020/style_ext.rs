@@ -376,6 +376,12 @@ impl ComputedValuesExt for ComputedValues {
             return true;
         }
 
+        // Elements in the top layer are rendered in a new stacking context.
+        // https://fullscreen.spec.whatwg.org/#new-stacking-layer
+        if self.in_top_layer() {
+            return true;
+        }
+
         // Fixed position and sticky position always create stacking contexts.
         // TODO(mrobinson): We need to handle sticky positioning here when we support it.
         if self.get_box().position == ComputedPosition::Fixed {
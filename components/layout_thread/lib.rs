@@ -50,11 +50,11 @@ use layout::incremental::{RelayoutMode, SpecialRestyleDamage};
 use layout::layout_debug;
 use layout::parallel;
 use layout::query::{
-    process_client_rect_query, process_content_box_request, process_content_boxes_request,
-    process_element_inner_text_query, process_node_scroll_area_request,
-    process_node_scroll_id_request, process_offset_parent_query,
-    process_resolved_font_style_request, process_resolved_style_request, LayoutRPCImpl,
-    LayoutThreadData,
+    process_box_sizes_request, process_caret_rect_request, process_client_rect_query,
+    process_content_box_request, process_content_boxes_request, process_element_inner_text_query,
+    process_node_scroll_area_request, process_node_scroll_id_request, process_offset_parent_query,
+    process_resolved_font_style_request, process_resolved_style_request,
+    process_text_position_request, LayoutRPCImpl, LayoutThreadData,
 };
 use layout::sequential;
 use layout::traversal::{
@@ -566,6 +566,9 @@ impl LayoutThread {
                 nodes_from_point_response: vec![],
                 element_inner_text_response: String::new(),
                 inner_window_dimensions_response: None,
+                box_sizes_response: None,
+                text_position_response: None,
+                caret_rect_response: None,
             })),
             webrender_image_cache: Arc::new(RwLock::new(FnvHashMap::default())),
             paint_time_metrics: paint_time_metrics,
@@ -1234,6 +1237,15 @@ impl LayoutThread {
                         &QueryMsg::InnerWindowDimensionsQuery(_) => {
                             rw_data.inner_window_dimensions_response = None;
                         },
+                        &QueryMsg::BoxSizesQuery(_) => {
+                            rw_data.box_sizes_response = None;
+                        },
+                        &QueryMsg::TextPositionQuery(..) => {
+                            rw_data.text_position_response = None;
+                        },
+                        &QueryMsg::CaretRectQuery(..) => {
+                            rw_data.caret_rect_response = None;
+                        },
                     },
                     ReflowGoal::Full | ReflowGoal::TickAnimations => {},
                 }
@@ -1526,6 +1538,16 @@ impl LayoutThread {
                 &QueryMsg::ContentBoxesQuery(node) => {
                     rw_data.content_boxes_response = process_content_boxes_request(node, root_flow);
                 },
+                &QueryMsg::BoxSizesQuery(node) => {
+                    rw_data.box_sizes_response = process_box_sizes_request(node, root_flow);
+                },
+                &QueryMsg::TextPositionQuery(node, point) => {
+                    let point = Point2D::new(Au::from_f32_px(point.x), Au::from_f32_px(point.y));
+                    rw_data.text_position_response = process_text_position_request(node, point);
+                },
+                &QueryMsg::CaretRectQuery(node, offset) => {
+                    rw_data.caret_rect_response = process_caret_rect_request(node, offset);
+                },
                 &QueryMsg::TextIndexQuery(node, point_in_node) => {
                     let point_in_node = Point2D::new(
                         Au::from_f32_px(point_in_node.x),
@@ -34,11 +34,13 @@ use gfx_traits::{node_id_from_scroll_id, Epoch};
 use ipc_channel::ipc::{self, IpcReceiver, IpcSender};
 use ipc_channel::router::ROUTER;
 use layout::context::LayoutContext;
-use layout::display_list::{DisplayListBuilder, WebRenderImageInfo};
+use layout::display_list::stacking_context::TopLayerElement;
+use layout::display_list::{Caret, DisplayListBuilder, WebRenderImageInfo};
 use layout::layout_debug;
 use layout::query::{
-    process_content_box_request, process_content_boxes_request, process_resolved_font_style_query,
-    LayoutRPCImpl, LayoutThreadData,
+    process_box_sizes_request, process_caret_rect_request, process_content_box_request,
+    process_content_boxes_request, process_resolved_font_style_query,
+    process_text_position_request, LayoutRPCImpl, LayoutThreadData,
 };
 use layout::query::{process_element_inner_text_query, process_node_geometry_request};
 use layout::query::{process_node_scroll_area_request, process_node_scroll_id_request};
@@ -65,6 +67,7 @@ use script_layout_interface::message::{LayoutThreadInit, Msg, NodesFromPointQuer
 use script_layout_interface::message::{QueryMsg, ReflowComplete, ReflowGoal, ScriptReflow};
 use script_layout_interface::rpc::TextIndexResponse;
 use script_layout_interface::rpc::{LayoutRPC, OffsetParentResponse};
+use script_layout_interface::TrustedNodeAddress;
 use script_traits::{ConstellationControlMsg, LayoutControlMsg, LayoutMsg as ConstellationMsg};
 use script_traits::{DrawAPaintImageResult, PaintWorkletError};
 use script_traits::{Painter, WebrenderIpcSender};
@@ -88,15 +91,16 @@ use style::context::{
 use style::dom::{TDocument, TElement, TNode};
 use style::driver;
 use style::error_reporting::RustLogReporter;
+use style::font_metrics::ServoMetricsProvider;
 use style::global_style_data::{GLOBAL_STYLE_DATA, STYLE_THREAD_POOL};
 use style::media_queries::{Device, MediaList, MediaType};
 use style::properties::PropertyId;
-use style::selector_parser::SnapshotMap;
+use style::selector_parser::{PseudoElement, SnapshotMap};
 use style::shared_lock::{SharedRwLock, SharedRwLockReadGuard, StylesheetGuards};
 use style::stylesheets::{
     DocumentStyleSheet, Origin, Stylesheet, StylesheetInDocument, UserAgentStylesheets,
 };
-use style::stylist::Stylist;
+use style::stylist::{RuleInclusion, Stylist};
 use style::thread_state::{self, ThreadState};
 use style::traversal::DomTraversal;
 use style::traversal_flags::TraversalFlags;
@@ -532,6 +536,9 @@ impl LayoutThread {
                 nodes_from_point_response: vec![],
                 element_inner_text_response: String::new(),
                 inner_window_dimensions_response: None,
+                box_sizes_response: None,
+                text_position_response: None,
+                caret_rect_response: None,
             })),
             webrender_image_cache: Default::default(),
             paint_time_metrics: paint_time_metrics,
@@ -926,6 +933,15 @@ impl LayoutThread {
                         &QueryMsg::InnerWindowDimensionsQuery(_) => {
                             rw_data.inner_window_dimensions_response = None;
                         },
+                        &QueryMsg::BoxSizesQuery(_) => {
+                            rw_data.box_sizes_response = None;
+                        },
+                        &QueryMsg::TextPositionQuery(..) => {
+                            rw_data.text_position_response = None;
+                        },
+                        &QueryMsg::CaretRectQuery(..) => {
+                            rw_data.caret_rect_response = None;
+                        },
                     },
                     ReflowGoal::Full | ReflowGoal::TickAnimations => {},
                 }
@@ -1139,11 +1155,18 @@ impl LayoutThread {
 
         // Perform post-style recalculation layout passes.
         if let Some(root) = &*self.fragment_tree.borrow() {
+            let top_layer = top_layer_elements(&data.top_layer, &layout_context);
+            let caret = data.caret.as_ref().map(|(node, offset)| Caret {
+                node: unsafe { ServoLayoutNode::new(node) }.opaque(),
+                offset: *offset,
+            });
             self.perform_post_style_recalc_layout_passes(
                 root.clone(),
                 &data.reflow_goal,
                 Some(&document),
                 &mut layout_context,
+                &top_layer,
+                caret,
             );
         }
 
@@ -1175,6 +1198,25 @@ impl LayoutThread {
                 &QueryMsg::ContentBoxesQuery(node) => {
                     rw_data.content_boxes_response = process_content_boxes_request(node);
                 },
+                &QueryMsg::BoxSizesQuery(node) => {
+                    rw_data.box_sizes_response =
+                        process_box_sizes_request(node, self.fragment_tree.borrow().clone());
+                },
+                &QueryMsg::TextPositionQuery(node, point) => {
+                    let point = Point2D::new(Au::from_f32_px(point.x), Au::from_f32_px(point.y));
+                    rw_data.text_position_response = process_text_position_request(
+                        node,
+                        point,
+                        self.fragment_tree.borrow().clone(),
+                    );
+                },
+                &QueryMsg::CaretRectQuery(node, offset) => {
+                    rw_data.caret_rect_response = process_caret_rect_request(
+                        node,
+                        offset,
+                        self.fragment_tree.borrow().clone(),
+                    );
+                },
                 &QueryMsg::TextIndexQuery(node, point_in_node) => {
                     let point_in_node = Point2D::new(
                         Au::from_f32_px(point_in_node.x),
@@ -1284,6 +1326,8 @@ impl LayoutThread {
         reflow_goal: &ReflowGoal,
         document: Option<&ServoLayoutDocument>,
         context: &mut LayoutContext,
+        top_layer: &[TopLayerElement],
+        caret: Option<Caret>,
     ) {
         Self::cancel_animations_for_nodes_not_in_fragment_tree(
             &context.style_context.animations,
@@ -1312,7 +1356,7 @@ impl LayoutThread {
         }
 
         let mut display_list =
-            DisplayListBuilder::new(self.id.to_webrender(), context, &fragment_tree);
+            DisplayListBuilder::new(self.id.to_webrender(), context, &fragment_tree, caret);
 
         // `dump_serialized_display_list` doesn't actually print anything. It sets up
         // the display list for printing the serialized version when `finalize()` is called.
@@ -1322,7 +1366,7 @@ impl LayoutThread {
             display_list.wr.dump_serialized_display_list();
         }
 
-        fragment_tree.build_display_list(&mut display_list);
+        fragment_tree.build_display_list(&mut display_list, top_layer);
 
         if self.dump_flow_tree {
             fragment_tree.print();
@@ -1397,6 +1441,41 @@ impl ProfilerMetadataFactory for LayoutThread {
     }
 }
 
+/// Finds the elements of the document's top layer, and resolves the styles of their
+/// `::backdrop` pseudo-elements.
+fn top_layer_elements(
+    top_layer: &[TrustedNodeAddress],
+    context: &LayoutContext,
+) -> Vec<TopLayerElement> {
+    let style_context = &context.style_context;
+    top_layer
+        .iter()
+        .filter_map(|address| unsafe { ServoLayoutNode::new(address) }.as_element())
+        .filter_map(|element| {
+            // Elements that haven't been styled, like those in a `display: none` subtree,
+            // aren't rendered.
+            let data = element.borrow_data()?;
+            let backdrop_style = style_context
+                .stylist
+                .lazily_compute_pseudo_element_style(
+                    &style_context.guards,
+                    element,
+                    &PseudoElement::Backdrop,
+                    RuleInclusion::All,
+                    data.styles.primary(),
+                    /* is_probe = */ false,
+                    &ServoMetricsProvider,
+                    /* matching_func = */ None,
+                )
+                .filter(|style| PseudoElement::Backdrop.should_exist(style));
+            Some(TopLayerElement {
+                node: element.as_node().opaque(),
+                backdrop_style,
+            })
+        })
+        .collect()
+}
+
 fn get_ua_stylesheets() -> Result<UserAgentStylesheets, &'static str> {
     fn parse_ua_stylesheet(
         shared_lock: &SharedRwLock,
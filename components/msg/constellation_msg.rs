@@ -185,6 +185,8 @@ impl PipelineNamespace {
     namespace_id_method! {next_service_worker_registration_id, ServiceWorkerRegistrationId,
     self, ServiceWorkerRegistrationIndex}
     namespace_id_method! {next_blob_id, BlobId, self, BlobIndex}
+    namespace_id_method! {next_shared_worker_id, SharedWorkerId, self, SharedWorkerIndex}
+    namespace_id_method! {next_lock_client_id, LockClientId, self, LockClientIndex}
 }
 
 thread_local!(pub static PIPELINE_NAMESPACE: Cell<Option<PipelineNamespace>> = Cell::new(None));
@@ -306,6 +308,35 @@ impl PartialEq<BrowsingContextId> for TopLevelBrowsingContextId {
     }
 }
 
+/// The browsing session a top-level browsing context belongs to.
+/// Browsers in the same session share cookies, caches, storage
+/// and service worker registrations with each other, and with no one else.
+#[derive(
+    Clone, Copy, Debug, Deserialize, Eq, Hash, MallocSizeOf, Ord, PartialEq, PartialOrd, Serialize,
+)]
+pub enum BrowsingSessionId {
+    /// The regular session, whose state is persisted to the config directory.
+    Default,
+    /// A private session, identified by the embedder. Its state is only kept in memory,
+    /// and is discarded once the last browser of the session is closed.
+    Private(u32),
+}
+
+impl BrowsingSessionId {
+    pub fn is_private(&self) -> bool {
+        match *self {
+            BrowsingSessionId::Default => false,
+            BrowsingSessionId::Private(_) => true,
+        }
+    }
+}
+
+impl Default for BrowsingSessionId {
+    fn default() -> BrowsingSessionId {
+        BrowsingSessionId::Default
+    }
+}
+
 namespace_id! {MessagePortId, MessagePortIndex}
 
 impl MessagePortId {
@@ -422,6 +453,48 @@ impl fmt::Display for ServiceWorkerRegistrationId {
     }
 }
 
+namespace_id! {SharedWorkerId, SharedWorkerIndex}
+
+impl SharedWorkerId {
+    pub fn new() -> SharedWorkerId {
+        PIPELINE_NAMESPACE.with(|tls| {
+            let mut namespace = tls.get().expect("No namespace set for this thread!");
+            let next_shared_worker_id = namespace.next_shared_worker_id();
+            tls.set(Some(namespace));
+            next_shared_worker_id
+        })
+    }
+}
+
+impl fmt::Display for SharedWorkerId {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let PipelineNamespaceId(namespace_id) = self.namespace_id;
+        let SharedWorkerIndex(index) = self.index;
+        write!(fmt, "(SharedWorkerId{},{})", namespace_id, index.get())
+    }
+}
+
+namespace_id! {LockClientId, LockClientIndex}
+
+impl LockClientId {
+    pub fn new() -> LockClientId {
+        PIPELINE_NAMESPACE.with(|tls| {
+            let mut namespace = tls.get().expect("No namespace set for this thread!");
+            let next_lock_client_id = namespace.next_lock_client_id();
+            tls.set(Some(namespace));
+            next_lock_client_id
+        })
+    }
+}
+
+impl fmt::Display for LockClientId {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let PipelineNamespaceId(namespace_id) = self.namespace_id;
+        let LockClientIndex(index) = self.index;
+        write!(fmt, "(LockClientId{},{})", namespace_id, index.get())
+    }
+}
+
 namespace_id! {BlobId, BlobIndex}
 
 impl BlobId {
@@ -0,0 +1,508 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! The Cache Storage engine. The caches of an origin are kept in memory once they are first
+//! used, and written to `<config_dir>/cache_storage/` whenever they change. Response bodies
+//! are streamed from script into a file of their own, and streamed back from it when matched.
+
+use crate::http_cache::vary_headers_match;
+use crate::resource_thread;
+use http::Method;
+use ipc_channel::ipc::{self, IpcSender};
+use net_traits::cache_storage_thread::{CacheMatch, CachePut, CacheQuery, CacheRequest};
+use net_traits::cache_storage_thread::{CacheResponse, CacheStorageThreadMsg};
+use net_traits::request::{BodyChunkRequest, BodyChunkResponse, RequestBody};
+use servo_url::{ImmutableOrigin, ServoUrl};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use url::Position;
+use uuid::Uuid;
+
+/// The size of the chunks response bodies are sent to script in.
+const BODY_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Where the body of a response is kept: in memory if there is no `config_dir`, in a file of
+/// the directory of its origin otherwise.
+#[derive(Clone)]
+enum Body {
+    Memory(Arc<Vec<u8>>),
+    File(PathBuf),
+}
+
+/// <https://w3c.github.io/ServiceWorker/#dfn-request-response-list>
+#[derive(Clone)]
+struct Entry {
+    request: CacheRequest,
+    response: CacheResponse,
+    body: Option<Body>,
+}
+
+#[derive(Default)]
+struct OriginCaches {
+    /// The names and ids of the caches, in the order they were created in.
+    names: Vec<(String, u64)>,
+    caches: HashMap<u64, Vec<Entry>>,
+}
+
+struct CacheStorage {
+    config_dir: Option<PathBuf>,
+    /// The caches of each origin that has been used so far, by ASCII serialization.
+    origins: HashMap<String, OriginCaches>,
+    next_cache_id: u64,
+}
+
+/// The state is shared with the threads reading the bodies of the entries being put, so that
+/// the storage thread doesn't wait on script while they are streamed.
+pub struct CacheStorageManager {
+    storage: Arc<Mutex<CacheStorage>>,
+}
+
+impl CacheStorageManager {
+    pub fn new(config_dir: Option<PathBuf>) -> CacheStorageManager {
+        let storage = CacheStorage {
+            config_dir: config_dir.map(|config_dir| config_dir.join("cache_storage")),
+            origins: HashMap::new(),
+            next_cache_id: 0,
+        };
+        CacheStorageManager {
+            storage: Arc::new(Mutex::new(storage)),
+        }
+    }
+
+    pub fn handle_msg(&mut self, msg: CacheStorageThreadMsg) {
+        let mut storage = self.storage.lock().unwrap();
+        match msg {
+            CacheStorageThreadMsg::Open(sender, origin, name) => {
+                let _ = sender.send(storage.open(&origin, name));
+            },
+            CacheStorageThreadMsg::Has(sender, origin, name) => {
+                let caches = storage.caches(&origin);
+                let _ = sender.send(caches.names.iter().any(|&(ref n, _)| *n == name));
+            },
+            CacheStorageThreadMsg::Delete(sender, origin, name) => {
+                let _ = sender.send(storage.delete(&origin, &name));
+            },
+            CacheStorageThreadMsg::Keys(sender, origin) => {
+                let caches = storage.caches(&origin);
+                let _ = sender.send(caches.names.iter().map(|&(ref n, _)| n.clone()).collect());
+            },
+            CacheStorageThreadMsg::Match(sender, origin, name, query) => {
+                let caches = storage.caches(&origin);
+                let found = caches
+                    .names
+                    .iter()
+                    .filter(|&&(ref n, _)| name.as_ref().map_or(true, |name| n == name))
+                    .filter_map(|&(_, id)| caches.caches.get(&id))
+                    .flat_map(|entries| entries.iter())
+                    .find(|entry| request_matches_cached_item(&query, entry));
+                let _ = sender.send(found.cloned().map(send_response));
+            },
+            CacheStorageThreadMsg::MatchFirst(sender, origin, id, query) => {
+                let caches = storage.caches(&origin);
+                let found = caches.caches.get(&id).and_then(|entries| {
+                    entries
+                        .iter()
+                        .find(|entry| request_matches_cached_item(&query, entry))
+                });
+                let _ = sender.send(found.cloned().map(send_response));
+            },
+            CacheStorageThreadMsg::MatchAll(sender, origin, id, query) => {
+                let matches = storage
+                    .query(&origin, id, query.as_ref())
+                    .into_iter()
+                    .map(send_response)
+                    .collect();
+                let _ = sender.send(matches);
+            },
+            CacheStorageThreadMsg::Requests(sender, origin, id, query) => {
+                let requests = storage
+                    .query(&origin, id, query.as_ref())
+                    .into_iter()
+                    .map(|entry| entry.request.clone())
+                    .collect();
+                let _ = sender.send(requests);
+            },
+            CacheStorageThreadMsg::Put(sender, origin, id, puts) => {
+                let body_dir = storage
+                    .config_dir
+                    .as_ref()
+                    .map(|config_dir| config_dir.join(file_stem(&origin)));
+                let shared_storage = self.storage.clone();
+                thread::Builder::new()
+                    .name("CacheStoragePut".to_owned())
+                    .spawn(move || {
+                        let body_dir = body_dir.as_ref().map(PathBuf::as_path);
+                        let result = read_entries(puts, body_dir).map(|entries| {
+                            shared_storage.lock().unwrap().put(&origin, id, entries)
+                        });
+                        let _ = sender.send(result);
+                    })
+                    .expect("Thread spawning failed");
+            },
+            CacheStorageThreadMsg::DeleteEntries(sender, origin, id, query) => {
+                let _ = sender.send(storage.delete_entries(&origin, id, &query));
+            },
+        }
+    }
+}
+
+impl CacheStorage {
+    /// The caches of an origin, read from disk the first time they are needed.
+    fn caches(&mut self, origin: &ImmutableOrigin) -> &mut OriginCaches {
+        let key = origin.ascii_serialization();
+        let config_dir = &self.config_dir;
+        let next_cache_id = &mut self.next_cache_id;
+        self.origins.entry(key).or_insert_with(|| {
+            let mut stored: Vec<StoredCache> = vec![];
+            if let Some(ref config_dir) = *config_dir {
+                resource_thread::read_json_from_file(
+                    &mut stored,
+                    config_dir,
+                    &(file_stem(origin) + ".json"),
+                );
+            }
+            let body_dir = config_dir
+                .as_ref()
+                .map(|config_dir| config_dir.join(file_stem(origin)));
+            let mut caches = OriginCaches::default();
+            for cache in stored {
+                let id = *next_cache_id;
+                *next_cache_id += 1;
+                let entries = cache
+                    .entries
+                    .into_iter()
+                    .map(|entry| Entry {
+                        request: entry.request,
+                        response: entry.response,
+                        body: match (entry.body, body_dir.as_ref()) {
+                            (Some(file), Some(body_dir)) => Some(Body::File(body_dir.join(file))),
+                            _ => None,
+                        },
+                    })
+                    .collect();
+                caches.names.push((cache.name, id));
+                caches.caches.insert(id, entries);
+            }
+            caches
+        })
+    }
+
+    fn save_state(&self, origin: &ImmutableOrigin) {
+        let config_dir = match self.config_dir {
+            Some(ref config_dir) => config_dir,
+            None => return,
+        };
+        let caches = match self.origins.get(&origin.ascii_serialization()) {
+            Some(caches) => caches,
+            None => return,
+        };
+        let stored: Vec<_> = caches
+            .names
+            .iter()
+            .map(|&(ref name, id)| StoredCache {
+                name: name.clone(),
+                entries: caches.caches[&id].iter().map(StoredEntry::new).collect(),
+            })
+            .collect();
+        if let Err(why) = fs::create_dir_all(config_dir) {
+            warn!("couldn't create {}: {}", config_dir.display(), why);
+            return;
+        }
+        resource_thread::write_json_to_file(&stored, config_dir, &(file_stem(origin) + ".json"));
+    }
+
+    /// <https://w3c.github.io/ServiceWorker/#cache-storage-open>
+    fn open(&mut self, origin: &ImmutableOrigin, name: String) -> u64 {
+        let next_cache_id = self.next_cache_id;
+        let caches = self.caches(origin);
+        if let Some(&(_, id)) = caches.names.iter().find(|&&(ref n, _)| *n == name) {
+            return id;
+        }
+        caches.names.push((name, next_cache_id));
+        caches.caches.insert(next_cache_id, vec![]);
+        self.next_cache_id += 1;
+        self.save_state(origin);
+        next_cache_id
+    }
+
+    /// <https://w3c.github.io/ServiceWorker/#cache-storage-delete>
+    fn delete(&mut self, origin: &ImmutableOrigin, name: &str) -> bool {
+        let caches = self.caches(origin);
+        let index = match caches.names.iter().position(|&(ref n, _)| n == name) {
+            Some(index) => index,
+            None => return false,
+        };
+        let (_, id) = caches.names.remove(index);
+        // The `Cache` objects of the deleted cache keep working, on an empty cache.
+        for entry in caches.caches.remove(&id).unwrap_or_default() {
+            remove_body(entry.body);
+        }
+        self.save_state(origin);
+        true
+    }
+
+    /// <https://w3c.github.io/ServiceWorker/#query-cache>, or all the entries of a cache
+    /// without a query.
+    fn query(
+        &mut self,
+        origin: &ImmutableOrigin,
+        id: u64,
+        query: Option<&CacheQuery>,
+    ) -> Vec<Entry> {
+        let caches = self.caches(origin);
+        let entries = match caches.caches.get(&id) {
+            Some(entries) => entries,
+            None => return vec![],
+        };
+        entries
+            .iter()
+            .filter(|entry| query.map_or(true, |query| request_matches_cached_item(query, entry)))
+            .cloned()
+            .collect()
+    }
+
+    /// The put operations of <https://w3c.github.io/ServiceWorker/#batch-cache-operations>.
+    fn put(&mut self, origin: &ImmutableOrigin, id: u64, new_entries: Vec<Entry>) {
+        let caches = self.caches(origin);
+        let entries = match caches.caches.get_mut(&id) {
+            Some(entries) => entries,
+            None => {
+                // The cache was deleted while the bodies were read.
+                for entry in new_entries {
+                    remove_body(entry.body);
+                }
+                return;
+            },
+        };
+        for entry in new_entries {
+            let query = CacheQuery {
+                request: entry.request.clone(),
+                options: Default::default(),
+            };
+            remove_matching_entries(entries, &query);
+            entries.push(entry);
+        }
+        self.save_state(origin);
+    }
+
+    /// The delete operation of <https://w3c.github.io/ServiceWorker/#batch-cache-operations>.
+    fn delete_entries(&mut self, origin: &ImmutableOrigin, id: u64, query: &CacheQuery) -> bool {
+        let caches = self.caches(origin);
+        let removed = match caches.caches.get_mut(&id) {
+            Some(entries) => remove_matching_entries(entries, query),
+            None => false,
+        };
+        if removed {
+            self.save_state(origin);
+        }
+        removed
+    }
+}
+
+/// Removes the entries matching a query, and their bodies, returning whether there were any.
+fn remove_matching_entries(entries: &mut Vec<Entry>, query: &CacheQuery) -> bool {
+    let (removed, kept): (Vec<Entry>, Vec<Entry>) = entries
+        .drain(..)
+        .partition(|entry| request_matches_cached_item(query, entry));
+    *entries = kept;
+    let any_removed = !removed.is_empty();
+    for entry in removed {
+        remove_body(entry.body);
+    }
+    any_removed
+}
+
+/// <https://w3c.github.io/ServiceWorker/#request-matches-cached-item-algorithm>
+fn request_matches_cached_item(query: &CacheQuery, entry: &Entry) -> bool {
+    let request = &query.request;
+    let options = &query.options;
+    // Step 1.
+    if !options.ignore_method && request.method != Method::GET {
+        return false;
+    }
+    // Steps 2-5.
+    if url_to_match(&request.url, options.ignore_search) !=
+        url_to_match(&entry.request.url, options.ignore_search)
+    {
+        return false;
+    }
+    // Steps 6-7.
+    options.ignore_vary ||
+        vary_headers_match(
+            &entry.response.headers,
+            &entry.request.headers,
+            &request.headers,
+        )
+}
+
+/// A URL without its fragment, and without its query if `ignore_search` is set.
+fn url_to_match(url: &ServoUrl, ignore_search: bool) -> &str {
+    if ignore_search {
+        &url[..Position::AfterPath]
+    } else {
+        &url[..Position::AfterQuery]
+    }
+}
+
+/// Streams the body of an entry to script, over the channel of the returned `CacheMatch`.
+fn send_response(entry: Entry) -> CacheMatch {
+    let (body_sender, body_receiver) = ipc::channel().unwrap();
+    let body = entry.body;
+    thread::Builder::new()
+        .name("CacheStorageMatch".to_owned())
+        .spawn(move || {
+            let result = match body {
+                Some(Body::Memory(bytes)) => send_chunks(&body_sender, &mut &bytes[..]),
+                Some(Body::File(path)) => match File::open(&path) {
+                    Ok(mut file) => send_chunks(&body_sender, &mut file),
+                    Err(why) => {
+                        warn!("couldn't open {}: {}", path.display(), why);
+                        Err(())
+                    },
+                },
+                None => Ok(()),
+            };
+            let _ = body_sender.send(match result {
+                Ok(()) => BodyChunkResponse::Done,
+                Err(()) => BodyChunkResponse::Error,
+            });
+        })
+        .expect("Thread spawning failed");
+    CacheMatch {
+        response: entry.response,
+        body: body_receiver,
+    }
+}
+
+fn send_chunks<R: Read>(sender: &IpcSender<BodyChunkResponse>, body: &mut R) -> Result<(), ()> {
+    let mut buffer = vec![0; BODY_CHUNK_SIZE];
+    loop {
+        let length = body.read(&mut buffer).map_err(|_| ())?;
+        if length == 0 {
+            return Ok(());
+        }
+        sender
+            .send(BodyChunkResponse::Chunk(buffer[..length].to_vec()))
+            .map_err(|_| ())?;
+    }
+}
+
+/// Reads the bodies of the entries being put, in a file of `body_dir` each if there is one.
+fn read_entries(puts: Vec<CachePut>, body_dir: Option<&Path>) -> Result<Vec<Entry>, ()> {
+    let mut entries: Vec<Entry> = vec![];
+    for put in puts {
+        let body = match put.body {
+            Some(body) => match read_body(body, body_dir) {
+                Ok(body) => Some(body),
+                Err(()) => {
+                    for entry in entries {
+                        remove_body(entry.body);
+                    }
+                    return Err(());
+                },
+            },
+            None => None,
+        };
+        entries.push(Entry {
+            request: put.request,
+            response: put.response,
+            body: body,
+        });
+    }
+    Ok(entries)
+}
+
+fn read_body(body: RequestBody, body_dir: Option<&Path>) -> Result<Body, ()> {
+    let body_dir = match body_dir {
+        Some(body_dir) => body_dir,
+        None => {
+            let mut bytes = vec![];
+            receive_chunks(body, &mut bytes)?;
+            return Ok(Body::Memory(Arc::new(bytes)));
+        },
+    };
+    if let Err(why) = fs::create_dir_all(body_dir) {
+        warn!("couldn't create {}: {}", body_dir.display(), why);
+        return Err(());
+    }
+    let path = body_dir.join(Uuid::new_v4().to_string());
+    let mut file = File::create(&path).map_err(|why| {
+        warn!("couldn't create {}: {}", path.display(), why);
+    })?;
+    if receive_chunks(body, &mut file).is_err() {
+        let _ = fs::remove_file(&path);
+        return Err(());
+    }
+    Ok(Body::File(path))
+}
+
+/// Reads a body from script, the way <https://fetch.spec.whatwg.org/#concept-request-transmit-body>
+/// does.
+fn receive_chunks<W: Write>(body: RequestBody, sink: &mut W) -> Result<(), ()> {
+    let chunk_requester = body.take_stream();
+    let (body_chan, body_port) = ipc::channel().unwrap();
+    let _ = chunk_requester.send(BodyChunkRequest::Connect(body_chan));
+    let _ = chunk_requester.send(BodyChunkRequest::Chunk);
+    loop {
+        match body_port.recv() {
+            Ok(BodyChunkResponse::Chunk(bytes)) => {
+                sink.write_all(&bytes).map_err(|_| ())?;
+                let _ = chunk_requester.send(BodyChunkRequest::Chunk);
+            },
+            Ok(BodyChunkResponse::Done) => return Ok(()),
+            Ok(BodyChunkResponse::Error) | Err(_) => return Err(()),
+        }
+    }
+}
+
+fn remove_body(body: Option<Body>) {
+    if let Some(Body::File(path)) = body {
+        if let Err(why) = fs::remove_file(&path) {
+            warn!("couldn't remove {}: {}", path.display(), why);
+        }
+    }
+}
+
+fn file_stem(origin: &ImmutableOrigin) -> String {
+    origin
+        .ascii_serialization()
+        .as_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+#[derive(Deserialize, Serialize)]
+struct StoredEntry {
+    request: CacheRequest,
+    response: CacheResponse,
+    /// The name of the file of the body, in the directory of the origin.
+    body: Option<String>,
+}
+
+impl StoredEntry {
+    fn new(entry: &Entry) -> StoredEntry {
+        let body = match entry.body {
+            Some(Body::File(ref path)) => path
+                .file_name()
+                .map(|file_name| file_name.to_string_lossy().into_owned()),
+            _ => None,
+        };
+        StoredEntry {
+            request: entry.request.clone(),
+            response: entry.response.clone(),
+            body: body,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct StoredCache {
+    name: String,
+    entries: Vec<StoredEntry>,
+}
@@ -0,0 +1,216 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Content Security Policy directives which are not handled by the
+//! `content_security_policy` crate itself, because they either modify the
+//! request or need information that is only available outside of fetch.
+
+use content_security_policy::{CspList, Directive, PolicyDisposition};
+use net_traits::request::{Destination, Origin, Request};
+use servo_url::{ImmutableOrigin, ServoUrl};
+
+/// Iterate over the directives named `name` of the enforced policies in `csp_list`.
+fn enforced_directives<'a>(
+    csp_list: &'a CspList,
+    name: &'a str,
+) -> impl Iterator<Item = &'a Directive> + 'a {
+    csp_list
+        .0
+        .iter()
+        .filter(|policy| matches!(policy.disposition, PolicyDisposition::Enforce))
+        .flat_map(|policy| policy.directive_set.iter())
+        .filter(move |directive| directive.name == name)
+}
+
+/// <https://w3c.github.io/webappsec-upgrade-insecure-requests/#upgrade-request>
+pub fn should_upgrade_request_to_potentially_trustworthy(request: &Request) -> bool {
+    let csp_list = match request.csp_list {
+        Some(ref csp_list) => csp_list,
+        None => return false,
+    };
+
+    // Step 1.
+    if enforced_directives(csp_list, "upgrade-insecure-requests")
+        .next()
+        .is_none()
+    {
+        return false;
+    }
+
+    // Step 2.
+    // Navigations are only upgraded if they stay on the same host as the
+    // client, so that pages can still link to insecure third-party sites.
+    if request.destination == Destination::Document {
+        let same_host = match request.origin {
+            Origin::Origin(ref origin) => {
+                origin.host().map(|host| host.to_string()) ==
+                    request.current_url().host_str().map(str::to_owned)
+            },
+            Origin::Client => false,
+        };
+        if !same_host {
+            return false;
+        }
+    }
+
+    // Step 3.
+    request.current_url().scheme() == "http"
+}
+
+/// Upgrade the current URL of `request` to use a secure scheme.
+/// <https://w3c.github.io/webappsec-upgrade-insecure-requests/#upgrade-request> step 3.
+pub fn upgrade_request_to_potentially_trustworthy(request: &mut Request) {
+    let url = request.current_url_mut().as_mut_url();
+    url.set_scheme("https").unwrap();
+    if url.port() == Some(80) {
+        url.set_port(Some(443)).unwrap();
+    }
+}
+
+/// <https://www.w3.org/TR/CSP/#frame-ancestors-navigation-response>
+///
+/// `ancestor_origins` are the origins of the documents of all ancestors of the
+/// browsing context being navigated, from the closest to the top-level one.
+pub fn should_navigation_response_be_blocked_by_frame_ancestors(
+    csp_list: &CspList,
+    url: &ServoUrl,
+    ancestor_origins: &[ImmutableOrigin],
+) -> bool {
+    enforced_directives(csp_list, "frame-ancestors").any(|directive| {
+        ancestor_origins.iter().any(|origin| {
+            let origin_url = match *origin {
+                ImmutableOrigin::Opaque(_) => return true,
+                ImmutableOrigin::Tuple(..) => {
+                    match ServoUrl::parse(&origin.ascii_serialization()) {
+                        Ok(origin_url) => origin_url,
+                        Err(_) => return true,
+                    }
+                },
+            };
+            !does_url_match_source_list(&origin_url, &directive.value, url)
+        })
+    })
+}
+
+/// <https://www.w3.org/TR/CSP/#match-url-to-source-list>
+fn does_url_match_source_list(
+    url: &ServoUrl,
+    source_list: &[String],
+    protected: &ServoUrl,
+) -> bool {
+    // Steps 2-3. An empty list, or `'none'`, matches nothing.
+    source_list
+        .iter()
+        .any(|expression| does_url_match_expression(url, expression, protected))
+}
+
+/// <https://www.w3.org/TR/CSP/#match-url-to-source-expression>
+fn does_url_match_expression(url: &ServoUrl, expression: &str, protected: &ServoUrl) -> bool {
+    let expression = expression.to_ascii_lowercase();
+
+    // Step 1.
+    if expression == "*" {
+        return matches!(url.scheme(), "http" | "https" | "ws" | "wss" | "ftp") ||
+            url.scheme() == protected.scheme();
+    }
+
+    // Step 4.
+    if expression == "'self'" {
+        if url.origin() == protected.origin() {
+            return true;
+        }
+        return url.host() == protected.host() &&
+            scheme_part_matches(protected.scheme(), url.scheme()) &&
+            (url.port_or_known_default() == protected.port_or_known_default() ||
+                (protected.port_or_known_default() == Some(80) &&
+                    url.port_or_known_default() == Some(443)));
+    }
+
+    // Keyword sources other than 'self' never match a URL.
+    if expression.starts_with('\'') {
+        return false;
+    }
+
+    // Step 2.
+    if expression.ends_with(':') && !expression.contains('/') {
+        return scheme_part_matches(&expression[..expression.len() - 1], url.scheme());
+    }
+
+    // Step 3.
+    let (scheme, rest) = match expression.find("://") {
+        Some(index) => (Some(&expression[..index]), &expression[index + 3..]),
+        None => (None, &expression[..]),
+    };
+    match scheme {
+        Some(scheme) if !scheme_part_matches(scheme, url.scheme()) => return false,
+        None if !scheme_part_matches(protected.scheme(), url.scheme()) => return false,
+        _ => {},
+    }
+
+    let url_host = match url.host_str() {
+        Some(host) => host.to_ascii_lowercase(),
+        None => return false,
+    };
+    // The path part is ignored: it is only meaningful when matching full URLs,
+    // and the URLs matched here are mostly origins.
+    let host_and_port = rest.split('/').next().unwrap_or("");
+    let (host, port) = match host_and_port.rfind(':') {
+        Some(index) if !host_and_port.ends_with(']') => {
+            (&host_and_port[..index], Some(&host_and_port[index + 1..]))
+        },
+        _ => (host_and_port, None),
+    };
+
+    if !host_part_matches(host, &url_host) {
+        return false;
+    }
+
+    port_part_matches(port, scheme.unwrap_or(url.scheme()), url)
+}
+
+/// <https://www.w3.org/TR/CSP/#match-schemes>
+fn scheme_part_matches(expression: &str, scheme: &str) -> bool {
+    match (expression, scheme) {
+        (a, b) if a == b => true,
+        ("http", "https") => true,
+        ("ws", "wss") | ("ws", "http") | ("ws", "https") => true,
+        ("wss", "https") => true,
+        _ => false,
+    }
+}
+
+/// <https://www.w3.org/TR/CSP/#match-hosts>
+fn host_part_matches(pattern: &str, host: &str) -> bool {
+    if pattern == "*" {
+        return true;
+    }
+    if pattern.starts_with("*.") {
+        return host.ends_with(&pattern[1..]);
+    }
+    pattern == host
+}
+
+/// <https://www.w3.org/TR/CSP/#match-ports>
+fn port_part_matches(port: Option<&str>, scheme: &str, url: &ServoUrl) -> bool {
+    let url_port = url.port_or_known_default();
+    match port {
+        Some("*") => true,
+        Some(port) => match port.parse::<u16>() {
+            Ok(port) => {
+                url_port == Some(port) ||
+                    (port == 80 && url_port == Some(443) && url.scheme() == "https")
+            },
+            Err(_) => false,
+        },
+        None => {
+            let default_port = match scheme {
+                "http" | "ws" => Some(80),
+                "https" | "wss" => Some(443),
+                "ftp" => Some(21),
+                _ => None,
+            };
+            url_port == default_port || (default_port == Some(80) && url_port == Some(443))
+        },
+    }
+}
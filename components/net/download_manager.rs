@@ -0,0 +1,704 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Saving responses to disk instead of handing them to a document.
+//!
+//! A download is a fetch whose target streams the response body to a file chosen
+//! by the embedder. Downloads are started either explicitly, for hyperlinks with a
+//! `download` attribute, or by diverting a navigation whose response is an attachment
+//! or cannot be rendered. The embedder is kept informed through `EmbedderMsg`s and
+//! controls downloads with `DownloadAction`s. Finished downloads are recorded in a
+//! history that is persisted in the config directory, except for the downloads of
+//! private browsing sessions.
+
+use crate::fetch::methods::{CancellationListener, PauseControl};
+use crate::http_loader::HANDLE;
+use crate::resource_thread::{read_json_from_file, write_json_to_file};
+use content_security_policy as csp;
+use embedder_traits::{DownloadAction, DownloadId, DownloadStatus, EmbedderMsg, EmbedderProxy};
+use headers::{ContentLength, ContentType, HeaderMapExt};
+use http::header::CONTENT_DISPOSITION;
+use http::HeaderMap;
+use ipc_channel::ipc::{self, IpcSender};
+use ipc_channel::router::ROUTER;
+use mime::Mime;
+use msg::constellation_msg::TopLevelBrowsingContextId;
+use net_traits::request::Request;
+use net_traits::response::Response;
+use net_traits::{FetchMetadata, FetchResponseMsg, FetchTaskTarget, Metadata};
+use percent_encoding::percent_decode_str;
+use servo_url::ServoUrl;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::prelude::Future;
+use tokio::timer::Delay;
+
+const HISTORY_FILE: &'static str = "download_history.json";
+
+/// How long the embedder has to choose where to save a download before it is cancelled.
+const SELECT_PATH_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// A finished or interrupted download, as recorded in the download history.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DownloadHistoryEntry {
+    pub id: DownloadId,
+    pub url: ServoUrl,
+    pub path: Option<PathBuf>,
+    pub received_bytes: u64,
+    pub total_bytes: Option<u64>,
+    pub status: DownloadStatus,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DownloadHistory {
+    pub version: u32,
+    pub entries: Vec<DownloadHistoryEntry>,
+}
+
+impl DownloadHistory {
+    pub fn new() -> DownloadHistory {
+        DownloadHistory {
+            version: 1,
+            entries: vec![],
+        }
+    }
+
+    fn next_id(&self) -> u64 {
+        self.entries
+            .iter()
+            .map(|entry| entry.id.0 + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn update(&mut self, entry: DownloadHistoryEntry) {
+        match self.entries.iter_mut().find(|e| e.id == entry.id) {
+            Some(existing) => *existing = entry,
+            None => self.entries.push(entry),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ControlState {
+    Running,
+    Paused,
+    Cancelled,
+}
+
+/// Where the body of a download goes.
+enum Destination {
+    /// The embedder hasn't chosen a path yet; chunks received so far are kept in memory.
+    AwaitingPath(Vec<u8>),
+    Writing(File),
+    Closed,
+}
+
+/// The part of a download shared between the fetch worker receiving it, the resource
+/// thread receiving actions from the embedder, and the handler of the path the embedder
+/// chose. None of them ever waits on another: pausing stops the reading of the body from
+/// the network, and whoever ends the pause resumes it.
+struct ActiveDownload {
+    cancel_chan: IpcSender<()>,
+    pause_control: PauseControl,
+    browser_id: Option<TopLevelBrowsingContextId>,
+    state: Mutex<DownloadState>,
+}
+
+struct DownloadState {
+    control: ControlState,
+    destination: Destination,
+    entry: DownloadHistoryEntry,
+    /// Whether the whole body was received, possibly before the embedder chose a path.
+    ended: bool,
+}
+
+impl ActiveDownload {
+    /// Apply a state requested by the embedder, returning whether it changed.
+    fn set_control(&self, new_control: ControlState) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if state.control == ControlState::Cancelled || state.control == new_control {
+            return false;
+        }
+        state.control = new_control;
+        match new_control {
+            ControlState::Paused => self.pause_control.pause(),
+            // The body stays paused until there is somewhere to write it.
+            ControlState::Running => match state.destination {
+                Destination::AwaitingPath(_) => {},
+                _ => self.pause_control.resume(),
+            },
+            ControlState::Cancelled => {
+                // Stop the fetch at the next chunk it receives, or right away if it is paused.
+                let _ = self.cancel_chan.send(());
+                self.pause_control.abandon();
+            },
+        }
+        true
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.state.lock().unwrap().control == ControlState::Cancelled
+    }
+}
+
+impl DownloadState {
+    fn write(&mut self, chunk: &[u8]) -> Result<(), String> {
+        match self.destination {
+            Destination::AwaitingPath(ref mut buffer) => buffer.extend_from_slice(chunk),
+            Destination::Writing(ref mut file) => file
+                .write_all(chunk)
+                .map_err(|e| format!("Couldn't write to disk: {}", e))?,
+            Destination::Closed => return Ok(()),
+        }
+        self.entry.received_bytes += chunk.len() as u64;
+        Ok(())
+    }
+
+    fn fail(&mut self, reason: String) {
+        warn!("Download {:?} failed: {}", self.entry.id, reason);
+        self.entry.status = DownloadStatus::Failed(reason);
+        self.destination = Destination::Closed;
+    }
+}
+
+#[derive(Clone)]
+pub struct DownloadManager {
+    embedder_proxy: EmbedderProxy,
+    config_dir: Option<PathBuf>,
+    active: Arc<Mutex<HashMap<DownloadId, Arc<ActiveDownload>>>>,
+    history: Arc<Mutex<DownloadHistory>>,
+    next_id: Arc<Mutex<u64>>,
+}
+
+impl DownloadManager {
+    pub fn new(embedder_proxy: EmbedderProxy, config_dir: Option<PathBuf>) -> DownloadManager {
+        let mut history = match config_dir {
+            Some(ref config_dir) => read_history(config_dir),
+            None => DownloadHistory::new(),
+        };
+        // Downloads that were still running when we last exited can't be resumed.
+        for entry in history.entries.iter_mut() {
+            if !entry.status.is_finished() {
+                entry.status = DownloadStatus::Failed("Interrupted".to_owned());
+            }
+        }
+        let next_id = history.next_id();
+        DownloadManager {
+            embedder_proxy,
+            config_dir,
+            active: Default::default(),
+            history: Arc::new(Mutex::new(history)),
+            next_id: Arc::new(Mutex::new(next_id)),
+        }
+    }
+
+    /// Create a download manager for a private browsing session. It shares download ids
+    /// with this one, so that the embedder can tell their downloads apart, but never
+    /// writes its history to disk.
+    pub fn ephemeral(&self) -> DownloadManager {
+        DownloadManager {
+            embedder_proxy: self.embedder_proxy.clone(),
+            config_dir: None,
+            active: Default::default(),
+            history: Arc::new(Mutex::new(DownloadHistory::new())),
+            next_id: self.next_id.clone(),
+        }
+    }
+
+    /// Create the fetch target and cancellation listener for a new download.
+    pub fn start(
+        &self,
+        url: ServoUrl,
+        browser_id: Option<TopLevelBrowsingContextId>,
+        suggested_filename: Option<String>,
+    ) -> (DownloadTarget, CancellationListener) {
+        let id = {
+            let mut next_id = self.next_id.lock().unwrap();
+            *next_id += 1;
+            DownloadId(*next_id - 1)
+        };
+        let (cancel_chan, cancel_port) = ipc::channel().expect("Failed to create IPC channel!");
+        let pause_control = PauseControl::default();
+        let download = Arc::new(ActiveDownload {
+            cancel_chan,
+            pause_control: pause_control.clone(),
+            browser_id,
+            state: Mutex::new(DownloadState {
+                control: ControlState::Running,
+                destination: Destination::Closed,
+                entry: DownloadHistoryEntry {
+                    id,
+                    url,
+                    path: None,
+                    received_bytes: 0,
+                    total_bytes: None,
+                    status: DownloadStatus::InProgress,
+                },
+                ended: false,
+            }),
+        });
+        self.active.lock().unwrap().insert(id, download.clone());
+        let target = DownloadTarget {
+            manager: self.clone(),
+            download,
+            suggested_filename,
+        };
+        let listener =
+            CancellationListener::new(Some(cancel_port)).with_pause_control(pause_control);
+        (target, listener)
+    }
+
+    /// Handle an action requested by the embedder.
+    pub fn handle_action(&self, id: DownloadId, action: DownloadAction) {
+        let download = match self.active.lock().unwrap().get(&id) {
+            Some(download) => download.clone(),
+            // Actions are sent to the download managers of all sessions, and only the one
+            // that started the download knows about it.
+            None => return debug!("Action {:?} for unknown download {:?}", action, id),
+        };
+        let (control, status) = match action {
+            DownloadAction::Pause => (ControlState::Paused, DownloadStatus::Paused),
+            DownloadAction::Resume => (ControlState::Running, DownloadStatus::InProgress),
+            // The fetch worker reports the cancellation once it has cleaned up.
+            DownloadAction::Cancel => (ControlState::Cancelled, DownloadStatus::Cancelled),
+        };
+        if download.set_control(control) && action != DownloadAction::Cancel {
+            self.notify(None, EmbedderMsg::DownloadStatusChanged(id, status));
+        }
+    }
+
+    /// Cancel all ongoing downloads and save the history to disk.
+    pub fn exit(&self) {
+        for download in self.active.lock().unwrap().values() {
+            download.set_control(ControlState::Cancelled);
+        }
+        self.persist();
+    }
+
+    fn finish(&self, entry: DownloadHistoryEntry) {
+        self.active.lock().unwrap().remove(&entry.id);
+        self.history.lock().unwrap().update(entry);
+        self.persist();
+    }
+
+    fn persist(&self) {
+        if let Some(ref config_dir) = self.config_dir {
+            match self.history.lock() {
+                Ok(history) => write_json_to_file(&*history, config_dir, HISTORY_FILE),
+                Err(_) => warn!("Error writing download history to disk"),
+            }
+        }
+    }
+
+    fn notify(&self, browser_id: Option<TopLevelBrowsingContextId>, msg: EmbedderMsg) {
+        self.embedder_proxy.send((browser_id, msg));
+    }
+
+    /// Ask the embedder where to save a download, without waiting for the answer.
+    /// The body isn't read from the network until the answer comes in, and an
+    /// unanswered request cancels the download after `SELECT_PATH_TIMEOUT`.
+    fn select_path(&self, download: Arc<ActiveDownload>, filename: String) {
+        let browser_id = download.browser_id;
+        let (ipc_sender, ipc_receiver) = ipc::channel().expect("Failed to create IPC channel!");
+        let manager = self.clone();
+        let answered = download.clone();
+        ROUTER.add_route(
+            ipc_receiver.to_opaque(),
+            Box::new(move |message| {
+                let path = message.to().unwrap_or_else(|e| {
+                    warn!("Failed to receive download path from embedder ({:?}).", e);
+                    None
+                });
+                manager.path_selected(&answered, path);
+            }),
+        );
+
+        let manager = self.clone();
+        HANDLE.lock().unwrap().as_ref().unwrap().executor().spawn(
+            Delay::new(Instant::now() + SELECT_PATH_TIMEOUT)
+                .map(move |_| manager.path_selected(&download, None))
+                .map_err(|_| ()),
+        );
+
+        self.notify(
+            browser_id,
+            EmbedderMsg::SelectDownloadPath(filename, ipc_sender),
+        );
+    }
+
+    /// Start writing a download to the path the embedder chose, or cancel it if there
+    /// is none. Does nothing if the path was chosen already, or the download was
+    /// cancelled in the meantime.
+    fn path_selected(&self, download: &ActiveDownload, path: Option<PathBuf>) {
+        let mut state = download.state.lock().unwrap();
+        let buffer = match state.destination {
+            Destination::AwaitingPath(ref mut buffer) => mem::replace(buffer, vec![]),
+            _ => return,
+        };
+        state.destination = Destination::Closed;
+        if state.control == ControlState::Cancelled {
+            return;
+        }
+        let path = match path {
+            Some(path) => path,
+            None => {
+                state.control = ControlState::Cancelled;
+                let _ = download.cancel_chan.send(());
+                download.pause_control.abandon();
+                if state.ended {
+                    drop(state);
+                    self.end(download);
+                }
+                return;
+            },
+        };
+        let mut file = match File::create(&path) {
+            Ok(file) => file,
+            Err(e) => {
+                state.fail(format!("Couldn't create {}: {}", path.display(), e));
+                let _ = download.cancel_chan.send(());
+                download.pause_control.abandon();
+                if state.ended {
+                    drop(state);
+                    self.end(download);
+                }
+                return;
+            },
+        };
+        if let Err(e) = file.write_all(&buffer) {
+            state.fail(format!("Couldn't write to disk: {}", e));
+        } else {
+            state.destination = Destination::Writing(file);
+        }
+        state.entry.path = Some(path.clone());
+        let id = state.entry.id;
+        self.notify(
+            download.browser_id,
+            EmbedderMsg::DownloadStarted(id, state.entry.url.clone(), path),
+        );
+        self.notify(
+            download.browser_id,
+            EmbedderMsg::DownloadProgress(id, state.entry.received_bytes, state.entry.total_bytes),
+        );
+        if state.ended {
+            drop(state);
+            return self.end(download);
+        }
+        if state.control == ControlState::Running {
+            download.pause_control.resume();
+        }
+    }
+
+    /// Report the final status of a download whose body ended, and record it.
+    fn end(&self, download: &ActiveDownload) {
+        let entry = {
+            let mut state = download.state.lock().unwrap();
+            // Close the file before reporting the final status.
+            state.destination = Destination::Closed;
+            if state.entry.status == DownloadStatus::InProgress {
+                state.entry.status = match state.control {
+                    ControlState::Cancelled => DownloadStatus::Cancelled,
+                    _ => DownloadStatus::Complete,
+                };
+            }
+            if state.entry.status != DownloadStatus::Complete {
+                if let Some(ref path) = state.entry.path {
+                    if let Err(e) = fs::remove_file(path) {
+                        warn!("Couldn't remove partial download {}: {}", path.display(), e);
+                    }
+                }
+            }
+            state.entry.clone()
+        };
+        self.notify(
+            download.browser_id,
+            EmbedderMsg::DownloadStatusChanged(entry.id, entry.status.clone()),
+        );
+        self.finish(entry);
+    }
+}
+
+/// A fetch target that writes the response body to disk.
+pub struct DownloadTarget {
+    manager: DownloadManager,
+    download: Arc<ActiveDownload>,
+    suggested_filename: Option<String>,
+}
+
+impl FetchTaskTarget for DownloadTarget {
+    fn process_request_body(&mut self, _: &Request) {}
+
+    fn process_request_eof(&mut self, _: &Request) {}
+
+    fn process_response(&mut self, response: &Response) {
+        if response.is_network_error() || self.download.is_cancelled() {
+            return;
+        }
+        let response = response.actual_response();
+        let filename = {
+            let mut state = self.download.state.lock().unwrap();
+            if let Some(url) = response.url() {
+                state.entry.url = url.clone();
+            }
+            state.entry.total_bytes = response
+                .headers
+                .typed_get::<ContentLength>()
+                .map(|length| length.0);
+            state.destination = Destination::AwaitingPath(vec![]);
+            filename_from_headers(&response.headers)
+                .or_else(|| {
+                    self.suggested_filename
+                        .as_ref()
+                        .and_then(|f| sanitize_filename(f))
+                })
+                .or_else(|| filename_from_url(&state.entry.url))
+                .unwrap_or_else(|| "download".to_owned())
+        };
+        // Don't read the body any further than what is in flight until we know where
+        // it goes.
+        self.download.pause_control.pause();
+        self.manager.select_path(self.download.clone(), filename);
+    }
+
+    fn process_response_chunk(&mut self, chunk: Vec<u8>) {
+        let mut state = self.download.state.lock().unwrap();
+        if state.control == ControlState::Cancelled {
+            return;
+        }
+        let writing = match state.destination {
+            Destination::Writing(_) => true,
+            Destination::AwaitingPath(_) => false,
+            Destination::Closed => return,
+        };
+        if let Err(reason) = state.write(&chunk) {
+            state.fail(reason);
+            // Make sure the fetch doesn't carry on in the background.
+            let _ = self.download.cancel_chan.send(());
+            self.download.pause_control.abandon();
+            return;
+        }
+        if writing {
+            self.manager.notify(
+                self.download.browser_id,
+                EmbedderMsg::DownloadProgress(
+                    state.entry.id,
+                    state.entry.received_bytes,
+                    state.entry.total_bytes,
+                ),
+            );
+        }
+    }
+
+    fn process_response_eof(&mut self, response: &Response) {
+        {
+            let mut state = self.download.state.lock().unwrap();
+            state.ended = true;
+            if state.control != ControlState::Cancelled {
+                if let Some(error) = response.get_network_error() {
+                    if state.entry.status == DownloadStatus::InProgress {
+                        state.entry.status = DownloadStatus::Failed(format!("{:?}", error));
+                    }
+                } else if response.aborted.load(Ordering::Acquire) {
+                    state.entry.status = DownloadStatus::Cancelled;
+                } else if let Destination::AwaitingPath(_) = state.destination {
+                    // The whole body was received, and is written once the embedder
+                    // chose where.
+                    return;
+                }
+            }
+        }
+        self.manager.end(&self.download);
+    }
+
+    fn process_csp_violations(&mut self, _: &Request, _: Vec<csp::Violation>) {}
+}
+
+/// The target of a navigation fetch. Responses that should be rendered are forwarded
+/// to the navigating document as usual; other responses are turned into downloads.
+pub struct NavigationTarget {
+    sender: IpcSender<FetchResponseMsg>,
+    manager: DownloadManager,
+    cancellation_listener: Arc<Mutex<CancellationListener>>,
+    download: Option<DownloadTarget>,
+}
+
+impl NavigationTarget {
+    pub fn new(
+        sender: IpcSender<FetchResponseMsg>,
+        manager: DownloadManager,
+        cancellation_listener: Arc<Mutex<CancellationListener>>,
+    ) -> NavigationTarget {
+        NavigationTarget {
+            sender,
+            manager,
+            cancellation_listener,
+            download: None,
+        }
+    }
+}
+
+impl FetchTaskTarget for NavigationTarget {
+    fn process_request_body(&mut self, request: &Request) {
+        self.sender.process_request_body(request);
+    }
+
+    fn process_request_eof(&mut self, request: &Request) {
+        self.sender.process_request_eof(request);
+    }
+
+    fn process_response(&mut self, response: &Response) {
+        let url = match response.actual_response().url() {
+            Some(url) if should_download(response.actual_response()) => url.clone(),
+            _ => return self.sender.process_response(response),
+        };
+        // https://html.spec.whatwg.org/multipage/#process-a-navigate-response
+        // The navigation is aborted, just like for a 204 response.
+        let mut metadata = Metadata::default(url.clone());
+        metadata.status = Some((204, b"No Content".to_vec()));
+        let _ = self.sender.send(FetchResponseMsg::ProcessResponse(Ok(
+            FetchMetadata::Unfiltered(metadata),
+        )));
+
+        let (mut download, listener) = self.manager.start(url, None, None);
+        // The document that started the navigation no longer controls this fetch.
+        *self.cancellation_listener.lock().unwrap() = listener;
+        download.process_response(response);
+        self.download = Some(download);
+    }
+
+    fn process_response_chunk(&mut self, chunk: Vec<u8>) {
+        match self.download {
+            Some(ref mut download) => download.process_response_chunk(chunk),
+            None => self.sender.process_response_chunk(chunk),
+        }
+    }
+
+    fn process_response_eof(&mut self, response: &Response) {
+        match self.download {
+            Some(ref mut download) => {
+                download.process_response_eof(response);
+                let _ = self
+                    .sender
+                    .send(FetchResponseMsg::ProcessResponseEOF(Ok(response
+                        .get_resource_timing()
+                        .lock()
+                        .unwrap()
+                        .clone())));
+            },
+            None => self.sender.process_response_eof(response),
+        }
+    }
+
+    fn process_csp_violations(&mut self, request: &Request, violations: Vec<csp::Violation>) {
+        self.sender.process_csp_violations(request, violations);
+    }
+}
+
+/// Whether a navigation response should be saved rather than rendered: it is an
+/// attachment, or its MIME type is not one a document can be created for.
+pub fn should_download(response: &Response) -> bool {
+    if response.is_network_error() || response.location_url.is_some() {
+        return false;
+    }
+    if let Some(value) = response.headers.get(CONTENT_DISPOSITION) {
+        if let Ok(value) = value.to_str() {
+            if is_attachment(value) {
+                return true;
+            }
+        }
+    }
+    match response.headers.typed_get::<ContentType>() {
+        Some(content_type) => !is_renderable(&content_type.into()),
+        None => false,
+    }
+}
+
+/// Whether script knows how to create a document for a response of this MIME type.
+/// <https://html.spec.whatwg.org/multipage/#loading-a-document>
+pub fn is_renderable(mime: &Mime) -> bool {
+    mime.type_() == mime::IMAGE ||
+        mime.type_() == mime::TEXT ||
+        (mime.type_() == mime::APPLICATION && mime.subtype() == mime::XML) ||
+        (mime.type_() == mime::APPLICATION &&
+            mime.subtype().as_str() == "xhtml" &&
+            mime.suffix() == Some(mime::XML))
+}
+
+/// <https://tools.ietf.org/html/rfc6266#section-4.2>
+pub fn is_attachment(content_disposition: &str) -> bool {
+    content_disposition
+        .split(';')
+        .next()
+        .map_or(false, |disposition| {
+            disposition.trim().eq_ignore_ascii_case("attachment")
+        })
+}
+
+/// Extract the file name from a `Content-Disposition` header value, preferring the
+/// RFC 5987 encoded `filename*` parameter over `filename`.
+pub fn filename_from_content_disposition(content_disposition: &str) -> Option<String> {
+    let mut filename = None;
+    for param in content_disposition.split(';').skip(1) {
+        let mut parts = param.splitn(2, '=');
+        let name = parts.next()?.trim();
+        let value = match parts.next() {
+            Some(value) => value.trim(),
+            None => continue,
+        };
+        if name.eq_ignore_ascii_case("filename*") {
+            // charset'language'percent-encoded-value
+            let mut parts = value.splitn(3, '\'');
+            let charset = parts.next()?;
+            let encoded = parts.nth(1)?;
+            if charset.eq_ignore_ascii_case("utf-8") {
+                if let Ok(decoded) = percent_decode_str(encoded).decode_utf8() {
+                    return sanitize_filename(&decoded);
+                }
+            }
+        } else if name.eq_ignore_ascii_case("filename") {
+            filename = Some(value.trim_matches('"').to_owned());
+        }
+    }
+    filename.and_then(|f| sanitize_filename(&f))
+}
+
+fn filename_from_headers(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(CONTENT_DISPOSITION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(filename_from_content_disposition)
+}
+
+fn filename_from_url(url: &ServoUrl) -> Option<String> {
+    url.as_url()
+        .path_segments()?
+        .last()
+        .and_then(|segment| percent_decode_str(segment).decode_utf8().ok())
+        .and_then(|segment| sanitize_filename(&segment))
+}
+
+/// Strip any directory components, so that a page can't choose where the file ends up.
+pub fn sanitize_filename(filename: &str) -> Option<String> {
+    let filename = filename.rsplit(|c| c == '/' || c == '\\').next()?;
+    let filename: String = filename.chars().filter(|c| !c.is_control()).collect();
+    let filename = filename.trim();
+    if filename.is_empty() || filename == "." || filename == ".." {
+        return None;
+    }
+    Some(filename.to_owned())
+}
+
+/// Load the download history from a config directory.
+pub fn read_history(config_dir: &Path) -> DownloadHistory {
+    let mut history = DownloadHistory::new();
+    read_json_from_file(&mut history, config_dir, HISTORY_FILE);
+    history
+}
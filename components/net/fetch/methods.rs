@@ -2,36 +2,47 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
+use crate::csp_directives::{
+    should_upgrade_request_to_potentially_trustworthy, upgrade_request_to_potentially_trustworthy,
+};
 use crate::data_loader::decode;
 use crate::fetch::cors_cache::CorsCache;
 use crate::filemanager_thread::{FileManager, FILE_CHUNK_SIZE};
-use crate::http_loader::{determine_requests_referrer, http_fetch, HttpState};
+use crate::http_loader::{
+    determine_requests_referrer, http_fetch, is_schemelessy_same_site, HttpState,
+};
 use crate::http_loader::{set_default_accept, set_default_accept_language};
 use crate::subresource_integrity::is_response_integrity_valid;
 use content_security_policy as csp;
 use crossbeam_channel::{unbounded, Receiver, Sender};
-use devtools_traits::DevtoolsControlMsg;
+use devtools_traits::{ConsoleMessage, DevtoolsControlMsg, LogLevel, ScriptToDevtoolsControlMsg};
+use futures::sync::oneshot;
 use headers::{AccessControlExposeHeaders, ContentType, HeaderMapExt, Range};
 use http::header::{self, HeaderMap, HeaderName};
 use hyper::Method;
 use hyper::StatusCode;
-use ipc_channel::ipc::{self, IpcReceiver};
+use ipc_channel::ipc::{self, IpcReceiver, IpcSender};
+use ipc_channel::router::ROUTER;
 use mime::{self, Mime};
 use net_traits::blob_url_store::{parse_blob_url, BlobURLStoreError};
 use net_traits::filemanager_thread::{FileTokenCheck, RelativePos};
 use net_traits::request::{
-    is_cors_safelisted_method, is_cors_safelisted_request_header, Origin, ResponseTainting, Window,
+    is_cors_safelisted_method, is_cors_safelisted_request_header, Initiator, Origin,
+    ResponseTainting, Window,
 };
 use net_traits::request::{
-    BodyChunkRequest, BodyChunkResponse, CredentialsMode, Destination, Referrer, Request,
-    RequestMode,
+    BodyChunkRequest, BodyChunkResponse, CredentialsMode, Destination, ParserMetadata, Referrer,
+    Request, RequestBody, RequestBuilder, RequestMode, ServiceWorkersMode,
 };
-use net_traits::response::{Response, ResponseBody, ResponseType};
-use net_traits::{FetchTaskTarget, NetworkError, ReferrerPolicy, ResourceFetchTiming};
+use net_traits::response::{HttpsState, Response, ResponseBody, ResponseType};
+use net_traits::ResponseBodyControlMsg;
+use net_traits::{CrossOriginEmbedderPolicy, FetchTaskTarget, NetworkError};
+use net_traits::{CustomResponseMediator, ReferrerPolicy, ResourceFetchTiming};
 use net_traits::{ResourceAttribute, ResourceTimeValue, ResourceTimingType};
 use servo_arc::Arc as ServoArc;
-use servo_url::ServoUrl;
+use servo_url::{Host, ImmutableOrigin, ServoUrl};
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufReader, Seek, SeekFrom};
 use std::mem;
@@ -47,6 +58,10 @@ lazy_static! {
 
 pub type Target<'a> = &'a mut (dyn FetchTaskTarget + Send);
 
+/// The channels to the service worker manager of each origin with a registration.
+pub type ServiceWorkerManagers =
+    Arc<Mutex<HashMap<ImmutableOrigin, IpcSender<CustomResponseMediator>>>>;
+
 #[derive(Clone)]
 pub enum Data {
     Payload(Vec<u8>),
@@ -61,12 +76,14 @@ pub struct FetchContext {
     pub filemanager: FileManager,
     pub file_token: FileTokenCheck,
     pub cancellation_listener: Arc<Mutex<CancellationListener>>,
+    pub service_worker_managers: ServiceWorkerManagers,
     pub timing: ServoArc<Mutex<ResourceFetchTiming>>,
 }
 
 pub struct CancellationListener {
     cancel_chan: Option<IpcReceiver<()>>,
     cancelled: bool,
+    pause_control: Option<PauseControl>,
 }
 
 impl CancellationListener {
@@ -74,9 +91,24 @@ impl CancellationListener {
         Self {
             cancel_chan: cancel_chan,
             cancelled: false,
+            pause_control: None,
         }
     }
 
+    /// Lets `pause_control` stop the reading of the response body.
+    pub fn with_pause_control(mut self, pause_control: PauseControl) -> Self {
+        self.pause_control = Some(pause_control);
+        self
+    }
+
+    /// A future that resolves once more of the response body may be read,
+    /// or `None` if the fetch isn't paused.
+    pub fn paused(&self) -> Option<oneshot::Receiver<()>> {
+        self.pause_control
+            .as_ref()
+            .and_then(|pause_control| pause_control.wait())
+    }
+
     pub fn cancelled(&mut self) -> bool {
         if let Some(ref cancel_chan) = self.cancel_chan {
             if self.cancelled {
@@ -92,6 +124,64 @@ impl CancellationListener {
         }
     }
 }
+
+/// Stops the reading of a response body from the network, so that the server is
+/// applied back-pressure, until it is resumed. Nothing blocks while the body is
+/// paused: the reader waits on a future that `resume` completes.
+#[derive(Clone, Default)]
+pub struct PauseControl(Arc<Mutex<PauseState>>);
+
+#[derive(Default)]
+struct PauseState {
+    paused: bool,
+    waiters: Vec<oneshot::Sender<()>>,
+}
+
+impl PauseControl {
+    pub fn pause(&self) {
+        self.0.lock().unwrap().paused = true;
+    }
+
+    pub fn resume(&self) {
+        let mut state = self.0.lock().unwrap();
+        state.paused = false;
+        for waiter in state.waiters.drain(..) {
+            let _ = waiter.send(());
+        }
+    }
+
+    /// Give up on the body: a paused reader fails instead of resuming.
+    pub fn abandon(&self) {
+        let mut state = self.0.lock().unwrap();
+        state.paused = false;
+        state.waiters.clear();
+    }
+
+    /// A pause control driven by the consumer of a response body over IPC.
+    pub fn controlled_by(receiver: IpcReceiver<ResponseBodyControlMsg>) -> PauseControl {
+        let pause_control = PauseControl::default();
+        let control = pause_control.clone();
+        ROUTER.add_route(
+            receiver.to_opaque(),
+            Box::new(move |message| match message.to() {
+                Ok(ResponseBodyControlMsg::Pause) => control.pause(),
+                Ok(ResponseBodyControlMsg::Resume) | Err(_) => control.resume(),
+            }),
+        );
+        pause_control
+    }
+
+    fn wait(&self) -> Option<oneshot::Receiver<()>> {
+        let mut state = self.0.lock().unwrap();
+        if !state.paused {
+            return None;
+        }
+        let (sender, receiver) = oneshot::channel();
+        state.waiters.push(sender);
+        Some(receiver)
+    }
+}
+
 pub type DoneChannel = Option<(Sender<Data>, Receiver<Data>)>;
 
 /// [Fetch](https://fetch.spec.whatwg.org#concept-fetch)
@@ -152,28 +242,66 @@ pub fn fetch_with_cors_cache(
     main_fetch(request, cache, false, false, target, &mut None, &context);
 }
 
-/// https://www.w3.org/TR/CSP/#should-block-request
-pub fn should_request_be_blocked_by_csp(request: &Request) -> csp::CheckResult {
+/// The `content_security_policy` view of a request, if it was made by a client.
+fn csp_request(request: &Request) -> Option<csp::Request> {
     let origin = match &request.origin {
-        Origin::Client => return csp::CheckResult::Allowed,
+        Origin::Client => return None,
         Origin::Origin(origin) => origin,
     };
-    let csp_request = csp::Request {
+    Some(csp::Request {
         url: request.url().into_url(),
         origin: origin.clone().into_url_origin(),
         redirect_count: request.redirect_count,
         destination: request.destination,
-        initiator: csp::Initiator::None,
-        nonce: String::new(),
+        initiator: match request.initiator {
+            Initiator::Download => csp::Initiator::Download,
+            Initiator::ImageSet => csp::Initiator::ImageSet,
+            Initiator::Manifest => csp::Initiator::Manifest,
+            Initiator::XSLT => csp::Initiator::Xslt,
+            Initiator::None => csp::Initiator::None,
+        },
+        nonce: request.cryptographic_nonce_metadata.clone(),
         integrity_metadata: request.integrity_metadata.clone(),
-        parser_metadata: csp::ParserMetadata::None,
+        parser_metadata: match request.parser_metadata {
+            ParserMetadata::ParserInserted => csp::ParserMetadata::ParserInserted,
+            ParserMetadata::NotParserInserted => csp::ParserMetadata::NotParserInserted,
+            ParserMetadata::Default => csp::ParserMetadata::None,
+        },
+    })
+}
+
+/// https://www.w3.org/TR/CSP/#should-block-request
+pub fn should_request_be_blocked_by_csp(
+    request: &Request,
+) -> (csp::CheckResult, Vec<csp::Violation>) {
+    let csp_request = match csp_request(request) {
+        Some(csp_request) => csp_request,
+        None => return (csp::CheckResult::Allowed, Vec::new()),
     };
-    // TODO: Instead of ignoring violations, report them.
     request
         .csp_list
         .as_ref()
-        .map(|c| c.should_request_be_blocked(&csp_request).0)
-        .unwrap_or(csp::CheckResult::Allowed)
+        .map(|c| c.should_request_be_blocked(&csp_request))
+        .unwrap_or((csp::CheckResult::Allowed, Vec::new()))
+}
+
+/// https://www.w3.org/TR/CSP/#should-block-response
+pub fn should_response_be_blocked_by_csp(
+    request: &Request,
+    response: &Response,
+) -> (csp::CheckResult, Vec<csp::Violation>) {
+    let (csp_list, csp_request) = match (request.csp_list.as_ref(), csp_request(request)) {
+        (Some(csp_list), Some(csp_request)) => (csp_list, csp_request),
+        _ => return (csp::CheckResult::Allowed, Vec::new()),
+    };
+    let csp_response = match response.actual_response().url() {
+        Some(url) => csp::Response {
+            url: url.clone().into_url(),
+            redirect_count: request.redirect_count,
+        },
+        None => return (csp::CheckResult::Allowed, Vec::new()),
+    };
+    csp_list.should_response_to_request_be_blocked(&csp_request, &csp_response)
 }
 
 /// [Main fetch](https://fetch.spec.whatwg.org/#concept-main-fetch)
@@ -201,22 +329,31 @@ pub fn main_fetch(
         }
     }
 
-    // Step 2.2.
-    // TODO: Report violations.
-
-    // Step 2.4.
-    if should_request_be_blocked_by_csp(request) == csp::CheckResult::Blocked {
-        warn!("Request blocked by CSP");
-        response = Some(Response::network_error(NetworkError::Internal(
-            "Blocked by Content-Security-Policy".into(),
-        )))
-    }
-
     // Step 3.
     // TODO: handle request abort.
 
     // Step 4.
-    // TODO: handle upgrade to a potentially secure URL.
+    if should_upgrade_request_to_potentially_trustworthy(request) {
+        upgrade_request_to_potentially_trustworthy(request);
+    }
+    if should_upgrade_mixed_content_request(request) {
+        let insecure_url = request.current_url();
+        request
+            .current_url_mut()
+            .as_mut_url()
+            .set_scheme("https")
+            .unwrap();
+        report_mixed_content(
+            request,
+            context,
+            LogLevel::Warn,
+            format!(
+                "Mixed Content: the insecure request for '{}' was upgraded to '{}'.",
+                insecure_url,
+                request.current_url()
+            ),
+        );
+    }
 
     // Step 5.
     if should_be_blocked_due_to_bad_port(&request.current_url()) {
@@ -224,8 +361,46 @@ pub fn main_fetch(
             "Request attempted on bad port".into(),
         )));
     }
-    // TODO: handle blocking as mixed content.
-    // TODO: handle blocking by content security policy.
+    match should_request_be_blocked_as_mixed_content(request) {
+        MixedContentCheckResult::Allowed => {},
+        MixedContentCheckResult::AllowedOptionallyBlockable => report_mixed_content(
+            request,
+            context,
+            LogLevel::Warn,
+            format!(
+                "Mixed Content: the page was loaded over HTTPS, but requested the insecure \
+                 content '{}'. This content should also be served over HTTPS.",
+                request.current_url()
+            ),
+        ),
+        MixedContentCheckResult::Blocked => {
+            report_mixed_content(
+                request,
+                context,
+                LogLevel::Error,
+                format!(
+                    "Mixed Content: the page was loaded over HTTPS, but requested the insecure \
+                     resource '{}'. This request has been blocked.",
+                    request.current_url()
+                ),
+            );
+            response = Some(Response::network_error(NetworkError::Internal(
+                "Blocked as mixed content".into(),
+            )));
+        },
+    }
+    // The policy is checked against the upgraded URL, so that `upgrade-insecure-requests`
+    // upgrades the insecure requests a source list with `https:` would block.
+    let (csp_result, violations) = should_request_be_blocked_by_csp(request);
+    if !violations.is_empty() {
+        target.process_csp_violations(request, violations);
+    }
+    if csp_result == csp::CheckResult::Blocked {
+        warn!("Request blocked by CSP");
+        response = Some(Response::network_error(NetworkError::Internal(
+            "Blocked by Content-Security-Policy".into(),
+        )))
+    }
 
     // Step 6
     // TODO: handle request's client's referrer policy.
@@ -235,6 +410,13 @@ pub fn main_fetch(
         .referrer_policy
         .or(Some(ReferrerPolicy::NoReferrerWhenDowngrade));
 
+    // The URL of the request's client, which decides whether it is controlled by a service
+    // worker, is only known until the referrer is determined.
+    let client_url = match request.referrer {
+        Referrer::Client(ref url) => Some(url.clone()),
+        _ => None,
+    };
+
     // Step 8.
     assert!(request.referrer_policy.is_some());
     let referrer_url = match mem::replace(&mut request.referrer, Referrer::NoReferrer) {
@@ -264,6 +446,12 @@ pub fn main_fetch(
     // Step 11.
     // Not applicable: see fetch_async.
 
+    // Step 3 of https://fetch.spec.whatwg.org/#concept-http-fetch, done ahead of the scheme
+    // fetch since the client's URL is not kept on the request.
+    if response.is_none() {
+        response = handle_service_worker_fetch(request, client_url, context);
+    }
+
     // Step 12.
     let mut response = response.unwrap_or_else(|| {
         let current_url = request.current_url();
@@ -374,6 +562,19 @@ pub fn main_fetch(
             should_be_blocked_due_to_nosniff(request.destination, &response.headers);
         let should_replace_with_mime_type_error = !response_is_network_error &&
             should_be_blocked_due_to_mime_type(request.destination, &response.headers);
+        let should_replace_with_mixed_content_error = !response_is_network_error &&
+            should_response_be_blocked_as_mixed_content(request, &response);
+        let should_replace_with_corp_error = !response_is_network_error &&
+            request.response_tainting != ResponseTainting::CorsTainting &&
+            cross_origin_resource_policy_check(request, response.actual_response()) ==
+                CrossOriginResourcePolicy::Blocked;
+        let should_replace_with_csp_error = !response_is_network_error && {
+            let (csp_result, violations) = should_response_be_blocked_by_csp(request, &response);
+            if !violations.is_empty() {
+                target.process_csp_violations(request, violations);
+            }
+            csp_result == csp::CheckResult::Blocked
+        };
 
         // Step 15.
         let mut network_error_response = response
@@ -392,10 +593,36 @@ pub fn main_fetch(
         }
 
         // Step 17.
-        // TODO: handle blocking as mixed content.
-        // TODO: handle blocking by content security policy.
         let blocked_error_response;
-        let internal_response = if should_replace_with_nosniff_error {
+        let internal_response = if should_replace_with_csp_error {
+            // Defer rebinding result
+            blocked_error_response = Response::network_error(NetworkError::Internal(
+                "Blocked by Content-Security-Policy".into(),
+            ));
+            &blocked_error_response
+        } else if should_replace_with_mixed_content_error {
+            report_mixed_content(
+                request,
+                context,
+                LogLevel::Error,
+                format!(
+                    "Mixed Content: the response for '{}' was served from the insecure \
+                     location '{}'. This response has been blocked.",
+                    request.url(),
+                    request.current_url()
+                ),
+            );
+            // Defer rebinding result
+            blocked_error_response =
+                Response::network_error(NetworkError::Internal("Blocked as mixed content".into()));
+            &blocked_error_response
+        } else if should_replace_with_corp_error {
+            // Defer rebinding result
+            blocked_error_response = Response::network_error(NetworkError::Internal(
+                "Cross-origin resource policy check failed".into(),
+            ));
+            &blocked_error_response
+        } else if should_replace_with_nosniff_error {
             // Defer rebinding result
             blocked_error_response =
                 Response::network_error(NetworkError::Internal("Blocked by nosniff".into()));
@@ -501,6 +728,150 @@ pub fn main_fetch(
     response
 }
 
+/// <https://w3c.github.io/ServiceWorker/#handle-fetch>
+///
+/// Returns `None` if the request is not controlled by a service worker, or if the
+/// worker didn't provide a response, in which case it falls back to the network.
+fn handle_service_worker_fetch(
+    request: &mut Request,
+    client_url: Option<ServoUrl>,
+    context: &FetchContext,
+) -> Option<Response> {
+    if request.service_workers_mode != ServiceWorkersMode::All ||
+        !matches!(request.current_url().scheme(), "http" | "https")
+    {
+        return None;
+    }
+
+    // A navigation is controlled by the registration whose scope matches
+    // its URL, a subresource request by the one of its client.
+    let load_url = if request.is_navigation_request() {
+        request.current_url()
+    } else {
+        client_url?
+    };
+    let manager = context
+        .service_worker_managers
+        .lock()
+        .unwrap()
+        .get(&load_url.origin())
+        .cloned()?;
+
+    let mut request_builder = RequestBuilder::new(request.current_url(), request.referrer.clone())
+        .method(request.method.clone())
+        .headers(request.headers.clone())
+        .destination(request.destination)
+        .mode(request.mode.clone())
+        .credentials_mode(request.credentials_mode)
+        .redirect_mode(request.redirect_mode)
+        .referrer_policy(request.referrer_policy)
+        .integrity_metadata(request.integrity_metadata.clone())
+        .pipeline_id(request.pipeline_id);
+    if let Origin::Origin(ref origin) = request.origin {
+        request_builder = request_builder.origin(origin.clone());
+    }
+    request_builder.cache_mode = request.cache_mode;
+
+    // The worker reads the body from script through a relay, rather than net reading
+    // it upfront, so that it stays available if the fetch falls back to the network.
+    let (body, body_relay) = match request.body {
+        Some(ref body) => {
+            let (chan, relay) = relay_request_body(body);
+            (Some(chan), Some(relay))
+        },
+        None => (None, None),
+    };
+
+    let (response_chan, response_port) = ipc::channel().unwrap();
+    let mediator = CustomResponseMediator {
+        response_chan,
+        load_url,
+        request: request_builder,
+        body,
+        navigation_preload: None,
+    };
+    if manager.send(mediator).is_err() {
+        return None;
+    }
+    let custom_response = match response_port.recv() {
+        Ok(Some(custom_response)) => custom_response,
+        _ => {
+            // Falling back to the network, the body can only be transmitted if the worker
+            // hasn't started reading it.
+            let body_read = body_relay.map_or(false, |relay| relay.lock().unwrap().withdraw());
+            if body_read {
+                return Some(Response::network_error(NetworkError::Internal(
+                    "The request body was read by the service worker".into(),
+                )));
+            }
+            return None;
+        },
+    };
+
+    let mut response = Response::new(
+        request.current_url(),
+        ResourceFetchTiming::new(request.timing_type()),
+    );
+    response.raw_status = Some((
+        custom_response.raw_status.0.as_u16(),
+        custom_response.raw_status.1.as_bytes().to_vec(),
+    ));
+    response.status = Some(custom_response.raw_status);
+    response.headers = custom_response.headers;
+    *response.body.lock().unwrap() = ResponseBody::Done(custom_response.body);
+    Some(response)
+}
+
+/// Forwards the body requests of a service worker to the body of the request it handles,
+/// until the fetch falls back to the network.
+struct RequestBodyRelay {
+    stream: Option<IpcSender<BodyChunkRequest>>,
+    read: bool,
+}
+
+impl RequestBodyRelay {
+    fn forward(&mut self, message: BodyChunkRequest) {
+        let stream = match self.stream {
+            Some(ref stream) => stream,
+            None => return,
+        };
+        match message {
+            BodyChunkRequest::Connect(_) => self.read = true,
+            // The worker never re-extracts the body, it isn't redirected.
+            BodyChunkRequest::Extract(_) => return,
+            _ => {},
+        }
+        let _ = stream.send(message);
+    }
+
+    /// Stops forwarding to the body, and returns whether the worker started reading it.
+    fn withdraw(&mut self) -> bool {
+        self.stream = None;
+        self.read
+    }
+}
+
+/// Relays a request body to the service worker handling the request. The worker connects
+/// straight to the source of the body in script, so its chunks aren't buffered in net.
+///
+/// The route is removed once the worker drops the returned sender, which it does
+/// as soon as it is done reading the body.
+fn relay_request_body(
+    body: &RequestBody,
+) -> (IpcSender<BodyChunkRequest>, Arc<Mutex<RequestBodyRelay>>) {
+    let (chan, port) = ipc::channel().unwrap();
+    let relay = Arc::new(Mutex::new(RequestBodyRelay {
+        stream: Some(body.take_stream()),
+        read: false,
+    }));
+    let route_relay = relay.clone();
+    ROUTER.add_route(
+        port.to_opaque(),
+        Box::new(move |message| route_relay.lock().unwrap().forward(message.to().unwrap())),
+    );
+    (chan, relay)
+}
+
 fn wait_for_response(response: &mut Response, target: Target, done_chan: &mut DoneChannel) {
     if let Some(ref ch) = *done_chan {
         loop {
@@ -894,6 +1265,69 @@ pub fn should_be_blocked_due_to_nosniff(
     }
 }
 
+#[derive(Debug, PartialEq)]
+pub enum CrossOriginResourcePolicy {
+    Allowed,
+    Blocked,
+}
+
+/// <https://fetch.spec.whatwg.org/#cross-origin-resource-policy-check>
+pub fn cross_origin_resource_policy_check(
+    request: &Request,
+    response: &Response,
+) -> CrossOriginResourcePolicy {
+    // Step 1
+    if request.mode != RequestMode::NoCors {
+        return CrossOriginResourcePolicy::Allowed;
+    }
+
+    // Step 2
+    let origin = match request.origin {
+        Origin::Origin(ref origin) => origin,
+        Origin::Client => return CrossOriginResourcePolicy::Allowed,
+    };
+    let response_url = response
+        .url()
+        .cloned()
+        .unwrap_or_else(|| request.current_url());
+    let response_origin = response_url.origin();
+    if *origin == response_origin {
+        return CrossOriginResourcePolicy::Allowed;
+    }
+
+    // https://fetch.spec.whatwg.org/#cross-origin-resource-policy-internal-check
+    // Step 1 does not apply, this check is never done for navigations.
+
+    // Steps 2-3
+    let policy = response
+        .headers
+        .get(HeaderName::from_static("cross-origin-resource-policy"))
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| matches!(*value, "same-origin" | "same-site" | "cross-origin"));
+
+    // Step 4
+    // Cross-origin no-cors requests from credentialless clients never include
+    // credentials, so only require-corp changes the policy here.
+    let policy = match (policy, request.embedder_policy) {
+        (None, CrossOriginEmbedderPolicy::RequireCorp) => Some("same-origin"),
+        (policy, _) => policy,
+    };
+
+    // Step 5
+    match policy {
+        Some("same-origin") => CrossOriginResourcePolicy::Blocked,
+        Some("same-site")
+            if is_schemelessy_same_site(origin, &response_origin) &&
+                (origin.scheme() == Some("https") ||
+                    response.https_state == HttpsState::None) =>
+        {
+            CrossOriginResourcePolicy::Allowed
+        },
+        Some("same-site") => CrossOriginResourcePolicy::Blocked,
+        _ => CrossOriginResourcePolicy::Allowed,
+    }
+}
+
 /// <https://fetch.spec.whatwg.org/#should-response-to-request-be-blocked-due-to-mime-type?>
 fn should_be_blocked_due_to_mime_type(
     destination: Destination,
@@ -915,6 +1349,156 @@ fn should_be_blocked_due_to_mime_type(
         }
 }
 
+/// The outcome of a mixed content check for a request or response.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MixedContentCheckResult {
+    /// The resource is not mixed content, or is allowed to load regardless.
+    Allowed,
+    /// The resource is [optionally-blockable][1] mixed content, which is
+    /// allowed to load but degrades the security state of the page.
+    ///
+    /// [1]: https://w3c.github.io/webappsec-mixed-content/#optionally-blockable-mixed-content
+    AllowedOptionallyBlockable,
+    /// The resource is blockable mixed content and must not be loaded.
+    Blocked,
+}
+
+/// <https://w3c.github.io/webappsec-mixed-content/#categorize-settings-object>
+pub fn do_settings_prohibit_mixed_security_contexts(request: &Request) -> bool {
+    // Navigation requests are issued with the origin of the document being
+    // loaded, rather than that of the client, so only the ancestors of the
+    // target browsing context are considered for them. A top-level navigation
+    // never has any, which lets it proceed as required by step 1.4 of
+    // <https://w3c.github.io/webappsec-mixed-content/#should-block-fetch>.
+    if request.destination == Destination::Document {
+        return request.has_trustworthy_ancestor_origin;
+    }
+
+    // Step 1.
+    if let Origin::Origin(ref origin) = request.origin {
+        if origin.is_potentially_trustworthy() {
+            return true;
+        }
+    }
+
+    // Step 2.
+    request.has_trustworthy_ancestor_origin
+}
+
+/// <https://w3c.github.io/webappsec-mixed-content/#category-optionally-blockable>
+fn is_optionally_blockable(request: &Request) -> bool {
+    match request.destination {
+        Destination::Image | Destination::Audio | Destination::Video => {
+            request.initiator != Initiator::ImageSet
+        },
+        _ => false,
+    }
+}
+
+/// <https://w3c.github.io/webappsec-mixed-content/#upgrade-algorithm>
+pub fn should_upgrade_mixed_content_request(request: &Request) -> bool {
+    if !pref!(network.mixed_content.upgrade) {
+        return false;
+    }
+
+    let url = request.current_url();
+
+    // Step 1.1.
+    if url.scheme() != "http" {
+        return false;
+    }
+
+    // Step 1.2.
+    if url.host().map_or(true, |host| match host {
+        Host::Ipv4(_) | Host::Ipv6(_) => true,
+        Host::Domain(_) => false,
+    }) {
+        return false;
+    }
+
+    // Step 1.3.
+    if !do_settings_prohibit_mixed_security_contexts(request) {
+        return false;
+    }
+
+    // Step 1.4.
+    is_optionally_blockable(request)
+}
+
+/// <https://w3c.github.io/webappsec-mixed-content/#should-block-fetch>
+pub fn should_request_be_blocked_as_mixed_content(request: &Request) -> MixedContentCheckResult {
+    // Step 1.1.
+    if !do_settings_prohibit_mixed_security_contexts(request) {
+        return MixedContentCheckResult::Allowed;
+    }
+
+    // Step 1.2.
+    if request.current_url().is_potentially_trustworthy() {
+        return MixedContentCheckResult::Allowed;
+    }
+
+    // Step 1.3 is not implemented: there is no way to instruct the user agent
+    // to allow mixed content.
+
+    // Step 1.4 is handled by do_settings_prohibit_mixed_security_contexts.
+
+    if is_optionally_blockable(request) {
+        return MixedContentCheckResult::AllowedOptionallyBlockable;
+    }
+
+    // Step 2.
+    MixedContentCheckResult::Blocked
+}
+
+/// <https://w3c.github.io/webappsec-mixed-content/#should-block-response>
+pub fn should_response_be_blocked_as_mixed_content(request: &Request, response: &Response) -> bool {
+    // Step 1.1.
+    if !do_settings_prohibit_mixed_security_contexts(request) {
+        return false;
+    }
+
+    // Step 1.2.
+    if response
+        .actual_response()
+        .url()
+        .map_or(true, |url| url.is_potentially_trustworthy())
+    {
+        return false;
+    }
+
+    // Step 1.3 is not implemented, see should_request_be_blocked_as_mixed_content.
+
+    // Step 1.4 is handled by do_settings_prohibit_mixed_security_contexts.
+
+    // Optionally-blockable content has already been reported when the request
+    // was made.
+    !is_optionally_blockable(request)
+}
+
+/// Report a mixed content decision to the console of the page which made the request.
+fn report_mixed_content(
+    request: &Request,
+    context: &FetchContext,
+    log_level: LogLevel,
+    message: String,
+) {
+    warn!("{}", message);
+    let (devtools_chan, pipeline_id) = match (&context.devtools_chan, request.pipeline_id) {
+        (Some(devtools_chan), Some(pipeline_id)) => (devtools_chan, pipeline_id),
+        _ => return,
+    };
+    let console_message = ConsoleMessage {
+        message,
+        logLevel: log_level,
+        filename: request.url().into_string(),
+        lineNumber: 0,
+        columnNumber: 0,
+    };
+    let _ = devtools_chan.send(DevtoolsControlMsg::FromScript(
+        ScriptToDevtoolsControlMsg::ConsoleAPI(pipeline_id, console_message, None),
+    ));
+}
+
 /// <https://fetch.spec.whatwg.org/#block-bad-port>
 pub fn should_be_blocked_due_to_bad_port(url: &ServoUrl) -> bool {
     // Step 1 is not applicable, this function just takes the URL directly.
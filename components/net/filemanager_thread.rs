@@ -187,6 +187,25 @@ impl FileManager {
                         );
                     });
             },
+            FileManagerThreadMsg::GrantDroppedFiles(paths) => {
+                self.store.grant_dropped_files(paths);
+            },
+            FileManagerThreadMsg::SelectDroppedFiles(paths, sender, origin) => {
+                let store = self.store.clone();
+                self.thread_pool
+                    .upgrade()
+                    .and_then(|pool| {
+                        pool.spawn(move || {
+                            store.select_dropped_files(paths, sender, origin);
+                        });
+                        Some(())
+                    })
+                    .unwrap_or_else(|| {
+                        warn!(
+                            "FileManager tried to select dropped files after CoreResourceManager has exited."
+                        );
+                    });
+            },
             FileManagerThreadMsg::ReadFile(sender, id, origin) => {
                 self.read_file(sender, id, origin);
             },
@@ -391,12 +410,15 @@ impl FileManager {
 /// Access to the content is encapsulated as methods of this struct.
 struct FileManagerStore {
     entries: RwLock<HashMap<Uuid, FileStoreEntry>>,
+    /// Paths of files the user dropped onto a page, which script may select.
+    dropped_files: RwLock<HashSet<PathBuf>>,
 }
 
 impl FileManagerStore {
     fn new() -> Self {
         FileManagerStore {
             entries: RwLock::new(HashMap::new()),
+            dropped_files: RwLock::new(HashSet::new()),
         }
     }
 
@@ -633,6 +655,36 @@ impl FileManagerStore {
         }
     }
 
+    fn grant_dropped_files(&self, paths: Vec<PathBuf>) {
+        self.dropped_files.write().unwrap().extend(paths);
+    }
+
+    fn select_dropped_files(
+        &self,
+        paths: Vec<PathBuf>,
+        sender: IpcSender<FileManagerResult<Vec<SelectedFile>>>,
+        origin: FileOrigin,
+    ) {
+        // Only allow files that the user actually dropped, to ensure process-level
+        // security against compromised script.
+        let mut replies = vec![];
+        for path in paths {
+            if !self.dropped_files.read().unwrap().contains(&path) {
+                let _ = sender.send(Err(FileManagerThreadError::InvalidSelection));
+                return;
+            }
+            match self.create_entry(&path, &origin) {
+                Ok(selected) => replies.push(selected),
+                Err(e) => {
+                    let _ = sender.send(Err(e));
+                    return;
+                },
+            }
+        }
+
+        let _ = sender.send(Ok(replies));
+    }
+
     fn create_entry(
         &self,
         file_path: &Path,
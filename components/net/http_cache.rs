@@ -582,6 +582,35 @@ fn handle_range_request(
     None
 }
 
+/// Whether the header fields nominated by the `Vary` header of a stored response have the same
+/// values in a new request as in the request the response was stored for, a header field absent
+/// from one only matching if it is also absent from the other.
+/// <https://tools.ietf.org/html/rfc7234#section-4.1>
+pub fn vary_headers_match(
+    stored_headers: &HeaderMap,
+    original_request_headers: &HeaderMap,
+    request_headers: &HeaderMap,
+) -> bool {
+    let vary_value = match stored_headers.typed_get::<Vary>() {
+        Some(vary_value) => vary_value,
+        None => return true,
+    };
+    if vary_value.is_any() {
+        debug!("vary value is any, not caching");
+        return false;
+    }
+    // For every header name found in the Vary header of the stored response.
+    vary_value.iter_strs().all(|vary_val| {
+        let header_data = request_headers.get_all(vary_val).iter();
+        let original_header_data = original_request_headers.get_all(vary_val).iter();
+        if !header_data.eq(original_header_data) {
+            debug!("headers don't match, not caching");
+            return false;
+        }
+        true
+    })
+}
+
 impl HttpCache {
     /// Create a new memory cache instance.
     pub fn new() -> HttpCache {
@@ -612,50 +641,13 @@ impl HttpCache {
             .filter(|r| !r.aborted.load(Ordering::Relaxed));
         let mut candidates = vec![];
         for cached_resource in resources {
-            let mut can_be_constructed = true;
             let cached_headers = cached_resource.data.metadata.headers.lock().unwrap();
             let original_request_headers = cached_resource.request_headers.lock().unwrap();
-            if let Some(vary_value) = cached_headers.typed_get::<Vary>() {
-                if vary_value.is_any() {
-                    debug!("vary value is any, not caching");
-                    can_be_constructed = false
-                } else {
-                    // For every header name found in the Vary header of the stored response.
-                    // Calculating Secondary Keys with Vary <https://tools.ietf.org/html/rfc7234#section-4.1>
-                    for vary_val in vary_value.iter_strs() {
-                        match request.headers.get(vary_val) {
-                            Some(header_data) => {
-                                // If the header is present in the request.
-                                if let Some(original_header_data) =
-                                    original_request_headers.get(vary_val)
-                                {
-                                    // Check that the value of the nominated header field,
-                                    // in the original request, matches the value in the current request.
-                                    if original_header_data != header_data {
-                                        debug!("headers don't match, not caching");
-                                        can_be_constructed = false;
-                                        break;
-                                    }
-                                }
-                            },
-                            None => {
-                                // If a header field is absent from a request,
-                                // it can only match a stored response if those headers,
-                                // were also absent in the original request.
-                                can_be_constructed =
-                                    original_request_headers.get(vary_val).is_none();
-                                if !can_be_constructed {
-                                    debug!("vary header present, not caching");
-                                }
-                            },
-                        }
-                        if !can_be_constructed {
-                            break;
-                        }
-                    }
-                }
-            }
-            if can_be_constructed {
+            if vary_headers_match(
+                &*cached_headers,
+                &*original_request_headers,
+                &request.headers,
+            ) {
                 candidates.push(cached_resource);
             }
         }
@@ -50,7 +50,9 @@ use net_traits::request::{
 use net_traits::request::{CacheMode, CredentialsMode, Destination, Origin};
 use net_traits::request::{ResponseTainting, ServiceWorkersMode};
 use net_traits::response::{HttpsState, Response, ResponseBody, ResponseType};
-use net_traits::{CookieSource, FetchMetadata, NetworkError, ReferrerPolicy};
+use net_traits::{
+    CookieSource, CrossOriginEmbedderPolicy, FetchMetadata, NetworkError, ReferrerPolicy,
+};
 use net_traits::{
     RedirectEndValue, RedirectStartValue, ResourceAttribute, ResourceFetchTiming, ResourceTimeValue,
 };
@@ -63,7 +65,8 @@ use std::ops::Deref;
 use std::sync::{Arc as StdArc, Condvar, Mutex, RwLock};
 use std::time::{Duration, SystemTime};
 use time::{self, Tm};
-use tokio::prelude::{future, Future, Sink, Stream};
+use tokio::prelude::future::{self, Either};
+use tokio::prelude::{Future, Sink, Stream};
 use tokio::runtime::Runtime;
 use tokio::sync::mpsc::{channel, Receiver as TokioReceiver, Sender as TokioSender};
 
@@ -219,7 +222,7 @@ fn strict_origin_when_cross_origin(
 }
 
 /// https://html.spec.whatwg.org/multipage/#schemelessly-same-site
-fn is_schemelessy_same_site(site_a: &ImmutableOrigin, site_b: &ImmutableOrigin) -> bool {
+pub fn is_schemelessy_same_site(site_a: &ImmutableOrigin, site_b: &ImmutableOrigin) -> bool {
     // Step 1
     if !site_a.is_tuple() && !site_b.is_tuple() && site_a == site_b {
         true
@@ -730,8 +733,8 @@ pub fn http_fetch(
 
     // Step 3
     if request.service_workers_mode == ServiceWorkersMode::All {
-        // TODO: Substep 1
-        // Set response to the result of invoking handle fetch for request.
+        // Substep 1
+        // Handle fetch is invoked in main_fetch, which knows the request's client.
 
         // Substep 2
         if let Some(ref res) = response {
@@ -1130,6 +1133,17 @@ fn http_network_or_cache_fetch(
         _ => false,
     };
 
+    // https://html.spec.whatwg.org/multipage/#coep-credentialless
+    // Cross-origin no-cors requests made by a credentialless client are sent
+    // without credentials.
+    let credentials_flag = credentials_flag &&
+        !(http_request.embedder_policy == CrossOriginEmbedderPolicy::Credentialless &&
+            http_request.mode == RequestMode::NoCors &&
+            match http_request.origin {
+                Origin::Origin(ref origin) => *origin != http_request.current_url().origin(),
+                Origin::Client => false,
+            });
+
     let content_length_value = match http_request.body {
         None => match http_request.method {
             // Step 5.5
@@ -1487,74 +1501,7 @@ fn http_network_or_cache_fetch(
     // TODO: if necessary set response's range-requested flag
 
     // Step 9
-    // https://fetch.spec.whatwg.org/#cross-origin-resource-policy-check
-    #[derive(PartialEq)]
-    enum CrossOriginResourcePolicy {
-        Allowed,
-        Blocked,
-    }
-
-    fn cross_origin_resource_policy_check(
-        request: &Request,
-        response: &Response,
-    ) -> CrossOriginResourcePolicy {
-        // Step 1
-        if request.mode != RequestMode::NoCors {
-            return CrossOriginResourcePolicy::Allowed;
-        }
-
-        // Step 2
-        let current_url_origin = request.current_url().origin();
-        let same_origin = if let Origin::Origin(ref origin) = request.origin {
-            *origin == request.current_url().origin()
-        } else {
-            false
-        };
-
-        if same_origin {
-            return CrossOriginResourcePolicy::Allowed;
-        }
-
-        // Step 3
-        let policy = response
-            .headers
-            .get(HeaderName::from_static("cross-origin-resource-policy"))
-            .map(|h| h.to_str().unwrap_or(""))
-            .unwrap_or("");
-
-        // Step 4
-        if policy == "same-origin" {
-            return CrossOriginResourcePolicy::Blocked;
-        }
-
-        // Step 5
-        if let Origin::Origin(ref request_origin) = request.origin {
-            let schemeless_same_origin =
-                is_schemelessy_same_site(&request_origin, &current_url_origin);
-            if schemeless_same_origin &&
-                (request_origin.scheme() == Some("https") ||
-                    response.https_state == HttpsState::None)
-            {
-                return CrossOriginResourcePolicy::Allowed;
-            }
-        };
-
-        // Step 6
-        if policy == "same-site" {
-            return CrossOriginResourcePolicy::Blocked;
-        }
-
-        CrossOriginResourcePolicy::Allowed
-    }
-
-    if http_request.response_tainting != ResponseTainting::CorsTainting &&
-        cross_origin_resource_policy_check(&http_request, &response) ==
-            CrossOriginResourcePolicy::Blocked
-    {
-        return Response::network_error(NetworkError::Internal(
-            "Cross-origin resource policy check failed".into(),
-        ));
-    }
+    // The cross-origin resource policy check is done in main_fetch.
 
     // Step 10
     // FIXME: Figure out what to do with request window objects
@@ -1849,14 +1796,19 @@ fn http_network_fetch(
                 if cancellation_listener.lock().unwrap().cancelled() {
                     *res_body.lock().unwrap() = ResponseBody::Done(vec![]);
                     let _ = done_sender.send(Data::Cancelled);
-                    return future::failed(());
+                    return Either::A(future::failed(()));
                 }
                 if let ResponseBody::Receiving(ref mut body) = *res_body.lock().unwrap() {
                     let bytes = chunk.into_bytes();
                     body.extend_from_slice(&*bytes);
                     let _ = done_sender.send(Data::Payload(bytes.to_vec()));
                 }
-                future::ok(res_body)
+                // Stop reading from the network while the fetch is paused, e.g. for a
+                // paused download, and carry on from here once it is resumed.
+                match cancellation_listener.lock().unwrap().paused() {
+                    Some(resumed) => Either::B(resumed.map(move |_| res_body).map_err(|_| ())),
+                    None => Either::A(future::ok(res_body)),
+                }
             })
             .and_then(move |res_body| {
                 debug!("successfully finished response for {:?}", url1);
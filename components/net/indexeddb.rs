@@ -0,0 +1,1290 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! The IndexedDB engine. The databases of an origin are kept in memory once they are first
+//! opened. Each database is a directory of `<config_dir>/indexeddb/<origin>/`, holding its
+//! metadata and a directory per object store with a file per record, and a commit only writes
+//! the records its transaction changed.
+//!
+//! Transactions run in the order they were created, except that a transaction only waits for
+//! the earlier ones it overlaps with: those with an object store in common where one of the
+//! two can write, and every transaction of the database for a versionchange transaction.
+
+use crate::resource_thread;
+use ipc_channel::ipc::IpcSender;
+use net_traits::indexeddb_thread::{DatabaseInfo, IndexInfo, IndexedDBConnectionEvent};
+use net_traits::indexeddb_thread::{IndexedDBError, IndexedDBKeyRange, IndexedDBKeyType};
+use net_traits::indexeddb_thread::{IndexedDBPut, IndexedDBQuery, IndexedDBRecord};
+use net_traits::indexeddb_thread::{IndexedDBResult, IndexedDBScheduleEvent, IndexedDBThreadMsg};
+use net_traits::indexeddb_thread::{IndexedDBTransactionMode, KeyPath, ObjectStoreInfo};
+use openssl::sha::sha256;
+use servo_url::ImmutableOrigin;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::io::ErrorKind;
+use std::mem;
+use std::ops::Bound;
+use std::path::{Path, PathBuf};
+
+/// The number of bytes of keys and values the databases of an origin may hold.
+pub const QUOTA_SIZE_LIMIT: usize = 50 * 1024 * 1024;
+
+/// <https://w3c.github.io/IndexedDB/#key-generator-construct>
+const MAX_GENERATED_KEY: u64 = 1 << 53;
+
+/// The name of the file with the metadata of a database, in its directory.
+const DATABASE_FILE_NAME: &str = "database.json";
+
+#[derive(Clone)]
+struct Index {
+    info: IndexInfo,
+    /// The primary keys of the records, by index key.
+    entries: BTreeMap<IndexedDBKeyType, BTreeSet<IndexedDBKeyType>>,
+}
+
+#[derive(Clone)]
+struct Record {
+    value: Vec<u8>,
+    /// The keys of the record in each index of the store, by index name.
+    index_keys: BTreeMap<String, Vec<IndexedDBKeyType>>,
+}
+
+#[derive(Clone)]
+struct ObjectStore {
+    key_path: Option<KeyPath>,
+    auto_increment: bool,
+    /// The next key the key generator hands out.
+    current_number: u64,
+    records: BTreeMap<IndexedDBKeyType, Record>,
+    indexes: BTreeMap<String, Index>,
+    /// The size of the keys and values of `records`, counted towards the quota.
+    size: usize,
+    /// The keys of the records added, changed or removed since the store was last written.
+    dirty: BTreeSet<IndexedDBKeyType>,
+}
+
+#[derive(Clone)]
+struct Database {
+    version: u64,
+    object_stores: BTreeMap<String, ObjectStore>,
+    /// The object stores deleted since the database was last written, whose records are
+    /// removed from disk before the records of the current stores are written.
+    removed_stores: BTreeSet<String>,
+}
+
+struct Connection {
+    origin: String,
+    database: String,
+    events: IpcSender<IndexedDBConnectionEvent>,
+}
+
+struct Transaction {
+    /// The connection that created the transaction, or none for the deletion of a database.
+    connection: Option<u64>,
+    origin: String,
+    database: String,
+    mode: IndexedDBTransactionMode,
+    /// The object stores of a readonly or readwrite transaction.
+    scope: Vec<String>,
+    /// The version a versionchange transaction upgrades the database to, or none for a deletion.
+    new_version: Option<u64>,
+    events: IpcSender<IndexedDBScheduleEvent>,
+    started: bool,
+    /// The number of connections yet to handle the versionchange event of the transaction.
+    pending_version_changes: usize,
+    /// The whole database as it was when a versionchange transaction began.
+    database_snapshot: Option<Database>,
+    /// The object stores a readwrite transaction wrote to, as they were before the first write.
+    store_snapshots: HashMap<String, ObjectStore>,
+}
+
+impl Transaction {
+    /// Whether one of the two transactions has to wait for the other.
+    fn overlaps(&self, other: &Transaction) -> bool {
+        if self.origin != other.origin || self.database != other.database {
+            return false;
+        }
+        if self.mode == IndexedDBTransactionMode::VersionChange ||
+            other.mode == IndexedDBTransactionMode::VersionChange
+        {
+            return true;
+        }
+        (self.mode == IndexedDBTransactionMode::ReadWrite ||
+            other.mode == IndexedDBTransactionMode::ReadWrite) &&
+            self.scope.iter().any(|name| other.scope.contains(name))
+    }
+}
+
+pub struct IndexedDBManager {
+    config_dir: Option<PathBuf>,
+    /// The databases of each origin that has been used so far, by ASCII serialization.
+    origins: HashMap<String, BTreeMap<String, Database>>,
+    connections: HashMap<u64, Connection>,
+    next_connection_id: u64,
+    /// The transactions that are waiting or running, in the order they were created.
+    transactions: BTreeMap<u64, Transaction>,
+    next_transaction_id: u64,
+}
+
+impl IndexedDBManager {
+    pub fn new(config_dir: Option<PathBuf>) -> IndexedDBManager {
+        IndexedDBManager {
+            config_dir: config_dir,
+            origins: HashMap::new(),
+            connections: HashMap::new(),
+            next_connection_id: 0,
+            transactions: BTreeMap::new(),
+            next_transaction_id: 0,
+        }
+    }
+
+    pub fn handle_msg(&mut self, msg: IndexedDBThreadMsg) {
+        match msg {
+            IndexedDBThreadMsg::Open(sender, events, origin, name) => {
+                let _ = sender.send(self.open(events, &origin, name));
+            },
+            IndexedDBThreadMsg::Close(id) => {
+                self.connections.remove(&id);
+                self.start_transactions();
+            },
+            IndexedDBThreadMsg::DeleteDatabase(events, origin, name) => {
+                self.delete_database(events, &origin, name);
+            },
+            IndexedDBThreadMsg::Databases(sender, origin) => {
+                let databases = self
+                    .databases(&origin)
+                    .iter()
+                    .filter(|&(_, database)| database.version > 0)
+                    .map(|(name, database)| (name.clone(), database.version))
+                    .collect();
+                let _ = sender.send(databases);
+            },
+            IndexedDBThreadMsg::BeginTransaction(sender, events, connection, mode, scope) => {
+                let _ = sender.send(self.begin_transaction(events, connection, mode, scope, None));
+            },
+            IndexedDBThreadMsg::Upgrade(sender, events, connection, version) => {
+                let result = self.begin_transaction(
+                    events,
+                    connection,
+                    IndexedDBTransactionMode::VersionChange,
+                    vec![],
+                    Some(version),
+                );
+                let _ = sender.send(result);
+            },
+            IndexedDBThreadMsg::VersionChangeFired(id) => {
+                self.version_change_fired(id);
+            },
+            IndexedDBThreadMsg::Commit(sender, id) => {
+                let _ = sender.send(self.commit(id));
+            },
+            IndexedDBThreadMsg::Abort(sender, id) => {
+                self.abort(id);
+                let _ = sender.send(());
+            },
+            IndexedDBThreadMsg::CreateObjectStore(sender, id, info) => {
+                let result = self.with_database(id, |database| {
+                    if database.object_stores.contains_key(&info.name) {
+                        return Err(IndexedDBError::Constraint);
+                    }
+                    let object_store = ObjectStore {
+                        key_path: info.key_path,
+                        auto_increment: info.auto_increment,
+                        current_number: 1,
+                        records: BTreeMap::new(),
+                        indexes: BTreeMap::new(),
+                        size: 0,
+                        dirty: BTreeSet::new(),
+                    };
+                    database.object_stores.insert(info.name, object_store);
+                    Ok(())
+                });
+                let _ = sender.send(result);
+            },
+            IndexedDBThreadMsg::DeleteObjectStore(sender, id, name) => {
+                let result = self.with_database(id, |database| {
+                    database
+                        .object_stores
+                        .remove(&name)
+                        .ok_or(IndexedDBError::NotFound)?;
+                    database.removed_stores.insert(name);
+                    Ok(())
+                });
+                let _ = sender.send(result);
+            },
+            IndexedDBThreadMsg::CreateIndex(sender, id, store_name, info, keys) => {
+                let result = self.with_database(id, |database| {
+                    let object_store = database
+                        .object_stores
+                        .get_mut(&store_name)
+                        .ok_or(IndexedDBError::NotFound)?;
+                    object_store.create_index(info, keys)
+                });
+                let _ = sender.send(result);
+            },
+            IndexedDBThreadMsg::DeleteIndex(sender, id, store_name, name) => {
+                let result = self.with_database(id, |database| {
+                    let object_store = database
+                        .object_stores
+                        .get_mut(&store_name)
+                        .ok_or(IndexedDBError::NotFound)?;
+                    object_store
+                        .indexes
+                        .remove(&name)
+                        .ok_or(IndexedDBError::NotFound)?;
+                    // The keys left in the files of the records are ignored when reading them.
+                    for record in object_store.records.values_mut() {
+                        record.index_keys.remove(&name);
+                    }
+                    Ok(())
+                });
+                let _ = sender.send(result);
+            },
+            IndexedDBThreadMsg::GenerateKey(sender, id, store_name) => {
+                let result = self.with_object_store(id, &store_name, true, |object_store, _| {
+                    object_store.generate_key()
+                });
+                let _ = sender.send(result);
+            },
+            IndexedDBThreadMsg::Put(sender, id, put) => {
+                let store_name = put.object_store.clone();
+                let result =
+                    self.with_object_store(id, &store_name, true, |object_store, usage| {
+                        object_store.put(put, usage)
+                    });
+                let _ = sender.send(result);
+            },
+            IndexedDBThreadMsg::Query(sender, id, query) => {
+                let store_name = query.object_store.clone();
+                let result = self.with_object_store(id, &store_name, false, |object_store, _| {
+                    object_store.query(&query)
+                });
+                let _ = sender.send(result);
+            },
+            IndexedDBThreadMsg::Count(sender, id, store_name, index, range) => {
+                let result = self.with_object_store(id, &store_name, false, |object_store, _| {
+                    object_store.count(index.as_ref().map(|name| &**name), &range)
+                });
+                let _ = sender.send(result);
+            },
+            IndexedDBThreadMsg::Delete(sender, id, store_name, range) => {
+                let result = self.with_object_store(id, &store_name, true, |object_store, _| {
+                    object_store.remove_records(&range);
+                    Ok(())
+                });
+                let _ = sender.send(result);
+            },
+            IndexedDBThreadMsg::Clear(sender, id, store_name) => {
+                let result = self.with_object_store(id, &store_name, true, |object_store, _| {
+                    object_store.remove_records(&IndexedDBKeyRange::default());
+                    Ok(())
+                });
+                let _ = sender.send(result);
+            },
+        }
+    }
+
+    /// The databases of an origin, read from disk the first time they are needed.
+    fn databases(&mut self, origin: &ImmutableOrigin) -> &mut BTreeMap<String, Database> {
+        let key = origin.ascii_serialization();
+        let origin_dir = self.origin_dir(&key);
+        self.origins.entry(key).or_insert_with(|| match origin_dir {
+            Some(origin_dir) => read_databases(&origin_dir),
+            None => BTreeMap::new(),
+        })
+    }
+
+    fn origin_dir(&self, origin: &str) -> Option<PathBuf> {
+        self.config_dir.as_ref().map(|config_dir| {
+            config_dir
+                .join("indexeddb")
+                .join(hex_encode(origin.as_bytes()))
+        })
+    }
+
+    fn database_dir(&self, origin: &str, database: &str) -> Option<PathBuf> {
+        self.origin_dir(origin)
+            .map(|origin_dir| origin_dir.join(hex_encode(database.as_bytes())))
+    }
+
+    /// Writes the changes a transaction made to its database: the metadata of the database,
+    /// and the records that were added, changed or removed.
+    fn save_database(&mut self, origin: &str, name: &str) {
+        let dir = self.database_dir(origin, name);
+        let database = match self
+            .origins
+            .get_mut(origin)
+            .and_then(|databases| databases.get_mut(name))
+        {
+            Some(database) => database,
+            None => return,
+        };
+        let removed_stores = mem::take(&mut database.removed_stores);
+        let dirty: Vec<_> = database
+            .object_stores
+            .iter_mut()
+            .map(|(name, object_store)| (name.clone(), mem::take(&mut object_store.dirty)))
+            .collect();
+        let dir = match dir {
+            Some(dir) => dir,
+            None => return,
+        };
+
+        for name in removed_stores {
+            let store_dir = dir.join(hex_encode(name.as_bytes()));
+            if let Err(why) = fs::remove_dir_all(&store_dir) {
+                if why.kind() != ErrorKind::NotFound {
+                    warn!("couldn't remove {}: {}", store_dir.display(), why);
+                }
+            }
+        }
+        for (store_name, keys) in dirty {
+            if keys.is_empty() {
+                continue;
+            }
+            let store_dir = dir.join(hex_encode(store_name.as_bytes()));
+            if let Err(why) = fs::create_dir_all(&store_dir) {
+                warn!("couldn't create {}: {}", store_dir.display(), why);
+                continue;
+            }
+            let records = &database.object_stores[&store_name].records;
+            for key in keys {
+                write_record(&store_dir, &key, records.get(&key));
+            }
+        }
+        if let Err(why) = fs::create_dir_all(&dir) {
+            warn!("couldn't create {}: {}", dir.display(), why);
+            return;
+        }
+        resource_thread::write_json_to_file(
+            &StoredDatabase::new(name, database),
+            &dir,
+            DATABASE_FILE_NAME,
+        );
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#open-a-database>
+    fn open(
+        &mut self,
+        events: IpcSender<IndexedDBConnectionEvent>,
+        origin: &ImmutableOrigin,
+        name: String,
+    ) -> (u64, DatabaseInfo) {
+        let database = self
+            .databases(origin)
+            .entry(name.clone())
+            .or_insert(Database {
+                version: 0,
+                object_stores: BTreeMap::new(),
+                removed_stores: BTreeSet::new(),
+            });
+        let info = DatabaseInfo {
+            name: name.clone(),
+            version: database.version,
+            object_stores: database
+                .object_stores
+                .iter()
+                .map(|(name, object_store)| object_store.info(name))
+                .collect(),
+        };
+        let id = self.next_connection_id;
+        self.next_connection_id += 1;
+        self.connections.insert(
+            id,
+            Connection {
+                origin: origin.ascii_serialization(),
+                database: name,
+                events: events,
+            },
+        );
+        (id, info)
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#delete-a-database>
+    fn delete_database(
+        &mut self,
+        events: IpcSender<IndexedDBScheduleEvent>,
+        origin: &ImmutableOrigin,
+        name: String,
+    ) {
+        // Read the databases of the origin, to know the version of the deleted one.
+        self.databases(origin);
+        let id = self.next_transaction_id;
+        self.next_transaction_id += 1;
+        self.transactions.insert(
+            id,
+            Transaction {
+                connection: None,
+                origin: origin.ascii_serialization(),
+                database: name,
+                mode: IndexedDBTransactionMode::VersionChange,
+                scope: vec![],
+                new_version: None,
+                events: events,
+                started: false,
+                pending_version_changes: 0,
+                database_snapshot: None,
+                store_snapshots: HashMap::new(),
+            },
+        );
+        self.fire_version_change(id);
+        self.start_transactions();
+    }
+
+    fn begin_transaction(
+        &mut self,
+        events: IpcSender<IndexedDBScheduleEvent>,
+        connection: u64,
+        mode: IndexedDBTransactionMode,
+        scope: Vec<String>,
+        new_version: Option<u64>,
+    ) -> IndexedDBResult<u64> {
+        let (origin, database) = match self.connections.get(&connection) {
+            Some(connection) => (connection.origin.clone(), connection.database.clone()),
+            None => return Err(IndexedDBError::InvalidState),
+        };
+        let id = self.next_transaction_id;
+        self.next_transaction_id += 1;
+        self.transactions.insert(
+            id,
+            Transaction {
+                connection: Some(connection),
+                origin: origin,
+                database: database,
+                mode: mode,
+                scope: scope,
+                new_version: new_version,
+                events: events,
+                started: false,
+                pending_version_changes: 0,
+                database_snapshot: None,
+                store_snapshots: HashMap::new(),
+            },
+        );
+        if mode == IndexedDBTransactionMode::VersionChange {
+            self.fire_version_change(id);
+        }
+        self.start_transactions();
+        Ok(id)
+    }
+
+    /// The version of a database, or 0 if it does not exist.
+    fn version(&self, origin: &str, name: &str) -> u64 {
+        self.origins
+            .get(origin)
+            .and_then(|databases| databases.get(name))
+            .map_or(0, |database| database.version)
+    }
+
+    /// The connections to the database of a transaction other than its own.
+    fn other_connections<'a>(
+        &'a self,
+        transaction: &'a Transaction,
+    ) -> impl Iterator<Item = (&'a u64, &'a Connection)> + 'a {
+        self.connections.iter().filter(move |&(id, connection)| {
+            Some(*id) != transaction.connection &&
+                connection.origin == transaction.origin &&
+                connection.database == transaction.database
+        })
+    }
+
+    /// Tells the other connections to the database of an upgrade or deletion about it.
+    /// Connections whose event loop is gone are closed.
+    fn fire_version_change(&mut self, id: u64) {
+        let transaction = &self.transactions[&id];
+        let version = self.version(&transaction.origin, &transaction.database);
+        let mut fired = 0;
+        let mut gone = vec![];
+        for (&connection_id, connection) in self.other_connections(transaction) {
+            let event =
+                IndexedDBConnectionEvent::VersionChange(id, version, transaction.new_version);
+            match connection.events.send(event) {
+                Ok(()) => fired += 1,
+                Err(_) => gone.push(connection_id),
+            }
+        }
+        for connection_id in gone {
+            self.connections.remove(&connection_id);
+        }
+        if let Some(transaction) = self.transactions.get_mut(&id) {
+            transaction.pending_version_changes = fired;
+        }
+    }
+
+    /// Reports an upgrade or deletion as blocked once every other connection handled its
+    /// versionchange event without closing.
+    fn version_change_fired(&mut self, id: u64) {
+        let transaction = match self.transactions.get_mut(&id) {
+            Some(transaction) => transaction,
+            None => return,
+        };
+        transaction.pending_version_changes = transaction.pending_version_changes.saturating_sub(1);
+        let transaction = &self.transactions[&id];
+        if transaction.started ||
+            transaction.pending_version_changes > 0 ||
+            self.other_connections(transaction).next().is_none()
+        {
+            return;
+        }
+        let version = self.version(&transaction.origin, &transaction.database);
+        let _ = transaction
+            .events
+            .send(IndexedDBScheduleEvent::Blocked(version));
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#transaction-scheduling>
+    fn start_transactions(&mut self) {
+        let waiting: Vec<u64> = self
+            .transactions
+            .iter()
+            .filter(|&(_, transaction)| !transaction.started)
+            .map(|(&id, _)| id)
+            .collect();
+        for id in waiting {
+            let transaction = match self.transactions.get(&id) {
+                Some(transaction) => transaction,
+                None => continue,
+            };
+            let waits_for_transaction = self
+                .transactions
+                .range(..id)
+                .any(|(_, earlier)| earlier.overlaps(transaction));
+            let waits_for_connection = transaction.mode == IndexedDBTransactionMode::VersionChange &&
+                self.other_connections(transaction).next().is_some();
+            if !waits_for_transaction && !waits_for_connection {
+                self.start(id);
+            }
+        }
+    }
+
+    fn start(&mut self, id: u64) {
+        let (origin, name, mode, new_version) = {
+            let transaction = &self.transactions[&id];
+            (
+                transaction.origin.clone(),
+                transaction.database.clone(),
+                transaction.mode,
+                transaction.new_version,
+            )
+        };
+        let version = self.version(&origin, &name);
+        if mode == IndexedDBTransactionMode::VersionChange {
+            let databases = self.origins.entry(origin.clone()).or_default();
+            match new_version {
+                Some(new_version) => {
+                    let snapshot = databases.get_mut(&name).map(|database| {
+                        let snapshot = database.clone();
+                        database.version = new_version;
+                        snapshot
+                    });
+                    self.transactions.get_mut(&id).unwrap().database_snapshot = snapshot;
+                },
+                None => {
+                    databases.remove(&name);
+                    if let Some(dir) = self.database_dir(&origin, &name) {
+                        if let Err(why) = fs::remove_dir_all(&dir) {
+                            if why.kind() != ErrorKind::NotFound {
+                                warn!("couldn't remove {}: {}", dir.display(), why);
+                            }
+                        }
+                    }
+                    // A deletion is done as soon as it starts.
+                    let transaction = self.transactions.remove(&id).unwrap();
+                    let _ = transaction
+                        .events
+                        .send(IndexedDBScheduleEvent::Started(version));
+                    return;
+                },
+            }
+        }
+        let transaction = self.transactions.get_mut(&id).unwrap();
+        transaction.started = true;
+        let _ = transaction
+            .events
+            .send(IndexedDBScheduleEvent::Started(version));
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#commit-a-transaction>
+    fn commit(&mut self, id: u64) -> IndexedDBResult<()> {
+        let transaction = self
+            .transactions
+            .remove(&id)
+            .ok_or(IndexedDBError::TransactionInactive)?;
+        if transaction.started && transaction.mode != IndexedDBTransactionMode::ReadOnly {
+            self.save_database(&transaction.origin, &transaction.database);
+        }
+        self.start_transactions();
+        Ok(())
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#abort-a-transaction>
+    fn abort(&mut self, id: u64) {
+        let transaction = match self.transactions.remove(&id) {
+            Some(transaction) => transaction,
+            None => return,
+        };
+        if let Some(databases) = self.origins.get_mut(&transaction.origin) {
+            if let Some(snapshot) = transaction.database_snapshot {
+                // Aborting the upgrade of a new database leaves no database behind.
+                if snapshot.version == 0 {
+                    databases.remove(&transaction.database);
+                } else {
+                    databases.insert(transaction.database, snapshot);
+                }
+            } else if let Some(database) = databases.get_mut(&transaction.database) {
+                database.object_stores.extend(transaction.store_snapshots);
+            }
+        }
+        self.start_transactions();
+    }
+
+    /// Runs a change to the structure of the database of a versionchange transaction.
+    fn with_database<T, F>(&mut self, id: u64, f: F) -> IndexedDBResult<T>
+    where
+        F: FnOnce(&mut Database) -> IndexedDBResult<T>,
+    {
+        let transaction = self
+            .transactions
+            .get(&id)
+            .filter(|transaction| transaction.started)
+            .ok_or(IndexedDBError::TransactionInactive)?;
+        if transaction.mode != IndexedDBTransactionMode::VersionChange {
+            return Err(IndexedDBError::InvalidState);
+        }
+        let database = self
+            .origins
+            .get_mut(&transaction.origin)
+            .and_then(|databases| databases.get_mut(&transaction.database))
+            .ok_or(IndexedDBError::NotFound)?;
+        f(database)
+    }
+
+    /// Runs an operation on an object store within a transaction. The closure is also given
+    /// the number of bytes the origin uses, to check the quota against.
+    fn with_object_store<T, F>(
+        &mut self,
+        id: u64,
+        store_name: &str,
+        write: bool,
+        f: F,
+    ) -> IndexedDBResult<T>
+    where
+        F: FnOnce(&mut ObjectStore, usize) -> IndexedDBResult<T>,
+    {
+        let transaction = self
+            .transactions
+            .get_mut(&id)
+            .filter(|transaction| transaction.started)
+            .ok_or(IndexedDBError::TransactionInactive)?;
+        if write && transaction.mode == IndexedDBTransactionMode::ReadOnly {
+            return Err(IndexedDBError::ReadOnly);
+        }
+        if transaction.mode != IndexedDBTransactionMode::VersionChange &&
+            !transaction.scope.iter().any(|name| name == store_name)
+        {
+            return Err(IndexedDBError::NotFound);
+        }
+        let databases = self
+            .origins
+            .get_mut(&transaction.origin)
+            .ok_or(IndexedDBError::NotFound)?;
+        let usage = databases
+            .values()
+            .flat_map(|database| database.object_stores.values())
+            .map(|object_store| object_store.size)
+            .sum();
+        let object_store = databases
+            .get_mut(&transaction.database)
+            .and_then(|database| database.object_stores.get_mut(store_name))
+            .ok_or(IndexedDBError::NotFound)?;
+        if write && transaction.database_snapshot.is_none() {
+            transaction
+                .store_snapshots
+                .entry(store_name.to_owned())
+                .or_insert_with(|| object_store.clone());
+        }
+        f(object_store, usage)
+    }
+}
+
+impl ObjectStore {
+    fn info(&self, name: &str) -> ObjectStoreInfo {
+        ObjectStoreInfo {
+            name: name.to_owned(),
+            key_path: self.key_path.clone(),
+            auto_increment: self.auto_increment,
+            indexes: self
+                .indexes
+                .values()
+                .map(|index| index.info.clone())
+                .collect(),
+        }
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#generate-a-key>
+    fn generate_key(&mut self) -> IndexedDBResult<IndexedDBKeyType> {
+        if self.current_number > MAX_GENERATED_KEY {
+            return Err(IndexedDBError::Constraint);
+        }
+        let key = IndexedDBKeyType::Number(self.current_number as f64);
+        self.current_number += 1;
+        Ok(key)
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#possibly-update-the-key-generator>
+    fn possibly_update_key_generator(&mut self, key: &IndexedDBKeyType) {
+        if let IndexedDBKeyType::Number(number) = *key {
+            if number >= self.current_number as f64 {
+                let next = number.floor().min(MAX_GENERATED_KEY as f64) as u64 + 1;
+                self.current_number = next;
+            }
+        }
+    }
+
+    fn create_index(
+        &mut self,
+        info: IndexInfo,
+        keys: Vec<(IndexedDBKeyType, Vec<IndexedDBKeyType>)>,
+    ) -> IndexedDBResult<()> {
+        if self.indexes.contains_key(&info.name) {
+            return Err(IndexedDBError::Constraint);
+        }
+        let mut index = Index {
+            info: info,
+            entries: BTreeMap::new(),
+        };
+        for &(ref primary_key, ref index_keys) in &keys {
+            if index.violates_uniqueness(primary_key, index_keys) {
+                return Err(IndexedDBError::Constraint);
+            }
+            index.insert(primary_key, index_keys);
+        }
+        for (primary_key, index_keys) in keys {
+            if let Some(record) = self.records.get_mut(&primary_key) {
+                record
+                    .index_keys
+                    .insert(index.info.name.clone(), index_keys);
+            }
+        }
+        // Every record is written again, so that none keeps the keys of an index that had the
+        // same name before.
+        self.dirty.extend(self.records.keys().cloned());
+        self.indexes.insert(index.info.name.clone(), index);
+        Ok(())
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#store-a-record-into-an-object-store>
+    fn put(&mut self, put: IndexedDBPut, usage: usize) -> IndexedDBResult<IndexedDBKeyType> {
+        let key = match put.key {
+            Some(key) => {
+                if self.auto_increment {
+                    self.possibly_update_key_generator(&key);
+                }
+                key
+            },
+            None => self.generate_key()?,
+        };
+        if put.no_overwrite && self.records.contains_key(&key) {
+            return Err(IndexedDBError::Constraint);
+        }
+        for &(ref name, ref index_keys) in &put.index_keys {
+            if let Some(index) = self.indexes.get(name) {
+                if index.violates_uniqueness(&key, index_keys) {
+                    return Err(IndexedDBError::Constraint);
+                }
+            }
+        }
+        let old_size = self
+            .records
+            .get(&key)
+            .map_or(0, |record| key_size(&key) + record.value.len());
+        let new_size = key_size(&key) + put.value.len();
+        if usage - old_size + new_size > QUOTA_SIZE_LIMIT {
+            return Err(IndexedDBError::QuotaExceeded);
+        }
+        self.remove_record(&key);
+        let record = Record {
+            value: put.value,
+            index_keys: put.index_keys.into_iter().collect(),
+        };
+        self.insert_record(key.clone(), record);
+        self.dirty.insert(key.clone());
+        Ok(key)
+    }
+
+    /// Adds a record to the store and to its indexes. Keys for indexes the store does not
+    /// have are dropped.
+    fn insert_record(&mut self, key: IndexedDBKeyType, mut record: Record) {
+        let indexes = &mut self.indexes;
+        record
+            .index_keys
+            .retain(|name, index_keys| match indexes.get_mut(name) {
+                Some(index) => {
+                    index.insert(&key, index_keys);
+                    true
+                },
+                None => false,
+            });
+        self.size += key_size(&key) + record.value.len();
+        self.records.insert(key, record);
+    }
+
+    fn remove_record(&mut self, key: &IndexedDBKeyType) {
+        let record = match self.records.remove(key) {
+            Some(record) => record,
+            None => return,
+        };
+        self.size -= key_size(key) + record.value.len();
+        for (name, index_keys) in &record.index_keys {
+            if let Some(index) = self.indexes.get_mut(name) {
+                index.remove(key, index_keys);
+            }
+        }
+        self.dirty.insert(key.clone());
+    }
+
+    fn remove_records(&mut self, range: &IndexedDBKeyRange) {
+        let keys: Vec<_> = self
+            .records
+            .keys()
+            .filter(|key| range.contains(key))
+            .cloned()
+            .collect();
+        for key in keys {
+            self.remove_record(&key);
+        }
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#iterate-a-cursor>, and the retrieval of values and keys
+    /// from an object store or index.
+    fn query(&self, query: &IndexedDBQuery) -> IndexedDBResult<Vec<IndexedDBRecord>> {
+        let reverse = query.direction.is_reverse();
+        let limit = query
+            .count
+            .map_or(usize::max_value(), |count| count as usize);
+        let position = query.position.as_ref();
+
+        let index = match query.index {
+            Some(ref name) => self.indexes.get(name).ok_or(IndexedDBError::NotFound)?,
+            None => {
+                let position = position.map(|position| (&position.key, position.inclusive));
+                let bounds = match bounds(&query.range, position, reverse) {
+                    Some(bounds) => bounds,
+                    None => return Ok(vec![]),
+                };
+                let records = self.records.range(bounds);
+                let records: Box<dyn Iterator<Item = _>> = if reverse {
+                    Box::new(records.rev())
+                } else {
+                    Box::new(records)
+                };
+                return Ok(records
+                    .take(limit)
+                    .map(|(key, record)| IndexedDBRecord {
+                        key: key.clone(),
+                        primary_key: key.clone(),
+                        value: if query.with_values {
+                            Some(record.value.clone())
+                        } else {
+                            None
+                        },
+                    })
+                    .collect());
+            },
+        };
+
+        // Within the index key a cursor is at, it continues from its primary key.
+        let key_position = position.map(|position| match position.primary_key {
+            Some(_) => (&position.key, true),
+            None => (&position.key, position.inclusive),
+        });
+        let bounds = match bounds(&query.range, key_position, reverse) {
+            Some(bounds) => bounds,
+            None => return Ok(vec![]),
+        };
+        let entries = index.entries.range(bounds);
+        let entries: Box<dyn Iterator<Item = _>> = if reverse {
+            Box::new(entries.rev())
+        } else {
+            Box::new(entries)
+        };
+        let mut records = vec![];
+        for (index_key, primary_keys) in entries {
+            let primary_keys: Vec<_> = match position {
+                Some(position) if position.key == *index_key => match position.primary_key {
+                    Some(ref primary_key) => primary_keys
+                        .iter()
+                        .filter(|key| match (reverse, position.inclusive) {
+                            (false, true) => *key >= primary_key,
+                            (false, false) => *key > primary_key,
+                            (true, true) => *key <= primary_key,
+                            (true, false) => *key < primary_key,
+                        })
+                        .collect(),
+                    None => primary_keys.iter().collect(),
+                },
+                _ => primary_keys.iter().collect(),
+            };
+            let primary_keys: Vec<_> = if query.direction.is_unique() {
+                // Unique cursors visit the record with the lowest primary key of each index key.
+                primary_keys.into_iter().take(1).collect()
+            } else if reverse {
+                primary_keys.into_iter().rev().collect()
+            } else {
+                primary_keys
+            };
+            for primary_key in primary_keys {
+                if records.len() == limit {
+                    return Ok(records);
+                }
+                records.push(IndexedDBRecord {
+                    key: index_key.clone(),
+                    primary_key: primary_key.clone(),
+                    value: if query.with_values {
+                        self.records
+                            .get(primary_key)
+                            .map(|record| record.value.clone())
+                    } else {
+                        None
+                    },
+                });
+            }
+        }
+        Ok(records)
+    }
+
+    fn count(&self, index: Option<&str>, range: &IndexedDBKeyRange) -> IndexedDBResult<u64> {
+        let count = match index {
+            Some(name) => {
+                let index = self.indexes.get(name).ok_or(IndexedDBError::NotFound)?;
+                index
+                    .entries
+                    .iter()
+                    .filter(|&(key, _)| range.contains(key))
+                    .map(|(_, primary_keys)| primary_keys.len())
+                    .sum()
+            },
+            None => self
+                .records
+                .keys()
+                .filter(|key| range.contains(key))
+                .count(),
+        };
+        Ok(count as u64)
+    }
+}
+
+impl Index {
+    fn violates_uniqueness(
+        &self,
+        primary_key: &IndexedDBKeyType,
+        index_keys: &[IndexedDBKeyType],
+    ) -> bool {
+        self.info.unique &&
+            index_keys.iter().any(|index_key| {
+                self.entries.get(index_key).map_or(false, |primary_keys| {
+                    primary_keys.iter().any(|key| key != primary_key)
+                })
+            })
+    }
+
+    fn insert(&mut self, primary_key: &IndexedDBKeyType, index_keys: &[IndexedDBKeyType]) {
+        for index_key in index_keys {
+            self.entries
+                .entry(index_key.clone())
+                .or_insert_with(BTreeSet::new)
+                .insert(primary_key.clone());
+        }
+    }
+
+    fn remove(&mut self, primary_key: &IndexedDBKeyType, index_keys: &[IndexedDBKeyType]) {
+        for index_key in index_keys {
+            let now_empty = match self.entries.get_mut(index_key) {
+                Some(primary_keys) => {
+                    primary_keys.remove(primary_key);
+                    primary_keys.is_empty()
+                },
+                None => false,
+            };
+            if now_empty {
+                self.entries.remove(index_key);
+            }
+        }
+    }
+}
+
+/// The bounds of a key range, narrowed to the keys after a cursor's position in the direction
+/// it iterates. `None` if no key is within them.
+fn bounds(
+    range: &IndexedDBKeyRange,
+    position: Option<(&IndexedDBKeyType, bool)>,
+    reverse: bool,
+) -> Option<(Bound<IndexedDBKeyType>, Bound<IndexedDBKeyType>)> {
+    fn bound(key: &Option<IndexedDBKeyType>, open: bool) -> Bound<IndexedDBKeyType> {
+        match *key {
+            Some(ref key) if open => Bound::Excluded(key.clone()),
+            Some(ref key) => Bound::Included(key.clone()),
+            None => Bound::Unbounded,
+        }
+    }
+    let mut lower = bound(&range.lower, range.lower_open);
+    let mut upper = bound(&range.upper, range.upper_open);
+    if let Some((key, inclusive)) = position {
+        let position = bound(&Some(key.clone()), !inclusive);
+        let narrower = match if reverse { &upper } else { &lower } {
+            Bound::Unbounded => true,
+            Bound::Included(ref bound) | Bound::Excluded(ref bound) => {
+                if reverse {
+                    key < bound || (key == bound && !inclusive)
+                } else {
+                    key > bound || (key == bound && !inclusive)
+                }
+            },
+        };
+        if narrower {
+            if reverse {
+                upper = position;
+            } else {
+                lower = position;
+            }
+        }
+    }
+    let empty = match (&lower, &upper) {
+        (Bound::Included(lower), Bound::Included(upper)) => lower > upper,
+        (Bound::Included(lower), Bound::Excluded(upper)) |
+        (Bound::Excluded(lower), Bound::Included(upper)) |
+        (Bound::Excluded(lower), Bound::Excluded(upper)) => lower >= upper,
+        _ => false,
+    };
+    if empty {
+        None
+    } else {
+        Some((lower, upper))
+    }
+}
+
+/// The number of bytes a key counts for towards the quota.
+fn key_size(key: &IndexedDBKeyType) -> usize {
+    match *key {
+        IndexedDBKeyType::Number(_) | IndexedDBKeyType::Date(_) => 8,
+        IndexedDBKeyType::String(ref string) => string.len(),
+        IndexedDBKeyType::Binary(ref bytes) => bytes.len(),
+        IndexedDBKeyType::Array(ref keys) => keys.iter().map(key_size).sum(),
+    }
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Reads the databases of an origin from its directory.
+fn read_databases(origin_dir: &Path) -> BTreeMap<String, Database> {
+    let entries = match fs::read_dir(origin_dir) {
+        Ok(entries) => entries,
+        Err(_) => return BTreeMap::new(),
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| read_database(&entry.path()))
+        .collect()
+}
+
+fn read_database(dir: &Path) -> Option<(String, Database)> {
+    let mut stored: Option<StoredDatabase> = None;
+    resource_thread::read_json_from_file(&mut stored, dir, DATABASE_FILE_NAME);
+    let stored = stored?;
+    let mut database = Database {
+        version: stored.version,
+        object_stores: BTreeMap::new(),
+        removed_stores: BTreeSet::new(),
+    };
+    for stored_store in stored.object_stores {
+        let mut object_store = ObjectStore {
+            key_path: stored_store.key_path,
+            auto_increment: stored_store.auto_increment,
+            current_number: stored_store.current_number,
+            records: BTreeMap::new(),
+            indexes: stored_store
+                .indexes
+                .into_iter()
+                .map(|info| {
+                    let index = Index {
+                        info: info,
+                        entries: BTreeMap::new(),
+                    };
+                    (index.info.name.clone(), index)
+                })
+                .collect(),
+            size: 0,
+            dirty: BTreeSet::new(),
+        };
+        let store_dir = dir.join(hex_encode(stored_store.name.as_bytes()));
+        if let Ok(entries) = fs::read_dir(&store_dir) {
+            for entry in entries.filter_map(|entry| entry.ok()) {
+                if let Some((key, record)) = read_record(&entry.path()) {
+                    object_store.insert_record(key, record);
+                }
+            }
+        }
+        database
+            .object_stores
+            .insert(stored_store.name, object_store);
+    }
+    Some((stored.name, database))
+}
+
+/// The name of the file of a record, in the directory of its object store.
+fn record_file_name(key: &IndexedDBKeyType) -> String {
+    let key = serde_json::to_vec(&StoredKey::new(key)).unwrap_or_default();
+    hex_encode(&sha256(&key))
+}
+
+/// Reads a record, written as a line with its key and index keys followed by its value.
+fn read_record(path: &Path) -> Option<(IndexedDBKeyType, Record)> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(why) => {
+            warn!("couldn't read {}: {}", path.display(), why);
+            return None;
+        },
+    };
+    let header_end = bytes.iter().position(|&byte| byte == b'\n')?;
+    let header: StoredRecord = match serde_json::from_slice(&bytes[..header_end]) {
+        Ok(header) => header,
+        Err(why) => {
+            warn!("couldn't decode {}: {}", path.display(), why);
+            return None;
+        },
+    };
+    let record = Record {
+        value: bytes[header_end + 1..].to_vec(),
+        index_keys: header
+            .index_keys
+            .into_iter()
+            .map(|(name, keys)| (name, keys.into_iter().map(StoredKey::into_key).collect()))
+            .collect(),
+    };
+    Some((header.key.into_key(), record))
+}
+
+/// Writes a record to the directory of its object store, or removes its file if the record
+/// does not exist anymore.
+fn write_record(store_dir: &Path, key: &IndexedDBKeyType, record: Option<&Record>) {
+    let path = store_dir.join(record_file_name(key));
+    let record = match record {
+        Some(record) => record,
+        None => {
+            if let Err(why) = fs::remove_file(&path) {
+                if why.kind() != ErrorKind::NotFound {
+                    warn!("couldn't remove {}: {}", path.display(), why);
+                }
+            }
+            return;
+        },
+    };
+    let header = StoredRecord {
+        key: StoredKey::new(key),
+        index_keys: record
+            .index_keys
+            .iter()
+            .map(|(name, keys)| (name.clone(), keys.iter().map(StoredKey::new).collect()))
+            .collect(),
+    };
+    let mut bytes = match serde_json::to_vec(&header) {
+        Ok(bytes) => bytes,
+        Err(_) => return,
+    };
+    bytes.push(b'\n');
+    bytes.extend_from_slice(&record.value);
+    if let Err(why) = fs::write(&path, bytes) {
+        warn!("couldn't write {}: {}", path.display(), why);
+    }
+}
+
+/// A key as written to disk. Numbers are kept as their bits, since JSON has no infinities.
+#[derive(Deserialize, Serialize)]
+enum StoredKey {
+    Number(u64),
+    Date(u64),
+    String(String),
+    Binary(String),
+    Array(Vec<StoredKey>),
+}
+
+impl StoredKey {
+    fn new(key: &IndexedDBKeyType) -> StoredKey {
+        match *key {
+            IndexedDBKeyType::Number(number) => StoredKey::Number(number.to_bits()),
+            IndexedDBKeyType::Date(date) => StoredKey::Date(date.to_bits()),
+            IndexedDBKeyType::String(ref string) => StoredKey::String(string.clone()),
+            IndexedDBKeyType::Binary(ref bytes) => StoredKey::Binary(base64::encode(bytes)),
+            IndexedDBKeyType::Array(ref keys) => {
+                StoredKey::Array(keys.iter().map(StoredKey::new).collect())
+            },
+        }
+    }
+
+    fn into_key(self) -> IndexedDBKeyType {
+        match self {
+            StoredKey::Number(bits) => IndexedDBKeyType::Number(f64::from_bits(bits)),
+            StoredKey::Date(bits) => IndexedDBKeyType::Date(f64::from_bits(bits)),
+            StoredKey::String(string) => IndexedDBKeyType::String(string),
+            StoredKey::Binary(bytes) => {
+                IndexedDBKeyType::Binary(base64::decode(&bytes).unwrap_or_default())
+            },
+            StoredKey::Array(keys) => {
+                IndexedDBKeyType::Array(keys.into_iter().map(StoredKey::into_key).collect())
+            },
+        }
+    }
+}
+
+/// The line at the start of the file of a record.
+#[derive(Deserialize, Serialize)]
+struct StoredRecord {
+    key: StoredKey,
+    index_keys: Vec<(String, Vec<StoredKey>)>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct StoredObjectStore {
+    name: String,
+    key_path: Option<KeyPath>,
+    auto_increment: bool,
+    current_number: u64,
+    indexes: Vec<IndexInfo>,
+}
+
+/// The metadata of a database. The records of its object stores have their own files.
+#[derive(Deserialize, Serialize)]
+struct StoredDatabase {
+    name: String,
+    version: u64,
+    object_stores: Vec<StoredObjectStore>,
+}
+
+impl StoredDatabase {
+    fn new(name: &str, database: &Database) -> StoredDatabase {
+        let object_stores = database
+            .object_stores
+            .iter()
+            .map(|(name, object_store)| StoredObjectStore {
+                name: name.clone(),
+                key_path: object_store.key_path.clone(),
+                auto_increment: object_store.auto_increment,
+                current_number: object_store.current_number,
+                indexes: object_store
+                    .indexes
+                    .values()
+                    .map(|index| index.info.clone())
+                    .collect(),
+            })
+            .collect();
+        StoredDatabase {
+            name: name.to_owned(),
+            version: database.version,
+            object_stores: object_stores,
+        }
+    }
+}
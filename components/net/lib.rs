@@ -17,20 +17,24 @@ extern crate serde;
 #[macro_use]
 extern crate servo_config;
 
+pub mod cache_storage;
 pub mod connector;
 pub mod cookie;
 pub mod cookie_storage;
+pub mod csp_directives;
 mod data_loader;
 mod decoder;
+pub mod download_manager;
 pub mod filemanager_thread;
 mod hosts;
 pub mod hsts;
 pub mod http_cache;
 pub mod http_loader;
 pub mod image_cache;
+pub mod indexeddb;
 pub mod mime_classifier;
 pub mod resource_thread;
-mod storage_thread;
+pub mod storage_thread;
 pub mod subresource_integrity;
 mod websocket_loader;
 /// An implementation of the [Fetch specification](https://fetch.spec.whatwg.org/)
@@ -9,8 +9,10 @@ use crate::connector::{
 };
 use crate::cookie;
 use crate::cookie_storage::CookieStorage;
+use crate::download_manager::{DownloadManager, NavigationTarget};
 use crate::fetch::cors_cache::CorsCache;
-use crate::fetch::methods::{fetch, CancellationListener, FetchContext};
+use crate::fetch::methods::ServiceWorkerManagers;
+use crate::fetch::methods::{fetch, CancellationListener, FetchContext, PauseControl};
 use crate::filemanager_thread::FileManager;
 use crate::hsts::HstsList;
 use crate::http_cache::HttpCache;
@@ -24,6 +26,7 @@ use embedder_traits::EmbedderProxy;
 use hyper_serde::Serde;
 use ipc_channel::ipc::{self, IpcReceiver, IpcReceiverSet, IpcSender};
 use malloc_size_of::{MallocSizeOf, MallocSizeOfOps};
+use msg::constellation_msg::TopLevelBrowsingContextId;
 use net_traits::blob_url_store::parse_blob_url;
 use net_traits::filemanager_thread::FileTokenCheck;
 use net_traits::request::{Destination, RequestBuilder};
@@ -33,7 +36,7 @@ use net_traits::DiscardFetch;
 use net_traits::FetchTaskTarget;
 use net_traits::WebSocketNetworkEvent;
 use net_traits::{CookieSource, CoreResourceMsg, CoreResourceThread};
-use net_traits::{CustomResponseMediator, FetchChannels};
+use net_traits::{FetchChannels, FetchResponseMsg};
 use net_traits::{ResourceFetchTiming, ResourceTimingType};
 use net_traits::{ResourceThreads, WebSocketDomAction};
 use profile_traits::mem::ProfilerChan as MemProfilerChan;
@@ -41,7 +44,7 @@ use profile_traits::mem::{Report, ReportKind, ReportsChan};
 use profile_traits::time::ProfilerChan;
 use serde::{Deserialize, Serialize};
 use servo_arc::Arc as ServoArc;
-use servo_url::{ImmutableOrigin, ServoUrl};
+use servo_url::ServoUrl;
 use std::borrow::{Cow, ToOwned};
 use std::collections::HashMap;
 use std::fs::{self, File};
@@ -101,6 +104,7 @@ pub fn new_core_resource_thread(
                 time_profiler_chan,
                 embedder_proxy,
                 certificate_path.clone(),
+                config_dir.clone(),
             );
 
             let mut channel_manager = ResourceChannelManager {
@@ -126,6 +130,20 @@ struct ResourceChannelManager {
     certificate_path: Option<String>,
 }
 
+/// The state of a private browsing session, which is never written to disk.
+struct PrivateSession {
+    http_state: Arc<HttpState>,
+    filemanager: FileManager,
+    downloads: DownloadManager,
+}
+
+fn read_certificates(certificate_path: Option<&str>) -> String {
+    match certificate_path {
+        Some(path) => fs::read_to_string(path).expect("Couldn't not find certificate file"),
+        None => resources::read_string(Resource::SSLCertificates),
+    }
+}
+
 fn create_http_states(
     config_dir: Option<&Path>,
     certificate_path: Option<String>,
@@ -140,10 +158,7 @@ fn create_http_states(
         read_json_from_file(&mut cookie_jar, config_dir, "cookie_jar.json");
     }
 
-    let certs = match certificate_path {
-        Some(ref path) => fs::read_to_string(path).expect("Couldn't not find certificate file"),
-        None => resources::read_string(Resource::SSLCertificates),
-    };
+    let certs = read_certificates(certificate_path.as_ref().map(Deref::deref));
 
     let extra_certs = ExtraCerts::new();
     let connection_certs = ConnectionCerts::new();
@@ -168,10 +183,18 @@ fn create_http_states(
         connection_certs,
     };
 
+    (
+        Arc::new(http_state),
+        Arc::new(create_ephemeral_http_state(&certs)),
+    )
+}
+
+/// Create an empty `HttpState`, for a session whose state is only kept in memory.
+fn create_ephemeral_http_state(certs: &str) -> HttpState {
     let extra_certs = ExtraCerts::new();
     let connection_certs = ConnectionCerts::new();
 
-    let private_http_state = HttpState {
+    HttpState {
         hsts_list: RwLock::new(HstsList::from_servo_preload()),
         cookie_jar: RwLock::new(CookieStorage::new(150)),
         auth_cache: RwLock::new(AuthCache::new()),
@@ -180,7 +203,7 @@ fn create_http_states(
         http_cache_state: Mutex::new(HashMap::new()),
         client: create_http_client(
             create_tls_config(
-                &certs,
+                certs,
                 ALPN_H2_H1,
                 extra_certs.clone(),
                 connection_certs.clone(),
@@ -189,9 +212,7 @@ fn create_http_states(
         ),
         extra_certs,
         connection_certs,
-    };
-
-    (Arc::new(http_state), Arc::new(private_http_state))
+    }
 }
 
 impl ResourceChannelManager {
@@ -207,6 +228,11 @@ impl ResourceChannelManager {
             self.certificate_path.clone(),
         );
 
+        let filemanager = self.resource_manager.filemanager.clone();
+        let public_downloads = self.resource_manager.downloads.clone();
+        let private_downloads = public_downloads.ephemeral();
+        let mut private_sessions: HashMap<u64, PrivateSession> = HashMap::new();
+
         let mut rx_set = IpcReceiverSet::new().unwrap();
         let private_id = rx_set.add(private_receiver).unwrap();
         let public_id = rx_set.add(public_receiver).unwrap();
@@ -215,8 +241,14 @@ impl ResourceChannelManager {
         loop {
             for receiver in rx_set.select().unwrap().into_iter() {
                 // Handles case where profiler thread shuts down before resource thread.
+                // A private session is over once all of its senders have been dropped.
                 match receiver {
-                    ipc::IpcSelectionResult::ChannelClosed(..) => continue,
+                    ipc::IpcSelectionResult::ChannelClosed(id) => {
+                        if let Some(session) = private_sessions.remove(&id) {
+                            session.downloads.exit();
+                        }
+                        continue;
+                    },
                     _ => {},
                 }
                 let (id, data) = receiver.unwrap();
@@ -226,17 +258,62 @@ impl ResourceChannelManager {
                         self.process_report(msg, &private_http_state, &public_http_state);
                         continue;
                     }
+                } else if let Some(session) = private_sessions.get(&id) {
+                    match data.to() {
+                        // Exiting a private session only discards the state of that session.
+                        Ok(CoreResourceMsg::Exit(sender)) => {
+                            session.downloads.exit();
+                            private_sessions.remove(&id);
+                            let _ = sender.send(());
+                        },
+                        Ok(msg) => {
+                            if !self.process_msg(
+                                msg,
+                                &session.http_state,
+                                &session.filemanager,
+                                &session.downloads,
+                            ) {
+                                return;
+                            }
+                        },
+                        Err(_) => {},
+                    }
                 } else {
-                    let group = if id == private_id {
-                        &private_http_state
+                    let (group, downloads) = if id == private_id {
+                        (&private_http_state, &private_downloads)
+                    } else if id == public_id {
+                        (&public_http_state, &public_downloads)
                     } else {
-                        assert_eq!(id, public_id);
-                        &public_http_state
+                        // A message for a private session that has already been discarded.
+                        continue;
                     };
-                    if let Ok(msg) = data.to() {
-                        if !self.process_msg(msg, group) {
-                            return;
-                        }
+                    match data.to() {
+                        Ok(CoreResourceMsg::NewPrivateSession(receiver)) => {
+                            let session_id = rx_set.add(receiver).unwrap();
+                            let certs =
+                                read_certificates(self.certificate_path.as_ref().map(Deref::deref));
+                            private_sessions.insert(
+                                session_id,
+                                PrivateSession {
+                                    http_state: Arc::new(create_ephemeral_http_state(&certs)),
+                                    filemanager: self.resource_manager.new_filemanager(),
+                                    downloads: public_downloads.ephemeral(),
+                                },
+                            );
+                        },
+                        Ok(msg) => {
+                            if let CoreResourceMsg::Exit(_) = msg {
+                                // Private downloads can't outlive the resource thread.
+                                private_downloads.exit();
+                                for session in private_sessions.values() {
+                                    session.downloads.exit();
+                                }
+                            }
+                            if !self.process_msg(msg, group, &filemanager, downloads) {
+                                return;
+                            }
+                        },
+                        Err(_) => {},
                     }
                 }
             }
@@ -269,12 +346,41 @@ impl ResourceChannelManager {
     }
 
     /// Returns false if the thread should exit.
-    fn process_msg(&mut self, msg: CoreResourceMsg, http_state: &Arc<HttpState>) -> bool {
+    fn process_msg(
+        &mut self,
+        msg: CoreResourceMsg,
+        http_state: &Arc<HttpState>,
+        filemanager: &FileManager,
+        downloads: &DownloadManager,
+    ) -> bool {
         match msg {
             CoreResourceMsg::Fetch(req_init, channels) => match channels {
-                FetchChannels::ResponseMsg(sender, cancel_chan) => {
-                    self.resource_manager
-                        .fetch(req_init, None, sender, http_state, cancel_chan)
+                FetchChannels::ResponseMsg(sender, cancel_chan, body_control) => {
+                    let mut cancellation_listener = CancellationListener::new(cancel_chan);
+                    if let Some(body_control) = body_control {
+                        cancellation_listener = cancellation_listener
+                            .with_pause_control(PauseControl::controlled_by(body_control));
+                    }
+                    if req_init.destination == Destination::Document {
+                        self.resource_manager.fetch_navigation(
+                            req_init,
+                            None,
+                            sender,
+                            http_state,
+                            filemanager,
+                            downloads,
+                            cancellation_listener,
+                        )
+                    } else {
+                        self.resource_manager.fetch(
+                            req_init,
+                            None,
+                            sender,
+                            http_state,
+                            filemanager,
+                            cancellation_listener,
+                        )
+                    }
                 },
                 FetchChannels::WebSocket {
                     event_sender,
@@ -285,10 +391,14 @@ impl ResourceChannelManager {
                     action_receiver,
                     http_state,
                 ),
-                FetchChannels::Prefetch => {
-                    self.resource_manager
-                        .fetch(req_init, None, DiscardFetch, http_state, None)
-                },
+                FetchChannels::Prefetch => self.resource_manager.fetch(
+                    req_init,
+                    None,
+                    DiscardFetch,
+                    http_state,
+                    filemanager,
+                    CancellationListener::new(None),
+                ),
             },
             CoreResourceMsg::DeleteCookies(request) => {
                 http_state
@@ -298,9 +408,28 @@ impl ResourceChannelManager {
                     .clear_storage(&request);
                 return true;
             },
-            CoreResourceMsg::FetchRedirect(req_init, res_init, sender, cancel_chan) => self
-                .resource_manager
-                .fetch(req_init, Some(res_init), sender, http_state, cancel_chan),
+            CoreResourceMsg::FetchRedirect(req_init, res_init, sender, cancel_chan) => {
+                self.resource_manager.fetch_navigation(
+                    req_init,
+                    Some(res_init),
+                    sender,
+                    http_state,
+                    filemanager,
+                    downloads,
+                    CancellationListener::new(cancel_chan),
+                )
+            },
+            CoreResourceMsg::Download(req_init, browser_id, suggested_filename) => {
+                self.resource_manager.download(
+                    req_init,
+                    browser_id,
+                    suggested_filename,
+                    http_state,
+                    filemanager,
+                    downloads,
+                )
+            },
+            CoreResourceMsg::DownloadAction(id, action) => downloads.handle_action(id, action),
             CoreResourceMsg::SetCookieForUrl(request, cookie, source) => self
                 .resource_manager
                 .set_cookie_for_url(&request, cookie.into_inner(), source, http_state),
@@ -324,6 +453,8 @@ impl ResourceChannelManager {
             CoreResourceMsg::NetworkMediator(mediator_chan, origin) => {
                 self.resource_manager
                     .sw_managers
+                    .lock()
+                    .unwrap()
                     .insert(origin, mediator_chan);
             },
             CoreResourceMsg::GetCookiesDataForUrl(url, consumer, source) => {
@@ -357,7 +488,10 @@ impl ResourceChannelManager {
             CoreResourceMsg::ClearCache => {
                 http_state.http_cache.write().unwrap().clear();
             },
-            CoreResourceMsg::ToFileManager(msg) => self.resource_manager.filemanager.handle(msg),
+            CoreResourceMsg::ToFileManager(msg) => filemanager.handle(msg),
+            CoreResourceMsg::NewPrivateSession(..) => {
+                warn!("Private sessions can only be started from the public resource thread.");
+            },
             CoreResourceMsg::Exit(sender) => {
                 if let Some(ref config_dir) = self.config_dir {
                     match http_state.auth_cache.read() {
@@ -458,8 +592,10 @@ pub struct AuthCache {
 pub struct CoreResourceManager {
     user_agent: Cow<'static, str>,
     devtools_chan: Option<Sender<DevtoolsControlMsg>>,
-    sw_managers: HashMap<ImmutableOrigin, IpcSender<CustomResponseMediator>>,
+    sw_managers: ServiceWorkerManagers,
+    embedder_proxy: EmbedderProxy,
     filemanager: FileManager,
+    downloads: DownloadManager,
     thread_pool: Arc<CoreResourceThreadPool>,
     certificate_path: Option<String>,
 }
@@ -506,6 +642,16 @@ impl ThreadPoolState {
     }
 }
 
+/// Where a fetch runs.
+#[derive(Clone, Copy, PartialEq)]
+enum FetchThread {
+    /// The shared thread-pool, for fetches that finish as soon as the network lets them.
+    Pool,
+    /// A thread of its own, for fetches that may turn into downloads, which can stay
+    /// paused, or wait on the embedder, for as long as the user wants.
+    Dedicated,
+}
+
 /// Threadpool used by Fetch and file operations.
 pub struct CoreResourceThreadPool {
     pool: rayon::ThreadPool,
@@ -596,6 +742,7 @@ impl CoreResourceManager {
         _profiler_chan: ProfilerChan,
         embedder_proxy: EmbedderProxy,
         certificate_path: Option<String>,
+        config_dir: Option<PathBuf>,
     ) -> CoreResourceManager {
         let pool = CoreResourceThreadPool::new(16);
         let pool_handle = Arc::new(pool);
@@ -603,14 +750,27 @@ impl CoreResourceManager {
             user_agent: user_agent,
             devtools_chan: devtools_channel,
             sw_managers: Default::default(),
-            filemanager: FileManager::new(embedder_proxy, Arc::downgrade(&pool_handle)),
+            downloads: DownloadManager::new(embedder_proxy.clone(), config_dir),
+            filemanager: FileManager::new(embedder_proxy.clone(), Arc::downgrade(&pool_handle)),
+            embedder_proxy,
             thread_pool: pool_handle,
             certificate_path,
         }
     }
 
+    /// Create a file manager with a blob store of its own.
+    fn new_filemanager(&self) -> FileManager {
+        FileManager::new(
+            self.embedder_proxy.clone(),
+            Arc::downgrade(&self.thread_pool),
+        )
+    }
+
     /// Exit the core resource manager.
     pub fn exit(&mut self) {
+        // Cancel the ongoing downloads, and save the download history.
+        self.downloads.exit();
+
         // Prevents further work from being spawned on the pool,
         // blocks until all workers in the pool are done,
         // or a short timeout has been reached.
@@ -639,17 +799,88 @@ impl CoreResourceManager {
     }
 
     fn fetch<Target: 'static + FetchTaskTarget + Send>(
+        &self,
+        request_builder: RequestBuilder,
+        res_init_: Option<ResponseInit>,
+        sender: Target,
+        http_state: &Arc<HttpState>,
+        filemanager: &FileManager,
+        cancellation_listener: CancellationListener,
+    ) {
+        let cancellation_listener = Arc::new(Mutex::new(cancellation_listener));
+        self.spawn_fetch(
+            request_builder,
+            res_init_,
+            sender,
+            http_state,
+            filemanager,
+            cancellation_listener,
+            FetchThread::Pool,
+        );
+    }
+
+    /// Fetch a document, turning the response into a download if it is not meant to be rendered.
+    fn fetch_navigation(
+        &self,
+        request_builder: RequestBuilder,
+        res_init_: Option<ResponseInit>,
+        sender: IpcSender<FetchResponseMsg>,
+        http_state: &Arc<HttpState>,
+        filemanager: &FileManager,
+        downloads: &DownloadManager,
+        cancellation_listener: CancellationListener,
+    ) {
+        let cancellation_listener = Arc::new(Mutex::new(cancellation_listener));
+        let target =
+            NavigationTarget::new(sender, downloads.clone(), cancellation_listener.clone());
+        self.spawn_fetch(
+            request_builder,
+            res_init_,
+            target,
+            http_state,
+            filemanager,
+            cancellation_listener,
+            FetchThread::Dedicated,
+        );
+    }
+
+    fn download(
+        &self,
+        request_builder: RequestBuilder,
+        browser_id: Option<TopLevelBrowsingContextId>,
+        suggested_filename: Option<String>,
+        http_state: &Arc<HttpState>,
+        filemanager: &FileManager,
+        downloads: &DownloadManager,
+    ) {
+        let (target, cancellation_listener) =
+            downloads.start(request_builder.url.clone(), browser_id, suggested_filename);
+        self.spawn_fetch(
+            request_builder,
+            None,
+            target,
+            http_state,
+            filemanager,
+            Arc::new(Mutex::new(cancellation_listener)),
+            FetchThread::Dedicated,
+        );
+    }
+
+    fn spawn_fetch<Target: 'static + FetchTaskTarget + Send>(
         &self,
         request_builder: RequestBuilder,
         res_init_: Option<ResponseInit>,
         mut sender: Target,
         http_state: &Arc<HttpState>,
-        cancel_chan: Option<IpcReceiver<()>>,
+        filemanager: &FileManager,
+        cancellation_listener: Arc<Mutex<CancellationListener>>,
+        fetch_thread: FetchThread,
     ) {
         let http_state = http_state.clone();
         let ua = self.user_agent.clone();
         let dc = self.devtools_chan.clone();
-        let filemanager = self.filemanager.clone();
+        let filemanager = filemanager.clone();
+        let sw_managers = self.sw_managers.clone();
 
         let timing_type = match request_builder.destination {
             Destination::Document => ResourceTimingType::Navigation,
@@ -672,7 +903,7 @@ impl CoreResourceManager {
         let (file_token, blob_url_file_id) = match url.scheme() {
             "blob" => {
                 if let Ok((id, _)) = parse_blob_url(&url) {
-                    (self.filemanager.get_token_for_file(&id), Some(id))
+                    (filemanager.get_token_for_file(&id), Some(id))
                 } else {
                     (FileTokenCheck::ShouldFail, None)
                 }
@@ -680,18 +911,18 @@ impl CoreResourceManager {
             _ => (FileTokenCheck::NotRequired, None),
         };
 
-        self.thread_pool.spawn(move || {
+        let work = move || {
             // XXXManishearth: Check origin against pipeline id (also ensure that the mode is allowed)
             // todo load context / mimesniff in fetch
             // todo referrer policy?
-            // todo service worker stuff
             let context = FetchContext {
                 state: http_state,
                 user_agent: ua,
                 devtools_chan: dc,
                 filemanager: filemanager,
                 file_token,
-                cancellation_listener: Arc::new(Mutex::new(CancellationListener::new(cancel_chan))),
+                cancellation_listener,
+                service_worker_managers: sw_managers,
                 timing: ServoArc::new(Mutex::new(ResourceFetchTiming::new(request.timing_type()))),
             };
 
@@ -717,7 +948,16 @@ impl CoreResourceManager {
                     .filemanager
                     .invalidate_token(&context.file_token, id);
             }
-        });
+        };
+        match fetch_thread {
+            FetchThread::Pool => self.thread_pool.spawn(work),
+            FetchThread::Dedicated => {
+                thread::Builder::new()
+                    .name(format!("Fetch {}", url))
+                    .spawn(work)
+                    .expect("Thread spawning failed");
+            },
+        }
     }
 
     fn websocket_connect(
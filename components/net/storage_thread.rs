@@ -2,6 +2,8 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
+use crate::cache_storage::CacheStorageManager;
+use crate::indexeddb::IndexedDBManager;
 use crate::resource_thread;
 use ipc_channel::ipc::{self, IpcReceiver, IpcSender};
 use net_traits::storage_thread::{StorageThreadMsg, StorageType};
@@ -37,6 +39,8 @@ struct StorageManager {
     session_data: HashMap<String, (usize, BTreeMap<String, String>)>,
     local_data: HashMap<String, (usize, BTreeMap<String, String>)>,
     config_dir: Option<PathBuf>,
+    indexeddb: IndexedDBManager,
+    cache_storage: CacheStorageManager,
 }
 
 impl StorageManager {
@@ -49,6 +53,8 @@ impl StorageManager {
             port: port,
             session_data: HashMap::new(),
             local_data: local_data,
+            indexeddb: IndexedDBManager::new(config_dir.clone()),
+            cache_storage: CacheStorageManager::new(config_dir.clone()),
             config_dir: config_dir,
         }
     }
@@ -56,8 +62,9 @@ impl StorageManager {
 
 impl StorageManager {
     fn start(&mut self) {
-        loop {
-            match self.port.recv().unwrap() {
+        // The storage of a private session lives until the last sender for it is dropped.
+        while let Ok(msg) = self.port.recv() {
+            match msg {
                 StorageThreadMsg::Length(sender, url, storage_type) => {
                     self.length(sender, url, storage_type)
                 },
@@ -82,6 +89,8 @@ impl StorageManager {
                     self.clear(sender, url, storage_type);
                     self.save_state()
                 },
+                StorageThreadMsg::IndexedDB(msg) => self.indexeddb.handle_msg(msg),
+                StorageThreadMsg::CacheStorage(msg) => self.cache_storage.handle_msg(msg),
                 StorageThreadMsg::Exit(sender) => {
                     // Nothing to do since we save localstorage set eagerly.
                     let _ = sender.send(());
@@ -0,0 +1,291 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use http::header::{HeaderValue, ACCEPT, VARY};
+use http::{HeaderMap, Method};
+use ipc_channel::ipc::{self, IpcSender};
+use net::storage_thread::StorageThreadFactory;
+use net_traits::cache_storage_thread::{CacheMatch, CachePut, CacheQuery, CacheQueryOptions};
+use net_traits::cache_storage_thread::{CacheRequest, CacheResponse, CacheStorageThreadMsg};
+use net_traits::request::{BodyChunkRequest, BodyChunkResponse, BodySource, RequestBody};
+use net_traits::response::ResponseType;
+use net_traits::storage_thread::StorageThreadMsg;
+use servo_url::{ImmutableOrigin, ServoUrl};
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+use uuid::Uuid;
+
+fn origin() -> ImmutableOrigin {
+    ServoUrl::parse("https://servo.org").unwrap().origin()
+}
+
+fn temp_config_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("servo-cache-storage-{}", Uuid::new_v4()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn send<T, F>(storage: &IpcSender<StorageThreadMsg>, message: F) -> T
+where
+    T: for<'de> serde::Deserialize<'de> + serde::Serialize,
+    F: FnOnce(IpcSender<T>) -> CacheStorageThreadMsg,
+{
+    let (sender, receiver) = ipc::channel().unwrap();
+    storage
+        .send(StorageThreadMsg::CacheStorage(message(sender)))
+        .unwrap();
+    receiver.recv().unwrap()
+}
+
+fn request(url: &str, headers: HeaderMap) -> CacheRequest {
+    CacheRequest {
+        url: ServoUrl::parse(url).unwrap(),
+        method: Method::GET,
+        headers: headers,
+    }
+}
+
+fn query(url: &str, headers: HeaderMap) -> CacheQuery {
+    CacheQuery {
+        request: request(url, headers),
+        options: CacheQueryOptions::default(),
+    }
+}
+
+/// A body that script streams in the given chunks.
+fn body(chunks: Vec<&'static str>) -> RequestBody {
+    let (sender, receiver) = ipc::channel().unwrap();
+    thread::spawn(move || {
+        let mut chunks = chunks.into_iter();
+        let mut body_sender = None;
+        while let Ok(message) = receiver.recv() {
+            match message {
+                BodyChunkRequest::Connect(sender) => body_sender = Some(sender),
+                BodyChunkRequest::Chunk => {
+                    let body_sender = body_sender.as_ref().unwrap();
+                    match chunks.next() {
+                        Some(chunk) => body_sender
+                            .send(BodyChunkResponse::Chunk(chunk.as_bytes().to_vec()))
+                            .unwrap(),
+                        None => return body_sender.send(BodyChunkResponse::Done).unwrap(),
+                    }
+                },
+                _ => {},
+            }
+        }
+    });
+    RequestBody::new(sender, BodySource::Null, None)
+}
+
+fn put(
+    storage: &IpcSender<StorageThreadMsg>,
+    cache: u64,
+    request: CacheRequest,
+    response_headers: HeaderMap,
+    chunks: Vec<&'static str>,
+) {
+    let response = CacheResponse {
+        url: Some(request.url.clone()),
+        response_type: ResponseType::Basic,
+        raw_status: Some((200, b"OK".to_vec())),
+        headers: response_headers,
+    };
+    let entry = CachePut {
+        request: request,
+        response: response,
+        body: Some(body(chunks)),
+    };
+    let result = send(storage, |sender| {
+        CacheStorageThreadMsg::Put(sender, origin(), cache, vec![entry])
+    });
+    assert_eq!(result, Ok(()));
+}
+
+fn read_body(cache_match: CacheMatch) -> Vec<u8> {
+    let mut bytes = vec![];
+    loop {
+        match cache_match.body.recv().unwrap() {
+            BodyChunkResponse::Chunk(chunk) => bytes.extend(chunk),
+            BodyChunkResponse::Done => return bytes,
+            BodyChunkResponse::Error => panic!("failed to read a cached body"),
+        }
+    }
+}
+
+fn match_body(storage: &IpcSender<StorageThreadMsg>, query: CacheQuery) -> Option<Vec<u8>> {
+    let found = send(storage, |sender| {
+        CacheStorageThreadMsg::Match(sender, origin(), None, query)
+    });
+    found.map(read_body)
+}
+
+fn exit(storage: &IpcSender<StorageThreadMsg>) {
+    let (sender, receiver) = ipc::channel().unwrap();
+    storage.send(StorageThreadMsg::Exit(sender)).unwrap();
+    receiver.recv().unwrap();
+}
+
+#[test]
+fn test_cache_storage_persists_caches_and_bodies() {
+    let config_dir = temp_config_dir();
+    let storage: IpcSender<StorageThreadMsg> = StorageThreadFactory::new(Some(config_dir.clone()));
+    let first = send(&storage, |sender| {
+        CacheStorageThreadMsg::Open(sender, origin(), "v1".to_owned())
+    });
+    let second = send(&storage, |sender| {
+        CacheStorageThreadMsg::Open(sender, origin(), "v2".to_owned())
+    });
+    let reopened = send(&storage, |sender| {
+        CacheStorageThreadMsg::Open(sender, origin(), "v1".to_owned())
+    });
+    assert_eq!(first, reopened);
+    assert_ne!(first, second);
+
+    let url = "https://servo.org/app.js?v=1";
+    put(
+        &storage,
+        first,
+        request(url, HeaderMap::new()),
+        HeaderMap::new(),
+        vec!["console", ".log(1)"],
+    );
+    // A later put of the same request replaces the entry.
+    put(
+        &storage,
+        first,
+        request(url, HeaderMap::new()),
+        HeaderMap::new(),
+        vec!["console.log(2)"],
+    );
+    exit(&storage);
+
+    let storage: IpcSender<StorageThreadMsg> = StorageThreadFactory::new(Some(config_dir.clone()));
+    let names = send(&storage, |sender| {
+        CacheStorageThreadMsg::Keys(sender, origin())
+    });
+    assert_eq!(names, vec!["v1".to_owned(), "v2".to_owned()]);
+    assert_eq!(
+        match_body(&storage, query(url, HeaderMap::new())),
+        Some(b"console.log(2)".to_vec())
+    );
+    assert_eq!(
+        match_body(
+            &storage,
+            query("https://servo.org/app.js", HeaderMap::new())
+        ),
+        None
+    );
+    let ignoring_search = CacheQuery {
+        options: CacheQueryOptions {
+            ignore_search: true,
+            ..Default::default()
+        },
+        ..query("https://servo.org/app.js", HeaderMap::new())
+    };
+    assert_eq!(
+        match_body(&storage, ignoring_search),
+        Some(b"console.log(2)".to_vec())
+    );
+
+    // Deleting a cache deletes the files of its bodies.
+    let deleted = send(&storage, |sender| {
+        CacheStorageThreadMsg::Delete(sender, origin(), "v1".to_owned())
+    });
+    assert!(deleted);
+    assert_eq!(match_body(&storage, query(url, HeaderMap::new())), None);
+    let body_dir = fs::read_dir(config_dir.join("cache_storage"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .find(|path| path.is_dir())
+        .unwrap();
+    assert_eq!(fs::read_dir(body_dir).unwrap().count(), 0);
+    exit(&storage);
+
+    fs::remove_dir_all(config_dir).unwrap();
+}
+
+#[test]
+fn test_cache_storage_matches_vary_headers() {
+    let storage: IpcSender<StorageThreadMsg> = StorageThreadFactory::new(None);
+    let cache = send(&storage, |sender| {
+        CacheStorageThreadMsg::Open(sender, origin(), "v1".to_owned())
+    });
+    let url = "https://servo.org/data";
+    let mut json = HeaderMap::new();
+    json.insert(ACCEPT, HeaderValue::from_static("application/json"));
+    let mut html = HeaderMap::new();
+    html.insert(ACCEPT, HeaderValue::from_static("text/html"));
+    let mut vary = HeaderMap::new();
+    vary.insert(VARY, HeaderValue::from_static("Accept"));
+
+    put(
+        &storage,
+        cache,
+        request(url, json.clone()),
+        vary.clone(),
+        vec!["{}"],
+    );
+    // Entries whose Vary headers don't match are kept alongside each other.
+    put(
+        &storage,
+        cache,
+        request(url, html.clone()),
+        vary,
+        vec!["<p>"],
+    );
+
+    assert_eq!(match_body(&storage, query(url, json)), Some(b"{}".to_vec()));
+    assert_eq!(
+        match_body(&storage, query(url, html)),
+        Some(b"<p>".to_vec())
+    );
+    assert_eq!(match_body(&storage, query(url, HeaderMap::new())), None);
+    let ignoring_vary = CacheQuery {
+        options: CacheQueryOptions {
+            ignore_vary: true,
+            ..Default::default()
+        },
+        ..query(url, HeaderMap::new())
+    };
+    let requests = send(&storage, |sender| {
+        CacheStorageThreadMsg::Requests(sender, origin(), cache, Some(ignoring_vary))
+    });
+    assert_eq!(requests.len(), 2);
+
+    let deleted = send(&storage, |sender| {
+        CacheStorageThreadMsg::DeleteEntries(sender, origin(), cache, query(url, HeaderMap::new()))
+    });
+    assert!(!deleted);
+    exit(&storage);
+}
+
+#[test]
+fn test_cache_storage_match_first_only_searches_one_cache() {
+    let storage: IpcSender<StorageThreadMsg> = StorageThreadFactory::new(None);
+    let first = send(&storage, |sender| {
+        CacheStorageThreadMsg::Open(sender, origin(), "v1".to_owned())
+    });
+    let second = send(&storage, |sender| {
+        CacheStorageThreadMsg::Open(sender, origin(), "v2".to_owned())
+    });
+    let url = "https://servo.org/style.css";
+    put(
+        &storage,
+        second,
+        request(url, HeaderMap::new()),
+        HeaderMap::new(),
+        vec!["p {}"],
+    );
+
+    let found = send(&storage, |sender| {
+        CacheStorageThreadMsg::MatchFirst(sender, origin(), first, query(url, HeaderMap::new()))
+    });
+    assert!(found.is_none());
+    let found = send(&storage, |sender| {
+        CacheStorageThreadMsg::MatchFirst(sender, origin(), second, query(url, HeaderMap::new()))
+    });
+    assert_eq!(found.map(read_body), Some(b"p {}".to_vec()));
+    exit(&storage);
+}
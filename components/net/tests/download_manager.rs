@@ -0,0 +1,140 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::create_embedder_proxy_and_receiver;
+use crossbeam_channel::Receiver;
+use embedder_traits::{DownloadStatus, EmbedderMsg};
+use futures::Future;
+use msg::constellation_msg::TopLevelBrowsingContextId;
+use net::download_manager::{
+    filename_from_content_disposition, is_attachment, is_renderable, sanitize_filename,
+    DownloadManager,
+};
+use net::fetch::methods::{CancellationListener, PauseControl};
+use net_traits::response::Response;
+use net_traits::{FetchTaskTarget, ResourceFetchTiming, ResourceTimingType};
+use servo_url::ServoUrl;
+use std::fs;
+use std::path::Path;
+use uuid::Uuid;
+
+#[test]
+fn test_is_attachment() {
+    assert!(is_attachment("attachment"));
+    assert!(is_attachment("Attachment; filename=report.pdf"));
+    assert!(!is_attachment("inline"));
+    assert!(!is_attachment("inline; filename=attachment"));
+}
+
+#[test]
+fn test_filename_from_content_disposition() {
+    assert_eq!(
+        filename_from_content_disposition("attachment; filename=\"report.pdf\""),
+        Some("report.pdf".to_owned())
+    );
+    assert_eq!(
+        filename_from_content_disposition("attachment; filename=report.pdf"),
+        Some("report.pdf".to_owned())
+    );
+    assert_eq!(
+        filename_from_content_disposition(
+            "attachment; filename=\"fallback.pdf\"; filename*=UTF-8''r%C3%A9sum%C3%A9.pdf"
+        ),
+        Some("résumé.pdf".to_owned())
+    );
+    assert_eq!(filename_from_content_disposition("attachment"), None);
+}
+
+#[test]
+fn test_sanitize_filename() {
+    assert_eq!(
+        sanitize_filename("../../.bashrc"),
+        Some(".bashrc".to_owned())
+    );
+    assert_eq!(
+        sanitize_filename("C:\\Windows\\evil.exe"),
+        Some("evil.exe".to_owned())
+    );
+    assert_eq!(sanitize_filename(".."), None);
+    assert_eq!(sanitize_filename("dir/"), None);
+}
+
+#[test]
+fn test_is_renderable() {
+    assert!(is_renderable(&mime::TEXT_HTML));
+    assert!(is_renderable(&mime::IMAGE_PNG));
+    assert!(is_renderable(&"application/xhtml+xml".parse().unwrap()));
+    assert!(!is_renderable(&mime::APPLICATION_PDF));
+    assert!(!is_renderable(&mime::APPLICATION_OCTET_STREAM));
+}
+
+#[test]
+fn test_paused_fetch_waits_for_resume() {
+    let pause_control = PauseControl::default();
+    let listener = CancellationListener::new(None).with_pause_control(pause_control.clone());
+    assert!(listener.paused().is_none());
+
+    pause_control.pause();
+    let resumed = listener.paused().expect("The fetch is paused");
+    pause_control.resume();
+    assert!(resumed.wait().is_ok());
+    assert!(listener.paused().is_none());
+}
+
+#[test]
+fn test_abandoned_fetch_stops_waiting() {
+    let pause_control = PauseControl::default();
+    let listener = CancellationListener::new(None).with_pause_control(pause_control.clone());
+
+    pause_control.pause();
+    let resumed = listener.paused().expect("The fetch is paused");
+    pause_control.abandon();
+    assert!(resumed.wait().is_err());
+}
+
+/// Download a small body with `manager`, saving it in `dir`.
+fn download(
+    manager: &DownloadManager,
+    embedder_receiver: &Receiver<(Option<TopLevelBrowsingContextId>, EmbedderMsg)>,
+    dir: &Path,
+) {
+    let url = ServoUrl::parse("http://example.com/report.txt").unwrap();
+    let response = Response::new(
+        url.clone(),
+        ResourceFetchTiming::new(ResourceTimingType::Navigation),
+    );
+    let (mut target, _) = manager.start(url, None, None);
+    target.process_response(&response);
+    match embedder_receiver.recv().unwrap().1 {
+        EmbedderMsg::SelectDownloadPath(filename, sender) => {
+            sender.send(Some(dir.join(filename))).unwrap()
+        },
+        msg => panic!("Unexpected embedder message {:?}", msg),
+    }
+    target.process_response_chunk(b"report".to_vec());
+    target.process_response_eof(&response);
+    loop {
+        if let EmbedderMsg::DownloadStatusChanged(_, status) = embedder_receiver.recv().unwrap().1 {
+            assert_eq!(status, DownloadStatus::Complete);
+            break;
+        }
+    }
+    assert_eq!(fs::read(dir.join("report.txt")).unwrap(), b"report");
+}
+
+#[test]
+fn test_private_downloads_are_not_persisted() {
+    let dir = std::env::temp_dir().join(format!("servo-downloads-{}", Uuid::new_v4()));
+    fs::create_dir_all(&dir).unwrap();
+    let (embedder_proxy, embedder_receiver) = create_embedder_proxy_and_receiver();
+    let manager = DownloadManager::new(embedder_proxy, Some(dir.clone()));
+
+    download(&manager.ephemeral(), &embedder_receiver, &dir);
+    assert!(!dir.join("download_history.json").exists());
+
+    download(&manager, &embedder_receiver, &dir);
+    assert!(dir.join("download_history.json").exists());
+
+    let _ = fs::remove_dir_all(&dir);
+}
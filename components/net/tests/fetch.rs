@@ -4,14 +4,17 @@
 
 use crate::fetch_with_context;
 use crate::fetch_with_cors_cache;
+use crate::http_loader::{create_request_body_with_content, read_response};
 use crate::http_loader::{expect_devtools_http_request, expect_devtools_http_response};
 use crate::{
     create_embedder_proxy, fetch, make_server, make_ssl_server, new_fetch_context,
     DEFAULT_USER_AGENT,
 };
+use content_security_policy as csp;
 use crossbeam_channel::{unbounded, Sender};
 use devtools_traits::HttpRequest as DevtoolsHttpRequest;
 use devtools_traits::HttpResponse as DevtoolsHttpResponse;
+use futures::{self, Future};
 use headers::StrictTransportSecurity;
 use headers::{AccessControlAllowCredentials, AccessControlAllowHeaders, AccessControlAllowOrigin};
 use headers::{AccessControlAllowMethods, AccessControlMaxAge, HeaderMapExt};
@@ -20,31 +23,38 @@ use http::header::{self, HeaderMap, HeaderName, HeaderValue};
 use http::{Method, StatusCode};
 use hyper::body::Body;
 use hyper::{Request as HyperRequest, Response as HyperResponse};
+use ipc_channel::ipc;
 use mime::{self, Mime};
 use msg::constellation_msg::TEST_PIPELINE_ID;
 use net::connector::{create_tls_config, ConnectionCerts, ExtraCerts, ALPN_H2_H1};
+use net::csp_directives;
 use net::fetch::cors_cache::CorsCache;
-use net::fetch::methods::{self, CancellationListener, FetchContext};
+use net::fetch::methods::{self, CancellationListener, FetchContext, MixedContentCheckResult};
 use net::filemanager_thread::FileManager;
 use net::hsts::HstsEntry;
 use net::resource_thread::CoreResourceThreadPool;
 use net::test::HttpState;
 use net_traits::filemanager_thread::FileTokenCheck;
 use net_traits::request::{
-    Destination, Origin, RedirectMode, Referrer, Request, RequestBuilder, RequestMode,
+    BodyChunkRequest, BodyChunkResponse, Destination, Initiator, Origin, RedirectMode, Referrer,
+    Request, RequestBuilder, RequestMode,
 };
 use net_traits::response::{CacheState, HttpsState, Response, ResponseBody, ResponseType};
 use net_traits::{
+    csp_list_from_headers, embedder_policy_from_headers, opener_policy_from_headers,
+    CrossOriginEmbedderPolicy, CrossOriginOpenerPolicy, CustomResponse, CustomResponseMediator,
     FetchTaskTarget, IncludeSubdomains, NetworkError, ReferrerPolicy, ResourceFetchTiming,
     ResourceTimingType,
 };
 use servo_arc::Arc as ServoArc;
+use servo_config::set_pref;
 use servo_url::{ImmutableOrigin, ServoUrl};
 use std::fs;
 use std::iter::FromIterator;
 use std::path::Path;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex, Weak};
+use std::thread;
 use std::time::{Duration, SystemTime};
 use uuid::Uuid;
 
@@ -94,6 +104,339 @@ fn test_fetch_on_bad_port_is_network_error() {
     )
 }
 
+fn mixed_content_request(url: &str, origin: &str, destination: Destination) -> Request {
+    let url = ServoUrl::parse(url).unwrap();
+    let origin = Origin::Origin(ServoUrl::parse(origin).unwrap().origin());
+    let mut request = Request::new(
+        url,
+        Some(origin),
+        Referrer::NoReferrer,
+        None,
+        HttpsState::Modern,
+    );
+    request.destination = destination;
+    request
+}
+
+#[test]
+fn test_fetch_blockable_mixed_content_is_network_error() {
+    let mut request = mixed_content_request(
+        "http://www.example.org/script.js",
+        "https://www.example.org",
+        Destination::Script,
+    );
+    let fetch_response = fetch(&mut request, None);
+    assert!(fetch_response.is_network_error());
+    let fetch_error = fetch_response.get_network_error().unwrap();
+    assert_eq!(
+        fetch_error,
+        &NetworkError::Internal("Blocked as mixed content".into())
+    )
+}
+
+#[test]
+fn test_mixed_content_categorization() {
+    // Blockable content from a secure origin.
+    let request = mixed_content_request(
+        "http://www.example.org/style.css",
+        "https://www.example.org",
+        Destination::Style,
+    );
+    assert_eq!(
+        methods::should_request_be_blocked_as_mixed_content(&request),
+        MixedContentCheckResult::Blocked
+    );
+
+    // Optionally-blockable content from a secure origin.
+    let request = mixed_content_request(
+        "http://www.example.org/image.png",
+        "https://www.example.org",
+        Destination::Image,
+    );
+    assert_eq!(
+        methods::should_request_be_blocked_as_mixed_content(&request),
+        MixedContentCheckResult::AllowedOptionallyBlockable
+    );
+
+    // Potentially trustworthy URLs are never mixed content.
+    let request = mixed_content_request(
+        "http://localhost/script.js",
+        "https://www.example.org",
+        Destination::Script,
+    );
+    assert_eq!(
+        methods::should_request_be_blocked_as_mixed_content(&request),
+        MixedContentCheckResult::Allowed
+    );
+
+    // Insecure origins do not prohibit mixed security contexts...
+    let mut request = mixed_content_request(
+        "http://www.example.org/script.js",
+        "http://www.example.org",
+        Destination::Script,
+    );
+    assert_eq!(
+        methods::should_request_be_blocked_as_mixed_content(&request),
+        MixedContentCheckResult::Allowed
+    );
+
+    // ...unless they have a trustworthy ancestor.
+    request.has_trustworthy_ancestor_origin = true;
+    assert_eq!(
+        methods::should_request_be_blocked_as_mixed_content(&request),
+        MixedContentCheckResult::Blocked
+    );
+
+    // Top-level navigations are always allowed.
+    let request = mixed_content_request(
+        "http://www.example.org/",
+        "https://www.example.org",
+        Destination::Document,
+    );
+    assert_eq!(
+        methods::should_request_be_blocked_as_mixed_content(&request),
+        MixedContentCheckResult::Allowed
+    );
+
+    // Nested navigations below a secure page are not.
+    let mut request = mixed_content_request(
+        "http://www.example.org/",
+        "http://www.example.org",
+        Destination::Document,
+    );
+    request.has_trustworthy_ancestor_origin = true;
+    assert_eq!(
+        methods::should_request_be_blocked_as_mixed_content(&request),
+        MixedContentCheckResult::Blocked
+    );
+}
+
+#[test]
+fn test_mixed_content_upgrade() {
+    set_pref!(network.mixed_content.upgrade, true);
+
+    let request = mixed_content_request(
+        "http://www.example.org/image.png",
+        "https://www.example.org",
+        Destination::Image,
+    );
+    assert!(methods::should_upgrade_mixed_content_request(&request));
+
+    // Blockable content is never upgraded.
+    let request = mixed_content_request(
+        "http://www.example.org/script.js",
+        "https://www.example.org",
+        Destination::Script,
+    );
+    assert!(!methods::should_upgrade_mixed_content_request(&request));
+
+    // Neither are hosts which are IP addresses.
+    let request = mixed_content_request(
+        "http://192.0.2.1/image.png",
+        "https://www.example.org",
+        Destination::Image,
+    );
+    assert!(!methods::should_upgrade_mixed_content_request(&request));
+
+    // Nor images from an image set.
+    let mut request = mixed_content_request(
+        "http://www.example.org/image.png",
+        "https://www.example.org",
+        Destination::Image,
+    );
+    request.initiator = Initiator::ImageSet;
+    assert!(!methods::should_upgrade_mixed_content_request(&request));
+}
+
+struct CspViolationsCollector {
+    sender: Sender<Response>,
+    violations: Sender<Vec<csp::Violation>>,
+}
+
+impl FetchTaskTarget for CspViolationsCollector {
+    fn process_request_body(&mut self, _: &Request) {}
+    fn process_request_eof(&mut self, _: &Request) {}
+    fn process_response(&mut self, _: &Response) {}
+    fn process_response_chunk(&mut self, _: Vec<u8>) {}
+    fn process_response_eof(&mut self, response: &Response) {
+        let _ = self.sender.send(response.clone());
+    }
+    fn process_csp_violations(&mut self, _: &Request, violations: Vec<csp::Violation>) {
+        let _ = self.violations.send(violations);
+    }
+}
+
+fn csp_request(url: ServoUrl, header: &'static str, policy: &'static str) -> Request {
+    let mut headers = HeaderMap::new();
+    headers.insert(header, HeaderValue::from_static(policy));
+    let mut request = Request::new(
+        url.clone(),
+        Some(Origin::Origin(url.origin())),
+        Referrer::NoReferrer,
+        None,
+        HttpsState::None,
+    );
+    request.csp_list = csp_list_from_headers(&headers);
+    request
+}
+
+#[test]
+fn test_fetch_blocked_by_csp_reports_violation() {
+    let url = ServoUrl::parse("http://www.example.org/script.js").unwrap();
+    let mut request = csp_request(url, "content-security-policy", "script-src 'none'");
+    request.destination = Destination::Script;
+
+    let (sender, receiver) = unbounded();
+    let (violations_sender, violations_receiver) = unbounded();
+    let mut target = CspViolationsCollector {
+        sender,
+        violations: violations_sender,
+    };
+    let context = new_fetch_context(None, None, None);
+    methods::fetch(&mut request, &mut target, &context);
+
+    let fetch_response = receiver.recv().unwrap();
+    assert_eq!(
+        fetch_response.get_network_error(),
+        Some(&NetworkError::Internal(
+            "Blocked by Content-Security-Policy".into()
+        ))
+    );
+    let violations = violations_receiver.recv().unwrap();
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].directive.name, "script-src");
+}
+
+#[test]
+fn test_fetch_report_only_csp_is_not_enforced() {
+    static MESSAGE: &'static [u8] = b"";
+    let handler = move |_: HyperRequest<Body>, response: &mut HyperResponse<Body>| {
+        *response.body_mut() = MESSAGE.to_vec().into();
+    };
+    let (server, url) = make_server(handler);
+
+    let mut request = csp_request(
+        url,
+        "content-security-policy-report-only",
+        "script-src 'none'",
+    );
+    request.destination = Destination::Script;
+
+    let (sender, receiver) = unbounded();
+    let (violations_sender, violations_receiver) = unbounded();
+    let mut target = CspViolationsCollector {
+        sender,
+        violations: violations_sender,
+    };
+    let context = new_fetch_context(None, None, None);
+    methods::fetch(&mut request, &mut target, &context);
+    let _ = server.close();
+
+    let fetch_response = receiver.recv().unwrap();
+    assert!(!fetch_response.is_network_error());
+    let violations = violations_receiver.recv().unwrap();
+    assert_eq!(violations.len(), 1);
+    assert!(matches!(
+        violations[0].policy.disposition,
+        csp::PolicyDisposition::Report
+    ));
+}
+
+#[test]
+fn test_upgrade_insecure_requests() {
+    let url = ServoUrl::parse("http://www.example.org/image.png").unwrap();
+    let mut request = csp_request(url, "content-security-policy", "upgrade-insecure-requests");
+    request.destination = Destination::Image;
+    assert!(csp_directives::should_upgrade_request_to_potentially_trustworthy(&request));
+    csp_directives::upgrade_request_to_potentially_trustworthy(&mut request);
+    assert_eq!(
+        request.current_url().as_str(),
+        "https://www.example.org/image.png"
+    );
+
+    // Navigations to other hosts are left alone.
+    let url = ServoUrl::parse("http://www.example.com/").unwrap();
+    let mut request = csp_request(url, "content-security-policy", "upgrade-insecure-requests");
+    request.origin = Origin::Origin(ServoUrl::parse("http://www.example.org/").unwrap().origin());
+    request.destination = Destination::Document;
+    assert!(!csp_directives::should_upgrade_request_to_potentially_trustworthy(&request));
+}
+
+#[test]
+fn test_csp_is_checked_after_upgrade_insecure_requests() {
+    let handler = move |_: HyperRequest<Body>, _: &mut HyperResponse<Body>| {};
+    let (server, url) = make_server(handler);
+
+    let mut request = csp_request(
+        url,
+        "content-security-policy",
+        "upgrade-insecure-requests; img-src https:",
+    );
+    request.destination = Destination::Image;
+
+    let (sender, receiver) = unbounded();
+    let (violations_sender, violations_receiver) = unbounded();
+    let mut target = CspViolationsCollector {
+        sender,
+        violations: violations_sender,
+    };
+    let context = new_fetch_context(None, None, None);
+    methods::fetch(&mut request, &mut target, &context);
+    let _ = server.close();
+
+    // The upgraded request fails the TLS handshake with the plain HTTP server,
+    // but it isn't blocked by the policy.
+    let fetch_response = receiver.recv().unwrap();
+    assert_ne!(
+        fetch_response.get_network_error(),
+        Some(&NetworkError::Internal(
+            "Blocked by Content-Security-Policy".into()
+        ))
+    );
+    assert_eq!(request.current_url().scheme(), "https");
+    assert!(violations_receiver.try_recv().is_err());
+}
+
+#[test]
+fn test_frame_ancestors() {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "content-security-policy",
+        HeaderValue::from_static("frame-ancestors 'self' https://trusted.example.com"),
+    );
+    let csp_list = csp_list_from_headers(&headers).unwrap();
+    let url = ServoUrl::parse("https://www.example.org/frame.html").unwrap();
+    let origin = |url: &str| ServoUrl::parse(url).unwrap().origin();
+
+    assert!(
+        !csp_directives::should_navigation_response_be_blocked_by_frame_ancestors(
+            &csp_list,
+            &url,
+            &[origin("https://www.example.org/")],
+        )
+    );
+    assert!(
+        !csp_directives::should_navigation_response_be_blocked_by_frame_ancestors(
+            &csp_list,
+            &url,
+            &[
+                origin("https://www.example.org/"),
+                origin("https://trusted.example.com/")
+            ],
+        )
+    );
+    assert!(
+        csp_directives::should_navigation_response_be_blocked_by_frame_ancestors(
+            &csp_list,
+            &url,
+            &[
+                origin("https://www.example.org/"),
+                origin("https://evil.example.com/")
+            ],
+        )
+    );
+}
+
 #[test]
 fn test_fetch_response_body_matches_const_message() {
     static MESSAGE: &'static [u8] = b"Hello World!";
@@ -174,6 +517,7 @@ fn test_fetch_blob() {
             assert_eq!(self.buffer, self.expected);
             let _ = self.sender.send(response.clone());
         }
+        fn process_csp_violations(&mut self, _: &Request, _: Vec<csp::Violation>) {}
     }
 
     let context = new_fetch_context(None, None, None);
@@ -649,6 +993,102 @@ fn test_fetch_response_is_opaque_filtered() {
     }
 }
 
+fn cross_origin_no_cors_request(url: ServoUrl) -> Request {
+    let origin = ServoUrl::parse("http://www.example.org/").unwrap().origin();
+    Request::new(
+        url,
+        Some(Origin::Origin(origin)),
+        Referrer::NoReferrer,
+        None,
+        HttpsState::None,
+    )
+}
+
+#[test]
+fn test_fetch_blocked_by_cross_origin_resource_policy() {
+    let handler = move |_: HyperRequest<Body>, response: &mut HyperResponse<Body>| {
+        response.headers_mut().insert(
+            HeaderName::from_static("cross-origin-resource-policy"),
+            HeaderValue::from_static("same-origin"),
+        );
+        *response.body_mut() = b"".to_vec().into();
+    };
+    let (server, url) = make_server(handler);
+
+    let mut request = cross_origin_no_cors_request(url);
+    let fetch_response = fetch(&mut request, None);
+    let _ = server.close();
+
+    assert_eq!(
+        fetch_response.get_network_error(),
+        Some(&NetworkError::Internal(
+            "Cross-origin resource policy check failed".into()
+        ))
+    );
+}
+
+#[test]
+fn test_fetch_require_corp_embedder_policy() {
+    let handler = move |request: HyperRequest<Body>, response: &mut HyperResponse<Body>| {
+        if request.uri().path() == "/corp" {
+            response.headers_mut().insert(
+                HeaderName::from_static("cross-origin-resource-policy"),
+                HeaderValue::from_static("cross-origin"),
+            );
+        }
+        *response.body_mut() = b"".to_vec().into();
+    };
+    let (server, url) = make_server(handler);
+
+    // Without a Cross-Origin-Resource-Policy header the response is treated as same-origin.
+    let mut request = cross_origin_no_cors_request(url.join("/no-corp").unwrap());
+    request.embedder_policy = CrossOriginEmbedderPolicy::RequireCorp;
+    let fetch_response = fetch(&mut request, None);
+    assert!(fetch_response.is_network_error());
+
+    let mut request = cross_origin_no_cors_request(url.join("/corp").unwrap());
+    request.embedder_policy = CrossOriginEmbedderPolicy::RequireCorp;
+    let fetch_response = fetch(&mut request, None);
+    assert!(!fetch_response.is_network_error());
+
+    // Credentialless clients do not require CORP for uncredentialed requests.
+    let mut request = cross_origin_no_cors_request(url.join("/no-corp").unwrap());
+    request.embedder_policy = CrossOriginEmbedderPolicy::Credentialless;
+    let fetch_response = fetch(&mut request, None);
+    let _ = server.close();
+    assert!(!fetch_response.is_network_error());
+}
+
+#[test]
+fn test_policies_are_ignored_in_non_secure_contexts() {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        HeaderName::from_static("cross-origin-embedder-policy"),
+        HeaderValue::from_static("require-corp"),
+    );
+    headers.insert(
+        HeaderName::from_static("cross-origin-opener-policy"),
+        HeaderValue::from_static("same-origin"),
+    );
+
+    assert_eq!(
+        embedder_policy_from_headers(&headers, true),
+        CrossOriginEmbedderPolicy::RequireCorp
+    );
+    assert_eq!(
+        opener_policy_from_headers(&headers, true),
+        CrossOriginOpenerPolicy::SameOrigin
+    );
+    assert_eq!(
+        embedder_policy_from_headers(&headers, false),
+        CrossOriginEmbedderPolicy::UnsafeNone
+    );
+    assert_eq!(
+        opener_policy_from_headers(&headers, false),
+        CrossOriginOpenerPolicy::UnsafeNone
+    );
+}
+
 #[test]
 fn test_fetch_response_is_opaque_redirect_filtered() {
     static MESSAGE: &'static [u8] = b"";
@@ -773,6 +1213,7 @@ fn test_fetch_with_hsts() {
         filemanager: FileManager::new(create_embedder_proxy(), Weak::new()),
         file_token: FileTokenCheck::NotRequired,
         cancellation_listener: Arc::new(Mutex::new(CancellationListener::new(None))),
+        service_worker_managers: Default::default(),
         timing: ServoArc::new(Mutex::new(ResourceFetchTiming::new(
             ResourceTimingType::Navigation,
         ))),
@@ -836,6 +1277,7 @@ fn test_load_adds_host_to_hsts_list_when_url_is_https() {
         filemanager: FileManager::new(create_embedder_proxy(), Weak::new()),
         file_token: FileTokenCheck::NotRequired,
         cancellation_listener: Arc::new(Mutex::new(CancellationListener::new(None))),
+        service_worker_managers: Default::default(),
         timing: ServoArc::new(Mutex::new(ResourceFetchTiming::new(
             ResourceTimingType::Navigation,
         ))),
@@ -901,6 +1343,7 @@ fn test_fetch_self_signed() {
         filemanager: FileManager::new(create_embedder_proxy(), Weak::new()),
         file_token: FileTokenCheck::NotRequired,
         cancellation_listener: Arc::new(Mutex::new(CancellationListener::new(None))),
+        service_worker_managers: Default::default(),
         timing: ServoArc::new(Mutex::new(ResourceFetchTiming::new(
             ResourceTimingType::Navigation,
         ))),
@@ -1419,3 +1862,165 @@ fn test_fetch_with_devtools() {
     assert_eq!(devhttprequest, httprequest);
     assert_eq!(devhttpresponse, httpresponse);
 }
+
+fn respond_from_service_worker(
+    context: &FetchContext,
+    scope_url: &ServoUrl,
+    response: Option<CustomResponse>,
+) -> thread::JoinHandle<CustomResponseMediator> {
+    let (sender, receiver) = ipc::channel().unwrap();
+    context
+        .service_worker_managers
+        .lock()
+        .unwrap()
+        .insert(scope_url.origin(), sender);
+    thread::spawn(move || {
+        let mediator: CustomResponseMediator = receiver.recv().unwrap();
+        mediator.response_chan.send(response).unwrap();
+        mediator
+    })
+}
+
+#[test]
+fn test_fetch_navigation_is_handled_by_service_worker() {
+    let url = ServoUrl::parse("http://offline.test/index.html").unwrap();
+    let mut context = new_fetch_context(None, None, None);
+    let worker = respond_from_service_worker(
+        &context,
+        &url,
+        Some(CustomResponse::new(
+            HeaderMap::new(),
+            (StatusCode::OK, "OK".to_owned()),
+            b"Offline".to_vec(),
+        )),
+    );
+
+    let mut request = Request::new(
+        url.clone(),
+        Some(Origin::Origin(url.origin())),
+        Referrer::NoReferrer,
+        None,
+        HttpsState::None,
+    );
+    request.destination = Destination::Document;
+    let fetch_response = fetch_with_context(&mut request, &mut context);
+
+    let mediator = worker.join().unwrap();
+    assert_eq!(mediator.load_url, url);
+    assert_eq!(mediator.request.url, url);
+    assert!(!fetch_response.is_network_error());
+    match *fetch_response.body.lock().unwrap() {
+        ResponseBody::Done(ref body) => assert_eq!(&**body, b"Offline"),
+        _ => panic!(),
+    };
+}
+
+#[test]
+fn test_fetch_falls_back_to_network_without_service_worker_response() {
+    static MESSAGE: &'static [u8] = b"Network";
+    let handler = move |_: HyperRequest<Body>, response: &mut HyperResponse<Body>| {
+        *response.body_mut() = MESSAGE.to_vec().into();
+    };
+    let (server, url) = make_server(handler);
+
+    let mut context = new_fetch_context(None, None, None);
+    let worker = respond_from_service_worker(&context, &url, None);
+
+    let mut request = Request::new(
+        url.clone(),
+        Some(Origin::Origin(url.origin())),
+        Referrer::Client(url.clone()),
+        None,
+        HttpsState::None,
+    );
+    let fetch_response = fetch_with_context(&mut request, &mut context);
+    let _ = server.close();
+
+    let mediator = worker.join().unwrap();
+    assert_eq!(mediator.load_url, url);
+    assert!(!fetch_response.is_network_error());
+    match *fetch_response.body.lock().unwrap() {
+        ResponseBody::Done(ref body) => assert_eq!(&**body, MESSAGE),
+        _ => panic!(),
+    };
+}
+
+#[test]
+fn test_fetch_hands_request_body_to_service_worker() {
+    static MESSAGE: &'static [u8] = b"Body on POST";
+    let handler = move |request: HyperRequest<Body>, response: &mut HyperResponse<Body>| {
+        read_response(request)
+            .and_then(|data| {
+                assert_eq!(data.as_bytes(), MESSAGE);
+                futures::future::ok(())
+            })
+            .poll()
+            .unwrap();
+        *response.body_mut() = b"Network".to_vec().into();
+    };
+    let (server, url) = make_server(handler);
+
+    let mut context = new_fetch_context(None, None, None);
+    let worker = respond_from_service_worker(&context, &url, None);
+
+    let mut request = Request::new(
+        url.clone(),
+        Some(Origin::Origin(url.origin())),
+        Referrer::Client(url.clone()),
+        None,
+        HttpsState::None,
+    );
+    request.method = Method::POST;
+    request.body = Some(create_request_body_with_content(MESSAGE.to_vec()));
+    let fetch_response = fetch_with_context(&mut request, &mut context);
+    let _ = server.close();
+
+    // The worker gets the body, which is still sent once the fetch falls back to the network.
+    let mediator = worker.join().unwrap();
+    assert!(mediator.body.is_some());
+    assert!(!fetch_response.is_network_error());
+    match *fetch_response.body.lock().unwrap() {
+        ResponseBody::Done(ref body) => assert_eq!(&**body, b"Network"),
+        _ => panic!(),
+    };
+}
+
+#[test]
+fn test_fetch_falling_back_after_service_worker_read_body_is_network_error() {
+    let url = ServoUrl::parse("http://offline.test/submit").unwrap();
+    let mut context = new_fetch_context(None, None, None);
+    let (sender, receiver) = ipc::channel().unwrap();
+    context
+        .service_worker_managers
+        .lock()
+        .unwrap()
+        .insert(url.origin(), sender);
+    let worker = thread::spawn(move || {
+        let mediator: CustomResponseMediator = receiver.recv().unwrap();
+        let body = mediator.body.unwrap();
+        let (body_chan, body_port) = ipc::channel().unwrap();
+        body.send(BodyChunkRequest::Connect(body_chan)).unwrap();
+        body.send(BodyChunkRequest::Chunk).unwrap();
+        let chunk = body_port.recv().unwrap();
+        mediator.response_chan.send(None).unwrap();
+        chunk
+    });
+
+    let mut request = Request::new(
+        url.clone(),
+        Some(Origin::Origin(url.origin())),
+        Referrer::Client(url.clone()),
+        None,
+        HttpsState::None,
+    );
+    request.method = Method::POST;
+    request.body = Some(create_request_body_with_content(b"Body on POST".to_vec()));
+    let fetch_response = fetch_with_context(&mut request, &mut context);
+
+    // The body was streamed to the worker, so it can't be sent to the network anymore.
+    match worker.join().unwrap() {
+        BodyChunkResponse::Chunk(ref bytes) => assert_eq!(&**bytes, b"Body on POST"),
+        _ => panic!(),
+    };
+    assert!(fetch_response.is_network_error());
+}
@@ -49,7 +49,7 @@ fn mock_origin() -> ImmutableOrigin {
     ServoUrl::parse("http://servo.org").unwrap().origin()
 }
 
-fn read_response(req: HyperRequest<Body>) -> impl Future<Item = String, Error = ()> {
+pub fn read_response(req: HyperRequest<Body>) -> impl Future<Item = String, Error = ()> {
     req.into_body()
         .concat2()
         .and_then(|body| futures::future::ok(str::from_utf8(&body).unwrap().to_owned()))
@@ -98,7 +98,7 @@ pub fn expect_devtools_http_response(
     }
 }
 
-fn create_request_body_with_content(content: Vec<u8>) -> RequestBody {
+pub fn create_request_body_with_content(content: Vec<u8>) -> RequestBody {
     let content_len = content.len();
 
     let (chunk_request_sender, chunk_request_receiver) = ipc::channel().unwrap();
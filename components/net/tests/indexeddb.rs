@@ -0,0 +1,365 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use ipc_channel::ipc::{self, IpcReceiver, IpcSender};
+use net::indexeddb::QUOTA_SIZE_LIMIT;
+use net::storage_thread::StorageThreadFactory;
+use net_traits::indexeddb_thread::{IndexInfo, IndexedDBConnectionEvent, IndexedDBCursorDirection};
+use net_traits::indexeddb_thread::{IndexedDBError, IndexedDBKeyRange, IndexedDBKeyType};
+use net_traits::indexeddb_thread::{IndexedDBPut, IndexedDBQuery, IndexedDBScheduleEvent};
+use net_traits::indexeddb_thread::{IndexedDBThreadMsg, IndexedDBTransactionMode};
+use net_traits::indexeddb_thread::{KeyPath, ObjectStoreInfo};
+use net_traits::storage_thread::StorageThreadMsg;
+use servo_url::{ImmutableOrigin, ServoUrl};
+use std::fs;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+fn origin(url: &str) -> ImmutableOrigin {
+    ServoUrl::parse(url).unwrap().origin()
+}
+
+fn temp_config_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("servo-indexeddb-{}", Uuid::new_v4()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn send(storage: &IpcSender<StorageThreadMsg>, msg: IndexedDBThreadMsg) {
+    storage.send(StorageThreadMsg::IndexedDB(msg)).unwrap();
+}
+
+/// Opens a connection to the database "db", returning its id, its events and the version of
+/// the database.
+fn open(
+    storage: &IpcSender<StorageThreadMsg>,
+    origin: &ImmutableOrigin,
+) -> (u64, IpcReceiver<IndexedDBConnectionEvent>, u64) {
+    let (sender, receiver) = ipc::channel().unwrap();
+    let (events_sender, events) = ipc::channel().unwrap();
+    send(
+        storage,
+        IndexedDBThreadMsg::Open(sender, events_sender, origin.clone(), "db".to_owned()),
+    );
+    let (connection, info) = receiver.recv().unwrap();
+    (connection, events, info.version)
+}
+
+/// Creates a transaction on the object store "store", which may not have started yet.
+fn begin(
+    storage: &IpcSender<StorageThreadMsg>,
+    connection: u64,
+    mode: IndexedDBTransactionMode,
+) -> (u64, IpcReceiver<IndexedDBScheduleEvent>) {
+    let (sender, receiver) = ipc::channel().unwrap();
+    let (events_sender, events) = ipc::channel().unwrap();
+    send(
+        storage,
+        IndexedDBThreadMsg::BeginTransaction(
+            sender,
+            events_sender,
+            connection,
+            mode,
+            vec!["store".to_owned()],
+        ),
+    );
+    (receiver.recv().unwrap().unwrap(), events)
+}
+
+/// Creates a transaction that starts right away.
+fn begin_started(
+    storage: &IpcSender<StorageThreadMsg>,
+    connection: u64,
+    mode: IndexedDBTransactionMode,
+) -> u64 {
+    let (transaction, events) = begin(storage, connection, mode);
+    assert_eq!(
+        events.try_recv().unwrap(),
+        IndexedDBScheduleEvent::Started(1)
+    );
+    transaction
+}
+
+fn upgrade(
+    storage: &IpcSender<StorageThreadMsg>,
+    connection: u64,
+    version: u64,
+) -> (u64, IpcReceiver<IndexedDBScheduleEvent>) {
+    let (sender, receiver) = ipc::channel().unwrap();
+    let (events_sender, events) = ipc::channel().unwrap();
+    send(
+        storage,
+        IndexedDBThreadMsg::Upgrade(sender, events_sender, connection, version),
+    );
+    (receiver.recv().unwrap().unwrap(), events)
+}
+
+fn commit(storage: &IpcSender<StorageThreadMsg>, transaction: u64) {
+    let (sender, receiver) = ipc::channel().unwrap();
+    send(storage, IndexedDBThreadMsg::Commit(sender, transaction));
+    receiver.recv().unwrap().unwrap();
+}
+
+fn abort(storage: &IpcSender<StorageThreadMsg>, transaction: u64) {
+    let (sender, receiver) = ipc::channel().unwrap();
+    send(storage, IndexedDBThreadMsg::Abort(sender, transaction));
+    receiver.recv().unwrap();
+}
+
+/// Opens the database "db" and gives it an object store "store" with an index "by_name".
+/// Returns the connection and its events.
+fn create_database(
+    storage: &IpcSender<StorageThreadMsg>,
+    origin: &ImmutableOrigin,
+) -> (u64, IpcReceiver<IndexedDBConnectionEvent>) {
+    let (connection, connection_events, version) = open(storage, origin);
+    assert_eq!(version, 0);
+
+    let (transaction, events) = upgrade(storage, connection, 1);
+    assert_eq!(events.recv().unwrap(), IndexedDBScheduleEvent::Started(0));
+    let (sender, receiver) = ipc::channel().unwrap();
+    let msgs = vec![
+        IndexedDBThreadMsg::CreateObjectStore(
+            sender.clone(),
+            transaction,
+            ObjectStoreInfo {
+                name: "store".to_owned(),
+                key_path: None,
+                auto_increment: true,
+                indexes: vec![],
+            },
+        ),
+        IndexedDBThreadMsg::CreateIndex(
+            sender,
+            transaction,
+            "store".to_owned(),
+            IndexInfo {
+                name: "by_name".to_owned(),
+                key_path: KeyPath::String("name".to_owned()),
+                unique: true,
+                multi_entry: false,
+            },
+            vec![],
+        ),
+    ];
+    for msg in msgs {
+        send(storage, msg);
+        receiver.recv().unwrap().unwrap();
+    }
+    commit(storage, transaction);
+    (connection, connection_events)
+}
+
+fn put(
+    storage: &IpcSender<StorageThreadMsg>,
+    transaction: u64,
+    name: &str,
+    value: Vec<u8>,
+) -> Result<IndexedDBKeyType, IndexedDBError> {
+    let (sender, receiver) = ipc::channel().unwrap();
+    let put = IndexedDBPut {
+        object_store: "store".to_owned(),
+        key: None,
+        value: value,
+        index_keys: vec![(
+            "by_name".to_owned(),
+            vec![IndexedDBKeyType::String(name.to_owned())],
+        )],
+        no_overwrite: true,
+    };
+    send(storage, IndexedDBThreadMsg::Put(sender, transaction, put));
+    receiver.recv().unwrap()
+}
+
+fn values_by_name(
+    storage: &IpcSender<StorageThreadMsg>,
+    connection: u64,
+) -> Vec<(IndexedDBKeyType, Vec<u8>)> {
+    let transaction = begin_started(storage, connection, IndexedDBTransactionMode::ReadOnly);
+    let (sender, receiver) = ipc::channel().unwrap();
+    let query = IndexedDBQuery {
+        object_store: "store".to_owned(),
+        index: Some("by_name".to_owned()),
+        range: IndexedDBKeyRange::default(),
+        direction: IndexedDBCursorDirection::Next,
+        position: None,
+        count: None,
+        with_values: true,
+    };
+    send(
+        storage,
+        IndexedDBThreadMsg::Query(sender, transaction, query),
+    );
+    let records = receiver.recv().unwrap().unwrap();
+    commit(storage, transaction);
+    records
+        .into_iter()
+        .map(|record| (record.key, record.value.unwrap()))
+        .collect()
+}
+
+fn databases(
+    storage: &IpcSender<StorageThreadMsg>,
+    origin: &ImmutableOrigin,
+) -> Vec<(String, u64)> {
+    let (sender, receiver) = ipc::channel().unwrap();
+    send(
+        storage,
+        IndexedDBThreadMsg::Databases(sender, origin.clone()),
+    );
+    receiver.recv().unwrap()
+}
+
+fn exit(storage: &IpcSender<StorageThreadMsg>) {
+    let (sender, receiver) = ipc::channel().unwrap();
+    storage.send(StorageThreadMsg::Exit(sender)).unwrap();
+    receiver.recv().unwrap();
+}
+
+#[test]
+fn test_indexeddb_persists_committed_transactions() {
+    let config_dir = temp_config_dir();
+    let origin = origin("https://servo.org");
+    let storage: IpcSender<StorageThreadMsg> = StorageThreadFactory::new(Some(config_dir.clone()));
+    let (connection, _) = create_database(&storage, &origin);
+
+    let transaction = begin_started(&storage, connection, IndexedDBTransactionMode::ReadWrite);
+    assert_eq!(
+        put(&storage, transaction, "b", vec![2]),
+        Ok(IndexedDBKeyType::Number(1.))
+    );
+    assert_eq!(
+        put(&storage, transaction, "a", vec![1]),
+        Ok(IndexedDBKeyType::Number(2.))
+    );
+    // The index is unique.
+    assert_eq!(
+        put(&storage, transaction, "a", vec![3]),
+        Err(IndexedDBError::Constraint)
+    );
+    commit(&storage, transaction);
+
+    // Changes of an aborted transaction are reverted.
+    let transaction = begin_started(&storage, connection, IndexedDBTransactionMode::ReadWrite);
+    put(&storage, transaction, "c", vec![3]).unwrap();
+    abort(&storage, transaction);
+    exit(&storage);
+
+    let storage: IpcSender<StorageThreadMsg> = StorageThreadFactory::new(Some(config_dir.clone()));
+    let (connection, _, version) = open(&storage, &origin);
+    assert_eq!(version, 1);
+    assert_eq!(
+        values_by_name(&storage, connection),
+        vec![
+            (IndexedDBKeyType::String("a".to_owned()), vec![1]),
+            (IndexedDBKeyType::String("b".to_owned()), vec![2]),
+        ]
+    );
+
+    // Other origins don't see the database.
+    assert!(databases(&storage, &self::origin("https://example.com")).is_empty());
+    exit(&storage);
+
+    fs::remove_dir_all(config_dir).unwrap();
+}
+
+#[test]
+fn test_indexeddb_overlapping_transactions_run_one_at_a_time() {
+    let origin = origin("https://servo.org");
+    let storage: IpcSender<StorageThreadMsg> = StorageThreadFactory::new(None);
+    let (connection, _) = create_database(&storage, &origin);
+
+    let first = begin_started(&storage, connection, IndexedDBTransactionMode::ReadWrite);
+    // Transactions on the same object store wait for a transaction that writes to it.
+    let (second, second_events) = begin(&storage, connection, IndexedDBTransactionMode::ReadWrite);
+    assert!(second_events.try_recv().is_err());
+    let (reader, reader_events) = begin(&storage, connection, IndexedDBTransactionMode::ReadOnly);
+    assert!(reader_events.try_recv().is_err());
+    assert_eq!(
+        put(&storage, second, "b", vec![2]),
+        Err(IndexedDBError::TransactionInactive)
+    );
+
+    put(&storage, first, "a", vec![1]).unwrap();
+    abort(&storage, first);
+
+    // The second transaction sees none of the changes of the aborted one.
+    assert_eq!(
+        second_events.recv().unwrap(),
+        IndexedDBScheduleEvent::Started(1)
+    );
+    assert_eq!(
+        put(&storage, second, "b", vec![2]),
+        Ok(IndexedDBKeyType::Number(1.))
+    );
+    assert!(reader_events.try_recv().is_err());
+    commit(&storage, second);
+    assert_eq!(
+        reader_events.recv().unwrap(),
+        IndexedDBScheduleEvent::Started(1)
+    );
+    commit(&storage, reader);
+
+    assert_eq!(
+        values_by_name(&storage, connection),
+        vec![(IndexedDBKeyType::String("b".to_owned()), vec![2])]
+    );
+    exit(&storage);
+}
+
+#[test]
+fn test_indexeddb_version_changes_wait_for_other_connections() {
+    let origin = origin("https://servo.org");
+    let storage: IpcSender<StorageThreadMsg> = StorageThreadFactory::new(None);
+    let (first, first_events) = create_database(&storage, &origin);
+    let (second, second_events, _) = open(&storage, &origin);
+
+    let (transaction, events) = upgrade(&storage, second, 2);
+    assert_eq!(
+        first_events.recv().unwrap(),
+        IndexedDBConnectionEvent::VersionChange(transaction, 1, Some(2))
+    );
+    // The upgrade is blocked once the other connection handled the event without closing.
+    send(
+        &storage,
+        IndexedDBThreadMsg::VersionChangeFired(transaction),
+    );
+    assert_eq!(events.recv().unwrap(), IndexedDBScheduleEvent::Blocked(1));
+    send(&storage, IndexedDBThreadMsg::Close(first));
+    assert_eq!(events.recv().unwrap(), IndexedDBScheduleEvent::Started(1));
+    commit(&storage, transaction);
+    assert_eq!(databases(&storage, &origin), vec![("db".to_owned(), 2)]);
+
+    // Deletions wait in the same way.
+    let (events_sender, events) = ipc::channel().unwrap();
+    send(
+        &storage,
+        IndexedDBThreadMsg::DeleteDatabase(events_sender, origin.clone(), "db".to_owned()),
+    );
+    match second_events.recv().unwrap() {
+        IndexedDBConnectionEvent::VersionChange(_, 2, None) => {},
+        event => panic!("Unexpected event {:?}", event),
+    }
+    assert!(events.try_recv().is_err());
+    send(&storage, IndexedDBThreadMsg::Close(second));
+    assert_eq!(events.recv().unwrap(), IndexedDBScheduleEvent::Started(2));
+    assert!(databases(&storage, &origin).is_empty());
+    exit(&storage);
+}
+
+#[test]
+fn test_indexeddb_quota() {
+    let origin = origin("https://servo.org");
+    let storage: IpcSender<StorageThreadMsg> = StorageThreadFactory::new(None);
+    let (connection, _) = create_database(&storage, &origin);
+
+    let transaction = begin_started(&storage, connection, IndexedDBTransactionMode::ReadWrite);
+    put(&storage, transaction, "half", vec![0; QUOTA_SIZE_LIMIT / 2]).unwrap();
+    assert_eq!(
+        put(&storage, transaction, "more", vec![0; QUOTA_SIZE_LIMIT / 2]),
+        Err(IndexedDBError::QuotaExceeded)
+    );
+    commit(&storage, transaction);
+    exit(&storage);
+}
@@ -7,28 +7,33 @@
 #[macro_use]
 extern crate lazy_static;
 
+mod cache_storage;
 mod cookie;
 mod cookie_http_state;
 mod data_loader;
+mod download_manager;
 mod fetch;
 mod file_loader;
 mod filemanager_thread;
 mod hsts;
 mod http_cache;
 mod http_loader;
+mod indexeddb;
 mod mime_classifier;
 mod resource_thread;
 mod subresource_integrity;
 
-use crossbeam_channel::{unbounded, Sender};
+use content_security_policy as csp;
+use crossbeam_channel::{unbounded, Receiver, Sender};
 use devtools_traits::DevtoolsControlMsg;
 use embedder_traits::resources::{self, Resource};
-use embedder_traits::{EmbedderProxy, EventLoopWaker};
+use embedder_traits::{EmbedderMsg, EmbedderProxy, EventLoopWaker};
 use futures::{Future, Stream};
 use hyper::server::conn::Http;
 use hyper::server::Server as HyperServer;
 use hyper::service::service_fn_ok;
 use hyper::{Body, Request as HyperRequest, Response as HyperResponse};
+use msg::constellation_msg::TopLevelBrowsingContextId;
 use net::connector::{create_tls_config, ConnectionCerts, ExtraCerts, ALPN_H2_H1};
 use net::fetch::cors_cache::CorsCache;
 use net::fetch::methods::{self, CancellationListener, FetchContext};
@@ -61,7 +66,14 @@ struct FetchResponseCollector {
 }
 
 fn create_embedder_proxy() -> EmbedderProxy {
-    let (sender, _) = unbounded();
+    create_embedder_proxy_and_receiver().0
+}
+
+fn create_embedder_proxy_and_receiver() -> (
+    EmbedderProxy,
+    Receiver<(Option<TopLevelBrowsingContextId>, EmbedderMsg)>,
+) {
+    let (sender, receiver) = unbounded();
     let event_loop_waker = || {
         struct DummyEventLoopWaker {}
         impl DummyEventLoopWaker {
@@ -79,10 +91,11 @@ fn create_embedder_proxy() -> EmbedderProxy {
         Box::new(DummyEventLoopWaker::new())
     };
 
-    EmbedderProxy {
+    let proxy = EmbedderProxy {
         sender: sender,
         event_loop_waker: event_loop_waker(),
-    }
+    };
+    (proxy, receiver)
 }
 
 fn new_fetch_context(
@@ -106,6 +119,7 @@ fn new_fetch_context(
         filemanager: FileManager::new(sender, pool_handle.unwrap_or_else(|| Weak::new())),
         file_token: FileTokenCheck::NotRequired,
         cancellation_listener: Arc::new(Mutex::new(CancellationListener::new(None))),
+        service_worker_managers: Default::default(),
         timing: ServoArc::new(Mutex::new(ResourceFetchTiming::new(
             ResourceTimingType::Navigation,
         ))),
@@ -120,6 +134,7 @@ impl FetchTaskTarget for FetchResponseCollector {
     fn process_response_eof(&mut self, response: &Response) {
         let _ = self.sender.send(response.clone());
     }
+    fn process_csp_violations(&mut self, _: &Request, _: Vec<csp::Violation>) {}
 }
 
 fn fetch(request: &mut Request, dc: Option<Sender<DevtoolsControlMsg>>) -> Response {
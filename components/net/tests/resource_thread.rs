@@ -3,12 +3,14 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
 use crate::create_embedder_proxy;
+use hyper_serde::Serde;
 use ipc_channel::ipc;
 use net::resource_thread::new_core_resource_thread;
 use net::test::parse_hostsfile;
-use net_traits::CoreResourceMsg;
+use net_traits::{CookieSource, CoreResourceMsg, CoreResourceThread};
 use profile_traits::mem::ProfilerChan as MemProfilerChan;
 use profile_traits::time::ProfilerChan;
+use servo_url::ServoUrl;
 use std::net::IpAddr;
 
 fn ip(s: &str) -> IpAddr {
@@ -33,6 +35,69 @@ fn test_exit() {
     receiver.recv().unwrap();
 }
 
+fn cookies_for_url(resource_thread: &CoreResourceThread, url: &ServoUrl) -> Option<String> {
+    let (sender, receiver) = ipc::channel().unwrap();
+    resource_thread
+        .send(CoreResourceMsg::GetCookiesForUrl(
+            url.clone(),
+            sender,
+            CookieSource::HTTP,
+        ))
+        .unwrap();
+    receiver.recv().unwrap()
+}
+
+#[test]
+fn test_private_session_is_isolated() {
+    let (tx, _rx) = ipc::channel().unwrap();
+    let (mtx, _mrx) = ipc::channel().unwrap();
+    let (resource_thread, _private_resource_thread) = new_core_resource_thread(
+        "".into(),
+        None,
+        ProfilerChan(tx),
+        MemProfilerChan(mtx),
+        create_embedder_proxy(),
+        None,
+        None,
+    );
+    let (session_thread, session_receiver) = ipc::channel().unwrap();
+    resource_thread
+        .send(CoreResourceMsg::NewPrivateSession(session_receiver))
+        .unwrap();
+
+    let url = ServoUrl::parse("http://mozilla.com").unwrap();
+    let cookie = cookie_rs::Cookie::parse("private=yes").unwrap();
+    session_thread
+        .send(CoreResourceMsg::SetCookieForUrl(
+            url.clone(),
+            Serde(cookie),
+            CookieSource::HTTP,
+        ))
+        .unwrap();
+    assert_eq!(
+        cookies_for_url(&session_thread, &url),
+        Some("private=yes".to_owned())
+    );
+    assert_eq!(cookies_for_url(&resource_thread, &url), None);
+
+    // Exiting the private session leaves the public state alone.
+    let (sender, receiver) = ipc::channel().unwrap();
+    session_thread.send(CoreResourceMsg::Exit(sender)).unwrap();
+    receiver.recv().unwrap();
+    assert_eq!(cookies_for_url(&resource_thread, &url), None);
+
+    // A new private session starts out empty.
+    let (session_thread, session_receiver) = ipc::channel().unwrap();
+    resource_thread
+        .send(CoreResourceMsg::NewPrivateSession(session_receiver))
+        .unwrap();
+    assert_eq!(cookies_for_url(&session_thread, &url), None);
+
+    let (sender, receiver) = ipc::channel().unwrap();
+    resource_thread.send(CoreResourceMsg::Exit(sender)).unwrap();
+    receiver.recv().unwrap();
+}
+
 #[test]
 fn test_parse_hostsfile() {
     let mock_hosts_file_content = "127.0.0.1 foo.bar.com\n127.0.0.2 servo.test.server";
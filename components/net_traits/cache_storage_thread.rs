@@ -0,0 +1,133 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Messages and types for the Cache Storage engine, which runs on the storage thread.
+
+use crate::request::{BodyChunkResponse, RequestBody};
+use crate::response::ResponseType;
+use http::{HeaderMap, Method};
+use ipc_channel::ipc::{IpcReceiver, IpcSender};
+use servo_url::{ImmutableOrigin, ServoUrl};
+
+/// The parts of a request that are kept in a cache, or that a cache is queried with.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CacheRequest {
+    pub url: ServoUrl,
+    #[serde(
+        deserialize_with = "::hyper_serde::deserialize",
+        serialize_with = "::hyper_serde::serialize"
+    )]
+    pub method: Method,
+    #[serde(
+        deserialize_with = "::hyper_serde::deserialize",
+        serialize_with = "::hyper_serde::serialize"
+    )]
+    pub headers: HeaderMap,
+}
+
+/// The parts of a response that are kept in a cache, besides its body.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CacheResponse {
+    pub url: Option<ServoUrl>,
+    pub response_type: ResponseType,
+    pub raw_status: Option<(u16, Vec<u8>)>,
+    #[serde(
+        deserialize_with = "::hyper_serde::deserialize",
+        serialize_with = "::hyper_serde::serialize"
+    )]
+    pub headers: HeaderMap,
+}
+
+/// <https://w3c.github.io/ServiceWorker/#dictdef-cachequeryoptions>
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub struct CacheQueryOptions {
+    pub ignore_search: bool,
+    pub ignore_method: bool,
+    pub ignore_vary: bool,
+}
+
+/// The arguments of <https://w3c.github.io/ServiceWorker/#query-cache>.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CacheQuery {
+    pub request: CacheRequest,
+    pub options: CacheQueryOptions,
+}
+
+/// A response found in a cache. Its body follows in chunks over `body`.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CacheMatch {
+    pub response: CacheResponse,
+    pub body: IpcReceiver<BodyChunkResponse>,
+}
+
+/// An entry to add to a cache, whose body is read from script in chunks.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CachePut {
+    pub request: CacheRequest,
+    pub response: CacheResponse,
+    pub body: Option<RequestBody>,
+}
+
+/// Request operations on the caches of an origin. Caches are referred to by name in the
+/// operations of `CacheStorage`, and by the id `Open` replied with in those of `Cache`.
+#[derive(Debug, Deserialize, Serialize)]
+pub enum CacheStorageThreadMsg {
+    /// Opens the cache with the given name, creating it if needed, and replies with its id.
+    Open(IpcSender<u64>, ImmutableOrigin, String),
+
+    /// Replies with whether a cache with the given name exists.
+    Has(IpcSender<bool>, ImmutableOrigin, String),
+
+    /// Deletes the cache with the given name, replying with whether it existed.
+    Delete(IpcSender<bool>, ImmutableOrigin, String),
+
+    /// Replies with the names of the caches, in the order they were created in.
+    Keys(IpcSender<Vec<String>>, ImmutableOrigin),
+
+    /// Replies with the first response matching the query, in the cache with the given name,
+    /// or in every cache in the order they were created in.
+    Match(
+        IpcSender<Option<CacheMatch>>,
+        ImmutableOrigin,
+        Option<String>,
+        CacheQuery,
+    ),
+
+    /// Replies with the first response of a cache matching the query.
+    MatchFirst(
+        IpcSender<Option<CacheMatch>>,
+        ImmutableOrigin,
+        u64,
+        CacheQuery,
+    ),
+
+    /// Replies with the responses of a cache matching the query, or all of them.
+    MatchAll(
+        IpcSender<Vec<CacheMatch>>,
+        ImmutableOrigin,
+        u64,
+        Option<CacheQuery>,
+    ),
+
+    /// Replies with the requests of a cache matching the query, or all of them.
+    Requests(
+        IpcSender<Vec<CacheRequest>>,
+        ImmutableOrigin,
+        u64,
+        Option<CacheQuery>,
+    ),
+
+    /// Reads the bodies of the entries, then adds them to a cache in place of the entries
+    /// matching their requests. Replies with an error if a body couldn't be read, in which
+    /// case the cache is left unchanged.
+    Put(
+        IpcSender<Result<(), ()>>,
+        ImmutableOrigin,
+        u64,
+        Vec<CachePut>,
+    ),
+
+    /// Deletes the entries of a cache matching the query, replying with whether there were any.
+    DeleteEntries(IpcSender<bool>, ImmutableOrigin, u64, CacheQuery),
+}
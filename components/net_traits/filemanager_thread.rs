@@ -135,6 +135,17 @@ pub enum FileManagerThreadMsg {
         Option<Vec<String>>,
     ),
 
+    /// Allow the files at these paths, which the user dropped onto a page, to be selected
+    /// with `SelectDroppedFiles`
+    GrantDroppedFiles(Vec<PathBuf>),
+
+    /// Select files that the user dropped onto a page
+    SelectDroppedFiles(
+        Vec<PathBuf>,
+        IpcSender<FileManagerResult<Vec<SelectedFile>>>,
+        FileOrigin,
+    ),
+
     /// Read FileID-indexed file in chunks, optionally check URL validity based on boolean flag
     ReadFile(
         IpcSender<FileManagerResult<ReadFileProgress>>,
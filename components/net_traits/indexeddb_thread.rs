@@ -0,0 +1,354 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Messages and types for the IndexedDB engine, which runs on the storage thread.
+
+use ipc_channel::ipc::IpcSender;
+use servo_url::ImmutableOrigin;
+use std::cmp::Ordering;
+
+/// <https://w3c.github.io/IndexedDB/#key-construct>
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, Serialize)]
+pub enum IndexedDBKeyType {
+    Number(f64),
+    Date(f64),
+    String(String),
+    Binary(Vec<u8>),
+    Array(Vec<IndexedDBKeyType>),
+}
+
+impl IndexedDBKeyType {
+    fn type_rank(&self) -> u8 {
+        match *self {
+            IndexedDBKeyType::Number(_) => 0,
+            IndexedDBKeyType::Date(_) => 1,
+            IndexedDBKeyType::String(_) => 2,
+            IndexedDBKeyType::Binary(_) => 3,
+            IndexedDBKeyType::Array(_) => 4,
+        }
+    }
+}
+
+/// <https://w3c.github.io/IndexedDB/#compare-two-keys>
+impl Ord for IndexedDBKeyType {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (IndexedDBKeyType::Number(a), IndexedDBKeyType::Number(b)) |
+            (IndexedDBKeyType::Date(a), IndexedDBKeyType::Date(b)) => {
+                a.partial_cmp(b).unwrap_or(Ordering::Equal)
+            },
+            (IndexedDBKeyType::String(a), IndexedDBKeyType::String(b)) => {
+                // Strings compare by their UTF-16 code units.
+                a.encode_utf16().cmp(b.encode_utf16())
+            },
+            (IndexedDBKeyType::Binary(a), IndexedDBKeyType::Binary(b)) => a.cmp(b),
+            (IndexedDBKeyType::Array(a), IndexedDBKeyType::Array(b)) => a.cmp(b),
+            _ => self.type_rank().cmp(&other.type_rank()),
+        }
+    }
+}
+
+impl PartialOrd for IndexedDBKeyType {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for IndexedDBKeyType {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for IndexedDBKeyType {}
+
+/// <https://w3c.github.io/IndexedDB/#range-construct>
+#[derive(Clone, Debug, Default, Deserialize, MallocSizeOf, Serialize)]
+pub struct IndexedDBKeyRange {
+    pub lower: Option<IndexedDBKeyType>,
+    pub upper: Option<IndexedDBKeyType>,
+    pub lower_open: bool,
+    pub upper_open: bool,
+}
+
+impl IndexedDBKeyRange {
+    /// A range containing only `key`.
+    pub fn only(key: IndexedDBKeyType) -> IndexedDBKeyRange {
+        IndexedDBKeyRange {
+            lower: Some(key.clone()),
+            upper: Some(key),
+            lower_open: false,
+            upper_open: false,
+        }
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#in>
+    pub fn contains(&self, key: &IndexedDBKeyType) -> bool {
+        let above_lower = match self.lower {
+            Some(ref lower) if self.lower_open => key > lower,
+            Some(ref lower) => key >= lower,
+            None => true,
+        };
+        let below_upper = match self.upper {
+            Some(ref upper) if self.upper_open => key < upper,
+            Some(ref upper) => key <= upper,
+            None => true,
+        };
+        above_lower && below_upper
+    }
+}
+
+/// <https://w3c.github.io/IndexedDB/#key-path-construct>
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub enum KeyPath {
+    String(String),
+    Sequence(Vec<String>),
+}
+
+/// <https://w3c.github.io/IndexedDB/#transaction-mode>
+#[derive(Clone, Copy, Debug, Deserialize, Eq, MallocSizeOf, PartialEq, Serialize)]
+pub enum IndexedDBTransactionMode {
+    ReadOnly,
+    ReadWrite,
+    VersionChange,
+}
+
+/// <https://w3c.github.io/IndexedDB/#cursor-direction>
+#[derive(Clone, Copy, Debug, Deserialize, Eq, MallocSizeOf, PartialEq, Serialize)]
+pub enum IndexedDBCursorDirection {
+    Next,
+    NextUnique,
+    Prev,
+    PrevUnique,
+}
+
+impl IndexedDBCursorDirection {
+    pub fn is_reverse(&self) -> bool {
+        match *self {
+            IndexedDBCursorDirection::Prev | IndexedDBCursorDirection::PrevUnique => true,
+            IndexedDBCursorDirection::Next | IndexedDBCursorDirection::NextUnique => false,
+        }
+    }
+
+    pub fn is_unique(&self) -> bool {
+        match *self {
+            IndexedDBCursorDirection::NextUnique | IndexedDBCursorDirection::PrevUnique => true,
+            IndexedDBCursorDirection::Next | IndexedDBCursorDirection::Prev => false,
+        }
+    }
+}
+
+/// The metadata of an index.
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, Serialize)]
+pub struct IndexInfo {
+    pub name: String,
+    pub key_path: KeyPath,
+    pub unique: bool,
+    pub multi_entry: bool,
+}
+
+/// The metadata of an object store.
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, Serialize)]
+pub struct ObjectStoreInfo {
+    pub name: String,
+    pub key_path: Option<KeyPath>,
+    pub auto_increment: bool,
+    pub indexes: Vec<IndexInfo>,
+}
+
+/// The metadata of a database, as seen by a new connection.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DatabaseInfo {
+    pub name: String,
+    pub version: u64,
+    pub object_stores: Vec<ObjectStoreInfo>,
+}
+
+/// A record returned by a query. For object stores `key` and `primary_key` are the same.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct IndexedDBRecord {
+    pub key: IndexedDBKeyType,
+    pub primary_key: IndexedDBKeyType,
+    /// The serialized value, if it was asked for.
+    pub value: Option<Vec<u8>>,
+}
+
+/// The position a cursor continues iterating from.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct IndexedDBCursorPosition {
+    pub key: IndexedDBKeyType,
+    /// For index cursors, the primary key within `key` to continue from.
+    pub primary_key: Option<IndexedDBKeyType>,
+    /// Whether the record at the position itself may be returned.
+    pub inclusive: bool,
+}
+
+/// A read of records from an object store, or from one of its indexes.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct IndexedDBQuery {
+    pub object_store: String,
+    pub index: Option<String>,
+    pub range: IndexedDBKeyRange,
+    pub direction: IndexedDBCursorDirection,
+    pub position: Option<IndexedDBCursorPosition>,
+    /// The maximum number of records to return, or all of them.
+    pub count: Option<u32>,
+    pub with_values: bool,
+}
+
+/// A write of a record to an object store.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct IndexedDBPut {
+    pub object_store: String,
+    /// The key, when not generated by the store's key generator.
+    pub key: Option<IndexedDBKeyType>,
+    pub value: Vec<u8>,
+    /// The keys of the record in each index of the store, by index name.
+    pub index_keys: Vec<(String, Vec<IndexedDBKeyType>)>,
+    /// Fail with a constraint error instead of replacing an existing record (`add()`).
+    pub no_overwrite: bool,
+}
+
+/// Errors reported by the engine, named after the `DOMException` they turn into.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum IndexedDBError {
+    Constraint,
+    Data,
+    InvalidState,
+    NotFound,
+    QuotaExceeded,
+    ReadOnly,
+    TransactionInactive,
+}
+
+pub type IndexedDBResult<T> = Result<T, IndexedDBError>;
+
+/// Events about a connection, sent to the event loop that opened it.
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub enum IndexedDBConnectionEvent {
+    /// Another connection is waiting to upgrade or delete the database until this one is
+    /// closed. Carries the id of the waiting transaction, the version of the database and the
+    /// version it is upgraded to, or none for a deletion.
+    VersionChange(u64, u64, Option<u64>),
+}
+
+/// Events about a transaction waiting for the transactions it overlaps with, or for the other
+/// connections to its database to close.
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub enum IndexedDBScheduleEvent {
+    /// Other connections are still open after they were told about the version change.
+    /// Carries the version of the database.
+    Blocked(u64),
+    /// The transaction may run its requests, or the database was deleted. Carries the version
+    /// the database had before the upgrade or deletion.
+    Started(u64),
+}
+
+/// Operations on the databases of an origin.
+#[derive(Debug, Deserialize, Serialize)]
+pub enum IndexedDBThreadMsg {
+    /// Opens a connection to a database, creating it at version 0 if it does not exist yet.
+    /// Replies with the id of the connection.
+    Open(
+        IpcSender<(u64, DatabaseInfo)>,
+        IpcSender<IndexedDBConnectionEvent>,
+        ImmutableOrigin,
+        String,
+    ),
+
+    /// Closes a connection.
+    Close(u64),
+
+    /// Deletes a database once the other connections to it are closed.
+    DeleteDatabase(IpcSender<IndexedDBScheduleEvent>, ImmutableOrigin, String),
+
+    /// Gets the name and version of every database of the origin.
+    Databases(IpcSender<Vec<(String, u64)>>, ImmutableOrigin),
+
+    /// Creates a readonly or readwrite transaction on the object stores of a connection,
+    /// replying with its id. The transaction starts once the earlier transactions it
+    /// overlaps with are finished.
+    BeginTransaction(
+        IpcSender<IndexedDBResult<u64>>,
+        IpcSender<IndexedDBScheduleEvent>,
+        u64,
+        IndexedDBTransactionMode,
+        Vec<String>,
+    ),
+
+    /// Creates the versionchange transaction of a connection, replying with its id. The
+    /// transaction starts and sets the version of the database once the other connections
+    /// are closed.
+    Upgrade(
+        IpcSender<IndexedDBResult<u64>>,
+        IpcSender<IndexedDBScheduleEvent>,
+        u64,
+        u64,
+    ),
+
+    /// Tells that a connection handled the versionchange event of a waiting transaction.
+    VersionChangeFired(u64),
+
+    /// Commits a transaction, writing its changes to disk.
+    Commit(IpcSender<IndexedDBResult<()>>, u64),
+
+    /// Aborts a transaction, reverting its changes.
+    Abort(IpcSender<()>, u64),
+
+    /// Creates an object store in a versionchange transaction.
+    CreateObjectStore(IpcSender<IndexedDBResult<()>>, u64, ObjectStoreInfo),
+
+    /// Deletes an object store in a versionchange transaction.
+    DeleteObjectStore(IpcSender<IndexedDBResult<()>>, u64, String),
+
+    /// Creates an index in a versionchange transaction, along with the index keys
+    /// of the records already in the store, by primary key.
+    CreateIndex(
+        IpcSender<IndexedDBResult<()>>,
+        u64,
+        String,
+        IndexInfo,
+        Vec<(IndexedDBKeyType, Vec<IndexedDBKeyType>)>,
+    ),
+
+    /// Deletes an index in a versionchange transaction.
+    DeleteIndex(IpcSender<IndexedDBResult<()>>, u64, String, String),
+
+    /// Takes the next key from the key generator of an object store.
+    GenerateKey(IpcSender<IndexedDBResult<IndexedDBKeyType>>, u64, String),
+
+    /// Stores a record, replying with its key.
+    Put(
+        IpcSender<IndexedDBResult<IndexedDBKeyType>>,
+        u64,
+        IndexedDBPut,
+    ),
+
+    /// Reads records.
+    Query(
+        IpcSender<IndexedDBResult<Vec<IndexedDBRecord>>>,
+        u64,
+        IndexedDBQuery,
+    ),
+
+    /// Counts the records of an object store or index within a range.
+    Count(
+        IpcSender<IndexedDBResult<u64>>,
+        u64,
+        String,
+        Option<String>,
+        IndexedDBKeyRange,
+    ),
+
+    /// Deletes the records of an object store within a range.
+    Delete(
+        IpcSender<IndexedDBResult<()>>,
+        u64,
+        String,
+        IndexedDBKeyRange,
+    ),
+
+    /// Deletes all the records of an object store.
+    Clear(IpcSender<IndexedDBResult<()>>, u64, String),
+}
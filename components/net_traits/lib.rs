@@ -16,10 +16,12 @@ extern crate malloc_size_of_derive;
 extern crate serde;
 
 use crate::filemanager_thread::FileManagerThreadMsg;
-use crate::request::{Request, RequestBuilder};
+use crate::request::{BodyChunkRequest, Request, RequestBuilder};
 use crate::response::{HttpsState, Response, ResponseInit};
 use crate::storage_thread::StorageThreadMsg;
+use content_security_policy as csp;
 use cookie::Cookie;
+use embedder_traits::{DownloadAction, DownloadId};
 use headers::{ContentType, HeaderMapExt, ReferrerPolicy as ReferrerPolicyHeader};
 use http::{Error as HttpError, HeaderMap};
 use hyper::Error as HyperError;
@@ -29,15 +31,17 @@ use ipc_channel::ipc::{self, IpcReceiver, IpcSender};
 use ipc_channel::router::ROUTER;
 use ipc_channel::Error as IpcError;
 use mime::Mime;
-use msg::constellation_msg::HistoryStateId;
+use msg::constellation_msg::{HistoryStateId, TopLevelBrowsingContextId};
 use servo_rand::RngCore;
 use servo_url::{ImmutableOrigin, ServoUrl};
 use time::precise_time_ns;
 use webrender_api::{ImageData, ImageDescriptor, ImageKey};
 
 pub mod blob_url_store;
+pub mod cache_storage_thread;
 pub mod filemanager_thread;
 pub mod image_cache;
+pub mod indexeddb_thread;
 pub mod pub_domains;
 pub mod quality;
 pub mod request;
@@ -99,10 +103,22 @@ impl CustomResponse {
     }
 }
 
+/// A request for the service worker controlling `load_url` to handle a fetch.
+/// A `None` response means the fetch should fall back to the network.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct CustomResponseMediator {
     pub response_chan: IpcSender<Option<CustomResponse>>,
+    /// The URL matched against the registered scopes: the request's own URL for navigations,
+    /// and the URL of the client that made it otherwise.
     pub load_url: ServoUrl,
+    /// The request to hand to the `fetch` event.
+    pub request: RequestBuilder,
+    /// The channel to read the request's body over, if it has one.
+    pub body: Option<IpcSender<BodyChunkRequest>>,
+    /// The value of the `Service-Worker-Navigation-Preload` header to send with a preload
+    /// request, set by the service worker manager if the registration enabled navigation preload.
+    /// <https://w3c.github.io/ServiceWorker/#service-worker-registration-navigation-preload-enabled-flag>
+    pub navigation_preload: Option<Vec<u8>>,
 }
 
 /// [Policies](https://w3c.github.io/webappsec-referrer-policy/#referrer-policy-states)
@@ -190,6 +206,7 @@ pub enum FetchResponseMsg {
     ProcessResponse(Result<FetchMetadata, NetworkError>),
     ProcessResponseChunk(Vec<u8>),
     ProcessResponseEOF(Result<ResourceFetchTiming, NetworkError>),
+    ProcessCspViolations(Vec<csp::Violation>),
 }
 
 pub trait FetchTaskTarget {
@@ -215,6 +232,11 @@ pub trait FetchTaskTarget {
     ///
     /// Fired when the response is fully fetched
     fn process_response_eof(&mut self, response: &Response);
+
+    /// <https://www.w3.org/TR/CSP/#report-violation>
+    ///
+    /// Fired when the request or its response violated the client's Content Security Policy
+    fn process_csp_violations(&mut self, request: &Request, violations: Vec<csp::Violation>);
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -240,6 +262,7 @@ pub trait FetchResponseListener {
     fn process_response(&mut self, metadata: Result<FetchMetadata, NetworkError>);
     fn process_response_chunk(&mut self, chunk: Vec<u8>);
     fn process_response_eof(&mut self, response: Result<ResourceFetchTiming, NetworkError>);
+    fn process_csp_violations(&mut self, violations: Vec<csp::Violation>);
     fn resource_timing(&self) -> &ResourceFetchTiming;
     fn resource_timing_mut(&mut self) -> &mut ResourceFetchTiming;
     fn submit_resource_timing(&mut self);
@@ -273,6 +296,10 @@ impl FetchTaskTarget for IpcSender<FetchResponseMsg> {
                 .clone())));
         }
     }
+
+    fn process_csp_violations(&mut self, _: &Request, violations: Vec<csp::Violation>) {
+        let _ = self.send(FetchResponseMsg::ProcessCspViolations(violations));
+    }
 }
 
 /// A fetch task that discards all data it's sent,
@@ -290,6 +317,8 @@ impl FetchTaskTarget for DiscardFetch {
     fn process_response_chunk(&mut self, _: Vec<u8>) {}
 
     fn process_response_eof(&mut self, _: &Response) {}
+
+    fn process_csp_violations(&mut self, _: &Request, _: Vec<csp::Violation>) {}
 }
 
 pub trait Action<Listener> {
@@ -321,6 +350,9 @@ impl<T: FetchResponseListener> Action<T> for FetchResponseMsg {
                     Err(e) => listener.process_response_eof(Err(e)),
                 }
             },
+            FetchResponseMsg::ProcessCspViolations(violations) => {
+                listener.process_csp_violations(violations)
+            },
         }
     }
 }
@@ -416,12 +448,23 @@ pub enum WebSocketNetworkEvent {
     Fail,
 }
 
+/// Messages the consumer of a response body sends to apply back-pressure to the
+/// reading of the body from the network.
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub enum ResponseBodyControlMsg {
+    /// Stop reading the body, because the consumer has enough of it queued.
+    Pause,
+    /// Carry on reading the body.
+    Resume,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 /// IPC channels to communicate with the script thread about network or DOM events.
 pub enum FetchChannels {
     ResponseMsg(
         IpcSender<FetchResponseMsg>,
         /* cancel_chan */ Option<IpcReceiver<()>>,
+        /* body_control */ Option<IpcReceiver<ResponseBodyControlMsg>>,
     ),
     WebSocket {
         event_sender: IpcSender<WebSocketNetworkEvent>,
@@ -469,6 +512,19 @@ pub enum CoreResourceMsg {
     NetworkMediator(IpcSender<CustomResponseMediator>, ImmutableOrigin),
     /// Message forwarded to file manager's handler
     ToFileManager(FileManagerThreadMsg),
+    /// Fetch a resource and save it to disk rather than handing it to a document,
+    /// optionally using a file name suggested by the page.
+    Download(
+        RequestBuilder,
+        Option<TopLevelBrowsingContextId>,
+        /* suggested_filename */ Option<String>,
+    ),
+    /// Pause, resume or cancel an ongoing download
+    DownloadAction(DownloadId, DownloadAction),
+    /// Start a private browsing session, whose cookies, caches and blobs are kept in memory
+    /// and apart from any other session, handling the messages sent over the given receiver.
+    /// The session is discarded once all the senders for that receiver have been dropped.
+    NewPrivateSession(IpcReceiver<CoreResourceMsg>),
     /// Break the load handler loop, send a reply when done cleaning up local resources
     /// and exit
     Exit(IpcSender<()>),
@@ -487,7 +543,7 @@ where
     core_resource_thread
         .send(CoreResourceMsg::Fetch(
             request,
-            FetchChannels::ResponseMsg(action_sender, None),
+            FetchChannels::ResponseMsg(action_sender, None, None),
         ))
         .unwrap();
 }
@@ -821,6 +877,116 @@ pub fn http_percent_encode(bytes: &[u8]) -> String {
     percent_encoding::percent_encode(bytes, HTTP_VALUE).to_string()
 }
 
+/// Parse the enforced and report-only Content Security Policies delivered with a response.
+/// <https://www.w3.org/TR/CSP/#parse-response-csp>
+pub fn csp_list_from_headers(headers: &HeaderMap) -> Option<csp::CspList> {
+    let headers_and_dispositions = [
+        ("content-security-policy", csp::PolicyDisposition::Enforce),
+        (
+            "content-security-policy-report-only",
+            csp::PolicyDisposition::Report,
+        ),
+    ];
+    let mut csp_list: Option<csp::CspList> = None;
+    for (name, disposition) in headers_and_dispositions.iter() {
+        for value in headers.get_all(*name).iter() {
+            // This silently ignores policies containing invalid Unicode.
+            let value = match value.to_str() {
+                Ok(value) => value,
+                Err(_) => continue,
+            };
+            let policies =
+                csp::CspList::parse(value, csp::PolicySource::Header, disposition.clone());
+            match csp_list {
+                Some(ref mut csp_list) => csp_list.append(policies),
+                None => csp_list = Some(policies),
+            }
+        }
+    }
+    csp_list
+}
+
+/// <https://html.spec.whatwg.org/multipage/#embedder-policy-value>
+#[derive(Clone, Copy, Debug, Deserialize, Eq, MallocSizeOf, PartialEq, Serialize)]
+pub enum CrossOriginEmbedderPolicy {
+    /// "unsafe-none"
+    UnsafeNone,
+    /// "require-corp"
+    RequireCorp,
+    /// "credentialless"
+    Credentialless,
+}
+
+impl CrossOriginEmbedderPolicy {
+    /// <https://html.spec.whatwg.org/multipage/#compatible-with-cross-origin-isolation>
+    pub fn is_compatible_with_cross_origin_isolation(&self) -> bool {
+        *self != CrossOriginEmbedderPolicy::UnsafeNone
+    }
+}
+
+impl Default for CrossOriginEmbedderPolicy {
+    fn default() -> Self {
+        CrossOriginEmbedderPolicy::UnsafeNone
+    }
+}
+
+/// <https://html.spec.whatwg.org/multipage/#cross-origin-opener-policy-value>
+#[derive(Clone, Copy, Debug, Deserialize, Eq, MallocSizeOf, PartialEq, Serialize)]
+pub enum CrossOriginOpenerPolicy {
+    /// "unsafe-none"
+    UnsafeNone,
+    /// "same-origin-allow-popups"
+    SameOriginAllowPopups,
+    /// "same-origin"
+    SameOrigin,
+}
+
+impl Default for CrossOriginOpenerPolicy {
+    fn default() -> Self {
+        CrossOriginOpenerPolicy::UnsafeNone
+    }
+}
+
+/// Returns the token of a structured header item, ignoring its parameters.
+fn structured_header_token<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    let value = headers.get(name)?.to_str().ok()?;
+    value.split(';').next().map(str::trim)
+}
+
+/// <https://html.spec.whatwg.org/multipage/#obtain-an-embedder-policy>
+///
+/// Documents and workers that aren't secure contexts always get the default policy.
+pub fn embedder_policy_from_headers(
+    headers: &HeaderMap,
+    is_secure_context: bool,
+) -> CrossOriginEmbedderPolicy {
+    if !is_secure_context {
+        return CrossOriginEmbedderPolicy::UnsafeNone;
+    }
+    match structured_header_token(headers, "cross-origin-embedder-policy") {
+        Some("require-corp") => CrossOriginEmbedderPolicy::RequireCorp,
+        Some("credentialless") => CrossOriginEmbedderPolicy::Credentialless,
+        _ => CrossOriginEmbedderPolicy::UnsafeNone,
+    }
+}
+
+/// <https://html.spec.whatwg.org/multipage/#obtain-coop>
+///
+/// Documents that aren't secure contexts always get the default policy.
+pub fn opener_policy_from_headers(
+    headers: &HeaderMap,
+    is_secure_context: bool,
+) -> CrossOriginOpenerPolicy {
+    if !is_secure_context {
+        return CrossOriginOpenerPolicy::UnsafeNone;
+    }
+    match structured_header_token(headers, "cross-origin-opener-policy") {
+        Some("same-origin") => CrossOriginOpenerPolicy::SameOrigin,
+        Some("same-origin-allow-popups") => CrossOriginOpenerPolicy::SameOriginAllowPopups,
+        _ => CrossOriginOpenerPolicy::UnsafeNone,
+    }
+}
+
 #[derive(Deserialize, Serialize)]
 pub enum WebrenderImageMsg {
     AddImage(ImageKey, ImageDescriptor, ImageData),
@@ -3,6 +3,7 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
 use crate::response::HttpsState;
+use crate::CrossOriginEmbedderPolicy;
 use crate::ReferrerPolicy;
 use crate::ResourceTimingType;
 use content_security_policy::{self as csp, CspList};
@@ -241,6 +242,7 @@ pub struct RequestBuilder {
     pub pipeline_id: Option<PipelineId>,
     pub redirect_mode: RedirectMode,
     pub integrity_metadata: String,
+    pub cryptographic_nonce_metadata: String,
     // This is nominally a part of the client's global object.
     // It is copied here to avoid having to reach across the thread
     // boundary every time a redirect occurs.
@@ -251,6 +253,11 @@ pub struct RequestBuilder {
     pub parser_metadata: ParserMetadata,
     pub initiator: Initiator,
     pub https_state: HttpsState,
+    /// Whether any ancestor of the client's document has a potentially
+    /// trustworthy origin.
+    pub has_trustworthy_ancestor_origin: bool,
+    /// The embedder policy of the client.
+    pub embedder_policy: CrossOriginEmbedderPolicy,
 }
 
 impl RequestBuilder {
@@ -275,11 +282,14 @@ impl RequestBuilder {
             pipeline_id: None,
             redirect_mode: RedirectMode::Follow,
             integrity_metadata: "".to_owned(),
+            cryptographic_nonce_metadata: "".to_owned(),
             url_list: vec![],
             parser_metadata: ParserMetadata::Default,
             initiator: Initiator::None,
             csp_list: None,
             https_state: HttpsState::None,
+            has_trustworthy_ancestor_origin: false,
+            embedder_policy: CrossOriginEmbedderPolicy::UnsafeNone,
         }
     }
 
@@ -308,6 +318,14 @@ impl RequestBuilder {
         self
     }
 
+    pub fn service_workers_mode(
+        mut self,
+        service_workers_mode: ServiceWorkersMode,
+    ) -> RequestBuilder {
+        self.service_workers_mode = service_workers_mode;
+        self
+    }
+
     pub fn destination(mut self, destination: Destination) -> RequestBuilder {
         self.destination = destination;
         self
@@ -363,6 +381,11 @@ impl RequestBuilder {
         self
     }
 
+    pub fn cryptographic_nonce_metadata(mut self, nonce_metadata: String) -> RequestBuilder {
+        self.cryptographic_nonce_metadata = nonce_metadata;
+        self
+    }
+
     pub fn parser_metadata(mut self, parser_metadata: ParserMetadata) -> RequestBuilder {
         self.parser_metadata = parser_metadata;
         self
@@ -373,6 +396,19 @@ impl RequestBuilder {
         self
     }
 
+    pub fn has_trustworthy_ancestor_origin(
+        mut self,
+        has_trustworthy_ancestor_origin: bool,
+    ) -> RequestBuilder {
+        self.has_trustworthy_ancestor_origin = has_trustworthy_ancestor_origin;
+        self
+    }
+
+    pub fn embedder_policy(mut self, embedder_policy: CrossOriginEmbedderPolicy) -> RequestBuilder {
+        self.embedder_policy = embedder_policy;
+        self
+    }
+
     pub fn build(self) -> Request {
         let mut request = Request::new(
             self.url.clone(),
@@ -403,8 +439,11 @@ impl RequestBuilder {
         request.redirect_count = url_list.len() as u32 - 1;
         request.url_list = url_list;
         request.integrity_metadata = self.integrity_metadata;
+        request.cryptographic_nonce_metadata = self.cryptographic_nonce_metadata;
         request.parser_metadata = self.parser_metadata;
         request.csp_list = self.csp_list;
+        request.has_trustworthy_ancestor_origin = self.has_trustworthy_ancestor_origin;
+        request.embedder_policy = self.embedder_policy;
         request
     }
 }
@@ -462,6 +501,8 @@ pub struct Request {
     pub redirect_mode: RedirectMode,
     /// <https://fetch.spec.whatwg.org/#concept-request-integrity-metadata>
     pub integrity_metadata: String,
+    /// <https://fetch.spec.whatwg.org/#concept-request-nonce-metadata>
+    pub cryptographic_nonce_metadata: String,
     // Use the last method on url_list to act as spec current url field, and
     // first method to act as spec url field
     /// <https://fetch.spec.whatwg.org/#concept-request-url-list>
@@ -478,6 +519,13 @@ pub struct Request {
     #[ignore_malloc_size_of = "Defined in rust-content-security-policy"]
     pub csp_list: Option<CspList>,
     pub https_state: HttpsState,
+    /// Whether any ancestor of the client's document has a potentially
+    /// trustworthy origin. Used when checking for mixed content.
+    pub has_trustworthy_ancestor_origin: bool,
+    /// The embedder policy of the client's global object, copied here for
+    /// the same reason as `csp_list`.
+    /// <https://html.spec.whatwg.org/multipage/#concept-settings-object-embedder-policy>
+    pub embedder_policy: CrossOriginEmbedderPolicy,
 }
 
 impl Request {
@@ -512,12 +560,15 @@ impl Request {
             cache_mode: CacheMode::Default,
             redirect_mode: RedirectMode::Follow,
             integrity_metadata: String::new(),
+            cryptographic_nonce_metadata: String::new(),
             url_list: vec![url],
             parser_metadata: ParserMetadata::Default,
             redirect_count: 0,
             response_tainting: ResponseTainting::Basic,
             csp_list: None,
             https_state: https_state,
+            has_trustworthy_ancestor_origin: false,
+            embedder_policy: CrossOriginEmbedderPolicy::UnsafeNone,
         }
     }
 
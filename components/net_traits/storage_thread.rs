@@ -2,6 +2,8 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
+use crate::cache_storage_thread::CacheStorageThreadMsg;
+use crate::indexeddb_thread::IndexedDBThreadMsg;
 use ipc_channel::ipc::IpcSender;
 use servo_url::ServoUrl;
 
@@ -41,6 +43,12 @@ pub enum StorageThreadMsg {
     /// clears the associated storage data by removing all the key/value pairs
     Clear(IpcSender<bool>, ServoUrl, StorageType),
 
+    /// operates on the IndexedDB databases of an origin
+    IndexedDB(IndexedDBThreadMsg),
+
+    /// operates on the Cache Storage of an origin
+    CacheStorage(CacheStorageThreadMsg),
+
     /// send a reply when done cleaning up thread resources and then shut it down
     Exit(IpcSender<()>),
 }
@@ -191,8 +191,11 @@ impl TransmitBodyConnectHandler {
 
                     // TODO: Step 2, If body is null.
 
-                    // Step 3, get a reader for stream.
-                    rooted_stream.start_reading().expect("Couldn't acquire a reader for the body stream.");
+                    // Step 3, get a reader for stream,
+                    // unless it was locked to one when the body was extracted.
+                    if !rooted_stream.has_native_reader() {
+                        rooted_stream.start_reading().expect("Couldn't acquire a reader for the body stream.");
+                    }
 
                     // Note: this algorithm continues when the first chunk is requested by `net`.
                 }),
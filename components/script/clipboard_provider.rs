@@ -2,7 +2,7 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
-use embedder_traits::EmbedderMsg;
+use embedder_traits::{ClipboardData, EmbedderMsg};
 use ipc_channel::ipc::channel;
 use script_traits::{ScriptMsg, ScriptToConstellationChan};
 
@@ -15,17 +15,26 @@ pub trait ClipboardProvider {
 
 impl ClipboardProvider for ScriptToConstellationChan {
     fn clipboard_contents(&mut self) -> String {
-        let (tx, rx) = channel().unwrap();
-        self.send(ScriptMsg::ForwardToEmbedder(
-            EmbedderMsg::GetClipboardContents(tx),
-        ))
-        .unwrap();
-        rx.recv().unwrap()
+        clipboard_data(self).text().unwrap_or_default()
     }
     fn set_clipboard_contents(&mut self, s: String) {
-        self.send(ScriptMsg::ForwardToEmbedder(
-            EmbedderMsg::SetClipboardContents(s),
-        ))
-        .unwrap();
+        set_clipboard_data(self, ClipboardData::from_text(s));
     }
 }
+
+/// Blocking function to get the clipboard contents, in all of their representations.
+pub fn clipboard_data(chan: &ScriptToConstellationChan) -> ClipboardData {
+    let (tx, rx) = channel().unwrap();
+    chan.send(ScriptMsg::ForwardToEmbedder(
+        EmbedderMsg::GetClipboardContents(tx),
+    ))
+    .unwrap();
+    rx.recv().unwrap()
+}
+
+pub fn set_clipboard_data(chan: &ScriptToConstellationChan, data: ClipboardData) {
+    chan.send(ScriptMsg::ForwardToEmbedder(
+        EmbedderMsg::SetClipboardContents(data),
+    ))
+    .unwrap();
+}
@@ -133,7 +133,7 @@ impl DocumentLoader {
             .sender()
             .send(CoreResourceMsg::Fetch(
                 request,
-                FetchChannels::ResponseMsg(fetch_target, Some(cancel_receiver)),
+                FetchChannels::ResponseMsg(fetch_target, Some(cancel_receiver), None),
             ))
             .unwrap();
     }
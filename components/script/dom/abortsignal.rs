@@ -0,0 +1,329 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::AbortSignalBinding::AbortSignalMethods;
+use crate::dom::bindings::codegen::Bindings::EventListenerBinding::EventListener;
+use crate::dom::bindings::codegen::Bindings::EventTargetBinding::EventListenerOptions;
+use crate::dom::bindings::error::{Error, ErrorResult};
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::refcounted::Trusted;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject};
+use crate::dom::bindings::root::{Dom, DomRoot};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::eventtarget::EventTarget;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::lockmanager::LockManager;
+use crate::dom::readablestream::PipeTo;
+use crate::dom::scheduler::Scheduler;
+use crate::fetch::FetchAbortAlgorithm;
+use crate::realms::enter_realm;
+use crate::script_runtime::JSContext;
+use crate::timers::OneshotTimerCallback;
+use dom_struct::dom_struct;
+use euclid::Length;
+use js::jsapi::{ExceptionStackBehavior, Heap};
+use js::jsval::{JSVal, UndefinedValue};
+use js::rust::wrappers::JS_SetPendingException;
+use js::rust::HandleValue;
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// An algorithm to run when a signal is aborted.
+/// <https://dom.spec.whatwg.org/#abortsignal-abort-algorithms>
+#[derive(JSTraceable, MallocSizeOf)]
+#[unrooted_must_root_lint::must_root]
+pub enum AbortAlgorithm {
+    /// Removes an event listener that was added with the signal.
+    /// <https://dom.spec.whatwg.org/#add-an-event-listener>
+    RemoveEventListener {
+        target: Dom<EventTarget>,
+        ty: DOMString,
+        #[ignore_malloc_size_of = "Rc"]
+        listener: Rc<EventListener>,
+        capture: bool,
+    },
+    /// Aborts a call to `fetch()`.
+    Fetch(FetchAbortAlgorithm),
+    /// Aborts a pipe from a readable stream to a writable stream.
+    /// <https://streams.spec.whatwg.org/#readable-stream-pipe-to>
+    StreamPipe(#[ignore_malloc_size_of = "Rc"] Rc<PipeTo>),
+    /// Aborts a pending lock request.
+    /// <https://w3c.github.io/web-locks/#abort-the-request>
+    LockRequest { manager: Dom<LockManager>, id: u64 },
+    /// Aborts a task posted with `scheduler.postTask()`.
+    /// <https://wicg.github.io/scheduling-apis/#dom-scheduler-posttask>
+    PostedTask { scheduler: Dom<Scheduler>, id: u64 },
+}
+
+impl AbortAlgorithm {
+    #[allow(unrooted_must_root)]
+    fn run(self, signal: &AbortSignal) {
+        match self {
+            AbortAlgorithm::RemoveEventListener {
+                target,
+                ty,
+                listener,
+                capture,
+            } => target.remove_event_listener(ty, Some(listener), EventListenerOptions { capture }),
+            AbortAlgorithm::Fetch(algorithm) => algorithm.abort(signal),
+            AbortAlgorithm::StreamPipe(pipe) => pipe.abort(signal),
+            AbortAlgorithm::LockRequest { manager, id } => manager.abort_request(id, signal),
+            AbortAlgorithm::PostedTask { scheduler, id } => scheduler.abort_task(id, signal),
+        }
+    }
+}
+
+// https://dom.spec.whatwg.org/#interface-AbortSignal
+#[dom_struct]
+pub struct AbortSignal {
+    eventtarget: EventTarget,
+    /// <https://dom.spec.whatwg.org/#abortsignal-abort-reason>
+    #[ignore_malloc_size_of = "Defined in rust-mozjs"]
+    reason: Heap<JSVal>,
+    /// <https://dom.spec.whatwg.org/#abortsignal-abort-algorithms>
+    abort_algorithms: DomRefCell<Vec<AbortAlgorithm>>,
+    /// <https://dom.spec.whatwg.org/#abortsignal-source-signals>
+    source_signals: DomRefCell<Vec<Dom<AbortSignal>>>,
+    /// <https://dom.spec.whatwg.org/#abortsignal-dependent-signals>
+    dependent_signals: DomRefCell<Vec<Dom<AbortSignal>>>,
+    /// <https://dom.spec.whatwg.org/#abortsignal-dependent>
+    dependent: Cell<bool>,
+}
+
+impl AbortSignal {
+    pub fn new_inherited() -> AbortSignal {
+        AbortSignal {
+            eventtarget: EventTarget::new_inherited(),
+            reason: Heap::default(),
+            abort_algorithms: Default::default(),
+            source_signals: Default::default(),
+            dependent_signals: Default::default(),
+            dependent: Default::default(),
+        }
+    }
+
+    pub fn new(global: &GlobalScope) -> DomRoot<AbortSignal> {
+        reflect_dom_object(Box::new(AbortSignal::new_inherited()), global)
+    }
+
+    /// <https://dom.spec.whatwg.org/#abortsignal-aborted>
+    pub fn aborted(&self) -> bool {
+        !self.reason.get().is_undefined()
+    }
+
+    /// <https://dom.spec.whatwg.org/#abortsignal-add>
+    #[allow(unrooted_must_root)]
+    pub fn add_abort_algorithm(&self, algorithm: AbortAlgorithm) {
+        if self.aborted() {
+            return;
+        }
+        self.abort_algorithms.borrow_mut().push(algorithm);
+    }
+
+    /// Removes the abort algorithm of a pipe that was finalized.
+    /// <https://dom.spec.whatwg.org/#abortsignal-remove>
+    pub fn remove_stream_pipe(&self, pipe: &Rc<PipeTo>) {
+        self.abort_algorithms
+            .borrow_mut()
+            .retain(|algorithm| match *algorithm {
+                AbortAlgorithm::StreamPipe(ref other) => !Rc::ptr_eq(other, pipe),
+                _ => true,
+            });
+    }
+
+    /// Sets the reason of the signal, or an "AbortError" `DOMException` if it is undefined.
+    #[allow(unsafe_code)]
+    fn set_reason(&self, reason: HandleValue) {
+        if !reason.is_undefined() {
+            return self.reason.set(reason.get());
+        }
+        let global = self.global();
+        let _ac = enter_realm(&*global);
+        let cx = global.get_cx();
+        rooted!(in(*cx) let mut error = UndefinedValue());
+        unsafe {
+            Error::Abort.to_jsval(*cx, &global, error.handle_mut());
+        }
+        self.reason.set(error.get());
+    }
+
+    /// <https://dom.spec.whatwg.org/#abortsignal-signal-abort>
+    pub fn signal_abort(&self, reason: HandleValue) {
+        // Step 1.
+        if self.aborted() {
+            return;
+        }
+        // Step 2.
+        self.set_reason(reason);
+        // Steps 3-4.
+        let cx = self.global().get_cx();
+        rooted!(in(*cx) let reason = self.reason.get());
+        let dependent_signals: Vec<_> = self
+            .dependent_signals
+            .borrow()
+            .iter()
+            .filter(|signal| !signal.aborted())
+            .map(|signal| DomRoot::from_ref(&**signal))
+            .collect();
+        for signal in &dependent_signals {
+            signal.set_reason(reason.handle());
+        }
+        // Step 5.
+        self.run_abort_steps();
+        // Step 6.
+        for signal in dependent_signals {
+            signal.run_abort_steps();
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#run-the-abort-steps>
+    #[allow(unrooted_must_root)]
+    fn run_abort_steps(&self) {
+        // Steps 1-2.
+        let algorithms = std::mem::replace(&mut *self.abort_algorithms.borrow_mut(), vec![]);
+        for algorithm in algorithms {
+            algorithm.run(self);
+        }
+        // Step 3.
+        self.upcast::<EventTarget>().fire_event(atom!("abort"));
+    }
+
+    /// Throws the reason of the signal if it is aborted.
+    /// <https://dom.spec.whatwg.org/#dom-abortsignal-throwifaborted>
+    #[allow(unsafe_code)]
+    pub fn throw_if_aborted(&self) -> ErrorResult {
+        if !self.aborted() {
+            return Ok(());
+        }
+        let cx = self.global().get_cx();
+        rooted!(in(*cx) let reason = self.reason.get());
+        unsafe {
+            JS_SetPendingException(*cx, reason.handle(), ExceptionStackBehavior::Capture);
+        }
+        Err(Error::JSFailed)
+    }
+
+    /// The reason of the signal, undefined if it isn't aborted.
+    pub fn reason(&self) -> JSVal {
+        self.reason.get()
+    }
+
+    /// <https://dom.spec.whatwg.org/#create-a-dependent-abort-signal>
+    pub fn new_dependent(
+        global: &GlobalScope,
+        signals: &[DomRoot<AbortSignal>],
+    ) -> DomRoot<AbortSignal> {
+        // Step 1.
+        let result = AbortSignal::new(global);
+        // Step 2.
+        if let Some(aborted) = signals.iter().find(|signal| signal.aborted()) {
+            let cx = global.get_cx();
+            rooted!(in(*cx) let reason = aborted.reason.get());
+            result.set_reason(reason.handle());
+            return result;
+        }
+        // Step 3.
+        result.dependent.set(true);
+        // Step 4.
+        for signal in signals {
+            if !signal.dependent.get() {
+                result.add_source_signal(signal);
+                continue;
+            }
+            for source in signal.source_signals.borrow().iter() {
+                result.add_source_signal(source);
+            }
+        }
+        // Step 5.
+        result
+    }
+
+    fn add_source_signal(&self, source: &AbortSignal) {
+        if self
+            .source_signals
+            .borrow()
+            .iter()
+            .any(|signal| &**signal == source)
+        {
+            return;
+        }
+        self.source_signals.borrow_mut().push(Dom::from_ref(source));
+        source
+            .dependent_signals
+            .borrow_mut()
+            .push(Dom::from_ref(self));
+    }
+}
+
+impl AbortSignalMethods for AbortSignal {
+    // https://dom.spec.whatwg.org/#dom-abortsignal-aborted
+    fn Aborted(&self) -> bool {
+        self.aborted()
+    }
+
+    // https://dom.spec.whatwg.org/#dom-abortsignal-reason
+    fn Reason(&self, _cx: JSContext) -> JSVal {
+        self.reason()
+    }
+
+    // https://dom.spec.whatwg.org/#dom-abortsignal-throwifaborted
+    fn ThrowIfAborted(&self) -> ErrorResult {
+        self.throw_if_aborted()
+    }
+
+    // https://dom.spec.whatwg.org/#abortsignal-onabort
+    event_handler!(abort, GetOnabort, SetOnabort);
+}
+
+#[allow(non_snake_case)]
+impl AbortSignal {
+    // https://dom.spec.whatwg.org/#dom-abortsignal-abort
+    pub fn Abort(
+        _cx: JSContext,
+        global: &GlobalScope,
+        reason: HandleValue,
+    ) -> DomRoot<AbortSignal> {
+        let signal = AbortSignal::new(global);
+        signal.set_reason(reason);
+        signal
+    }
+
+    // https://dom.spec.whatwg.org/#dom-abortsignal-timeout
+    pub fn Timeout(global: &GlobalScope, milliseconds: u64) -> DomRoot<AbortSignal> {
+        let signal = AbortSignal::new(global);
+        let callback = OneshotTimerCallback::AbortSignalTimeout(AbortSignalTimeoutCallback {
+            signal: Trusted::new(&*signal),
+        });
+        global.schedule_callback(callback, Length::new(milliseconds));
+        signal
+    }
+
+    // https://dom.spec.whatwg.org/#dom-abortsignal-any
+    pub fn Any(global: &GlobalScope, signals: Vec<DomRoot<AbortSignal>>) -> DomRoot<AbortSignal> {
+        AbortSignal::new_dependent(global, &signals)
+    }
+}
+
+/// Aborts the signal of `AbortSignal.timeout()` with a "TimeoutError" `DOMException`.
+#[derive(JSTraceable, MallocSizeOf)]
+pub struct AbortSignalTimeoutCallback {
+    #[ignore_malloc_size_of = "Because it is non-owning"]
+    signal: Trusted<AbortSignal>,
+}
+
+impl AbortSignalTimeoutCallback {
+    #[allow(unsafe_code)]
+    pub fn invoke(self) {
+        let signal = self.signal.root();
+        let global = signal.global();
+        let _ac = enter_realm(&*global);
+        let cx = global.get_cx();
+        rooted!(in(*cx) let mut error = UndefinedValue());
+        unsafe {
+            Error::Timeout.to_jsval(*cx, &global, error.handle_mut());
+        }
+        signal.signal_abort(error.handle());
+    }
+}
@@ -2860,6 +2860,7 @@ class CGWrapGlobalMethod(CGAbstractMethod):
         values["members"] = "\n".join(members)
 
         return CGGeneric("""\
+let cross_origin_isolated = object.upcast::<GlobalScope>().cross_origin_isolated();
 let raw = Root::new(MaybeUnreflectedDom::from_box(object));
 
 rooted!(in(*cx) let mut obj = ptr::null_mut::<JSObject>());
@@ -2868,6 +2869,7 @@ create_global_object(
     &Class.base,
     raw.as_ptr() as *const %(concreteType)s as *const libc::c_void,
     _trace,
+    cross_origin_isolated,
     obj.handle_mut());
 assert!(!obj.is_null());
 
@@ -6191,6 +6193,7 @@ def generate_imports(config, cgthings, descriptors, callbacks=None, dictionaries
         'crate::dom::bindings::callback::CallbackObject',
         'crate::dom::bindings::callback::ExceptionHandling',
         'crate::dom::bindings::callback::wrap_call_this_object',
+        'crate::dom::bindings::callback::ThisReflector',
         'crate::dom::bindings::conversions::ConversionBehavior',
         'crate::dom::bindings::conversions::ConversionResult',
         'crate::dom::bindings::conversions::DOM_OBJECT_SLOT',
@@ -7128,7 +7131,7 @@ class CGCallback(CGClass):
               })
         return [ClassMethod(method.name + '_', method.returnType, args,
                             bodyInHeader=True,
-                            templateArgs=["T: DomObject"],
+                            templateArgs=["T: ThisReflector"],
                             body=bodyWithThis,
                             visibility='pub'),
                 ClassMethod(method.name + '__', method.returnType, argsWithoutThis,
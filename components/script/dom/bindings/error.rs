@@ -14,7 +14,7 @@ use crate::dom::bindings::conversions::{
 use crate::dom::bindings::str::USVString;
 use crate::dom::domexception::{DOMErrorName, DOMException};
 use crate::dom::globalscope::GlobalScope;
-use crate::realms::InRealm;
+use crate::realms::{enter_realm, InRealm};
 use crate::script_runtime::JSContext as SafeJSContext;
 #[cfg(feature = "js_backtrace")]
 use backtrace::Backtrace;
@@ -90,6 +90,18 @@ pub enum Error {
     NotReadable,
     /// OperationError DOMException
     Operation,
+    /// DataError DOMException
+    Data,
+    /// ConstraintError DOMException
+    Constraint,
+    /// ReadOnlyError DOMException
+    ReadOnly,
+    /// TransactionInactiveError DOMException
+    TransactionInactive,
+    /// VersionError DOMException
+    Version,
+    /// NotAllowedError DOMException
+    NotAllowed,
 
     /// TypeError JavaScript Error
     Type(String),
@@ -143,6 +155,12 @@ pub fn throw_dom_exception(cx: SafeJSContext, global: &GlobalScope, result: Erro
         Error::InvalidModification => DOMErrorName::InvalidModificationError,
         Error::NotReadable => DOMErrorName::NotReadableError,
         Error::Operation => DOMErrorName::OperationError,
+        Error::Data => DOMErrorName::DataError,
+        Error::Constraint => DOMErrorName::ConstraintError,
+        Error::ReadOnly => DOMErrorName::ReadOnlyError,
+        Error::TransactionInactive => DOMErrorName::TransactionInactiveError,
+        Error::Version => DOMErrorName::VersionError,
+        Error::NotAllowed => DOMErrorName::NotAllowedError,
         Error::Type(message) => unsafe {
             assert!(!JS_IsExceptionPending(*cx));
             throw_type_error(*cx, &message);
@@ -322,3 +340,18 @@ impl Error {
         JS_ClearPendingException(cx);
     }
 }
+
+/// Takes the exception of a failed callback call, or converts `error` to a JS value.
+pub fn take_exception(global: &GlobalScope, error: Error, rval: MutableHandleValue) {
+    let cx = global.get_cx();
+    let _ac = enter_realm(global);
+    unsafe {
+        if let Error::JSFailed = error {
+            if JS_GetPendingException(*cx, rval) {
+                JS_ClearPendingException(*cx);
+                return;
+            }
+        }
+        error.to_jsval(*cx, global, rval);
+    }
+}
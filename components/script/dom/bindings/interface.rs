@@ -10,6 +10,7 @@ use crate::dom::bindings::constant::{define_constants, ConstantSpec};
 use crate::dom::bindings::conversions::{get_dom_class, DOM_OBJECT_SLOT};
 use crate::dom::bindings::guard::Guard;
 use crate::dom::bindings::utils::{ProtoOrIfaceArray, DOM_PROTOTYPE_SLOT};
+use crate::dom::readablestream::define_pipe_methods;
 use crate::script_runtime::JSContext as SafeJSContext;
 use js::error::throw_type_error;
 use js::glue::UncheckedUnwrapObject;
@@ -32,6 +33,7 @@ use js::rust::wrappers::{JS_DefineProperty3, JS_DefineProperty4, JS_DefineProper
 use js::rust::wrappers::{JS_LinkConstructorAndPrototype, JS_NewObjectWithUniqueType};
 use js::rust::{define_methods, define_properties, get_object_class};
 use js::rust::{HandleObject, HandleValue, MutableHandleObject, RealmOptions};
+use servo_config::pref;
 use std::convert::TryFrom;
 use std::ptr;
 
@@ -131,13 +133,15 @@ pub unsafe fn create_global_object(
     class: &'static JSClass,
     private: *const libc::c_void,
     trace: TraceHook,
+    cross_origin_isolated: bool,
     mut rval: MutableHandleObject,
 ) {
     assert!(rval.is_null());
 
     let mut options = RealmOptions::default();
     options.creationOptions_.traceGlobal_ = Some(trace);
-    options.creationOptions_.sharedMemoryAndAtomics_ = false;
+    options.creationOptions_.sharedMemoryAndAtomics_ =
+        cross_origin_isolated && pref!(js.shared_memory.enabled);
     options.creationOptions_.streams_ = true;
 
     rval.set(JS_NewGlobalObject(
@@ -160,6 +164,7 @@ pub unsafe fn create_global_object(
 
     let _ac = JSAutoRealm::new(*cx, rval.get());
     JS_FireOnNewGlobalObject(*cx, rval.handle());
+    define_pipe_methods(cx, rval.handle());
 }
 
 /// Create and define the interface object of a callback interface.
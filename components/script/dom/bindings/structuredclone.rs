@@ -5,15 +5,30 @@
 //! This module implements structured cloning, as defined by [HTML]
 //! (https://html.spec.whatwg.org/multipage/#safe-passing-of-structured-data).
 
+use crate::dom::bindings::codegen::Bindings::DOMPointReadOnlyBinding::DOMPointReadOnlyMethods;
+use crate::dom::bindings::codegen::Bindings::FileBinding::FileMethods;
+use crate::dom::bindings::codegen::Bindings::ImageBitmapBinding::ImageBitmapMethods;
+use crate::dom::bindings::codegen::Bindings::ImageDataBinding::ImageDataMethods;
 use crate::dom::bindings::conversions::{root_from_object, ToJSValConvertible};
 use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::inheritance::Castable;
 use crate::dom::bindings::reflector::DomObject;
 use crate::dom::bindings::root::DomRoot;
 use crate::dom::bindings::serializable::{Serializable, StorageKey};
 use crate::dom::bindings::transferable::Transferable;
 use crate::dom::blob::Blob;
+use crate::dom::cryptokey::CryptoKey;
+use crate::dom::dommatrix::DOMMatrix;
+use crate::dom::dommatrixreadonly::{entries_to_matrix, DOMMatrixReadOnly};
+use crate::dom::dompoint::DOMPoint;
+use crate::dom::dompointreadonly::DOMPointReadOnly;
+use crate::dom::file::File;
+use crate::dom::filelist::FileList;
 use crate::dom::globalscope::GlobalScope;
+use crate::dom::imagebitmap::ImageBitmap;
+use crate::dom::imagedata::ImageData;
 use crate::dom::messageport::MessagePort;
+use crate::dom::offscreencanvas::OffscreenCanvas;
 use crate::realms::{enter_realm, AlreadyInRealm, InRealm};
 use crate::script_runtime::JSContext as SafeJSContext;
 use js::glue::CopyJSStructuredCloneData;
@@ -30,13 +45,13 @@ use js::jsapi::TransferableOwnership;
 use js::jsapi::JS_STRUCTURED_CLONE_VERSION;
 use js::jsapi::{JSObject, JS_ClearPendingException};
 use js::jsapi::{JSStructuredCloneCallbacks, JSStructuredCloneReader, JSStructuredCloneWriter};
-use js::jsapi::{JS_ReadUint32Pair, JS_WriteUint32Pair};
+use js::jsapi::{JS_ReadBytes, JS_ReadUint32Pair, JS_WriteBytes, JS_WriteUint32Pair};
 use js::jsval::UndefinedValue;
 use js::rust::wrappers::{JS_ReadStructuredClone, JS_WriteStructuredClone};
 use js::rust::{CustomAutoRooterGuard, HandleValue, MutableHandleValue};
 use msg::constellation_msg::{BlobId, MessagePortId};
-use script_traits::serializable::BlobImpl;
-use script_traits::transferable::MessagePortImpl;
+use script_traits::serializable::{BlobImpl, CryptoKeyImpl};
+use script_traits::transferable::{ImageBitmapImpl, MessagePortImpl};
 use script_traits::StructuredSerializedData;
 use std::collections::HashMap;
 use std::os::raw;
@@ -52,6 +67,16 @@ enum StructuredCloneTags {
     Min = 0xFFFF8000,
     DomBlob = 0xFFFF8001,
     MessagePort = 0xFFFF8002,
+    DomFile = 0xFFFF8003,
+    DomFileList = 0xFFFF8004,
+    ImageData = 0xFFFF8005,
+    ImageBitmap = 0xFFFF8006,
+    DomPoint = 0xFFFF8007,
+    DomPointReadOnly = 0xFFFF8008,
+    DomMatrix = 0xFFFF8009,
+    DomMatrixReadOnly = 0xFFFF800A,
+    OffscreenCanvas = 0xFFFF800B,
+    CryptoKey = 0xFFFF800C,
     Max = 0xFFFFFFFF,
 }
 
@@ -113,11 +138,236 @@ unsafe fn write_blob(
     return false;
 }
 
+unsafe fn read_crypto_key(
+    owner: &GlobalScope,
+    r: *mut JSStructuredCloneReader,
+    sc_holder: &mut StructuredDataHolder,
+) -> Option<*mut JSObject> {
+    let mut name_space: u32 = 0;
+    let mut index: u32 = 0;
+    if !JS_ReadUint32Pair(r, &mut name_space as *mut u32, &mut index as *mut u32) {
+        return None;
+    }
+    let storage_key = StorageKey { index, name_space };
+    <CryptoKey as Serializable>::deserialize(owner, sc_holder, storage_key).ok()?;
+    let crypto_keys = match sc_holder {
+        StructuredDataHolder::Read { crypto_keys, .. } => crypto_keys,
+        _ => panic!("Unexpected variant of StructuredDataHolder"),
+    };
+    let crypto_key = crypto_keys.as_ref()?.get(&storage_key)?;
+    Some(crypto_key.reflector().get_jsobject().get())
+}
+
+/// <https://w3c.github.io/webcrypto/#cryptokey-interface-clone>
+unsafe fn write_crypto_key(
+    crypto_key: &CryptoKey,
+    w: *mut JSStructuredCloneWriter,
+    sc_holder: &mut StructuredDataHolder,
+) -> bool {
+    let storage_key = match crypto_key.serialize(sc_holder) {
+        Ok(storage_key) => storage_key,
+        Err(()) => return false,
+    };
+    JS_WriteUint32Pair(w, StructuredCloneTags::CryptoKey as u32, 0) &&
+        JS_WriteUint32Pair(w, storage_key.name_space, storage_key.index)
+}
+
+unsafe fn write_u64(w: *mut JSStructuredCloneWriter, value: u64) -> bool {
+    JS_WriteUint32Pair(w, (value >> 32) as u32, value as u32)
+}
+
+unsafe fn read_u64(r: *mut JSStructuredCloneReader) -> Option<u64> {
+    let mut high: u32 = 0;
+    let mut low: u32 = 0;
+    if !JS_ReadUint32Pair(r, &mut high as *mut u32, &mut low as *mut u32) {
+        return None;
+    }
+    Some((high as u64) << 32 | low as u64)
+}
+
+unsafe fn write_doubles(w: *mut JSStructuredCloneWriter, values: &[f64]) -> bool {
+    values.iter().all(|value| write_u64(w, value.to_bits()))
+}
+
+unsafe fn read_doubles(r: *mut JSStructuredCloneReader, count: usize) -> Option<Vec<f64>> {
+    (0..count)
+        .map(|_| read_u64(r).map(f64::from_bits))
+        .collect()
+}
+
+/// Writes the length of `bytes`, followed by the bytes themselves.
+unsafe fn write_bytes(w: *mut JSStructuredCloneWriter, bytes: &[u8]) -> bool {
+    write_u64(w, bytes.len() as u64) &&
+        JS_WriteBytes(w, bytes.as_ptr() as *const raw::c_void, bytes.len())
+}
+
+unsafe fn read_bytes(r: *mut JSStructuredCloneReader) -> Option<Vec<u8>> {
+    let len = read_u64(r)? as usize;
+    let mut bytes = vec![0; len];
+    if !JS_ReadBytes(r, bytes.as_mut_ptr() as *mut raw::c_void, len) {
+        return None;
+    }
+    Some(bytes)
+}
+
+/// Writes the blob of a file, and its name and last modification date.
+unsafe fn write_file_data(
+    file: &File,
+    w: *mut JSStructuredCloneWriter,
+    sc_holder: &mut StructuredDataHolder,
+) -> bool {
+    let storage_key = match file.upcast::<Blob>().serialize(sc_holder) {
+        Ok(storage_key) => storage_key,
+        Err(()) => return false,
+    };
+    JS_WriteUint32Pair(w, storage_key.name_space, storage_key.index) &&
+        write_bytes(w, file.name().as_bytes()) &&
+        write_u64(w, file.LastModified() as u64)
+}
+
+unsafe fn read_file_data(
+    owner: &GlobalScope,
+    r: *mut JSStructuredCloneReader,
+    sc_holder: &mut StructuredDataHolder,
+) -> Option<DomRoot<File>> {
+    let mut name_space: u32 = 0;
+    let mut index: u32 = 0;
+    if !JS_ReadUint32Pair(r, &mut name_space as *mut u32, &mut index as *mut u32) {
+        return None;
+    }
+    let blob_impl = Blob::take_serialized_impl(sc_holder, StorageKey { index, name_space });
+    let name = String::from_utf8(read_bytes(r)?).ok()?;
+    let modified = read_u64(r)? as i64;
+    Some(File::new(owner, blob_impl, name.into(), Some(modified)))
+}
+
+/// <https://w3c.github.io/FileAPI/#file-section>
+unsafe fn write_file(
+    file: &File,
+    w: *mut JSStructuredCloneWriter,
+    sc_holder: &mut StructuredDataHolder,
+) -> bool {
+    JS_WriteUint32Pair(w, StructuredCloneTags::DomFile as u32, 0) &&
+        write_file_data(file, w, sc_holder)
+}
+
+/// <https://w3c.github.io/FileAPI/#filelist-section>
+unsafe fn write_file_list(
+    file_list: &FileList,
+    w: *mut JSStructuredCloneWriter,
+    sc_holder: &mut StructuredDataHolder,
+) -> bool {
+    let mut files = file_list.iter_files();
+    JS_WriteUint32Pair(
+        w,
+        StructuredCloneTags::DomFileList as u32,
+        files.len() as u32,
+    ) && files.all(|file| write_file_data(file, w, sc_holder))
+}
+
+unsafe fn read_file_list(
+    owner: &GlobalScope,
+    r: *mut JSStructuredCloneReader,
+    length: u32,
+    sc_holder: &mut StructuredDataHolder,
+) -> Option<DomRoot<FileList>> {
+    let files = (0..length)
+        .map(|_| read_file_data(owner, r, sc_holder))
+        .collect::<Option<Vec<_>>>()?;
+    Some(FileList::new(owner, files))
+}
+
+/// <https://html.spec.whatwg.org/multipage/#the-imagedata-interface:serialization-steps>
+unsafe fn write_image_data(image_data: &ImageData, w: *mut JSStructuredCloneWriter) -> bool {
+    JS_WriteUint32Pair(w, StructuredCloneTags::ImageData as u32, 0) &&
+        JS_WriteUint32Pair(w, image_data.Width(), image_data.Height()) &&
+        write_bytes(w, image_data.as_slice())
+}
+
+unsafe fn read_image_data(
+    owner: &GlobalScope,
+    r: *mut JSStructuredCloneReader,
+) -> Option<DomRoot<ImageData>> {
+    let mut width: u32 = 0;
+    let mut height: u32 = 0;
+    if !JS_ReadUint32Pair(r, &mut width as *mut u32, &mut height as *mut u32) {
+        return None;
+    }
+    let data = read_bytes(r)?;
+    ImageData::new(owner, width, height, Some(data)).ok()
+}
+
+/// <https://html.spec.whatwg.org/multipage/#the-imagebitmap-interface:serialization-steps>
+unsafe fn write_image_bitmap(image_bitmap: &ImageBitmap, w: *mut JSStructuredCloneWriter) -> bool {
+    // Step 1.
+    if !image_bitmap.origin_is_clean() || image_bitmap.is_detached() {
+        return false;
+    }
+    // Step 2.
+    JS_WriteUint32Pair(w, StructuredCloneTags::ImageBitmap as u32, 0) &&
+        JS_WriteUint32Pair(w, image_bitmap.Width(), image_bitmap.Height()) &&
+        write_bytes(w, &image_bitmap.bitmap_data())
+}
+
+unsafe fn read_image_bitmap(
+    owner: &GlobalScope,
+    r: *mut JSStructuredCloneReader,
+) -> Option<DomRoot<ImageBitmap>> {
+    let mut width: u32 = 0;
+    let mut height: u32 = 0;
+    if !JS_ReadUint32Pair(r, &mut width as *mut u32, &mut height as *mut u32) {
+        return None;
+    }
+    let data = read_bytes(r)?;
+    let image_bitmap = ImageBitmap::new(owner, width, height).ok()?;
+    image_bitmap.set_bitmap_data(data);
+    Some(image_bitmap)
+}
+
+/// <https://drafts.fxtf.org/geometry-1/#structured-serialization>
+unsafe fn write_point(point: &DOMPointReadOnly, w: *mut JSStructuredCloneWriter) -> bool {
+    let tag = if point.is::<DOMPoint>() {
+        StructuredCloneTags::DomPoint
+    } else {
+        StructuredCloneTags::DomPointReadOnly
+    };
+    JS_WriteUint32Pair(w, tag as u32, 0) &&
+        write_doubles(w, &[point.X(), point.Y(), point.Z(), point.W()])
+}
+
+/// <https://drafts.fxtf.org/geometry-1/#structured-serialization>
+unsafe fn write_matrix(matrix: &DOMMatrixReadOnly, w: *mut JSStructuredCloneWriter) -> bool {
+    let tag = if matrix.is::<DOMMatrix>() {
+        StructuredCloneTags::DomMatrix
+    } else {
+        StructuredCloneTags::DomMatrixReadOnly
+    };
+    JS_WriteUint32Pair(w, tag as u32, matrix.is2D() as u32) &&
+        write_doubles(w, &matrix.matrix().to_row_major_array())
+}
+
+unsafe fn read_matrix(
+    owner: &GlobalScope,
+    r: *mut JSStructuredCloneReader,
+    tag: u32,
+    is_2d: bool,
+) -> Option<*mut JSObject> {
+    let entries = read_doubles(r, 16)?;
+    let (_, matrix) = entries_to_matrix(&entries).ok()?;
+    if tag == StructuredCloneTags::DomMatrix as u32 {
+        let matrix = DOMMatrix::new(owner, is_2d, matrix);
+        Some(matrix.reflector().get_jsobject().get())
+    } else {
+        let matrix = DOMMatrixReadOnly::new(owner, is_2d, matrix);
+        Some(matrix.reflector().get_jsobject().get())
+    }
+}
+
 unsafe extern "C" fn read_callback(
     cx: *mut JSContext,
     r: *mut JSStructuredCloneReader,
     tag: u32,
-    _data: u32,
+    data: u32,
     closure: *mut raw::c_void,
 ) -> *mut JSObject {
     assert!(
@@ -128,15 +378,49 @@ unsafe extern "C" fn read_callback(
         tag > StructuredCloneTags::Min as u32,
         "tag should be higher than StructuredCloneTags::Min"
     );
+    let in_realm_proof = AlreadyInRealm::assert_for_cx(SafeJSContext::from_ptr(cx));
+    let owner = GlobalScope::from_context(cx, InRealm::Already(&in_realm_proof));
+    let sc_holder = &mut *(closure as *mut StructuredDataHolder);
     if tag == StructuredCloneTags::DomBlob as u32 {
-        let in_realm_proof = AlreadyInRealm::assert_for_cx(SafeJSContext::from_ptr(cx));
-        return read_blob(
-            &GlobalScope::from_context(cx, InRealm::Already(&in_realm_proof)),
-            r,
-            &mut *(closure as *mut StructuredDataHolder),
-        );
+        return read_blob(&owner, r, sc_holder);
     }
-    return ptr::null_mut();
+    let object = if tag == StructuredCloneTags::DomFile as u32 {
+        read_file_data(&owner, r, sc_holder).map(|file| file.reflector().get_jsobject().get())
+    } else if tag == StructuredCloneTags::DomFileList as u32 {
+        read_file_list(&owner, r, data, sc_holder)
+            .map(|file_list| file_list.reflector().get_jsobject().get())
+    } else if tag == StructuredCloneTags::ImageData as u32 {
+        read_image_data(&owner, r).map(|image_data| image_data.reflector().get_jsobject().get())
+    } else if tag == StructuredCloneTags::ImageBitmap as u32 {
+        read_image_bitmap(&owner, r)
+            .map(|image_bitmap| image_bitmap.reflector().get_jsobject().get())
+    } else if tag == StructuredCloneTags::DomPoint as u32 {
+        read_doubles(r, 4).map(|p| {
+            let point = DOMPoint::new(&owner, p[0], p[1], p[2], p[3]);
+            point.reflector().get_jsobject().get()
+        })
+    } else if tag == StructuredCloneTags::DomPointReadOnly as u32 {
+        read_doubles(r, 4).map(|p| {
+            let point = DOMPointReadOnly::new(&owner, p[0], p[1], p[2], p[3]);
+            point.reflector().get_jsobject().get()
+        })
+    } else if tag == StructuredCloneTags::DomMatrix as u32 ||
+        tag == StructuredCloneTags::DomMatrixReadOnly as u32
+    {
+        read_matrix(&owner, r, tag, data != 0)
+    } else if tag == StructuredCloneTags::CryptoKey as u32 {
+        read_crypto_key(&owner, r, sc_holder)
+    } else {
+        None
+    };
+    object.unwrap_or_else(|| {
+        warn!(
+            "Reading structured data with tag {:x} failed in {:?}.",
+            tag,
+            owner.get_url()
+        );
+        ptr::null_mut()
+    })
 }
 
 unsafe extern "C" fn write_callback(
@@ -145,15 +429,37 @@ unsafe extern "C" fn write_callback(
     obj: RawHandleObject,
     closure: *mut raw::c_void,
 ) -> bool {
+    let sc_holder = &mut *(closure as *mut StructuredDataHolder);
     if let Ok(blob) = root_from_object::<Blob>(*obj, cx) {
+        if let Some(file) = blob.downcast::<File>() {
+            return write_file(file, w, sc_holder);
+        }
         let in_realm_proof = AlreadyInRealm::assert_for_cx(SafeJSContext::from_ptr(cx));
         return write_blob(
             &GlobalScope::from_context(cx, InRealm::Already(&in_realm_proof)),
             blob,
             w,
-            &mut *(closure as *mut StructuredDataHolder),
+            sc_holder,
         );
     }
+    if let Ok(file_list) = root_from_object::<FileList>(*obj, cx) {
+        return write_file_list(&file_list, w, sc_holder);
+    }
+    if let Ok(image_data) = root_from_object::<ImageData>(*obj, cx) {
+        return write_image_data(&image_data, w);
+    }
+    if let Ok(image_bitmap) = root_from_object::<ImageBitmap>(*obj, cx) {
+        return write_image_bitmap(&image_bitmap, w);
+    }
+    if let Ok(point) = root_from_object::<DOMPointReadOnly>(*obj, cx) {
+        return write_point(&point, w);
+    }
+    if let Ok(matrix) = root_from_object::<DOMMatrixReadOnly>(*obj, cx) {
+        return write_matrix(&matrix, w);
+    }
+    if let Ok(crypto_key) = root_from_object::<CryptoKey>(*obj, cx) {
+        return write_crypto_key(&crypto_key, w, sc_holder);
+    }
     return false;
 }
 
@@ -166,20 +472,19 @@ unsafe extern "C" fn read_transfer_callback(
     closure: *mut raw::c_void,
     return_object: RawMutableHandleObject,
 ) -> bool {
-    if tag == StructuredCloneTags::MessagePort as u32 {
-        let mut sc_holder = &mut *(closure as *mut StructuredDataHolder);
-        let in_realm_proof = AlreadyInRealm::assert_for_cx(SafeJSContext::from_ptr(cx));
-        let owner = GlobalScope::from_context(cx, InRealm::Already(&in_realm_proof));
-        if let Ok(_) = <MessagePort as Transferable>::transfer_receive(
-            &owner,
-            &mut sc_holder,
-            extra_data,
-            return_object,
-        ) {
-            return true;
-        }
-    }
-    false
+    let transfer_receive = if tag == StructuredCloneTags::MessagePort as u32 {
+        <MessagePort as Transferable>::transfer_receive
+    } else if tag == StructuredCloneTags::ImageBitmap as u32 {
+        <ImageBitmap as Transferable>::transfer_receive
+    } else if tag == StructuredCloneTags::OffscreenCanvas as u32 {
+        <OffscreenCanvas as Transferable>::transfer_receive
+    } else {
+        return false;
+    };
+    let sc_holder = &mut *(closure as *mut StructuredDataHolder);
+    let in_realm_proof = AlreadyInRealm::assert_for_cx(SafeJSContext::from_ptr(cx));
+    let owner = GlobalScope::from_context(cx, InRealm::Already(&in_realm_proof));
+    transfer_receive(&owner, sc_holder, extra_data, return_object).is_ok()
 }
 
 /// <https://html.spec.whatwg.org/multipage/#structuredserializewithtransfer>
@@ -192,14 +497,23 @@ unsafe extern "C" fn write_transfer_callback(
     _content: *mut *mut raw::c_void,
     extra_data: *mut u64,
 ) -> bool {
-    if let Ok(port) = root_from_object::<MessagePort>(*obj, cx) {
+    let sc_holder = &mut *(closure as *mut StructuredDataHolder);
+    let transferred = if let Ok(port) = root_from_object::<MessagePort>(*obj, cx) {
         *tag = StructuredCloneTags::MessagePort as u32;
-        *ownership = TransferableOwnership::SCTAG_TMO_CUSTOM;
-        let mut sc_holder = &mut *(closure as *mut StructuredDataHolder);
-        if let Ok(data) = port.transfer(&mut sc_holder) {
-            *extra_data = data;
-            return true;
-        }
+        port.transfer(sc_holder)
+    } else if let Ok(image_bitmap) = root_from_object::<ImageBitmap>(*obj, cx) {
+        *tag = StructuredCloneTags::ImageBitmap as u32;
+        image_bitmap.transfer(sc_holder)
+    } else if let Ok(canvas) = root_from_object::<OffscreenCanvas>(*obj, cx) {
+        *tag = StructuredCloneTags::OffscreenCanvas as u32;
+        canvas.transfer(sc_holder)
+    } else {
+        return false;
+    };
+    *ownership = TransferableOwnership::SCTAG_TMO_CUSTOM;
+    if let Ok(data) = transferred {
+        *extra_data = data;
+        return true;
     }
     false
 }
@@ -221,6 +535,12 @@ unsafe extern "C" fn can_transfer_callback(
     if let Ok(_port) = root_from_object::<MessagePort>(*obj, cx) {
         return true;
     }
+    if let Ok(_image_bitmap) = root_from_object::<ImageBitmap>(*obj, cx) {
+        return true;
+    }
+    if let Ok(_canvas) = root_from_object::<OffscreenCanvas>(*obj, cx) {
+        return true;
+    }
     false
 }
 
@@ -253,6 +573,15 @@ pub enum StructuredDataHolder {
         /// used as part of the "deserialize" steps of blobs,
         /// to produce the DOM blobs stored in `blobs` above.
         blob_impls: Option<HashMap<BlobId, BlobImpl>>,
+        /// A map of deserialized crypto keys, stored temporarily here to keep them rooted.
+        crypto_keys: Option<HashMap<StorageKey, DomRoot<CryptoKey>>>,
+        /// A map of the internal slots of serialized crypto keys,
+        /// used as part of the "deserialize" steps of crypto keys,
+        /// to produce the DOM crypto keys stored in `crypto_keys` above.
+        crypto_key_impls: Option<HashMap<u32, CryptoKeyImpl>>,
+        /// A map of the bitmap data of transferred image bitmaps,
+        /// used as part of their "transfer-receiving" steps.
+        image_bitmaps: Option<HashMap<u64, ImageBitmapImpl>>,
     },
     /// A data holder for transferred and serialized objects.
     Write {
@@ -260,6 +589,10 @@ pub enum StructuredDataHolder {
         ports: Option<HashMap<MessagePortId, MessagePortImpl>>,
         /// Serialized blobs.
         blobs: Option<HashMap<BlobId, BlobImpl>>,
+        /// Serialized crypto keys.
+        crypto_keys: Option<HashMap<u32, CryptoKeyImpl>>,
+        /// The bitmap data of transferred image bitmaps.
+        image_bitmaps: Option<HashMap<u64, ImageBitmapImpl>>,
     },
 }
 
@@ -277,6 +610,8 @@ pub fn write(
         let mut sc_holder = StructuredDataHolder::Write {
             ports: None,
             blobs: None,
+            crypto_keys: None,
+            image_bitmaps: None,
         };
         let sc_holder_ptr = &mut sc_holder as *mut _;
 
@@ -311,8 +646,13 @@ pub fn write(
 
         DeleteJSAutoStructuredCloneBuffer(scbuf);
 
-        let (mut blob_impls, mut port_impls) = match sc_holder {
-            StructuredDataHolder::Write { blobs, ports } => (blobs, ports),
+        let (mut blob_impls, mut crypto_keys, mut port_impls, mut image_bitmaps) = match sc_holder {
+            StructuredDataHolder::Write {
+                blobs,
+                crypto_keys,
+                ports,
+                image_bitmaps,
+            } => (blobs, crypto_keys, ports, image_bitmaps),
             _ => panic!("Unexpected variant of StructuredDataHolder"),
         };
 
@@ -320,6 +660,8 @@ pub fn write(
             serialized: data,
             ports: port_impls.take(),
             blobs: blob_impls.take(),
+            crypto_keys: crypto_keys.take(),
+            image_bitmaps: image_bitmaps.take(),
         };
 
         Ok(data)
@@ -340,6 +682,9 @@ pub fn read(
         message_ports: None,
         port_impls: data.ports.take(),
         blob_impls: data.blobs.take(),
+        crypto_keys: None,
+        crypto_key_impls: data.crypto_keys.take(),
+        image_bitmaps: data.image_bitmaps.take(),
     };
     let sc_holder_ptr = &mut sc_holder as *mut _;
     unsafe {
@@ -66,7 +66,7 @@ use content_security_policy::CspList;
 use crossbeam_channel::{Receiver, Sender};
 use cssparser::RGBA;
 use devtools_traits::{CSSError, TimelineMarkerType, WorkerId};
-use embedder_traits::{EventLoopWaker, MediaMetadata};
+use embedder_traits::{ClipboardRepresentation, EventLoopWaker, MediaMetadata};
 use encoding_rs::{Decoder, Encoding};
 use euclid::default::{Point2D, Rect, Rotation3D, Transform2D};
 use euclid::Length as EuclidLength;
@@ -93,15 +93,19 @@ use msg::constellation_msg::{
     BlobId, BroadcastChannelRouterId, BrowsingContextId, HistoryStateId, MessagePortId,
     MessagePortRouterId, PipelineId, TopLevelBrowsingContextId,
 };
-use msg::constellation_msg::{ServiceWorkerId, ServiceWorkerRegistrationId};
+use msg::constellation_msg::{LockClientId, ServiceWorkerId, ServiceWorkerRegistrationId};
 use net_traits::filemanager_thread::RelativePos;
 use net_traits::image::base::{Image, ImageMetadata};
 use net_traits::image_cache::{ImageCache, PendingImageId};
+use net_traits::indexeddb_thread::{IndexedDBCursorDirection, IndexedDBKeyRange};
+use net_traits::indexeddb_thread::{IndexedDBKeyType, IndexedDBTransactionMode};
+use net_traits::indexeddb_thread::{KeyPath, ObjectStoreInfo};
 use net_traits::request::{CredentialsMode, ParserMetadata, Referrer, Request, RequestBuilder};
 use net_traits::response::HttpsState;
 use net_traits::response::{Response, ResponseBody};
 use net_traits::storage_thread::StorageType;
-use net_traits::{Metadata, NetworkError, ReferrerPolicy, ResourceFetchTiming, ResourceThreads};
+use net_traits::{CrossOriginEmbedderPolicy, Metadata, NetworkError, ReferrerPolicy};
+use net_traits::{ResourceFetchTiming, ResourceThreads};
 use parking_lot::{Mutex as ParkMutex, RwLock};
 use profile_traits::mem::ProfilerChan as MemProfilerChan;
 use profile_traits::time::ProfilerChan as TimeProfilerChan;
@@ -200,6 +204,8 @@ unsafe_no_jsmanaged_fields!(ServiceWorkerRegistrationId);
 
 unsafe_no_jsmanaged_fields!(BroadcastChannelRouterId);
 
+unsafe_no_jsmanaged_fields!(LockClientId);
+
 unsafe_no_jsmanaged_fields!(BlobId);
 unsafe_no_jsmanaged_fields!(BlobImpl);
 
@@ -556,6 +562,9 @@ unsafe_no_jsmanaged_fields!(UntrustedNodeAddress, OpaqueNode);
 unsafe_no_jsmanaged_fields!(LengthOrPercentageOrAuto);
 unsafe_no_jsmanaged_fields!(RGBA);
 unsafe_no_jsmanaged_fields!(StorageType);
+unsafe_no_jsmanaged_fields!(IndexedDBKeyType, IndexedDBKeyRange, KeyPath);
+unsafe_no_jsmanaged_fields!(IndexedDBCursorDirection, IndexedDBTransactionMode);
+unsafe_no_jsmanaged_fields!(ObjectStoreInfo);
 unsafe_no_jsmanaged_fields!(CanvasGradientStop, LinearGradientStyle, RadialGradientStyle);
 unsafe_no_jsmanaged_fields!(LineCapStyle, LineJoinStyle, CompositionOrBlending);
 unsafe_no_jsmanaged_fields!(TextAlign, TextBaseline, Direction);
@@ -575,6 +584,7 @@ unsafe_no_jsmanaged_fields!(Snapshot);
 unsafe_no_jsmanaged_fields!(PendingRestyle);
 unsafe_no_jsmanaged_fields!(Stylesheet);
 unsafe_no_jsmanaged_fields!(HttpsState);
+unsafe_no_jsmanaged_fields!(CrossOriginEmbedderPolicy);
 unsafe_no_jsmanaged_fields!(Request);
 unsafe_no_jsmanaged_fields!(RequestBuilder);
 unsafe_no_jsmanaged_fields!(StyleSharedRwLock);
@@ -674,6 +684,7 @@ unsafe_no_jsmanaged_fields!(WebGLContextId);
 unsafe_no_jsmanaged_fields!(Arc<Mutex<dyn AudioRenderer>>);
 unsafe_no_jsmanaged_fields!(MediaSessionActionType);
 unsafe_no_jsmanaged_fields!(MediaMetadata);
+unsafe_no_jsmanaged_fields!(ClipboardRepresentation);
 unsafe_no_jsmanaged_fields!(WebrenderIpcSender);
 unsafe_no_jsmanaged_fields!(StreamConsumer);
 unsafe_no_jsmanaged_fields!(DocumentAnimationSet);
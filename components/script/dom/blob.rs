@@ -92,6 +92,42 @@ impl Blob {
     pub fn get_stream(&self) -> DomRoot<ReadableStream> {
         self.global().get_blob_stream(&self.blob_id)
     }
+
+    /// Take the blob impl stored at `storage_key` by the serialization steps,
+    /// which files share with blobs.
+    pub fn take_serialized_impl(
+        sc_holder: &mut StructuredDataHolder,
+        storage_key: StorageKey,
+    ) -> BlobImpl {
+        // 1. Re-build the key for the storage location
+        // of the serialized object.
+        let namespace_id = PipelineNamespaceId(storage_key.name_space.clone());
+        let index = BlobIndex(
+            NonZeroU32::new(storage_key.index.clone()).expect("Deserialized blob index is zero"),
+        );
+
+        let id = BlobId {
+            namespace_id,
+            index,
+        };
+
+        let blob_impls = match sc_holder {
+            StructuredDataHolder::Read { blob_impls, .. } => blob_impls,
+            _ => panic!("Unexpected variant of StructuredDataHolder"),
+        };
+
+        // 2. Get the transferred object from its storage, using the key.
+        let blob_impls_map = blob_impls
+            .as_mut()
+            .expect("The SC holder does not have any blob impls");
+        let blob_impl = blob_impls_map
+            .remove(&id)
+            .expect("No blob to be deserialized found.");
+        if blob_impls_map.is_empty() {
+            *blob_impls = None;
+        }
+        blob_impl
+    }
 }
 
 impl Serializable for Blob {
@@ -136,38 +172,15 @@ impl Serializable for Blob {
         sc_holder: &mut StructuredDataHolder,
         storage_key: StorageKey,
     ) -> Result<(), ()> {
-        // 1. Re-build the key for the storage location
-        // of the serialized object.
-        let namespace_id = PipelineNamespaceId(storage_key.name_space.clone());
-        let index = BlobIndex(
-            NonZeroU32::new(storage_key.index.clone()).expect("Deserialized blob index is zero"),
-        );
+        // Steps 1-2.
+        let blob_impl = Blob::take_serialized_impl(sc_holder, storage_key);
 
-        let id = BlobId {
-            namespace_id,
-            index,
-        };
+        let deserialized_blob = Blob::new(&*owner, blob_impl);
 
-        let (blobs, blob_impls) = match sc_holder {
-            StructuredDataHolder::Read {
-                blobs, blob_impls, ..
-            } => (blobs, blob_impls),
+        let blobs = match sc_holder {
+            StructuredDataHolder::Read { blobs, .. } => blobs,
             _ => panic!("Unexpected variant of StructuredDataHolder"),
         };
-
-        // 2. Get the transferred object from its storage, using the key.
-        let blob_impls_map = blob_impls
-            .as_mut()
-            .expect("The SC holder does not have any blob impls");
-        let blob_impl = blob_impls_map
-            .remove(&id)
-            .expect("No blob to be deserialized found.");
-        if blob_impls_map.is_empty() {
-            *blob_impls = None;
-        }
-
-        let deserialized_blob = Blob::new(&*owner, blob_impl);
-
         let blobs = blobs.get_or_insert_with(|| HashMap::new());
         blobs.insert(storage_key, deserialized_blob);
 
@@ -0,0 +1,591 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::body::{BodyMixin, BodySource, ExtractedBody};
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::CacheBinding::{CacheMethods, CacheQueryOptions};
+use crate::dom::bindings::codegen::Bindings::HeadersBinding::HeadersMethods;
+use crate::dom::bindings::codegen::Bindings::RequestBinding::RequestInit;
+use crate::dom::bindings::codegen::Bindings::ResponseBinding::{
+    ResponseMethods, ResponseType as DOMResponseType,
+};
+use crate::dom::bindings::codegen::UnionTypes::RequestOrUSVString;
+use crate::dom::bindings::conversions::root_from_handlevalue;
+use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::refcounted::{Trusted, TrustedPromise};
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::DomRoot;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::headers::{Guard, Headers};
+use crate::dom::promise::Promise;
+use crate::dom::promisenativehandler::{Callback, PromiseNativeHandler};
+use crate::dom::request::Request;
+use crate::dom::response::Response;
+use crate::fetch;
+use crate::realms::{enter_realm, InRealm};
+use crate::script_runtime::JSContext;
+use crate::task_source::{TaskSource, TaskSourceName};
+use dom_struct::dom_struct;
+use http::header::VARY;
+use http::Method;
+use hyper_serde::Serde;
+use ipc_channel::ipc::{self, IpcSender};
+use ipc_channel::router::ROUTER;
+use js::rust::HandleValue;
+use net_traits::cache_storage_thread::{
+    CacheMatch, CachePut, CacheQuery, CacheQueryOptions as NetCacheQueryOptions,
+};
+use net_traits::cache_storage_thread::{CacheRequest, CacheResponse, CacheStorageThreadMsg};
+use net_traits::request::BodyChunkResponse;
+use net_traits::response::ResponseType;
+use net_traits::storage_thread::StorageThreadMsg;
+use net_traits::{IpcSend, NetworkError};
+use serde::{Deserialize, Serialize};
+use servo_url::ServoUrl;
+use std::cell::Cell;
+use std::rc::Rc;
+
+// https://w3c.github.io/ServiceWorker/#cache-interface
+#[dom_struct]
+pub struct Cache {
+    reflector_: Reflector,
+    /// The id of the cache in the Cache Storage engine.
+    id: u64,
+}
+
+impl Cache {
+    fn new_inherited(id: u64) -> Cache {
+        Cache {
+            reflector_: Reflector::new(),
+            id: id,
+        }
+    }
+
+    pub fn new(global: &GlobalScope, id: u64) -> DomRoot<Cache> {
+        reflect_dom_object(Box::new(Cache::new_inherited(id)), global)
+    }
+
+    /// Reads the bodies of the entries in `net`, then adds them to the cache, settling the
+    /// promise once they are.
+    fn put_entries(&self, promise: &Rc<Promise>, entries: Vec<CachePut>) {
+        let global = self.global();
+        let origin = global.origin().immutable().clone();
+        send_message(
+            &global,
+            promise,
+            |sender| CacheStorageThreadMsg::Put(sender, origin, self.id, entries),
+            |promise, result: Result<(), ()>| match result {
+                Ok(()) => promise.resolve_native(&()),
+                Err(()) => promise.reject_error(Error::Type(
+                    "Failed to read the body of a response".to_owned(),
+                )),
+            },
+        );
+    }
+}
+
+impl CacheMethods for Cache {
+    // https://w3c.github.io/ServiceWorker/#dom-cache-match
+    fn Match(&self, request: RequestOrUSVString, options: &CacheQueryOptions) -> Rc<Promise> {
+        let global = self.global();
+        let promise = Promise::new(&global);
+        let query = match query_from_info(&global, request, options) {
+            Ok(Some(query)) => query,
+            Ok(None) => {
+                promise.resolve_native(&());
+                return promise;
+            },
+            Err(error) => {
+                promise.reject_error(error);
+                return promise;
+            },
+        };
+        let origin = global.origin().immutable().clone();
+        send_message(
+            &global,
+            &promise,
+            |sender| CacheStorageThreadMsg::MatchFirst(sender, origin, self.id, query),
+            |promise, found: Option<CacheMatch>| match found {
+                Some(cache_match) => {
+                    promise.resolve_native(&response_from_match(&promise.global(), cache_match))
+                },
+                None => promise.resolve_native(&()),
+            },
+        );
+        promise
+    }
+
+    // https://w3c.github.io/ServiceWorker/#dom-cache-matchall
+    fn MatchAll(
+        &self,
+        request: Option<RequestOrUSVString>,
+        options: &CacheQueryOptions,
+    ) -> Rc<Promise> {
+        let global = self.global();
+        let promise = Promise::new(&global);
+        // Steps 1-2.
+        let query = match request {
+            Some(request) => match query_from_info(&global, request, options) {
+                Ok(Some(query)) => Some(query),
+                Ok(None) => {
+                    promise.resolve_native(&Vec::<DomRoot<Response>>::new());
+                    return promise;
+                },
+                Err(error) => {
+                    promise.reject_error(error);
+                    return promise;
+                },
+            },
+            None => None,
+        };
+        // Steps 3-5.
+        let origin = global.origin().immutable().clone();
+        send_message(
+            &global,
+            &promise,
+            |sender| CacheStorageThreadMsg::MatchAll(sender, origin, self.id, query),
+            |promise, matches: Vec<CacheMatch>| {
+                let global = promise.global();
+                let responses: Vec<_> = matches
+                    .into_iter()
+                    .map(|cache_match| response_from_match(&global, cache_match))
+                    .collect();
+                promise.resolve_native(&responses);
+            },
+        );
+        promise
+    }
+
+    // https://w3c.github.io/ServiceWorker/#dom-cache-add
+    fn Add(&self, request: RequestOrUSVString) -> Rc<Promise> {
+        self.AddAll(vec![request])
+    }
+
+    // https://w3c.github.io/ServiceWorker/#dom-cache-addall
+    fn AddAll(&self, requests: Vec<RequestOrUSVString>) -> Rc<Promise> {
+        let global = self.global();
+        let promise = Promise::new(&global);
+        // Steps 1-3.
+        let mut request_list = vec![];
+        for request in requests {
+            let request = match request_from_info(&global, request) {
+                Ok(request) => request,
+                Err(error) => {
+                    promise.reject_error(error);
+                    return promise;
+                },
+            };
+            if let Err(error) = check_request_can_be_put(&request) {
+                promise.reject_error(error);
+                return promise;
+            }
+            request_list.push(request);
+        }
+        if request_list.is_empty() {
+            promise.resolve_native(&());
+            return promise;
+        }
+
+        // Steps 5-6.
+        let batch = Rc::new(AddAllBatch {
+            cache: DomRoot::from_ref(self),
+            promise: promise.clone(),
+            responses: DomRefCell::new(request_list.iter().map(|_| None).collect()),
+            requests: request_list.clone(),
+            failed: Cell::new(false),
+        });
+        let realm = enter_realm(&*global);
+        for (index, request) in request_list.into_iter().enumerate() {
+            let fetched = fetch::Fetch(
+                &global,
+                RequestOrUSVString::Request(request),
+                RequestInit::empty(),
+                InRealm::Entered(&realm),
+            );
+            let handler = PromiseNativeHandler::new(
+                &global,
+                Some(Box::new(AddAllResponseHandler {
+                    batch: batch.clone(),
+                    index: index,
+                })),
+                Some(Box::new(AddAllRejectionHandler {
+                    batch: batch.clone(),
+                })),
+            );
+            fetched.append_native_handler(&handler, InRealm::Entered(&realm));
+        }
+        promise
+    }
+
+    // https://w3c.github.io/ServiceWorker/#dom-cache-put
+    fn Put(&self, request: RequestOrUSVString, response: &Response) -> Rc<Promise> {
+        let global = self.global();
+        let promise = Promise::new(&global);
+        // Steps 1-3.
+        let request = match request_from_info(&global, request) {
+            Ok(request) => request,
+            Err(error) => {
+                promise.reject_error(error);
+                return promise;
+            },
+        };
+        if let Err(error) = check_request_can_be_put(&request) {
+            promise.reject_error(error);
+            return promise;
+        }
+        // Steps 4-7.
+        if response.Status() == 206 {
+            promise.reject_error(Error::Type("Partial responses can't be cached".to_owned()));
+            return promise;
+        }
+        if varies_on_everything(&response.Headers()) {
+            promise.reject_error(Error::Type(
+                "Responses that vary on '*' can't be cached".to_owned(),
+            ));
+            return promise;
+        }
+        if response.is_disturbed() || response.is_locked() {
+            promise.reject_error(Error::Type("The body of the response is used".to_owned()));
+            return promise;
+        }
+        // Steps 8-13.
+        self.put_entries(&promise, vec![cache_put(&request, response)]);
+        promise
+    }
+
+    // https://w3c.github.io/ServiceWorker/#dom-cache-delete
+    fn Delete(&self, request: RequestOrUSVString, options: &CacheQueryOptions) -> Rc<Promise> {
+        let global = self.global();
+        let promise = Promise::new(&global);
+        // Steps 1-2.
+        let query = match query_from_info(&global, request, options) {
+            Ok(Some(query)) => query,
+            Ok(None) => {
+                promise.resolve_native(&false);
+                return promise;
+            },
+            Err(error) => {
+                promise.reject_error(error);
+                return promise;
+            },
+        };
+        // Steps 3-9.
+        let origin = global.origin().immutable().clone();
+        send_message(
+            &global,
+            &promise,
+            |sender| CacheStorageThreadMsg::DeleteEntries(sender, origin, self.id, query),
+            |promise, deleted: bool| promise.resolve_native(&deleted),
+        );
+        promise
+    }
+
+    // https://w3c.github.io/ServiceWorker/#dom-cache-keys
+    fn Keys(
+        &self,
+        request: Option<RequestOrUSVString>,
+        options: &CacheQueryOptions,
+    ) -> Rc<Promise> {
+        let global = self.global();
+        let promise = Promise::new(&global);
+        // Steps 1-2.
+        let query = match request {
+            Some(request) => match query_from_info(&global, request, options) {
+                Ok(Some(query)) => Some(query),
+                Ok(None) => {
+                    promise.resolve_native(&Vec::<DomRoot<Request>>::new());
+                    return promise;
+                },
+                Err(error) => {
+                    promise.reject_error(error);
+                    return promise;
+                },
+            },
+            None => None,
+        };
+        // Steps 3-5.
+        let origin = global.origin().immutable().clone();
+        send_message(
+            &global,
+            &promise,
+            |sender| CacheStorageThreadMsg::Requests(sender, origin, self.id, query),
+            |promise, requests: Vec<CacheRequest>| {
+                let global = promise.global();
+                let requests: Vec<_> = requests
+                    .into_iter()
+                    .map(|request| {
+                        Request::new_for_cache_entry(&global, request.url, request.headers)
+                    })
+                    .collect();
+                promise.resolve_native(&requests);
+            },
+        );
+        promise
+    }
+}
+
+/// The responses of the fetches of `Cache.addAll`, which are put once they have all arrived.
+#[derive(JSTraceable, MallocSizeOf)]
+struct AddAllBatch {
+    cache: DomRoot<Cache>,
+    #[ignore_malloc_size_of = "Rc"]
+    promise: Rc<Promise>,
+    requests: Vec<DomRoot<Request>>,
+    responses: DomRefCell<Vec<Option<DomRoot<Response>>>>,
+    failed: Cell<bool>,
+}
+
+impl AddAllBatch {
+    fn fail(&self, error: Error) {
+        if !self.failed.get() {
+            self.failed.set(true);
+            self.promise.reject_error(error);
+        }
+    }
+}
+
+/// Steps 6.4-6.5 of <https://w3c.github.io/ServiceWorker/#dom-cache-addall>, and the steps
+/// after all fetches completed.
+#[derive(JSTraceable, MallocSizeOf)]
+struct AddAllResponseHandler {
+    #[ignore_malloc_size_of = "Rc"]
+    batch: Rc<AddAllBatch>,
+    index: usize,
+}
+
+impl Callback for AddAllResponseHandler {
+    fn callback(&self, cx: JSContext, v: HandleValue, _realm: InRealm) {
+        let batch = &self.batch;
+        if batch.failed.get() {
+            return;
+        }
+        let response = match root_from_handlevalue::<Response>(v, *cx) {
+            Ok(response) => response,
+            Err(()) => return batch.fail(Error::Type("Fetch didn't return a response".to_owned())),
+        };
+        let status = response.Status();
+        if response.Type() == DOMResponseType::Error ||
+            status < 200 ||
+            status > 299 ||
+            status == 206
+        {
+            return batch.fail(Error::Type("Failed to fetch a request to add".to_owned()));
+        }
+        if varies_on_everything(&response.Headers()) {
+            return batch.fail(Error::Type(
+                "Responses that vary on '*' can't be cached".to_owned(),
+            ));
+        }
+        batch.responses.borrow_mut()[self.index] = Some(response);
+
+        if batch.responses.borrow().iter().any(Option::is_none) {
+            return;
+        }
+        let entries = batch
+            .requests
+            .iter()
+            .zip(batch.responses.borrow().iter())
+            .filter_map(|(request, response)| {
+                response
+                    .as_ref()
+                    .map(|response| cache_put(request, response))
+            })
+            .collect();
+        batch.cache.put_entries(&batch.promise, entries);
+    }
+}
+
+/// Rejects the promise of `Cache.addAll` when one of its fetches fails.
+#[derive(JSTraceable, MallocSizeOf)]
+struct AddAllRejectionHandler {
+    #[ignore_malloc_size_of = "Rc"]
+    batch: Rc<AddAllBatch>,
+}
+
+impl Callback for AddAllRejectionHandler {
+    fn callback(&self, _cx: JSContext, _v: HandleValue, _realm: InRealm) {
+        self.batch
+            .fail(Error::Type("Failed to fetch a request to add".to_owned()));
+    }
+}
+
+/// Sends a message to the Cache Storage engine of the storage thread, and runs `on_reply` in a
+/// task with the promise of the operation once the engine replies.
+pub fn send_message<T, M, F>(global: &GlobalScope, promise: &Rc<Promise>, message: M, on_reply: F)
+where
+    T: for<'de> Deserialize<'de> + Serialize + Send + 'static,
+    M: FnOnce(IpcSender<T>) -> CacheStorageThreadMsg,
+    F: FnOnce(&Promise, T) + Send + 'static,
+{
+    let (sender, receiver) = ipc::channel().unwrap();
+    let task_source = global.dom_manipulation_task_source();
+    let canceller = global.task_canceller(TaskSourceName::DOMManipulation);
+    let mut pending = Some((TrustedPromise::new(promise.clone()), on_reply));
+    ROUTER.add_route(
+        receiver.to_opaque(),
+        Box::new(move |message| {
+            let (trusted, on_reply) = match pending.take() {
+                Some(pending) => pending,
+                None => return,
+            };
+            let reply: T = message.to().unwrap();
+            let _ = task_source.queue_with_canceller(
+                task!(cache_storage_reply: move || {
+                    on_reply(&trusted.root(), reply);
+                }),
+                &canceller,
+            );
+        }),
+    );
+    let storage_thread: IpcSender<StorageThreadMsg> = global.resource_threads().sender();
+    let _ = storage_thread.send(StorageThreadMsg::CacheStorage(message(sender)));
+}
+
+/// The request of a `RequestInfo`, as the methods of `Cache` and `CacheStorage` get it.
+fn request_from_info(global: &GlobalScope, info: RequestOrUSVString) -> Fallible<DomRoot<Request>> {
+    match info {
+        RequestOrUSVString::Request(request) => Ok(request),
+        info => Request::Constructor(global, info, RequestInit::empty()),
+    }
+}
+
+/// The query of the methods that look a request up, or `None` if nothing can match it.
+pub fn query_from_info(
+    global: &GlobalScope,
+    info: RequestOrUSVString,
+    options: &CacheQueryOptions,
+) -> Fallible<Option<CacheQuery>> {
+    let is_request = match info {
+        RequestOrUSVString::Request(_) => true,
+        RequestOrUSVString::USVString(_) => false,
+    };
+    let request = cache_request(&*request_from_info(global, info)?);
+    if is_request && request.method != Method::GET && !options.ignoreMethod {
+        return Ok(None);
+    }
+    Ok(Some(CacheQuery {
+        request: request,
+        options: NetCacheQueryOptions {
+            ignore_search: options.ignoreSearch,
+            ignore_method: options.ignoreMethod,
+            ignore_vary: options.ignoreVary,
+        },
+    }))
+}
+
+fn cache_request(request: &Request) -> CacheRequest {
+    let net_request = request.get_request();
+    CacheRequest {
+        url: net_request.url(),
+        method: net_request.method.clone(),
+        headers: request.Headers().get_headers_list(),
+    }
+}
+
+fn check_request_can_be_put(request: &Request) -> Fallible<()> {
+    let net_request = request.get_request();
+    let url = net_request.url();
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(Error::Type(
+            "Only http(s) requests can be cached".to_owned(),
+        ));
+    }
+    if net_request.method != Method::GET {
+        return Err(Error::Type("Only GET requests can be cached".to_owned()));
+    }
+    Ok(())
+}
+
+fn varies_on_everything(headers: &Headers) -> bool {
+    headers
+        .get_headers_list()
+        .get_all(VARY)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|field| field.trim() == "*")
+}
+
+/// An entry to put in a cache. The body of the response is streamed to `net` as it is read.
+fn cache_put(request: &Request, response: &Response) -> CachePut {
+    let response_type = match response.Type() {
+        DOMResponseType::Basic => ResponseType::Basic,
+        DOMResponseType::Cors => ResponseType::Cors,
+        DOMResponseType::Default => ResponseType::Default,
+        DOMResponseType::Error => {
+            ResponseType::Error(NetworkError::Internal("Cached network error".to_owned()))
+        },
+        DOMResponseType::Opaque => ResponseType::Opaque,
+        DOMResponseType::Opaqueredirect => ResponseType::OpaqueRedirect,
+    };
+    let body = response.body().map(|stream| {
+        // The body is locked right away, so that script can't read it while `net` does.
+        let _ = stream.start_reading();
+        let extracted = ExtractedBody {
+            stream: stream,
+            source: BodySource::Null,
+            total_bytes: None,
+            content_type: None,
+        };
+        extracted.into_net_request_body().0
+    });
+    CachePut {
+        request: cache_request(request),
+        response: CacheResponse {
+            url: ServoUrl::parse(&response.Url().0).ok(),
+            response_type: response_type,
+            raw_status: Some((response.Status(), response.StatusText().to_vec())),
+            headers: response.Headers().get_headers_list(),
+        },
+        body: body,
+    }
+}
+
+/// The response object of a response found in a cache, whose body is streamed from `net`.
+pub fn response_from_match(global: &GlobalScope, cache_match: CacheMatch) -> DomRoot<Response> {
+    let CacheMatch {
+        response: cached,
+        body,
+    } = cache_match;
+    let response = Response::new(global);
+    response.set_type(match cached.response_type {
+        ResponseType::Basic => DOMResponseType::Basic,
+        ResponseType::Cors => DOMResponseType::Cors,
+        ResponseType::Default => DOMResponseType::Default,
+        ResponseType::Error(_) => DOMResponseType::Error,
+        ResponseType::Opaque => DOMResponseType::Opaque,
+        ResponseType::OpaqueRedirect => DOMResponseType::Opaqueredirect,
+    });
+    response.set_headers(Some(Serde(cached.headers)));
+    response.set_raw_status(cached.raw_status);
+    if let Some(url) = cached.url {
+        response.set_final_url(url);
+    }
+    response.Headers().set_guard(Guard::Immutable);
+
+    let trusted = Trusted::new(&*response);
+    let task_source = global.networking_task_source();
+    let canceller = global.task_canceller(TaskSourceName::Networking);
+    ROUTER.add_route(
+        body.to_opaque(),
+        Box::new(move |message| {
+            let response = trusted.clone();
+            let chunk: BodyChunkResponse = message.to().unwrap();
+            let _ = task_source.queue_with_canceller(
+                task!(stream_cached_response_body: move || {
+                    let response = response.root();
+                    match chunk {
+                        BodyChunkResponse::Chunk(bytes) => response.stream_chunk(bytes),
+                        BodyChunkResponse::Done => response.finish(),
+                        BodyChunkResponse::Error => response.error_stream(Error::Type(
+                            "Failed to read the body of a cached response".to_owned(),
+                        )),
+                    }
+                }),
+                &canceller,
+            );
+        }),
+    );
+    response
+}
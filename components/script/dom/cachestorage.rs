@@ -0,0 +1,137 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::codegen::Bindings::CacheStorageBinding::{
+    CacheStorageMethods, MultiCacheQueryOptions,
+};
+use crate::dom::bindings::codegen::UnionTypes::RequestOrUSVString;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::DomRoot;
+use crate::dom::bindings::str::DOMString;
+use crate::dom::cache::{query_from_info, response_from_match, send_message, Cache};
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::promise::Promise;
+use crate::script_runtime::JSContext;
+use dom_struct::dom_struct;
+use js::rust::HandleObject;
+use net_traits::cache_storage_thread::{CacheMatch, CacheStorageThreadMsg};
+use std::rc::Rc;
+
+// https://w3c.github.io/ServiceWorker/#cachestorage-interface
+#[dom_struct]
+pub struct CacheStorage {
+    reflector_: Reflector,
+}
+
+impl CacheStorage {
+    fn new_inherited() -> CacheStorage {
+        CacheStorage {
+            reflector_: Reflector::new(),
+        }
+    }
+
+    pub fn new(global: &GlobalScope) -> DomRoot<CacheStorage> {
+        reflect_dom_object(Box::new(CacheStorage::new_inherited()), global)
+    }
+
+    /// The condition to expose `caches`, which is only exposed in secure contexts.
+    pub fn is_exposed(cx: JSContext, obj: HandleObject) -> bool {
+        pref!(dom.caches.enabled) && GlobalScope::is_secure_context_exposed(cx, obj)
+    }
+}
+
+impl CacheStorageMethods for CacheStorage {
+    // https://w3c.github.io/ServiceWorker/#cache-storage-match
+    fn Match(&self, request: RequestOrUSVString, options: &MultiCacheQueryOptions) -> Rc<Promise> {
+        let global = self.global();
+        let promise = Promise::new(&global);
+        let query = match query_from_info(&global, request, &options.parent) {
+            Ok(Some(query)) => query,
+            Ok(None) => {
+                promise.resolve_native(&());
+                return promise;
+            },
+            Err(error) => {
+                promise.reject_error(error);
+                return promise;
+            },
+        };
+        let origin = global.origin().immutable().clone();
+        let cache_name = options
+            .cacheName
+            .as_ref()
+            .map(|name| String::from(name.clone()));
+        send_message(
+            &global,
+            &promise,
+            |sender| CacheStorageThreadMsg::Match(sender, origin, cache_name, query),
+            |promise, found: Option<CacheMatch>| match found {
+                Some(cache_match) => {
+                    promise.resolve_native(&response_from_match(&promise.global(), cache_match))
+                },
+                None => promise.resolve_native(&()),
+            },
+        );
+        promise
+    }
+
+    // https://w3c.github.io/ServiceWorker/#cache-storage-has
+    fn Has(&self, cache_name: DOMString) -> Rc<Promise> {
+        let global = self.global();
+        let promise = Promise::new(&global);
+        let origin = global.origin().immutable().clone();
+        send_message(
+            &global,
+            &promise,
+            |sender| CacheStorageThreadMsg::Has(sender, origin, cache_name.into()),
+            |promise, found: bool| promise.resolve_native(&found),
+        );
+        promise
+    }
+
+    // https://w3c.github.io/ServiceWorker/#cache-storage-open
+    fn Open(&self, cache_name: DOMString) -> Rc<Promise> {
+        let global = self.global();
+        let promise = Promise::new(&global);
+        let origin = global.origin().immutable().clone();
+        send_message(
+            &global,
+            &promise,
+            |sender| CacheStorageThreadMsg::Open(sender, origin, cache_name.into()),
+            |promise, id: u64| promise.resolve_native(&Cache::new(&promise.global(), id)),
+        );
+        promise
+    }
+
+    // https://w3c.github.io/ServiceWorker/#cache-storage-delete
+    fn Delete(&self, cache_name: DOMString) -> Rc<Promise> {
+        let global = self.global();
+        let promise = Promise::new(&global);
+        let origin = global.origin().immutable().clone();
+        send_message(
+            &global,
+            &promise,
+            |sender| CacheStorageThreadMsg::Delete(sender, origin, cache_name.into()),
+            |promise, deleted: bool| promise.resolve_native(&deleted),
+        );
+        promise
+    }
+
+    // https://w3c.github.io/ServiceWorker/#cache-storage-keys
+    fn Keys(&self) -> Rc<Promise> {
+        let global = self.global();
+        let promise = Promise::new(&global);
+        let origin = global.origin().immutable().clone();
+        send_message(
+            &global,
+            &promise,
+            |sender| CacheStorageThreadMsg::Keys(sender, origin),
+            |promise, names: Vec<String>| {
+                let names: Vec<DOMString> = names.into_iter().map(DOMString::from).collect();
+                promise.resolve_native(&names);
+            },
+        );
+        promise
+    }
+}
@@ -0,0 +1,151 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::codegen::Bindings::ClipboardBinding::ClipboardMethods;
+use crate::dom::bindings::codegen::Bindings::PermissionStatusBinding::{
+    PermissionName, PermissionState,
+};
+use crate::dom::bindings::error::Error;
+use crate::dom::bindings::refcounted::TrustedPromise;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject};
+use crate::dom::bindings::root::DomRoot;
+use crate::dom::bindings::str::DOMString;
+use crate::dom::clipboarditem::ClipboardItem;
+use crate::dom::eventtarget::EventTarget;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::permissions::request_permission_to_use;
+use crate::dom::promise::Promise;
+use crate::task_source::{TaskSource, TaskSourceName};
+use dom_struct::dom_struct;
+use embedder_traits::{ClipboardData, EmbedderMsg};
+use ipc_channel::ipc;
+use ipc_channel::router::ROUTER;
+use std::rc::Rc;
+
+// https://w3c.github.io/clipboard-apis/#clipboard-interface
+#[dom_struct]
+pub struct Clipboard {
+    eventtarget: EventTarget,
+}
+
+impl Clipboard {
+    fn new_inherited() -> Clipboard {
+        Clipboard {
+            eventtarget: EventTarget::new_inherited(),
+        }
+    }
+
+    pub fn new(global: &GlobalScope) -> DomRoot<Clipboard> {
+        reflect_dom_object(Box::new(Clipboard::new_inherited()), global)
+    }
+
+    /// Whether the user grants the permission that a clipboard operation requires.
+    // TODO: the document should also be required to have focus.
+    fn is_allowed(&self, permission_name: PermissionName) -> bool {
+        request_permission_to_use(permission_name, &self.global()) == PermissionState::Granted
+    }
+
+    /// Asks the embedder for the contents of the system clipboard, and runs `on_reply`
+    /// in a task with the promise of the operation once they are received.
+    fn read_clipboard_data<F>(&self, promise: &Rc<Promise>, on_reply: F)
+    where
+        F: FnOnce(&Promise, ClipboardData) + Send + 'static,
+    {
+        let global = self.global();
+        let (sender, receiver) = ipc::channel().expect("ipc channel failure");
+        let task_source = global.dom_manipulation_task_source();
+        let canceller = global.task_canceller(TaskSourceName::DOMManipulation);
+        let mut pending = Some((TrustedPromise::new(promise.clone()), on_reply));
+        ROUTER.add_route(
+            receiver.to_opaque(),
+            Box::new(move |message| {
+                let (trusted, on_reply) = match pending.take() {
+                    Some(pending) => pending,
+                    None => return,
+                };
+                let data: ClipboardData = message.to().unwrap_or_default();
+                let _ = task_source.queue_with_canceller(
+                    task!(clipboard_read: move || {
+                        on_reply(&trusted.root(), data);
+                    }),
+                    &canceller,
+                );
+            }),
+        );
+        global.send_to_embedder(EmbedderMsg::GetClipboardContents(sender));
+    }
+}
+
+impl ClipboardMethods for Clipboard {
+    // https://w3c.github.io/clipboard-apis/#dom-clipboard-read
+    fn Read(&self) -> Rc<Promise> {
+        let promise = Promise::new(&self.global());
+        if !self.is_allowed(PermissionName::Clipboard_read) {
+            promise.reject_error(Error::NotAllowed);
+            return promise;
+        }
+        self.read_clipboard_data(&promise, |promise, data| {
+            let items = if data.representations.is_empty() {
+                vec![]
+            } else {
+                vec![ClipboardItem::new_from_clipboard_data(
+                    &promise.global(),
+                    data,
+                )]
+            };
+            promise.resolve_native(&items);
+        });
+        promise
+    }
+
+    // https://w3c.github.io/clipboard-apis/#dom-clipboard-readtext
+    fn ReadText(&self) -> Rc<Promise> {
+        let promise = Promise::new(&self.global());
+        if !self.is_allowed(PermissionName::Clipboard_read) {
+            promise.reject_error(Error::NotAllowed);
+            return promise;
+        }
+        self.read_clipboard_data(&promise, |promise, data| {
+            promise.resolve_native(&DOMString::from(data.text().unwrap_or_default()));
+        });
+        promise
+    }
+
+    // https://w3c.github.io/clipboard-apis/#dom-clipboard-write
+    fn Write(&self, data: Vec<DomRoot<ClipboardItem>>) -> Rc<Promise> {
+        let promise = Promise::new(&self.global());
+        if !self.is_allowed(PermissionName::Clipboard_write) {
+            promise.reject_error(Error::NotAllowed);
+            return promise;
+        }
+        // The system clipboard holds a single item, in one or more representations.
+        if data.len() > 1 {
+            promise.reject_error(Error::NotAllowed);
+            return promise;
+        }
+        let data = data
+            .first()
+            .map(|item| item.clipboard_data())
+            .unwrap_or_default();
+        self.global()
+            .send_to_embedder(EmbedderMsg::SetClipboardContents(data));
+        promise.resolve_native(&());
+        promise
+    }
+
+    // https://w3c.github.io/clipboard-apis/#dom-clipboard-writetext
+    fn WriteText(&self, data: DOMString) -> Rc<Promise> {
+        let promise = Promise::new(&self.global());
+        if !self.is_allowed(PermissionName::Clipboard_write) {
+            promise.reject_error(Error::NotAllowed);
+            return promise;
+        }
+        self.global()
+            .send_to_embedder(EmbedderMsg::SetClipboardContents(ClipboardData::from_text(
+                data.into(),
+            )));
+        promise.resolve_native(&());
+        promise
+    }
+}
@@ -0,0 +1,108 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::codegen::Bindings::ClipboardEventBinding;
+use crate::dom::bindings::codegen::Bindings::ClipboardEventBinding::ClipboardEventMethods;
+use crate::dom::bindings::codegen::Bindings::EventBinding::EventMethods;
+use crate::dom::bindings::error::Fallible;
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::reflector::reflect_dom_object;
+use crate::dom::bindings::root::{DomRoot, MutNullableDom};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::datatransfer::DataTransfer;
+use crate::dom::event::Event;
+use crate::dom::keyboardevent::KeyboardEvent;
+use crate::dom::window::Window;
+use crate::textinput::CMD_OR_CONTROL;
+use dom_struct::dom_struct;
+use keyboard_types::Key;
+use servo_atoms::Atom;
+
+/// The clipboard actions that fire a clipboard event.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ClipboardEventType {
+    Copy,
+    Cut,
+    Paste,
+}
+
+impl ClipboardEventType {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            ClipboardEventType::Copy => "copy",
+            ClipboardEventType::Cut => "cut",
+            ClipboardEventType::Paste => "paste",
+        }
+    }
+
+    /// The clipboard action that the keyboard shortcut of a `keydown` event triggers, if
+    /// any. Synthetic events trigger none, so that scripts can't read the clipboard.
+    pub fn for_shortcut(event: &KeyboardEvent) -> Option<ClipboardEventType> {
+        if !event.upcast::<Event>().IsTrusted() || event.modifiers() != CMD_OR_CONTROL {
+            return None;
+        }
+        match event.key() {
+            Key::Character(ref c) if c.eq_ignore_ascii_case("c") => Some(ClipboardEventType::Copy),
+            Key::Character(ref c) if c.eq_ignore_ascii_case("x") => Some(ClipboardEventType::Cut),
+            Key::Character(ref c) if c.eq_ignore_ascii_case("v") => Some(ClipboardEventType::Paste),
+            _ => None,
+        }
+    }
+}
+
+// https://w3c.github.io/clipboard-apis/#clipboardevent
+#[dom_struct]
+pub struct ClipboardEvent {
+    event: Event,
+    clipboard_data: MutNullableDom<DataTransfer>,
+}
+
+impl ClipboardEvent {
+    fn new_inherited() -> ClipboardEvent {
+        ClipboardEvent {
+            event: Event::new_inherited(),
+            clipboard_data: Default::default(),
+        }
+    }
+
+    pub fn new(
+        window: &Window,
+        type_: Atom,
+        bubbles: bool,
+        cancelable: bool,
+        clipboard_data: Option<&DataTransfer>,
+    ) -> DomRoot<ClipboardEvent> {
+        let ev = reflect_dom_object(Box::new(ClipboardEvent::new_inherited()), window);
+        ev.clipboard_data.set(clipboard_data);
+        ev.upcast::<Event>().init_event(type_, bubbles, cancelable);
+        ev
+    }
+
+    #[allow(non_snake_case)]
+    pub fn Constructor(
+        window: &Window,
+        type_: DOMString,
+        init: &ClipboardEventBinding::ClipboardEventInit,
+    ) -> Fallible<DomRoot<ClipboardEvent>> {
+        Ok(ClipboardEvent::new(
+            window,
+            Atom::from(type_),
+            init.parent.bubbles,
+            init.parent.cancelable,
+            init.clipboardData.as_deref(),
+        ))
+    }
+}
+
+impl ClipboardEventMethods for ClipboardEvent {
+    // https://w3c.github.io/clipboard-apis/#dom-clipboardevent-clipboarddata
+    fn GetClipboardData(&self) -> Option<DomRoot<DataTransfer>> {
+        self.clipboard_data.get()
+    }
+
+    // https://dom.spec.whatwg.org/#dom-event-istrusted
+    fn IsTrusted(&self) -> bool {
+        self.event.IsTrusted()
+    }
+}
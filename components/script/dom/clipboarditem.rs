@@ -0,0 +1,146 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::codegen::Bindings::ClipboardItemBinding::{
+    ClipboardItemMethods, ClipboardItemOptions, PresentationStyle,
+};
+use crate::dom::bindings::codegen::UnionTypes::StringOrBlob;
+use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::record::Record;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::DomRoot;
+use crate::dom::bindings::str::DOMString;
+use crate::dom::bindings::utils::to_frozen_array;
+use crate::dom::blob::{normalize_type_string, Blob};
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::promise::Promise;
+use crate::script_runtime::JSContext;
+use dom_struct::dom_struct;
+use embedder_traits::{ClipboardData, ClipboardRepresentation};
+use js::jsval::JSVal;
+use script_traits::serializable::BlobImpl;
+use std::rc::Rc;
+
+// https://w3c.github.io/clipboard-apis/#clipboarditem
+#[dom_struct]
+pub struct ClipboardItem {
+    reflector_: Reflector,
+    presentation_style: PresentationStyle,
+    /// <https://w3c.github.io/clipboard-apis/#clipboarditem-clipboard-item>, whose data is
+    /// read as soon as the item is created.
+    #[ignore_malloc_size_of = "Defined in embedder_traits"]
+    representations: Vec<ClipboardRepresentation>,
+}
+
+impl ClipboardItem {
+    fn new_inherited(
+        representations: Vec<ClipboardRepresentation>,
+        presentation_style: PresentationStyle,
+    ) -> ClipboardItem {
+        ClipboardItem {
+            reflector_: Reflector::new(),
+            presentation_style,
+            representations,
+        }
+    }
+
+    pub fn new(
+        global: &GlobalScope,
+        representations: Vec<ClipboardRepresentation>,
+        presentation_style: PresentationStyle,
+    ) -> DomRoot<ClipboardItem> {
+        reflect_dom_object(
+            Box::new(ClipboardItem::new_inherited(
+                representations,
+                presentation_style,
+            )),
+            global,
+        )
+    }
+
+    /// Creates an item for the contents of the system clipboard.
+    pub fn new_from_clipboard_data(
+        global: &GlobalScope,
+        data: ClipboardData,
+    ) -> DomRoot<ClipboardItem> {
+        ClipboardItem::new(global, data.representations, PresentationStyle::Unspecified)
+    }
+
+    // https://w3c.github.io/clipboard-apis/#dom-clipboarditem-clipboarditem
+    #[allow(non_snake_case)]
+    pub fn Constructor(
+        global: &GlobalScope,
+        items: Record<DOMString, StringOrBlob>,
+        options: &ClipboardItemOptions,
+    ) -> Fallible<DomRoot<ClipboardItem>> {
+        // Step 1.
+        if items.is_empty() {
+            return Err(Error::Type(
+                "ClipboardItem must have at least one type".to_owned(),
+            ));
+        }
+
+        // Step 2 - 5.
+        let mut representations = Vec::with_capacity(items.len());
+        for (type_, data) in items.iter() {
+            let data = match *data {
+                StringOrBlob::String(ref text) => text.to_string().into_bytes(),
+                StringOrBlob::Blob(ref blob) => blob.get_bytes().map_err(|_| Error::NotReadable)?,
+            };
+            representations.push(ClipboardRepresentation {
+                mime_type: normalize_type_string(type_),
+                data,
+            });
+        }
+
+        Ok(ClipboardItem::new(
+            global,
+            representations,
+            options.presentationStyle,
+        ))
+    }
+
+    /// The data that writing this item puts on the system clipboard.
+    pub fn clipboard_data(&self) -> ClipboardData {
+        ClipboardData {
+            representations: self.representations.clone(),
+        }
+    }
+}
+
+impl ClipboardItemMethods for ClipboardItem {
+    // https://w3c.github.io/clipboard-apis/#dom-clipboarditem-presentationstyle
+    fn PresentationStyle(&self) -> PresentationStyle {
+        self.presentation_style
+    }
+
+    // https://w3c.github.io/clipboard-apis/#dom-clipboarditem-types
+    fn Types(&self, cx: JSContext) -> JSVal {
+        let types: Vec<DOMString> = self
+            .representations
+            .iter()
+            .map(|representation| DOMString::from(&*representation.mime_type))
+            .collect();
+        to_frozen_array(types.as_slice(), cx)
+    }
+
+    // https://w3c.github.io/clipboard-apis/#dom-clipboarditem-gettype
+    fn GetType(&self, type_: DOMString) -> Rc<Promise> {
+        let global = self.global();
+        let promise = Promise::new(&global);
+        let type_ = normalize_type_string(&type_);
+        match self
+            .representations
+            .iter()
+            .find(|representation| representation.mime_type == type_)
+        {
+            Some(representation) => {
+                let blob_impl = BlobImpl::new_from_bytes(representation.data.clone(), type_);
+                promise.resolve_native(&Blob::new(&global, blob_impl));
+            },
+            None => promise.reject_error(Error::NotFound),
+        }
+        promise
+    }
+}
@@ -0,0 +1,160 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::CompressionStreamBinding::{
+    CompressionFormat, CompressionStreamMethods,
+};
+use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::reflector::{reflect_dom_object, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot, MutNullableDom};
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::transformstream::TransformStream;
+use crate::dom::transformstreamdefaultcontroller::{buffer_source_to_vec, TransformerType};
+use crate::dom::writablestream::WritableStream;
+use crate::script_runtime::JSContext;
+use dom_struct::dom_struct;
+use flate2::write::{DeflateEncoder, GzEncoder, ZlibEncoder};
+use flate2::Compression;
+use js::jsapi::JSObject;
+use js::rust::HandleValue;
+use std::io::{self, Write};
+use std::mem;
+use std::ptr::NonNull;
+
+/// The compression context of a stream, which writes the compressed data to a buffer.
+enum Compressor {
+    Deflate(ZlibEncoder<Vec<u8>>),
+    DeflateRaw(DeflateEncoder<Vec<u8>>),
+    Gzip(GzEncoder<Vec<u8>>),
+}
+
+unsafe_no_jsmanaged_fields!(Compressor);
+
+impl Compressor {
+    fn new(format: CompressionFormat) -> Compressor {
+        let output = Vec::new();
+        let level = Compression::default();
+        match format {
+            CompressionFormat::Deflate => Compressor::Deflate(ZlibEncoder::new(output, level)),
+            CompressionFormat::Deflate_raw => {
+                Compressor::DeflateRaw(DeflateEncoder::new(output, level))
+            },
+            CompressionFormat::Gzip => Compressor::Gzip(GzEncoder::new(output, level)),
+        }
+    }
+
+    /// Compresses `input`, and takes the output produced so far.
+    fn compress(&mut self, input: &[u8]) -> io::Result<Vec<u8>> {
+        match *self {
+            Compressor::Deflate(ref mut encoder) => {
+                encoder.write_all(input)?;
+                Ok(mem::take(encoder.get_mut()))
+            },
+            Compressor::DeflateRaw(ref mut encoder) => {
+                encoder.write_all(input)?;
+                Ok(mem::take(encoder.get_mut()))
+            },
+            Compressor::Gzip(ref mut encoder) => {
+                encoder.write_all(input)?;
+                Ok(mem::take(encoder.get_mut()))
+            },
+        }
+    }
+
+    /// Finishes the compressed data, and takes the rest of the output.
+    fn finish(&mut self) -> io::Result<Vec<u8>> {
+        match *self {
+            Compressor::Deflate(ref mut encoder) => {
+                encoder.try_finish()?;
+                Ok(mem::take(encoder.get_mut()))
+            },
+            Compressor::DeflateRaw(ref mut encoder) => {
+                encoder.try_finish()?;
+                Ok(mem::take(encoder.get_mut()))
+            },
+            Compressor::Gzip(ref mut encoder) => {
+                encoder.try_finish()?;
+                Ok(mem::take(encoder.get_mut()))
+            },
+        }
+    }
+}
+
+// https://compression.spec.whatwg.org/#compressionstream
+#[dom_struct]
+pub struct CompressionStream {
+    reflector_: Reflector,
+    #[ignore_malloc_size_of = "Defined in flate2"]
+    compressor: DomRefCell<Compressor>,
+    /// <https://streams.spec.whatwg.org/#generictransformstream-transform>
+    transform: MutNullableDom<TransformStream>,
+}
+
+impl CompressionStream {
+    fn new_inherited(format: CompressionFormat) -> CompressionStream {
+        CompressionStream {
+            reflector_: Reflector::new(),
+            compressor: DomRefCell::new(Compressor::new(format)),
+            transform: Default::default(),
+        }
+    }
+
+    // https://compression.spec.whatwg.org/#dom-compressionstream-compressionstream
+    #[allow(non_snake_case)]
+    pub fn Constructor(
+        global: &GlobalScope,
+        format: CompressionFormat,
+    ) -> Fallible<DomRoot<CompressionStream>> {
+        // Steps 1-2.
+        let stream = reflect_dom_object(Box::new(CompressionStream::new_inherited(format)), global);
+        // Steps 3-6.
+        let transform = TransformStream::new_with_transformer(
+            global,
+            TransformerType::Compression(Dom::from_ref(&*stream)),
+        )?;
+        stream.transform.set(Some(&transform));
+        Ok(stream)
+    }
+
+    fn transform(&self) -> DomRoot<TransformStream> {
+        self.transform.get().expect("The stream was constructed")
+    }
+
+    /// Enqueues the compressed `output`, unless it is empty.
+    fn enqueue(&self, output: io::Result<Vec<u8>>) -> Fallible<()> {
+        let output = output.map_err(|_| Error::Type("Compression failed".to_owned()))?;
+        if output.is_empty() {
+            return Ok(());
+        }
+        self.transform().enqueue_bytes(&output)
+    }
+
+    /// <https://compression.spec.whatwg.org/#compress-and-enqueue-a-chunk>
+    pub fn compress_and_enqueue_chunk(&self, cx: JSContext, chunk: HandleValue) -> Fallible<()> {
+        // Step 1.
+        let input = buffer_source_to_vec(cx, chunk)?;
+        // Steps 2-5.
+        let output = self.compressor.borrow_mut().compress(&input);
+        self.enqueue(output)
+    }
+
+    /// <https://compression.spec.whatwg.org/#compress-flush-and-enqueue>
+    pub fn compress_flush_and_enqueue(&self) -> Fallible<()> {
+        let output = self.compressor.borrow_mut().finish();
+        self.enqueue(output)
+    }
+}
+
+impl CompressionStreamMethods for CompressionStream {
+    // https://streams.spec.whatwg.org/#dom-generictransformstream-readable
+    fn Readable(&self, _cx: JSContext) -> NonNull<JSObject> {
+        self.transform().readable().get_js_stream()
+    }
+
+    // https://streams.spec.whatwg.org/#dom-generictransformstream-writable
+    fn Writable(&self) -> DomRoot<WritableStream> {
+        self.transform().writable()
+    }
+}
@@ -5,9 +5,11 @@
 use crate::dom::bindings::cell::DomRefCell;
 use crate::dom::bindings::codegen::Bindings::CryptoBinding::CryptoMethods;
 use crate::dom::bindings::error::{Error, Fallible};
-use crate::dom::bindings::reflector::{reflect_dom_object, Reflector};
-use crate::dom::bindings::root::DomRoot;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{DomRoot, MutNullableDom};
+use crate::dom::bindings::str::DOMString;
 use crate::dom::globalscope::GlobalScope;
+use crate::dom::subtlecrypto::SubtleCrypto;
 use crate::script_runtime::JSContext;
 use dom_struct::dom_struct;
 use js::jsapi::JSObject;
@@ -16,6 +18,7 @@ use js::rust::CustomAutoRooterGuard;
 use js::typedarray::ArrayBufferView;
 use servo_rand::{RngCore, ServoRng};
 use std::ptr::NonNull;
+use uuid::Uuid;
 
 unsafe_no_jsmanaged_fields!(ServoRng);
 
@@ -25,6 +28,7 @@ pub struct Crypto {
     reflector_: Reflector,
     #[ignore_malloc_size_of = "Defined in rand"]
     rng: DomRefCell<ServoRng>,
+    subtle: MutNullableDom<SubtleCrypto>,
 }
 
 impl Crypto {
@@ -32,6 +36,7 @@ impl Crypto {
         Crypto {
             reflector_: Reflector::new(),
             rng: DomRefCell::new(ServoRng::new()),
+            subtle: Default::default(),
         }
     }
 
@@ -62,6 +67,21 @@ impl CryptoMethods for Crypto {
 
         unsafe { Ok(NonNull::new_unchecked(*input.underlying_object())) }
     }
+
+    // https://w3c.github.io/webcrypto/#Crypto-attribute-subtle
+    fn Subtle(&self) -> DomRoot<SubtleCrypto> {
+        self.subtle.or_init(|| SubtleCrypto::new(&self.global()))
+    }
+
+    // https://w3c.github.io/webcrypto/#Crypto-method-randomUUID
+    fn RandomUUID(&self) -> DOMString {
+        let mut bytes = [0; 16];
+        self.rng.borrow_mut().fill_bytes(&mut bytes);
+        // Version 4, variant 1, see https://w3c.github.io/webcrypto/#dfn-generate-a-random-uuid.
+        bytes[6] = (bytes[6] & 0x0f) | 0x40;
+        bytes[8] = (bytes[8] & 0x3f) | 0x80;
+        DOMString::from(Uuid::from_bytes(bytes).to_hyphenated().to_string())
+    }
 }
 
 fn is_integer_buffer(array_type: Type) -> bool {
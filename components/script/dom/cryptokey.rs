@@ -0,0 +1,246 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::codegen::Bindings::CryptoKeyBinding::{
+    CryptoKeyMethods, KeyType, KeyTypeValues, KeyUsage, KeyUsageValues,
+};
+use crate::dom::bindings::conversions::ToJSValConvertible;
+use crate::dom::bindings::reflector::{reflect_dom_object, Reflector};
+use crate::dom::bindings::root::DomRoot;
+use crate::dom::bindings::serializable::{Serializable, StorageKey};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::bindings::structuredclone::StructuredDataHolder;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::subtlecrypto::KeyAlgorithm;
+use crate::script_runtime::JSContext;
+use dom_struct::dom_struct;
+use js::jsapi::{Heap, JSObject};
+use js::jsval::UndefinedValue;
+use openssl::pkey::{PKey, Private, Public};
+use script_traits::serializable::CryptoKeyImpl;
+use std::collections::HashMap;
+use std::ptr::{self, NonNull};
+
+/// The key material of a key.
+/// <https://w3c.github.io/webcrypto/#dfn-CryptoKey-slot-handle>
+#[derive(Clone)]
+pub enum Handle {
+    Secret(Vec<u8>),
+    Public(PKey<Public>),
+    Private(PKey<Private>),
+}
+
+unsafe_no_jsmanaged_fields!(Handle);
+
+/// The internal slots of a key, which operations run in parallel use.
+#[derive(Clone, JSTraceable, MallocSizeOf)]
+pub struct KeyData {
+    /// <https://w3c.github.io/webcrypto/#dfn-CryptoKey-slot-type>
+    pub key_type: KeyType,
+    /// <https://w3c.github.io/webcrypto/#dfn-CryptoKey-slot-extractable>
+    pub extractable: bool,
+    /// <https://w3c.github.io/webcrypto/#dfn-CryptoKey-slot-algorithm>
+    pub algorithm: KeyAlgorithm,
+    /// <https://w3c.github.io/webcrypto/#dfn-CryptoKey-slot-usages>
+    pub usages: Vec<KeyUsage>,
+    #[ignore_malloc_size_of = "Defined in openssl"]
+    pub handle: Handle,
+}
+
+impl KeyData {
+    pub fn has_usage(&self, usage: KeyUsage) -> bool {
+        self.usages.contains(&usage)
+    }
+}
+
+/// <https://w3c.github.io/webcrypto/#concept-usage-intersection>, the usages in
+/// both lists, in the order of the list of recognized key usage values.
+pub fn usage_intersection(usages: &[KeyUsage], allowed: &[KeyUsage]) -> Vec<KeyUsage> {
+    KeyUsageValues::pairs
+        .iter()
+        .map(|&(_, usage)| usage)
+        .filter(|usage| usages.contains(usage) && allowed.contains(usage))
+        .collect()
+}
+
+// https://w3c.github.io/webcrypto/#cryptokey-interface
+#[dom_struct]
+pub struct CryptoKey {
+    reflector_: Reflector,
+    data: KeyData,
+    /// <https://w3c.github.io/webcrypto/#dfn-CryptoKey-slot-algorithm_cached>
+    #[ignore_malloc_size_of = "Defined in rust-mozjs"]
+    algorithm_cached: Heap<*mut JSObject>,
+    /// <https://w3c.github.io/webcrypto/#dfn-CryptoKey-slot-usages_cached>
+    #[ignore_malloc_size_of = "Defined in rust-mozjs"]
+    usages_cached: Heap<*mut JSObject>,
+}
+
+impl CryptoKey {
+    fn new_inherited(data: KeyData) -> CryptoKey {
+        CryptoKey {
+            reflector_: Reflector::new(),
+            data,
+            algorithm_cached: Heap::default(),
+            usages_cached: Heap::default(),
+        }
+    }
+
+    pub fn new(global: &GlobalScope, data: KeyData) -> DomRoot<CryptoKey> {
+        reflect_dom_object(Box::new(CryptoKey::new_inherited(data)), global)
+    }
+
+    pub fn data(&self) -> &KeyData {
+        &self.data
+    }
+}
+
+impl CryptoKeyMethods for CryptoKey {
+    // https://w3c.github.io/webcrypto/#dom-cryptokey-type
+    fn Type(&self) -> KeyType {
+        self.data.key_type
+    }
+
+    // https://w3c.github.io/webcrypto/#dom-cryptokey-extractable
+    fn Extractable(&self) -> bool {
+        self.data.extractable
+    }
+
+    // https://w3c.github.io/webcrypto/#dom-cryptokey-algorithm
+    fn Algorithm(&self, cx: JSContext) -> NonNull<JSObject> {
+        if self.algorithm_cached.get().is_null() {
+            rooted!(in(*cx) let mut algorithm = ptr::null_mut::<JSObject>());
+            self.data.algorithm.to_js_object(cx, algorithm.handle_mut());
+            self.algorithm_cached.set(algorithm.get());
+        }
+        NonNull::new(self.algorithm_cached.get()).expect("The algorithm object is not null")
+    }
+
+    // https://w3c.github.io/webcrypto/#dom-cryptokey-usages
+    #[allow(unsafe_code)]
+    fn Usages(&self, cx: JSContext) -> NonNull<JSObject> {
+        if self.usages_cached.get().is_null() {
+            let usages: Vec<DOMString> = self
+                .data
+                .usages
+                .iter()
+                .map(|usage| DOMString::from(usage.as_str()))
+                .collect();
+            rooted!(in(*cx) let mut array = UndefinedValue());
+            unsafe {
+                usages.to_jsval(*cx, array.handle_mut());
+            }
+            self.usages_cached.set(array.to_object());
+        }
+        NonNull::new(self.usages_cached.get()).expect("The usages array is not null")
+    }
+}
+
+impl Serializable for CryptoKey {
+    /// <https://w3c.github.io/webcrypto/#cryptokey-interface-clone>
+    fn serialize(&self, sc_holder: &mut StructuredDataHolder) -> Result<StorageKey, ()> {
+        let crypto_keys = match sc_holder {
+            StructuredDataHolder::Write { crypto_keys, .. } => crypto_keys,
+            _ => panic!("Unexpected variant of StructuredDataHolder"),
+        };
+
+        // 1-4. Serialize the type, extractable, algorithm and usages slots.
+        // 5. Serialize the handle slot, as bytes openssl can read back.
+        let handle = match self.data.handle {
+            Handle::Secret(ref bytes) => bytes.clone(),
+            Handle::Public(ref key) => key.public_key_to_der().map_err(|_| ())?,
+            Handle::Private(ref key) => key.private_key_to_der().map_err(|_| ())?,
+        };
+        let crypto_key = CryptoKeyImpl {
+            key_type: self.data.key_type.as_str().to_owned(),
+            extractable: self.data.extractable,
+            algorithm: self.data.algorithm.to_impl(),
+            usages: self
+                .data
+                .usages
+                .iter()
+                .map(|usage| usage.as_str().to_owned())
+                .collect(),
+            handle,
+        };
+
+        // Store the slots at the next index.
+        let crypto_keys = crypto_keys.get_or_insert_with(|| HashMap::new());
+        let index = crypto_keys.len() as u32;
+        crypto_keys.insert(index, crypto_key);
+
+        Ok(StorageKey {
+            index,
+            name_space: 0,
+        })
+    }
+
+    /// <https://w3c.github.io/webcrypto/#cryptokey-interface-clone>
+    fn deserialize(
+        owner: &GlobalScope,
+        sc_holder: &mut StructuredDataHolder,
+        storage_key: StorageKey,
+    ) -> Result<(), ()> {
+        let crypto_key_impls = match sc_holder {
+            StructuredDataHolder::Read {
+                crypto_key_impls, ..
+            } => crypto_key_impls,
+            _ => panic!("Unexpected variant of StructuredDataHolder"),
+        };
+        let crypto_key = crypto_key_impls
+            .as_mut()
+            .and_then(|impls| impls.remove(&storage_key.index))
+            .ok_or(())?;
+
+        // 1-4. Deserialize the type, extractable, algorithm and usages slots.
+        let key_type = KeyTypeValues::pairs
+            .iter()
+            .find(|&&(name, _)| name == crypto_key.key_type)
+            .map(|&(_, key_type)| key_type)
+            .ok_or(())?;
+        let algorithm = KeyAlgorithm::from_impl(&crypto_key.algorithm).ok_or(())?;
+        let usages = crypto_key
+            .usages
+            .iter()
+            .map(|usage| {
+                KeyUsageValues::pairs
+                    .iter()
+                    .find(|&&(name, _)| name == usage)
+                    .map(|&(_, usage)| usage)
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or(())?;
+
+        // 5. Deserialize the handle slot.
+        let handle = match key_type {
+            KeyType::Secret => Handle::Secret(crypto_key.handle),
+            KeyType::Public => {
+                Handle::Public(PKey::public_key_from_der(&crypto_key.handle).map_err(|_| ())?)
+            },
+            KeyType::Private => {
+                Handle::Private(PKey::private_key_from_der(&crypto_key.handle).map_err(|_| ())?)
+            },
+        };
+
+        let deserialized_key = CryptoKey::new(
+            owner,
+            KeyData {
+                key_type,
+                extractable: crypto_key.extractable,
+                algorithm,
+                usages,
+                handle,
+            },
+        );
+
+        let crypto_keys = match sc_holder {
+            StructuredDataHolder::Read { crypto_keys, .. } => crypto_keys,
+            _ => panic!("Unexpected variant of StructuredDataHolder"),
+        };
+        let crypto_keys = crypto_keys.get_or_insert_with(|| HashMap::new());
+        crypto_keys.insert(storage_key, deserialized_key);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,451 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::DataTransferBinding::DataTransferMethods;
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot, MutNullableDom};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::bindings::utils::to_frozen_array;
+use crate::dom::blob::Blob;
+use crate::dom::datatransferitemlist::DataTransferItemList;
+use crate::dom::element::Element;
+use crate::dom::file::File;
+use crate::dom::filelist::FileList;
+use crate::dom::window::Window;
+use crate::script_runtime::JSContext;
+use dom_struct::dom_struct;
+use embedder_traits::{ClipboardData, ClipboardRepresentation};
+use js::jsval::JSVal;
+use script_traits::serializable::BlobImpl;
+use std::cell::Cell;
+
+/// <https://html.spec.whatwg.org/multipage/#drag-data-store-mode>
+#[derive(Clone, Copy, Debug, JSTraceable, MallocSizeOf, PartialEq)]
+pub enum DragDataStoreMode {
+    /// Used for the `dragstart` event. New data can be added to the store.
+    ReadWrite,
+    /// Used for the `drop` event. The data in the store can be read.
+    ReadOnly,
+    /// Used for all other events. Only the kinds and types of the items can be read.
+    Protected,
+}
+
+/// <https://html.spec.whatwg.org/multipage/#the-drag-data-item-kind>
+#[unrooted_must_root_lint::must_root]
+#[derive(JSTraceable, MallocSizeOf)]
+enum DragDataItemKind {
+    Text(DOMString),
+    File(Dom<File>),
+}
+
+/// An item of the drag data store item list.
+#[unrooted_must_root_lint::must_root]
+#[derive(JSTraceable, MallocSizeOf)]
+struct DragDataItem {
+    /// Identifies the item to its `DataTransferItem`, which outlives it.
+    id: u64,
+    kind: DragDataItemKind,
+    /// <https://html.spec.whatwg.org/multipage/#the-drag-data-item-type-string>
+    type_: DOMString,
+}
+
+/// The values that `dropEffect` can be set to.
+const DROP_EFFECTS: [&str; 4] = ["none", "copy", "link", "move"];
+
+/// The values that `effectAllowed` can be set to.
+const ALLOWED_EFFECTS: [&str; 9] = [
+    "none",
+    "copy",
+    "copyLink",
+    "copyMove",
+    "link",
+    "linkMove",
+    "move",
+    "all",
+    "uninitialized",
+];
+
+/// Converts a format passed to `getData`, `setData` or `clearData` to a type string,
+/// and whether the data should be treated as a URL.
+fn normalize_format(format: &str) -> (DOMString, bool) {
+    let format = format.to_ascii_lowercase();
+    match &*format {
+        "text" => (DOMString::from("text/plain"), false),
+        "url" => (DOMString::from("text/uri-list"), true),
+        _ => (DOMString::from(format), false),
+    }
+}
+
+#[dom_struct]
+pub struct DataTransfer {
+    reflector_: Reflector,
+    /// <https://html.spec.whatwg.org/multipage/#drag-data-store-item-list>
+    item_list: DomRefCell<Vec<DragDataItem>>,
+    next_item_id: Cell<u64>,
+    mode: Cell<DragDataStoreMode>,
+    drop_effect: DomRefCell<DOMString>,
+    effect_allowed: DomRefCell<DOMString>,
+    items: MutNullableDom<DataTransferItemList>,
+    /// The list returned by `files`, until the item list or the mode changes.
+    files: MutNullableDom<FileList>,
+}
+
+impl DataTransfer {
+    fn new_inherited(mode: DragDataStoreMode, effect_allowed: &str) -> DataTransfer {
+        DataTransfer {
+            reflector_: Reflector::new(),
+            item_list: DomRefCell::new(Vec::new()),
+            next_item_id: Cell::new(0),
+            mode: Cell::new(mode),
+            drop_effect: DomRefCell::new(DOMString::from("none")),
+            effect_allowed: DomRefCell::new(DOMString::from(effect_allowed)),
+            items: Default::default(),
+            files: Default::default(),
+        }
+    }
+
+    /// Creates the `DataTransfer` of a drag-and-drop operation, which is in read/write
+    /// mode until the `dragstart` event has been fired.
+    pub fn new_for_drag(window: &Window) -> DomRoot<DataTransfer> {
+        reflect_dom_object(
+            Box::new(DataTransfer::new_inherited(
+                DragDataStoreMode::ReadWrite,
+                "uninitialized",
+            )),
+            window,
+        )
+    }
+
+    /// Creates the `clipboardData` of a clipboard event.
+    pub fn new_for_clipboard(window: &Window, mode: DragDataStoreMode) -> DomRoot<DataTransfer> {
+        reflect_dom_object(Box::new(DataTransfer::new_inherited(mode, "none")), window)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-datatransfer
+    #[allow(non_snake_case)]
+    pub fn Constructor(window: &Window) -> DomRoot<DataTransfer> {
+        reflect_dom_object(
+            Box::new(DataTransfer::new_inherited(
+                DragDataStoreMode::ReadWrite,
+                "none",
+            )),
+            window,
+        )
+    }
+
+    pub fn mode(&self) -> DragDataStoreMode {
+        self.mode.get()
+    }
+
+    pub fn set_mode(&self, mode: DragDataStoreMode) {
+        self.mode.set(mode);
+        self.files.set(None);
+    }
+
+    pub fn set_drop_effect(&self, drop_effect: &str) {
+        *self.drop_effect.borrow_mut() = DOMString::from(drop_effect);
+    }
+
+    /// Sets `dropEffect` to the default for the current `effectAllowed`, before firing
+    /// `dragenter` or `dragover`.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#dndevents>
+    pub fn reset_drop_effect(&self) {
+        let drop_effect = match &**self.effect_allowed.borrow() {
+            "none" => "none",
+            "link" | "linkMove" => "link",
+            "move" => "move",
+            _ => "copy",
+        };
+        self.set_drop_effect(drop_effect);
+    }
+
+    /// The operation that a drop would perform, given the current `dropEffect` and
+    /// `effectAllowed`.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#current-drag-operation>
+    pub fn current_drag_operation(&self) -> &'static str {
+        let drop_effect = self.drop_effect.borrow();
+        let allowed = match &**self.effect_allowed.borrow() {
+            "all" | "uninitialized" => true,
+            "copyLink" => *drop_effect == "copy" || *drop_effect == "link",
+            "copyMove" => *drop_effect == "copy" || *drop_effect == "move",
+            "linkMove" => *drop_effect == "link" || *drop_effect == "move",
+            effect_allowed => *drop_effect == effect_allowed,
+        };
+        match DROP_EFFECTS.iter().find(|effect| *drop_effect == **effect) {
+            Some(effect) if allowed => *effect,
+            _ => "none",
+        }
+    }
+
+    #[allow(unrooted_must_root)]
+    fn push_item(&self, kind: DragDataItemKind, type_: DOMString) -> u64 {
+        let id = self.next_item_id.get();
+        self.next_item_id.set(id + 1);
+        self.item_list
+            .borrow_mut()
+            .push(DragDataItem { id, kind, type_ });
+        self.files.set(None);
+        id
+    }
+
+    /// Adds a text item, unless there is already a text item of the same type.
+    pub fn add_text_item(&self, type_: &str, data: DOMString) -> Option<u64> {
+        let type_ = DOMString::from(type_.to_ascii_lowercase());
+        let exists = self.item_list.borrow().iter().any(|item| match item.kind {
+            DragDataItemKind::Text(_) => item.type_ == type_,
+            DragDataItemKind::File(_) => false,
+        });
+        if exists {
+            return None;
+        }
+        Some(self.push_item(DragDataItemKind::Text(data), type_))
+    }
+
+    pub fn add_file_item(&self, file: &File) -> u64 {
+        let type_ = DOMString::from(file.upcast::<Blob>().type_string().to_ascii_lowercase());
+        self.push_item(DragDataItemKind::File(Dom::from_ref(file)), type_)
+    }
+
+    fn remove_text_items(&self, type_: Option<&DOMString>) {
+        self.item_list.borrow_mut().retain(|item| match item.kind {
+            DragDataItemKind::Text(_) => type_.map_or(false, |type_| item.type_ != *type_),
+            DragDataItemKind::File(_) => true,
+        });
+    }
+
+    /// Adds the contents of the system clipboard, as text items for the textual
+    /// representations and file items for the others, such as images.
+    pub fn add_clipboard_data(&self, data: ClipboardData) {
+        for representation in data.representations {
+            let ClipboardRepresentation { mime_type, data } = representation;
+            if mime_type.starts_with("text/") {
+                let text = DOMString::from(String::from_utf8_lossy(&data).into_owned());
+                self.add_text_item(&mime_type, text);
+                continue;
+            }
+            let name = DOMString::from(mime_type.replacen('/', ".", 1));
+            let blob_impl = BlobImpl::new_from_bytes(data, mime_type);
+            let file = File::new(&self.global(), blob_impl, name, None);
+            self.add_file_item(&file);
+        }
+    }
+
+    /// The items of the store, in the form that the system clipboard holds them.
+    pub fn clipboard_data(&self) -> ClipboardData {
+        let representations = self
+            .item_list
+            .borrow()
+            .iter()
+            .filter_map(|item| {
+                let data = match item.kind {
+                    DragDataItemKind::Text(ref text) => text.to_string().into_bytes(),
+                    DragDataItemKind::File(ref file) => file.upcast::<Blob>().get_bytes().ok()?,
+                };
+                Some(ClipboardRepresentation {
+                    mime_type: item.type_.to_string(),
+                    data,
+                })
+            })
+            .collect();
+        ClipboardData { representations }
+    }
+
+    pub fn item_count(&self) -> usize {
+        self.item_list.borrow().len()
+    }
+
+    pub fn item_id(&self, index: usize) -> Option<u64> {
+        self.item_list.borrow().get(index).map(|item| item.id)
+    }
+
+    pub fn has_item(&self, id: u64) -> bool {
+        self.item_list.borrow().iter().any(|item| item.id == id)
+    }
+
+    pub fn remove_item(&self, index: usize) {
+        let mut item_list = self.item_list.borrow_mut();
+        if index < item_list.len() {
+            item_list.remove(index);
+            self.files.set(None);
+        }
+    }
+
+    pub fn clear_items(&self) {
+        self.item_list.borrow_mut().clear();
+        self.files.set(None);
+    }
+
+    /// The kind of the item with this id, as returned by `DataTransferItem.kind`.
+    pub fn item_kind(&self, id: u64) -> Option<&'static str> {
+        self.item_list
+            .borrow()
+            .iter()
+            .find(|item| item.id == id)
+            .map(|item| match item.kind {
+                DragDataItemKind::Text(_) => "string",
+                DragDataItemKind::File(_) => "file",
+            })
+    }
+
+    pub fn item_type(&self, id: u64) -> Option<DOMString> {
+        self.item_list
+            .borrow()
+            .iter()
+            .find(|item| item.id == id)
+            .map(|item| item.type_.clone())
+    }
+
+    pub fn item_text(&self, id: u64) -> Option<DOMString> {
+        self.item_list
+            .borrow()
+            .iter()
+            .find(|item| item.id == id)
+            .and_then(|item| match item.kind {
+                DragDataItemKind::Text(ref data) => Some(data.clone()),
+                DragDataItemKind::File(_) => None,
+            })
+    }
+
+    pub fn item_file(&self, id: u64) -> Option<DomRoot<File>> {
+        self.item_list
+            .borrow()
+            .iter()
+            .find(|item| item.id == id)
+            .and_then(|item| match item.kind {
+                DragDataItemKind::Text(_) => None,
+                DragDataItemKind::File(ref file) => Some(DomRoot::from_ref(&**file)),
+            })
+    }
+}
+
+impl DataTransferMethods for DataTransfer {
+    // https://html.spec.whatwg.org/multipage/#dom-datatransfer-dropeffect
+    fn DropEffect(&self) -> DOMString {
+        self.drop_effect.borrow().clone()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-datatransfer-dropeffect
+    fn SetDropEffect(&self, value: DOMString) {
+        if DROP_EFFECTS.contains(&&*value) {
+            *self.drop_effect.borrow_mut() = value;
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-datatransfer-effectallowed
+    fn EffectAllowed(&self) -> DOMString {
+        self.effect_allowed.borrow().clone()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-datatransfer-effectallowed
+    fn SetEffectAllowed(&self, value: DOMString) {
+        if self.mode.get() == DragDataStoreMode::ReadWrite && ALLOWED_EFFECTS.contains(&&*value) {
+            *self.effect_allowed.borrow_mut() = value;
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-datatransfer-items
+    fn Items(&self) -> DomRoot<DataTransferItemList> {
+        self.items
+            .or_init(|| DataTransferItemList::new(self.global().as_window(), self))
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-datatransfer-setdragimage
+    fn SetDragImage(&self, _image: &Element, _x: i32, _y: i32) {
+        // Servo doesn't render feedback for drags, so the drag image is never used.
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-datatransfer-types
+    fn Types(&self, cx: JSContext) -> JSVal {
+        let mut types = vec![];
+        let mut has_files = false;
+        for item in self.item_list.borrow().iter() {
+            match item.kind {
+                DragDataItemKind::Text(_) => types.push(item.type_.clone()),
+                DragDataItemKind::File(_) => has_files = true,
+            }
+        }
+        if has_files {
+            types.push(DOMString::from("Files"));
+        }
+        to_frozen_array(types.as_slice(), cx)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-datatransfer-getdata
+    fn GetData(&self, format: DOMString) -> DOMString {
+        if self.mode.get() == DragDataStoreMode::Protected {
+            return DOMString::new();
+        }
+
+        let (type_, convert_to_url) = normalize_format(&format);
+        let data = self
+            .item_list
+            .borrow()
+            .iter()
+            .find_map(|item| match item.kind {
+                DragDataItemKind::Text(ref data) if item.type_ == type_ => Some(data.clone()),
+                _ => None,
+            });
+        let data = match data {
+            Some(data) => data,
+            None => return DOMString::new(),
+        };
+
+        if convert_to_url {
+            // Return the first URL of the list, skipping comments.
+            return data
+                .lines()
+                .map(str::trim)
+                .find(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(DOMString::from)
+                .unwrap_or_default();
+        }
+        data
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-datatransfer-setdata
+    fn SetData(&self, format: DOMString, data: DOMString) {
+        if self.mode.get() != DragDataStoreMode::ReadWrite {
+            return;
+        }
+
+        let (type_, _) = normalize_format(&format);
+        self.remove_text_items(Some(&type_));
+        self.push_item(DragDataItemKind::Text(data), type_);
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-datatransfer-cleardata
+    fn ClearData(&self, format: Option<DOMString>) {
+        if self.mode.get() != DragDataStoreMode::ReadWrite {
+            return;
+        }
+
+        match format {
+            Some(format) => self.remove_text_items(Some(&normalize_format(&format).0)),
+            None => self.remove_text_items(None),
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-datatransfer-files
+    fn Files(&self) -> DomRoot<FileList> {
+        self.files.or_init(|| {
+            let files = if self.mode.get() == DragDataStoreMode::Protected {
+                vec![]
+            } else {
+                self.item_list
+                    .borrow()
+                    .iter()
+                    .filter_map(|item| match item.kind {
+                        DragDataItemKind::Text(_) => None,
+                        DragDataItemKind::File(ref file) => Some(DomRoot::from_ref(&**file)),
+                    })
+                    .collect()
+            };
+            FileList::new(&self.global(), files)
+        })
+    }
+}
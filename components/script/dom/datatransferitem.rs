@@ -0,0 +1,119 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::callback::ExceptionHandling;
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::DataTransferItemBinding::{
+    DataTransferItemMethods, FunctionStringCallback,
+};
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::refcounted::Trusted;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::datatransfer::{DataTransfer, DragDataStoreMode};
+use crate::dom::file::File;
+use crate::dom::window::Window;
+use crate::task_source::TaskSource;
+use dom_struct::dom_struct;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+// https://html.spec.whatwg.org/multipage/#the-datatransferitem-interface
+#[dom_struct]
+pub struct DataTransferItem {
+    reflector_: Reflector,
+    data_transfer: Dom<DataTransfer>,
+    /// The id of the item of the drag data store that this object represents. The
+    /// object is disabled once the item has been removed from the store.
+    id: u64,
+    /// Callbacks passed to `getAsString`, with the data to call them with, which are
+    /// soon to be invoked by a queued task.
+    #[ignore_malloc_size_of = "callbacks are hard"]
+    pending_string_callbacks: DomRefCell<VecDeque<(Rc<FunctionStringCallback>, DOMString)>>,
+}
+
+impl DataTransferItem {
+    fn new_inherited(data_transfer: &DataTransfer, id: u64) -> DataTransferItem {
+        DataTransferItem {
+            reflector_: Reflector::new(),
+            data_transfer: Dom::from_ref(data_transfer),
+            id,
+            pending_string_callbacks: DomRefCell::new(VecDeque::new()),
+        }
+    }
+
+    pub fn new(
+        window: &Window,
+        data_transfer: &DataTransfer,
+        id: u64,
+    ) -> DomRoot<DataTransferItem> {
+        reflect_dom_object(
+            Box::new(DataTransferItem::new_inherited(data_transfer, id)),
+            window,
+        )
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    fn invoke_pending_string_callback(&self) {
+        let pending = self.pending_string_callbacks.borrow_mut().pop_front();
+        if let Some((callback, data)) = pending {
+            let _ = callback.Call__(data, ExceptionHandling::Report);
+        }
+    }
+}
+
+impl DataTransferItemMethods for DataTransferItem {
+    // https://html.spec.whatwg.org/multipage/#dom-datatransferitem-kind
+    fn Kind(&self) -> DOMString {
+        self.data_transfer
+            .item_kind(self.id)
+            .map(DOMString::from)
+            .unwrap_or_default()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-datatransferitem-type
+    fn Type(&self) -> DOMString {
+        self.data_transfer.item_type(self.id).unwrap_or_default()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-datatransferitem-getasstring
+    fn GetAsString(&self, callback: Option<Rc<FunctionStringCallback>>) {
+        let callback = match callback {
+            Some(callback) => callback,
+            None => return,
+        };
+        if self.data_transfer.mode() == DragDataStoreMode::Protected {
+            return;
+        }
+        let data = match self.data_transfer.item_text(self.id) {
+            Some(data) => data,
+            None => return,
+        };
+
+        self.pending_string_callbacks
+            .borrow_mut()
+            .push_back((callback, data));
+        let global = self.global();
+        let window = global.as_window();
+        let this = Trusted::new(self);
+        let _ = window.task_manager().dom_manipulation_task_source().queue(
+            task!(invoke_string_callback: move || {
+                this.root().invoke_pending_string_callback();
+            }),
+            window.upcast(),
+        );
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-datatransferitem-getasfile
+    fn GetAsFile(&self) -> Option<DomRoot<File>> {
+        if self.data_transfer.mode() == DragDataStoreMode::Protected {
+            return None;
+        }
+        self.data_transfer.item_file(self.id)
+    }
+}
@@ -0,0 +1,109 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::DataTransferItemListBinding::DataTransferItemListMethods;
+use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::datatransfer::{DataTransfer, DragDataStoreMode};
+use crate::dom::datatransferitem::DataTransferItem;
+use crate::dom::file::File;
+use crate::dom::window::Window;
+use dom_struct::dom_struct;
+
+// https://html.spec.whatwg.org/multipage/#the-datatransferitemlist-interface
+#[dom_struct]
+pub struct DataTransferItemList {
+    reflector_: Reflector,
+    data_transfer: Dom<DataTransfer>,
+    /// The `DataTransferItem` objects that have been handed out for items of the
+    /// drag data store, so that the same object is returned for the same item.
+    items: DomRefCell<Vec<Dom<DataTransferItem>>>,
+}
+
+impl DataTransferItemList {
+    fn new_inherited(data_transfer: &DataTransfer) -> DataTransferItemList {
+        DataTransferItemList {
+            reflector_: Reflector::new(),
+            data_transfer: Dom::from_ref(data_transfer),
+            items: DomRefCell::new(Vec::new()),
+        }
+    }
+
+    pub fn new(window: &Window, data_transfer: &DataTransfer) -> DomRoot<DataTransferItemList> {
+        reflect_dom_object(
+            Box::new(DataTransferItemList::new_inherited(data_transfer)),
+            window,
+        )
+    }
+
+    fn item(&self, id: u64) -> DomRoot<DataTransferItem> {
+        let mut items = self.items.borrow_mut();
+        // Items removed from the store are disabled, and won't be returned again.
+        items.retain(|item| self.data_transfer.has_item(item.id()));
+        if let Some(item) = items.iter().find(|item| item.id() == id) {
+            return DomRoot::from_ref(&**item);
+        }
+        let item = DataTransferItem::new(self.global().as_window(), &self.data_transfer, id);
+        items.push(Dom::from_ref(&*item));
+        item
+    }
+}
+
+impl DataTransferItemListMethods for DataTransferItemList {
+    // https://html.spec.whatwg.org/multipage/#dom-datatransferitemlist-length
+    fn Length(&self) -> u32 {
+        self.data_transfer.item_count() as u32
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-datatransferitemlist-item
+    fn IndexedGetter(&self, index: u32) -> Option<DomRoot<DataTransferItem>> {
+        self.data_transfer
+            .item_id(index as usize)
+            .map(|id| self.item(id))
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-datatransferitemlist-add
+    fn Add(
+        &self,
+        data: DOMString,
+        type_: DOMString,
+    ) -> Fallible<Option<DomRoot<DataTransferItem>>> {
+        if self.data_transfer.mode() != DragDataStoreMode::ReadWrite {
+            return Ok(None);
+        }
+        match self.data_transfer.add_text_item(&type_, data) {
+            Some(id) => Ok(Some(self.item(id))),
+            None => Err(Error::NotSupported),
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-datatransferitemlist-add
+    fn Add_(&self, data: &File) -> Fallible<Option<DomRoot<DataTransferItem>>> {
+        if self.data_transfer.mode() != DragDataStoreMode::ReadWrite {
+            return Ok(None);
+        }
+        let id = self.data_transfer.add_file_item(data);
+        Ok(Some(self.item(id)))
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-datatransferitemlist-remove
+    fn Remove(&self, index: u32) -> Fallible<()> {
+        if self.data_transfer.mode() != DragDataStoreMode::ReadWrite {
+            return Err(Error::InvalidState);
+        }
+        self.data_transfer.remove_item(index as usize);
+        Ok(())
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-datatransferitemlist-clear
+    fn Clear(&self) {
+        if self.data_transfer.mode() != DragDataStoreMode::ReadWrite {
+            return;
+        }
+        self.data_transfer.clear_items();
+    }
+}
@@ -0,0 +1,184 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::CompressionStreamBinding::CompressionFormat;
+use crate::dom::bindings::codegen::Bindings::DecompressionStreamBinding::DecompressionStreamMethods;
+use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::reflector::{reflect_dom_object, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot, MutNullableDom};
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::transformstream::TransformStream;
+use crate::dom::transformstreamdefaultcontroller::{buffer_source_to_vec, TransformerType};
+use crate::dom::writablestream::WritableStream;
+use crate::script_runtime::JSContext;
+use dom_struct::dom_struct;
+use flate2::write::GzDecoder;
+use flate2::{Decompress, FlushDecompress, Status};
+use js::jsapi::JSObject;
+use js::rust::HandleValue;
+use std::io::Write;
+use std::mem;
+use std::ptr::NonNull;
+
+/// The size by which the output buffer grows while inflating.
+const OUTPUT_CHUNK_SIZE: usize = 32 * 1024;
+
+/// The decompression context of a stream.
+enum Decompressor {
+    /// The "deflate" and "deflate-raw" formats, for which the end of the compressed data is
+    /// tracked, to reject truncated input and input after the end.
+    Deflate {
+        inflater: Decompress,
+        ended: bool,
+    },
+    Gzip(GzDecoder<Vec<u8>>),
+}
+
+unsafe_no_jsmanaged_fields!(Decompressor);
+
+impl Decompressor {
+    fn new(format: CompressionFormat) -> Decompressor {
+        let deflate = |zlib_header| Decompressor::Deflate {
+            inflater: Decompress::new(zlib_header),
+            ended: false,
+        };
+        match format {
+            CompressionFormat::Deflate => deflate(true),
+            CompressionFormat::Deflate_raw => deflate(false),
+            CompressionFormat::Gzip => Decompressor::Gzip(GzDecoder::new(Vec::new())),
+        }
+    }
+
+    /// Decompresses `input`, and returns the output produced so far.
+    fn decompress(&mut self, mut input: &[u8]) -> Result<Vec<u8>, ()> {
+        let (inflater, ended) = match *self {
+            Decompressor::Deflate {
+                ref mut inflater,
+                ref mut ended,
+            } => (inflater, ended),
+            Decompressor::Gzip(ref mut decoder) => {
+                decoder.write_all(input).map_err(|_| ())?;
+                return Ok(mem::take(decoder.get_mut()));
+            },
+        };
+        let mut output = Vec::new();
+        loop {
+            if *ended {
+                // Data after the end of the compressed data is an error.
+                return if input.is_empty() {
+                    Ok(output)
+                } else {
+                    Err(())
+                };
+            }
+            output.reserve(OUTPUT_CHUNK_SIZE);
+            let (total_in, total_out) = (inflater.total_in(), inflater.total_out());
+            let status = inflater
+                .decompress_vec(input, &mut output, FlushDecompress::None)
+                .map_err(|_| ())?;
+            let read = (inflater.total_in() - total_in) as usize;
+            let written = inflater.total_out() - total_out;
+            input = &input[read..];
+            match status {
+                Status::StreamEnd => *ended = true,
+                // The inflater wants more input, since it had room left for its output.
+                _ if input.is_empty() && output.len() < output.capacity() => return Ok(output),
+                _ if read == 0 && written == 0 => return Ok(output),
+                _ => {},
+            }
+        }
+    }
+
+    /// Checks that the compressed data is complete, and returns the rest of the output.
+    fn finish(&mut self) -> Result<Vec<u8>, ()> {
+        match *self {
+            Decompressor::Deflate { ended: true, .. } => Ok(vec![]),
+            Decompressor::Deflate { ended: false, .. } => Err(()),
+            Decompressor::Gzip(ref mut decoder) => {
+                decoder.try_finish().map_err(|_| ())?;
+                Ok(mem::take(decoder.get_mut()))
+            },
+        }
+    }
+}
+
+// https://compression.spec.whatwg.org/#decompressionstream
+#[dom_struct]
+pub struct DecompressionStream {
+    reflector_: Reflector,
+    #[ignore_malloc_size_of = "Defined in flate2"]
+    decompressor: DomRefCell<Decompressor>,
+    /// <https://streams.spec.whatwg.org/#generictransformstream-transform>
+    transform: MutNullableDom<TransformStream>,
+}
+
+impl DecompressionStream {
+    fn new_inherited(format: CompressionFormat) -> DecompressionStream {
+        DecompressionStream {
+            reflector_: Reflector::new(),
+            decompressor: DomRefCell::new(Decompressor::new(format)),
+            transform: Default::default(),
+        }
+    }
+
+    // https://compression.spec.whatwg.org/#dom-decompressionstream-decompressionstream
+    #[allow(non_snake_case)]
+    pub fn Constructor(
+        global: &GlobalScope,
+        format: CompressionFormat,
+    ) -> Fallible<DomRoot<DecompressionStream>> {
+        // Steps 1-2.
+        let stream =
+            reflect_dom_object(Box::new(DecompressionStream::new_inherited(format)), global);
+        // Steps 3-6.
+        let transform = TransformStream::new_with_transformer(
+            global,
+            TransformerType::Decompression(Dom::from_ref(&*stream)),
+        )?;
+        stream.transform.set(Some(&transform));
+        Ok(stream)
+    }
+
+    fn transform(&self) -> DomRoot<TransformStream> {
+        self.transform.get().expect("The stream was constructed")
+    }
+
+    /// Enqueues the decompressed `output`, unless it is empty.
+    fn enqueue(&self, output: Result<Vec<u8>, ()>) -> Fallible<()> {
+        let output =
+            output.map_err(|()| Error::Type("The compressed data is invalid".to_owned()))?;
+        if output.is_empty() {
+            return Ok(());
+        }
+        self.transform().enqueue_bytes(&output)
+    }
+
+    /// <https://compression.spec.whatwg.org/#decompress-and-enqueue-a-chunk>
+    pub fn decompress_and_enqueue_chunk(&self, cx: JSContext, chunk: HandleValue) -> Fallible<()> {
+        // Step 1.
+        let input = buffer_source_to_vec(cx, chunk)?;
+        // Steps 2-6.
+        let output = self.decompressor.borrow_mut().decompress(&input);
+        self.enqueue(output)
+    }
+
+    /// <https://compression.spec.whatwg.org/#decompress-flush-and-enqueue>
+    pub fn decompress_flush_and_enqueue(&self) -> Fallible<()> {
+        let output = self.decompressor.borrow_mut().finish();
+        self.enqueue(output)
+    }
+}
+
+impl DecompressionStreamMethods for DecompressionStream {
+    // https://streams.spec.whatwg.org/#dom-generictransformstream-readable
+    fn Readable(&self, _cx: JSContext) -> NonNull<JSObject> {
+        self.transform().readable().get_js_stream()
+    }
+
+    // https://streams.spec.whatwg.org/#dom-generictransformstream-writable
+    fn Writable(&self) -> DomRoot<WritableStream> {
+        self.transform().writable()
+    }
+}
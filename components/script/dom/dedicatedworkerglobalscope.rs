@@ -18,6 +18,7 @@ use crate::dom::bindings::root::{DomRoot, RootCollection, ThreadLocalStackRoots}
 use crate::dom::bindings::str::DOMString;
 use crate::dom::bindings::structuredclone;
 use crate::dom::bindings::trace::RootedTraceableBox;
+use crate::dom::console::Console;
 use crate::dom::errorevent::ErrorEvent;
 use crate::dom::event::{Event, EventBubbles, EventCancelable, EventStatus};
 use crate::dom::eventtarget::EventTarget;
@@ -49,7 +50,7 @@ use msg::constellation_msg::{BrowsingContextId, PipelineId, TopLevelBrowsingCont
 use net_traits::image_cache::ImageCache;
 use net_traits::request::{CredentialsMode, Destination, ParserMetadata};
 use net_traits::request::{Referrer, RequestBuilder, RequestMode};
-use net_traits::IpcSend;
+use net_traits::{embedder_policy_from_headers, IpcSend};
 use parking_lot::Mutex;
 use script_traits::{WorkerGlobalScopeInit, WorkerScriptLoadOrigin};
 use servo_rand::random;
@@ -335,6 +336,9 @@ impl DedicatedWorkerGlobalScope {
         let referrer = current_global.get_referrer();
         let parent = current_global.runtime_handle();
         let current_global_https_state = current_global.get_https_state();
+        let owner_embedder_policy = current_global.get_embedder_policy();
+        // Dedicated workers are secure contexts if their owner is.
+        let is_secure_context = current_global.is_secure_context();
 
         thread::Builder::new()
             .name(name)
@@ -366,6 +370,7 @@ impl DedicatedWorkerGlobalScope {
                     .use_url_credentials(true)
                     .pipeline_id(Some(pipeline_id))
                     .referrer_policy(referrer_policy)
+                    .embedder_policy(owner_embedder_policy)
                     .origin(origin);
 
                 let runtime = unsafe {
@@ -429,6 +434,48 @@ impl DedicatedWorkerGlobalScope {
                     },
                     Ok((metadata, bytes)) => (metadata, bytes),
                 };
+
+                // https://html.spec.whatwg.org/multipage/#check-a-global-object's-embedder-policy
+                let embedder_policy = if metadata.final_url.is_local_scheme() {
+                    owner_embedder_policy
+                } else {
+                    metadata
+                        .headers
+                        .as_ref()
+                        .map(|headers| embedder_policy_from_headers(headers, is_secure_context))
+                        .unwrap_or_default()
+                };
+                if owner_embedder_policy.is_compatible_with_cross_origin_isolation() &&
+                    !embedder_policy.is_compatible_with_cross_origin_isolation()
+                {
+                    let message = format!(
+                        "Blocked worker script {} by Cross-Origin-Embedder-Policy",
+                        serialized_worker_url
+                    );
+                    warn!("{}", message);
+                    let owner = worker.clone();
+                    parent_sender
+                        .send(CommonScriptMsg::Task(
+                            WorkerEvent,
+                            Box::new(task!(report_blocked_worker_script: move || {
+                                let worker = owner.root();
+                                Console::Error(&worker.global(), vec![DOMString::from(message)]);
+                            })),
+                            Some(pipeline_id),
+                            TaskSourceName::DOMManipulation,
+                        ))
+                        .unwrap();
+                    parent_sender
+                        .send(CommonScriptMsg::Task(
+                            WorkerEvent,
+                            Box::new(SimpleWorkerErrorHandler::new(worker)),
+                            Some(pipeline_id),
+                            TaskSourceName::DOMManipulation,
+                        ))
+                        .unwrap();
+                    return;
+                }
+                global_scope.set_embedder_policy(embedder_policy);
                 scope.set_url(metadata.final_url);
                 global_scope.set_https_state(metadata.https_state);
                 let source = String::from_utf8_lossy(&bytes);
@@ -4,6 +4,7 @@
 
 use crate::animation_timeline::AnimationTimeline;
 use crate::animations::Animations;
+use crate::clipboard_provider;
 use crate::document_loader::{DocumentLoader, LoadType};
 use crate::dom::attr::Attr;
 use crate::dom::beforeunloadevent::BeforeUnloadEvent;
@@ -21,13 +22,14 @@ use crate::dom::bindings::codegen::Bindings::NavigatorBinding::NavigatorBinding:
 use crate::dom::bindings::codegen::Bindings::NodeBinding::NodeMethods;
 use crate::dom::bindings::codegen::Bindings::NodeFilterBinding::NodeFilter;
 use crate::dom::bindings::codegen::Bindings::PerformanceBinding::PerformanceMethods;
+use crate::dom::bindings::codegen::Bindings::SelectionBinding::SelectionMethods;
 use crate::dom::bindings::codegen::Bindings::ShadowRootBinding::ShadowRootMethods;
 use crate::dom::bindings::codegen::Bindings::TouchBinding::TouchMethods;
 use crate::dom::bindings::codegen::Bindings::WindowBinding::{
     FrameRequestCallback, ScrollBehavior, WindowMethods,
 };
 use crate::dom::bindings::codegen::UnionTypes::{NodeOrString, StringOrElementCreationOptions};
-use crate::dom::bindings::error::{Error, ErrorResult, Fallible};
+use crate::dom::bindings::error::{Error, ErrorInfo, ErrorResult, Fallible};
 use crate::dom::bindings::inheritance::{Castable, ElementTypeId, HTMLElementTypeId, NodeTypeId};
 use crate::dom::bindings::num::Finite;
 use crate::dom::bindings::refcounted::{Trusted, TrustedPromise};
@@ -39,21 +41,25 @@ use crate::dom::bindings::xmlname::{
     namespace_from_domstring, validate_and_extract, xml_name_type,
 };
 use crate::dom::cdatasection::CDATASection;
+use crate::dom::clipboardevent::{ClipboardEvent, ClipboardEventType};
 use crate::dom::comment::Comment;
 use crate::dom::compositionevent::CompositionEvent;
 use crate::dom::cssstylesheet::CSSStyleSheet;
 use crate::dom::customelementregistry::CustomElementDefinition;
 use crate::dom::customevent::CustomEvent;
+use crate::dom::datatransfer::{DataTransfer, DragDataStoreMode};
 use crate::dom::documentfragment::DocumentFragment;
 use crate::dom::documentorshadowroot::{DocumentOrShadowRoot, StyleSheetInDocument};
 use crate::dom::documenttype::DocumentType;
 use crate::dom::domimplementation::DOMImplementation;
+use crate::dom::dragevent::DragEvent;
 use crate::dom::element::CustomElementCreationMode;
 use crate::dom::element::{
     Element, ElementCreator, ElementPerformFullscreenEnter, ElementPerformFullscreenExit,
 };
 use crate::dom::event::{Event, EventBubbles, EventCancelable, EventDefault, EventStatus};
 use crate::dom::eventtarget::EventTarget;
+use crate::dom::file::File;
 use crate::dom::focusevent::FocusEvent;
 use crate::dom::globalscope::GlobalScope;
 use crate::dom::gpucanvascontext::{GPUCanvasContext, WebGPUContextId};
@@ -63,6 +69,7 @@ use crate::dom::htmlareaelement::HTMLAreaElement;
 use crate::dom::htmlbaseelement::HTMLBaseElement;
 use crate::dom::htmlbodyelement::HTMLBodyElement;
 use crate::dom::htmlcollection::{CollectionFilter, HTMLCollection};
+use crate::dom::htmldialogelement::HTMLDialogElement;
 use crate::dom::htmlelement::HTMLElement;
 use crate::dom::htmlembedelement::HTMLEmbedElement;
 use crate::dom::htmlformelement::{FormControl, FormControlElementHelpers, HTMLFormElement};
@@ -74,6 +81,7 @@ use crate::dom::htmlinputelement::HTMLInputElement;
 use crate::dom::htmlscriptelement::{HTMLScriptElement, ScriptResult};
 use crate::dom::htmltextareaelement::HTMLTextAreaElement;
 use crate::dom::htmltitleelement::HTMLTitleElement;
+use crate::dom::intersectionobserver::{intersects_lazy_load_root, IntersectionObserver};
 use crate::dom::keyboardevent::KeyboardEvent;
 use crate::dom::location::Location;
 use crate::dom::messageevent::MessageEvent;
@@ -83,9 +91,12 @@ use crate::dom::node::{Node, NodeDamage, NodeFlags, ShadowIncluding};
 use crate::dom::nodeiterator::NodeIterator;
 use crate::dom::nodelist::NodeList;
 use crate::dom::pagetransitionevent::PageTransitionEvent;
+use crate::dom::pointerevent::{touch_pointer_id, PointerData, PointerEvent, PointerType};
+use crate::dom::pointerevent::{MOUSE_POINTER_ID, PEN_POINTER_ID};
 use crate::dom::processinginstruction::ProcessingInstruction;
 use crate::dom::promise::Promise;
 use crate::dom::range::Range;
+use crate::dom::resizeobserver::ResizeObserver;
 use crate::dom::selection::Selection;
 use crate::dom::servoparser::ServoParser;
 use crate::dom::shadowroot::ShadowRoot;
@@ -102,11 +113,13 @@ use crate::dom::webglrenderingcontext::WebGLRenderingContext;
 use crate::dom::wheelevent::WheelEvent;
 use crate::dom::window::{ReflowReason, Window};
 use crate::dom::windowproxy::WindowProxy;
+use crate::editing::{self, EditingState};
 use crate::fetch::FetchCanceller;
 use crate::realms::{AlreadyInRealm, InRealm};
 use crate::script_runtime::JSContext;
 use crate::script_runtime::{CommonScriptMsg, ScriptThreadEventCategory};
 use crate::script_thread::{MainThreadScriptMsg, ScriptThread};
+use crate::security_manager::{sandboxing_flags_from_csp_list, SandboxingFlagSet};
 use crate::stylesheet_set::StylesheetSetRef;
 use crate::task::TaskBox;
 use crate::task_source::{TaskSource, TaskSourceName};
@@ -116,26 +129,30 @@ use content_security_policy::{self as csp, CspList};
 use cookie::Cookie;
 use devtools_traits::ScriptToDevtoolsControlMsg;
 use dom_struct::dom_struct;
-use embedder_traits::EmbedderMsg;
+use embedder_traits::{ClipboardData, EmbedderMsg, SecurityState};
 use encoding_rs::{Encoding, UTF_8};
 use euclid::default::{Point2D, Rect, Size2D};
 use html5ever::{LocalName, Namespace, QualName};
 use hyper_serde::Serde;
 use ipc_channel::ipc::{self, IpcSender};
 use js::jsapi::{JSObject, JSRuntime};
-use keyboard_types::{Code, Key, KeyState};
+use js::rust::HandleValue;
+use keyboard_types::{Code, Key, KeyState, Modifiers};
 use metrics::{
     InteractiveFlag, InteractiveMetrics, InteractiveWindow, ProfilerMetadataFactory,
     ProgressiveWebMetric,
 };
 use mime::{self, Mime};
 use msg::constellation_msg::BrowsingContextId;
+use net_traits::blob_url_store::get_blob_origin;
+use net_traits::filemanager_thread::FileManagerThreadMsg;
 use net_traits::pub_domains::is_pub_domain;
 use net_traits::request::RequestBuilder;
 use net_traits::response::HttpsState;
 use net_traits::CookieSource::NonHTTP;
+use net_traits::CoreResourceMsg::ToFileManager;
 use net_traits::CoreResourceMsg::{GetCookiesForUrl, SetCookiesForUrl};
-use net_traits::{FetchResponseMsg, IpcSend, ReferrerPolicy};
+use net_traits::{FetchResponseMsg, IpcSend, Metadata, ReferrerPolicy};
 use num_traits::ToPrimitive;
 use percent_encoding::percent_decode;
 use profile_traits::ipc as profile_ipc;
@@ -144,8 +161,10 @@ use ref_slice::ref_slice;
 use script_layout_interface::message::{Msg, PendingRestyle, ReflowGoal};
 use script_layout_interface::TrustedNodeAddress;
 use script_traits::{AnimationState, DocumentActivity, MouseButton, MouseEventType};
+use script_traits::{DragData, DragEventType};
 use script_traits::{
-    MsDuration, ScriptMsg, TouchEventType, TouchId, UntrustedNodeAddress, WheelDelta,
+    MsDuration, PenState, ScriptMsg, TouchActions, TouchEventType, TouchId, UntrustedNodeAddress,
+    WheelDelta,
 };
 use servo_arc::Arc;
 use servo_atoms::Atom;
@@ -159,11 +178,13 @@ use std::collections::hash_map::Entry::{Occupied, Vacant};
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::default::Default;
 use std::mem;
+use std::path::PathBuf;
 use std::ptr::NonNull;
 use std::rc::Rc;
 use std::time::{Duration, Instant};
 use style::attr::AttrValue;
 use style::context::QuirksMode;
+use style::element_state::ElementState;
 use style::invalidation::element::restyle_hints::RestyleHint;
 use style::media_queries::{Device, MediaType};
 use style::selector_parser::Snapshot;
@@ -185,7 +206,9 @@ const SPURIOUS_ANIMATION_FRAME_THRESHOLD: u8 = 5;
 const FAKE_REQUEST_ANIMATION_FRAME_DELAY: u64 = 16;
 
 pub enum TouchEventResult {
-    Processed(bool),
+    /// Whether the default action is allowed, and the touch behaviors that the
+    /// `touch-action` of the touch's target allows.
+    Processed(bool, TouchActions),
     Forwarded,
 }
 
@@ -210,12 +233,127 @@ impl FireMouseEventType {
     }
 }
 
+/// The targets of the `mouseenter`/`mouseleave` style events for a pointer moving between
+/// `event_target` and `related_target`, in dispatch order.
+fn enter_leave_event_targets(
+    event_target: DomRoot<Node>,
+    related_target: Option<&Node>,
+    entering: bool,
+) -> Vec<DomRoot<Node>> {
+    let common_ancestor = match related_target {
+        Some(related_target) => event_target
+            .common_ancestor(related_target, ShadowIncluding::No)
+            .unwrap_or_else(|| DomRoot::from_ref(&*event_target)),
+        None => DomRoot::from_ref(&*event_target),
+    };
+
+    // We need to create a target chain in case the event target shares
+    // its boundaries with its ancestors.
+    let mut targets = vec![];
+    let mut current = Some(event_target);
+    while let Some(node) = current {
+        if node == common_ancestor {
+            break;
+        }
+        current = node.GetParentNode();
+        targets.push(node);
+    }
+
+    // The order for dispatching enter events starts from the topmost
+    // common ancestor of the event target and the related target.
+    if entering {
+        targets.reverse();
+    }
+    targets
+}
+
 #[derive(Clone, Copy, Debug, JSTraceable, MallocSizeOf, PartialEq)]
 pub enum IsHTMLDocument {
     HTMLDocument,
     NonHTMLDocument,
 }
 
+/// <https://w3c.github.io/pointerevents/#dfn-active-pointer>
+#[derive(JSTraceable, MallocSizeOf)]
+#[unrooted_must_root_lint::must_root]
+struct ActivePointer {
+    pointer_type: PointerType,
+    is_primary: bool,
+    /// Whether any of the pointer's buttons are pressed, or it is touching the screen.
+    active_buttons: bool,
+    /// The element the pointer was last over, used to fire boundary events.
+    over_target: Option<Dom<Element>>,
+    /// <https://w3c.github.io/pointerevents/#dfn-pointer-capture-target-override>
+    capture_target: Option<Dom<Element>>,
+    /// <https://w3c.github.io/pointerevents/#dfn-pending-pointer-capture-target-override>
+    pending_capture_target: Option<Dom<Element>>,
+    /// Whether `pointerdown` was canceled, which suppresses compatibility mouse events
+    /// until the pointer is released.
+    ///
+    /// <https://w3c.github.io/pointerevents/#compatibility-mapping-with-mouse-events>
+    compatibility_mouse_events_prevented: bool,
+}
+
+impl ActivePointer {
+    fn new(pointer_type: PointerType, is_primary: bool) -> ActivePointer {
+        ActivePointer {
+            pointer_type,
+            is_primary,
+            active_buttons: false,
+            over_target: None,
+            capture_target: None,
+            pending_capture_target: None,
+            compatibility_mouse_events_prevented: false,
+        }
+    }
+}
+
+/// How far, in CSS pixels, the mouse has to move with the button pressed to start
+/// dragging an element.
+const DRAG_THRESHOLD: f32 = 4.;
+
+/// A draggable element that the mouse button was pressed on, which is dragged if the
+/// mouse moves far enough before the button is released.
+#[derive(JSTraceable, MallocSizeOf)]
+#[unrooted_must_root_lint::must_root]
+struct PendingDrag {
+    source: Dom<Element>,
+    origin: Point2D<f32>,
+}
+
+/// A drag-and-drop operation over this document.
+///
+/// <https://html.spec.whatwg.org/multipage/#drag-and-drop-processing-model>
+#[derive(JSTraceable, MallocSizeOf)]
+#[unrooted_must_root_lint::must_root]
+struct DragSession {
+    /// <https://html.spec.whatwg.org/multipage/#source-node>, which is `None` for data
+    /// dragged in from the host system.
+    source_node: Option<Dom<Element>>,
+    data_transfer: Dom<DataTransfer>,
+    /// <https://html.spec.whatwg.org/multipage/#immediate-user-selection>
+    immediate_user_selection: Option<Dom<Element>>,
+    /// <https://html.spec.whatwg.org/multipage/#current-target-element>
+    current_target: Option<Dom<Element>>,
+    /// <https://html.spec.whatwg.org/multipage/#current-drag-operation>
+    current_drag_operation: DOMString,
+    /// Where the pointer was last over, where the events that end the drag are fired.
+    client_point: Point2D<f32>,
+}
+
+impl DragSession {
+    fn new(source_node: Option<&Element>, data_transfer: &DataTransfer) -> DragSession {
+        DragSession {
+            source_node: source_node.map(Dom::from_ref),
+            data_transfer: Dom::from_ref(data_transfer),
+            immediate_user_selection: None,
+            current_target: None,
+            current_drag_operation: DOMString::from("none"),
+            client_point: Point2D::zero(),
+        }
+    }
+}
+
 #[derive(JSTraceable, MallocSizeOf)]
 #[unrooted_must_root_lint::must_root]
 enum FocusTransaction {
@@ -314,6 +452,15 @@ pub struct Document {
     needs_paint: Cell<bool>,
     /// <http://w3c.github.io/touch-events/#dfn-active-touch-point>
     active_touch_points: DomRefCell<Vec<Dom<Touch>>>,
+    /// The active pointers, by pointer id. The mouse is always an active pointer.
+    active_pointers: DomRefCell<HashMap<i32, ActivePointer>>,
+    /// A drag that starts if the mouse moves far enough.
+    pending_drag: DomRefCell<Option<PendingDrag>>,
+    /// The drag-and-drop operation over this document, if any.
+    drag_session: DomRefCell<Option<DragSession>>,
+    /// Whether to ignore the click that follows the mouse button being released to end
+    /// a drag-and-drop operation.
+    suppress_next_click: Cell<bool>,
     /// Navigation Timing properties:
     /// <https://w3c.github.io/navigation-timing/#sec-PerformanceNavigationTiming>
     dom_loading: Cell<u64>,
@@ -328,6 +475,14 @@ pub struct Document {
     unload_event_end: Cell<u64>,
     /// <https://html.spec.whatwg.org/multipage/#concept-document-https-state>
     https_state: Cell<HttpsState>,
+    /// Whether any ancestor of this document has a potentially trustworthy origin.
+    /// <https://w3c.github.io/webappsec-mixed-content/#categorize-settings-object>
+    has_trustworthy_ancestor_origin: Cell<bool>,
+    /// Whether every ancestor of this document is a secure context.
+    has_only_trustworthy_ancestor_origins: Cell<bool>,
+    /// Whether this document has displayed optionally-blockable mixed content.
+    /// <https://w3c.github.io/webappsec-mixed-content/#optionally-blockable-mixed-content>
+    displayed_mixed_content: Cell<bool>,
     /// The document's origin.
     origin: MutableOrigin,
     ///  https://w3c.github.io/webappsec-referrer-policy/#referrer-policy-states
@@ -354,8 +509,12 @@ pub struct Document {
     ///
     /// See also: https://github.com/servo/servo/issues/10110
     dom_count: Cell<u32>,
-    /// Entry node for fullscreen.
-    fullscreen_element: MutNullableDom<Element>,
+    /// <https://fullscreen.spec.whatwg.org/#top-layer>
+    top_layer: DomRefCell<Vec<Dom<Element>>>,
+    /// <https://html.spec.whatwg.org/multipage/#designMode>
+    design_mode: Cell<bool>,
+    /// The undo history of the editing hosts of this document.
+    editing_state: DomRefCell<EditingState>,
     /// Map from ID to set of form control elements that have that ID as
     /// their 'form' content attribute. Used to reset form controls
     /// whenever any element with the same ID as the form attribute
@@ -404,6 +563,10 @@ pub struct Document {
     /// https://html.spec.whatwg.org/multipage/#concept-document-csp-list
     #[ignore_malloc_size_of = "Defined in rust-content-security-policy"]
     csp_list: DomRefCell<Option<CspList>>,
+    /// https://html.spec.whatwg.org/multipage/#active-sandboxing-flag-set
+    active_sandboxing_flag_set: Cell<SandboxingFlagSet>,
+    /// https://w3c.github.io/reporting/#document-reporting-endpoints
+    reporting_endpoints: DomRefCell<HashMap<String, ServoUrl>>,
     /// https://w3c.github.io/slection-api/#dfn-selection
     selection: MutNullableDom<Selection>,
     /// A timeline for animations which is used for synchronizing animations.
@@ -413,6 +576,18 @@ pub struct Document {
     animations: DomRefCell<Animations>,
     /// The nearest inclusive ancestors to all the nodes that require a restyle.
     dirty_root: MutNullableDom<Element>,
+    /// The intersection observers created in this document that have observation targets
+    /// or queued entries.
+    intersection_observers: DomRefCell<Vec<Dom<IntersectionObserver>>>,
+    /// <https://w3c.github.io/IntersectionObserver/#document-intersectionobservertaskqueued>
+    intersection_observer_task_queued: Cell<bool>,
+    /// <https://drafts.csswg.org/resize-observer/#dom-document-resizeobservers-slot>
+    resize_observers: DomRefCell<Vec<Dom<ResizeObserver>>>,
+    /// The images and iframes of this document whose load is deferred until they come near
+    /// the viewport, which are the targets of its lazy load intersection observer.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#lazy-load-intersection-observer>
+    lazy_load_elements: DomRefCell<Vec<Dom<Element>>>,
 }
 
 #[derive(JSTraceable, MallocSizeOf)]
@@ -618,6 +793,69 @@ impl Document {
         self.https_state.set(https_state);
     }
 
+    pub fn has_trustworthy_ancestor_origin(&self) -> bool {
+        self.has_trustworthy_ancestor_origin.get()
+    }
+
+    pub fn set_has_trustworthy_ancestor_origin(&self, has_trustworthy_ancestor_origin: bool) {
+        self.has_trustworthy_ancestor_origin
+            .set(has_trustworthy_ancestor_origin);
+    }
+
+    pub fn has_only_trustworthy_ancestor_origins(&self) -> bool {
+        self.has_only_trustworthy_ancestor_origins.get()
+    }
+
+    pub fn set_has_only_trustworthy_ancestor_origins(
+        &self,
+        has_only_trustworthy_ancestor_origins: bool,
+    ) {
+        self.has_only_trustworthy_ancestor_origins
+            .set(has_only_trustworthy_ancestor_origins);
+    }
+
+    /// Whether this document or any of its ancestors has a potentially trustworthy
+    /// origin, i.e. whether its subresources are subject to mixed content checks.
+    pub fn has_trustworthy_ancestor_or_current_origin(&self) -> bool {
+        self.has_trustworthy_ancestor_origin.get() ||
+            self.origin.immutable().is_potentially_trustworthy()
+    }
+
+    /// The security state of this document, as reported to the embedder.
+    pub fn security_state(&self) -> SecurityState {
+        if self.https_state.get() == HttpsState::None {
+            SecurityState::Insecure
+        } else if self.displayed_mixed_content.get() {
+            SecurityState::MixedContent
+        } else {
+            SecurityState::Secure
+        }
+    }
+
+    /// Let the embedder know about the security state of a top-level document.
+    pub fn report_security_state(&self) {
+        if self.window().is_top_level() {
+            self.send_to_embedder(EmbedderMsg::SecurityStateChanged(self.security_state()));
+        }
+    }
+
+    /// Record that a subresource was received, degrading the security state of
+    /// the page if it is optionally-blockable mixed content. Blockable mixed
+    /// content never reaches this point, as it is blocked by the fetch.
+    pub fn note_subresource_response(&self, metadata: &Metadata) {
+        if !degrades_security_state(self.https_state.get(), &metadata.final_url) ||
+            self.displayed_mixed_content.get()
+        {
+            return;
+        }
+        self.displayed_mixed_content.set(true);
+        // Mixed content in a nested document degrades the security state of
+        // the whole page.
+        self.send_to_embedder(EmbedderMsg::SecurityStateChanged(
+            SecurityState::MixedContent,
+        ));
+    }
+
     pub fn is_fully_active(&self) -> bool {
         self.activity.get() == DocumentActivity::FullyActive
     }
@@ -1031,7 +1269,8 @@ impl Document {
 
     /// Return whether scripting is enabled or not
     pub fn is_scripting_enabled(&self) -> bool {
-        self.scripting_enabled
+        self.scripting_enabled &&
+            !self.has_active_sandboxing_flag(SandboxingFlagSet::SANDBOXED_SCRIPTS)
     }
 
     /// Return the element that currently has focus.
@@ -1068,7 +1307,9 @@ impl Document {
         if implicit_transaction {
             self.begin_focus_transaction();
         }
-        if elem.map_or(true, |e| e.is_focusable_area()) {
+        if elem.map_or(true, |e| {
+            e.is_focusable_area() && !e.upcast::<Node>().is_inert()
+        }) {
             *self.focus_transaction.borrow_mut() =
                 FocusTransaction::InTransaction(elem.map(Dom::from_ref));
         }
@@ -1184,11 +1425,27 @@ impl Document {
         }
     }
 
+    /// The element that a hit test found at the point of a user interaction, if any. Inert
+    /// elements can't be the targets of user interaction events.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#inert>
     #[allow(unsafe_code)]
+    fn hit_test_target(
+        &self,
+        js_runtime: *mut JSRuntime,
+        node_address: Option<UntrustedNodeAddress>,
+    ) -> Option<DomRoot<Element>> {
+        let node = unsafe { node::from_untrusted_node_address(js_runtime, node_address?) };
+        node.inclusive_ancestors(ShadowIncluding::No)
+            .filter_map(DomRoot::downcast::<Element>)
+            .next()
+            .filter(|element| !element.upcast::<Node>().is_inert())
+    }
+
     pub fn handle_mouse_event(
         &self,
         js_runtime: *mut JSRuntime,
-        _button: MouseButton,
+        button: MouseButton,
         client_point: Point2D<f32>,
         mouse_event_type: MouseEventType,
         node_address: Option<UntrustedNodeAddress>,
@@ -1202,12 +1459,27 @@ impl Document {
         };
         debug!("{}: at {:?}", mouse_event_type_string, client_point);
 
-        let el = node_address.and_then(|address| {
-            let node = unsafe { node::from_untrusted_node_address(js_runtime, address) };
-            node.inclusive_ancestors(ShadowIncluding::No)
-                .filter_map(DomRoot::downcast::<Element>)
-                .next()
-        });
+        // Mouse events aren't fired while an element is being dragged, and the mouse
+        // button being released drops it.
+        // https://html.spec.whatwg.org/multipage/#drag-and-drop-processing-model
+        if self.is_dragging_from_page() {
+            if let MouseEventType::MouseUp = mouse_event_type {
+                self.end_drag(false);
+                self.suppress_next_click.set(true);
+            }
+            return;
+        }
+        match mouse_event_type {
+            MouseEventType::Click => {
+                if self.suppress_next_click.replace(false) {
+                    return;
+                }
+            },
+            MouseEventType::MouseDown => self.suppress_next_click.set(false),
+            MouseEventType::MouseUp => *self.pending_drag.borrow_mut() = None,
+        }
+
+        let el = self.hit_test_target(js_runtime, node_address);
         let el = match el {
             Some(el) => el,
             None => return,
@@ -1221,10 +1493,20 @@ impl Document {
                 return;
             }
 
+            // Clicking the contents of an editing host focuses the host.
+            let focus_target = node.editing_host().unwrap_or_else(|| el.clone());
             self.begin_focus_transaction();
-            self.request_focus(Some(&*el), FocusType::Element);
+            self.request_focus(Some(&*focus_target), FocusType::Element);
         }
 
+        let compatibility_mouse_events_prevented = self.handle_mouse_button_pointer_event(
+            &el,
+            button,
+            client_point,
+            &mouse_event_type,
+            pressed_mouse_buttons,
+        );
+
         // https://w3c.github.io/uievents/#event-type-click
         let client_x = client_point.x as i32;
         let client_y = client_point.y as i32;
@@ -1266,16 +1548,25 @@ impl Document {
                     a.enter_formal_activation_state();
                 }
 
-                let target = node.upcast();
-                event.fire(target);
+                if !compatibility_mouse_events_prevented {
+                    let target = node.upcast();
+                    if event.fire(target) == EventStatus::NotCanceled {
+                        if let MouseButton::Left = button {
+                            self.set_pending_drag(&el, client_point);
+                            editing::handle_mousedown(self, &el, client_point);
+                        }
+                    }
+                }
             },
             MouseEventType::MouseUp => {
                 if let Some(a) = activatable {
                     a.exit_formal_activation_state();
                 }
 
-                let target = node.upcast();
-                event.fire(target);
+                if !compatibility_mouse_events_prevented {
+                    let target = node.upcast();
+                    event.fire(target);
+                }
             },
         }
 
@@ -1384,7 +1675,6 @@ impl Document {
         event.fire(target);
     }
 
-    #[allow(unsafe_code)]
     pub fn handle_mouse_move_event(
         &self,
         js_runtime: *mut JSRuntime,
@@ -1393,18 +1683,25 @@ impl Document {
         node_address: Option<UntrustedNodeAddress>,
         pressed_mouse_buttons: u16,
     ) {
-        let maybe_new_target = node_address.and_then(|address| {
-            let node = unsafe { node::from_untrusted_node_address(js_runtime, address) };
-            node.inclusive_ancestors(ShadowIncluding::No)
-                .filter_map(DomRoot::downcast::<Element>)
-                .next()
-        });
+        let maybe_new_target = self.hit_test_target(js_runtime, node_address);
 
         let new_target = match maybe_new_target {
             Some(ref target) => target,
             None => return,
         };
 
+        // The mouse moving drags the element instead of firing mouse events.
+        // https://html.spec.whatwg.org/multipage/#drag-and-drop-processing-model
+        if self.is_dragging_from_page() ||
+            self.maybe_start_drag(new_target, client_point, pressed_mouse_buttons)
+        {
+            self.update_drag(Some(new_target), client_point);
+            return;
+        }
+
+        let compatibility_mouse_events_prevented =
+            self.handle_mouse_move_pointer_event(new_target, client_point, pressed_mouse_buttons);
+
         let target_has_changed = prev_mouse_over_target
             .get()
             .as_ref()
@@ -1490,14 +1787,16 @@ impl Document {
 
         // Send mousemove event to topmost target, unless it's an iframe, in which case the
         // compositor should have also sent an event to the inner document.
-        self.fire_mouse_event(
-            client_point,
-            new_target.upcast(),
-            FireMouseEventType::Move,
-            EventBubbles::Bubbles,
-            EventCancelable::Cancelable,
-            pressed_mouse_buttons,
-        );
+        if !compatibility_mouse_events_prevented {
+            self.fire_mouse_event(
+                client_point,
+                new_target.upcast(),
+                FireMouseEventType::Move,
+                EventBubbles::Bubbles,
+                EventCancelable::Cancelable,
+                pressed_mouse_buttons,
+            );
+        }
 
         // If the target has changed then store the current mouse over target for next frame.
         if target_has_changed {
@@ -1520,31 +1819,11 @@ impl Document {
             FireMouseEventType::Enter | FireMouseEventType::Leave
         ));
 
-        let common_ancestor = match related_target.as_ref() {
-            Some(related_target) => event_target
-                .common_ancestor(related_target, ShadowIncluding::No)
-                .unwrap_or_else(|| DomRoot::from_ref(&*event_target)),
-            None => DomRoot::from_ref(&*event_target),
-        };
-
-        // We need to create a target chain in case the event target shares
-        // its boundaries with its ancestors.
-        let mut targets = vec![];
-        let mut current = Some(event_target);
-        while let Some(node) = current {
-            if node == common_ancestor {
-                break;
-            }
-            current = node.GetParentNode();
-            targets.push(node);
-        }
-
-        // The order for dispatching mouseenter events starts from the topmost
-        // common ancestor of the event target and the related target.
-        if event_type == FireMouseEventType::Enter {
-            targets = targets.into_iter().rev().collect();
-        }
-
+        let targets = enter_leave_event_targets(
+            event_target,
+            related_target.as_deref(),
+            event_type == FireMouseEventType::Enter,
+        );
         for target in targets {
             self.fire_mouse_event(
                 client_point,
@@ -1557,7 +1836,6 @@ impl Document {
         }
     }
 
-    #[allow(unsafe_code)]
     pub fn handle_wheel_event(
         &self,
         js_runtime: *mut JSRuntime,
@@ -1568,12 +1846,7 @@ impl Document {
         let wheel_event_type_string = "wheel".to_owned();
         debug!("{}: at {:?}", wheel_event_type_string, client_point);
 
-        let el = node_address.and_then(|address| {
-            let node = unsafe { node::from_untrusted_node_address(js_runtime, address) };
-            node.inclusive_ancestors(ShadowIncluding::No)
-                .filter_map(DomRoot::downcast::<Element>)
-                .next()
-        });
+        let el = self.hit_test_target(js_runtime, node_address);
 
         let el = match el {
             Some(el) => el,
@@ -1604,7 +1877,6 @@ impl Document {
         event.fire(target);
     }
 
-    #[allow(unsafe_code)]
     pub fn handle_touch_event(
         &self,
         js_runtime: *mut JSRuntime,
@@ -1622,17 +1894,18 @@ impl Document {
             TouchEventType::Cancel => "touchcancel",
         };
 
-        let el = node_address.and_then(|address| {
-            let node = unsafe { node::from_untrusted_node_address(js_runtime, address) };
-            node.inclusive_ancestors(ShadowIncluding::No)
-                .filter_map(DomRoot::downcast::<Element>)
-                .next()
-        });
+        let el = self.hit_test_target(js_runtime, node_address);
         let el = match el {
             Some(el) => el,
             None => return TouchEventResult::Forwarded,
         };
 
+        self.handle_touch_pointer_event(&el, event_type, touch_id, point);
+
+        let allowed_touch_actions = match event_type {
+            TouchEventType::Down => el.allowed_touch_actions(),
+            _ => TouchActions::all(),
+        };
         let target = DomRoot::upcast::<EventTarget>(el);
         let window = &*self.window;
 
@@ -1709,116 +1982,1086 @@ impl Document {
         window.reflow(ReflowGoal::Full, ReflowReason::MouseEvent);
 
         match result {
-            EventStatus::Canceled => TouchEventResult::Processed(false),
-            EventStatus::NotCanceled => TouchEventResult::Processed(true),
+            EventStatus::Canceled => TouchEventResult::Processed(false, allowed_touch_actions),
+            EventStatus::NotCanceled => TouchEventResult::Processed(true, allowed_touch_actions),
         }
     }
 
-    /// The entry point for all key processing for web content
-    pub fn dispatch_key_event(&self, keyboard_event: ::keyboard_types::KeyboardEvent) {
-        let focused = self.get_focused_element();
-        let body = self.GetBody();
+    pub fn handle_pen_event(
+        &self,
+        js_runtime: *mut JSRuntime,
+        event_type: TouchEventType,
+        client_point: Point2D<f32>,
+        node_address: Option<UntrustedNodeAddress>,
+        pen_state: PenState,
+    ) {
+        if !pref!(dom.pointer_events.enabled) {
+            return;
+        }
 
-        let target = match (&focused, &body) {
-            (&Some(ref focused), _) => focused.upcast(),
-            (&None, &Some(ref body)) => body.upcast(),
-            (&None, &None) => self.window.upcast(),
-        };
+        let hit_target = self.hit_test_target(js_runtime, node_address);
 
-        let keyevent = KeyboardEvent::new(
-            &self.window,
-            DOMString::from(keyboard_event.state.to_string()),
-            true,
-            true,
-            Some(&self.window),
-            0,
-            keyboard_event.key.clone(),
-            DOMString::from(keyboard_event.code.to_string()),
-            keyboard_event.location as u32,
-            keyboard_event.repeat,
-            keyboard_event.is_composing,
-            keyboard_event.modifiers,
-            0,
-            keyboard_event.key.legacy_keycode(),
-        );
-        let event = keyevent.upcast::<Event>();
-        event.fire(target);
-        let mut cancel_state = event.get_cancel_state();
+        let was_touching = self
+            .active_pointers
+            .borrow_mut()
+            .entry(PEN_POINTER_ID)
+            .or_insert_with(|| ActivePointer::new(PointerType::Pen, true))
+            .active_buttons;
+        let (event_name, button, buttons) = match event_type {
+            TouchEventType::Down => {
+                self.set_pointer_active_buttons(PEN_POINTER_ID, true);
+                ("pointerdown", 0, 1)
+            },
+            TouchEventType::Move => ("pointermove", -1, was_touching as u16),
+            TouchEventType::Up => ("pointerup", 0, 0),
+            TouchEventType::Cancel => ("pointercancel", -1, 0),
+        };
+        let data = PointerData::pen(client_point, button, buttons, pen_state);
+        self.dispatch_pointer_event(event_name, hit_target.as_deref(), &data);
 
-        // https://w3c.github.io/uievents/#keys-cancelable-keys
-        if keyboard_event.state == KeyState::Down &&
-            is_character_value_key(&(keyboard_event.key)) &&
-            !keyboard_event.is_composing &&
-            cancel_state != EventDefault::Prevented
-        {
-            // https://w3c.github.io/uievents/#keypress-event-order
-            let event = KeyboardEvent::new(
-                &self.window,
-                DOMString::from("keypress"),
-                true,
-                true,
-                Some(&self.window),
-                0,
-                keyboard_event.key.clone(),
-                DOMString::from(keyboard_event.code.to_string()),
-                keyboard_event.location as u32,
-                keyboard_event.repeat,
-                keyboard_event.is_composing,
-                keyboard_event.modifiers,
-                keyboard_event.key.legacy_charcode(),
-                0,
-            );
-            let ev = event.upcast::<Event>();
-            ev.fire(target);
-            cancel_state = ev.get_cancel_state();
+        match event_type {
+            // The pen keeps hovering over the page after it is lifted.
+            TouchEventType::Up => self.release_pointer(&data, false),
+            TouchEventType::Cancel => self.release_pointer(&data, true),
+            TouchEventType::Down | TouchEventType::Move => {},
         }
 
-        if cancel_state == EventDefault::Allowed {
-            let msg = EmbedderMsg::Keyboard(keyboard_event.clone());
-            self.send_to_embedder(msg);
+        self.window
+            .reflow(ReflowGoal::Full, ReflowReason::MouseEvent);
+    }
 
-            // This behavior is unspecced
-            // We are supposed to dispatch synthetic click activation for Space and/or Return,
-            // however *when* we do it is up to us.
-            // Here, we're dispatching it after the key event so the script has a chance to cancel it
-            // https://www.w3.org/Bugs/Public/show_bug.cgi?id=27337
-            if (keyboard_event.key == Key::Enter || keyboard_event.code == Code::Space) &&
-                keyboard_event.state == KeyState::Up
-            {
-                if let Some(elem) = target.downcast::<Element>() {
-                    elem.upcast::<Node>()
-                        .fire_synthetic_mouse_event_not_trusted(DOMString::from("click"));
-                }
-            }
+    /// Fires the pointer event for a mouse button being pressed or released, and returns
+    /// whether compatibility mouse events are prevented.
+    fn handle_mouse_button_pointer_event(
+        &self,
+        hit_target: &Element,
+        button: MouseButton,
+        client_point: Point2D<f32>,
+        mouse_event_type: &MouseEventType,
+        pressed_mouse_buttons: u16,
+    ) -> bool {
+        if !pref!(dom.pointer_events.enabled) {
+            return false;
         }
 
-        self.window.reflow(ReflowGoal::Full, ReflowReason::KeyEvent);
+        // https://w3c.github.io/pointerevents/#the-button-property
+        let pointer_button = match button {
+            MouseButton::Left => 0,
+            MouseButton::Middle => 1,
+            MouseButton::Right => 2,
+        };
+        let data = PointerData::mouse(client_point, pointer_button, pressed_mouse_buttons);
+        match *mouse_event_type {
+            // Pressing or releasing a button while another one is pressed is reported as a
+            // `pointermove`.
+            // https://w3c.github.io/pointerevents/#chorded-button-interactions
+            MouseEventType::MouseDown if pressed_mouse_buttons != button as u16 => {
+                self.dispatch_pointer_event("pointermove", Some(hit_target), &data);
+            },
+            MouseEventType::MouseUp if pressed_mouse_buttons != 0 => {
+                self.dispatch_pointer_event("pointermove", Some(hit_target), &data);
+            },
+            MouseEventType::MouseDown => {
+                self.set_pointer_active_buttons(MOUSE_POINTER_ID, true);
+                if self.dispatch_pointer_event("pointerdown", Some(hit_target), &data) ==
+                    EventStatus::Canceled
+                {
+                    if let Some(pointer) =
+                        self.active_pointers.borrow_mut().get_mut(&MOUSE_POINTER_ID)
+                    {
+                        pointer.compatibility_mouse_events_prevented = true;
+                    }
+                }
+            },
+            MouseEventType::MouseUp => {
+                self.dispatch_pointer_event("pointerup", Some(hit_target), &data);
+                let prevented = self.compatibility_mouse_events_prevented(MOUSE_POINTER_ID);
+                self.release_pointer(&data, false);
+                return prevented;
+            },
+            MouseEventType::Click => {},
+        }
+        self.compatibility_mouse_events_prevented(MOUSE_POINTER_ID)
     }
 
-    pub fn ime_dismissed(&self) {
-        self.request_focus(
-            self.GetBody().as_ref().map(|e| &*e.upcast()),
-            FocusType::Element,
-        )
+    /// Fires the pointer event for the mouse moving, and returns whether compatibility
+    /// mouse events are prevented.
+    fn handle_mouse_move_pointer_event(
+        &self,
+        hit_target: &Element,
+        client_point: Point2D<f32>,
+        pressed_mouse_buttons: u16,
+    ) -> bool {
+        if !pref!(dom.pointer_events.enabled) {
+            return false;
+        }
+
+        let data = PointerData::mouse(client_point, -1, pressed_mouse_buttons);
+        self.dispatch_pointer_event("pointermove", Some(hit_target), &data);
+        self.compatibility_mouse_events_prevented(MOUSE_POINTER_ID)
     }
 
-    pub fn dispatch_composition_event(
+    fn handle_touch_pointer_event(
         &self,
-        composition_event: ::keyboard_types::CompositionEvent,
+        hit_target: &Element,
+        event_type: TouchEventType,
+        touch_id: TouchId,
+        client_point: Point2D<f32>,
     ) {
-        // spec: https://w3c.github.io/uievents/#compositionstart
-        // spec: https://w3c.github.io/uievents/#compositionupdate
-        // spec: https://w3c.github.io/uievents/#compositionend
-        // > Event.target : focused element processing the composition
-        let focused = self.get_focused_element();
-        let target = if let Some(elem) = &focused {
-            elem.upcast()
-        } else {
-            // Event is only dispatched if there is a focused element.
+        if !pref!(dom.pointer_events.enabled) {
             return;
-        };
-
-        let cancelable = composition_event.state == keyboard_types::CompositionState::Start;
+        }
+
+        let pointer_id = touch_pointer_id(touch_id);
+        if let TouchEventType::Down = event_type {
+            // The first touch point of a gesture is the primary pointer.
+            let is_primary = !self
+                .active_pointers
+                .borrow()
+                .values()
+                .any(|pointer| pointer.pointer_type == PointerType::Touch);
+            self.active_pointers.borrow_mut().insert(
+                pointer_id,
+                ActivePointer::new(PointerType::Touch, is_primary),
+            );
+            self.set_pointer_active_buttons(pointer_id, true);
+        }
+
+        let is_primary = match self.active_pointers.borrow().get(&pointer_id) {
+            Some(pointer) => pointer.is_primary,
+            None => return warn!("Got a touch event for a non-active pointer"),
+        };
+        let (event_name, button, buttons) = match event_type {
+            TouchEventType::Down => ("pointerdown", 0, 1),
+            TouchEventType::Move => ("pointermove", -1, 1),
+            TouchEventType::Up => ("pointerup", 0, 0),
+            TouchEventType::Cancel => ("pointercancel", -1, 0),
+        };
+        let data = PointerData::touch(touch_id, is_primary, client_point, button, buttons);
+        self.dispatch_pointer_event(event_name, Some(hit_target), &data);
+
+        if let TouchEventType::Up | TouchEventType::Cancel = event_type {
+            self.release_pointer(&data, true);
+        }
+    }
+
+    /// Fires a pointer event at the pointer's capture target, or else at the element under
+    /// the pointer, after firing any boundary events for the pointer moving to that target.
+    fn dispatch_pointer_event(
+        &self,
+        event_type: &str,
+        hit_target: Option<&Element>,
+        data: &PointerData,
+    ) -> EventStatus {
+        self.process_pending_pointer_capture(data);
+
+        let target = self
+            .pointer_capture_target(data.pointer_id)
+            .or_else(|| hit_target.map(DomRoot::from_ref));
+        let target = match target {
+            Some(target) => target,
+            None => return EventStatus::NotCanceled,
+        };
+        self.update_pointer_over_target(data, Some(&target));
+
+        // Direct manipulation devices capture the pointer as if `setPointerCapture` was
+        // called just before the `pointerdown` listeners run.
+        // https://w3c.github.io/pointerevents/#implicit-pointer-capture
+        if event_type == "pointerdown" && data.pointer_type == PointerType::Touch {
+            if let Some(pointer) = self.active_pointers.borrow_mut().get_mut(&data.pointer_id) {
+                pointer.pending_capture_target = Some(Dom::from_ref(&*target));
+            }
+        }
+
+        let cancelable = if event_type == "pointercancel" {
+            EventCancelable::NotCancelable
+        } else {
+            EventCancelable::Cancelable
+        };
+        self.fire_pointer_event(
+            event_type,
+            target.upcast(),
+            data,
+            EventBubbles::Bubbles,
+            cancelable,
+            None,
+        )
+    }
+
+    fn fire_pointer_event(
+        &self,
+        event_type: &str,
+        target: &EventTarget,
+        data: &PointerData,
+        can_bubble: EventBubbles,
+        cancelable: EventCancelable,
+        related_target: Option<&EventTarget>,
+    ) -> EventStatus {
+        let event = PointerEvent::new_for_pointer(
+            &self.window,
+            DOMString::from(event_type),
+            can_bubble,
+            cancelable,
+            data,
+            related_target,
+        );
+        event.upcast::<Event>().fire(target)
+    }
+
+    /// Fires `pointerout`, `pointerleave`, `pointerover` and `pointerenter` events as the
+    /// pointer moves from the element it was last over to `new_target`.
+    ///
+    /// <https://w3c.github.io/pointerevents/#boundary-events-caused-by-layout-changes>
+    fn update_pointer_over_target(&self, data: &PointerData, new_target: Option<&Element>) {
+        let old_target = match self.active_pointers.borrow_mut().get_mut(&data.pointer_id) {
+            Some(pointer) => {
+                let old_target = pointer
+                    .over_target
+                    .as_ref()
+                    .map(|target| DomRoot::from_ref(&**target));
+                if old_target.as_deref() == new_target {
+                    return;
+                }
+                pointer.over_target = new_target.map(Dom::from_ref);
+                old_target
+            },
+            None => return,
+        };
+
+        if let Some(ref old_target) = old_target {
+            self.fire_pointer_event(
+                "pointerout",
+                old_target.upcast(),
+                data,
+                EventBubbles::Bubbles,
+                EventCancelable::Cancelable,
+                new_target.map(|target| target.upcast()),
+            );
+            let targets = enter_leave_event_targets(
+                DomRoot::from_ref(old_target.upcast()),
+                new_target.map(|target| target.upcast()),
+                false,
+            );
+            for target in targets {
+                self.fire_pointer_event(
+                    "pointerleave",
+                    target.upcast(),
+                    data,
+                    EventBubbles::DoesNotBubble,
+                    EventCancelable::NotCancelable,
+                    new_target.map(|target| target.upcast()),
+                );
+            }
+        }
+
+        if let Some(new_target) = new_target {
+            self.fire_pointer_event(
+                "pointerover",
+                new_target.upcast(),
+                data,
+                EventBubbles::Bubbles,
+                EventCancelable::Cancelable,
+                old_target.as_ref().map(|target| target.upcast()),
+            );
+            let targets = enter_leave_event_targets(
+                DomRoot::from_ref(new_target.upcast()),
+                old_target.as_ref().map(|target| target.upcast()),
+                true,
+            );
+            for target in targets {
+                self.fire_pointer_event(
+                    "pointerenter",
+                    target.upcast(),
+                    data,
+                    EventBubbles::DoesNotBubble,
+                    EventCancelable::NotCancelable,
+                    old_target.as_ref().map(|target| target.upcast()),
+                );
+            }
+        }
+    }
+
+    fn set_pointer_active_buttons(&self, pointer_id: i32, active_buttons: bool) {
+        if let Some(pointer) = self.active_pointers.borrow_mut().get_mut(&pointer_id) {
+            pointer.active_buttons = active_buttons;
+        }
+    }
+
+    fn compatibility_mouse_events_prevented(&self, pointer_id: i32) -> bool {
+        self.active_pointers
+            .borrow()
+            .get(&pointer_id)
+            .map_or(false, |pointer| {
+                pointer.compatibility_mouse_events_prevented
+            })
+    }
+
+    /// Releases the pointer after a `pointerup` or `pointercancel` event. Pointers that
+    /// `leave` the page also get boundary events and stop being active.
+    ///
+    /// <https://w3c.github.io/pointerevents/#implicit-release-of-pointer-capture>
+    fn release_pointer(&self, data: &PointerData, leave: bool) {
+        if let Some(pointer) = self.active_pointers.borrow_mut().get_mut(&data.pointer_id) {
+            pointer.active_buttons = false;
+            pointer.pending_capture_target = None;
+            pointer.compatibility_mouse_events_prevented = false;
+        }
+        self.process_pending_pointer_capture(data);
+
+        if leave {
+            self.update_pointer_over_target(data, None);
+            self.active_pointers.borrow_mut().remove(&data.pointer_id);
+        }
+    }
+
+    fn pointer_capture_target(&self, pointer_id: i32) -> Option<DomRoot<Element>> {
+        self.active_pointers
+            .borrow()
+            .get(&pointer_id)
+            .and_then(|pointer| pointer.capture_target.as_ref())
+            .map(|target| DomRoot::from_ref(&**target))
+    }
+
+    /// <https://w3c.github.io/pointerevents/#process-pending-pointer-capture>
+    fn process_pending_pointer_capture(&self, data: &PointerData) {
+        let (capture_target, pending_capture_target) =
+            match self.active_pointers.borrow().get(&data.pointer_id) {
+                Some(pointer) => (
+                    pointer
+                        .capture_target
+                        .as_ref()
+                        .map(|target| DomRoot::from_ref(&**target)),
+                    pointer
+                        .pending_capture_target
+                        .as_ref()
+                        .map(|target| DomRoot::from_ref(&**target)),
+                ),
+                None => return,
+            };
+        if capture_target == pending_capture_target {
+            return;
+        }
+
+        // Step 1
+        if let Some(capture_target) = capture_target {
+            let target = if capture_target.upcast::<Node>().is_connected() {
+                capture_target.upcast::<EventTarget>()
+            } else {
+                self.upcast::<EventTarget>()
+            };
+            self.fire_pointer_event(
+                "lostpointercapture",
+                target,
+                data,
+                EventBubbles::Bubbles,
+                EventCancelable::NotCancelable,
+                None,
+            );
+        }
+
+        // Step 2
+        if let Some(pending_capture_target) = pending_capture_target {
+            self.fire_pointer_event(
+                "gotpointercapture",
+                pending_capture_target.upcast(),
+                data,
+                EventBubbles::Bubbles,
+                EventCancelable::NotCancelable,
+                None,
+            );
+        }
+
+        // Step 3
+        if let Some(pointer) = self.active_pointers.borrow_mut().get_mut(&data.pointer_id) {
+            pointer.capture_target = pointer.pending_capture_target.clone();
+        }
+    }
+
+    /// <https://w3c.github.io/pointerevents/#setting-pointer-capture>
+    pub fn set_pointer_capture(&self, element: &Element, pointer_id: i32) -> ErrorResult {
+        let mut active_pointers = self.active_pointers.borrow_mut();
+
+        // Steps 1-2
+        let pointer = match active_pointers.get_mut(&pointer_id) {
+            Some(pointer) => pointer,
+            None => return Err(Error::NotFound),
+        };
+
+        // Step 3. There is no pointer lock, so step 4 doesn't apply.
+        if !element.upcast::<Node>().is_connected() {
+            return Err(Error::InvalidState);
+        }
+
+        // Step 5
+        if !pointer.active_buttons {
+            return Ok(());
+        }
+
+        // Step 6
+        pointer.pending_capture_target = Some(Dom::from_ref(element));
+        Ok(())
+    }
+
+    /// <https://w3c.github.io/pointerevents/#releasing-pointer-capture>
+    pub fn release_pointer_capture(&self, element: &Element, pointer_id: i32) -> ErrorResult {
+        let mut active_pointers = self.active_pointers.borrow_mut();
+
+        // Step 1
+        let pointer = match active_pointers.get_mut(&pointer_id) {
+            Some(pointer) => pointer,
+            None => return Err(Error::NotFound),
+        };
+
+        // Steps 2-3
+        if pointer.pending_capture_target.as_deref() == Some(element) {
+            pointer.pending_capture_target = None;
+        }
+        Ok(())
+    }
+
+    /// <https://w3c.github.io/pointerevents/#dom-element-haspointercapture>
+    pub fn has_pointer_capture(&self, element: &Element, pointer_id: i32) -> bool {
+        self.active_pointers
+            .borrow()
+            .get(&pointer_id)
+            .and_then(|pointer| pointer.pending_capture_target.as_deref())
+            .map_or(false, |target| target == element)
+    }
+
+    pub fn handle_drag_event(
+        &self,
+        js_runtime: *mut JSRuntime,
+        event_type: DragEventType,
+        client_point: Point2D<f32>,
+        node_address: Option<UntrustedNodeAddress>,
+        data: DragData,
+    ) {
+        if !pref!(dom.drag_and_drop.enabled) {
+            return;
+        }
+
+        // Drags within the page are driven by mouse events instead.
+        if self.is_dragging_from_page() {
+            return;
+        }
+
+        if let DragEventType::Leave = event_type {
+            self.end_drag(true);
+            return;
+        }
+
+        let hit_target = self.hit_test_target(js_runtime, node_address);
+
+        if self.drag_session.borrow().is_none() {
+            self.start_drag_from_host(data);
+        }
+        match event_type {
+            // Entering the document is followed by the drag moving over it.
+            DragEventType::Enter | DragEventType::Leave => {},
+            DragEventType::Over => self.update_drag(hit_target.as_deref(), client_point),
+            DragEventType::Drop => {
+                self.update_drag(hit_target.as_deref(), client_point);
+                self.end_drag(false);
+            },
+        }
+    }
+
+    /// Whether an element of this document is being dragged.
+    fn is_dragging_from_page(&self) -> bool {
+        self.drag_session
+            .borrow()
+            .as_ref()
+            .map_or(false, |session| session.source_node.is_some())
+    }
+
+    /// Remembers the draggable element that the mouse button was pressed on, if any, to
+    /// drag it once the mouse moves far enough.
+    fn set_pending_drag(&self, hit_target: &Element, client_point: Point2D<f32>) {
+        if !pref!(dom.drag_and_drop.enabled) {
+            return;
+        }
+
+        let source = hit_target
+            .upcast::<Node>()
+            .inclusive_ancestors(ShadowIncluding::No)
+            .filter_map(DomRoot::downcast::<HTMLElement>)
+            .find(|element| element.is_draggable());
+        *self.pending_drag.borrow_mut() = source.map(|source| PendingDrag {
+            source: Dom::from_ref(source.upcast()),
+            origin: client_point,
+        });
+    }
+
+    /// Starts dragging the pending drag's element if the mouse has moved far enough with
+    /// the button pressed, and returns whether the element is now being dragged.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#drag-and-drop-processing-model>
+    fn maybe_start_drag(
+        &self,
+        hit_target: &Element,
+        client_point: Point2D<f32>,
+        pressed_mouse_buttons: u16,
+    ) -> bool {
+        if pressed_mouse_buttons & MouseButton::Left as u16 == 0 {
+            *self.pending_drag.borrow_mut() = None;
+            return false;
+        }
+        let source = match *self.pending_drag.borrow() {
+            Some(ref pending) if (client_point - pending.origin).length() >= DRAG_THRESHOLD => {
+                DomRoot::from_ref(&*pending.source)
+            },
+            _ => return false,
+        };
+        *self.pending_drag.borrow_mut() = None;
+
+        // The mouse stops being a pointer for the page once the drag starts.
+        // https://w3c.github.io/pointerevents/#suppressing-a-pointer-event-stream
+        if pref!(dom.pointer_events.enabled) {
+            let data = PointerData::mouse(client_point, -1, 0);
+            self.dispatch_pointer_event("pointercancel", Some(hit_target), &data);
+            self.release_pointer(&data, false);
+        }
+
+        // Steps 2-3
+        let data_transfer = DataTransfer::new_for_drag(&self.window);
+        self.add_default_drag_data(&source, &data_transfer);
+
+        // Step 12
+        let status = self.fire_drag_event("dragstart", &source, client_point, None, &data_transfer);
+        if status == EventStatus::Canceled {
+            return false;
+        }
+
+        *self.drag_session.borrow_mut() = Some(DragSession::new(Some(&source), &data_transfer));
+        true
+    }
+
+    /// Adds the URL of a dragged link or image to the drag data store.
+    fn add_default_drag_data(&self, source: &Element, data_transfer: &DataTransfer) {
+        let url = if source.is::<HTMLAnchorElement>() {
+            source.get_string_attribute(&local_name!("href"))
+        } else if source.is::<HTMLImageElement>() {
+            source.get_string_attribute(&local_name!("src"))
+        } else {
+            return;
+        };
+        if let Ok(url) = self.base_url().join(&url) {
+            data_transfer.add_text_item("text/uri-list", DOMString::from(url.as_str()));
+            data_transfer.add_text_item("text/plain", DOMString::from(url.as_str()));
+        }
+    }
+
+    /// Starts a drag-and-drop operation for data dragged in from the host system.
+    fn start_drag_from_host(&self, data: DragData) {
+        let data_transfer = DataTransfer::new_for_drag(&self.window);
+        if let Some(text) = data.text {
+            data_transfer.add_text_item("text/plain", DOMString::from(text));
+        }
+        if !data.files.is_empty() {
+            for file in self.select_dropped_files(data.files) {
+                data_transfer.add_file_item(&file);
+            }
+        }
+        data_transfer.set_mode(DragDataStoreMode::Protected);
+        *self.drag_session.borrow_mut() = Some(DragSession::new(None, &data_transfer));
+    }
+
+    /// Creates `File` objects for files dragged in from the host system, which the
+    /// constellation has allowed this document to read.
+    fn select_dropped_files(&self, paths: Vec<PathBuf>) -> Vec<DomRoot<File>> {
+        let origin = get_blob_origin(&self.window.get_url());
+        let (chan, recv) = profile_ipc::channel(self.global().time_profiler_chan().clone())
+            .expect("Error initializing channel");
+        let msg = FileManagerThreadMsg::SelectDroppedFiles(paths, chan, origin);
+        let _ = self
+            .window
+            .upcast::<GlobalScope>()
+            .resource_threads()
+            .send(ToFileManager(msg));
+
+        match recv.recv().expect("IpcSender side error") {
+            Ok(selected_files) => selected_files
+                .into_iter()
+                .map(|selected| File::new_from_selected(&self.window, selected))
+                .collect(),
+            Err(err) => {
+                warn!("Selecting dropped files failed: {:?}", err);
+                vec![]
+            },
+        }
+    }
+
+    /// Runs the steps of the drag-and-drop processing model for the pointer moving over
+    /// `hit_target`.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#drag-and-drop-processing-model>
+    fn update_drag(&self, hit_target: Option<&Element>, client_point: Point2D<f32>) {
+        let (source, data_transfer, immediate_user_selection, old_target) =
+            match *self.drag_session.borrow_mut() {
+                Some(ref mut session) => {
+                    session.client_point = client_point;
+                    (
+                        session
+                            .source_node
+                            .as_ref()
+                            .map(|source| DomRoot::from_ref(&**source)),
+                        DomRoot::from_ref(&*session.data_transfer),
+                        session
+                            .immediate_user_selection
+                            .as_ref()
+                            .map(|element| DomRoot::from_ref(&**element)),
+                        session
+                            .current_target
+                            .as_ref()
+                            .map(|target| DomRoot::from_ref(&**target)),
+                    )
+                },
+                None => return,
+            };
+
+        // Step 1
+        if let Some(ref source) = source {
+            let status = self.fire_drag_event("drag", source, client_point, None, &data_transfer);
+            if status == EventStatus::Canceled {
+                self.end_drag(true);
+                return;
+            }
+        }
+
+        // Step 2
+        let mut new_target = old_target.clone();
+        if immediate_user_selection.as_deref() != hit_target {
+            if let Some(ref mut session) = *self.drag_session.borrow_mut() {
+                session.immediate_user_selection = hit_target.map(Dom::from_ref);
+            }
+
+            match hit_target {
+                Some(hit_target) if old_target.as_deref() != Some(hit_target) => {
+                    let status = self.fire_drag_event(
+                        "dragenter",
+                        hit_target,
+                        client_point,
+                        old_target.as_deref(),
+                        &data_transfer,
+                    );
+                    let body = self.GetBody();
+                    let body = body.as_ref().map(|body| body.upcast::<Element>());
+                    if status == EventStatus::Canceled {
+                        new_target = Some(DomRoot::from_ref(hit_target));
+                    } else if body != Some(hit_target) {
+                        // Elements that don't accept drops delegate to the body element.
+                        if let Some(body) = body {
+                            self.fire_drag_event(
+                                "dragenter",
+                                body,
+                                client_point,
+                                old_target.as_deref(),
+                                &data_transfer,
+                            );
+                        }
+                        new_target = body.map(DomRoot::from_ref);
+                    }
+                },
+                Some(_) => {},
+                None => new_target = None,
+            }
+
+            if new_target != old_target {
+                if let Some(ref mut session) = *self.drag_session.borrow_mut() {
+                    session.current_target = new_target.as_deref().map(Dom::from_ref);
+                }
+                if let Some(ref old_target) = old_target {
+                    self.fire_drag_event(
+                        "dragleave",
+                        old_target,
+                        client_point,
+                        new_target.as_deref(),
+                        &data_transfer,
+                    );
+                }
+            }
+        }
+
+        // Step 3
+        let operation = match new_target {
+            Some(ref target) => {
+                let status =
+                    self.fire_drag_event("dragover", target, client_point, None, &data_transfer);
+                if status == EventStatus::Canceled {
+                    data_transfer.current_drag_operation()
+                } else {
+                    "none"
+                }
+            },
+            None => "none",
+        };
+        if let Some(ref mut session) = *self.drag_session.borrow_mut() {
+            session.current_drag_operation = DOMString::from(operation);
+        }
+    }
+
+    /// Ends the drag-and-drop operation, dropping the data onto the current target element
+    /// unless the drag was canceled or the target doesn't accept it.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#drag-and-drop-processing-model>
+    fn end_drag(&self, canceled: bool) {
+        let (source, data_transfer, current_target, mut operation, client_point) =
+            match *self.drag_session.borrow() {
+                Some(ref session) => (
+                    session
+                        .source_node
+                        .as_ref()
+                        .map(|source| DomRoot::from_ref(&**source)),
+                    DomRoot::from_ref(&*session.data_transfer),
+                    session
+                        .current_target
+                        .as_ref()
+                        .map(|target| DomRoot::from_ref(&**target)),
+                    session.current_drag_operation.clone(),
+                    session.client_point,
+                ),
+                None => return,
+            };
+        *self.drag_session.borrow_mut() = None;
+        if canceled {
+            operation = DOMString::from("none");
+        }
+
+        if let Some(current_target) = current_target {
+            if operation == "none" {
+                self.fire_drag_event(
+                    "dragleave",
+                    &current_target,
+                    client_point,
+                    None,
+                    &data_transfer,
+                );
+            } else {
+                data_transfer.set_drop_effect(&operation);
+                let status = self.fire_drag_event(
+                    "drop",
+                    &current_target,
+                    client_point,
+                    None,
+                    &data_transfer,
+                );
+                if status == EventStatus::Canceled {
+                    operation = data_transfer.DropEffect();
+                }
+            }
+        }
+
+        if let Some(source) = source {
+            data_transfer.set_drop_effect(&operation);
+            self.fire_drag_event("dragend", &source, client_point, None, &data_transfer);
+        }
+    }
+
+    /// Fires a drag-and-drop event with the drag data store in the mode for the event, and
+    /// `dropEffect` initialized for the event.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#fire-a-dnd-event>
+    fn fire_drag_event(
+        &self,
+        event_type: &str,
+        target: &Element,
+        client_point: Point2D<f32>,
+        related_target: Option<&Element>,
+        data_transfer: &DataTransfer,
+    ) -> EventStatus {
+        match event_type {
+            "dragstart" => data_transfer.set_mode(DragDataStoreMode::ReadWrite),
+            "drop" => data_transfer.set_mode(DragDataStoreMode::ReadOnly),
+            _ => data_transfer.set_mode(DragDataStoreMode::Protected),
+        }
+        match event_type {
+            "dragenter" | "dragover" => data_transfer.reset_drop_effect(),
+            "drop" | "dragend" => {},
+            _ => data_transfer.set_drop_effect("none"),
+        }
+        let (cancelable, buttons) = match event_type {
+            "dragleave" => (EventCancelable::NotCancelable, MouseButton::Left as u16),
+            "dragend" => (EventCancelable::NotCancelable, 0),
+            "drop" => (EventCancelable::Cancelable, 0),
+            _ => (EventCancelable::Cancelable, MouseButton::Left as u16),
+        };
+
+        let event = DragEvent::new_for_drag(
+            &self.window,
+            DOMString::from(event_type),
+            cancelable,
+            client_point,
+            buttons,
+            related_target.map(|target| target.upcast()),
+            Some(data_transfer),
+        );
+        let status = event.upcast::<Event>().fire(target.upcast());
+        data_transfer.set_mode(DragDataStoreMode::Protected);
+        status
+    }
+
+    /// Fires the clipboard event of a clipboard action of the user, and returns whether
+    /// the default action should run. Canceling a `copy` or `cut` event puts the data
+    /// that listeners set on the system clipboard instead.
+    ///
+    /// <https://w3c.github.io/clipboard-apis/#fire-a-clipboard-event>
+    pub fn fire_clipboard_event(&self, type_: ClipboardEventType) -> bool {
+        if !pref!(dom.clipboard.enabled) {
+            return true;
+        }
+
+        // Step 2 - 4.
+        let mode = match type_ {
+            ClipboardEventType::Paste => DragDataStoreMode::ReadOnly,
+            ClipboardEventType::Copy | ClipboardEventType::Cut => DragDataStoreMode::ReadWrite,
+        };
+        let clipboard_data = DataTransfer::new_for_clipboard(&self.window, mode);
+        let chan = self
+            .window
+            .upcast::<GlobalScope>()
+            .script_to_constellation_chan();
+        if type_ == ClipboardEventType::Paste {
+            clipboard_data.add_clipboard_data(clipboard_provider::clipboard_data(chan));
+        }
+
+        // Step 5.
+        let focused = self.get_focused_element();
+        let body = self.GetBody();
+        let target = match (&focused, &body) {
+            (&Some(ref focused), _) => focused.upcast(),
+            (&None, &Some(ref body)) => body.upcast(),
+            (&None, &None) => self.upcast(),
+        };
+
+        // Step 6.
+        let event = ClipboardEvent::new(
+            &self.window,
+            Atom::from(type_.as_str()),
+            true,
+            true,
+            Some(&clipboard_data),
+        );
+        let event = event.upcast::<Event>();
+        event.set_trusted(true);
+        let status = event.fire(target);
+        clipboard_data.set_mode(DragDataStoreMode::Protected);
+
+        if status == EventStatus::NotCanceled {
+            return true;
+        }
+        if type_ != ClipboardEventType::Paste {
+            clipboard_provider::set_clipboard_data(chan, clipboard_data.clipboard_data());
+        }
+        false
+    }
+
+    /// Runs a clipboard action that no text control handled. Copying and cutting put the
+    /// selected text on the clipboard, and cutting and pasting edit the focused editing
+    /// host, if any.
+    fn handle_clipboard_action(&self, type_: ClipboardEventType) {
+        if !self.fire_clipboard_event(type_) {
+            return;
+        }
+        let chan = self
+            .window
+            .upcast::<GlobalScope>()
+            .script_to_constellation_chan();
+        if type_ == ClipboardEventType::Paste {
+            if let Some(text) = clipboard_provider::clipboard_data(chan).text() {
+                editing::paste(self, DOMString::from(text));
+            }
+            return;
+        }
+        let text = match self.GetSelection() {
+            Some(selection) => selection.Stringifier(),
+            None => return,
+        };
+        if !text.is_empty() {
+            clipboard_provider::set_clipboard_data(chan, ClipboardData::from_text(text.into()));
+        }
+        if type_ == ClipboardEventType::Cut {
+            editing::cut(self);
+        }
+    }
+
+    /// The entry point for all key processing for web content
+    pub fn dispatch_key_event(&self, keyboard_event: ::keyboard_types::KeyboardEvent) {
+        let focused = self.get_focused_element();
+        let modal_dialog = self.active_modal_dialog();
+        let body = self.GetBody();
+
+        let target = match (&focused, &modal_dialog, &body) {
+            (&Some(ref focused), _, _) => focused.upcast(),
+            // The body is inert while a modal dialog is open.
+            (&None, &Some(ref dialog), _) => dialog.upcast(),
+            (&None, &None, &Some(ref body)) => body.upcast(),
+            (&None, &None, &None) => self.window.upcast(),
+        };
+
+        let keyevent = KeyboardEvent::new(
+            &self.window,
+            DOMString::from(keyboard_event.state.to_string()),
+            true,
+            true,
+            Some(&self.window),
+            0,
+            keyboard_event.key.clone(),
+            DOMString::from(keyboard_event.code.to_string()),
+            keyboard_event.location as u32,
+            keyboard_event.repeat,
+            keyboard_event.is_composing,
+            keyboard_event.modifiers,
+            0,
+            keyboard_event.key.legacy_keycode(),
+        );
+        let event = keyevent.upcast::<Event>();
+        event.fire(target);
+        let mut cancel_state = event.get_cancel_state();
+
+        // https://w3c.github.io/uievents/#keys-cancelable-keys
+        if keyboard_event.state == KeyState::Down &&
+            is_character_value_key(&(keyboard_event.key)) &&
+            !keyboard_event.is_composing &&
+            cancel_state != EventDefault::Prevented
+        {
+            // https://w3c.github.io/uievents/#keypress-event-order
+            let event = KeyboardEvent::new(
+                &self.window,
+                DOMString::from("keypress"),
+                true,
+                true,
+                Some(&self.window),
+                0,
+                keyboard_event.key.clone(),
+                DOMString::from(keyboard_event.code.to_string()),
+                keyboard_event.location as u32,
+                keyboard_event.repeat,
+                keyboard_event.is_composing,
+                keyboard_event.modifiers,
+                keyboard_event.key.legacy_charcode(),
+                0,
+            );
+            let ev = event.upcast::<Event>();
+            ev.fire(target);
+            cancel_state = ev.get_cancel_state();
+        }
+
+        // Escape exits fullscreen, which pages can't prevent, instead of being handled by
+        // the embedder.
+        // https://fullscreen.spec.whatwg.org/#ui
+        if keyboard_event.state == KeyState::Down &&
+            keyboard_event.key == Key::Escape &&
+            self.fullscreen_element().is_some()
+        {
+            self.exit_fullscreen_for_user();
+            cancel_state = EventDefault::Handled;
+        }
+
+        // The default action of the key in an editing host edits it.
+        if cancel_state == EventDefault::Allowed &&
+            keyboard_event.state == KeyState::Down &&
+            !keyboard_event.is_composing &&
+            editing::handle_keydown(self, &keyevent)
+        {
+            cancel_state = EventDefault::Handled;
+        }
+
+        if cancel_state == EventDefault::Allowed {
+            let msg = EmbedderMsg::Keyboard(keyboard_event.clone());
+            self.send_to_embedder(msg);
+
+            if keyboard_event.state == KeyState::Down {
+                if let Some(type_) = ClipboardEventType::for_shortcut(&keyevent) {
+                    self.handle_clipboard_action(type_);
+                }
+
+                match keyboard_event.key {
+                    // https://html.spec.whatwg.org/multipage/#canceling-dialogs
+                    Key::Escape => {
+                        if let Some(dialog) = self.active_modal_dialog() {
+                            dialog.cancel();
+                        }
+                    },
+                    Key::Tab => self.move_focus_within_modal_dialog(
+                        keyboard_event.modifiers.contains(Modifiers::SHIFT),
+                    ),
+                    _ => {},
+                }
+            }
+
+            // This behavior is unspecced
+            // We are supposed to dispatch synthetic click activation for Space and/or Return,
+            // however *when* we do it is up to us.
+            // Here, we're dispatching it after the key event so the script has a chance to cancel it
+            // https://www.w3.org/Bugs/Public/show_bug.cgi?id=27337
+            if (keyboard_event.key == Key::Enter || keyboard_event.code == Code::Space) &&
+                keyboard_event.state == KeyState::Up
+            {
+                if let Some(elem) = target.downcast::<Element>() {
+                    elem.upcast::<Node>()
+                        .fire_synthetic_mouse_event_not_trusted(DOMString::from("click"));
+                }
+            }
+        }
+
+        self.window.reflow(ReflowGoal::Full, ReflowReason::KeyEvent);
+    }
+
+    /// Moves focus to the next focusable element of the active modal dialog, or to the
+    /// previous one if `backwards`, wrapping around so that sequential focus navigation
+    /// doesn't leave the dialog.
+    fn move_focus_within_modal_dialog(&self, backwards: bool) {
+        let dialog = match self.active_modal_dialog() {
+            Some(dialog) => dialog,
+            None => return,
+        };
+        let focusable: Vec<DomRoot<Element>> = dialog
+            .upcast::<Node>()
+            .traverse_preorder(ShadowIncluding::No)
+            .filter_map(DomRoot::downcast::<Element>)
+            .filter(|element| element.is_focusable_area())
+            .collect();
+        if focusable.is_empty() {
+            return;
+        }
+
+        let current = self
+            .focused
+            .get()
+            .and_then(|focused| focusable.iter().position(|element| *element == focused));
+        let next = match (current, backwards) {
+            (Some(index), false) => (index + 1) % focusable.len(),
+            (Some(index), true) => (index + focusable.len() - 1) % focusable.len(),
+            (None, false) => 0,
+            (None, true) => focusable.len() - 1,
+        };
+        self.request_focus(Some(&*focusable[next]), FocusType::Element);
+    }
+
+    pub fn ime_dismissed(&self) {
+        self.request_focus(
+            self.GetBody().as_ref().map(|e| &*e.upcast()),
+            FocusType::Element,
+        )
+    }
+
+    pub fn dispatch_composition_event(
+        &self,
+        composition_event: ::keyboard_types::CompositionEvent,
+    ) {
+        // spec: https://w3c.github.io/uievents/#compositionstart
+        // spec: https://w3c.github.io/uievents/#compositionupdate
+        // spec: https://w3c.github.io/uievents/#compositionend
+        // > Event.target : focused element processing the composition
+        let focused = self.get_focused_element();
+        let target = if let Some(elem) = &focused {
+            elem.upcast()
+        } else {
+            // Event is only dispatched if there is a focused element.
+            return;
+        };
+
+        let cancelable = composition_event.state == keyboard_types::CompositionState::Start;
 
         let compositionevent = CompositionEvent::new(
             &self.window,
@@ -1827,10 +3070,12 @@ impl Document {
             cancelable,
             Some(&self.window),
             0,
-            DOMString::from(composition_event.data),
+            DOMString::from(composition_event.data.clone()),
         );
         let event = compositionevent.upcast::<Event>();
         event.fire(target);
+
+        editing::handle_composition_event(self, &composition_event);
     }
 
     // https://dom.spec.whatwg.org/#converting-nodes-into-a-node
@@ -1956,6 +3201,14 @@ impl Document {
         }
     }
 
+    /// Whether there are animation frame callbacks waiting for the next frame.
+    pub fn has_animation_frame_callbacks(&self) -> bool {
+        self.animation_frame_list
+            .borrow()
+            .iter()
+            .any(|(_, callback)| callback.is_some())
+    }
+
     /// <https://html.spec.whatwg.org/multipage/#run-the-animation-frame-callbacks>
     pub fn run_the_animation_frame_callbacks(&self) {
         rooted_vec!(let mut animation_frame_list);
@@ -2041,6 +3294,8 @@ impl Document {
     ) {
         request.csp_list = self.get_csp_list().map(|x| x.clone());
         request.https_state = self.https_state.get();
+        request.has_trustworthy_ancestor_origin = self.has_trustworthy_ancestor_origin.get();
+        request.embedder_policy = self.window.upcast::<GlobalScope>().get_embedder_policy();
         let mut loader = self.loader.borrow_mut();
         loader.fetch_async(load, request, fetch_target);
     }
@@ -2127,14 +3382,15 @@ impl Document {
             self.salvageable.set(false);
         }
         let mut can_unload = true;
-        // TODO: Step 8, also check sandboxing modals flag.
+        // Step 8
+        let sandboxed_modals = self.has_active_sandboxing_flag(SandboxingFlagSet::SANDBOXED_MODALS);
         let default_prevented = event.DefaultPrevented();
         let return_value_not_empty = !event
             .downcast::<BeforeUnloadEvent>()
             .unwrap()
             .ReturnValue()
             .is_empty();
-        if default_prevented || return_value_not_empty {
+        if !sandboxed_modals && (default_prevented || return_value_not_empty) {
             let (chan, port) = ipc::channel().expect("Failed to create IPC channel!");
             let msg = EmbedderMsg::AllowUnload(chan);
             self.send_to_embedder(msg);
@@ -3065,6 +4321,14 @@ fn url_has_network_scheme(url: &ServoUrl) -> bool {
     }
 }
 
+/// Whether a subresource at `url`, received by a document that was delivered with
+/// `https_state`, is mixed content that degrades the security state of the page.
+/// A document that wasn't delivered securely has no security state to degrade, even
+/// if its origin is potentially trustworthy, like `http://localhost`.
+pub fn degrades_security_state(https_state: HttpsState, url: &ServoUrl) -> bool {
+    https_state != HttpsState::None && !url.is_potentially_trustworthy()
+}
+
 #[derive(Clone, Copy, Eq, JSTraceable, MallocSizeOf, PartialEq)]
 pub enum HasBrowsingContext {
     No,
@@ -3179,6 +4443,17 @@ impl Document {
             pending_restyles: DomRefCell::new(HashMap::new()),
             needs_paint: Cell::new(false),
             active_touch_points: DomRefCell::new(Vec::new()),
+            active_pointers: DomRefCell::new(
+                vec![(
+                    MOUSE_POINTER_ID,
+                    ActivePointer::new(PointerType::Mouse, true),
+                )]
+                .into_iter()
+                .collect(),
+            ),
+            pending_drag: DomRefCell::new(None),
+            drag_session: DomRefCell::new(None),
+            suppress_next_click: Cell::new(false),
             dom_loading: Cell::new(Default::default()),
             dom_interactive: Cell::new(Default::default()),
             dom_content_loaded_event_start: Cell::new(Default::default()),
@@ -3190,6 +4465,9 @@ impl Document {
             unload_event_start: Cell::new(Default::default()),
             unload_event_end: Cell::new(Default::default()),
             https_state: Cell::new(HttpsState::None),
+            has_trustworthy_ancestor_origin: Cell::new(false),
+            has_only_trustworthy_ancestor_origins: Cell::new(true),
+            displayed_mixed_content: Cell::new(false),
             origin: origin,
             referrer: referrer,
             referrer_policy: Cell::new(referrer_policy),
@@ -3199,7 +4477,9 @@ impl Document {
             ignore_opens_during_unload_counter: Default::default(),
             spurious_animation_frames: Cell::new(0),
             dom_count: Cell::new(1),
-            fullscreen_element: MutNullableDom::new(None),
+            top_layer: DomRefCell::new(vec![]),
+            design_mode: Cell::new(false),
+            editing_state: Default::default(),
             form_id_listener_map: Default::default(),
             interactive_time: DomRefCell::new(interactive_time),
             tti_window: DomRefCell::new(InteractiveWindow::new()),
@@ -3220,6 +4500,8 @@ impl Document {
             dirty_webgl_contexts: DomRefCell::new(HashMap::new()),
             dirty_webgpu_contexts: DomRefCell::new(HashMap::new()),
             csp_list: DomRefCell::new(None),
+            active_sandboxing_flag_set: Cell::new(SandboxingFlagSet::empty()),
+            reporting_endpoints: DomRefCell::new(HashMap::new()),
             selection: MutNullableDom::new(None),
             animation_timeline: if pref!(layout.animations.test.enabled) {
                 DomRefCell::new(AnimationTimeline::new_for_testing())
@@ -3228,11 +4510,33 @@ impl Document {
             },
             animations: DomRefCell::new(Animations::new()),
             dirty_root: Default::default(),
+            intersection_observers: DomRefCell::new(vec![]),
+            intersection_observer_task_queued: Cell::new(false),
+            resize_observers: DomRefCell::new(vec![]),
+            lazy_load_elements: DomRefCell::new(vec![]),
         }
     }
 
-    pub fn set_csp_list(&self, csp_list: Option<CspList>) {
-        *self.csp_list.borrow_mut() = csp_list;
+    pub fn set_csp_list(&self, csp_list: Option<CspList>) {
+        // https://w3c.github.io/webappsec-csp/#sandbox-init
+        let sandboxing_flags = csp_list
+            .as_ref()
+            .map(sandboxing_flags_from_csp_list)
+            .unwrap_or_else(SandboxingFlagSet::empty);
+        self.active_sandboxing_flag_set.set(sandboxing_flags);
+        *self.csp_list.borrow_mut() = csp_list;
+    }
+
+    pub fn has_active_sandboxing_flag(&self, flag: SandboxingFlagSet) -> bool {
+        self.active_sandboxing_flag_set.get().contains(flag)
+    }
+
+    pub fn set_reporting_endpoints(&self, endpoints: HashMap<String, ServoUrl>) {
+        *self.reporting_endpoints.borrow_mut() = endpoints;
+    }
+
+    pub fn reporting_endpoint(&self, name: &str) -> Option<ServoUrl> {
+        self.reporting_endpoints.borrow().get(name).cloned()
     }
 
     pub fn get_csp_list(&self) -> Option<Ref<CspList>> {
@@ -3251,13 +4555,16 @@ impl Document {
                 .get_attribute(&ns!(), &local_name!("nonce"))
                 .map(|attr| Cow::Owned(attr.value().to_string())),
         };
-        // TODO: Instead of ignoring violations, report them.
-        self.get_csp_list()
-            .map(|c| {
-                c.should_elements_inline_type_behavior_be_blocked(&element, type_, source)
-                    .0
-            })
-            .unwrap_or(csp::CheckResult::Allowed)
+        let (result, violations) = match self.get_csp_list() {
+            Some(csp_list) => {
+                csp_list.should_elements_inline_type_behavior_be_blocked(&element, type_, source)
+            },
+            None => return csp::CheckResult::Allowed,
+        };
+        self.window
+            .upcast::<GlobalScope>()
+            .report_csp_violations(violations, Some(el));
+        result
     }
 
     /// Prevent any JS or layout from running until the corresponding call to
@@ -3624,9 +4931,17 @@ impl Document {
         let in_realm_proof = AlreadyInRealm::assert(&self.global());
         let promise =
             Promise::new_in_current_realm(&self.global(), InRealm::Already(&in_realm_proof));
-        let mut error = false;
+
+        // Step 3
+        if !self.is_fully_active() {
+            promise.reject_error(Error::Type(String::from("document is not fully active")));
+            return promise;
+        }
 
         // Step 4
+        let mut error = false;
+
+        // Step 5
         // check namespace
         match *pending.namespace() {
             ns!(mathml) => {
@@ -3639,7 +4954,11 @@ impl Document {
                     error = true;
                 }
             },
-            ns!(html) => (),
+            ns!(html) => {
+                if pending.is::<HTMLDialogElement>() {
+                    error = true;
+                }
+            },
             _ => error = true,
         }
         // fullscreen element ready check
@@ -3647,28 +4966,24 @@ impl Document {
             error = true;
         }
 
-        if pref!(dom.fullscreen.test) {
-            // For reftests we just take over the current window,
-            // and don't try to really enter fullscreen.
-            info!("Tests don't really enter fullscreen.");
-        } else {
-            // TODO fullscreen is supported
-            // TODO This algorithm is allowed to request fullscreen.
-            warn!("Fullscreen not supported yet");
-        }
-
-        // Step 5 Parallel start
+        // Step 7 Parallel start
 
         let window = self.window();
-        // Step 6
+        // Step 7.1
         if !error {
-            let event = EmbedderMsg::SetFullscreenState(true);
-            self.send_to_embedder(event);
+            if pref!(dom.fullscreen.test) {
+                // For reftests we just take over the current window,
+                // and don't try to really enter fullscreen.
+                info!("Tests don't really enter fullscreen.");
+            } else {
+                let event = EmbedderMsg::SetFullscreenState(true);
+                self.send_to_embedder(event);
+            }
         }
 
         let pipeline_id = self.window().pipeline_id();
 
-        // Step 7
+        // Step 7.2 - 7.6
         let trusted_pending = Trusted::new(pending);
         let trusted_promise = TrustedPromise::new(promise.clone());
         let handler = ElementPerformFullscreenEnter::new(trusted_pending, trusted_promise, error);
@@ -3693,24 +5008,30 @@ impl Document {
         let in_realm_proof = AlreadyInRealm::assert(&global);
         let promise = Promise::new_in_current_realm(&global, InRealm::Already(&in_realm_proof));
         // Step 2
-        if self.fullscreen_element.get().is_none() {
-            promise.reject_error(Error::Type(String::from("fullscreen is null")));
-            return promise;
+        let element = match self.fullscreen_element() {
+            Some(element) if self.is_fully_active() => element,
+            _ => {
+                promise.reject_error(Error::Type(String::from("fullscreen is null")));
+                return promise;
+            },
+        };
+
+        // Step 7. The fullscreen element is being removed from the document.
+        if !element.is_connected() {
+            self.unfullscreen_element(&element);
+            self.fire_fullscreen_event(atom!("fullscreenchange"), &element);
+            if self.fullscreen_element().is_none() {
+                self.fullscreen_exited();
+            }
         }
-        // TODO Step 3-6
-        let element = self.fullscreen_element.get().unwrap();
 
-        // Step 7 Parallel start
+        // Step 8 Parallel start
 
         let window = self.window();
-        // Step 8
-        let event = EmbedderMsg::SetFullscreenState(false);
-        self.send_to_embedder(event);
-
-        // Step 9
-        let trusted_element = Trusted::new(&*element);
+        // Step 9 - 15
+        let trusted_document = Trusted::new(self);
         let trusted_promise = TrustedPromise::new(promise.clone());
-        let handler = ElementPerformFullscreenExit::new(trusted_element, trusted_promise);
+        let handler = ElementPerformFullscreenExit::new(trusted_document, trusted_promise);
         let pipeline_id = Some(global.pipeline_id());
         // NOTE: This steps should be running in parallel
         // https://fullscreen.spec.whatwg.org/#exit-fullscreen
@@ -3726,8 +5047,228 @@ impl Document {
         promise
     }
 
-    pub fn set_fullscreen_element(&self, element: Option<&Element>) {
-        self.fullscreen_element.set(element);
+    /// Makes `element` fullscreen, once the checks of `requestFullscreen()` passed, along
+    /// with the iframes that this document is nested in.
+    ///
+    /// <https://fullscreen.spec.whatwg.org/#dom-element-requestfullscreen>, step 7.3 - 7.5.
+    pub fn fullscreen_requested_element(&self, element: &Element) {
+        if self.fullscreen_element().as_deref() == Some(element) {
+            return;
+        }
+        if let Some(iframe) = element.downcast::<HTMLIFrameElement>() {
+            iframe.set_fullscreen_flag(true);
+        }
+        self.fullscreen(element);
+        self.fire_fullscreen_event(atom!("fullscreenchange"), element);
+        if !self.window.is_top_level() {
+            self.window
+                .send_to_constellation(ScriptMsg::FullscreenChanged(true));
+        }
+    }
+
+    /// Exits fullscreen in this document once the checks of `exitFullscreen()` passed,
+    /// which leaves fullscreen in the documents that this document is nested in too if
+    /// this document has no fullscreen element left after it.
+    ///
+    /// <https://fullscreen.spec.whatwg.org/#exit-fullscreen>, step 10 - 14.
+    pub fn exit_fullscreen_steps(&self) {
+        let element = match self.fullscreen_element() {
+            Some(element) => element,
+            None => return,
+        };
+        let simple = self.is_simple_fullscreen_document();
+        self.unfullscreen_element(&element);
+        self.fire_fullscreen_event(atom!("fullscreenchange"), &element);
+
+        // The documents nested in an iframe that leaves fullscreen leave it too.
+        if let Some(browsing_context_id) = element
+            .downcast::<HTMLIFrameElement>()
+            .and_then(|iframe| iframe.browsing_context_id())
+        {
+            self.window
+                .send_to_constellation(ScriptMsg::ExitFullscreen(Some(browsing_context_id)));
+        }
+
+        if simple {
+            self.fullscreen_exited();
+        }
+    }
+
+    /// Makes the iframe that a nested browsing context is in enter fullscreen, because its
+    /// document did, or leave it, because its document has no fullscreen element left.
+    ///
+    /// <https://fullscreen.spec.whatwg.org/#collect-documents-to-unfullscreen>
+    pub fn set_iframe_fullscreen(&self, iframe: &HTMLIFrameElement, fullscreen: bool) {
+        let element = iframe.upcast::<Element>();
+        if fullscreen {
+            if self.fullscreen_element().as_deref() == Some(element) {
+                return;
+            }
+            self.fullscreen(element);
+            self.fire_fullscreen_event(atom!("fullscreenchange"), element);
+            if !self.window.is_top_level() {
+                self.window
+                    .send_to_constellation(ScriptMsg::FullscreenChanged(true));
+            }
+            return;
+        }
+
+        // An iframe that was requested to be fullscreen itself stays fullscreen.
+        if !element.state().contains(ElementState::IN_FULLSCREEN_STATE) || iframe.fullscreen_flag()
+        {
+            return;
+        }
+        let simple = self.is_simple_fullscreen_document();
+        self.unfullscreen_element(element);
+        self.fire_fullscreen_event(atom!("fullscreenchange"), element);
+        if simple {
+            self.fullscreen_exited();
+        }
+    }
+
+    /// <https://fullscreen.spec.whatwg.org/#fully-exit-fullscreen>
+    pub fn fully_exit_fullscreen(&self) {
+        // Step 1.
+        let element = match self.fullscreen_element() {
+            Some(element) => element,
+            None => return,
+        };
+
+        // Step 2 - 3.
+        for other in self.top_layer() {
+            if other.state().contains(ElementState::IN_FULLSCREEN_STATE) {
+                self.unfullscreen_element(&other);
+            }
+        }
+        self.fire_fullscreen_event(atom!("fullscreenchange"), &element);
+    }
+
+    /// Leaves fullscreen in the iframe that this document is nested in, or in the window
+    /// if it is the document of a top-level browsing context, because this document has no
+    /// fullscreen element left.
+    fn fullscreen_exited(&self) {
+        if !self.window.is_top_level() {
+            self.window
+                .send_to_constellation(ScriptMsg::FullscreenChanged(false));
+        } else if !pref!(dom.fullscreen.test) {
+            self.send_to_embedder(EmbedderMsg::SetFullscreenState(false));
+        }
+    }
+
+    /// Exits fullscreen in the whole browser, which the user asked for, usually by
+    /// pressing `Escape`.
+    fn exit_fullscreen_for_user(&self) {
+        if !pref!(dom.fullscreen.test) {
+            self.send_to_embedder(EmbedderMsg::SetFullscreenState(false));
+        }
+        self.window
+            .send_to_constellation(ScriptMsg::ExitFullscreen(None));
+    }
+
+    /// <https://fullscreen.spec.whatwg.org/#fullscreen-element>
+    pub fn fullscreen_element(&self) -> Option<DomRoot<Element>> {
+        self.top_layer
+            .borrow()
+            .iter()
+            .rev()
+            .find(|element| element.state().contains(ElementState::IN_FULLSCREEN_STATE))
+            .map(|element| DomRoot::from_ref(&**element))
+    }
+
+    /// <https://fullscreen.spec.whatwg.org/#simple-fullscreen-document>
+    fn is_simple_fullscreen_document(&self) -> bool {
+        self.top_layer
+            .borrow()
+            .iter()
+            .filter(|element| element.state().contains(ElementState::IN_FULLSCREEN_STATE))
+            .count() ==
+            1
+    }
+
+    /// <https://fullscreen.spec.whatwg.org/#fullscreen-an-element>
+    fn fullscreen(&self, element: &Element) {
+        element.set_fullscreen_state(true);
+        self.add_to_top_layer(element);
+    }
+
+    /// <https://fullscreen.spec.whatwg.org/#unfullscreen-an-element>
+    pub fn unfullscreen_element(&self, element: &Element) {
+        element.set_fullscreen_state(false);
+        if let Some(iframe) = element.downcast::<HTMLIFrameElement>() {
+            iframe.set_fullscreen_flag(false);
+        }
+        self.remove_from_top_layer(element);
+    }
+
+    /// Fires a `fullscreenchange` or `fullscreenerror` event at `element`, or at this
+    /// document if `element` isn't in it anymore.
+    ///
+    /// <https://fullscreen.spec.whatwg.org/#run-the-fullscreen-steps>
+    pub fn fire_fullscreen_event(&self, type_: Atom, element: &Element) {
+        let target = if element.is_connected() && *element.upcast::<Node>().owner_doc() == *self {
+            element.upcast::<EventTarget>()
+        } else {
+            self.upcast::<EventTarget>()
+        };
+        let event = Event::new(
+            self.window.upcast(),
+            type_,
+            EventBubbles::Bubbles,
+            EventCancelable::NotCancelable,
+        );
+        event.set_trusted(true);
+        event.fire(target);
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#designMode>
+    pub fn is_design_mode(&self) -> bool {
+        self.design_mode.get()
+    }
+
+    pub fn editing_state(&self) -> &DomRefCell<EditingState> {
+        &self.editing_state
+    }
+
+    /// The elements of the top layer, from bottom to top.
+    ///
+    /// <https://fullscreen.spec.whatwg.org/#top-layer>
+    pub fn top_layer(&self) -> Vec<DomRoot<Element>> {
+        self.top_layer
+            .borrow()
+            .iter()
+            .map(|element| DomRoot::from_ref(&**element))
+            .collect()
+    }
+
+    /// <https://fullscreen.spec.whatwg.org/#top-layer-add>
+    pub fn add_to_top_layer(&self, element: &Element) {
+        {
+            let mut top_layer = self.top_layer.borrow_mut();
+            top_layer.retain(|other| &**other != element);
+            top_layer.push(Dom::from_ref(element));
+        }
+        element.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+    }
+
+    /// <https://fullscreen.spec.whatwg.org/#top-layer-remove>
+    pub fn remove_from_top_layer(&self, element: &Element) {
+        self.top_layer
+            .borrow_mut()
+            .retain(|other| &**other != element);
+        element.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+    }
+
+    /// The topmost modal dialog of the top layer, which blocks the rest of the document.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#blocked-by-a-modal-dialog>
+    pub fn active_modal_dialog(&self) -> Option<DomRoot<HTMLDialogElement>> {
+        self.top_layer
+            .borrow()
+            .iter()
+            .rev()
+            .filter_map(|element| element.downcast::<HTMLDialogElement>())
+            .find(|dialog| dialog.is_modal())
+            .map(DomRoot::from_ref)
     }
 
     pub fn get_allow_fullscreen(&self) -> bool {
@@ -3980,6 +5521,216 @@ impl Document {
     pub(crate) fn cancel_animations_for_node(&self, node: &Node) {
         self.animations.borrow().cancel_animations_for_node(node);
     }
+
+    pub(crate) fn add_intersection_observer(&self, observer: &IntersectionObserver) {
+        let mut observers = self.intersection_observers.borrow_mut();
+        if !observers.iter().any(|o| &**o == observer) {
+            observers.push(Dom::from_ref(observer));
+        }
+    }
+
+    pub(crate) fn intersection_observers(&self) -> Vec<DomRoot<IntersectionObserver>> {
+        self.intersection_observers
+            .borrow()
+            .iter()
+            .map(|observer| DomRoot::from_ref(&**observer))
+            .collect()
+    }
+
+    pub(crate) fn intersection_observer_task_queued(&self) -> bool {
+        self.intersection_observer_task_queued.get()
+    }
+
+    pub(crate) fn set_intersection_observer_task_queued(&self, queued: bool) {
+        self.intersection_observer_task_queued.set(queued);
+    }
+
+    /// <https://w3c.github.io/IntersectionObserver/#queue-an-intersection-observer-task>
+    /// Step 3
+    pub(crate) fn queue_notify_intersection_observers(&self) {
+        let document = Trusted::new(self);
+        let _ = self
+            .window
+            .task_manager()
+            .dom_manipulation_task_source()
+            .queue(
+                task!(notify_intersection_observers: move || {
+                    IntersectionObserver::notify_intersection_observers(&document.root());
+                }),
+                self.window.upcast(),
+            );
+    }
+
+    pub(crate) fn add_resize_observer(&self, observer: &ResizeObserver) {
+        let mut observers = self.resize_observers.borrow_mut();
+        if !observers.iter().any(|o| &**o == observer) {
+            observers.push(Dom::from_ref(observer));
+        }
+    }
+
+    fn resize_observers(&self) -> Vec<DomRoot<ResizeObserver>> {
+        self.resize_observers
+            .borrow()
+            .iter()
+            .map(|observer| DomRoot::from_ref(&**observer))
+            .collect()
+    }
+
+    /// <https://drafts.csswg.org/resize-observer/#gather-active-observations-h>
+    pub(crate) fn gather_active_resize_observations_at_depth(&self, depth: usize) {
+        // Observers without targets no longer need to be kept alive by this document.
+        self.resize_observers
+            .borrow_mut()
+            .retain(|observer| observer.has_observation_targets());
+
+        // Step 2
+        for observer in self.resize_observers() {
+            observer.gather_active_observations_at_depth(depth);
+        }
+    }
+
+    /// <https://drafts.csswg.org/resize-observer/#has-active-observations-h>
+    pub(crate) fn has_active_resize_observations(&self) -> bool {
+        self.resize_observers
+            .borrow()
+            .iter()
+            .any(|observer| observer.has_active_observations())
+    }
+
+    /// <https://drafts.csswg.org/resize-observer/#has-skipped-observations-h>
+    pub(crate) fn has_skipped_resize_observations(&self) -> bool {
+        self.resize_observers
+            .borrow()
+            .iter()
+            .any(|observer| observer.has_skipped_observations())
+    }
+
+    /// <https://drafts.csswg.org/resize-observer/#broadcast-active-observations>
+    ///
+    /// Returns the depth of the shallowest target that was broadcast.
+    pub(crate) fn broadcast_active_resize_observations(&self) -> usize {
+        // Steps 1-2
+        self.resize_observers()
+            .iter()
+            .filter_map(|observer| observer.broadcast_active_observations())
+            .min()
+            .unwrap_or(usize::max_value())
+    }
+
+    /// <https://drafts.csswg.org/resize-observer/#deliver-resize-error>
+    pub(crate) fn deliver_resize_loop_error_notification(&self) {
+        let error_info = ErrorInfo {
+            message: "ResizeObserver loop completed with undelivered notifications.".to_owned(),
+            filename: String::new(),
+            lineno: 0,
+            column: 0,
+        };
+        self.window
+            .upcast::<GlobalScope>()
+            .report_an_error(error_info, HandleValue::null());
+    }
+
+    /// <https://w3c.github.io/IntersectionObserver/#run-the-update-intersection-observations-steps>
+    pub(crate) fn update_intersection_observations(&self) {
+        self.update_lazy_load_intersection_observations();
+
+        // Observers that were disconnected and have delivered their last entries
+        // no longer need to be kept alive by this document.
+        self.intersection_observers
+            .borrow_mut()
+            .retain(|observer| observer.has_observation_targets() || observer.has_queued_entries());
+        if self.intersection_observers.borrow().is_empty() {
+            return;
+        }
+
+        // Step 1
+        let time = self.global().performance().Now();
+        // Step 2
+        for observer in self.intersection_observers() {
+            observer.update_intersection_observations(time);
+        }
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#start-intersection-observing-a-lazy-loading-element>
+    pub(crate) fn start_intersection_observing_a_lazy_loading_element(&self, element: &Element) {
+        let mut elements = self.lazy_load_elements.borrow_mut();
+        if !elements.iter().any(|e| &**e == element) {
+            elements.push(Dom::from_ref(element));
+        }
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#stop-intersection-observing-a-lazy-loading-element>
+    pub(crate) fn stop_intersection_observing_a_lazy_loading_element(&self, element: &Element) {
+        self.lazy_load_elements
+            .borrow_mut()
+            .retain(|e| &**e != element);
+    }
+
+    /// Resumes the loads of the lazily loaded elements that came within the root margin
+    /// of the viewport, which the spec does in the callback of the lazy load intersection
+    /// observer. The margin is the `dom.lazy_loading.root_margin` pref.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#lazy-load-intersection-observer>
+    fn update_lazy_load_intersection_observations(&self) {
+        if self.lazy_load_elements.borrow().is_empty() {
+            return;
+        }
+
+        let root_margin = pref!(dom.lazy_loading.root_margin);
+        let elements: Vec<DomRoot<Element>> = self
+            .lazy_load_elements
+            .borrow()
+            .iter()
+            .map(|element| DomRoot::from_ref(&**element))
+            .collect();
+        for element in elements {
+            // An element that was adopted by another document is observed by that one.
+            if &*element.upcast::<Node>().owner_doc() != self {
+                self.stop_intersection_observing_a_lazy_loading_element(&element);
+                continue;
+            }
+
+            // Step 1.2
+            if !intersects_lazy_load_root(&element, &root_margin) {
+                continue;
+            }
+
+            // Steps 1.4-1.6
+            self.stop_intersection_observing_a_lazy_loading_element(&element);
+            let element = Trusted::new(&*element);
+            let _ = self
+                .window
+                .task_manager()
+                .dom_manipulation_task_source()
+                .queue(
+                    task!(lazy_load_resumption_steps: move || {
+                        let element = element.root();
+                        // A load that was restarted since then waits for the element to
+                        // come near the viewport again.
+                        let document = element.upcast::<Node>().owner_doc();
+                        if document.lazy_load_elements.borrow().iter().any(|e| **e == *element) {
+                            return;
+                        }
+                        element.resume_lazy_load();
+                    }),
+                    self.window.upcast(),
+                );
+        }
+    }
+
+    /// Resumes the loads of all the lazily loaded elements of this document without waiting
+    /// for them to come near the viewport, because the whole document is needed.
+    pub(crate) fn load_lazy_loading_elements(&self) {
+        let elements: Vec<DomRoot<Element>> = self
+            .lazy_load_elements
+            .borrow_mut()
+            .drain(..)
+            .map(|element| DomRoot::from_ref(&*element))
+            .collect();
+        for element in elements {
+            element.resume_lazy_load();
+        }
+    }
 }
 
 impl Element {
@@ -4009,9 +5760,68 @@ impl ProfilerMetadataFactory for Document {
 }
 
 impl DocumentMethods for Document {
-    // https://w3c.github.io/editing/ActiveDocuments/execCommand.html#querycommandsupported()
-    fn QueryCommandSupported(&self, _command: DOMString) -> bool {
-        false
+    // https://html.spec.whatwg.org/multipage/#dom-document-designmode
+    fn DesignMode(&self) -> DOMString {
+        DOMString::from(if self.design_mode.get() { "on" } else { "off" })
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-document-designmode
+    fn SetDesignMode(&self, value: DOMString) {
+        // Step 1.
+        let design_mode = if value.eq_ignore_ascii_case("on") {
+            true
+        } else if value.eq_ignore_ascii_case("off") {
+            false
+        } else {
+            return;
+        };
+        if design_mode == self.design_mode.get() {
+            return;
+        }
+        self.design_mode.set(design_mode);
+
+        // Step 2.
+        if design_mode {
+            if let Some(selection) = self.GetSelection() {
+                let _ = selection.Collapse(Some(self.upcast()), 0);
+            }
+            if let Some(root) = self.GetDocumentElement() {
+                self.request_focus(Some(&*root), FocusType::Element);
+            }
+        }
+        if let Some(root) = self.GetDocumentElement() {
+            root.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+        }
+    }
+
+    // https://w3c.github.io/editing/docs/execCommand/#execcommand()
+    fn ExecCommand(&self, command: DOMString, _show_ui: bool, value: DOMString) -> bool {
+        editing::exec_command(self, &command, value)
+    }
+
+    // https://w3c.github.io/editing/docs/execCommand/#querycommandenabled()
+    fn QueryCommandEnabled(&self, command: DOMString) -> bool {
+        editing::query_command_enabled(self, &command)
+    }
+
+    // https://w3c.github.io/editing/docs/execCommand/#querycommandindeterm()
+    fn QueryCommandIndeterm(&self, command: DOMString) -> bool {
+        editing::query_command_indeterm(self, &command)
+    }
+
+    // https://w3c.github.io/editing/docs/execCommand/#querycommandstate()
+    fn QueryCommandState(&self, command: DOMString) -> bool {
+        editing::query_command_state(self, &command)
+    }
+
+    // https://w3c.github.io/editing/docs/execCommand/#querycommandsupported()
+    fn QueryCommandSupported(&self, command: DOMString) -> bool {
+        editing::query_command_supported(&command)
+    }
+
+    // https://w3c.github.io/editing/docs/execCommand/#querycommandvalue()
+    fn QueryCommandValue(&self, command: DOMString) -> DOMString {
+        editing::query_command_value(self, &command)
     }
 
     // https://drafts.csswg.org/cssom/#dom-document-stylesheets
@@ -4077,9 +5887,10 @@ impl DocumentMethods for Document {
             return Err(Error::Security);
         }
 
-        // TODO: Step 2. "If this Document object's active sandboxing
-        // flag set has its sandboxed document.domain browsing context
-        // flag set, then throw a "SecurityError" DOMException."
+        // Step 2.
+        if self.has_active_sandboxing_flag(SandboxingFlagSet::SANDBOXED_DOCUMENT_DOMAIN) {
+            return Err(Error::Security);
+        }
 
         // Steps 3-4.
         let effective_domain = match self.origin.effective_domain() {
@@ -5173,13 +6984,13 @@ impl DocumentMethods for Document {
 
     // https://fullscreen.spec.whatwg.org/#dom-document-fullscreen
     fn Fullscreen(&self) -> bool {
-        self.fullscreen_element.get().is_some()
+        self.fullscreen_element().is_some()
     }
 
     // https://fullscreen.spec.whatwg.org/#dom-document-fullscreenelement
     fn GetFullscreenElement(&self) -> Option<DomRoot<Element>> {
         // TODO ShadowRoot
-        self.fullscreen_element.get()
+        self.fullscreen_element()
     }
 
     // https://fullscreen.spec.whatwg.org/#dom-document-exitfullscreen
@@ -38,6 +38,12 @@ pub enum DOMErrorName {
     DataCloneError = DOMExceptionConstants::DATA_CLONE_ERR,
     NotReadableError,
     OperationError,
+    DataError,
+    ConstraintError,
+    ReadOnlyError,
+    TransactionInactiveError,
+    VersionError,
+    NotAllowedError,
 }
 
 impl DOMErrorName {
@@ -67,6 +73,12 @@ impl DOMErrorName {
             "DataCloneError" => Some(DOMErrorName::DataCloneError),
             "NotReadableError" => Some(DOMErrorName::NotReadableError),
             "OperationError" => Some(DOMErrorName::OperationError),
+            "DataError" => Some(DOMErrorName::DataError),
+            "ConstraintError" => Some(DOMErrorName::ConstraintError),
+            "ReadOnlyError" => Some(DOMErrorName::ReadOnlyError),
+            "TransactionInactiveError" => Some(DOMErrorName::TransactionInactiveError),
+            "VersionError" => Some(DOMErrorName::VersionError),
+            "NotAllowedError" => Some(DOMErrorName::NotAllowedError),
             _ => None,
         }
     }
@@ -114,6 +126,22 @@ impl DOMException {
             DOMErrorName::OperationError => {
                 "The operation failed for an operation-specific reason."
             },
+            DOMErrorName::DataError => "The provided data is inadequate.",
+            DOMErrorName::ConstraintError => {
+                "A mutation operation in a transaction failed because a constraint was not satisfied."
+            },
+            DOMErrorName::ReadOnlyError => {
+                "A mutation operation was attempted in a read-only transaction."
+            },
+            DOMErrorName::TransactionInactiveError => {
+                "A request was placed against a transaction that is not active."
+            },
+            DOMErrorName::VersionError => {
+                "An attempt was made to open a database using a lower version than the existing version."
+            },
+            DOMErrorName::NotAllowedError => {
+                "The request is not allowed by the user agent or the platform in the current context."
+            },
         };
 
         (
@@ -0,0 +1,117 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::codegen::Bindings::DragEventBinding;
+use crate::dom::bindings::codegen::Bindings::DragEventBinding::DragEventMethods;
+use crate::dom::bindings::codegen::Bindings::MouseEventBinding::MouseEventMethods;
+use crate::dom::bindings::error::Fallible;
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::reflector::reflect_dom_object;
+use crate::dom::bindings::root::{DomRoot, MutNullableDom};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::datatransfer::DataTransfer;
+use crate::dom::event::{Event, EventBubbles, EventCancelable};
+use crate::dom::eventtarget::EventTarget;
+use crate::dom::mouseevent::MouseEvent;
+use crate::dom::window::Window;
+use dom_struct::dom_struct;
+use euclid::default::Point2D;
+
+#[dom_struct]
+pub struct DragEvent {
+    mouseevent: MouseEvent,
+    data_transfer: MutNullableDom<DataTransfer>,
+}
+
+impl DragEvent {
+    fn new_inherited() -> DragEvent {
+        DragEvent {
+            mouseevent: MouseEvent::new_inherited(),
+            data_transfer: Default::default(),
+        }
+    }
+
+    pub fn new_uninitialized(window: &Window) -> DomRoot<DragEvent> {
+        reflect_dom_object(Box::new(DragEvent::new_inherited()), window)
+    }
+
+    /// Creates a trusted drag event for a drag-and-drop operation.
+    pub fn new_for_drag(
+        window: &Window,
+        type_: DOMString,
+        cancelable: EventCancelable,
+        client_point: Point2D<f32>,
+        buttons: u16,
+        related_target: Option<&EventTarget>,
+        data_transfer: Option<&DataTransfer>,
+    ) -> DomRoot<DragEvent> {
+        let client_x = client_point.x as i32;
+        let client_y = client_point.y as i32;
+        let ev = DragEvent::new_uninitialized(window);
+        ev.mouseevent.initialize_mouse_event(
+            type_,
+            EventBubbles::Bubbles,
+            cancelable,
+            Some(window),
+            0,
+            client_x,
+            client_y, // TODO: Get real screen coordinates?
+            client_x,
+            client_y,
+            false,
+            false,
+            false,
+            false,
+            0,
+            buttons,
+            related_target,
+            None,
+        );
+        ev.data_transfer.set(data_transfer);
+        ev.upcast::<Event>().set_trusted(true);
+        ev
+    }
+
+    #[allow(non_snake_case)]
+    pub fn Constructor(
+        window: &Window,
+        type_: DOMString,
+        init: &DragEventBinding::DragEventInit,
+    ) -> Fallible<DomRoot<DragEvent>> {
+        let ev = DragEvent::new_uninitialized(window);
+        ev.mouseevent.initialize_mouse_event(
+            type_,
+            EventBubbles::from(init.parent.parent.parent.parent.bubbles),
+            EventCancelable::from(init.parent.parent.parent.parent.cancelable),
+            init.parent.parent.parent.view.as_deref(),
+            init.parent.parent.parent.detail,
+            init.parent.screenX,
+            init.parent.screenY,
+            init.parent.clientX,
+            init.parent.clientY,
+            init.parent.parent.ctrlKey,
+            init.parent.parent.altKey,
+            init.parent.parent.shiftKey,
+            init.parent.parent.metaKey,
+            init.parent.button,
+            init.parent.buttons,
+            init.parent.relatedTarget.as_deref(),
+            None,
+        );
+        ev.data_transfer.set(init.dataTransfer.as_deref());
+        Ok(ev)
+    }
+}
+
+impl DragEventMethods for DragEvent {
+    // https://html.spec.whatwg.org/multipage/#dom-dragevent-datatransfer
+    fn GetDataTransfer(&self) -> Option<DomRoot<DataTransfer>> {
+        self.data_transfer.get()
+    }
+
+    // https://dom.spec.whatwg.org/#dom-event-istrusted
+    fn IsTrusted(&self) -> bool {
+        self.mouseevent.IsTrusted()
+    }
+}
@@ -37,7 +37,6 @@ use crate::dom::document::{determine_policy_for_token, Document, LayoutDocumentH
 use crate::dom::documentfragment::DocumentFragment;
 use crate::dom::domrect::DOMRect;
 use crate::dom::domtokenlist::DOMTokenList;
-use crate::dom::eventtarget::EventTarget;
 use crate::dom::htmlanchorelement::HTMLAnchorElement;
 use crate::dom::htmlbodyelement::{HTMLBodyElement, HTMLBodyElementLayoutHelpers};
 use crate::dom::htmlbuttonelement::HTMLButtonElement;
@@ -67,6 +66,7 @@ use crate::dom::htmltablesectionelement::{
 };
 use crate::dom::htmltemplateelement::HTMLTemplateElement;
 use crate::dom::htmltextareaelement::{HTMLTextAreaElement, LayoutHTMLTextAreaElementHelpers};
+use crate::dom::intersectionobserver::{IntersectionObserver, IntersectionObserverRegistration};
 use crate::dom::mutationobserver::{Mutation, MutationObserver};
 use crate::dom::namednodemap::NamedNodeMap;
 use crate::dom::node::{document_from_node, window_from_node};
@@ -84,6 +84,7 @@ use crate::dom::window::ReflowReason;
 use crate::script_thread::ScriptThread;
 use crate::stylesheet_loader::StylesheetOwner;
 use crate::task::TaskOnce;
+use content_security_policy as csp;
 use devtools_traits::AttrInfo;
 use dom_struct::dom_struct;
 use euclid::default::Rect;
@@ -98,6 +99,7 @@ use msg::constellation_msg::InputMethodType;
 use net_traits::request::CorsSettings;
 use net_traits::ReferrerPolicy;
 use script_layout_interface::message::ReflowGoal;
+use script_traits::TouchActions;
 use selectors::attr::{AttrSelectorOperation, CaseSensitivity, NamespaceConstraint};
 use selectors::matching::{ElementSelectorFlags, MatchingContext};
 use selectors::sink::Push;
@@ -354,6 +356,42 @@ impl Element {
         self.ensure_rare_data().custom_element_definition = None;
     }
 
+    /// Add a new intersection observer registration for this element.
+    pub fn add_intersection_observer_registration(
+        &self,
+        registration: IntersectionObserverRegistration,
+    ) {
+        self.ensure_rare_data()
+            .registered_intersection_observers
+            .push(registration);
+    }
+
+    /// Removes the intersection observer registration of the given observer.
+    pub fn remove_intersection_observer_registration(&self, observer: &IntersectionObserver) {
+        if let Some(ref mut rare_data) = *self.rare_data_mut() {
+            rare_data
+                .registered_intersection_observers
+                .retain(|registration| &*registration.observer != observer);
+        }
+    }
+
+    /// Runs `f` with the registration of the given intersection observer, if any.
+    pub fn with_intersection_observer_registration<F, R>(
+        &self,
+        observer: &IntersectionObserver,
+        f: F,
+    ) -> Option<R>
+    where
+        F: FnOnce(&IntersectionObserverRegistration) -> R,
+    {
+        self.rare_data()
+            .as_ref()?
+            .registered_intersection_observers
+            .iter()
+            .find(|registration| &*registration.observer == observer)
+            .map(f)
+    }
+
     pub fn push_callback_reaction(&self, function: Rc<Function>, args: Box<[Heap<JSVal>]>) {
         self.ensure_rare_data()
             .custom_element_reaction_queue
@@ -400,6 +438,36 @@ impl Element {
         self.upcast::<Node>().style()
     }
 
+    /// The touch behaviors allowed by the `touch-action` of this element and its ancestors.
+    ///
+    /// <https://w3c.github.io/pointerevents/#determining-supported-touch-behavior>
+    pub fn allowed_touch_actions(&self) -> TouchActions {
+        self.upcast::<Node>()
+            .inclusive_ancestors(ShadowIncluding::No)
+            .filter_map(DomRoot::downcast::<Element>)
+            .filter_map(|element| element.style())
+            .fold(TouchActions::all(), |allowed, style| {
+                let touch_action = style.get_box().clone_touch_action();
+                let element_allows = if touch_action.contains(computed::TouchAction::AUTO) {
+                    TouchActions::all()
+                } else if touch_action.contains(computed::TouchAction::MANIPULATION) {
+                    TouchActions::PAN_X | TouchActions::PAN_Y | TouchActions::PINCH_ZOOM
+                } else {
+                    let mut element_allows = TouchActions::empty();
+                    element_allows.set(
+                        TouchActions::PAN_X,
+                        touch_action.contains(computed::TouchAction::PAN_X),
+                    );
+                    element_allows.set(
+                        TouchActions::PAN_Y,
+                        touch_action.contains(computed::TouchAction::PAN_Y),
+                    );
+                    element_allows
+                };
+                allowed & element_allows
+            })
+    }
+
     // https://drafts.csswg.org/cssom-view/#css-layout-box
     pub fn has_css_layout_box(&self) -> bool {
         self.style()
@@ -1288,7 +1356,7 @@ impl Element {
 
         if let Some(input) = self.downcast::<HTMLInputElement>() {
             input.input_type().as_ime_type()
-        } else if self.is::<HTMLTextAreaElement>() {
+        } else if self.is::<HTMLTextAreaElement>() || self.upcast::<Node>().is_editing_host() {
             Some(InputMethodType::Text)
         } else {
             // Other focusable elements that are not input fields.
@@ -1301,7 +1369,7 @@ impl Element {
             return false;
         }
         let node = self.upcast::<Node>();
-        if node.get_flag(NodeFlags::SEQUENTIALLY_FOCUSABLE) {
+        if node.get_flag(NodeFlags::SEQUENTIALLY_FOCUSABLE) || node.is_editing_host() {
             return true;
         }
 
@@ -1816,6 +1884,39 @@ impl Element {
         document.get_allow_fullscreen()
     }
 
+    /// Whether the `loading` attribute of this element is in the lazy state.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#lazy-loading-attribute>
+    pub fn has_lazy_loading_attribute(&self) -> bool {
+        // TODO change this to local_name! when html5ever updates
+        self.get_attribute(&ns!(), &LocalName::from("loading"))
+            .map_or(false, |attr| attr.value().eq_ignore_ascii_case("lazy"))
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#will-lazy-load-element-steps>
+    pub fn will_lazy_load(&self) -> bool {
+        // Step 1. Without scripting, the deferred fetches would let a page track how far
+        // the user scrolls, which it otherwise couldn't.
+        if !document_from_node(self).is_scripting_enabled() {
+            return false;
+        }
+
+        // Step 2.
+        self.has_lazy_loading_attribute()
+    }
+
+    /// Runs the lazy load resumption steps of this element, which fetch the image or
+    /// navigate the iframe whose load was deferred.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#lazy-load-resumption-steps>
+    pub fn resume_lazy_load(&self) {
+        if let Some(image) = self.downcast::<HTMLImageElement>() {
+            image.resume_lazy_load();
+        } else if let Some(iframe) = self.downcast::<HTMLIFrameElement>() {
+            iframe.resume_lazy_load();
+        }
+    }
+
     // https://html.spec.whatwg.org/multipage/#home-subtree
     pub fn is_in_same_home_subtree<T>(&self, other: &T) -> bool
     where
@@ -2787,6 +2888,21 @@ impl ElementMethods for Element {
         doc.enter_fullscreen(self)
     }
 
+    // https://w3c.github.io/pointerevents/#dom-element-setpointercapture
+    fn SetPointerCapture(&self, pointer_id: i32) -> ErrorResult {
+        document_from_node(self).set_pointer_capture(self, pointer_id)
+    }
+
+    // https://w3c.github.io/pointerevents/#dom-element-releasepointercapture
+    fn ReleasePointerCapture(&self, pointer_id: i32) -> ErrorResult {
+        document_from_node(self).release_pointer_capture(self, pointer_id)
+    }
+
+    // https://w3c.github.io/pointerevents/#dom-element-haspointercapture
+    fn HasPointerCapture(&self, pointer_id: i32) -> bool {
+        document_from_node(self).has_pointer_capture(self, pointer_id)
+    }
+
     // XXX Hidden under dom.shadowdom.enabled pref. Only exposed to be able
     //     to test partial Shadow DOM support for UA widgets.
     // https://dom.spec.whatwg.org/#dom-element-attachshadow
@@ -2843,18 +2959,29 @@ impl VirtualMethods for Element {
                             };
                             let mut value = AttrValue::String(serialization);
                             attr.swap_value(&mut value);
-                            block
+                            Some(block)
+                        } else if doc.should_elements_inline_type_behavior_be_blocked(
+                            self,
+                            csp::InlineCheckType::StyleAttribute,
+                            &attr.value(),
+                        ) == csp::CheckResult::Blocked
+                        {
+                            // https://html.spec.whatwg.org/multipage/#the-style-attribute
+                            warn!("Blocking style attribute due to CSP");
+                            None
                         } else {
                             let win = window_from_node(self);
-                            Arc::new(doc.style_shared_lock().wrap(parse_style_attribute(
-                                &attr.value(),
-                                &doc.base_url(),
-                                win.css_error_reporter(),
-                                doc.quirks_mode(),
+                            Some(Arc::new(doc.style_shared_lock().wrap(
+                                parse_style_attribute(
+                                    &attr.value(),
+                                    &doc.base_url(),
+                                    win.css_error_reporter(),
+                                    doc.quirks_mode(),
+                                ),
                             )))
                         };
 
-                        Some(block)
+                        block
                     },
                     AttributeMutation::Removed => None,
                 };
@@ -3030,9 +3157,13 @@ impl VirtualMethods for Element {
             }
         }
 
-        let fullscreen = doc.GetFullscreenElement();
-        if fullscreen.as_deref() == Some(self) {
-            doc.exit_fullscreen();
+        // https://fullscreen.spec.whatwg.org/#removing-steps
+        if self.state().contains(ElementState::IN_FULLSCREEN_STATE) {
+            if doc.fullscreen_element().as_deref() == Some(self) {
+                doc.exit_fullscreen();
+            } else {
+                doc.unfullscreen_element(self);
+            }
         }
         if let Some(ref value) = *self.id_attribute.borrow() {
             doc.unregister_element_id(self, value.clone());
@@ -3213,6 +3344,7 @@ impl<'a> SelectorsElement for DomRoot<Element> {
             NonTSPseudoClass::Focus |
             NonTSPseudoClass::Fullscreen |
             NonTSPseudoClass::Hover |
+            NonTSPseudoClass::Modal |
             NonTSPseudoClass::Defined |
             NonTSPseudoClass::Enabled |
             NonTSPseudoClass::Disabled |
@@ -3682,45 +3814,36 @@ impl TaskOnce for ElementPerformFullscreenEnter {
         let promise = self.promise.root();
         let document = document_from_node(&*element);
 
-        // Step 7.1
+        // Step 7.2
         if self.error || !element.fullscreen_element_ready_check() {
-            document
-                .upcast::<EventTarget>()
-                .fire_event(atom!("fullscreenerror"));
+            document.fire_fullscreen_event(atom!("fullscreenerror"), &element);
             promise.reject_error(Error::Type(String::from("fullscreen is not connected")));
             return;
         }
 
-        // TODO Step 7.2-4
-        // Step 7.5
-        element.set_fullscreen_state(true);
-        document.set_fullscreen_element(Some(&element));
+        // Step 7.3 - 7.5
+        document.fullscreen_requested_element(&element);
         document
             .window()
             .reflow(ReflowGoal::Full, ReflowReason::ElementStateChanged);
 
         // Step 7.6
-        document
-            .upcast::<EventTarget>()
-            .fire_event(atom!("fullscreenchange"));
-
-        // Step 7.7
         promise.resolve_native(&());
     }
 }
 
 pub struct ElementPerformFullscreenExit {
-    element: Trusted<Element>,
+    document: Trusted<Document>,
     promise: TrustedPromise,
 }
 
 impl ElementPerformFullscreenExit {
     pub fn new(
-        element: Trusted<Element>,
+        document: Trusted<Document>,
         promise: TrustedPromise,
     ) -> Box<ElementPerformFullscreenExit> {
         Box::new(ElementPerformFullscreenExit {
-            element: element,
+            document: document,
             promise: promise,
         })
     }
@@ -3729,24 +3852,15 @@ impl ElementPerformFullscreenExit {
 impl TaskOnce for ElementPerformFullscreenExit {
     #[allow(unrooted_must_root)]
     fn run_once(self) {
-        let element = self.element.root();
-        let document = document_from_node(&*element);
-        // TODO Step 9.1-5
-        // Step 9.6
-        element.set_fullscreen_state(false);
+        let document = self.document.root();
 
+        // Step 10 - 14
+        document.exit_fullscreen_steps();
         document
             .window()
             .reflow(ReflowGoal::Full, ReflowReason::ElementStateChanged);
 
-        document.set_fullscreen_element(None);
-
-        // Step 9.8
-        document
-            .upcast::<EventTarget>()
-            .fire_event(atom!("fullscreenchange"));
-
-        // Step 9.10
+        // Step 15
         self.promise.root().resolve_native(&());
     }
 }
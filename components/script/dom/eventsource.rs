@@ -22,6 +22,7 @@ use crate::network_listener::{self, NetworkListener, PreInvoke, ResourceTimingLi
 use crate::realms::enter_realm;
 use crate::task_source::{TaskSource, TaskSourceName};
 use crate::timers::OneshotTimerCallback;
+use content_security_policy as csp;
 use dom_struct::dom_struct;
 use euclid::Length;
 use headers::ContentType;
@@ -422,6 +423,11 @@ impl FetchResponseListener for EventSourceContext {
         &self.resource_timing
     }
 
+    fn process_csp_violations(&mut self, violations: Vec<csp::Violation>) {
+        let global = &self.resource_timing_global();
+        global.report_csp_violations(violations, None);
+    }
+
     fn submit_resource_timing(&mut self) {
         network_listener::submit_timing(self)
     }
@@ -582,7 +588,7 @@ impl EventSource {
             .core_resource_thread()
             .send(CoreResourceMsg::Fetch(
                 request,
-                FetchChannels::ResponseMsg(action_sender, Some(cancel_receiver)),
+                FetchChannels::ResponseMsg(action_sender, Some(cancel_receiver), None),
             ))
             .unwrap();
         // Step 13
@@ -666,7 +672,7 @@ impl EventSourceTimeoutCallback {
             .core_resource_thread()
             .send(CoreResourceMsg::Fetch(
                 request,
-                FetchChannels::ResponseMsg(self.action_sender, None),
+                FetchChannels::ResponseMsg(self.action_sender, None, None),
             ))
             .unwrap();
     }
@@ -2,6 +2,7 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
+use crate::dom::abortsignal::AbortAlgorithm;
 use crate::dom::beforeunloadevent::BeforeUnloadEvent;
 use crate::dom::bindings::callback::{CallbackContainer, CallbackFunction, ExceptionHandling};
 use crate::dom::bindings::cell::DomRefCell;
@@ -22,7 +23,7 @@ use crate::dom::bindings::codegen::UnionTypes::EventOrString;
 use crate::dom::bindings::error::{report_pending_exception, Error, Fallible};
 use crate::dom::bindings::inheritance::Castable;
 use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
-use crate::dom::bindings::root::DomRoot;
+use crate::dom::bindings::root::{Dom, DomRoot};
 use crate::dom::bindings::str::DOMString;
 use crate::dom::element::Element;
 use crate::dom::errorevent::ErrorEvent;
@@ -700,8 +701,14 @@ impl EventTarget {
             Some(l) => l,
             None => return,
         };
+        // Step 2.
+        if let Some(ref signal) = options.signal {
+            if signal.aborted() {
+                return;
+            }
+        }
         let mut handlers = self.handlers.borrow_mut();
-        let entry = match handlers.entry(Atom::from(ty)) {
+        let entry = match handlers.entry(Atom::from(ty.clone())) {
             Occupied(entry) => entry.into_mut(),
             Vacant(entry) => entry.insert(EventListeners(vec![])),
         };
@@ -713,11 +720,22 @@ impl EventTarget {
         };
         let new_entry = EventListenerEntry {
             phase: phase,
-            listener: EventListenerType::Additive(listener),
+            listener: EventListenerType::Additive(listener.clone()),
             once: options.once,
         };
-        if !entry.contains(&new_entry) {
-            entry.push(new_entry);
+        if entry.contains(&new_entry) {
+            return;
+        }
+        entry.push(new_entry);
+
+        // Step 6.
+        if let Some(ref signal) = options.signal {
+            signal.add_abort_algorithm(AbortAlgorithm::RemoveEventListener {
+                target: Dom::from_ref(self),
+                ty: ty,
+                listener: listener,
+                capture: options.parent.capture,
+            });
         }
     }
 
@@ -799,6 +817,7 @@ impl From<AddEventListenerOptionsOrBoolean> for AddEventListenerOptions {
             AddEventListenerOptionsOrBoolean::Boolean(capture) => Self {
                 parent: EventListenerOptions { capture },
                 once: false,
+                signal: None,
             },
         }
     }
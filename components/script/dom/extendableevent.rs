@@ -6,21 +6,26 @@ use crate::dom::bindings::codegen::Bindings::EventBinding::{self, EventMethods};
 use crate::dom::bindings::codegen::Bindings::ExtendableEventBinding;
 use crate::dom::bindings::error::{Error, ErrorResult, Fallible};
 use crate::dom::bindings::inheritance::Castable;
-use crate::dom::bindings::reflector::reflect_dom_object;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject};
 use crate::dom::bindings::root::DomRoot;
 use crate::dom::bindings::str::DOMString;
 use crate::dom::event::Event;
+use crate::dom::promise::Promise;
+use crate::dom::promisenativehandler::{Callback, PromiseNativeHandler};
 use crate::dom::serviceworkerglobalscope::ServiceWorkerGlobalScope;
+use crate::realms::{enter_realm, InRealm};
 use crate::script_runtime::JSContext;
 use dom_struct::dom_struct;
 use js::rust::HandleValue;
 use servo_atoms::Atom;
+use std::cell::Cell;
 
 // https://w3c.github.io/ServiceWorker/#extendable-event
 #[dom_struct]
 pub struct ExtendableEvent {
     event: Event,
-    extensions_allowed: bool,
+    /// <https://w3c.github.io/ServiceWorker/#extendableevent-pending-promises-count>
+    pending_promises: Cell<usize>,
 }
 
 #[allow(non_snake_case)]
@@ -28,7 +33,7 @@ impl ExtendableEvent {
     pub fn new_inherited() -> ExtendableEvent {
         ExtendableEvent {
             event: Event::new_inherited(),
-            extensions_allowed: true,
+            pending_promises: Cell::new(0),
         }
     }
     pub fn new(
@@ -58,14 +63,41 @@ impl ExtendableEvent {
         ))
     }
 
+    /// <https://w3c.github.io/ServiceWorker/#extendableevent-active>
+    pub fn is_active(&self) -> bool {
+        self.pending_promises.get() > 0 || self.event.dispatching()
+    }
+
+    /// <https://w3c.github.io/ServiceWorker/#extendableevent-add-lifetime-promise>
+    pub fn add_lifetime_promise(&self, promise: &Promise) {
+        self.pending_promises.set(self.pending_promises.get() + 1);
+        let global = self.global();
+        let handler = PromiseNativeHandler::new(
+            &global,
+            Some(Box::new(LifetimePromiseHandler {
+                event: DomRoot::from_ref(self),
+            })),
+            Some(Box::new(LifetimePromiseHandler {
+                event: DomRoot::from_ref(self),
+            })),
+        );
+        let realm = enter_realm(&*global);
+        promise.append_native_handler(&handler, InRealm::Entered(&realm));
+    }
+
     // https://w3c.github.io/ServiceWorker/#wait-until-method
-    pub fn WaitUntil(&self, _cx: JSContext, _val: HandleValue) -> ErrorResult {
+    pub fn WaitUntil(&self, cx: JSContext, val: HandleValue) -> ErrorResult {
         // Step 1
-        if !self.extensions_allowed {
+        if !self.IsTrusted() {
             return Err(Error::InvalidState);
         }
         // Step 2
-        // TODO add a extended_promises array to enqueue the `val`
+        if !self.is_active() {
+            return Err(Error::InvalidState);
+        }
+        // Step 3
+        let promise = Promise::new_resolved(&self.global(), cx, val)?;
+        self.add_lifetime_promise(&promise);
         Ok(())
     }
 
@@ -82,3 +114,16 @@ impl Default for ExtendableEventBinding::ExtendableEventInit {
         }
     }
 }
+
+/// Settles one of the promises extending the lifetime of an event.
+#[derive(JSTraceable, MallocSizeOf)]
+struct LifetimePromiseHandler {
+    event: DomRoot<ExtendableEvent>,
+}
+
+impl Callback for LifetimePromiseHandler {
+    fn callback(&self, _cx: JSContext, _v: HandleValue, _realm: InRealm) {
+        let pending_promises = &self.event.pending_promises;
+        pending_promises.set(pending_promises.get() - 1);
+    }
+}
@@ -0,0 +1,320 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::body::BodyMixin;
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::EventBinding::EventMethods;
+use crate::dom::bindings::codegen::Bindings::FetchEventBinding::{
+    FetchEventInit, FetchEventMethods,
+};
+use crate::dom::bindings::codegen::Bindings::HeadersBinding::HeadersMethods;
+use crate::dom::bindings::codegen::Bindings::ResponseBinding::{
+    ResponseMethods, ResponseType as DOMResponseType,
+};
+use crate::dom::bindings::conversions::root_from_handlevalue;
+use crate::dom::bindings::error::{Error, ErrorResult, Fallible};
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject};
+use crate::dom::bindings::root::{Dom, DomRoot};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::event::Event;
+use crate::dom::extendableevent::ExtendableEvent;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::promise::Promise;
+use crate::dom::promisenativehandler::{Callback, PromiseNativeHandler};
+use crate::dom::request::Request;
+use crate::dom::response::Response;
+use crate::dom::serviceworkerglobalscope::ServiceWorkerGlobalScope;
+use crate::fetch;
+use crate::realms::{enter_realm, InRealm};
+use crate::script_runtime::JSContext;
+use dom_struct::dom_struct;
+use http::header::{HeaderMap, HeaderName, HeaderValue};
+use hyper::StatusCode;
+use ipc_channel::ipc::IpcSender;
+use js::rust::HandleValue;
+use js::typedarray::ArrayBuffer;
+use net_traits::request::ServiceWorkersMode;
+use net_traits::{CustomResponse, CustomResponseMediator};
+use servo_atoms::Atom;
+use std::cell::Cell;
+use std::rc::Rc;
+
+// https://w3c.github.io/ServiceWorker/#fetchevent-interface
+#[dom_struct]
+pub struct FetchEvent {
+    event: ExtendableEvent,
+    request: Dom<Request>,
+    #[ignore_malloc_size_of = "Rc"]
+    preload_response: Rc<Promise>,
+    /// <https://w3c.github.io/ServiceWorker/#fetchevent-respond-with-entered-flag>
+    respond_with_entered: Cell<bool>,
+    /// Where to send the response of a trusted event, until `respondWith` is called.
+    #[ignore_malloc_size_of = "Channels are hard"]
+    response_chan: DomRefCell<Option<IpcSender<Option<CustomResponse>>>>,
+}
+
+impl FetchEvent {
+    fn new_inherited(
+        request: &Request,
+        preload_response: Rc<Promise>,
+        response_chan: Option<IpcSender<Option<CustomResponse>>>,
+    ) -> FetchEvent {
+        FetchEvent {
+            event: ExtendableEvent::new_inherited(),
+            request: Dom::from_ref(request),
+            preload_response,
+            respond_with_entered: Cell::new(false),
+            response_chan: DomRefCell::new(response_chan),
+        }
+    }
+
+    pub fn new(
+        global: &GlobalScope,
+        type_: Atom,
+        bubbles: bool,
+        cancelable: bool,
+        request: &Request,
+        preload_response: Rc<Promise>,
+        response_chan: Option<IpcSender<Option<CustomResponse>>>,
+    ) -> DomRoot<FetchEvent> {
+        let ev = reflect_dom_object(
+            Box::new(FetchEvent::new_inherited(
+                request,
+                preload_response,
+                response_chan,
+            )),
+            global,
+        );
+        {
+            let event = ev.upcast::<Event>();
+            event.init_event(type_, bubbles, cancelable);
+        }
+        ev
+    }
+
+    #[allow(non_snake_case)]
+    pub fn Constructor(
+        worker: &ServiceWorkerGlobalScope,
+        type_: DOMString,
+        init: &FetchEventInit,
+    ) -> Fallible<DomRoot<FetchEvent>> {
+        let global = worker.upcast::<GlobalScope>();
+        let preload_response = init.preloadResponse.clone().unwrap_or_else(|| {
+            let promise = Promise::new(global);
+            promise.resolve_native(&());
+            promise
+        });
+        Ok(FetchEvent::new(
+            global,
+            Atom::from(type_),
+            init.parent.parent.bubbles,
+            init.parent.parent.cancelable,
+            &init.request,
+            preload_response,
+            None,
+        ))
+    }
+
+    /// The steps of <https://w3c.github.io/ServiceWorker/#handle-fetch> run in the
+    /// controlling service worker, from creating the event on.
+    pub fn dispatch(worker: &ServiceWorkerGlobalScope, mediator: CustomResponseMediator) {
+        let global = worker.upcast::<GlobalScope>();
+        let _ac = enter_realm(global);
+        let CustomResponseMediator {
+            response_chan,
+            request,
+            body,
+            navigation_preload,
+            ..
+        } = mediator;
+
+        let preload_response = match navigation_preload {
+            Some(header_value) => {
+                let mut preload_request = request
+                    .clone()
+                    .service_workers_mode(ServiceWorkersMode::None);
+                if let Ok(value) = HeaderValue::from_bytes(&header_value) {
+                    preload_request.headers.insert(
+                        HeaderName::from_static("service-worker-navigation-preload"),
+                        value,
+                    );
+                }
+                fetch::fetch_navigation_preload(global, preload_request)
+            },
+            None => {
+                let promise = Promise::new(global);
+                promise.resolve_native(&());
+                promise
+            },
+        };
+        let request = Request::new_for_fetch_event(global, request.build(), body);
+        let event = FetchEvent::new(
+            global,
+            atom!("fetch"),
+            false,
+            true,
+            &request,
+            preload_response,
+            Some(response_chan),
+        );
+        event.upcast::<Event>().fire(worker.upcast());
+
+        // Without a call to `respondWith`, the fetch falls back to the network.
+        // TODO: a canceled event should result in a network error instead.
+        if let Some(response_chan) = event.response_chan.borrow_mut().take() {
+            let _ = response_chan.send(None);
+        }
+    }
+}
+
+impl FetchEventMethods for FetchEvent {
+    // https://w3c.github.io/ServiceWorker/#fetch-event-request
+    fn Request(&self) -> DomRoot<Request> {
+        DomRoot::from_ref(&*self.request)
+    }
+
+    // https://w3c.github.io/ServiceWorker/#fetch-event-preloadresponse
+    fn PreloadResponse(&self) -> Rc<Promise> {
+        self.preload_response.clone()
+    }
+
+    // https://w3c.github.io/ServiceWorker/#fetch-event-respondwith
+    fn RespondWith(&self, r: &Promise) -> ErrorResult {
+        let event = self.upcast::<Event>();
+        // Step 1.
+        if !event.dispatching() {
+            return Err(Error::InvalidState);
+        }
+        // Step 2.
+        if self.respond_with_entered.get() {
+            return Err(Error::InvalidState);
+        }
+        // Step 3.
+        self.upcast::<ExtendableEvent>().add_lifetime_promise(r);
+        // Step 4.
+        event.StopPropagation();
+        event.StopImmediatePropagation();
+        // Step 5.
+        self.respond_with_entered.set(true);
+
+        // Step 8.
+        let response_chan = match self.response_chan.borrow_mut().take() {
+            Some(response_chan) => response_chan,
+            None => return Ok(()),
+        };
+        let global = self.global();
+        let handler = PromiseNativeHandler::new(
+            &global,
+            Some(Box::new(RespondWithResolvedHandler {
+                response_chan: response_chan.clone(),
+            })),
+            Some(Box::new(RespondWithRejectedHandler { response_chan })),
+        );
+        let realm = enter_realm(&*global);
+        r.append_native_handler(&handler, InRealm::Entered(&realm));
+        Ok(())
+    }
+
+    // https://dom.spec.whatwg.org/#dom-event-istrusted
+    fn IsTrusted(&self) -> bool {
+        self.upcast::<Event>().IsTrusted()
+    }
+}
+
+/// Step 8.2 of <https://w3c.github.io/ServiceWorker/#fetch-event-respondwith>,
+/// reading the body of the response the promise was fulfilled with.
+#[derive(JSTraceable, MallocSizeOf)]
+struct RespondWithResolvedHandler {
+    #[ignore_malloc_size_of = "Channels are hard"]
+    response_chan: IpcSender<Option<CustomResponse>>,
+}
+
+impl Callback for RespondWithResolvedHandler {
+    fn callback(&self, cx: JSContext, v: HandleValue, realm: InRealm) {
+        let response = match root_from_handlevalue::<Response>(v, *cx) {
+            Ok(response) => response,
+            Err(()) => return fall_back_to_network(&self.response_chan),
+        };
+        if response.Type() == DOMResponseType::Error ||
+            response.is_disturbed() ||
+            response.is_locked()
+        {
+            return fall_back_to_network(&self.response_chan);
+        }
+        let status = match StatusCode::from_u16(response.Status()) {
+            Ok(status) => status,
+            Err(_) => return fall_back_to_network(&self.response_chan),
+        };
+
+        let handler = PromiseNativeHandler::new(
+            &response.global(),
+            Some(Box::new(ResponseBodyHandler {
+                response_chan: self.response_chan.clone(),
+                headers: response.Headers().get_headers_list(),
+                status,
+                status_text: String::from_utf8_lossy(&response.StatusText()).into_owned(),
+            })),
+            Some(Box::new(RespondWithRejectedHandler {
+                response_chan: self.response_chan.clone(),
+            })),
+        );
+        response
+            .ArrayBuffer()
+            .append_native_handler(&handler, realm);
+    }
+}
+
+/// Sends the response of a `FetchEvent` once its body has been read.
+#[derive(JSTraceable, MallocSizeOf)]
+struct ResponseBodyHandler {
+    #[ignore_malloc_size_of = "Channels are hard"]
+    response_chan: IpcSender<Option<CustomResponse>>,
+    #[ignore_malloc_size_of = "Defined in hyper"]
+    headers: HeaderMap,
+    #[ignore_malloc_size_of = "Defined in hyper"]
+    status: StatusCode,
+    status_text: String,
+}
+
+impl Callback for ResponseBodyHandler {
+    #[allow(unsafe_code)]
+    fn callback(&self, cx: JSContext, v: HandleValue, _realm: InRealm) {
+        if !v.is_object() {
+            return fall_back_to_network(&self.response_chan);
+        }
+        let body = unsafe {
+            typedarray!(in(*cx) let buffer: ArrayBuffer = v.to_object());
+            match buffer {
+                Ok(buffer) => buffer.to_vec(),
+                Err(()) => return fall_back_to_network(&self.response_chan),
+            }
+        };
+        let response = CustomResponse::new(
+            self.headers.clone(),
+            (self.status, self.status_text.clone()),
+            body,
+        );
+        let _ = self.response_chan.send(Some(response));
+    }
+}
+
+/// Falls back to the network when the promise passed to `respondWith`, or the
+/// reading of the body of its response, is rejected.
+#[derive(JSTraceable, MallocSizeOf)]
+struct RespondWithRejectedHandler {
+    #[ignore_malloc_size_of = "Channels are hard"]
+    response_chan: IpcSender<Option<CustomResponse>>,
+}
+
+impl Callback for RespondWithRejectedHandler {
+    fn callback(&self, _cx: JSContext, _v: HandleValue, _realm: InRealm) {
+        fall_back_to_network(&self.response_chan);
+    }
+}
+
+// TODO: the failures of `respondWith` should result in a network error instead.
+fn fall_back_to_network(response_chan: &IpcSender<Option<CustomResponse>>) {
+    let _ = response_chan.send(None);
+}
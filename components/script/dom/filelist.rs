@@ -6,7 +6,7 @@ use crate::dom::bindings::codegen::Bindings::FileListBinding::FileListMethods;
 use crate::dom::bindings::reflector::{reflect_dom_object, Reflector};
 use crate::dom::bindings::root::{Dom, DomRoot};
 use crate::dom::file::File;
-use crate::dom::window::Window;
+use crate::dom::globalscope::GlobalScope;
 use dom_struct::dom_struct;
 use std::slice::Iter;
 
@@ -27,12 +27,12 @@ impl FileList {
     }
 
     #[allow(unrooted_must_root)]
-    pub fn new(window: &Window, files: Vec<DomRoot<File>>) -> DomRoot<FileList> {
+    pub fn new(global: &GlobalScope, files: Vec<DomRoot<File>>) -> DomRoot<FileList> {
         reflect_dom_object(
             Box::new(FileList::new_inherited(
                 files.iter().map(|r| Dom::from_ref(&**r)).collect(),
             )),
-            window,
+            global,
         )
     }
 
@@ -8,12 +8,13 @@ use crate::dom::bindings::codegen::Bindings::EventSourceBinding::EventSourceBind
 use crate::dom::bindings::codegen::Bindings::ImageBitmapBinding::{
     ImageBitmapOptions, ImageBitmapSource,
 };
+use crate::dom::bindings::codegen::Bindings::MessagePortBinding::StructuredSerializeOptions;
 use crate::dom::bindings::codegen::Bindings::PermissionStatusBinding::PermissionState;
 use crate::dom::bindings::codegen::Bindings::VoidFunctionBinding::VoidFunction;
 use crate::dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
 use crate::dom::bindings::codegen::Bindings::WorkerGlobalScopeBinding::WorkerGlobalScopeMethods;
 use crate::dom::bindings::conversions::{root_from_object, root_from_object_static};
-use crate::dom::bindings::error::{report_pending_exception, Error, ErrorInfo};
+use crate::dom::bindings::error::{report_pending_exception, Error, ErrorInfo, Fallible};
 use crate::dom::bindings::inheritance::Castable;
 use crate::dom::bindings::refcounted::{Trusted, TrustedPromise};
 use crate::dom::bindings::reflector::DomObject;
@@ -21,6 +22,7 @@ use crate::dom::bindings::root::{Dom, DomRoot, MutNullableDom};
 use crate::dom::bindings::settings_stack::{entry_global, incumbent_global, AutoEntryScript};
 use crate::dom::bindings::str::DOMString;
 use crate::dom::bindings::structuredclone;
+use crate::dom::bindings::trace::RootedTraceableBox;
 use crate::dom::bindings::utils::to_frozen_array;
 use crate::dom::bindings::weakref::{DOMTracker, WeakRef};
 use crate::dom::blob::Blob;
@@ -29,6 +31,7 @@ use crate::dom::crypto::Crypto;
 use crate::dom::dedicatedworkerglobalscope::{
     DedicatedWorkerControlMsg, DedicatedWorkerGlobalScope,
 };
+use crate::dom::element::Element;
 use crate::dom::errorevent::ErrorEvent;
 use crate::dom::event::{Event, EventBubbles, EventCancelable, EventStatus};
 use crate::dom::eventsource::EventSource;
@@ -38,6 +41,7 @@ use crate::dom::gpudevice::GPUDevice;
 use crate::dom::htmlscriptelement::{ScriptId, SourceCode};
 use crate::dom::identityhub::Identities;
 use crate::dom::imagebitmap::ImageBitmap;
+use crate::dom::lockmanager::LockManager;
 use crate::dom::messageevent::MessageEvent;
 use crate::dom::messageport::MessagePort;
 use crate::dom::paintworkletglobalscope::PaintWorkletGlobalScope;
@@ -47,6 +51,7 @@ use crate::dom::promise::Promise;
 use crate::dom::readablestream::{ExternalUnderlyingSource, ReadableStream};
 use crate::dom::serviceworker::ServiceWorker;
 use crate::dom::serviceworkerregistration::ServiceWorkerRegistration;
+use crate::dom::sharedworkerglobalscope::SharedWorkerGlobalScope;
 use crate::dom::window::Window;
 use crate::dom::workerglobalscope::WorkerGlobalScope;
 use crate::dom::workletglobalscope::WorkletGlobalScope;
@@ -58,6 +63,7 @@ use crate::script_runtime::{
     CommonScriptMsg, ContextForRequestInterrupt, JSContext as SafeJSContext, ScriptChan, ScriptPort,
 };
 use crate::script_thread::{MainThreadScriptChan, ScriptThread};
+use crate::security_manager::{report_csp_violation, CSPViolationReport};
 use crate::task::TaskCanceller;
 use crate::task_source::dom_manipulation::DOMManipulationTaskSource;
 use crate::task_source::file_reading::FileReadingTaskSource;
@@ -71,7 +77,7 @@ use crate::task_source::TaskSource;
 use crate::task_source::TaskSourceName;
 use crate::timers::{IsInterval, OneshotTimerCallback, OneshotTimerHandle};
 use crate::timers::{OneshotTimers, TimerCallback};
-use content_security_policy::CspList;
+use content_security_policy::{self as csp, CspList};
 use crossbeam_channel::Sender;
 use devtools_traits::{PageError, ScriptToDevtoolsControlMsg};
 use dom_struct::dom_struct;
@@ -90,7 +96,8 @@ use js::panic::maybe_resume_unwind;
 use js::rust::transform_str_to_source_text;
 use js::rust::wrappers::{JS_ExecuteScript, JS_GetScriptPrivate};
 use js::rust::{get_object_class, CompileOptionsWrapper, ParentRuntime, Runtime};
-use js::rust::{HandleValue, MutableHandleValue};
+use js::rust::{CustomAutoRooter, CustomAutoRooterGuard};
+use js::rust::{HandleObject as SafeHandleObject, HandleValue, MutableHandleValue};
 use js::{JSCLASS_IS_DOMJSCLASS, JSCLASS_IS_GLOBAL};
 use msg::constellation_msg::{
     BlobId, BroadcastChannelRouterId, MessagePortId, MessagePortRouterId, PipelineId,
@@ -103,7 +110,8 @@ use net_traits::filemanager_thread::{
 use net_traits::image_cache::ImageCache;
 use net_traits::request::Referrer;
 use net_traits::response::HttpsState;
-use net_traits::{CoreResourceMsg, CoreResourceThread, IpcSend, ResourceThreads};
+use net_traits::{CoreResourceMsg, CoreResourceThread, CrossOriginEmbedderPolicy};
+use net_traits::{IpcSend, ResourceThreads};
 use parking_lot::Mutex;
 use profile_traits::{ipc as profile_ipc, mem as profile_mem, time as profile_time};
 use script_traits::serializable::{BlobData, BlobImpl, FileBlob};
@@ -176,6 +184,9 @@ pub struct GlobalScope {
     eventtarget: EventTarget,
     crypto: MutNullableDom<Crypto>,
 
+    /// <https://w3c.github.io/web-locks/#lockmanager>
+    lock_manager: MutNullableDom<LockManager>,
+
     /// The message-port router id for this global, if it is managing ports.
     message_port_state: DomRefCell<MessagePortState>,
 
@@ -304,6 +315,12 @@ pub struct GlobalScope {
     /// currect https state (from previous request)
     https_state: Cell<HttpsState>,
 
+    /// <https://html.spec.whatwg.org/multipage/#concept-settings-object-embedder-policy>
+    embedder_policy: Cell<CrossOriginEmbedderPolicy>,
+
+    /// <https://html.spec.whatwg.org/multipage/#concept-settings-object-cross-origin-isolated-capability>
+    cross_origin_isolated: bool,
+
     /// The stack of active group labels for the Console APIs.
     console_group_stack: DomRefCell<Vec<DOMString>>,
 
@@ -723,6 +740,7 @@ impl GlobalScope {
         is_headless: bool,
         user_agent: Cow<'static, str>,
         gpu_id_hub: Arc<Mutex<Identities>>,
+        cross_origin_isolated: bool,
     ) -> Self {
         Self {
             message_port_state: DomRefCell::new(MessagePortState::UnManaged),
@@ -730,6 +748,7 @@ impl GlobalScope {
             blob_state: DomRefCell::new(BlobState::UnManaged),
             eventtarget: EventTarget::new_inherited(),
             crypto: Default::default(),
+            lock_manager: Default::default(),
             registration_map: DomRefCell::new(HashMap::new()),
             worker_map: DomRefCell::new(HashMap::new()),
             pipeline_id,
@@ -759,6 +778,8 @@ impl GlobalScope {
             gpu_devices: DomRefCell::new(HashMap::new()),
             frozen_supported_performance_entry_types: DomRefCell::new(Default::default()),
             https_state: Cell::new(HttpsState::None),
+            embedder_policy: Cell::new(CrossOriginEmbedderPolicy::UnsafeNone),
+            cross_origin_isolated,
             console_group_stack: DomRefCell::new(Vec::new()),
             dynamic_modules: DomRefCell::new(DynamicModuleList::new()),
         }
@@ -915,11 +936,15 @@ impl GlobalScope {
         self.perform_a_broadcast_channel_garbage_collection_checkpoint();
     }
 
-    /// Remove the routers for ports and broadcast-channels.
+    /// Remove the routers for ports and broadcast-channels,
+    /// and release the locks held by this global.
     /// Drain the list of workers.
     pub fn remove_web_messaging_and_dedicated_workers_infra(&self) {
         self.remove_message_ports_router();
         self.remove_broadcast_channel_router();
+        if let Some(lock_manager) = self.lock_manager.get() {
+            lock_manager.remove_client();
+        }
 
         // Drop each ref to a worker explicitly now,
         // which will send a shutdown signal,
@@ -2215,6 +2240,10 @@ impl GlobalScope {
         self.crypto.or_init(|| Crypto::new(self))
     }
 
+    pub fn lock_manager(&self) -> DomRoot<LockManager> {
+        self.lock_manager.or_init(|| LockManager::new(self))
+    }
+
     pub fn live_devtools_updates(&self) -> bool {
         self.devtools_wants_updates.get()
     }
@@ -2318,6 +2347,9 @@ impl GlobalScope {
         if let Some(worker) = self.downcast::<DedicatedWorkerGlobalScope>() {
             return worker.image_cache();
         }
+        if let Some(worker) = self.downcast::<SharedWorkerGlobalScope>() {
+            return worker.image_cache();
+        }
         if let Some(worker) = self.downcast::<PaintWorkletGlobalScope>() {
             return worker.image_cache();
         }
@@ -2698,6 +2730,34 @@ impl GlobalScope {
         }))
     }
 
+    /// <https://html.spec.whatwg.org/multipage/#dom-structuredclone>
+    pub fn structured_clone(
+        &self,
+        cx: SafeJSContext,
+        value: HandleValue,
+        options: RootedTraceableBox<StructuredSerializeOptions>,
+    ) -> Fallible<JSVal> {
+        let mut rooted = CustomAutoRooter::new(
+            options
+                .transfer
+                .iter()
+                .map(|js: &RootedTraceableBox<Heap<*mut JSObject>>| js.get())
+                .collect(),
+        );
+        let transfer = CustomAutoRooterGuard::new(*cx, &mut rooted);
+
+        // Step 1.
+        let data = structuredclone::write(cx, value, Some(transfer))?;
+
+        // Step 2.
+        rooted!(in(*cx) let mut message_clone = UndefinedValue());
+        structuredclone::read(self, data, message_clone.handle_mut())
+            .map_err(|_| Error::DataClone)?;
+
+        // Step 3.
+        Ok(message_clone.get())
+    }
+
     pub fn create_image_bitmap(
         &self,
         image: ImageBitmapSource,
@@ -2994,6 +3054,48 @@ impl GlobalScope {
         self.https_state.set(https_state);
     }
 
+    /// <https://html.spec.whatwg.org/multipage/#secure-context>
+    ///
+    /// A document is a secure context if it and its ancestors were delivered over
+    /// potentially trustworthy URLs, and a worker if its script was.
+    pub fn is_secure_context(&self) -> bool {
+        if let Some(window) = self.downcast::<Window>() {
+            return window.is_secure_context();
+        }
+        self.get_url().is_potentially_trustworthy()
+    }
+
+    /// The condition to expose the interfaces and members marked `[SecureContext]`,
+    /// which the bindings don't check by themselves.
+    #[allow(unsafe_code)]
+    pub fn is_secure_context_exposed(_cx: SafeJSContext, obj: SafeHandleObject) -> bool {
+        let global = unsafe { GlobalScope::from_object(obj.get()) };
+        global.is_secure_context()
+    }
+
+    pub fn get_embedder_policy(&self) -> CrossOriginEmbedderPolicy {
+        self.embedder_policy.get()
+    }
+
+    pub fn set_embedder_policy(&self, embedder_policy: CrossOriginEmbedderPolicy) {
+        self.embedder_policy.set(embedder_policy);
+    }
+
+    /// Whether this global has the cross-origin isolated capability, which
+    /// exposes `SharedArrayBuffer`.
+    pub fn cross_origin_isolated(&self) -> bool {
+        self.cross_origin_isolated
+    }
+
+    /// Whether any ancestor of this global's document has a potentially trustworthy origin.
+    pub fn has_trustworthy_ancestor_origin(&self) -> bool {
+        if let Some(window) = self.downcast::<Window>() {
+            return window.Document().has_trustworthy_ancestor_origin();
+        }
+        // TODO: Worker and Worklet global scopes.
+        false
+    }
+
     /// https://www.w3.org/TR/CSP/#get-csp-of-object
     pub fn get_csp_list(&self) -> Option<CspList> {
         if let Some(window) = self.downcast::<Window>() {
@@ -3003,6 +3105,30 @@ impl GlobalScope {
         None
     }
 
+    /// <https://w3c.github.io/webappsec-csp/#report-violation>
+    ///
+    /// `element` is the element whose inline behaviour caused the violations, if any.
+    pub fn report_csp_violations(
+        &self,
+        violations: Vec<csp::Violation>,
+        element: Option<&Element>,
+    ) {
+        for violation in violations {
+            let report = CSPViolationReport::from_violation(self, &violation);
+            report_csp_violation(self, report, &violation.policy, element);
+        }
+    }
+
+    /// The URL of the reporting endpoint called `name`, as declared by the
+    /// `Reporting-Endpoints` header of this global's response.
+    pub fn reporting_endpoint(&self, name: &str) -> Option<ServoUrl> {
+        if let Some(window) = self.downcast::<Window>() {
+            return window.Document().reporting_endpoint(name);
+        }
+        // TODO: Worker and Worklet global scopes.
+        None
+    }
+
     pub fn wgpu_id_hub(&self) -> Arc<Mutex<Identities>> {
         self.gpu_id_hub.clone()
     }
@@ -115,6 +115,8 @@ impl History {
                     serialized: data,
                     ports: None,
                     blobs: None,
+                    crypto_keys: None,
+                    image_bitmaps: None,
                 };
                 let global_scope = self.window.upcast::<GlobalScope>();
                 rooted!(in(*global_scope.get_cx()) let mut state = UndefinedValue());
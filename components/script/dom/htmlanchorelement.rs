@@ -29,7 +29,8 @@ use crate::dom::virtualmethods::VirtualMethods;
 use crate::task_source::TaskSource;
 use dom_struct::dom_struct;
 use html5ever::{LocalName, Prefix};
-use net_traits::request::Referrer;
+use net_traits::request::{CredentialsMode, Referrer, RequestBuilder};
+use net_traits::CoreResourceMsg;
 use num_traits::ToPrimitive;
 use script_traits::{HistoryEntryReplacement, LoadData, LoadOrigin};
 use servo_atoms::Atom;
@@ -128,6 +129,12 @@ impl HTMLAnchorElementMethods for HTMLAnchorElement {
         self.upcast::<Node>().SetTextContent(Some(value))
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-a-download
+    make_getter!(Download, "download");
+
+    // https://html.spec.whatwg.org/multipage/#dom-a-download
+    make_setter!(SetDownload, "download");
+
     // https://html.spec.whatwg.org/multipage/#dom-a-rel
     make_getter!(Rel, "rel");
 
@@ -573,8 +580,11 @@ impl Activatable for HTMLAnchorElement {
         }
 
         // Step 2.
-        //TODO: Download the link is `download` attribute is set.
-        follow_hyperlink(element, ismap_suffix);
+        if element.has_attribute(&local_name!("download")) {
+            download_hyperlink(element, ismap_suffix);
+        } else {
+            follow_hyperlink(element, ismap_suffix);
+        }
     }
 }
 
@@ -624,6 +634,52 @@ pub fn get_element_noopener(subject: &Element, target_attribute_value: Option<DO
         (!link_types.contains("opener") && target_is_blank);
 }
 
+/// <https://html.spec.whatwg.org/multipage/#downloading-hyperlinks>
+pub fn download_hyperlink(subject: &Element, hyperlink_suffix: Option<String>) {
+    // Step 1.
+    if subject.cannot_navigate() {
+        return;
+    }
+    // Step 2. TODO: check the sandboxed downloads browsing context flag.
+
+    // Step 3-5.
+    let document = document_from_node(subject);
+    let attribute = subject.get_attribute(&ns!(), &local_name!("href")).unwrap();
+    let mut href = attribute.Value();
+    // Step 6.
+    if let Some(suffix) = hyperlink_suffix {
+        href.push_str(&suffix);
+    }
+    let url = match document.base_url().join(&href) {
+        Ok(url) => url,
+        Err(_) => return,
+    };
+
+    // https://html.spec.whatwg.org/multipage/#getting-the-suggested-filename
+    // The download attribute is only honoured for same-origin and data: URLs.
+    let origin = document.origin().immutable().clone();
+    let suggested_filename = if url.origin() == origin || url.scheme() == "data" {
+        Some(subject.get_string_attribute(&local_name!("download")))
+            .filter(|filename| !filename.is_empty())
+            .map(String::from)
+    } else {
+        None
+    };
+
+    // Step 7.
+    let window = document.window();
+    let request = RequestBuilder::new(url, window.upcast::<GlobalScope>().get_referrer())
+        .origin(origin)
+        .referrer_policy(referrer_policy_for_element(subject))
+        .credentials_mode(CredentialsMode::Include)
+        .pipeline_id(Some(window.upcast::<GlobalScope>().pipeline_id()));
+    let browser_id = window.window_proxy().top_level_browsing_context_id();
+    let msg = CoreResourceMsg::Download(request, Some(browser_id), suggested_filename);
+    if let Err(e) = window.upcast::<GlobalScope>().core_resource_thread().send(msg) {
+        warn!("Resource thread unavailable ({})", e);
+    }
+}
+
 /// <https://html.spec.whatwg.org/multipage/#following-hyperlinks-2>
 pub fn follow_hyperlink(subject: &Element, hyperlink_suffix: Option<String>) {
     // Step 1.
@@ -14,6 +14,7 @@ use crate::dom::eventtarget::EventTarget;
 use crate::dom::htmlelement::HTMLElement;
 use crate::dom::node::{document_from_node, window_from_node, BindContext, Node};
 use crate::dom::virtualmethods::VirtualMethods;
+use content_security_policy as csp;
 use cssparser::RGBA;
 use dom_struct::dom_struct;
 use embedder_traits::EmbedderMsg;
@@ -195,6 +196,15 @@ impl VirtualMethods for HTMLBodyElement {
                     &local_name!("onresize") |
                     &local_name!("onunload") |
                     &local_name!("onerror") => {
+                        if document_from_node(self).should_elements_inline_type_behavior_be_blocked(
+                            self.upcast(),
+                            csp::InlineCheckType::ScriptAttribute,
+                            &attr.value(),
+                        ) == csp::CheckResult::Blocked
+                        {
+                            warn!("Blocking inline event handler due to CSP");
+                            return;
+                        }
                         let evtarget = window.upcast::<EventTarget>(); // forwarded event
                         let source_line = 1; //TODO(#9604) obtain current JS execution line
                         evtarget.set_event_handler_uncompiled(
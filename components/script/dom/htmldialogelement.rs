@@ -4,21 +4,29 @@
 
 use crate::dom::bindings::cell::DomRefCell;
 use crate::dom::bindings::codegen::Bindings::HTMLDialogElementBinding::HTMLDialogElementMethods;
+use crate::dom::bindings::error::{Error, ErrorResult};
 use crate::dom::bindings::inheritance::Castable;
-use crate::dom::bindings::root::DomRoot;
+use crate::dom::bindings::root::{DomRoot, MutNullableDom};
 use crate::dom::bindings::str::DOMString;
-use crate::dom::document::Document;
+use crate::dom::document::{Document, FocusType};
 use crate::dom::element::Element;
+use crate::dom::event::{Event, EventBubbles, EventCancelable, EventStatus};
 use crate::dom::eventtarget::EventTarget;
 use crate::dom::htmlelement::HTMLElement;
-use crate::dom::node::{window_from_node, Node};
+use crate::dom::node::{
+    document_from_node, window_from_node, Node, ShadowIncluding, UnbindContext,
+};
+use crate::dom::virtualmethods::VirtualMethods;
 use dom_struct::dom_struct;
 use html5ever::{LocalName, Prefix};
+use style::element_state::ElementState;
 
 #[dom_struct]
 pub struct HTMLDialogElement {
     htmlelement: HTMLElement,
     return_value: DomRefCell<DOMString>,
+    /// <https://html.spec.whatwg.org/multipage/#previously-focused-element>
+    previously_focused_element: MutNullableDom<Element>,
 }
 
 impl HTMLDialogElement {
@@ -30,6 +38,7 @@ impl HTMLDialogElement {
         HTMLDialogElement {
             htmlelement: HTMLElement::new_inherited(local_name, prefix, document),
             return_value: DomRefCell::new(DOMString::new()),
+            previously_focused_element: Default::default(),
         }
     }
 
@@ -46,6 +55,69 @@ impl HTMLDialogElement {
             document,
         )
     }
+
+    /// <https://html.spec.whatwg.org/multipage/#is-modal>
+    pub fn is_modal(&self) -> bool {
+        self.upcast::<Element>()
+            .state()
+            .contains(ElementState::IN_MODAL_DIALOG_STATE)
+    }
+
+    fn set_modal(&self, value: bool) {
+        self.upcast::<Element>()
+            .set_state(ElementState::IN_MODAL_DIALOG_STATE, value);
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dialog-focusing-steps>
+    fn run_focusing_steps(&self) {
+        // Step 2 - 4.
+        let focusable_descendants = || {
+            self.upcast::<Node>()
+                .traverse_preorder(ShadowIncluding::No)
+                .skip(1)
+                .filter_map(DomRoot::downcast::<Element>)
+                .filter(|element| element.is_focusable_area())
+        };
+        let control = focusable_descendants()
+            .find(|element| element.has_attribute(&local_name!("autofocus")))
+            .or_else(|| focusable_descendants().next());
+
+        // Step 5. The dialog itself is only focusable with a tabindex, so when it has no
+        // focusable descendant the element that had focus loses it instead.
+        let control = control.or_else(|| {
+            Some(DomRoot::from_ref(self.upcast::<Element>()))
+                .filter(|element| element.is_focusable_area())
+        });
+        document_from_node(self).request_focus(control.as_deref(), FocusType::Element);
+    }
+
+    /// Runs the `cancel` steps of a modal dialog that the user asked to close, usually by
+    /// pressing `Escape`.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#canceling-dialogs>
+    pub fn cancel(&self) {
+        let window = window_from_node(self);
+        let event = Event::new(
+            window.upcast(),
+            atom!("cancel"),
+            EventBubbles::DoesNotBubble,
+            EventCancelable::Cancelable,
+        );
+        event.set_trusted(true);
+        if event.fire(self.upcast::<EventTarget>()) == EventStatus::NotCanceled {
+            self.Close(None);
+        }
+    }
+
+    /// Takes the dialog out of the top layer if it is there, because it is being closed or
+    /// removed from its document.
+    fn remove_from_top_layer(&self) {
+        if !self.is_modal() {
+            return;
+        }
+        document_from_node(self).remove_from_top_layer(self.upcast());
+        self.set_modal(false);
+    }
 }
 
 impl HTMLDialogElementMethods for HTMLDialogElement {
@@ -66,6 +138,56 @@ impl HTMLDialogElementMethods for HTMLDialogElement {
         *self.return_value.borrow_mut() = return_value;
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-dialog-show
+    fn Show(&self) {
+        let element = self.upcast::<Element>();
+
+        // Step 1.
+        if element.has_attribute(&local_name!("open")) {
+            return;
+        }
+
+        // Step 2.
+        element.set_bool_attribute(&local_name!("open"), true);
+
+        // Step 3.
+        self.run_focusing_steps();
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-dialog-showmodal
+    fn ShowModal(&self) -> ErrorResult {
+        let element = self.upcast::<Element>();
+
+        // Step 1.
+        if element.has_attribute(&local_name!("open")) {
+            return Err(Error::InvalidState);
+        }
+
+        // Step 2.
+        if !element.is_connected() {
+            return Err(Error::InvalidState);
+        }
+
+        // Step 3.
+        element.set_bool_attribute(&local_name!("open"), true);
+
+        // Step 4.
+        self.set_modal(true);
+
+        // Step 5. The rest of the document is now inert.
+        let document = document_from_node(self);
+        document.add_to_top_layer(element);
+
+        // Step 6.
+        self.previously_focused_element
+            .set(document.get_focused_element().as_deref());
+
+        // Step 7.
+        self.run_focusing_steps();
+
+        Ok(())
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-dialog-close
     fn Close(&self, return_value: Option<DOMString>) {
         let element = self.upcast::<Element>();
@@ -85,11 +207,32 @@ impl HTMLDialogElementMethods for HTMLDialogElement {
             *self.return_value.borrow_mut() = new_value;
         }
 
-        // TODO: Step 4 implement pending dialog stack removal
+        // Step 4
+        self.remove_from_top_layer();
 
         // Step 5
+        if let Some(previously_focused_element) = self.previously_focused_element.get() {
+            self.previously_focused_element.set(None);
+            document_from_node(self)
+                .request_focus(Some(&*previously_focused_element), FocusType::Element);
+        }
+
+        // Step 6
         win.task_manager()
             .dom_manipulation_task_source()
             .queue_simple_event(target, atom!("close"), &win);
     }
 }
+
+impl VirtualMethods for HTMLDialogElement {
+    fn super_type(&self) -> Option<&dyn VirtualMethods> {
+        Some(self.upcast::<HTMLElement>() as &dyn VirtualMethods)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#the-dialog-element:html-element-removing-steps
+    fn unbind_from_tree(&self, context: &UnbindContext) {
+        self.super_type().unwrap().unbind_from_tree(context);
+
+        self.remove_from_top_layer();
+    }
+}
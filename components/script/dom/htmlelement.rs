@@ -34,6 +34,7 @@ use crate::dom::node::{document_from_node, window_from_node};
 use crate::dom::node::{Node, ShadowIncluding};
 use crate::dom::text::Text;
 use crate::dom::virtualmethods::VirtualMethods;
+use content_security_policy as csp;
 use dom_struct::dom_struct;
 use html5ever::{LocalName, Prefix};
 use script_layout_interface::message::QueryMsg;
@@ -125,6 +126,18 @@ impl HTMLElementMethods for HTMLElement {
     // https://html.spec.whatwg.org/multipage/#dom-hidden
     make_bool_setter!(SetHidden, "hidden");
 
+    // https://html.spec.whatwg.org/multipage/#dom-draggable
+    fn Draggable(&self) -> bool {
+        self.is_draggable()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-draggable
+    fn SetDraggable(&self, draggable: bool) {
+        let value = if draggable { "true" } else { "false" };
+        self.upcast::<Element>()
+            .set_string_attribute(&local_name!("draggable"), DOMString::from(value));
+    }
+
     // https://html.spec.whatwg.org/multipage/#the-dir-attribute
     make_getter!(Dir, "dir");
     // https://html.spec.whatwg.org/multipage/#the-dir-attribute
@@ -523,23 +536,31 @@ impl HTMLElementMethods for HTMLElement {
 
     // https://html.spec.whatwg.org/multipage/#dom-contenteditable
     fn ContentEditable(&self) -> DOMString {
-        // TODO: https://github.com/servo/servo/issues/12776
-        self.upcast::<Element>()
-            .get_attribute(&ns!(), &local_name!("contenteditable"))
-            .map(|attr| DOMString::from(&**attr.value()))
-            .unwrap_or_else(|| DOMString::from("inherit"))
+        match self.content_editable_state() {
+            Some(true) => DOMString::from("true"),
+            Some(false) => DOMString::from("false"),
+            None => DOMString::from("inherit"),
+        }
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-contenteditable
-    fn SetContentEditable(&self, _: DOMString) {
-        // TODO: https://github.com/servo/servo/issues/12776
-        warn!("The contentEditable attribute is not implemented yet");
+    fn SetContentEditable(&self, value: DOMString) -> ErrorResult {
+        let element = self.upcast::<Element>();
+        if value.eq_ignore_ascii_case("inherit") {
+            element.remove_attribute(&ns!(), &local_name!("contenteditable"));
+        } else if value.eq_ignore_ascii_case("true") {
+            element.set_string_attribute(&local_name!("contenteditable"), DOMString::from("true"));
+        } else if value.eq_ignore_ascii_case("false") {
+            element.set_string_attribute(&local_name!("contenteditable"), DOMString::from("false"));
+        } else {
+            return Err(Error::Syntax);
+        }
+        Ok(())
     }
 
-    // https://html.spec.whatwg.org/multipage/#dom-contenteditable
+    // https://html.spec.whatwg.org/multipage/#dom-iscontenteditable
     fn IsContentEditable(&self) -> bool {
-        // TODO: https://github.com/servo/servo/issues/12776
-        false
+        self.upcast::<Node>().editing_host().is_some()
     }
 }
 
@@ -645,6 +666,48 @@ impl HTMLElement {
             .remove_attribute(&ns!(), &local_name);
     }
 
+    // https://html.spec.whatwg.org/multipage/#concept-draggable
+    pub fn is_draggable(&self) -> bool {
+        let element = self.upcast::<Element>();
+        if let Some(attr) = element.get_attribute(&ns!(), &local_name!("draggable")) {
+            // https://html.spec.whatwg.org/multipage/#the-draggable-attribute
+            if attr.value().eq_ignore_ascii_case("true") {
+                return true;
+            }
+            if attr.value().eq_ignore_ascii_case("false") {
+                return false;
+            }
+        }
+
+        // Images and links are draggable by default.
+        match self.upcast::<Node>().type_id() {
+            NodeTypeId::Element(ElementTypeId::HTMLElement(
+                HTMLElementTypeId::HTMLImageElement,
+            )) => true,
+            NodeTypeId::Element(ElementTypeId::HTMLElement(
+                HTMLElementTypeId::HTMLAnchorElement,
+            )) => element.has_attribute(&local_name!("href")),
+            _ => false,
+        }
+    }
+
+    /// The state of the `contenteditable` attribute, which is `None` for the inherit state.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#attr-contenteditable>
+    pub fn content_editable_state(&self) -> Option<bool> {
+        let attr = self
+            .upcast::<Element>()
+            .get_attribute(&ns!(), &local_name!("contenteditable"))?;
+        let value = attr.value();
+        if value.is_empty() || value.eq_ignore_ascii_case("true") {
+            Some(true)
+        } else if value.eq_ignore_ascii_case("false") {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
     // https://html.spec.whatwg.org/multipage/#category-label
     pub fn is_labelable_element(&self) -> bool {
         // Note: HTMLKeygenElement is omitted because Servo doesn't currently implement it
@@ -840,6 +903,17 @@ impl VirtualMethods for HTMLElement {
         self.super_type().unwrap().attribute_mutated(attr, mutation);
         match (attr.local_name(), mutation) {
             (name, AttributeMutation::Set(_)) if name.starts_with("on") => {
+                // https://html.spec.whatwg.org/multipage/
+                // #event-handlers-on-elements,-document-objects,-and-window-objects:event-handlers-4
+                if document_from_node(self).should_elements_inline_type_behavior_be_blocked(
+                    self.upcast(),
+                    csp::InlineCheckType::ScriptAttribute,
+                    &attr.value(),
+                ) == csp::CheckResult::Blocked
+                {
+                    warn!("Blocking inline event handler due to CSP");
+                    return;
+                }
                 let evtarget = self.upcast::<EventTarget>();
                 let source_line = 1; //TODO(#9604) get current JS execution line
                 evtarget.set_event_handler_uncompiled(
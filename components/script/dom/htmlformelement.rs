@@ -56,6 +56,7 @@ use crate::dom::submitevent::SubmitEvent;
 use crate::dom::validitystate::ValidationFlags;
 use crate::dom::virtualmethods::VirtualMethods;
 use crate::dom::window::Window;
+use crate::security_manager::SandboxingFlagSet;
 use crate::task_source::TaskSource;
 use dom_struct::dom_struct;
 use encoding_rs::{Encoding, UTF_8};
@@ -690,7 +691,11 @@ impl HTMLFormElement {
         // Step 3
         let doc = document_from_node(self);
         let base = doc.base_url();
-        // TODO: Handle browsing contexts (Step 4, 5)
+        // Step 4
+        if doc.has_active_sandboxing_flag(SandboxingFlagSet::SANDBOXED_FORMS) {
+            return;
+        }
+        // TODO: Handle browsing contexts (Step 5)
         // Step 6
         if submit_method_flag == SubmittedFrom::NotFromForm {
             // Step 6.1
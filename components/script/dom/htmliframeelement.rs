@@ -80,6 +80,12 @@ pub struct HTMLIFrameElement {
     sandbox_allowance: Cell<Option<SandboxAllowance>>,
     load_blocker: DomRefCell<Option<LoadBlocker>>,
     visibility: Cell<bool>,
+    /// <https://fullscreen.spec.whatwg.org/#iframe-fullscreen-flag>
+    fullscreen_flag: Cell<bool>,
+    /// The URL to navigate to when the lazily loaded iframe comes near the viewport.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#lazy-load-resumption-steps>
+    lazy_load_resumption_url: DomRefCell<Option<ServoUrl>>,
 }
 
 impl HTMLIFrameElement {
@@ -127,6 +133,12 @@ impl HTMLIFrameElement {
         };
 
         let document = document_from_node(self);
+        load_data.has_trustworthy_ancestor_origin =
+            document.has_trustworthy_ancestor_or_current_origin();
+        load_data.has_only_trustworthy_ancestor_origins = document
+            .window()
+            .upcast::<GlobalScope>()
+            .is_secure_context();
 
         {
             let mut load_blocker = self.load_blocker.borrow_mut();
@@ -230,6 +242,8 @@ impl HTMLIFrameElement {
 
     /// <https://html.spec.whatwg.org/multipage/#process-the-iframe-attributes>
     fn process_the_iframe_attributes(&self, mode: ProcessingMode) {
+        self.cancel_lazy_load();
+
         if self
             .upcast::<Element>()
             .has_attribute(&local_name!("srcdoc"))
@@ -314,6 +328,19 @@ impl HTMLIFrameElement {
             ancestor = a.parent().map(|p| DomRoot::from_ref(p));
         }
 
+        // https://html.spec.whatwg.org/multipage/#will-lazy-load-element-steps
+        if self.upcast::<Element>().will_lazy_load() {
+            *self.lazy_load_resumption_url.borrow_mut() = Some(url);
+            document_from_node(self)
+                .start_intersection_observing_a_lazy_loading_element(self.upcast());
+            return;
+        }
+        self.navigate_an_iframe_or_frame(url);
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#navigate-an-iframe-or-frame>
+    fn navigate_an_iframe_or_frame(&self, url: ServoUrl) {
+        let window = window_from_node(self);
         let creator_pipeline_id = if url.as_str() == "about:blank" {
             Some(window.upcast::<GlobalScope>().pipeline_id())
         } else {
@@ -342,6 +369,22 @@ impl HTMLIFrameElement {
         self.navigate_or_reload_child_browsing_context(load_data, NavigationType::Regular, replace);
     }
 
+    /// <https://html.spec.whatwg.org/multipage/#lazy-load-resumption-steps>
+    pub fn resume_lazy_load(&self) {
+        let url = self.lazy_load_resumption_url.borrow_mut().take();
+        if let Some(url) = url {
+            self.navigate_an_iframe_or_frame(url);
+        }
+    }
+
+    /// Forgets the navigation that was waiting for the iframe to come near the viewport.
+    fn cancel_lazy_load(&self) {
+        if self.lazy_load_resumption_url.borrow_mut().take().is_some() {
+            document_from_node(self)
+                .stop_intersection_observing_a_lazy_loading_element(self.upcast());
+        }
+    }
+
     fn create_nested_browsing_context(&self) {
         // Synchronously create a new context and navigate it to about:blank.
         let url = ServoUrl::parse("about:blank").unwrap();
@@ -414,6 +457,8 @@ impl HTMLIFrameElement {
             sandbox_allowance: Cell::new(None),
             load_blocker: DomRefCell::new(None),
             visibility: Cell::new(true),
+            fullscreen_flag: Cell::new(false),
+            lazy_load_resumption_url: DomRefCell::new(None),
         }
     }
 
@@ -446,6 +491,16 @@ impl HTMLIFrameElement {
         self.top_level_browsing_context_id.get()
     }
 
+    /// Whether the iframe itself was requested to be fullscreen, rather than being
+    /// fullscreen because of an element of its nested browsing context.
+    pub fn fullscreen_flag(&self) -> bool {
+        self.fullscreen_flag.get()
+    }
+
+    pub fn set_fullscreen_flag(&self, value: bool) {
+        self.fullscreen_flag.set(value);
+    }
+
     pub fn change_visibility_status(&self, visibility: bool) {
         if self.visibility.get() != visibility {
             self.visibility.set(visibility);
@@ -592,6 +647,22 @@ impl HTMLIFrameElementMethods for HTMLIFrameElement {
     // https://html.spec.whatwg.org/multipage/#dom-dim-height
     make_dimension_setter!(SetHeight, "height");
 
+    // https://html.spec.whatwg.org/multipage/#dom-iframe-loading
+    fn Loading(&self) -> DOMString {
+        if self.upcast::<Element>().has_lazy_loading_attribute() {
+            DOMString::from("lazy")
+        } else {
+            DOMString::from("eager")
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-iframe-loading
+    fn SetLoading(&self, value: DOMString) {
+        // TODO change this to local_name! when html5ever updates
+        self.upcast::<Element>()
+            .set_string_attribute(&LocalName::from("loading"), value);
+    }
+
     // https://html.spec.whatwg.org/multipage/#other-elements,-attributes-and-apis:attr-iframe-frameborder
     make_getter!(FrameBorder, "frameborder");
     // https://html.spec.whatwg.org/multipage/#other-elements,-attributes-and-apis:attr-iframe-frameborder
@@ -664,6 +735,16 @@ impl VirtualMethods for HTMLIFrameElement {
                     self.process_the_iframe_attributes(ProcessingMode::NotFirstTime);
                 }
             },
+            // https://html.spec.whatwg.org/multipage/#the-iframe-element:lazy-loading-attribute
+            name if &**name == "loading" => {
+                if !self.upcast::<Element>().has_lazy_loading_attribute() &&
+                    self.lazy_load_resumption_url.borrow().is_some()
+                {
+                    document_from_node(self)
+                        .stop_intersection_observing_a_lazy_loading_element(self.upcast());
+                    self.resume_lazy_load();
+                }
+            },
             _ => {},
         }
     }
@@ -709,6 +790,7 @@ impl VirtualMethods for HTMLIFrameElement {
 
         let mut blocker = self.load_blocker.borrow_mut();
         LoadBlocker::terminate(&mut blocker);
+        self.cancel_lazy_load();
 
         // https://html.spec.whatwg.org/multipage/#a-browsing-context-is-discarded
         let window = window_from_node(self);
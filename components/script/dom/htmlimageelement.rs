@@ -49,6 +49,7 @@ use crate::network_listener::{self, NetworkListener, PreInvoke, ResourceTimingLi
 use crate::script_thread::ScriptThread;
 use crate::task_source::TaskSource;
 use app_units::{Au, AU_PER_PX};
+use content_security_policy as csp;
 use cssparser::{Parser, ParserInput};
 use dom_struct::dom_struct;
 use euclid::Point2D;
@@ -159,6 +160,10 @@ pub struct HTMLImageElement {
     #[ignore_malloc_size_of = "SourceSet"]
     source_set: DomRefCell<SourceSet>,
     last_selected_source: DomRefCell<Option<USVString>>,
+    /// The URL of the image whose fetch is deferred until the element comes near the viewport.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#lazy-load-resumption-steps>
+    lazy_load_resumption_url: DomRefCell<Option<ServoUrl>>,
 }
 
 impl HTMLImageElement {
@@ -215,8 +220,10 @@ impl FetchResponseListener for ImageContext {
             FetchMetadata::Filtered { unsafe_, .. } => unsafe_,
         });
 
-        // Step 14.5 of https://html.spec.whatwg.org/multipage/#img-environment-changes
         if let Some(metadata) = metadata.as_ref() {
+            self.doc.root().note_subresource_response(metadata);
+
+            // Step 14.5 of https://html.spec.whatwg.org/multipage/#img-environment-changes
             if let Some(ref content_type) = metadata.content_type {
                 let mime: Mime = content_type.clone().into_inner().into();
                 if mime.type_() == mime::MULTIPART && mime.subtype().as_str() == "x-mixed-replace" {
@@ -262,6 +269,11 @@ impl FetchResponseListener for ImageContext {
         &self.resource_timing
     }
 
+    fn process_csp_violations(&mut self, violations: Vec<csp::Violation>) {
+        let global = &self.resource_timing_global();
+        global.report_csp_violations(violations, None);
+    }
+
     fn submit_resource_timing(&mut self) {
         network_listener::submit_timing(self)
     }
@@ -316,8 +328,51 @@ pub(crate) fn image_fetch_request(
 
 #[allow(non_snake_case)]
 impl HTMLImageElement {
+    /// <https://html.spec.whatwg.org/multipage/#lazy-load-resumption-steps>
+    pub fn resume_lazy_load(&self) {
+        let url = self.lazy_load_resumption_url.borrow_mut().take();
+        if let Some(url) = url {
+            self.track_image(&url);
+        }
+    }
+
+    /// Fetches the image right away if it is waiting to come near the viewport.
+    fn load_lazy_image_now(&self) {
+        if self.lazy_load_resumption_url.borrow().is_none() {
+            return;
+        }
+        document_from_node(self).stop_intersection_observing_a_lazy_loading_element(self.upcast());
+        self.resume_lazy_load();
+    }
+
     /// Update the current image with a valid URL.
+    ///
+    /// An image that will lazy load is only looked up in the image cache here: its
+    /// fetch is deferred until the element comes near the viewport, which only the
+    /// document can tell from layout. The deferral can't live inside the image cache
+    /// itself: it is shared between documents, and the element it answers with
+    /// `ReadyForRequest` for a URL has to fetch it for every other element waiting on it.
     fn fetch_image(&self, img_url: &ServoUrl) {
+        // https://html.spec.whatwg.org/multipage/#will-lazy-load-element-steps
+        if self.upcast::<Element>().will_lazy_load() {
+            let window = window_from_node(self);
+            let cached_image = window.image_cache().get_image(
+                img_url.clone(),
+                window.origin().immutable().clone(),
+                cors_setting_for_element(self.upcast()),
+            );
+            if cached_image.is_none() {
+                *self.lazy_load_resumption_url.borrow_mut() = Some(img_url.clone());
+                document_from_node(self)
+                    .start_intersection_observing_a_lazy_loading_element(self.upcast());
+                return;
+            }
+        }
+        self.track_image(img_url);
+    }
+
+    /// Request the image from the image cache, fetching it if no other element is.
+    fn track_image(&self, img_url: &ServoUrl) {
         let window = window_from_node(self);
         let image_cache = window.image_cache();
         let sender = generate_cache_listener_for_element(self);
@@ -393,7 +448,8 @@ impl HTMLImageElement {
             } else {
                 FromPictureOrSrcSet::No
             },
-        );
+        )
+        .has_trustworthy_ancestor_origin(document.has_trustworthy_ancestor_origin());
 
         // This is a background load because the load blocker already fulfills the
         // purpose of delaying the document's load event.
@@ -785,7 +841,10 @@ impl HTMLImageElement {
         request.metadata = None;
         let document = document_from_node(self);
         LoadBlocker::terminate(&mut request.blocker);
-        request.blocker = Some(LoadBlocker::new(&*document, LoadType::Image(url.clone())));
+        // A lazily loaded image doesn't delay the load event of its document.
+        if !self.upcast::<Element>().will_lazy_load() {
+            request.blocker = Some(LoadBlocker::new(&*document, LoadType::Image(url.clone())));
+        }
     }
 
     /// Step 13-17 of html.spec.whatwg.org/multipage/#update-the-image-data
@@ -832,6 +891,7 @@ impl HTMLImageElement {
                 }
             },
         }
+
         self.fetch_image(&url);
     }
 
@@ -918,6 +978,11 @@ impl HTMLImageElement {
             current_request.state = State::Unavailable;
         }
 
+        // A fetch that was deferred until the element came near the viewport is superseded.
+        if self.lazy_load_resumption_url.borrow_mut().take().is_some() {
+            document.stop_intersection_observing_a_lazy_loading_element(elem);
+        }
+
         if !document.is_active() {
             // Step 1 (if the document is inactive)
             // TODO: use GlobalScope::enqueue_microtask,
@@ -1236,6 +1301,7 @@ impl HTMLImageElement {
             generation: Default::default(),
             source_set: DomRefCell::new(SourceSet::new()),
             last_selected_source: DomRefCell::new(None),
+            lazy_load_resumption_url: DomRefCell::new(None),
         }
     }
 
@@ -1510,6 +1576,8 @@ impl HTMLImageElementMethods for HTMLImageElement {
 
     // https://html.spec.whatwg.org/multipage/#dom-img-naturalwidth
     fn NaturalWidth(&self) -> u32 {
+        // Script can't tell the size of an image that was never fetched.
+        self.load_lazy_image_now();
         let request = self.current_request.borrow();
         let pixel_density = request.current_pixel_density.unwrap_or(1f64);
 
@@ -1521,6 +1589,7 @@ impl HTMLImageElementMethods for HTMLImageElement {
 
     // https://html.spec.whatwg.org/multipage/#dom-img-naturalheight
     fn NaturalHeight(&self) -> u32 {
+        self.load_lazy_image_now();
         let request = self.current_request.borrow();
         let pixel_density = request.current_pixel_density.unwrap_or(1f64);
 
@@ -1587,6 +1656,22 @@ impl HTMLImageElementMethods for HTMLImageElement {
         }
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-img-loading
+    fn Loading(&self) -> DOMString {
+        if self.upcast::<Element>().has_lazy_loading_attribute() {
+            DOMString::from("lazy")
+        } else {
+            DOMString::from("eager")
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-img-loading
+    fn SetLoading(&self, value: DOMString) {
+        // TODO change this to local_name! when html5ever updates
+        self.upcast::<Element>()
+            .set_string_attribute(&LocalName::from("loading"), value);
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-img-name
     make_getter!(Name, "name");
 
@@ -1643,6 +1728,12 @@ impl VirtualMethods for HTMLImageElement {
             &local_name!("crossorigin") |
             &local_name!("sizes") |
             &local_name!("referrerpolicy") => self.update_the_image_data(),
+            // https://html.spec.whatwg.org/multipage/#the-img-element:lazy-loading-attribute
+            name if &**name == "loading" => {
+                if !self.upcast::<Element>().has_lazy_loading_attribute() {
+                    self.load_lazy_image_now();
+                }
+            },
             _ => {},
         }
     }
@@ -16,6 +16,7 @@ use crate::dom::bindings::inheritance::Castable;
 use crate::dom::bindings::reflector::DomObject;
 use crate::dom::bindings::root::{DomRoot, LayoutDom, MutNullableDom};
 use crate::dom::bindings::str::{DOMString, USVString};
+use crate::dom::clipboardevent::ClipboardEventType;
 use crate::dom::compositionevent::CompositionEvent;
 use crate::dom::document::Document;
 use crate::dom::element::{AttributeMutation, Element, LayoutElementHelpers};
@@ -1289,7 +1290,7 @@ impl HTMLInputElementMethods for HTMLInputElement {
             ValueMode::Filename => {
                 if value.is_empty() {
                     let window = window_from_node(self);
-                    let fl = FileList::new(&window, vec![]);
+                    let fl = FileList::new(window.upcast(), vec![]);
                     self.filelist.set(Some(&fl));
                 } else {
                     return Err(Error::InvalidState);
@@ -1910,7 +1911,7 @@ impl HTMLInputElement {
         if let Some(err) = error {
             debug!("Input file select error: {:?}", err);
         } else {
-            let filelist = FileList::new(&window, files);
+            let filelist = FileList::new(window.upcast(), files);
             self.filelist.set(Some(&filelist));
 
             target.fire_bubbling_event(atom!("input"));
@@ -2336,7 +2337,7 @@ impl VirtualMethods for HTMLInputElement {
 
                         if new_type == InputType::File {
                             let window = window_from_node(self);
-                            let filelist = FileList::new(&window, vec![]);
+                            let filelist = FileList::new(window.upcast(), vec![]);
                             self.filelist.set(Some(&filelist));
                         }
 
@@ -2554,6 +2555,14 @@ impl VirtualMethods for HTMLInputElement {
             self.input_type().is_textual_or_password()
         {
             if let Some(keyevent) = event.downcast::<KeyboardEvent>() {
+                // Clipboard shortcuts fire a clipboard event first, and the text input
+                // only copies, cuts or pastes if it isn't canceled.
+                if let Some(type_) = ClipboardEventType::for_shortcut(keyevent) {
+                    if !document_from_node(self).fire_clipboard_event(type_) {
+                        event.mark_as_handled();
+                        return;
+                    }
+                }
                 // This can't be inlined, as holding on to textinput.borrow_mut()
                 // during self.implicit_submission will cause a panic.
                 let action = self.textinput.borrow_mut().handle_keydown(keyevent);
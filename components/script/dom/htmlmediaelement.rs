@@ -65,6 +65,7 @@ use crate::network_listener::{self, NetworkListener, PreInvoke, ResourceTimingLi
 use crate::realms::InRealm;
 use crate::script_thread::ScriptThread;
 use crate::task_source::TaskSource;
+use content_security_policy as csp;
 use dom_struct::dom_struct;
 use embedder_traits::resources::{self, Resource as EmbedderResource};
 use embedder_traits::{MediaPositionState, MediaSessionEvent, MediaSessionPlaybackState};
@@ -856,7 +857,8 @@ impl HTMLMediaElement {
         .headers(headers)
         .origin(document.origin().immutable().clone())
         .pipeline_id(Some(self.global().pipeline_id()))
-        .referrer_policy(document.get_referrer_policy());
+        .referrer_policy(document.get_referrer_policy())
+        .has_trustworthy_ancestor_origin(document.has_trustworthy_ancestor_origin());
 
         let mut current_fetch_context = self.current_fetch_context.borrow_mut();
         if let Some(ref mut current_fetch_context) = *current_fetch_context {
@@ -891,7 +893,7 @@ impl HTMLMediaElement {
             .core_resource_thread()
             .send(CoreResourceMsg::Fetch(
                 request,
-                FetchChannels::ResponseMsg(action_sender, Some(cancel_receiver)),
+                FetchChannels::ResponseMsg(action_sender, Some(cancel_receiver), None),
             ))
             .unwrap();
     }
@@ -2621,6 +2623,8 @@ impl FetchResponseListener for HTMLMediaElementFetchListener {
         });
 
         if let Some(metadata) = self.metadata.as_ref() {
+            document_from_node(&*elem).note_subresource_response(metadata);
+
             if let Some(headers) = metadata.headers.as_ref() {
                 // For range requests we get the size of the media asset from the Content-Range
                 // header. Otherwise, we get it from the Content-Length header.
@@ -2826,6 +2830,11 @@ impl FetchResponseListener for HTMLMediaElementFetchListener {
         &self.resource_timing
     }
 
+    fn process_csp_violations(&mut self, violations: Vec<csp::Violation>) {
+        let global = &self.resource_timing_global();
+        global.report_csp_violations(violations, None);
+    }
+
     fn submit_resource_timing(&mut self) {
         network_listener::submit_timing(self)
     }
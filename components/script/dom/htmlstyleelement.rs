@@ -18,6 +18,7 @@ use crate::dom::node::{
 use crate::dom::stylesheet::StyleSheet as DOMStyleSheet;
 use crate::dom::virtualmethods::VirtualMethods;
 use crate::stylesheet_loader::{StylesheetLoader, StylesheetOwner};
+use content_security_policy as csp;
 use cssparser::{Parser as CssParser, ParserInput};
 use dom_struct::dom_struct;
 use html5ever::{LocalName, Prefix};
@@ -94,6 +95,22 @@ impl HTMLStyleElement {
         let data = node
             .GetTextContent()
             .expect("Element.textContent must be a string");
+
+        // https://html.spec.whatwg.org/multipage/#update-a-style-block Step 5
+        if doc.should_elements_inline_type_behavior_be_blocked(
+            element,
+            csp::InlineCheckType::Style,
+            &data,
+        ) == csp::CheckResult::Blocked
+        {
+            warn!("Blocking inline stylesheet due to CSP");
+            if let Some(s) = self.stylesheet.borrow_mut().take() {
+                self.clean_stylesheet_ownership();
+                stylesheets_owner_from_node(self).remove_stylesheet(self.upcast(), &s)
+            }
+            return;
+        }
+
         let url = window.get_url();
         let css_error_reporter = window.css_error_reporter();
         let context = CssParserContext::new_for_cssom(
@@ -12,6 +12,7 @@ use crate::dom::bindings::error::ErrorResult;
 use crate::dom::bindings::inheritance::Castable;
 use crate::dom::bindings::root::{DomRoot, LayoutDom, MutNullableDom};
 use crate::dom::bindings::str::DOMString;
+use crate::dom::clipboardevent::ClipboardEventType;
 use crate::dom::compositionevent::CompositionEvent;
 use crate::dom::document::Document;
 use crate::dom::element::LayoutElementHelpers;
@@ -23,7 +24,7 @@ use crate::dom::htmlfieldsetelement::HTMLFieldSetElement;
 use crate::dom::htmlformelement::{FormControl, HTMLFormElement};
 use crate::dom::htmlinputelement::HTMLInputElement;
 use crate::dom::keyboardevent::KeyboardEvent;
-use crate::dom::node::window_from_node;
+use crate::dom::node::{document_from_node, window_from_node};
 use crate::dom::node::{
     BindContext, ChildrenMutation, CloneChildrenFlag, Node, NodeDamage, UnbindContext,
 };
@@ -609,6 +610,14 @@ impl VirtualMethods for HTMLTextAreaElement {
             //TODO: set the editing position for text inputs
         } else if event.type_() == atom!("keydown") && !event.DefaultPrevented() {
             if let Some(kevent) = event.downcast::<KeyboardEvent>() {
+                // Clipboard shortcuts fire a clipboard event first, and the text input
+                // only copies, cuts or pastes if it isn't canceled.
+                if let Some(type_) = ClipboardEventType::for_shortcut(kevent) {
+                    if !document_from_node(self).fire_clipboard_event(type_) {
+                        event.mark_as_handled();
+                        return;
+                    }
+                }
                 // This can't be inlined, as holding on to textinput.borrow_mut()
                 // during self.implicit_submission will cause a panic.
                 let action = self.textinput.borrow_mut().handle_keydown(kevent);
@@ -21,6 +21,7 @@ use crate::dom::virtualmethods::VirtualMethods;
 use crate::fetch::FetchCanceller;
 use crate::image_listener::{generate_cache_listener_for_element, ImageCacheListener};
 use crate::network_listener::{self, NetworkListener, PreInvoke, ResourceTimingListener};
+use content_security_policy as csp;
 use dom_struct::dom_struct;
 use euclid::default::Size2D;
 use html5ever::{LocalName, Prefix};
@@ -234,7 +235,7 @@ impl HTMLVideoElement {
             .core_resource_thread()
             .send(CoreResourceMsg::Fetch(
                 request,
-                FetchChannels::ResponseMsg(action_sender, Some(cancel_receiver)),
+                FetchChannels::ResponseMsg(action_sender, Some(cancel_receiver), None),
             ))
             .unwrap();
     }
@@ -364,6 +365,11 @@ impl FetchResponseListener for PosterFrameFetchContext {
         &self.resource_timing
     }
 
+    fn process_csp_violations(&mut self, violations: Vec<csp::Violation>) {
+        let global = &self.resource_timing_global();
+        global.report_csp_violations(violations, None);
+    }
+
     fn submit_resource_timing(&mut self) {
         network_listener::submit_timing(self)
     }
@@ -0,0 +1,365 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::IDBCursorBinding::{
+    IDBCursorDirection, IDBCursorMethods,
+};
+use crate::dom::bindings::codegen::UnionTypes::IDBObjectStoreOrIDBIndex;
+use crate::dom::bindings::codegen::UnionTypes::IDBObjectStoreOrIDBIndexOrIDBCursor;
+use crate::dom::bindings::error::{Error, ErrorResult, Fallible};
+use crate::dom::bindings::refcounted::Trusted;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot};
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::idbcursorwithvalue::IDBCursorWithValue;
+use crate::dom::idbindex::IDBIndex;
+use crate::dom::idbobjectstore::IDBObjectStore;
+use crate::dom::idbrequest::{IDBRequest, IDBRequestResult};
+use crate::indexed_db::serialize;
+use crate::indexed_db::{convert_value_to_key, deserialize, extract_key, key_to_jsval};
+use crate::script_runtime::JSContext;
+use dom_struct::dom_struct;
+use js::jsapi::Heap;
+use js::jsval::{JSVal, UndefinedValue};
+use js::rust::HandleValue;
+use net_traits::indexeddb_thread::{IndexedDBCursorDirection, IndexedDBCursorPosition};
+use net_traits::indexeddb_thread::{IndexedDBKeyRange, IndexedDBKeyType, IndexedDBQuery};
+use net_traits::indexeddb_thread::{IndexedDBRecord, IndexedDBTransactionMode};
+use std::cell::Cell;
+
+// https://w3c.github.io/IndexedDB/#cursor-interface
+#[dom_struct]
+pub struct IDBCursor {
+    reflector_: Reflector,
+    object_store: Dom<IDBObjectStore>,
+    /// The index the cursor iterates over, if its source is not the object store.
+    index: Option<Dom<IDBIndex>>,
+    direction: IndexedDBCursorDirection,
+    range: IndexedDBKeyRange,
+    request: Dom<IDBRequest>,
+    key_only: bool,
+    position: DomRefCell<Option<IndexedDBKeyType>>,
+    object_store_position: DomRefCell<Option<IndexedDBKeyType>>,
+    got_value: Cell<bool>,
+    #[ignore_malloc_size_of = "Defined in rust-mozjs"]
+    key: Heap<JSVal>,
+    #[ignore_malloc_size_of = "Defined in rust-mozjs"]
+    primary_key: Heap<JSVal>,
+    #[ignore_malloc_size_of = "Defined in rust-mozjs"]
+    value: Heap<JSVal>,
+}
+
+impl IDBCursor {
+    pub fn new_inherited(
+        object_store: &IDBObjectStore,
+        index: Option<&IDBIndex>,
+        range: IndexedDBKeyRange,
+        direction: IndexedDBCursorDirection,
+        request: &IDBRequest,
+        key_only: bool,
+    ) -> IDBCursor {
+        IDBCursor {
+            reflector_: Reflector::new(),
+            object_store: Dom::from_ref(object_store),
+            index: index.map(Dom::from_ref),
+            direction: direction,
+            range: range,
+            request: Dom::from_ref(request),
+            key_only: key_only,
+            position: DomRefCell::new(None),
+            object_store_position: DomRefCell::new(None),
+            got_value: Cell::new(false),
+            key: Heap::default(),
+            primary_key: Heap::default(),
+            value: Heap::default(),
+        }
+    }
+
+    /// Creates an `IDBCursorWithValue`, unless the cursor is `key_only`.
+    #[allow(unrooted_must_root)]
+    pub fn new(
+        global: &GlobalScope,
+        object_store: &IDBObjectStore,
+        index: Option<&IDBIndex>,
+        range: IndexedDBKeyRange,
+        direction: IndexedDBCursorDirection,
+        request: &IDBRequest,
+        key_only: bool,
+    ) -> DomRoot<IDBCursor> {
+        let cursor =
+            IDBCursor::new_inherited(object_store, index, range, direction, request, key_only);
+        if key_only {
+            reflect_dom_object(Box::new(cursor), global)
+        } else {
+            DomRoot::upcast(IDBCursorWithValue::new(global, cursor))
+        }
+    }
+
+    pub fn value(&self) -> JSVal {
+        self.value.get()
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#iterate-a-cursor>, moving `count` records past
+    /// `position`, or to the first record without one.
+    pub fn iterate(
+        &self,
+        position: Option<IndexedDBCursorPosition>,
+        count: u32,
+    ) -> Fallible<IDBRequestResult> {
+        let query = IndexedDBQuery {
+            object_store: self.object_store.name(),
+            index: self.index.as_ref().map(|index| index.name()),
+            range: self.range.clone(),
+            direction: self.direction,
+            position: position,
+            count: Some(count),
+            with_values: !self.key_only,
+        };
+        let mut records = self.object_store.query(query)?;
+        let record = if records.len() == count as usize {
+            records.pop()
+        } else {
+            None
+        };
+        Ok(IDBRequestResult::Cursor(record))
+    }
+
+    /// Moves the cursor to the record an iteration found, returning whether there was one.
+    pub fn set_record(&self, record: Option<IndexedDBRecord>) -> Fallible<bool> {
+        let record = match record {
+            Some(record) => record,
+            None => {
+                *self.position.borrow_mut() = None;
+                *self.object_store_position.borrow_mut() = None;
+                self.key.set(UndefinedValue());
+                self.primary_key.set(UndefinedValue());
+                self.value.set(UndefinedValue());
+                return Ok(false);
+            },
+        };
+        let global = self.global();
+        let cx = global.get_cx();
+        rooted!(in(*cx) let mut value = UndefinedValue());
+        key_to_jsval(cx, &record.key, value.handle_mut());
+        self.key.set(value.get());
+        key_to_jsval(cx, &record.primary_key, value.handle_mut());
+        self.primary_key.set(value.get());
+        if let Some(serialized) = record.value {
+            deserialize(&global, serialized, value.handle_mut())?;
+            self.value.set(value.get());
+        }
+        *self.position.borrow_mut() = Some(record.key);
+        *self.object_store_position.borrow_mut() = Some(record.primary_key);
+        self.got_value.set(true);
+        Ok(true)
+    }
+
+    fn source(&self) -> IDBObjectStoreOrIDBIndexOrIDBCursor {
+        IDBObjectStoreOrIDBIndexOrIDBCursor::IDBCursor(DomRoot::from_ref(self))
+    }
+
+    /// Checks that the transaction is active and the source was not deleted.
+    fn check_source(&self) -> ErrorResult {
+        self.object_store.check_active()?;
+        if let Some(ref index) = self.index {
+            index.check_active()?;
+        }
+        Ok(())
+    }
+
+    fn check_got_value(&self) -> ErrorResult {
+        if !self.got_value.get() {
+            return Err(Error::InvalidState);
+        }
+        Ok(())
+    }
+
+    /// The checks of `update()` and `delete()`.
+    fn check_writable(&self) -> ErrorResult {
+        self.check_source()?;
+        if self.object_store.transaction().mode() == IndexedDBTransactionMode::ReadOnly {
+            return Err(Error::ReadOnly);
+        }
+        self.check_got_value()?;
+        if self.key_only {
+            return Err(Error::InvalidState);
+        }
+        Ok(())
+    }
+
+    fn current_key(&self) -> IndexedDBKeyType {
+        self.position
+            .borrow()
+            .clone()
+            .expect("Cursor without a position")
+    }
+
+    fn current_primary_key(&self) -> IndexedDBKeyType {
+        self.object_store_position
+            .borrow()
+            .clone()
+            .expect("Cursor without a position")
+    }
+
+    /// The position right after the current record.
+    fn next_position(&self) -> IndexedDBCursorPosition {
+        // Unique cursors skip the other records with the same index key.
+        let primary_key = match self.index {
+            Some(_) if !self.direction.is_unique() => Some(self.current_primary_key()),
+            _ => None,
+        };
+        IndexedDBCursorPosition {
+            key: self.current_key(),
+            primary_key: primary_key,
+            inclusive: false,
+        }
+    }
+
+    /// Iterates the cursor, completing its request again once done.
+    fn iterate_request(&self, position: IndexedDBCursorPosition, count: u32) {
+        self.got_value.set(false);
+        let cursor = Trusted::new(self);
+        self.request.run(&self.object_store.transaction(), move || {
+            cursor.root().iterate(Some(position), count)
+        });
+    }
+}
+
+impl IDBCursorMethods for IDBCursor {
+    // https://w3c.github.io/IndexedDB/#dom-idbcursor-source
+    fn Source(&self) -> IDBObjectStoreOrIDBIndex {
+        match self.index {
+            Some(ref index) => IDBObjectStoreOrIDBIndex::IDBIndex(DomRoot::from_ref(&**index)),
+            None => {
+                IDBObjectStoreOrIDBIndex::IDBObjectStore(DomRoot::from_ref(&*self.object_store))
+            },
+        }
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbcursor-direction
+    fn Direction(&self) -> IDBCursorDirection {
+        match self.direction {
+            IndexedDBCursorDirection::Next => IDBCursorDirection::Next,
+            IndexedDBCursorDirection::NextUnique => IDBCursorDirection::Nextunique,
+            IndexedDBCursorDirection::Prev => IDBCursorDirection::Prev,
+            IndexedDBCursorDirection::PrevUnique => IDBCursorDirection::Prevunique,
+        }
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbcursor-key
+    fn Key(&self, _cx: JSContext) -> JSVal {
+        self.key.get()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbcursor-primarykey
+    fn PrimaryKey(&self, _cx: JSContext) -> JSVal {
+        self.primary_key.get()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbcursor-request
+    fn Request(&self) -> DomRoot<IDBRequest> {
+        DomRoot::from_ref(&*self.request)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbcursor-advance
+    fn Advance(&self, count: u32) -> ErrorResult {
+        if count == 0 {
+            return Err(Error::Type("The count must not be 0".to_owned()));
+        }
+        self.check_source()?;
+        self.check_got_value()?;
+        self.iterate_request(self.next_position(), count);
+        Ok(())
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbcursor-continue
+    fn Continue(&self, cx: JSContext, key: HandleValue) -> ErrorResult {
+        self.check_source()?;
+        self.check_got_value()?;
+        let position = if key.is_undefined() {
+            self.next_position()
+        } else {
+            let key = convert_value_to_key(cx, key)?;
+            let current = self.current_key();
+            let behind = if self.direction.is_reverse() {
+                key >= current
+            } else {
+                key <= current
+            };
+            if behind {
+                return Err(Error::Data);
+            }
+            IndexedDBCursorPosition {
+                key: key,
+                primary_key: None,
+                inclusive: true,
+            }
+        };
+        self.iterate_request(position, 1);
+        Ok(())
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbcursor-continueprimarykey
+    fn ContinuePrimaryKey(
+        &self,
+        cx: JSContext,
+        key: HandleValue,
+        primary_key: HandleValue,
+    ) -> ErrorResult {
+        self.check_source()?;
+        if self.index.is_none() || self.direction.is_unique() {
+            return Err(Error::InvalidAccess);
+        }
+        self.check_got_value()?;
+        let key = convert_value_to_key(cx, key)?;
+        let primary_key = convert_value_to_key(cx, primary_key)?;
+        let current = (self.current_key(), self.current_primary_key());
+        let target = (key, primary_key);
+        let behind = if self.direction.is_reverse() {
+            target >= current
+        } else {
+            target <= current
+        };
+        if behind {
+            return Err(Error::Data);
+        }
+        let (key, primary_key) = target;
+        self.iterate_request(
+            IndexedDBCursorPosition {
+                key: key,
+                primary_key: Some(primary_key),
+                inclusive: true,
+            },
+            1,
+        );
+        Ok(())
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbcursor-update
+    fn Update(&self, cx: JSContext, value: HandleValue) -> Fallible<DomRoot<IDBRequest>> {
+        self.check_writable()?;
+        let primary_key = self.current_primary_key();
+        let key = match self.object_store.key_path() {
+            // In-line keys must not change.
+            Some(key_path) => {
+                rooted!(in(*cx) let mut clone = UndefinedValue());
+                deserialize(&self.global(), serialize(cx, value)?, clone.handle_mut())?;
+                if extract_key(cx, clone.handle(), key_path)? != Some(primary_key) {
+                    return Err(Error::Data);
+                }
+                None
+            },
+            None => Some(primary_key),
+        };
+        self.object_store.put(cx, value, key, false, self.source())
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbcursor-delete
+    fn Delete(&self) -> Fallible<DomRoot<IDBRequest>> {
+        self.check_writable()?;
+        let range = IndexedDBKeyRange::only(self.current_primary_key());
+        Ok(self.object_store.delete_range(range, self.source()))
+    }
+}
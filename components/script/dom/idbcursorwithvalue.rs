@@ -0,0 +1,32 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::codegen::Bindings::IDBCursorWithValueBinding::IDBCursorWithValueMethods;
+use crate::dom::bindings::reflector::reflect_dom_object;
+use crate::dom::bindings::root::DomRoot;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::idbcursor::IDBCursor;
+use crate::script_runtime::JSContext;
+use dom_struct::dom_struct;
+use js::jsval::JSVal;
+
+// https://w3c.github.io/IndexedDB/#idbcursorwithvalue
+#[dom_struct]
+pub struct IDBCursorWithValue {
+    cursor: IDBCursor,
+}
+
+impl IDBCursorWithValue {
+    #[allow(unrooted_must_root)]
+    pub fn new(global: &GlobalScope, cursor: IDBCursor) -> DomRoot<IDBCursorWithValue> {
+        reflect_dom_object(Box::new(IDBCursorWithValue { cursor: cursor }), global)
+    }
+}
+
+impl IDBCursorWithValueMethods for IDBCursorWithValue {
+    // https://w3c.github.io/IndexedDB/#dom-idbcursorwithvalue-value
+    fn Value(&self, _cx: JSContext) -> JSVal {
+        self.cursor.value()
+    }
+}
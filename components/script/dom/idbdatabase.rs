@@ -0,0 +1,316 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::IDBDatabaseBinding::{
+    IDBDatabaseMethods, IDBObjectStoreParameters,
+};
+use crate::dom::bindings::codegen::Bindings::IDBTransactionBinding::IDBTransactionMode;
+use crate::dom::bindings::codegen::UnionTypes::StringOrStringSequence;
+use crate::dom::bindings::error::{Error, ErrorResult, Fallible};
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject};
+use crate::dom::bindings::root::{DomRoot, MutNullableDom};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::domstringlist::DOMStringList;
+use crate::dom::event::{Event, EventBubbles, EventCancelable};
+use crate::dom::eventtarget::EventTarget;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::idbobjectstore::IDBObjectStore;
+use crate::dom::idbtransaction::IDBTransaction;
+use crate::dom::idbversionchangeevent::IDBVersionChangeEvent;
+use crate::indexed_db::{key_path_from_idl, post_message, route_events, send_fallible_message};
+use dom_struct::dom_struct;
+use ipc_channel::ipc::{self, IpcReceiver};
+use net_traits::indexeddb_thread::{DatabaseInfo, IndexedDBConnectionEvent, IndexedDBThreadMsg};
+use net_traits::indexeddb_thread::{IndexedDBTransactionMode, KeyPath, ObjectStoreInfo};
+use std::cell::Cell;
+
+// https://w3c.github.io/IndexedDB/#database-interface
+#[dom_struct]
+pub struct IDBDatabase {
+    eventtarget: EventTarget,
+    /// The id of the connection in the engine.
+    id: u64,
+    name: DOMString,
+    version: Cell<u64>,
+    object_stores: DomRefCell<Vec<ObjectStoreInfo>>,
+    /// The version and object stores to restore if the upgrade transaction aborts.
+    old_version: Cell<u64>,
+    old_object_stores: DomRefCell<Vec<ObjectStoreInfo>>,
+    upgrade_transaction: MutNullableDom<IDBTransaction>,
+    closed: Cell<bool>,
+}
+
+impl IDBDatabase {
+    fn new_inherited(id: u64, info: DatabaseInfo) -> IDBDatabase {
+        IDBDatabase {
+            eventtarget: EventTarget::new_inherited(),
+            id: id,
+            name: DOMString::from(info.name),
+            version: Cell::new(info.version),
+            object_stores: DomRefCell::new(info.object_stores),
+            old_version: Cell::new(info.version),
+            old_object_stores: DomRefCell::new(vec![]),
+            upgrade_transaction: Default::default(),
+            closed: Cell::new(false),
+        }
+    }
+
+    pub fn new(
+        global: &GlobalScope,
+        id: u64,
+        info: DatabaseInfo,
+        events: IpcReceiver<IndexedDBConnectionEvent>,
+    ) -> DomRoot<IDBDatabase> {
+        let connection = reflect_dom_object(Box::new(IDBDatabase::new_inherited(id, info)), global);
+        route_events(&*connection, events, IDBDatabase::handle_event);
+        connection
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    fn handle_event(&self, event: IndexedDBConnectionEvent) {
+        match event {
+            // https://w3c.github.io/IndexedDB/#open-a-database step 10.3
+            IndexedDBConnectionEvent::VersionChange(transaction, old_version, new_version) => {
+                if !self.closed.get() {
+                    let event = IDBVersionChangeEvent::new(
+                        &self.global(),
+                        atom!("versionchange"),
+                        EventBubbles::DoesNotBubble,
+                        EventCancelable::NotCancelable,
+                        old_version,
+                        new_version,
+                    );
+                    event.upcast::<Event>().fire(self.upcast());
+                }
+                // The engine waits for this before it tells whether the request is blocked.
+                post_message(
+                    &self.global(),
+                    IndexedDBThreadMsg::VersionChangeFired(transaction),
+                );
+            },
+        }
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#close-a-database-connection>
+    pub fn close(&self) {
+        if self.closed.get() {
+            return;
+        }
+        self.closed.set(true);
+        post_message(&self.global(), IndexedDBThreadMsg::Close(self.id));
+    }
+
+    pub fn name(&self) -> String {
+        self.name.to_string()
+    }
+
+    pub fn object_store_info(&self, name: &str) -> Option<ObjectStoreInfo> {
+        self.object_stores
+            .borrow()
+            .iter()
+            .find(|store| store.name == name)
+            .cloned()
+    }
+
+    /// Updates the metadata of an object store after a change to its indexes.
+    pub fn set_object_store_info(&self, info: ObjectStoreInfo) {
+        let mut object_stores = self.object_stores.borrow_mut();
+        if let Some(store) = object_stores
+            .iter_mut()
+            .find(|store| store.name == info.name)
+        {
+            *store = info;
+        }
+    }
+
+    /// The sorted names of the object stores.
+    pub fn object_store_names(&self) -> Vec<DOMString> {
+        let mut names: Vec<_> = self
+            .object_stores
+            .borrow()
+            .iter()
+            .map(|store| DOMString::from(store.name.clone()))
+            .collect();
+        names.sort();
+        names
+    }
+
+    pub fn begin_upgrade(&self, transaction: &IDBTransaction, version: u64) {
+        self.old_version.set(self.version.get());
+        *self.old_object_stores.borrow_mut() = self.object_stores.borrow().clone();
+        self.version.set(version);
+        self.upgrade_transaction.set(Some(transaction));
+    }
+
+    pub fn upgrade_finished(&self) {
+        self.upgrade_transaction.set(None);
+        self.old_object_stores.borrow_mut().clear();
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#abort-an-upgrade-transaction>
+    pub fn abort_upgrade(&self) {
+        self.version.set(self.old_version.get());
+        *self.object_stores.borrow_mut() = self.old_object_stores.borrow_mut().split_off(0);
+        self.upgrade_transaction.set(None);
+        self.close();
+    }
+
+    /// The running upgrade transaction, which the structure of the database can only
+    /// be changed in.
+    pub fn check_upgrade_transaction(&self) -> Fallible<DomRoot<IDBTransaction>> {
+        let transaction = self.upgrade_transaction.get().ok_or(Error::InvalidState)?;
+        transaction.check_active()?;
+        Ok(transaction)
+    }
+}
+
+impl IDBDatabaseMethods for IDBDatabase {
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-name
+    fn Name(&self) -> DOMString {
+        self.name.clone()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-version
+    fn Version(&self) -> u64 {
+        self.version.get()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-objectstorenames
+    fn ObjectStoreNames(&self) -> DomRoot<DOMStringList> {
+        DOMStringList::new(&self.global(), self.object_store_names())
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-transaction
+    fn Transaction(
+        &self,
+        store_names: StringOrStringSequence,
+        mode: IDBTransactionMode,
+    ) -> Fallible<DomRoot<IDBTransaction>> {
+        // Steps 1-2.
+        if self.upgrade_transaction.get().is_some() || self.closed.get() {
+            return Err(Error::InvalidState);
+        }
+        // Steps 3-4.
+        let mut scope = match store_names {
+            StringOrStringSequence::String(name) => vec![name],
+            StringOrStringSequence::StringSequence(names) => names,
+        };
+        scope.sort();
+        scope.dedup();
+        if scope
+            .iter()
+            .any(|name| self.object_store_info(name).is_none())
+        {
+            return Err(Error::NotFound);
+        }
+        // Step 5.
+        if scope.is_empty() {
+            return Err(Error::InvalidAccess);
+        }
+        // Step 6.
+        let mode = match mode {
+            IDBTransactionMode::Readonly => IndexedDBTransactionMode::ReadOnly,
+            IDBTransactionMode::Readwrite => IndexedDBTransactionMode::ReadWrite,
+            IDBTransactionMode::Versionchange => {
+                return Err(Error::Type(
+                    "Versionchange transactions can't be created directly".to_owned(),
+                ));
+            },
+        };
+        // Steps 7-8.
+        let global = self.global();
+        let (events_sender, events) = ipc::channel().unwrap();
+        let names = scope.iter().map(|name| name.to_string()).collect();
+        let id = send_fallible_message(&global, |sender| {
+            IndexedDBThreadMsg::BeginTransaction(sender, events_sender, self.id, mode, names)
+        })?;
+        let transaction = IDBTransaction::new(&global, self, id, mode, scope, None);
+        route_events(&*transaction, events, IDBTransaction::handle_schedule_event);
+        transaction.queue_deactivation();
+        Ok(transaction)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-close
+    fn Close(&self) {
+        self.close();
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-createobjectstore
+    fn CreateObjectStore(
+        &self,
+        name: DOMString,
+        options: &IDBObjectStoreParameters,
+    ) -> Fallible<DomRoot<IDBObjectStore>> {
+        // Steps 2-4.
+        let transaction = self.check_upgrade_transaction()?;
+        // Steps 5-6.
+        let key_path = match options.keyPath {
+            Some(ref key_path) => Some(key_path_from_idl(key_path)?),
+            None => None,
+        };
+        // Step 7.
+        if self.object_store_info(&name).is_some() {
+            return Err(Error::Constraint);
+        }
+        // Step 9.
+        let auto_increment = options.autoIncrement;
+        let invalid_for_key_generator = match key_path {
+            Some(KeyPath::String(ref path)) => path.is_empty(),
+            Some(KeyPath::Sequence(_)) => true,
+            None => false,
+        };
+        if auto_increment && invalid_for_key_generator {
+            return Err(Error::InvalidAccess);
+        }
+        // Steps 10-11.
+        let info = ObjectStoreInfo {
+            name: name.to_string(),
+            key_path: key_path,
+            auto_increment: auto_increment,
+            indexes: vec![],
+        };
+        let message_info = info.clone();
+        send_fallible_message(&self.global(), |sender| {
+            IndexedDBThreadMsg::CreateObjectStore(sender, transaction.id(), message_info)
+        })?;
+        self.object_stores.borrow_mut().push(info);
+        transaction.object_store(&name)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-deleteobjectstore
+    fn DeleteObjectStore(&self, name: DOMString) -> ErrorResult {
+        // Steps 2-3.
+        let transaction = self.check_upgrade_transaction()?;
+        // Step 4.
+        if self.object_store_info(&name).is_none() {
+            return Err(Error::NotFound);
+        }
+        // Steps 5-6.
+        send_fallible_message(&self.global(), |sender| {
+            IndexedDBThreadMsg::DeleteObjectStore(sender, transaction.id(), name.to_string())
+        })?;
+        self.object_stores
+            .borrow_mut()
+            .retain(|store| store.name.as_str() != &*name);
+        transaction.forget_object_store(&name);
+        Ok(())
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-onabort
+    event_handler!(abort, GetOnabort, SetOnabort);
+
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-onclose
+    event_handler!(close, GetOnclose, SetOnclose);
+
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-onerror
+    event_handler!(error, GetOnerror, SetOnerror);
+
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-onversionchange
+    event_handler!(versionchange, GetOnversionchange, SetOnversionchange);
+}
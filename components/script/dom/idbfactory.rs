@@ -0,0 +1,105 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::codegen::Bindings::IDBFactoryBinding::{
+    IDBDatabaseInfo, IDBFactoryMethods,
+};
+use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::DomRoot;
+use crate::dom::bindings::str::DOMString;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::idbopendbrequest::IDBOpenDBRequest;
+use crate::dom::promise::Promise;
+use crate::indexed_db::{convert_value_to_key, send_message};
+use crate::script_runtime::JSContext;
+use dom_struct::dom_struct;
+use js::rust::HandleValue;
+use net_traits::indexeddb_thread::IndexedDBThreadMsg;
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+// https://w3c.github.io/IndexedDB/#factory-interface
+#[dom_struct]
+pub struct IDBFactory {
+    reflector_: Reflector,
+}
+
+impl IDBFactory {
+    fn new_inherited() -> IDBFactory {
+        IDBFactory {
+            reflector_: Reflector::new(),
+        }
+    }
+
+    pub fn new(global: &GlobalScope) -> DomRoot<IDBFactory> {
+        reflect_dom_object(Box::new(IDBFactory::new_inherited()), global)
+    }
+
+    /// Databases are only available to tuple origins.
+    fn check_origin(&self) -> Fallible<()> {
+        if !self.global().origin().is_tuple() {
+            return Err(Error::Security);
+        }
+        Ok(())
+    }
+}
+
+impl IDBFactoryMethods for IDBFactory {
+    // https://w3c.github.io/IndexedDB/#dom-idbfactory-open
+    fn Open(&self, name: DOMString, version: Option<u64>) -> Fallible<DomRoot<IDBOpenDBRequest>> {
+        // Step 1.
+        if version == Some(0) {
+            return Err(Error::Type("The version must not be 0".to_owned()));
+        }
+        // Steps 2-3.
+        self.check_origin()?;
+        // Steps 4-5.
+        let request = IDBOpenDBRequest::new(&self.global());
+        request.queue_open(name.into(), version);
+        Ok(request)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbfactory-deletedatabase
+    fn DeleteDatabase(&self, name: DOMString) -> Fallible<DomRoot<IDBOpenDBRequest>> {
+        self.check_origin()?;
+        let request = IDBOpenDBRequest::new(&self.global());
+        request.queue_delete(name.into());
+        Ok(request)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbfactory-databases
+    fn Databases(&self) -> Rc<Promise> {
+        let global = self.global();
+        let promise = Promise::new(&global);
+        if self.check_origin().is_err() {
+            promise.reject_error(Error::Security);
+            return promise;
+        }
+        let origin = global.origin().immutable().clone();
+        let databases = send_message(&global, |sender| {
+            IndexedDBThreadMsg::Databases(sender, origin)
+        });
+        let databases: Vec<IDBDatabaseInfo> = databases
+            .into_iter()
+            .map(|(name, version)| IDBDatabaseInfo {
+                name: Some(DOMString::from(name)),
+                version: Some(version),
+            })
+            .collect();
+        promise.resolve_native(&databases);
+        promise
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbfactory-cmp
+    fn Cmp(&self, cx: JSContext, first: HandleValue, second: HandleValue) -> Fallible<i16> {
+        let first = convert_value_to_key(cx, first)?;
+        let second = convert_value_to_key(cx, second)?;
+        Ok(match first.cmp(&second) {
+            Ordering::Less => -1,
+            Ordering::Equal => 0,
+            Ordering::Greater => 1,
+        })
+    }
+}
@@ -0,0 +1,198 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::codegen::Bindings::IDBCursorBinding::IDBCursorDirection;
+use crate::dom::bindings::codegen::Bindings::IDBIndexBinding::IDBIndexMethods;
+use crate::dom::bindings::codegen::UnionTypes::IDBObjectStoreOrIDBIndexOrIDBCursor;
+use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::reflector::{reflect_dom_object, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::idbobjectstore::{GetKind, IDBObjectStore};
+use crate::dom::idbrequest::IDBRequest;
+use crate::indexed_db::key_path_to_jsval;
+use crate::indexed_db::{convert_value_to_key_range, cursor_direction_from_idl};
+use crate::script_runtime::JSContext;
+use dom_struct::dom_struct;
+use js::jsval::{JSVal, UndefinedValue};
+use js::rust::HandleValue;
+use net_traits::indexeddb_thread::{IndexInfo, KeyPath};
+
+// https://w3c.github.io/IndexedDB/#index-interface
+#[dom_struct]
+pub struct IDBIndex {
+    reflector_: Reflector,
+    object_store: Dom<IDBObjectStore>,
+    name: DOMString,
+    key_path: KeyPath,
+    unique: bool,
+    multi_entry: bool,
+}
+
+impl IDBIndex {
+    fn new_inherited(object_store: &IDBObjectStore, info: &IndexInfo) -> IDBIndex {
+        IDBIndex {
+            reflector_: Reflector::new(),
+            object_store: Dom::from_ref(object_store),
+            name: DOMString::from(info.name.clone()),
+            key_path: info.key_path.clone(),
+            unique: info.unique,
+            multi_entry: info.multi_entry,
+        }
+    }
+
+    pub fn new(
+        global: &GlobalScope,
+        object_store: &IDBObjectStore,
+        info: &IndexInfo,
+    ) -> DomRoot<IDBIndex> {
+        reflect_dom_object(
+            Box::new(IDBIndex::new_inherited(object_store, info)),
+            global,
+        )
+    }
+
+    pub fn name(&self) -> String {
+        self.name.to_string()
+    }
+
+    pub fn object_store(&self) -> DomRoot<IDBObjectStore> {
+        DomRoot::from_ref(&*self.object_store)
+    }
+
+    /// Checks that neither the index nor its store were deleted, and that the
+    /// transaction is active.
+    pub fn check_active(&self) -> Fallible<()> {
+        let info = self.object_store.check_active()?;
+        if !info.indexes.iter().any(|index| index.name == *self.name) {
+            return Err(Error::InvalidState);
+        }
+        Ok(())
+    }
+
+    fn source(&self) -> IDBObjectStoreOrIDBIndexOrIDBCursor {
+        IDBObjectStoreOrIDBIndexOrIDBCursor::IDBIndex(DomRoot::from_ref(self))
+    }
+
+    fn get_records(
+        &self,
+        cx: JSContext,
+        query: HandleValue,
+        null_disallowed: bool,
+        count: Option<u32>,
+        kind: GetKind,
+    ) -> Fallible<DomRoot<IDBRequest>> {
+        self.check_active()?;
+        let range = convert_value_to_key_range(cx, query, null_disallowed)?;
+        Ok(self
+            .object_store
+            .get_records(self.source(), Some(self.name()), range, count, kind))
+    }
+
+    fn open_cursor(
+        &self,
+        cx: JSContext,
+        query: HandleValue,
+        direction: IDBCursorDirection,
+        key_only: bool,
+    ) -> Fallible<DomRoot<IDBRequest>> {
+        self.check_active()?;
+        let range = convert_value_to_key_range(cx, query, false)?;
+        let direction = cursor_direction_from_idl(direction);
+        Ok(self
+            .object_store
+            .open_cursor(Some(self), range, direction, key_only))
+    }
+}
+
+impl IDBIndexMethods for IDBIndex {
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-name
+    fn Name(&self) -> DOMString {
+        self.name.clone()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-objectstore
+    fn ObjectStore(&self) -> DomRoot<IDBObjectStore> {
+        self.object_store()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-keypath
+    fn KeyPath(&self, cx: JSContext) -> JSVal {
+        rooted!(in(*cx) let mut key_path = UndefinedValue());
+        key_path_to_jsval(cx, &self.key_path, key_path.handle_mut());
+        key_path.get()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-multientry
+    fn MultiEntry(&self) -> bool {
+        self.multi_entry
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-unique
+    fn Unique(&self) -> bool {
+        self.unique
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-get
+    fn Get(&self, cx: JSContext, query: HandleValue) -> Fallible<DomRoot<IDBRequest>> {
+        self.get_records(cx, query, true, Some(1), GetKind::Value)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-getkey
+    fn GetKey(&self, cx: JSContext, query: HandleValue) -> Fallible<DomRoot<IDBRequest>> {
+        self.get_records(cx, query, true, Some(1), GetKind::Key)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-getall
+    fn GetAll(
+        &self,
+        cx: JSContext,
+        query: HandleValue,
+        count: Option<u32>,
+    ) -> Fallible<DomRoot<IDBRequest>> {
+        let count = count.filter(|count| *count > 0);
+        self.get_records(cx, query, false, count, GetKind::AllValues)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-getallkeys
+    fn GetAllKeys(
+        &self,
+        cx: JSContext,
+        query: HandleValue,
+        count: Option<u32>,
+    ) -> Fallible<DomRoot<IDBRequest>> {
+        let count = count.filter(|count| *count > 0);
+        self.get_records(cx, query, false, count, GetKind::AllKeys)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-count
+    fn Count(&self, cx: JSContext, query: HandleValue) -> Fallible<DomRoot<IDBRequest>> {
+        self.check_active()?;
+        let range = convert_value_to_key_range(cx, query, false)?;
+        Ok(self
+            .object_store
+            .count_records(self.source(), Some(self.name()), range))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-opencursor
+    fn OpenCursor(
+        &self,
+        cx: JSContext,
+        query: HandleValue,
+        direction: IDBCursorDirection,
+    ) -> Fallible<DomRoot<IDBRequest>> {
+        self.open_cursor(cx, query, direction, false)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-openkeycursor
+    fn OpenKeyCursor(
+        &self,
+        cx: JSContext,
+        query: HandleValue,
+        direction: IDBCursorDirection,
+    ) -> Fallible<DomRoot<IDBRequest>> {
+        self.open_cursor(cx, query, direction, true)
+    }
+}
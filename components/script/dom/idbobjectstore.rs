@@ -0,0 +1,620 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::IDBCursorBinding::IDBCursorDirection;
+use crate::dom::bindings::codegen::Bindings::IDBObjectStoreBinding::{
+    IDBIndexParameters, IDBObjectStoreMethods,
+};
+use crate::dom::bindings::codegen::UnionTypes::IDBObjectStoreOrIDBIndexOrIDBCursor;
+use crate::dom::bindings::codegen::UnionTypes::StringOrStringSequence;
+use crate::dom::bindings::error::{Error, ErrorResult, Fallible};
+use crate::dom::bindings::refcounted::Trusted;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::domexception::{DOMErrorName, DOMException};
+use crate::dom::domstringlist::DOMStringList;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::idbcursor::IDBCursor;
+use crate::dom::idbindex::IDBIndex;
+use crate::dom::idbrequest::{IDBRequest, IDBRequestResult};
+use crate::dom::idbtransaction::IDBTransaction;
+use crate::indexed_db::{can_inject_key, convert_value_to_key, convert_value_to_key_range};
+use crate::indexed_db::{cursor_direction_from_idl, deserialize, extract_index_keys};
+use crate::indexed_db::{extract_key, inject_key, key_path_from_idl, key_path_to_jsval};
+use crate::indexed_db::{send_fallible_message, serialize};
+use crate::realms::enter_realm;
+use crate::script_runtime::JSContext;
+use dom_struct::dom_struct;
+use js::jsval::{JSVal, NullValue, UndefinedValue};
+use js::rust::HandleValue;
+use net_traits::indexeddb_thread::{IndexInfo, IndexedDBCursorDirection, IndexedDBKeyRange};
+use net_traits::indexeddb_thread::{IndexedDBKeyType, IndexedDBPut, IndexedDBQuery};
+use net_traits::indexeddb_thread::{IndexedDBRecord, IndexedDBThreadMsg};
+use net_traits::indexeddb_thread::{IndexedDBTransactionMode, KeyPath, ObjectStoreInfo};
+use std::collections::HashMap;
+
+/// What the `get*()` methods of object stores and indexes read.
+#[derive(Clone, Copy)]
+pub enum GetKind {
+    Value,
+    Key,
+    AllValues,
+    AllKeys,
+}
+
+// https://w3c.github.io/IndexedDB/#object-store-interface
+#[dom_struct]
+pub struct IDBObjectStore {
+    reflector_: Reflector,
+    name: DOMString,
+    transaction: Dom<IDBTransaction>,
+    key_path: Option<KeyPath>,
+    auto_increment: bool,
+    indexes: DomRefCell<HashMap<DOMString, Dom<IDBIndex>>>,
+}
+
+impl IDBObjectStore {
+    fn new_inherited(transaction: &IDBTransaction, info: &ObjectStoreInfo) -> IDBObjectStore {
+        IDBObjectStore {
+            reflector_: Reflector::new(),
+            name: DOMString::from(info.name.clone()),
+            transaction: Dom::from_ref(transaction),
+            key_path: info.key_path.clone(),
+            auto_increment: info.auto_increment,
+            indexes: Default::default(),
+        }
+    }
+
+    pub fn new(
+        global: &GlobalScope,
+        transaction: &IDBTransaction,
+        info: &ObjectStoreInfo,
+    ) -> DomRoot<IDBObjectStore> {
+        reflect_dom_object(
+            Box::new(IDBObjectStore::new_inherited(transaction, info)),
+            global,
+        )
+    }
+
+    pub fn name(&self) -> String {
+        self.name.to_string()
+    }
+
+    pub fn transaction(&self) -> DomRoot<IDBTransaction> {
+        DomRoot::from_ref(&*self.transaction)
+    }
+
+    pub fn key_path(&self) -> Option<&KeyPath> {
+        self.key_path.as_ref()
+    }
+
+    /// The metadata of the store, which is gone once the store is deleted.
+    pub fn info(&self) -> Fallible<ObjectStoreInfo> {
+        self.transaction
+            .db()
+            .object_store_info(&self.name)
+            .ok_or(Error::InvalidState)
+    }
+
+    pub fn check_active(&self) -> Fallible<ObjectStoreInfo> {
+        let info = self.info()?;
+        self.transaction.check_active()?;
+        Ok(info)
+    }
+
+    pub fn check_writable(&self) -> Fallible<ObjectStoreInfo> {
+        let info = self.check_active()?;
+        if self.transaction.mode() == IndexedDBTransactionMode::ReadOnly {
+            return Err(Error::ReadOnly);
+        }
+        Ok(info)
+    }
+
+    fn source(&self) -> IDBObjectStoreOrIDBIndexOrIDBCursor {
+        IDBObjectStoreOrIDBIndexOrIDBCursor::IDBObjectStore(DomRoot::from_ref(self))
+    }
+
+    /// Checks that the structure of the store can be changed.
+    fn check_upgrade_transaction(&self) -> Fallible<ObjectStoreInfo> {
+        if self.transaction.mode() != IndexedDBTransactionMode::VersionChange {
+            return Err(Error::InvalidState);
+        }
+        self.check_active()
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#add-or-put>
+    pub fn put(
+        &self,
+        cx: JSContext,
+        value: HandleValue,
+        key: Option<IndexedDBKeyType>,
+        no_overwrite: bool,
+        source: IDBObjectStoreOrIDBIndexOrIDBCursor,
+    ) -> Fallible<DomRoot<IDBRequest>> {
+        // Steps 4-7.
+        let info = self.check_writable()?;
+        // Steps 8-9.
+        if self.key_path.is_some() && key.is_some() {
+            return Err(Error::Data);
+        }
+        if self.key_path.is_none() && !self.auto_increment && key.is_none() {
+            return Err(Error::Data);
+        }
+        // Steps 11-12.
+        let global = self.global();
+        let serialized = serialize(cx, value)?;
+        rooted!(in(*cx) let mut clone = UndefinedValue());
+        deserialize(&global, serialized.clone(), clone.handle_mut())?;
+        // Step 13.
+        let mut key = key;
+        let mut inject_path = None;
+        if let Some(ref key_path) = self.key_path {
+            match extract_key(cx, clone.handle(), key_path)? {
+                Some(extracted) => key = Some(extracted),
+                None => match *key_path {
+                    KeyPath::String(ref path) if self.auto_increment => {
+                        if !can_inject_key(cx, clone.handle(), path)? {
+                            return Err(Error::Data);
+                        }
+                        inject_path = Some(path.clone());
+                    },
+                    _ => return Err(Error::Data),
+                },
+            }
+        }
+        // Step 14.
+        let store = Trusted::new(self);
+        let indexes = info.indexes;
+        Ok(IDBRequest::execute(source, &self.transaction, move || {
+            store
+                .root()
+                .store_record(&indexes, serialized, key, inject_path, no_overwrite)
+        }))
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#store-a-record-into-an-object-store>
+    fn store_record(
+        &self,
+        indexes: &[IndexInfo],
+        serialized: Vec<u8>,
+        key: Option<IndexedDBKeyType>,
+        inject_path: Option<String>,
+        no_overwrite: bool,
+    ) -> Fallible<IDBRequestResult> {
+        let global = self.global();
+        let _ac = enter_realm(&*global);
+        let cx = global.get_cx();
+        rooted!(in(*cx) let mut value = UndefinedValue());
+        deserialize(&global, serialized.clone(), value.handle_mut())?;
+        let id = self.transaction.id();
+        let (key, serialized) = match inject_path {
+            // In-line keys are generated here, to be injected into the value.
+            Some(path) => {
+                let generated = send_fallible_message(&global, |sender| {
+                    IndexedDBThreadMsg::GenerateKey(sender, id, self.name())
+                })?;
+                inject_key(cx, value.handle(), &generated, &path)?;
+                (Some(generated), serialize(cx, value.handle())?)
+            },
+            None => (key, serialized),
+        };
+        let mut index_keys = vec![];
+        for index in indexes {
+            let keys = extract_index_keys(cx, value.handle(), &index.key_path, index.multi_entry)?;
+            index_keys.push((index.name.clone(), keys));
+        }
+        let put = IndexedDBPut {
+            object_store: self.name(),
+            key: key,
+            value: serialized,
+            index_keys: index_keys,
+            no_overwrite: no_overwrite,
+        };
+        send_fallible_message(&global, |sender| IndexedDBThreadMsg::Put(sender, id, put))
+            .map(|key| IDBRequestResult::Key(Some(key)))
+    }
+
+    fn put_with_key(
+        &self,
+        cx: JSContext,
+        value: HandleValue,
+        key: HandleValue,
+        no_overwrite: bool,
+    ) -> Fallible<DomRoot<IDBRequest>> {
+        let key = if key.is_undefined() {
+            None
+        } else {
+            Some(convert_value_to_key(cx, key)?)
+        };
+        self.put(cx, value, key, no_overwrite, self.source())
+    }
+
+    /// Deletes the records within `range`, for `delete()` of stores and cursors.
+    pub fn delete_range(
+        &self,
+        range: IndexedDBKeyRange,
+        source: IDBObjectStoreOrIDBIndexOrIDBCursor,
+    ) -> DomRoot<IDBRequest> {
+        let store = Trusted::new(self);
+        IDBRequest::execute(source, &self.transaction, move || {
+            let store = store.root();
+            let id = store.transaction.id();
+            send_fallible_message(&store.global(), |sender| {
+                IndexedDBThreadMsg::Delete(sender, id, store.name(), range)
+            })
+            .map(|()| IDBRequestResult::Undefined)
+        })
+    }
+
+    pub fn query(&self, query: IndexedDBQuery) -> Fallible<Vec<IndexedDBRecord>> {
+        let id = self.transaction.id();
+        send_fallible_message(&self.global(), |sender| {
+            IndexedDBThreadMsg::Query(sender, id, query)
+        })
+    }
+
+    /// Reads records of the store, or of one of its indexes, for the `get*()` methods.
+    pub fn get_records(
+        &self,
+        source: IDBObjectStoreOrIDBIndexOrIDBCursor,
+        index: Option<String>,
+        range: IndexedDBKeyRange,
+        count: Option<u32>,
+        kind: GetKind,
+    ) -> DomRoot<IDBRequest> {
+        let query = IndexedDBQuery {
+            object_store: self.name(),
+            index: index,
+            range: range,
+            direction: IndexedDBCursorDirection::Next,
+            position: None,
+            count: count,
+            with_values: match kind {
+                GetKind::Value | GetKind::AllValues => true,
+                GetKind::Key | GetKind::AllKeys => false,
+            },
+        };
+        let store = Trusted::new(self);
+        IDBRequest::execute(source, &self.transaction, move || {
+            store.root().query(query).map(|records| {
+                let mut records = records.into_iter();
+                match kind {
+                    GetKind::Value => {
+                        IDBRequestResult::Value(records.next().and_then(|record| record.value))
+                    },
+                    GetKind::Key => {
+                        IDBRequestResult::Key(records.next().map(|record| record.primary_key))
+                    },
+                    GetKind::AllValues => IDBRequestResult::Values(
+                        records.filter_map(|record| record.value).collect(),
+                    ),
+                    GetKind::AllKeys => {
+                        IDBRequestResult::Keys(records.map(|record| record.primary_key).collect())
+                    },
+                }
+            })
+        })
+    }
+
+    /// Counts records of the store, or of one of its indexes.
+    pub fn count_records(
+        &self,
+        source: IDBObjectStoreOrIDBIndexOrIDBCursor,
+        index: Option<String>,
+        range: IndexedDBKeyRange,
+    ) -> DomRoot<IDBRequest> {
+        let store = Trusted::new(self);
+        IDBRequest::execute(source, &self.transaction, move || {
+            let store = store.root();
+            let id = store.transaction.id();
+            send_fallible_message(&store.global(), |sender| {
+                IndexedDBThreadMsg::Count(sender, id, store.name(), index, range)
+            })
+            .map(IDBRequestResult::Count)
+        })
+    }
+
+    /// Opens a cursor over the store, or over one of its indexes.
+    pub fn open_cursor(
+        &self,
+        index: Option<&IDBIndex>,
+        range: IndexedDBKeyRange,
+        direction: IndexedDBCursorDirection,
+        key_only: bool,
+    ) -> DomRoot<IDBRequest> {
+        let global = self.global();
+        let source = match index {
+            Some(index) => IDBObjectStoreOrIDBIndexOrIDBCursor::IDBIndex(DomRoot::from_ref(index)),
+            None => self.source(),
+        };
+        let request = IDBRequest::new(&global, source);
+        let cursor = IDBCursor::new(&global, self, index, range, direction, &request, key_only);
+        request.set_cursor(&cursor);
+        let trusted = Trusted::new(&*cursor);
+        request.run(&self.transaction, move || trusted.root().iterate(None, 1));
+        request
+    }
+
+    /// Gets the records of the store with their index keys, to fill a new index.
+    fn index_keys(
+        &self,
+        index: &IndexInfo,
+    ) -> Fallible<Vec<(IndexedDBKeyType, Vec<IndexedDBKeyType>)>> {
+        let global = self.global();
+        let cx = global.get_cx();
+        let records = self.query(IndexedDBQuery {
+            object_store: self.name(),
+            index: None,
+            range: IndexedDBKeyRange::default(),
+            direction: IndexedDBCursorDirection::Next,
+            position: None,
+            count: None,
+            with_values: true,
+        })?;
+        let mut keys = vec![];
+        for record in records {
+            rooted!(in(*cx) let mut value = UndefinedValue());
+            deserialize(
+                &global,
+                record.value.unwrap_or_default(),
+                value.handle_mut(),
+            )?;
+            let index_keys =
+                extract_index_keys(cx, value.handle(), &index.key_path, index.multi_entry)?;
+            keys.push((record.primary_key, index_keys));
+        }
+        Ok(keys)
+    }
+}
+
+impl IDBObjectStoreMethods for IDBObjectStore {
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-name
+    fn Name(&self) -> DOMString {
+        self.name.clone()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-keypath
+    fn KeyPath(&self, cx: JSContext) -> JSVal {
+        rooted!(in(*cx) let mut key_path = NullValue());
+        if let Some(ref path) = self.key_path {
+            key_path_to_jsval(cx, path, key_path.handle_mut());
+        }
+        key_path.get()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-indexnames
+    fn IndexNames(&self) -> DomRoot<DOMStringList> {
+        let mut names: Vec<_> = self
+            .info()
+            .map(|info| info.indexes)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|index| DOMString::from(index.name))
+            .collect();
+        names.sort();
+        DOMStringList::new(&self.global(), names)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-transaction
+    fn Transaction(&self) -> DomRoot<IDBTransaction> {
+        self.transaction()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-autoincrement
+    fn AutoIncrement(&self) -> bool {
+        self.auto_increment
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-put
+    fn Put(
+        &self,
+        cx: JSContext,
+        value: HandleValue,
+        key: HandleValue,
+    ) -> Fallible<DomRoot<IDBRequest>> {
+        self.put_with_key(cx, value, key, false)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-add
+    fn Add(
+        &self,
+        cx: JSContext,
+        value: HandleValue,
+        key: HandleValue,
+    ) -> Fallible<DomRoot<IDBRequest>> {
+        self.put_with_key(cx, value, key, true)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-delete
+    fn Delete(&self, cx: JSContext, query: HandleValue) -> Fallible<DomRoot<IDBRequest>> {
+        self.check_writable()?;
+        let range = convert_value_to_key_range(cx, query, true)?;
+        Ok(self.delete_range(range, self.source()))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-clear
+    fn Clear(&self) -> Fallible<DomRoot<IDBRequest>> {
+        self.check_writable()?;
+        let store = Trusted::new(self);
+        Ok(IDBRequest::execute(
+            self.source(),
+            &self.transaction,
+            move || {
+                let store = store.root();
+                let id = store.transaction.id();
+                send_fallible_message(&store.global(), |sender| {
+                    IndexedDBThreadMsg::Clear(sender, id, store.name())
+                })
+                .map(|()| IDBRequestResult::Undefined)
+            },
+        ))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-get
+    fn Get(&self, cx: JSContext, query: HandleValue) -> Fallible<DomRoot<IDBRequest>> {
+        self.check_active()?;
+        let range = convert_value_to_key_range(cx, query, true)?;
+        Ok(self.get_records(self.source(), None, range, Some(1), GetKind::Value))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-getkey
+    fn GetKey(&self, cx: JSContext, query: HandleValue) -> Fallible<DomRoot<IDBRequest>> {
+        self.check_active()?;
+        let range = convert_value_to_key_range(cx, query, true)?;
+        Ok(self.get_records(self.source(), None, range, Some(1), GetKind::Key))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-getall
+    fn GetAll(
+        &self,
+        cx: JSContext,
+        query: HandleValue,
+        count: Option<u32>,
+    ) -> Fallible<DomRoot<IDBRequest>> {
+        self.check_active()?;
+        let range = convert_value_to_key_range(cx, query, false)?;
+        let count = count.filter(|count| *count > 0);
+        Ok(self.get_records(self.source(), None, range, count, GetKind::AllValues))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-getallkeys
+    fn GetAllKeys(
+        &self,
+        cx: JSContext,
+        query: HandleValue,
+        count: Option<u32>,
+    ) -> Fallible<DomRoot<IDBRequest>> {
+        self.check_active()?;
+        let range = convert_value_to_key_range(cx, query, false)?;
+        let count = count.filter(|count| *count > 0);
+        Ok(self.get_records(self.source(), None, range, count, GetKind::AllKeys))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-count
+    fn Count(&self, cx: JSContext, query: HandleValue) -> Fallible<DomRoot<IDBRequest>> {
+        self.check_active()?;
+        let range = convert_value_to_key_range(cx, query, false)?;
+        Ok(self.count_records(self.source(), None, range))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-opencursor
+    fn OpenCursor(
+        &self,
+        cx: JSContext,
+        query: HandleValue,
+        direction: IDBCursorDirection,
+    ) -> Fallible<DomRoot<IDBRequest>> {
+        self.check_active()?;
+        let range = convert_value_to_key_range(cx, query, false)?;
+        let direction = cursor_direction_from_idl(direction);
+        Ok(self.open_cursor(None, range, direction, false))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-openkeycursor
+    fn OpenKeyCursor(
+        &self,
+        cx: JSContext,
+        query: HandleValue,
+        direction: IDBCursorDirection,
+    ) -> Fallible<DomRoot<IDBRequest>> {
+        self.check_active()?;
+        let range = convert_value_to_key_range(cx, query, false)?;
+        let direction = cursor_direction_from_idl(direction);
+        Ok(self.open_cursor(None, range, direction, true))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-index
+    fn Index(&self, name: DOMString) -> Fallible<DomRoot<IDBIndex>> {
+        let info = self.info()?;
+        if self.transaction.is_finished() {
+            return Err(Error::InvalidState);
+        }
+        let index = info
+            .indexes
+            .iter()
+            .find(|index| index.name == *name)
+            .ok_or(Error::NotFound)?;
+        if let Some(index) = self.indexes.borrow().get(&name) {
+            return Ok(DomRoot::from_ref(&**index));
+        }
+        let index = IDBIndex::new(&self.global(), self, index);
+        self.indexes
+            .borrow_mut()
+            .insert(name, Dom::from_ref(&*index));
+        Ok(index)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-createindex
+    fn CreateIndex(
+        &self,
+        name: DOMString,
+        key_path: StringOrStringSequence,
+        options: &IDBIndexParameters,
+    ) -> Fallible<DomRoot<IDBIndex>> {
+        // Steps 2-6.
+        let mut info = self.check_upgrade_transaction()?;
+        // Step 7.
+        if info.indexes.iter().any(|index| index.name == *name) {
+            return Err(Error::Constraint);
+        }
+        // Steps 8-10.
+        let key_path = key_path_from_idl(&key_path)?;
+        if let KeyPath::Sequence(_) = key_path {
+            if options.multiEntry {
+                return Err(Error::InvalidAccess);
+            }
+        }
+        // Steps 11-13.
+        let index = IndexInfo {
+            name: name.to_string(),
+            key_path: key_path,
+            unique: options.unique,
+            multi_entry: options.multiEntry,
+        };
+        let keys = self.index_keys(&index)?;
+        let global = self.global();
+        let id = self.transaction.id();
+        let message_index = index.clone();
+        let result = send_fallible_message(&global, |sender| {
+            IndexedDBThreadMsg::CreateIndex(sender, id, self.name(), message_index, keys)
+        });
+        info.indexes.push(index.clone());
+        self.transaction.db().set_object_store_info(info);
+        match result {
+            Ok(()) => {},
+            // Records violating a unique index abort the transaction, rather than throw.
+            Err(Error::Constraint) => self.transaction.abort_with(Some(&DOMException::new(
+                &global,
+                DOMErrorName::ConstraintError,
+            ))),
+            Err(error) => return Err(error),
+        }
+        let index = IDBIndex::new(&global, self, &index);
+        self.indexes
+            .borrow_mut()
+            .insert(name, Dom::from_ref(&*index));
+        Ok(index)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-deleteindex
+    fn DeleteIndex(&self, name: DOMString) -> ErrorResult {
+        // Steps 2-6.
+        let mut info = self.check_upgrade_transaction()?;
+        // Step 7.
+        if !info.indexes.iter().any(|index| index.name == *name) {
+            return Err(Error::NotFound);
+        }
+        // Steps 8-9.
+        let id = self.transaction.id();
+        send_fallible_message(&self.global(), |sender| {
+            IndexedDBThreadMsg::DeleteIndex(sender, id, self.name(), name.to_string())
+        })?;
+        info.indexes.retain(|index| index.name != *name);
+        self.transaction.db().set_object_store_info(info);
+        self.indexes.borrow_mut().remove(&name);
+        Ok(())
+    }
+}
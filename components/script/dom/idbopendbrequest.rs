@@ -0,0 +1,222 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::codegen::Bindings::IDBOpenDBRequestBinding::IDBOpenDBRequestMethods;
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::refcounted::Trusted;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject};
+use crate::dom::bindings::root::{DomRoot, MutNullableDom};
+use crate::dom::domexception::{DOMErrorName, DOMException};
+use crate::dom::event::{Event, EventBubbles, EventCancelable};
+use crate::dom::eventtarget::EventTarget;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::idbdatabase::IDBDatabase;
+use crate::dom::idbrequest::IDBRequest;
+use crate::dom::idbtransaction::IDBTransaction;
+use crate::dom::idbversionchangeevent::IDBVersionChangeEvent;
+use crate::indexed_db::{post_message, route_events, send_fallible_message, send_message};
+use crate::task_source::TaskSource;
+use dom_struct::dom_struct;
+use ipc_channel::ipc;
+use js::jsval::{ObjectValue, UndefinedValue};
+use net_traits::indexeddb_thread::IndexedDBTransactionMode;
+use net_traits::indexeddb_thread::{IndexedDBScheduleEvent, IndexedDBThreadMsg};
+use std::cell::Cell;
+use std::cmp;
+
+// https://w3c.github.io/IndexedDB/#idbopendbrequest
+#[dom_struct]
+pub struct IDBOpenDBRequest {
+    request: IDBRequest,
+    /// The connection an upgrade is waiting to start for, or none for a deletion.
+    connection: MutNullableDom<IDBDatabase>,
+    /// The id of the waiting upgrade transaction.
+    transaction_id: Cell<u64>,
+    /// The version the database is upgraded to, or none for a deletion.
+    new_version: Cell<Option<u64>>,
+}
+
+impl IDBOpenDBRequest {
+    fn new_inherited() -> IDBOpenDBRequest {
+        IDBOpenDBRequest {
+            request: IDBRequest::new_inherited(None),
+            connection: Default::default(),
+            transaction_id: Cell::new(0),
+            new_version: Cell::new(None),
+        }
+    }
+
+    pub fn new(global: &GlobalScope) -> DomRoot<IDBOpenDBRequest> {
+        reflect_dom_object(Box::new(IDBOpenDBRequest::new_inherited()), global)
+    }
+
+    pub fn queue_open(&self, name: String, version: Option<u64>) {
+        let this = Trusted::new(self);
+        let global = self.global();
+        let _ = global.dom_manipulation_task_source().queue(
+            task!(idb_open_database: move || {
+                this.root().open_database(name, version);
+            }),
+            &global,
+        );
+    }
+
+    pub fn queue_delete(&self, name: String) {
+        let this = Trusted::new(self);
+        let global = self.global();
+        let _ = global.dom_manipulation_task_source().queue(
+            task!(idb_delete_database: move || {
+                this.root().delete_database(name);
+            }),
+            &global,
+        );
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#open-a-database>
+    fn open_database(&self, name: String, version: Option<u64>) {
+        let global = self.global();
+        let origin = global.origin().immutable().clone();
+        let (events_sender, events) = ipc::channel().unwrap();
+        let (id, info) = send_message(&global, |sender| {
+            IndexedDBThreadMsg::Open(sender, events_sender, origin, name)
+        });
+
+        // Steps 5-6.
+        let old_version = info.version;
+        let version = version.unwrap_or(cmp::max(old_version, 1));
+        if version < old_version {
+            post_message(&global, IndexedDBThreadMsg::Close(id));
+            self.request
+                .set_error(&DOMException::new(&global, DOMErrorName::VersionError));
+            self.request.fire_error_event();
+            return;
+        }
+
+        // Step 7.
+        let connection = IDBDatabase::new(&global, id, info, events);
+        if version == old_version {
+            let result = ObjectValue(connection.reflector().get_jsobject().get());
+            self.request.set_result(result);
+            self.upcast::<EventTarget>().fire_event(atom!("success"));
+            return;
+        }
+
+        // Step 10: https://w3c.github.io/IndexedDB/#run-an-upgrade-transaction
+        // The engine tells the other connections about the upgrade, and starts it once they
+        // are closed.
+        let (events_sender, events) = ipc::channel().unwrap();
+        let transaction_id = match send_fallible_message(&global, |sender| {
+            IndexedDBThreadMsg::Upgrade(sender, events_sender, id, version)
+        }) {
+            Ok(transaction_id) => transaction_id,
+            Err(error) => {
+                warn!("Failed to upgrade an IndexedDB database: {:?}", error);
+                return;
+            },
+        };
+        self.connection.set(Some(&connection));
+        self.transaction_id.set(transaction_id);
+        self.new_version.set(Some(version));
+        route_events(self, events, IDBOpenDBRequest::handle_schedule_event);
+    }
+
+    fn handle_schedule_event(&self, event: IndexedDBScheduleEvent) {
+        match event {
+            IndexedDBScheduleEvent::Blocked(old_version) => {
+                let event = IDBVersionChangeEvent::new(
+                    &self.global(),
+                    atom!("blocked"),
+                    EventBubbles::DoesNotBubble,
+                    EventCancelable::NotCancelable,
+                    old_version,
+                    self.new_version.get(),
+                );
+                event.upcast::<Event>().fire(self.upcast());
+            },
+            IndexedDBScheduleEvent::Started(old_version) => match self.connection.take() {
+                Some(connection) => self.run_upgrade(&connection, old_version),
+                None => self.database_deleted(old_version),
+            },
+        }
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#run-an-upgrade-transaction>
+    fn run_upgrade(&self, connection: &IDBDatabase, old_version: u64) {
+        let global = self.global();
+        let version = self.new_version.get().unwrap_or(old_version);
+        let transaction = IDBTransaction::new(
+            &global,
+            connection,
+            self.transaction_id.get(),
+            IndexedDBTransactionMode::VersionChange,
+            vec![],
+            Some(self),
+        );
+        transaction.start();
+        connection.begin_upgrade(&transaction, version);
+        let result = ObjectValue(connection.reflector().get_jsobject().get());
+        self.request.set_result(result);
+        self.request.set_transaction(Some(&transaction));
+
+        let event = IDBVersionChangeEvent::new(
+            &global,
+            atom!("upgradeneeded"),
+            EventBubbles::DoesNotBubble,
+            EventCancelable::NotCancelable,
+            old_version,
+            Some(version),
+        );
+        event.upcast::<Event>().fire(self.upcast());
+        transaction.set_inactive();
+        transaction.maybe_commit();
+    }
+
+    /// Called once the upgrade transaction of the request committed.
+    pub fn upgrade_finished(&self) {
+        self.request.set_transaction(None);
+        self.upcast::<EventTarget>().fire_event(atom!("success"));
+    }
+
+    /// Called once the upgrade transaction of the request aborted.
+    pub fn upgrade_aborted(&self) {
+        let global = self.global();
+        self.request.set_transaction(None);
+        self.request
+            .set_error(&DOMException::new(&global, DOMErrorName::AbortError));
+        self.request.fire_error_event();
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#delete-a-database>
+    fn delete_database(&self, name: String) {
+        let global = self.global();
+        let origin = global.origin().immutable().clone();
+        let (events_sender, events) = ipc::channel().unwrap();
+        route_events(self, events, IDBOpenDBRequest::handle_schedule_event);
+        post_message(
+            &global,
+            IndexedDBThreadMsg::DeleteDatabase(events_sender, origin, name),
+        );
+    }
+
+    fn database_deleted(&self, old_version: u64) {
+        self.request.set_result(UndefinedValue());
+        let event = IDBVersionChangeEvent::new(
+            &self.global(),
+            atom!("success"),
+            EventBubbles::DoesNotBubble,
+            EventCancelable::NotCancelable,
+            old_version,
+            None,
+        );
+        event.upcast::<Event>().fire(self.upcast());
+    }
+}
+
+impl IDBOpenDBRequestMethods for IDBOpenDBRequest {
+    // https://w3c.github.io/IndexedDB/#dom-idbopendbrequest-onblocked
+    event_handler!(blocked, GetOnblocked, SetOnblocked);
+
+    // https://w3c.github.io/IndexedDB/#dom-idbopendbrequest-onupgradeneeded
+    event_handler!(upgradeneeded, GetOnupgradeneeded, SetOnupgradeneeded);
+}
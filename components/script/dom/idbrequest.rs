@@ -0,0 +1,304 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::codegen::Bindings::EventBinding::EventMethods;
+use crate::dom::bindings::codegen::Bindings::IDBRequestBinding::{
+    IDBRequestMethods, IDBRequestReadyState,
+};
+use crate::dom::bindings::codegen::UnionTypes::IDBObjectStoreOrIDBIndexOrIDBCursor;
+use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::refcounted::Trusted;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject};
+use crate::dom::bindings::root::{Dom, DomRoot, MutNullableDom};
+use crate::dom::domexception::{DOMErrorName, DOMException};
+use crate::dom::eventtarget::EventTarget;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::idbcursor::IDBCursor;
+use crate::dom::idbindex::IDBIndex;
+use crate::dom::idbobjectstore::IDBObjectStore;
+use crate::dom::idbtransaction::IDBTransaction;
+use crate::indexed_db::{deserialize, error_to_dom_exception, key_to_jsval, new_array};
+use crate::realms::enter_realm;
+use crate::script_runtime::JSContext;
+use crate::task_source::TaskSource;
+use dom_struct::dom_struct;
+use js::jsapi::Heap;
+use js::jsval::{DoubleValue, JSVal, NullValue, ObjectValue, UndefinedValue};
+use net_traits::indexeddb_thread::{IndexedDBKeyType, IndexedDBRecord};
+use std::cell::Cell;
+use std::mem;
+
+#[unrooted_must_root_lint::must_root]
+#[derive(JSTraceable, MallocSizeOf)]
+enum IDBRequestSource {
+    ObjectStore(Dom<IDBObjectStore>),
+    Index(Dom<IDBIndex>),
+    Cursor(Dom<IDBCursor>),
+}
+
+/// The outcome of the operation of a request, converted to a JavaScript value when the
+/// request completes.
+pub enum IDBRequestResult {
+    Undefined,
+    Key(Option<IndexedDBKeyType>),
+    Keys(Vec<IndexedDBKeyType>),
+    /// A serialized value.
+    Value(Option<Vec<u8>>),
+    Values(Vec<Vec<u8>>),
+    Count(u64),
+    /// The record the cursor of the request moved to, or none if it reached the end.
+    Cursor(Option<IndexedDBRecord>),
+}
+
+// https://w3c.github.io/IndexedDB/#request-api
+#[dom_struct]
+pub struct IDBRequest {
+    eventtarget: EventTarget,
+    #[ignore_malloc_size_of = "Defined in rust-mozjs"]
+    result: Heap<JSVal>,
+    error: MutNullableDom<DOMException>,
+    source: Option<IDBRequestSource>,
+    transaction: MutNullableDom<IDBTransaction>,
+    done: Cell<bool>,
+    /// The cursor of the `openCursor()` and `openKeyCursor()` requests.
+    cursor: MutNullableDom<IDBCursor>,
+}
+
+impl IDBRequest {
+    #[allow(unrooted_must_root)]
+    pub fn new_inherited(source: Option<IDBObjectStoreOrIDBIndexOrIDBCursor>) -> IDBRequest {
+        let source = source.map(|source| match source {
+            IDBObjectStoreOrIDBIndexOrIDBCursor::IDBObjectStore(store) => {
+                IDBRequestSource::ObjectStore(Dom::from_ref(&*store))
+            },
+            IDBObjectStoreOrIDBIndexOrIDBCursor::IDBIndex(index) => {
+                IDBRequestSource::Index(Dom::from_ref(&*index))
+            },
+            IDBObjectStoreOrIDBIndexOrIDBCursor::IDBCursor(cursor) => {
+                IDBRequestSource::Cursor(Dom::from_ref(&*cursor))
+            },
+        });
+        IDBRequest {
+            eventtarget: EventTarget::new_inherited(),
+            result: Heap::default(),
+            error: Default::default(),
+            source: source,
+            transaction: Default::default(),
+            done: Cell::new(false),
+            cursor: Default::default(),
+        }
+    }
+
+    pub fn new(
+        global: &GlobalScope,
+        source: IDBObjectStoreOrIDBIndexOrIDBCursor,
+    ) -> DomRoot<IDBRequest> {
+        reflect_dom_object(Box::new(IDBRequest::new_inherited(Some(source))), global)
+    }
+
+    /// Creates a request for an operation of `transaction`.
+    pub fn execute<F>(
+        source: IDBObjectStoreOrIDBIndexOrIDBCursor,
+        transaction: &IDBTransaction,
+        operation: F,
+    ) -> DomRoot<IDBRequest>
+    where
+        F: FnOnce() -> Fallible<IDBRequestResult> + 'static,
+    {
+        let request = IDBRequest::new(&transaction.global(), source);
+        request.run(transaction, operation);
+        request
+    }
+
+    /// Runs the operation of the request once its transaction has started, and completes the
+    /// request with its result. Cursors reuse their request each time they iterate.
+    pub fn run<F>(&self, transaction: &IDBTransaction, operation: F)
+    where
+        F: FnOnce() -> Fallible<IDBRequestResult> + 'static,
+    {
+        self.done.set(false);
+        self.transaction.set(Some(transaction));
+        transaction.add_request();
+        transaction.schedule(self, Box::new(operation));
+    }
+
+    /// Queues the completion of the request with the result of its operation.
+    pub fn queue_completion(&self, result: Fallible<IDBRequestResult>) {
+        let this = Trusted::new(self);
+        let global = self.global();
+        let _ = global.dom_manipulation_task_source().queue(
+            task!(idb_request_complete: move || {
+                this.root().complete(result);
+            }),
+            &global,
+        );
+    }
+
+    pub fn get_transaction(&self) -> Option<DomRoot<IDBTransaction>> {
+        self.transaction.get()
+    }
+
+    pub fn set_transaction(&self, transaction: Option<&IDBTransaction>) {
+        self.transaction.set(transaction);
+    }
+
+    pub fn set_cursor(&self, cursor: &IDBCursor) {
+        self.cursor.set(Some(cursor));
+    }
+
+    /// Marks the request as done with `value` as its result, without firing events.
+    pub fn set_result(&self, value: JSVal) {
+        self.done.set(true);
+        self.error.set(None);
+        self.result.set(value);
+    }
+
+    /// Marks the request as done with `error`, without firing events.
+    pub fn set_error(&self, error: &DOMException) {
+        self.done.set(true);
+        self.error.set(Some(error));
+        self.result.set(UndefinedValue());
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#fire-an-error-event>
+    pub fn fire_error_event(&self) -> bool {
+        let event = self
+            .upcast::<EventTarget>()
+            .fire_bubbling_cancelable_event(atom!("error"));
+        !event.DefaultPrevented()
+    }
+
+    /// Converts the result of an operation to a JavaScript value.
+    fn convert_result(&self, result: IDBRequestResult) -> Fallible<JSVal> {
+        let global = self.global();
+        let _ac = enter_realm(&*global);
+        let cx = global.get_cx();
+        rooted!(in(*cx) let mut value = UndefinedValue());
+        match result {
+            IDBRequestResult::Undefined |
+            IDBRequestResult::Key(None) |
+            IDBRequestResult::Value(None) => {},
+            IDBRequestResult::Key(Some(key)) => key_to_jsval(cx, &key, value.handle_mut()),
+            IDBRequestResult::Keys(keys) => {
+                new_array(cx, keys.len(), value.handle_mut(), |index, element| {
+                    key_to_jsval(cx, &keys[index], element)
+                })
+            },
+            IDBRequestResult::Value(Some(serialized)) => {
+                deserialize(&global, serialized, value.handle_mut())?
+            },
+            IDBRequestResult::Values(mut values) => {
+                let mut result = Ok(());
+                new_array(cx, values.len(), value.handle_mut(), |index, element| {
+                    if result.is_ok() {
+                        result = deserialize(&global, mem::take(&mut values[index]), element);
+                    }
+                });
+                result?;
+            },
+            IDBRequestResult::Count(count) => value.set(DoubleValue(count as f64)),
+            IDBRequestResult::Cursor(record) => {
+                let cursor = self.cursor.get().expect("Cursor result without a cursor");
+                if cursor.set_record(record)? {
+                    value.set(ObjectValue(cursor.reflector().get_jsobject().get()));
+                } else {
+                    value.set(NullValue());
+                }
+            },
+        }
+        Ok(value.get())
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#asynchronously-execute-a-request>
+    fn complete(&self, result: Fallible<IDBRequestResult>) {
+        let transaction = self
+            .transaction
+            .get()
+            .expect("Request completed without a transaction");
+        let global = self.global();
+
+        // Requests of an aborted transaction fail with an AbortError.
+        if transaction.is_aborted() {
+            self.set_error(&DOMException::new(&global, DOMErrorName::AbortError));
+            self.fire_error_event();
+            transaction.request_finished();
+            return;
+        }
+
+        match result.and_then(|result| self.convert_result(result)) {
+            Ok(value) => {
+                self.set_result(value);
+                // https://w3c.github.io/IndexedDB/#fire-a-success-event
+                transaction.set_active();
+                self.upcast::<EventTarget>().fire_event(atom!("success"));
+                transaction.set_inactive();
+            },
+            Err(error) => {
+                let error = error_to_dom_exception(&global, error);
+                self.set_error(&error);
+                transaction.set_active();
+                let not_canceled = self.fire_error_event();
+                transaction.set_inactive();
+                if not_canceled {
+                    transaction.abort_with(Some(&error));
+                }
+            },
+        }
+        transaction.request_finished();
+    }
+}
+
+impl IDBRequestMethods for IDBRequest {
+    // https://w3c.github.io/IndexedDB/#dom-idbrequest-result
+    fn GetResult(&self, _cx: JSContext) -> Fallible<JSVal> {
+        if !self.done.get() {
+            return Err(Error::InvalidState);
+        }
+        Ok(self.result.get())
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbrequest-error
+    fn GetError(&self) -> Fallible<Option<DomRoot<DOMException>>> {
+        if !self.done.get() {
+            return Err(Error::InvalidState);
+        }
+        Ok(self.error.get())
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbrequest-source
+    fn GetSource(&self) -> Option<IDBObjectStoreOrIDBIndexOrIDBCursor> {
+        self.source.as_ref().map(|source| match *source {
+            IDBRequestSource::ObjectStore(ref store) => {
+                IDBObjectStoreOrIDBIndexOrIDBCursor::IDBObjectStore(DomRoot::from_ref(&**store))
+            },
+            IDBRequestSource::Index(ref index) => {
+                IDBObjectStoreOrIDBIndexOrIDBCursor::IDBIndex(DomRoot::from_ref(&**index))
+            },
+            IDBRequestSource::Cursor(ref cursor) => {
+                IDBObjectStoreOrIDBIndexOrIDBCursor::IDBCursor(DomRoot::from_ref(&**cursor))
+            },
+        })
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbrequest-transaction
+    fn GetTransaction(&self) -> Option<DomRoot<IDBTransaction>> {
+        self.transaction.get()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbrequest-readystate
+    fn ReadyState(&self) -> IDBRequestReadyState {
+        if self.done.get() {
+            IDBRequestReadyState::Done
+        } else {
+            IDBRequestReadyState::Pending
+        }
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbrequest-onsuccess
+    event_handler!(success, GetOnsuccess, SetOnsuccess);
+
+    // https://w3c.github.io/IndexedDB/#dom-idbrequest-onerror
+    event_handler!(error, GetOnerror, SetOnerror);
+}
@@ -0,0 +1,378 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::IDBTransactionBinding::{
+    IDBTransactionMethods, IDBTransactionMode,
+};
+use crate::dom::bindings::error::{Error, ErrorResult, Fallible};
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::refcounted::Trusted;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject};
+use crate::dom::bindings::root::{Dom, DomRoot, MutNullableDom};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::domexception::DOMException;
+use crate::dom::domstringlist::DOMStringList;
+use crate::dom::eventtarget::EventTarget;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::idbdatabase::IDBDatabase;
+use crate::dom::idbobjectstore::IDBObjectStore;
+use crate::dom::idbopendbrequest::IDBOpenDBRequest;
+use crate::dom::idbrequest::{IDBRequest, IDBRequestResult};
+use crate::indexed_db::{error_to_dom_exception, send_fallible_message, send_message};
+use crate::task_source::TaskSource;
+use dom_struct::dom_struct;
+use net_traits::indexeddb_thread::IndexedDBTransactionMode;
+use net_traits::indexeddb_thread::{IndexedDBScheduleEvent, IndexedDBThreadMsg};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::mem;
+
+/// <https://w3c.github.io/IndexedDB/#transaction-lifetime>
+#[derive(Clone, Copy, Debug, JSTraceable, MallocSizeOf, PartialEq)]
+enum TransactionState {
+    Active,
+    Inactive,
+    Committing,
+    Finished,
+}
+
+/// The operation of a request, run once its transaction has started.
+pub type IDBOperation = Box<dyn FnOnce() -> Fallible<IDBRequestResult>>;
+
+/// A request placed before its transaction started.
+struct PendingRequest {
+    request: Trusted<IDBRequest>,
+    operation: IDBOperation,
+}
+
+unsafe_no_jsmanaged_fields!(PendingRequest);
+
+// https://w3c.github.io/IndexedDB/#transaction
+#[dom_struct]
+pub struct IDBTransaction {
+    eventtarget: EventTarget,
+    db: Dom<IDBDatabase>,
+    /// The id of the transaction in the engine.
+    id: u64,
+    mode: IndexedDBTransactionMode,
+    /// The names of the object stores in the scope of a readonly or readwrite transaction.
+    scope: Vec<DOMString>,
+    state: Cell<TransactionState>,
+    /// Whether the engine has started the transaction, after the earlier transactions it
+    /// overlaps with finished.
+    started: Cell<bool>,
+    /// The requests placed before the transaction started, with their operations.
+    #[ignore_malloc_size_of = "Closures are hard"]
+    pending_operations: DomRefCell<Vec<PendingRequest>>,
+    aborted: Cell<bool>,
+    pending_requests: Cell<u32>,
+    error: MutNullableDom<DOMException>,
+    object_stores: DomRefCell<HashMap<DOMString, Dom<IDBObjectStore>>>,
+    /// The open request of an upgrade transaction.
+    open_request: MutNullableDom<IDBOpenDBRequest>,
+}
+
+impl IDBTransaction {
+    fn new_inherited(
+        db: &IDBDatabase,
+        id: u64,
+        mode: IndexedDBTransactionMode,
+        scope: Vec<DOMString>,
+        open_request: Option<&IDBOpenDBRequest>,
+    ) -> IDBTransaction {
+        IDBTransaction {
+            eventtarget: EventTarget::new_inherited(),
+            db: Dom::from_ref(db),
+            id: id,
+            mode: mode,
+            scope: scope,
+            state: Cell::new(TransactionState::Active),
+            started: Cell::new(false),
+            pending_operations: Default::default(),
+            aborted: Cell::new(false),
+            pending_requests: Cell::new(0),
+            error: Default::default(),
+            object_stores: Default::default(),
+            open_request: MutNullableDom::new(open_request),
+        }
+    }
+
+    pub fn new(
+        global: &GlobalScope,
+        db: &IDBDatabase,
+        id: u64,
+        mode: IndexedDBTransactionMode,
+        scope: Vec<DOMString>,
+        open_request: Option<&IDBOpenDBRequest>,
+    ) -> DomRoot<IDBTransaction> {
+        reflect_dom_object(
+            Box::new(IDBTransaction::new_inherited(
+                db,
+                id,
+                mode,
+                scope,
+                open_request,
+            )),
+            global,
+        )
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn db(&self) -> DomRoot<IDBDatabase> {
+        DomRoot::from_ref(&*self.db)
+    }
+
+    pub fn mode(&self) -> IndexedDBTransactionMode {
+        self.mode
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.state.get() == TransactionState::Active
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.state.get() == TransactionState::Finished
+    }
+
+    pub fn is_aborted(&self) -> bool {
+        self.aborted.get()
+    }
+
+    pub fn check_active(&self) -> ErrorResult {
+        if !self.is_active() {
+            return Err(Error::TransactionInactive);
+        }
+        Ok(())
+    }
+
+    pub fn set_active(&self) {
+        if self.state.get() == TransactionState::Inactive {
+            self.state.set(TransactionState::Active);
+        }
+    }
+
+    pub fn set_inactive(&self) {
+        if self.state.get() == TransactionState::Active {
+            self.state.set(TransactionState::Inactive);
+        }
+    }
+
+    /// A new transaction stays active until control returns to the event loop.
+    pub fn queue_deactivation(&self) {
+        let this = Trusted::new(self);
+        let global = self.global();
+        let _ = global.dom_manipulation_task_source().queue(
+            task!(idb_transaction_deactivate: move || {
+                let this = this.root();
+                this.set_inactive();
+                this.maybe_commit();
+            }),
+            &global,
+        );
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#transaction-scheduling>
+    pub fn handle_schedule_event(&self, event: IndexedDBScheduleEvent) {
+        match event {
+            IndexedDBScheduleEvent::Started(_) => self.start(),
+            IndexedDBScheduleEvent::Blocked(_) => {},
+        }
+    }
+
+    /// Runs the requests that were waiting for the transaction to start.
+    pub fn start(&self) {
+        if self.started.get() || self.is_finished() {
+            return;
+        }
+        self.started.set(true);
+        let pending = mem::take(&mut *self.pending_operations.borrow_mut());
+        for pending in pending {
+            let result = (pending.operation)();
+            pending.request.root().queue_completion(result);
+        }
+        self.maybe_commit();
+    }
+
+    /// Runs the operation of a request, or keeps it until the transaction starts.
+    pub fn schedule(&self, request: &IDBRequest, operation: IDBOperation) {
+        if self.started.get() {
+            return request.queue_completion(operation());
+        }
+        self.pending_operations.borrow_mut().push(PendingRequest {
+            request: Trusted::new(request),
+            operation: operation,
+        });
+    }
+
+    pub fn add_request(&self) {
+        self.pending_requests.set(self.pending_requests.get() + 1);
+    }
+
+    pub fn request_finished(&self) {
+        self.pending_requests.set(self.pending_requests.get() - 1);
+        self.maybe_commit();
+    }
+
+    /// Commits the transaction once it is inactive and all of its requests are done.
+    pub fn maybe_commit(&self) {
+        if self.aborted.get() || !self.started.get() || self.pending_requests.get() > 0 {
+            return;
+        }
+        match self.state.get() {
+            TransactionState::Inactive | TransactionState::Committing => self.commit(),
+            TransactionState::Active | TransactionState::Finished => {},
+        }
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#commit-a-transaction>
+    fn commit(&self) {
+        let global = self.global();
+        if let Err(error) = send_fallible_message(&global, |sender| {
+            IndexedDBThreadMsg::Commit(sender, self.id)
+        }) {
+            return self.abort_with(Some(&error_to_dom_exception(&global, error)));
+        }
+        self.state.set(TransactionState::Finished);
+
+        let this = Trusted::new(self);
+        let _ = global.dom_manipulation_task_source().queue(
+            task!(idb_transaction_complete: move || {
+                let this = this.root();
+                if this.mode == IndexedDBTransactionMode::VersionChange {
+                    this.db.upgrade_finished();
+                }
+                this.upcast::<EventTarget>().fire_event(atom!("complete"));
+                if let Some(request) = this.open_request.get() {
+                    request.upgrade_finished();
+                }
+            }),
+            &global,
+        );
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#abort-a-transaction>
+    pub fn abort_with(&self, error: Option<&DOMException>) {
+        if self.state.get() == TransactionState::Finished {
+            return;
+        }
+        let global = self.global();
+        send_message(&global, |sender| IndexedDBThreadMsg::Abort(sender, self.id));
+        self.state.set(TransactionState::Finished);
+        self.aborted.set(true);
+        self.error.set(error);
+        // Requests that never ran fail like the others, with an AbortError.
+        let pending = mem::take(&mut *self.pending_operations.borrow_mut());
+        for pending in pending {
+            pending.request.root().queue_completion(Err(Error::Abort));
+        }
+        if self.mode == IndexedDBTransactionMode::VersionChange {
+            self.db.abort_upgrade();
+        }
+
+        let this = Trusted::new(self);
+        let _ = global.dom_manipulation_task_source().queue(
+            task!(idb_transaction_abort: move || {
+                let this = this.root();
+                this.upcast::<EventTarget>().fire_bubbling_event(atom!("abort"));
+                if let Some(request) = this.open_request.get() {
+                    request.upgrade_aborted();
+                }
+            }),
+            &global,
+        );
+    }
+
+    /// The object store named `name` in the scope of the transaction.
+    pub fn object_store(&self, name: &DOMString) -> Fallible<DomRoot<IDBObjectStore>> {
+        if self.is_finished() {
+            return Err(Error::InvalidState);
+        }
+        if self.mode != IndexedDBTransactionMode::VersionChange && !self.scope.contains(name) {
+            return Err(Error::NotFound);
+        }
+        let info = self.db.object_store_info(name).ok_or(Error::NotFound)?;
+        if let Some(store) = self.object_stores.borrow().get(name) {
+            return Ok(DomRoot::from_ref(&**store));
+        }
+        let store = IDBObjectStore::new(&self.global(), self, &info);
+        self.object_stores
+            .borrow_mut()
+            .insert(name.clone(), Dom::from_ref(&*store));
+        Ok(store)
+    }
+
+    /// Forgets the object store named `name`, after it was deleted.
+    pub fn forget_object_store(&self, name: &DOMString) {
+        self.object_stores.borrow_mut().remove(name);
+    }
+}
+
+impl IDBTransactionMethods for IDBTransaction {
+    // https://w3c.github.io/IndexedDB/#dom-idbtransaction-objectstorenames
+    fn ObjectStoreNames(&self) -> DomRoot<DOMStringList> {
+        let names = match self.mode {
+            IndexedDBTransactionMode::VersionChange => self.db.object_store_names(),
+            _ => self.scope.clone(),
+        };
+        DOMStringList::new(&self.global(), names)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbtransaction-mode
+    fn Mode(&self) -> IDBTransactionMode {
+        match self.mode {
+            IndexedDBTransactionMode::ReadOnly => IDBTransactionMode::Readonly,
+            IndexedDBTransactionMode::ReadWrite => IDBTransactionMode::Readwrite,
+            IndexedDBTransactionMode::VersionChange => IDBTransactionMode::Versionchange,
+        }
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbtransaction-db
+    fn Db(&self) -> DomRoot<IDBDatabase> {
+        self.db()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbtransaction-error
+    fn GetError(&self) -> Option<DomRoot<DOMException>> {
+        self.error.get()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbtransaction-objectstore
+    fn ObjectStore(&self, name: DOMString) -> Fallible<DomRoot<IDBObjectStore>> {
+        self.object_store(&name)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbtransaction-commit
+    fn Commit(&self) -> ErrorResult {
+        if !self.is_active() {
+            return Err(Error::InvalidState);
+        }
+        self.state.set(TransactionState::Committing);
+        self.maybe_commit();
+        Ok(())
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbtransaction-abort
+    fn Abort(&self) -> ErrorResult {
+        match self.state.get() {
+            TransactionState::Committing | TransactionState::Finished => Err(Error::InvalidState),
+            TransactionState::Active | TransactionState::Inactive => {
+                self.abort_with(None);
+                Ok(())
+            },
+        }
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbtransaction-onabort
+    event_handler!(abort, GetOnabort, SetOnabort);
+
+    // https://w3c.github.io/IndexedDB/#dom-idbtransaction-oncomplete
+    event_handler!(complete, GetOncomplete, SetOncomplete);
+
+    // https://w3c.github.io/IndexedDB/#dom-idbtransaction-onerror
+    event_handler!(error, GetOnerror, SetOnerror);
+}
@@ -0,0 +1,80 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::codegen::Bindings::IdleDeadlineBinding::{
+    IdleDeadlineMethods, IdleRequestCallback,
+};
+use crate::dom::bindings::num::Finite;
+use crate::dom::bindings::refcounted::Trusted;
+use crate::dom::bindings::reflector::{reflect_dom_object, Reflector};
+use crate::dom::bindings::root::DomRoot;
+use crate::dom::performance::reduce_timing_resolution;
+use crate::dom::window::Window;
+use crate::timers::OneshotTimerHandle;
+use dom_struct::dom_struct;
+use metrics::ToMs;
+use std::rc::Rc;
+
+/// A callback that was passed to `requestIdleCallback()`.
+#[derive(JSTraceable, MallocSizeOf)]
+pub struct IdleRequest {
+    pub handle: u32,
+    #[ignore_malloc_size_of = "Rc"]
+    pub callback: Rc<IdleRequestCallback>,
+    /// The timer of the `timeout` option, if there is one.
+    pub timeout: Option<OneshotTimerHandle>,
+}
+
+// https://w3c.github.io/requestidlecallback/#idledeadline-interface
+#[dom_struct]
+pub struct IdleDeadline {
+    reflector_: Reflector,
+    /// The end of the idle period, in nanoseconds of `time::precise_time_ns`.
+    deadline: u64,
+    did_timeout: bool,
+}
+
+impl IdleDeadline {
+    fn new_inherited(deadline: u64, did_timeout: bool) -> IdleDeadline {
+        IdleDeadline {
+            reflector_: Reflector::new(),
+            deadline,
+            did_timeout,
+        }
+    }
+
+    pub fn new(window: &Window, deadline: u64, did_timeout: bool) -> DomRoot<IdleDeadline> {
+        reflect_dom_object(
+            Box::new(IdleDeadline::new_inherited(deadline, did_timeout)),
+            window,
+        )
+    }
+}
+
+impl IdleDeadlineMethods for IdleDeadline {
+    // https://w3c.github.io/requestidlecallback/#dom-idledeadline-timeremaining
+    fn TimeRemaining(&self) -> Finite<f64> {
+        let remaining = self.deadline.saturating_sub(time::precise_time_ns());
+        reduce_timing_resolution(remaining.to_ms())
+    }
+
+    // https://w3c.github.io/requestidlecallback/#dom-idledeadline-didtimeout
+    fn DidTimeout(&self) -> bool {
+        self.did_timeout
+    }
+}
+
+/// Runs an idle callback whose `timeout` elapsed before an idle period let it run.
+#[derive(JSTraceable, MallocSizeOf)]
+pub struct IdleCallbackTimeoutCallback {
+    #[ignore_malloc_size_of = "Because it is non-owning"]
+    pub window: Trusted<Window>,
+    pub handle: u32,
+}
+
+impl IdleCallbackTimeoutCallback {
+    pub fn invoke(self) {
+        self.window.root().invoke_idle_callback_timeout(self.handle);
+    }
+}
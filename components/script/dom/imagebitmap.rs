@@ -2,17 +2,23 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
-use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::cell::{DomRefCell, Ref};
 
 use crate::dom::bindings::codegen::Bindings::ImageBitmapBinding::ImageBitmapMethods;
 use crate::dom::bindings::root::DomRoot;
+use crate::dom::bindings::structuredclone::StructuredDataHolder;
+use crate::dom::bindings::transferable::Transferable;
 use crate::dom::globalscope::GlobalScope;
 
 use crate::dom::bindings::error::Fallible;
-use crate::dom::bindings::reflector::{reflect_dom_object, Reflector};
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
 use dom_struct::dom_struct;
+use js::jsapi::MutableHandleObject;
+use script_traits::transferable::ImageBitmapImpl;
 
 use std::cell::Cell;
+use std::collections::HashMap;
+use std::mem;
 use std::vec::Vec;
 
 #[dom_struct]
@@ -22,6 +28,8 @@ pub struct ImageBitmap {
     height: u32,
     bitmap_data: DomRefCell<Vec<u8>>,
     origin_clean: Cell<bool>,
+    /// <https://html.spec.whatwg.org/multipage/#detached>
+    detached: Cell<bool>,
 }
 
 impl ImageBitmap {
@@ -32,10 +40,10 @@ impl ImageBitmap {
             height: height_arg,
             bitmap_data: DomRefCell::new(vec![]),
             origin_clean: Cell::new(true),
+            detached: Cell::new(false),
         }
     }
 
-    #[allow(dead_code)]
     pub fn new(global: &GlobalScope, width: u32, height: u32) -> Fallible<DomRoot<ImageBitmap>> {
         //assigning to a variable the return object of new_inherited
         let imagebitmap = Box::new(ImageBitmap::new_inherited(width, height));
@@ -47,23 +55,102 @@ impl ImageBitmap {
         *self.bitmap_data.borrow_mut() = data;
     }
 
+    pub fn bitmap_data(&self) -> Ref<Vec<u8>> {
+        self.bitmap_data.borrow()
+    }
+
     pub fn set_origin_clean(&self, origin_is_clean: bool) {
         self.origin_clean.set(origin_is_clean);
     }
+
+    pub fn origin_is_clean(&self) -> bool {
+        self.origin_clean.get()
+    }
+
+    pub fn is_detached(&self) -> bool {
+        self.detached.get()
+    }
+}
+
+impl Transferable for ImageBitmap {
+    /// <https://html.spec.whatwg.org/multipage/#the-imagebitmap-interface:transfer-steps>
+    fn transfer(&self, sc_holder: &mut StructuredDataHolder) -> Result<u64, ()> {
+        if self.detached.get() {
+            return Err(());
+        }
+
+        let image_bitmaps = match sc_holder {
+            StructuredDataHolder::Write { image_bitmaps, .. } => image_bitmaps,
+            _ => panic!("Unexpected variant of StructuredDataHolder"),
+        };
+
+        // 1. Move the bitmap data to the data holder, without copying it.
+        let bitmap_impl = ImageBitmapImpl {
+            width: self.width,
+            height: self.height,
+            bitmap_data: mem::take(&mut *self.bitmap_data.borrow_mut()),
+            origin_clean: self.origin_clean.get(),
+        };
+        self.detached.set(true);
+
+        // 2. Store it at a key that is unique within this transfer.
+        let image_bitmaps = image_bitmaps.get_or_insert_with(|| HashMap::new());
+        let key = image_bitmaps.len() as u64;
+        image_bitmaps.insert(key, bitmap_impl);
+
+        Ok(key)
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#the-imagebitmap-interface:transfer-receiving-steps>
+    fn transfer_receive(
+        owner: &GlobalScope,
+        sc_holder: &mut StructuredDataHolder,
+        extra_data: u64,
+        return_object: MutableHandleObject,
+    ) -> Result<(), ()> {
+        let image_bitmaps = match sc_holder {
+            StructuredDataHolder::Read { image_bitmaps, .. } => image_bitmaps,
+            _ => panic!("Unexpected variant of StructuredDataHolder"),
+        };
+
+        // 1. Get the transferred bitmap data from its storage.
+        let bitmap_impl = image_bitmaps
+            .as_mut()
+            .and_then(|image_bitmaps| image_bitmaps.remove(&extra_data))
+            .ok_or(())?;
+
+        // 2. Set the new bitmap's data to it.
+        let image_bitmap =
+            ImageBitmap::new(owner, bitmap_impl.width, bitmap_impl.height).map_err(|_| ())?;
+        image_bitmap.set_bitmap_data(bitmap_impl.bitmap_data);
+        image_bitmap.set_origin_clean(bitmap_impl.origin_clean);
+
+        return_object.set(image_bitmap.reflector().rootable().get());
+        Ok(())
+    }
 }
 
 impl ImageBitmapMethods for ImageBitmap {
     // https://html.spec.whatwg.org/multipage/#dom-imagebitmap-height
     fn Height(&self) -> u32 {
-        //to do: add a condition for checking detached internal slot
-        //and return 0 if set to true
+        if self.detached.get() {
+            return 0;
+        }
         self.height
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-imagebitmap-width
     fn Width(&self) -> u32 {
-        //to do: add a condition to check detached internal slot
-        ////and return 0 if set to true
+        if self.detached.get() {
+            return 0;
+        }
         self.width
     }
+
+    // https://html.spec.whatwg.org/multipage/#dom-imagebitmap-close
+    fn Close(&self) {
+        // Steps 1-2.
+        self.detached.set(true);
+        *self.bitmap_data.borrow_mut() = Vec::new();
+    }
 }
@@ -0,0 +1,685 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::callback::ExceptionHandling;
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::IntersectionObserverBinding::IntersectionObserverCallback;
+use crate::dom::bindings::codegen::Bindings::IntersectionObserverBinding::IntersectionObserverInit;
+use crate::dom::bindings::codegen::Bindings::IntersectionObserverBinding::IntersectionObserverMethods;
+use crate::dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
+use crate::dom::bindings::codegen::UnionTypes::DoubleOrDoubleSequence;
+use crate::dom::bindings::codegen::UnionTypes::ElementOrDocument;
+use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::num::Finite;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::bindings::utils::to_frozen_array;
+use crate::dom::document::Document;
+use crate::dom::element::Element;
+use crate::dom::intersectionobserverentry::IntersectionObserverEntry;
+use crate::dom::node::Node;
+use crate::dom::window::Window;
+use crate::script_runtime::JSContext;
+use app_units::Au;
+use dom_struct::dom_struct;
+use euclid::default::{Point2D, Rect, Size2D, Vector2D};
+use js::jsapi::Heap;
+use js::jsval::JSVal;
+use std::cell::Cell;
+use std::rc::Rc;
+use style::computed_values::position::T as Position;
+use style::properties::longhands::{overflow_x, overflow_y};
+
+/// One side of a root margin, as parsed from the `rootMargin` option.
+#[derive(Clone, Copy, Debug, JSTraceable, MallocSizeOf, PartialEq)]
+enum RootMarginValue {
+    Pixels(f64),
+    Percentage(f64),
+}
+
+impl RootMarginValue {
+    fn parse(value: &str) -> Option<RootMarginValue> {
+        let lower = value.to_ascii_lowercase();
+        let (number, result): (&str, fn(f64) -> RootMarginValue) =
+            if let Some(number) = lower.strip_suffix("px") {
+                (number, RootMarginValue::Pixels)
+            } else if let Some(number) = lower.strip_suffix('%') {
+                (number, RootMarginValue::Percentage)
+            } else {
+                return None;
+            };
+        match number.parse::<f64>() {
+            Ok(number) if number.is_finite() => Some(result(number)),
+            _ => None,
+        }
+    }
+
+    /// Resolves this value against the given dimension of the root intersection rectangle.
+    fn resolve(&self, basis: f64) -> f64 {
+        match *self {
+            RootMarginValue::Pixels(pixels) => pixels,
+            RootMarginValue::Percentage(percentage) => basis * percentage / 100.,
+        }
+    }
+
+    fn serialize(&self) -> String {
+        match *self {
+            RootMarginValue::Pixels(pixels) => format!("{}px", pixels),
+            RootMarginValue::Percentage(percentage) => format!("{}%", percentage),
+        }
+    }
+}
+
+/// <https://w3c.github.io/IntersectionObserver/#parse-a-root-margin>
+///
+/// The margins are returned in top, right, bottom, left order.
+fn parse_root_margin(margin: &str) -> Option<[RootMarginValue; 4]> {
+    let values = margin
+        .split_ascii_whitespace()
+        .map(RootMarginValue::parse)
+        .collect::<Option<Vec<_>>>()?;
+    match values.len() {
+        1 => Some([values[0]; 4]),
+        2 => Some([values[0], values[1], values[0], values[1]]),
+        3 => Some([values[0], values[1], values[2], values[1]]),
+        4 => Some([values[0], values[1], values[2], values[3]]),
+        _ => None,
+    }
+}
+
+/// The registration of an observer on one of its targets, which holds the state
+/// of the previous intersection observation of that target.
+///
+/// <https://w3c.github.io/IntersectionObserver/#intersectionobserverregistration>
+#[derive(JSTraceable, MallocSizeOf)]
+#[unrooted_must_root_lint::must_root]
+pub struct IntersectionObserverRegistration {
+    pub observer: Dom<IntersectionObserver>,
+    previous_threshold_index: Cell<i32>,
+    previous_is_intersecting: Cell<bool>,
+}
+
+#[dom_struct]
+pub struct IntersectionObserver {
+    reflector_: Reflector,
+    #[ignore_malloc_size_of = "can't measure Rc values"]
+    callback: Rc<IntersectionObserverCallback>,
+    /// <https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-root>
+    ///
+    /// Either an element or a document; `None` is the implicit root.
+    root: Option<Dom<Node>>,
+    /// <https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-rootmargin-slot>
+    root_margin: [RootMarginValue; 4],
+    /// <https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-thresholds>
+    thresholds: Vec<f64>,
+    #[ignore_malloc_size_of = "mozjs"]
+    frozen_thresholds: DomRefCell<Option<Heap<JSVal>>>,
+    /// <https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-queuedentries-slot>
+    queued_entries: DomRefCell<Vec<Dom<IntersectionObserverEntry>>>,
+    /// <https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-observationtargets-slot>
+    observation_targets: DomRefCell<Vec<Dom<Element>>>,
+}
+
+/// The outcome of computing the intersection of a target with the root of an observer.
+struct Intersection {
+    root_bounds: Option<Rect<f64>>,
+    bounding_client_rect: Rect<f64>,
+    intersection_rect: Rect<f64>,
+    is_intersecting: bool,
+}
+
+impl IntersectionObserver {
+    fn new(
+        window: &Window,
+        callback: Rc<IntersectionObserverCallback>,
+        root: Option<&Node>,
+        root_margin: [RootMarginValue; 4],
+        thresholds: Vec<f64>,
+    ) -> DomRoot<IntersectionObserver> {
+        reflect_dom_object(
+            Box::new(IntersectionObserver::new_inherited(
+                callback,
+                root,
+                root_margin,
+                thresholds,
+            )),
+            window,
+        )
+    }
+
+    fn new_inherited(
+        callback: Rc<IntersectionObserverCallback>,
+        root: Option<&Node>,
+        root_margin: [RootMarginValue; 4],
+        thresholds: Vec<f64>,
+    ) -> IntersectionObserver {
+        IntersectionObserver {
+            reflector_: Reflector::new(),
+            callback,
+            root: root.map(Dom::from_ref),
+            root_margin,
+            thresholds,
+            frozen_thresholds: DomRefCell::new(None),
+            queued_entries: DomRefCell::new(vec![]),
+            observation_targets: DomRefCell::new(vec![]),
+        }
+    }
+
+    /// <https://w3c.github.io/IntersectionObserver/#initialize-a-new-intersectionobserver>
+    #[allow(non_snake_case)]
+    pub fn Constructor(
+        window: &Window,
+        callback: Rc<IntersectionObserverCallback>,
+        options: &IntersectionObserverInit,
+    ) -> Fallible<DomRoot<IntersectionObserver>> {
+        // Steps 3-4
+        let root_margin = match parse_root_margin(&options.rootMargin) {
+            Some(root_margin) => root_margin,
+            None => {
+                return Err(Error::Syntax);
+            },
+        };
+
+        // Steps 5-9
+        let mut thresholds: Vec<f64> = match options.threshold {
+            DoubleOrDoubleSequence::Double(threshold) => vec![*threshold],
+            DoubleOrDoubleSequence::DoubleSequence(ref thresholds) => {
+                thresholds.iter().map(|threshold| **threshold).collect()
+            },
+        };
+        if thresholds
+            .iter()
+            .any(|threshold| *threshold < 0. || *threshold > 1.)
+        {
+            return Err(Error::Range(
+                "Threshold values must be between 0 and 1".into(),
+            ));
+        }
+        thresholds.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        if thresholds.is_empty() {
+            thresholds.push(0.);
+        }
+
+        // Step 10
+        let root = match options.root {
+            Some(ElementOrDocument::Element(ref element)) => Some(element.upcast::<Node>()),
+            Some(ElementOrDocument::Document(ref document)) => Some(document.upcast::<Node>()),
+            None => None,
+        };
+
+        Ok(IntersectionObserver::new(
+            window,
+            callback,
+            root,
+            root_margin,
+            thresholds,
+        ))
+    }
+
+    pub fn has_observation_targets(&self) -> bool {
+        !self.observation_targets.borrow().is_empty()
+    }
+
+    pub fn has_queued_entries(&self) -> bool {
+        !self.queued_entries.borrow().is_empty()
+    }
+
+    /// <https://w3c.github.io/IntersectionObserver/#notify-intersection-observers-algo>
+    pub fn notify_intersection_observers(document: &Document) {
+        // Step 1
+        document.set_intersection_observer_task_queued(false);
+        // Step 2
+        let notify_list = document.intersection_observers();
+        // Step 3
+        for observer in &notify_list {
+            let queue = observer.TakeRecords();
+            if !queue.is_empty() {
+                let _ = observer.callback.Call_(
+                    &**observer,
+                    queue,
+                    &**observer,
+                    ExceptionHandling::Report,
+                );
+            }
+        }
+    }
+
+    /// <https://w3c.github.io/IntersectionObserver/#update-intersection-observations-algo>
+    /// Steps 2.2 and later, for a single observer.
+    pub fn update_intersection_observations(&self, time: Finite<f64>) {
+        let targets: Vec<DomRoot<Element>> = self
+            .observation_targets
+            .borrow()
+            .iter()
+            .map(|target| DomRoot::from_ref(&**target))
+            .collect();
+
+        for target in targets {
+            // Steps 2.2.2-2.2.8
+            let intersection =
+                compute_intersection(self.root.as_deref(), &self.root_margin, &target);
+
+            // Steps 2.2.9-2.2.10
+            let target_area = intersection
+                .as_ref()
+                .map_or(0., |i| i.bounding_client_rect.size.area());
+            let intersection_area = intersection
+                .as_ref()
+                .map_or(0., |i| i.intersection_rect.size.area());
+            let is_intersecting = intersection.as_ref().map_or(false, |i| i.is_intersecting);
+            let intersection_ratio = if target_area > 0. {
+                intersection_area / target_area
+            } else if is_intersecting {
+                1.
+            } else {
+                0.
+            };
+
+            // Step 2.2.11
+            let threshold_index = self
+                .thresholds
+                .iter()
+                .position(|threshold| *threshold > intersection_ratio)
+                .unwrap_or(self.thresholds.len()) as i32;
+
+            // Steps 2.2.12-2.2.14
+            let changed = target.with_intersection_observer_registration(self, |registration| {
+                let changed = registration.previous_threshold_index.get() != threshold_index ||
+                    registration.previous_is_intersecting.get() != is_intersecting;
+                registration.previous_threshold_index.set(threshold_index);
+                registration.previous_is_intersecting.set(is_intersecting);
+                changed
+            });
+            if changed != Some(true) {
+                continue;
+            }
+
+            let (root_bounds, bounding_client_rect, intersection_rect) = match intersection {
+                Some(intersection) => (
+                    intersection.root_bounds,
+                    intersection.bounding_client_rect,
+                    intersection.intersection_rect,
+                ),
+                None => (None, Rect::zero(), Rect::zero()),
+            };
+            self.queue_an_intersection_observer_entry(
+                &target,
+                time,
+                root_bounds,
+                bounding_client_rect,
+                intersection_rect,
+                is_intersecting,
+                intersection_ratio,
+            );
+        }
+    }
+
+    /// <https://w3c.github.io/IntersectionObserver/#queue-an-intersectionobserverentry>
+    fn queue_an_intersection_observer_entry(
+        &self,
+        target: &Element,
+        time: Finite<f64>,
+        root_bounds: Option<Rect<f64>>,
+        bounding_client_rect: Rect<f64>,
+        intersection_rect: Rect<f64>,
+        is_intersecting: bool,
+        intersection_ratio: f64,
+    ) {
+        let global = self.global();
+        // Step 1-2
+        let entry = IntersectionObserverEntry::new(
+            &global,
+            time,
+            root_bounds.as_ref(),
+            &bounding_client_rect,
+            &intersection_rect,
+            is_intersecting,
+            Finite::wrap(intersection_ratio),
+            target,
+        );
+        self.queued_entries
+            .borrow_mut()
+            .push(Dom::from_ref(&*entry));
+        // Step 3
+        IntersectionObserver::queue_an_intersection_observer_task(&global.as_window().Document());
+    }
+
+    /// <https://w3c.github.io/IntersectionObserver/#queue-an-intersection-observer-task>
+    fn queue_an_intersection_observer_task(document: &Document) {
+        // Step 1
+        if document.intersection_observer_task_queued() {
+            return;
+        }
+        // Step 2
+        document.set_intersection_observer_task_queued(true);
+        // Step 3
+        document.queue_notify_intersection_observers();
+    }
+}
+
+impl IntersectionObserverMethods for IntersectionObserver {
+    /// <https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-root>
+    fn GetRoot(&self) -> Option<ElementOrDocument> {
+        let root = self.root.as_ref()?;
+        Some(match root.downcast::<Element>() {
+            Some(element) => ElementOrDocument::Element(DomRoot::from_ref(element)),
+            None => {
+                ElementOrDocument::Document(DomRoot::downcast(DomRoot::from_ref(&**root)).unwrap())
+            },
+        })
+    }
+
+    /// <https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-rootmargin>
+    fn RootMargin(&self) -> DOMString {
+        let margin: Vec<String> = self.root_margin.iter().map(|m| m.serialize()).collect();
+        DOMString::from(margin.join(" "))
+    }
+
+    /// <https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-thresholds>
+    fn Thresholds(&self, cx: JSContext) -> JSVal {
+        if let Some(thresholds) = &*self.frozen_thresholds.borrow() {
+            return thresholds.get();
+        }
+
+        let thresholds: Vec<f64> = self.thresholds.clone();
+        let frozen_thresholds = to_frozen_array(thresholds.as_slice(), cx);
+
+        // Safety: need to create the Heap value in its final memory location before setting it.
+        *self.frozen_thresholds.borrow_mut() = Some(Heap::default());
+        self.frozen_thresholds
+            .borrow()
+            .as_ref()
+            .unwrap()
+            .set(frozen_thresholds);
+
+        frozen_thresholds
+    }
+
+    /// <https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-observe>
+    fn Observe(&self, target: &Element) {
+        // Step 1
+        if self
+            .observation_targets
+            .borrow()
+            .iter()
+            .any(|t| &**t == target)
+        {
+            return;
+        }
+        // Steps 2-3
+        target.add_intersection_observer_registration(IntersectionObserverRegistration {
+            observer: Dom::from_ref(self),
+            previous_threshold_index: Cell::new(-1),
+            previous_is_intersecting: Cell::new(false),
+        });
+        // Step 4
+        self.observation_targets
+            .borrow_mut()
+            .push(Dom::from_ref(target));
+        self.global()
+            .as_window()
+            .Document()
+            .add_intersection_observer(self);
+    }
+
+    /// <https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-unobserve>
+    fn Unobserve(&self, target: &Element) {
+        // Step 1
+        target.remove_intersection_observer_registration(self);
+        // Step 2
+        self.observation_targets
+            .borrow_mut()
+            .retain(|t| &**t != target);
+    }
+
+    /// <https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-disconnect>
+    fn Disconnect(&self) {
+        let mut targets = self.observation_targets.borrow_mut();
+        for target in targets.drain(..) {
+            target.remove_intersection_observer_registration(self);
+        }
+    }
+
+    /// <https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-takerecords>
+    fn TakeRecords(&self) -> Vec<DomRoot<IntersectionObserverEntry>> {
+        self.queued_entries
+            .borrow_mut()
+            .drain(..)
+            .map(|entry| DomRoot::from_ref(&*entry))
+            .collect()
+    }
+}
+
+/// <https://w3c.github.io/IntersectionObserver/#intersection-root>
+///
+/// Returns the document of the root, and the root element if any.
+fn intersection_root(
+    root: Option<&Node>,
+    target: &Element,
+) -> (DomRoot<Document>, Option<DomRoot<Element>>) {
+    match root {
+        Some(root) => match root.downcast::<Element>() {
+            Some(element) => (root.owner_doc(), Some(DomRoot::from_ref(element))),
+            None => (DomRoot::downcast(DomRoot::from_ref(root)).unwrap(), None),
+        },
+        // The implicit root is the document of the top-level browsing context,
+        // or the topmost document of this event loop if that one lives elsewhere.
+        None => {
+            let mut document = target.upcast::<Node>().owner_doc();
+            while let Some(parent) = parent_document(&document) {
+                document = parent;
+            }
+            (document, None)
+        },
+    }
+}
+
+/// <https://w3c.github.io/IntersectionObserver/#calculate-intersection-rect-algo>
+fn compute_intersection(
+    root: Option<&Node>,
+    root_margin: &[RootMarginValue; 4],
+    target: &Element,
+) -> Option<Intersection> {
+    let (root_document, root_element) = intersection_root(root, target);
+    let target_document = target.upcast::<Node>().owner_doc();
+
+    // https://w3c.github.io/IntersectionObserver/#intersectionobserver-root-intersection-rectangle
+    let mut root_rect = match root_element {
+        Some(ref root) => {
+            // The intersection root must be a containing block ancestor of the target.
+            if !root.upcast::<Node>().is_ancestor_of(target.upcast()) {
+                return None;
+            }
+            scrolled_border_box(root)?
+        },
+        None => viewport_rect(&root_document),
+    };
+    let same_origin_domain = target_document
+        .origin()
+        .same_origin_domain(root_document.origin());
+    if same_origin_domain {
+        let [top, right, bottom, left] = *root_margin;
+        let top = top.resolve(root_rect.size.height);
+        let right = right.resolve(root_rect.size.width);
+        let bottom = bottom.resolve(root_rect.size.height);
+        let left = left.resolve(root_rect.size.width);
+        root_rect = Rect::new(
+            Point2D::new(root_rect.origin.x - left, root_rect.origin.y - top),
+            Size2D::new(
+                (root_rect.size.width + left + right).max(0.),
+                (root_rect.size.height + top + bottom).max(0.),
+            ),
+        );
+    }
+
+    // Step 1-2
+    let target_rect = scrolled_border_box(target)?;
+    let target_viewport = viewport_rect(&target_document);
+    let bounding_client_rect = target_rect.translate(-target_viewport.origin.to_vector());
+
+    // Steps 3-4: map the target rectangle into the coordinate space of the
+    // root document, clipping it to the containing blocks of the target that
+    // have an overflow clip, and to the viewport of each document it crosses.
+    let mut intersection_rect = Some(target_rect);
+    let mut translation = Vector2D::zero();
+    let mut document = target_document.clone();
+    let mut container = containing_block(target);
+    loop {
+        while let Some(element) = container {
+            if root_element.as_deref() == Some(&*element) {
+                break;
+            }
+            if let Some(clip) = overflow_clip_rect(&element) {
+                intersection_rect = intersection_rect.and_then(|rect| intersect(&rect, &clip));
+            }
+            container = containing_block(&element);
+        }
+        if document == root_document {
+            break;
+        }
+        let viewport = viewport_rect(&document);
+        intersection_rect = intersection_rect.and_then(|rect| intersect(&rect, &viewport));
+        let frame = document
+            .browsing_context()
+            .and_then(|context| context.frame_element().map(DomRoot::from_ref))?;
+        let frame_rect = scrolled_border_box(&frame)?;
+        let delta = frame_rect.origin - viewport.origin;
+        intersection_rect = intersection_rect.map(|rect| rect.translate(delta));
+        translation += delta;
+        document = frame.upcast::<Node>().owner_doc();
+        container = containing_block(&frame);
+    }
+
+    // Step 5-6
+    let intersection_rect = intersection_rect.and_then(|rect| intersect(&rect, &root_rect));
+
+    // Step 7: map the intersection back into the viewport of the target.
+    let is_intersecting = intersection_rect.is_some();
+    let intersection_rect = intersection_rect
+        .map(|rect| rect.translate(-translation - target_viewport.origin.to_vector()))
+        .unwrap_or_else(Rect::zero);
+
+    let root_bounds = if same_origin_domain {
+        let root_viewport = viewport_rect(&root_document);
+        Some(root_rect.translate(-root_viewport.origin.to_vector()))
+    } else {
+        None
+    };
+
+    Some(Intersection {
+        root_bounds,
+        bounding_client_rect,
+        intersection_rect,
+        is_intersecting,
+    })
+}
+
+/// Whether `target` intersects the viewport of the topmost document of this event loop,
+/// grown by `root_margin`, which is what the lazy load intersection observer waits for
+/// before resuming the load of an element. An unparsable margin counts as zero.
+///
+/// <https://html.spec.whatwg.org/multipage/#lazy-load-intersection-observer>
+pub(crate) fn intersects_lazy_load_root(target: &Element, root_margin: &str) -> bool {
+    let root_margin = parse_root_margin(root_margin).unwrap_or([RootMarginValue::Pixels(0.); 4]);
+    compute_intersection(None, &root_margin, target)
+        .map_or(false, |intersection| intersection.is_intersecting)
+}
+
+/// The document of the parent browsing context of `document`, if it is in this
+/// event loop.
+fn parent_document(document: &Document) -> Option<DomRoot<Document>> {
+    let context = document.browsing_context()?;
+    let frame = context.frame_element()?;
+    Some(frame.upcast::<Node>().owner_doc())
+}
+
+/// The viewport of `document`, in the coordinate space of that document.
+fn viewport_rect(document: &Document) -> Rect<f64> {
+    au_rect_to_px(&document.window().current_viewport())
+}
+
+/// The border box of `element` as reported by layout, in the coordinate space of
+/// its document and moved by the scroll offsets of its ancestors.
+fn scrolled_border_box(element: &Element) -> Option<Rect<f64>> {
+    let node = element.upcast::<Node>();
+    let rect = au_rect_to_px(&node.bounding_content_box()?);
+    let scroll = node
+        .ancestors()
+        .filter(|ancestor| ancestor.is::<Element>())
+        .fold(Vector2D::zero(), |scroll, ancestor| {
+            let offset = ancestor.scroll_offset();
+            scroll + Vector2D::new(offset.x.abs() as f64, offset.y.abs() as f64)
+        });
+    Some(rect.translate(-scroll))
+}
+
+/// The containing block of `element` within its document: the nearest positioned
+/// ancestor of an absolutely positioned element, and the parent element of an
+/// element in flow. Fixed position elements are only contained by the viewport.
+fn containing_block(element: &Element) -> Option<DomRoot<Element>> {
+    let position = element.style()?.get_box().clone_position();
+    let mut ancestors = element
+        .upcast::<Node>()
+        .ancestors()
+        .filter_map(DomRoot::downcast::<Element>);
+    match position {
+        Position::Fixed => None,
+        Position::Absolute => ancestors.find(|ancestor| {
+            ancestor.style().map_or(false, |style| {
+                style.get_box().clone_position() != Position::Static
+            })
+        }),
+        Position::Static | Position::Relative | Position::Sticky => ancestors.next(),
+    }
+}
+
+/// The padding box of `element`, to which it clips its descendants if it has an
+/// overflow clip, in the same coordinate space as `scrolled_border_box`.
+/// <https://drafts.csswg.org/css-overflow-3/#overflow-clip-edge>
+fn overflow_clip_rect(element: &Element) -> Option<Rect<f64>> {
+    let style = element.style()?;
+    let box_ = style.get_box();
+    if box_.clone_overflow_x() == overflow_x::computed_value::T::Visible &&
+        box_.clone_overflow_y() == overflow_y::computed_value::T::Visible
+    {
+        return None;
+    }
+    let border_box = scrolled_border_box(element)?;
+    let client_rect = element.upcast::<Node>().client_rect();
+    Some(Rect::new(
+        Point2D::new(
+            border_box.origin.x + client_rect.origin.x as f64,
+            border_box.origin.y + client_rect.origin.y as f64,
+        ),
+        Size2D::new(
+            client_rect.size.width as f64,
+            client_rect.size.height as f64,
+        ),
+    ))
+}
+
+fn au_rect_to_px(rect: &Rect<Au>) -> Rect<f64> {
+    Rect::new(
+        Point2D::new(rect.origin.x.to_f64_px(), rect.origin.y.to_f64_px()),
+        Size2D::new(rect.size.width.to_f64_px(), rect.size.height.to_f64_px()),
+    )
+}
+
+/// The intersection of two rectangles, which unlike `Rect::intersection` counts
+/// rectangles that only share an edge as intersecting.
+fn intersect(a: &Rect<f64>, b: &Rect<f64>) -> Option<Rect<f64>> {
+    let min_x = a.min_x().max(b.min_x());
+    let min_y = a.min_y().max(b.min_y());
+    let max_x = a.max_x().min(b.max_x());
+    let max_y = a.max_y().min(b.max_y());
+    if min_x > max_x || min_y > max_y {
+        return None;
+    }
+    Some(Rect::new(
+        Point2D::new(min_x, min_y),
+        Size2D::new(max_x - min_x, max_y - min_y),
+    ))
+}
@@ -0,0 +1,45 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::codegen::Bindings::LockBinding::LockMethods;
+use crate::dom::bindings::codegen::Bindings::LockManagerBinding::LockMode;
+use crate::dom::bindings::reflector::{reflect_dom_object, Reflector};
+use crate::dom::bindings::root::DomRoot;
+use crate::dom::bindings::str::DOMString;
+use crate::dom::globalscope::GlobalScope;
+use dom_struct::dom_struct;
+
+// https://w3c.github.io/web-locks/#lock
+#[dom_struct]
+pub struct Lock {
+    reflector_: Reflector,
+    name: DOMString,
+    mode: LockMode,
+}
+
+impl Lock {
+    fn new_inherited(name: DOMString, mode: LockMode) -> Lock {
+        Lock {
+            reflector_: Reflector::new(),
+            name,
+            mode,
+        }
+    }
+
+    pub fn new(global: &GlobalScope, name: DOMString, mode: LockMode) -> DomRoot<Lock> {
+        reflect_dom_object(Box::new(Lock::new_inherited(name, mode)), global)
+    }
+}
+
+impl LockMethods for Lock {
+    // https://w3c.github.io/web-locks/#dom-lock-name
+    fn Name(&self) -> DOMString {
+        self.name.clone()
+    }
+
+    // https://w3c.github.io/web-locks/#dom-lock-mode
+    fn Mode(&self) -> LockMode {
+        self.mode
+    }
+}
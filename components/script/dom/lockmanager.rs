@@ -0,0 +1,428 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::abortsignal::AbortAlgorithm;
+use crate::dom::abortsignal::AbortSignal;
+use crate::dom::bindings::callback::ExceptionHandling;
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::LockManagerBinding::{
+    LockGrantedCallback, LockInfo, LockManagerMethods, LockManagerSnapshot, LockMode, LockOptions,
+};
+use crate::dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
+use crate::dom::bindings::error::Error;
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::refcounted::Trusted;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::lock::Lock;
+use crate::dom::promise::Promise;
+use crate::dom::promisenativehandler::{Callback, PromiseNativeHandler};
+use crate::dom::window::Window;
+use crate::dom::writablestreamdefaultcontroller::promise_from_callback_result;
+use crate::realms::{enter_realm, InRealm};
+use crate::script_runtime::JSContext;
+use crate::task::TaskCanceller;
+use crate::task_source::dom_manipulation::DOMManipulationTaskSource;
+use crate::task_source::{TaskSource, TaskSourceName};
+use dom_struct::dom_struct;
+use ipc_channel::ipc;
+use ipc_channel::router::ROUTER;
+use js::rust::HandleValue;
+use msg::constellation_msg::LockClientId;
+use script_traits::{
+    LockInfo as IpcLockInfo, LockManagerMsg, LockManagerSnapshot as IpcLockManagerSnapshot,
+    LockMode as IpcLockMode, LockRequest, ScriptMsg,
+};
+use std::cell::Cell;
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+
+impl From<LockMode> for IpcLockMode {
+    fn from(mode: LockMode) -> IpcLockMode {
+        match mode {
+            LockMode::Shared => IpcLockMode::Shared,
+            LockMode::Exclusive => IpcLockMode::Exclusive,
+        }
+    }
+}
+
+impl From<IpcLockMode> for LockMode {
+    fn from(mode: IpcLockMode) -> LockMode {
+        match mode {
+            IpcLockMode::Shared => LockMode::Shared,
+            IpcLockMode::Exclusive => LockMode::Exclusive,
+        }
+    }
+}
+
+impl From<IpcLockInfo> for LockInfo {
+    fn from(info: IpcLockInfo) -> LockInfo {
+        LockInfo {
+            name: Some(DOMString::from(info.name)),
+            mode: Some(info.mode.into()),
+            clientId: Some(DOMString::from(info.client_id)),
+        }
+    }
+}
+
+impl From<IpcLockManagerSnapshot> for LockManagerSnapshot {
+    fn from(snapshot: IpcLockManagerSnapshot) -> LockManagerSnapshot {
+        LockManagerSnapshot {
+            held: Some(snapshot.held.into_iter().map(LockInfo::from).collect()),
+            pending: Some(snapshot.pending.into_iter().map(LockInfo::from).collect()),
+        }
+    }
+}
+
+/// A wrapper for the answers of the lock manager coming in over IPC, and the event-loop.
+struct LockManagerListener {
+    canceller: TaskCanceller,
+    task_source: DOMManipulationTaskSource,
+    context: Trusted<LockManager>,
+}
+
+impl LockManagerListener {
+    fn handle(&self, message: LockManagerMsg) {
+        let context = self.context.clone();
+        let _ = self.task_source.queue_with_canceller(
+            task!(lock_manager_message: move || {
+                context.root().handle_message(message);
+            }),
+            &self.canceller,
+        );
+    }
+}
+
+/// <https://w3c.github.io/web-locks/#lock-request>
+#[derive(JSTraceable, MallocSizeOf)]
+struct LockRequestState {
+    name: DOMString,
+    mode: LockMode,
+    #[ignore_malloc_size_of = "Rc"]
+    callback: Rc<LockGrantedCallback>,
+    /// The promise returned by `request()`.
+    #[ignore_malloc_size_of = "Rc"]
+    promise: Rc<Promise>,
+    /// Whether the lock manager granted the request.
+    granted: bool,
+}
+
+// https://w3c.github.io/web-locks/#lockmanager
+#[dom_struct]
+pub struct LockManager {
+    reflector_: Reflector,
+    /// The id of this global as a client of the lock manager, once it is registered.
+    client_id: Cell<Option<LockClientId>>,
+    next_request_id: Cell<u64>,
+    /// The pending requests, and the requests holding a lock, by id.
+    requests: DomRefCell<HashMap<u64, LockRequestState>>,
+    /// The promises returned by `query()`, answered in order.
+    #[ignore_malloc_size_of = "Rc"]
+    queries: DomRefCell<VecDeque<Rc<Promise>>>,
+}
+
+impl LockManager {
+    fn new_inherited() -> LockManager {
+        LockManager {
+            reflector_: Reflector::new(),
+            client_id: Cell::new(None),
+            next_request_id: Cell::new(0),
+            requests: Default::default(),
+            queries: Default::default(),
+        }
+    }
+
+    pub fn new(global: &GlobalScope) -> DomRoot<LockManager> {
+        reflect_dom_object(Box::new(LockManager::new_inherited()), global)
+    }
+
+    /// Register this global as a client of the lock manager of its origin,
+    /// if it isn't already.
+    fn client_id(&self) -> LockClientId {
+        if let Some(client_id) = self.client_id.get() {
+            return client_id;
+        }
+        let global = self.global();
+        let (sender, receiver) = ipc::channel().expect("ipc channel failure");
+        let listener = LockManagerListener {
+            canceller: global.task_canceller(TaskSourceName::DOMManipulation),
+            task_source: global.dom_manipulation_task_source(),
+            context: Trusted::new(self),
+        };
+        ROUTER.add_route(
+            receiver.to_opaque(),
+            Box::new(move |message| match message.to() {
+                Ok(message) => listener.handle(message),
+                Err(err) => warn!("Error receiving a LockManagerMsg: {:?}", err),
+            }),
+        );
+        let client_id = LockClientId::new();
+        let _ = global
+            .script_to_constellation_chan()
+            .send(ScriptMsg::NewLockClient(
+                client_id,
+                sender,
+                global.origin().immutable().clone(),
+            ));
+        self.client_id.set(Some(client_id));
+        client_id
+    }
+
+    /// Tell the lock manager that this global went away,
+    /// releasing its locks and aborting its pending requests.
+    pub fn remove_client(&self) {
+        if let Some(client_id) = self.client_id.take() {
+            let _ = self
+                .global()
+                .script_to_constellation_chan()
+                .send(ScriptMsg::RemoveLockClient(client_id));
+        }
+    }
+
+    fn handle_message(&self, message: LockManagerMsg) {
+        match message {
+            LockManagerMsg::Granted(id) => self.grant(id, true),
+            LockManagerMsg::NotGranted(id) => self.grant(id, false),
+            LockManagerMsg::Stolen(id) => {
+                // Step 7.1 of https://w3c.github.io/web-locks/#request-a-lock.
+                if let Some(request) = self.requests.borrow_mut().remove(&id) {
+                    request.promise.reject_error(Error::Abort);
+                }
+            },
+            LockManagerMsg::Snapshot(snapshot) => {
+                if let Some(promise) = self.queries.borrow_mut().pop_front() {
+                    promise.resolve_native(&LockManagerSnapshot::from(snapshot));
+                }
+            },
+        }
+    }
+
+    /// <https://w3c.github.io/web-locks/#process-the-lock-request-queue>, step 2.5,
+    /// or step 8.1 of <https://w3c.github.io/web-locks/#request-a-lock>
+    /// for an `ifAvailable` request that could not be granted.
+    fn grant(&self, id: u64, granted: bool) {
+        let (callback, name, mode) = match self.requests.borrow_mut().get_mut(&id) {
+            Some(request) => {
+                request.granted = granted;
+                (request.callback.clone(), request.name.clone(), request.mode)
+            },
+            // The request was aborted.
+            None => return,
+        };
+
+        let global = self.global();
+        let lock = if granted {
+            Some(Lock::new(&global, name, mode))
+        } else {
+            None
+        };
+        let realm = enter_realm(&*global);
+        let result = callback.Call__(lock.as_deref(), ExceptionHandling::Rethrow);
+        let waiting = promise_from_callback_result(&global, result);
+        let handler = PromiseNativeHandler::new(
+            &global,
+            Some(Box::new(LockReleaseHandler {
+                manager: DomRoot::from_ref(self),
+                id,
+                fulfilled: true,
+            })),
+            Some(Box::new(LockReleaseHandler {
+                manager: DomRoot::from_ref(self),
+                id,
+                fulfilled: false,
+            })),
+        );
+        waiting.append_native_handler(&handler, InRealm::Entered(&realm));
+    }
+
+    /// <https://w3c.github.io/web-locks/#release-the-lock>, and settle the promise
+    /// returned by `request()` with the result of the callback.
+    fn release(&self, id: u64, fulfilled: bool, result: HandleValue) {
+        let request = match self.requests.borrow_mut().remove(&id) {
+            Some(request) => request,
+            // The lock was stolen.
+            None => return,
+        };
+        if request.granted {
+            if let Some(client_id) = self.client_id.get() {
+                let _ = self
+                    .global()
+                    .script_to_constellation_chan()
+                    .send(ScriptMsg::ReleaseLock(client_id, id));
+            }
+        }
+        let cx = self.global().get_cx();
+        if fulfilled {
+            request.promise.resolve(cx, result);
+        } else {
+            request.promise.reject(cx, result);
+        }
+    }
+
+    /// <https://w3c.github.io/web-locks/#abort-the-request>
+    pub fn abort_request(&self, id: u64, signal: &AbortSignal) {
+        {
+            let requests = self.requests.borrow();
+            match requests.get(&id) {
+                Some(request) if !request.granted => {},
+                // The lock was granted already.
+                _ => return,
+            }
+        }
+        let request = self
+            .requests
+            .borrow_mut()
+            .remove(&id)
+            .expect("The request is pending");
+        if let Some(client_id) = self.client_id.get() {
+            let _ = self
+                .global()
+                .script_to_constellation_chan()
+                .send(ScriptMsg::ReleaseLock(client_id, id));
+        }
+        let global = self.global();
+        let _ac = enter_realm(&*global);
+        let cx = global.get_cx();
+        rooted!(in(*cx) let reason = signal.reason());
+        request.promise.reject(cx, reason.handle());
+    }
+}
+
+impl LockManagerMethods for LockManager {
+    // https://w3c.github.io/web-locks/#dom-lockmanager-request
+    fn Request(&self, name: DOMString, callback: Rc<LockGrantedCallback>) -> Rc<Promise> {
+        self.Request_(name, &LockOptions::empty(), callback)
+    }
+
+    // https://w3c.github.io/web-locks/#dom-lockmanager-request
+    fn Request_(
+        &self,
+        name: DOMString,
+        options: &LockOptions,
+        callback: Rc<LockGrantedCallback>,
+    ) -> Rc<Promise> {
+        let global = self.global();
+        let promise = Promise::new(&global);
+
+        // Step 2.
+        if let Some(window) = global.downcast::<Window>() {
+            if !window.Document().is_fully_active() {
+                promise.reject_error(Error::InvalidState);
+                return promise;
+            }
+        }
+        // Step 3.
+        if !global.origin().is_tuple() {
+            promise.reject_error(Error::Security);
+            return promise;
+        }
+        // Step 4.
+        if name.starts_with('-') {
+            promise.reject_error(Error::NotSupported);
+            return promise;
+        }
+        // Step 5.
+        if options.steal && options.ifAvailable {
+            promise.reject_error(Error::NotSupported);
+            return promise;
+        }
+        // Step 6.
+        if options.steal && options.mode != LockMode::Exclusive {
+            promise.reject_error(Error::NotSupported);
+            return promise;
+        }
+        // Step 7.
+        if options.signal.is_some() && (options.steal || options.ifAvailable) {
+            promise.reject_error(Error::NotSupported);
+            return promise;
+        }
+        // Step 8.
+        if let Some(signal) = &options.signal {
+            if signal.aborted() {
+                let cx = global.get_cx();
+                let _ac = enter_realm(&*global);
+                rooted!(in(*cx) let reason = signal.reason());
+                promise.reject(cx, reason.handle());
+                return promise;
+            }
+        }
+
+        // Steps 9-10, request a lock.
+        let client_id = self.client_id();
+        let id = self.next_request_id.get();
+        self.next_request_id.set(id + 1);
+        self.requests.borrow_mut().insert(
+            id,
+            LockRequestState {
+                name: name.clone(),
+                mode: options.mode,
+                callback,
+                promise: promise.clone(),
+                granted: false,
+            },
+        );
+        if let Some(signal) = &options.signal {
+            signal.add_abort_algorithm(AbortAlgorithm::LockRequest {
+                manager: Dom::from_ref(self),
+                id,
+            });
+        }
+        let _ = global
+            .script_to_constellation_chan()
+            .send(ScriptMsg::RequestLock(LockRequest {
+                client_id,
+                id,
+                name: name.to_string(),
+                mode: options.mode.into(),
+                if_available: options.ifAvailable,
+                steal: options.steal,
+            }));
+
+        // Step 11.
+        promise
+    }
+
+    // https://w3c.github.io/web-locks/#dom-lockmanager-query
+    fn Query(&self) -> Rc<Promise> {
+        let global = self.global();
+        let promise = Promise::new(&global);
+
+        // Step 1.
+        if let Some(window) = global.downcast::<Window>() {
+            if !window.Document().is_fully_active() {
+                promise.reject_error(Error::InvalidState);
+                return promise;
+            }
+        }
+        // Step 2.
+        if !global.origin().is_tuple() {
+            promise.reject_error(Error::Security);
+            return promise;
+        }
+
+        // Steps 3-4, snapshot the lock state.
+        let client_id = self.client_id();
+        self.queries.borrow_mut().push_back(promise.clone());
+        let _ = global
+            .script_to_constellation_chan()
+            .send(ScriptMsg::QueryLocks(client_id));
+        promise
+    }
+}
+
+/// Releases a lock once the promise returned by its callback settles.
+#[derive(JSTraceable, MallocSizeOf)]
+struct LockReleaseHandler {
+    manager: DomRoot<LockManager>,
+    id: u64,
+    fulfilled: bool,
+}
+
+impl Callback for LockReleaseHandler {
+    fn callback(&self, _cx: JSContext, v: HandleValue, _realm: InRealm) {
+        self.manager.release(self.id, self.fulfilled, v);
+    }
+}
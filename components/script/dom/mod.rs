@@ -209,6 +209,8 @@ pub mod types {
     include!(concat!(env!("OUT_DIR"), "/InterfaceTypes.rs"));
 }
 
+pub mod abortcontroller;
+pub mod abortsignal;
 pub mod abstractworker;
 pub mod abstractworkerglobalscope;
 pub mod activation;
@@ -241,6 +243,8 @@ pub mod bluetoothremotegattserver;
 pub mod bluetoothremotegattservice;
 pub mod bluetoothuuid;
 pub mod broadcastchannel;
+pub mod cache;
+pub mod cachestorage;
 pub mod canvasgradient;
 pub mod canvaspattern;
 pub mod canvasrenderingcontext2d;
@@ -249,13 +253,18 @@ pub mod channelmergernode;
 pub mod channelsplitternode;
 pub mod characterdata;
 pub mod client;
+pub mod clipboard;
+pub mod clipboardevent;
+pub mod clipboarditem;
 pub mod closeevent;
 pub mod comment;
 pub mod compositionevent;
+pub mod compressionstream;
 pub mod console;
 pub mod constantsourcenode;
 mod create;
 pub mod crypto;
+pub mod cryptokey;
 pub mod css;
 pub mod cssconditionrule;
 pub mod cssfontfacerule;
@@ -275,6 +284,10 @@ pub mod csssupportsrule;
 pub mod cssviewportrule;
 pub mod customelementregistry;
 pub mod customevent;
+pub mod datatransfer;
+pub mod datatransferitem;
+pub mod datatransferitemlist;
+pub mod decompressionstream;
 pub mod dedicatedworkerglobalscope;
 pub mod dissimilaroriginlocation;
 pub mod dissimilaroriginwindow;
@@ -295,6 +308,7 @@ pub mod domrectreadonly;
 pub mod domstringlist;
 pub mod domstringmap;
 pub mod domtokenlist;
+pub mod dragevent;
 pub mod dynamicmoduleowner;
 pub mod element;
 pub mod errorevent;
@@ -305,6 +319,7 @@ pub mod extendableevent;
 pub mod extendablemessageevent;
 pub mod fakexrdevice;
 pub mod fakexrinputcontroller;
+pub mod fetchevent;
 pub mod file;
 pub mod filelist;
 pub mod filereader;
@@ -426,12 +441,28 @@ pub mod htmltrackelement;
 pub mod htmlulistelement;
 pub mod htmlunknownelement;
 pub mod htmlvideoelement;
+pub mod idbcursor;
+pub mod idbcursorwithvalue;
+pub mod idbdatabase;
+pub mod idbfactory;
+pub mod idbindex;
+pub mod idbkeyrange;
+pub mod idbobjectstore;
+pub mod idbopendbrequest;
+pub mod idbrequest;
+pub mod idbtransaction;
+pub mod idbversionchangeevent;
 pub mod identityhub;
+pub mod idledeadline;
 pub mod imagebitmap;
 pub mod imagedata;
 pub mod inputevent;
+pub mod intersectionobserver;
+pub mod intersectionobserverentry;
 pub mod keyboardevent;
 pub mod location;
+pub mod lock;
+pub mod lockmanager;
 pub mod mediadeviceinfo;
 pub mod mediadevices;
 pub mod mediaelementaudiosourcenode;
@@ -486,6 +517,7 @@ pub mod permissions;
 pub mod permissionstatus;
 pub mod plugin;
 pub mod pluginarray;
+pub mod pointerevent;
 pub mod popstateevent;
 pub mod processinginstruction;
 pub mod progressevent;
@@ -497,6 +529,9 @@ pub mod range;
 pub mod raredata;
 pub mod readablestream;
 pub mod request;
+pub mod resizeobserver;
+pub mod resizeobserverentry;
+pub mod resizeobserversize;
 pub mod response;
 pub mod rtcdatachannel;
 pub mod rtcdatachannelevent;
@@ -509,7 +544,9 @@ pub(crate) mod rtcrtpsender;
 pub(crate) mod rtcrtptransceiver;
 pub mod rtcsessiondescription;
 pub mod rtctrackevent;
+pub mod scheduler;
 pub mod screen;
+pub mod securitypolicyviolationevent;
 pub mod selection;
 pub mod serviceworker;
 pub mod serviceworkercontainer;
@@ -517,6 +554,8 @@ pub mod serviceworkerglobalscope;
 pub mod serviceworkerregistration;
 pub mod servoparser;
 pub mod shadowroot;
+pub mod sharedworker;
+pub mod sharedworkerglobalscope;
 pub mod stereopannernode;
 pub mod storage;
 pub mod storageevent;
@@ -524,9 +563,13 @@ pub mod stylepropertymapreadonly;
 pub mod stylesheet;
 pub mod stylesheetlist;
 pub mod submitevent;
+pub mod subtlecrypto;
 pub mod svgelement;
 pub mod svggraphicselement;
 pub mod svgsvgelement;
+pub mod taskcontroller;
+pub mod taskprioritychangeevent;
+pub mod tasksignal;
 pub mod testbinding;
 pub mod testbindingiterable;
 pub mod testbindingpairiterable;
@@ -537,7 +580,9 @@ pub mod testworkletglobalscope;
 pub mod text;
 pub mod textcontrol;
 pub mod textdecoder;
+pub mod textdecoderstream;
 pub mod textencoder;
+pub mod textencoderstream;
 pub mod textmetrics;
 pub mod texttrack;
 pub mod texttrackcue;
@@ -548,6 +593,8 @@ pub mod touch;
 pub mod touchevent;
 pub mod touchlist;
 pub mod trackevent;
+pub mod transformstream;
+pub mod transformstreamdefaultcontroller;
 pub mod transitionevent;
 pub mod treewalker;
 pub mod uievent;
@@ -595,6 +642,9 @@ pub mod workerlocation;
 pub mod workernavigator;
 pub mod worklet;
 pub mod workletglobalscope;
+pub mod writablestream;
+pub mod writablestreamdefaultcontroller;
+pub mod writablestreamdefaultwriter;
 pub mod xmldocument;
 pub mod xmlhttprequest;
 pub mod xmlhttprequesteventtarget;
@@ -8,8 +8,10 @@ use crate::dom::bindings::root::{DomRoot, MutNullableDom};
 use crate::dom::bindings::str::DOMString;
 use crate::dom::bindings::utils::to_frozen_array;
 use crate::dom::bluetooth::Bluetooth;
+use crate::dom::clipboard::Clipboard;
 use crate::dom::gamepadlist::GamepadList;
 use crate::dom::gpu::GPU;
+use crate::dom::lockmanager::LockManager;
 use crate::dom::mediadevices::MediaDevices;
 use crate::dom::mediasession::MediaSession;
 use crate::dom::mimetypearray::MimeTypeArray;
@@ -36,6 +38,7 @@ pub struct Navigator {
     permissions: MutNullableDom<Permissions>,
     mediasession: MutNullableDom<MediaSession>,
     gpu: MutNullableDom<GPU>,
+    clipboard: MutNullableDom<Clipboard>,
 }
 
 impl Navigator {
@@ -52,6 +55,7 @@ impl Navigator {
             permissions: Default::default(),
             mediasession: Default::default(),
             gpu: Default::default(),
+            clipboard: Default::default(),
         }
     }
 
@@ -173,6 +177,11 @@ impl NavigatorMethods for Navigator {
             .or_init(|| Permissions::new(&self.global()))
     }
 
+    // https://w3c.github.io/web-locks/#dom-navigatorlocks-locks
+    fn Locks(&self) -> DomRoot<LockManager> {
+        self.global().lock_manager()
+    }
+
     /// https://immersive-web.github.io/webxr/#dom-navigator-xr
     fn Xr(&self) -> DomRoot<XRSystem> {
         self.xr
@@ -205,4 +214,9 @@ impl NavigatorMethods for Navigator {
     fn Gpu(&self) -> DomRoot<GPU> {
         self.gpu.or_init(|| GPU::new(&self.global()))
     }
+
+    // https://w3c.github.io/clipboard-apis/#dom-navigator-clipboard
+    fn Clipboard(&self) -> DomRoot<Clipboard> {
+        self.clipboard.or_init(|| Clipboard::new(&self.global()))
+    }
 }
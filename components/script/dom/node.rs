@@ -733,6 +733,52 @@ impl Node {
             .any(|ancestor| &*ancestor == self)
     }
 
+    /// Whether this node is inert because a modal dialog blocks the rest of its document.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#inert>
+    pub fn is_inert(&self) -> bool {
+        self.owner_doc()
+            .active_modal_dialog()
+            .map_or(false, |dialog| {
+                !dialog
+                    .upcast::<Node>()
+                    .is_shadow_including_inclusive_ancestor_of(self)
+            })
+    }
+
+    /// The editing host that this node can be edited in, if any: the closest inclusive
+    /// ancestor whose `contenteditable` attribute is in the true state, or the document
+    /// element if design mode is enabled.
+    ///
+    /// <https://w3c.github.io/editing/docs/execCommand/#editing-host-of>
+    pub fn editing_host(&self) -> Option<DomRoot<Element>> {
+        for ancestor in self.inclusive_ancestors(ShadowIncluding::No) {
+            let element = match ancestor.downcast::<HTMLElement>() {
+                Some(element) => element,
+                None => continue,
+            };
+            match element.content_editable_state() {
+                Some(true) => return Some(DomRoot::from_ref(element.upcast())),
+                Some(false) => return None,
+                None => {},
+            }
+        }
+
+        let document = self.owner_doc();
+        if !document.is_design_mode() {
+            return None;
+        }
+        document
+            .GetDocumentElement()
+            .filter(|root| root.upcast::<Node>().is_inclusive_ancestor_of(self))
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#editing-host>
+    pub fn is_editing_host(&self) -> bool {
+        self.editing_host()
+            .map_or(false, |host| host.upcast::<Node>() == self)
+    }
+
     pub fn following_siblings(&self) -> impl Iterator<Item = DomRoot<Node>> {
         SimpleNodeIterator {
             current: self.GetNextSibling(),
@@ -6,11 +6,13 @@ use crate::dom::bindings::cell::{ref_filter_map, DomRefCell, Ref};
 use crate::dom::bindings::codegen::Bindings::OffscreenCanvasBinding::{
     OffscreenCanvasMethods, OffscreenRenderingContext,
 };
-use crate::dom::bindings::error::Fallible;
+use crate::dom::bindings::error::{Error, Fallible};
 use crate::dom::bindings::reflector::reflect_dom_object;
 use crate::dom::bindings::reflector::DomObject;
 use crate::dom::bindings::root::{Dom, DomRoot};
 use crate::dom::bindings::str::DOMString;
+use crate::dom::bindings::structuredclone::StructuredDataHolder;
+use crate::dom::bindings::transferable::Transferable;
 use crate::dom::eventtarget::EventTarget;
 use crate::dom::globalscope::GlobalScope;
 use crate::dom::htmlcanvaselement::HTMLCanvasElement;
@@ -20,6 +22,7 @@ use canvas_traits::canvas::{CanvasMsg, FromScriptMsg};
 use dom_struct::dom_struct;
 use euclid::default::Size2D;
 use ipc_channel::ipc::IpcSharedMemory;
+use js::jsapi::MutableHandleObject;
 use js::rust::HandleValue;
 use profile_traits::ipc;
 use std::cell::Cell;
@@ -39,6 +42,9 @@ pub struct OffscreenCanvas {
     height: Cell<u64>,
     context: DomRefCell<Option<OffscreenCanvasContext>>,
     placeholder: Option<Dom<HTMLCanvasElement>>,
+    /// Whether the canvas was transferred to another global,
+    /// which is the "detached" context mode.
+    detached: Cell<bool>,
 }
 
 impl OffscreenCanvas {
@@ -53,6 +59,7 @@ impl OffscreenCanvas {
             height: Cell::new(height),
             context: DomRefCell::new(None),
             placeholder: placeholder.map(Dom::from_ref),
+            detached: Cell::new(false),
         }
     }
 
@@ -143,6 +150,49 @@ impl OffscreenCanvas {
     }
 }
 
+impl Transferable for OffscreenCanvas {
+    /// <https://html.spec.whatwg.org/multipage/#the-offscreencanvas-interface:transfer-steps>
+    fn transfer(&self, _sc_holder: &mut StructuredDataHolder) -> Result<u64, ()> {
+        // Step 1.
+        if self.detached.get() || self.context.borrow().is_some() {
+            return Err(());
+        }
+
+        // The dimensions are stored in the extra data, which fits any bitmap
+        // that could actually be allocated.
+        let size = self.get_size();
+        if size.width > u32::MAX as u64 || size.height > u32::MAX as u64 {
+            return Err(());
+        }
+
+        // Step 2.
+        self.detached.set(true);
+
+        // Steps 3-4.
+        self.width.set(0);
+        self.height.set(0);
+
+        // Step 5.
+        Ok(size.width << 32 | size.height)
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#the-offscreencanvas-interface:transfer-receiving-steps>
+    fn transfer_receive(
+        owner: &GlobalScope,
+        _sc_holder: &mut StructuredDataHolder,
+        extra_data: u64,
+        return_object: MutableHandleObject,
+    ) -> Result<(), ()> {
+        // Step 1.
+        let width = extra_data >> 32;
+        let height = extra_data & u32::MAX as u64;
+        let canvas = OffscreenCanvas::new(owner, width, height, None);
+
+        return_object.set(canvas.reflector().rootable().get());
+        Ok(())
+    }
+}
+
 impl OffscreenCanvasMethods for OffscreenCanvas {
     // https://html.spec.whatwg.org/multipage/#dom-offscreencanvas-getcontext
     fn GetContext(
@@ -150,8 +200,11 @@ impl OffscreenCanvasMethods for OffscreenCanvas {
         _cx: JSContext,
         id: DOMString,
         _options: HandleValue,
-    ) -> Option<OffscreenRenderingContext> {
-        match &*id {
+    ) -> Fallible<Option<OffscreenRenderingContext>> {
+        if self.detached.get() {
+            return Err(Error::InvalidState);
+        }
+        Ok(match &*id {
             "2d" => self
                 .get_or_init_2d_context()
                 .map(OffscreenRenderingContext::OffscreenCanvasRenderingContext2D),
@@ -162,7 +215,7 @@ impl OffscreenCanvasMethods for OffscreenCanvas {
                 .get_or_init_webgl2_context(cx, options)
                 .map(OffscreenRenderingContext::WebGL2RenderingContext),*/
             _ => None,
-        }
+        })
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-offscreencanvas-width
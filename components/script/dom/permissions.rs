@@ -323,6 +323,29 @@ pub fn get_descriptor_permission_state(
     state
 }
 
+// https://w3c.github.io/permissions/#request-permission-to-use
+pub fn request_permission_to_use(
+    permission_name: PermissionName,
+    global: &GlobalScope,
+) -> PermissionState {
+    // Step 1 - 2.
+    let state = get_descriptor_permission_state(permission_name, Some(global));
+    if state != PermissionState::Prompt {
+        return state;
+    }
+
+    // Step 3 - 4.
+    let prompt = PermissionPrompt::Request(embedder_traits::PermissionName::from(permission_name));
+    let state = prompt_user_from_embedder(prompt, global);
+    global
+        .permission_state_invocation_results()
+        .borrow_mut()
+        .insert(permission_name.to_string(), state);
+
+    // Step 5.
+    state
+}
+
 // https://w3c.github.io/permissions/#allowed-in-non-secure-contexts
 fn allowed_in_nonsecure_contexts(permission_name: &PermissionName) -> bool {
     match *permission_name {
@@ -348,6 +371,9 @@ fn allowed_in_nonsecure_contexts(permission_name: &PermissionName) -> bool {
         PermissionName::Bluetooth => false,
         // https://storage.spec.whatwg.org/#dom-permissionname-persistent-storage
         PermissionName::Persistent_storage => false,
+        // https://w3c.github.io/clipboard-apis/#clipboard-permissions
+        PermissionName::Clipboard_read => false,
+        PermissionName::Clipboard_write => false,
     }
 }
 
@@ -384,6 +410,8 @@ impl From<PermissionName> for embedder_traits::PermissionName {
             PermissionName::Persistent_storage => {
                 embedder_traits::PermissionName::PersistentStorage
             },
+            PermissionName::Clipboard_read => embedder_traits::PermissionName::ClipboardRead,
+            PermissionName::Clipboard_write => embedder_traits::PermissionName::ClipboardWrite,
         }
     }
 }
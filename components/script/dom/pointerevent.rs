@@ -0,0 +1,303 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::MouseEventBinding::MouseEventMethods;
+use crate::dom::bindings::codegen::Bindings::PointerEventBinding;
+use crate::dom::bindings::codegen::Bindings::PointerEventBinding::PointerEventMethods;
+use crate::dom::bindings::error::Fallible;
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::num::Finite;
+use crate::dom::bindings::reflector::reflect_dom_object;
+use crate::dom::bindings::root::DomRoot;
+use crate::dom::bindings::str::DOMString;
+use crate::dom::event::{Event, EventBubbles, EventCancelable};
+use crate::dom::eventtarget::EventTarget;
+use crate::dom::mouseevent::MouseEvent;
+use crate::dom::window::Window;
+use dom_struct::dom_struct;
+use euclid::default::Point2D;
+use script_traits::{PenState, TouchId};
+use std::cell::Cell;
+
+/// The pointer id of the mouse, which is always an active pointer.
+///
+/// <https://w3c.github.io/pointerevents/#dom-pointerevent-pointerid>
+pub const MOUSE_POINTER_ID: i32 = 1;
+
+/// The pointer id of the pen.
+pub const PEN_POINTER_ID: i32 = 2;
+
+/// The pointer id of a touch point. Touch points are numbered after the mouse and pen.
+pub fn touch_pointer_id(touch_id: TouchId) -> i32 {
+    touch_id.0.wrapping_add(PEN_POINTER_ID + 1)
+}
+
+/// <https://w3c.github.io/pointerevents/#dom-pointerevent-pointertype>
+#[derive(Clone, Copy, Debug, JSTraceable, MallocSizeOf, PartialEq)]
+pub enum PointerType {
+    Mouse,
+    Pen,
+    Touch,
+}
+
+impl PointerType {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            PointerType::Mouse => "mouse",
+            PointerType::Pen => "pen",
+            PointerType::Touch => "touch",
+        }
+    }
+}
+
+/// The state of an input device when it generates a pointer event.
+#[derive(Clone, Copy, Debug)]
+pub struct PointerData {
+    pub pointer_id: i32,
+    pub pointer_type: PointerType,
+    pub is_primary: bool,
+    pub client_point: Point2D<f32>,
+    /// <https://w3c.github.io/pointerevents/#the-button-property>
+    pub button: i16,
+    pub buttons: u16,
+    pub pressure: f32,
+    pub tilt_x: i32,
+    pub tilt_y: i32,
+    pub twist: i32,
+}
+
+impl PointerData {
+    pub fn mouse(client_point: Point2D<f32>, button: i16, buttons: u16) -> PointerData {
+        PointerData {
+            pointer_id: MOUSE_POINTER_ID,
+            pointer_type: PointerType::Mouse,
+            is_primary: true,
+            client_point,
+            button,
+            buttons,
+            // https://w3c.github.io/pointerevents/#dom-pointerevent-pressure
+            pressure: if buttons == 0 { 0. } else { 0.5 },
+            tilt_x: 0,
+            tilt_y: 0,
+            twist: 0,
+        }
+    }
+
+    pub fn pen(
+        client_point: Point2D<f32>,
+        button: i16,
+        buttons: u16,
+        pen: PenState,
+    ) -> PointerData {
+        PointerData {
+            pointer_id: PEN_POINTER_ID,
+            pointer_type: PointerType::Pen,
+            is_primary: true,
+            client_point,
+            button,
+            buttons,
+            pressure: pen.pressure,
+            tilt_x: pen.tilt_x,
+            tilt_y: pen.tilt_y,
+            twist: pen.twist,
+        }
+    }
+
+    pub fn touch(
+        touch_id: TouchId,
+        is_primary: bool,
+        client_point: Point2D<f32>,
+        button: i16,
+        buttons: u16,
+    ) -> PointerData {
+        PointerData {
+            pointer_id: touch_pointer_id(touch_id),
+            pointer_type: PointerType::Touch,
+            is_primary,
+            client_point,
+            button,
+            buttons,
+            pressure: if buttons == 0 { 0. } else { 0.5 },
+            tilt_x: 0,
+            tilt_y: 0,
+            twist: 0,
+        }
+    }
+}
+
+#[dom_struct]
+pub struct PointerEvent {
+    mouseevent: MouseEvent,
+    pointer_id: Cell<i32>,
+    width: Cell<Finite<f64>>,
+    height: Cell<Finite<f64>>,
+    pressure: Cell<Finite<f32>>,
+    tangential_pressure: Cell<Finite<f32>>,
+    tilt_x: Cell<i32>,
+    tilt_y: Cell<i32>,
+    twist: Cell<i32>,
+    pointer_type: DomRefCell<DOMString>,
+    is_primary: Cell<bool>,
+}
+
+impl PointerEvent {
+    fn new_inherited() -> PointerEvent {
+        PointerEvent {
+            mouseevent: MouseEvent::new_inherited(),
+            pointer_id: Cell::new(0),
+            width: Cell::new(Finite::wrap(1.)),
+            height: Cell::new(Finite::wrap(1.)),
+            pressure: Cell::new(Finite::wrap(0.)),
+            tangential_pressure: Cell::new(Finite::wrap(0.)),
+            tilt_x: Cell::new(0),
+            tilt_y: Cell::new(0),
+            twist: Cell::new(0),
+            pointer_type: DomRefCell::new(DOMString::new()),
+            is_primary: Cell::new(false),
+        }
+    }
+
+    pub fn new_uninitialized(window: &Window) -> DomRoot<PointerEvent> {
+        reflect_dom_object(Box::new(PointerEvent::new_inherited()), window)
+    }
+
+    /// Creates a trusted pointer event for an input device.
+    pub fn new_for_pointer(
+        window: &Window,
+        type_: DOMString,
+        can_bubble: EventBubbles,
+        cancelable: EventCancelable,
+        data: &PointerData,
+        related_target: Option<&EventTarget>,
+    ) -> DomRoot<PointerEvent> {
+        let client_x = data.client_point.x as i32;
+        let client_y = data.client_point.y as i32;
+        let ev = PointerEvent::new_uninitialized(window);
+        ev.mouseevent.initialize_mouse_event(
+            type_,
+            can_bubble,
+            cancelable,
+            Some(window),
+            0,
+            client_x,
+            client_y, // TODO: Get real screen coordinates?
+            client_x,
+            client_y,
+            false,
+            false,
+            false,
+            false,
+            data.button,
+            data.buttons,
+            related_target,
+            None,
+        );
+        ev.pointer_id.set(data.pointer_id);
+        ev.pressure.set(Finite::wrap(data.pressure));
+        ev.tilt_x.set(data.tilt_x);
+        ev.tilt_y.set(data.tilt_y);
+        ev.twist.set(data.twist);
+        *ev.pointer_type.borrow_mut() = DOMString::from(data.pointer_type.as_str());
+        ev.is_primary.set(data.is_primary);
+        ev.upcast::<Event>().set_trusted(true);
+        ev
+    }
+
+    #[allow(non_snake_case)]
+    pub fn Constructor(
+        window: &Window,
+        type_: DOMString,
+        init: &PointerEventBinding::PointerEventInit,
+    ) -> Fallible<DomRoot<PointerEvent>> {
+        let ev = PointerEvent::new_uninitialized(window);
+        ev.mouseevent.initialize_mouse_event(
+            type_,
+            EventBubbles::from(init.parent.parent.parent.parent.bubbles),
+            EventCancelable::from(init.parent.parent.parent.parent.cancelable),
+            init.parent.parent.parent.view.as_deref(),
+            init.parent.parent.parent.detail,
+            init.parent.screenX,
+            init.parent.screenY,
+            init.parent.clientX,
+            init.parent.clientY,
+            init.parent.parent.ctrlKey,
+            init.parent.parent.altKey,
+            init.parent.parent.shiftKey,
+            init.parent.parent.metaKey,
+            init.parent.button,
+            init.parent.buttons,
+            init.parent.relatedTarget.as_deref(),
+            None,
+        );
+        ev.pointer_id.set(init.pointerId);
+        ev.width.set(init.width);
+        ev.height.set(init.height);
+        ev.pressure.set(init.pressure);
+        ev.tangential_pressure.set(init.tangentialPressure);
+        ev.tilt_x.set(init.tiltX);
+        ev.tilt_y.set(init.tiltY);
+        ev.twist.set(init.twist);
+        *ev.pointer_type.borrow_mut() = init.pointerType.clone();
+        ev.is_primary.set(init.isPrimary);
+        Ok(ev)
+    }
+}
+
+impl PointerEventMethods for PointerEvent {
+    // https://w3c.github.io/pointerevents/#dom-pointerevent-pointerid
+    fn PointerId(&self) -> i32 {
+        self.pointer_id.get()
+    }
+
+    // https://w3c.github.io/pointerevents/#dom-pointerevent-width
+    fn Width(&self) -> Finite<f64> {
+        self.width.get()
+    }
+
+    // https://w3c.github.io/pointerevents/#dom-pointerevent-height
+    fn Height(&self) -> Finite<f64> {
+        self.height.get()
+    }
+
+    // https://w3c.github.io/pointerevents/#dom-pointerevent-pressure
+    fn Pressure(&self) -> Finite<f32> {
+        self.pressure.get()
+    }
+
+    // https://w3c.github.io/pointerevents/#dom-pointerevent-tangentialpressure
+    fn TangentialPressure(&self) -> Finite<f32> {
+        self.tangential_pressure.get()
+    }
+
+    // https://w3c.github.io/pointerevents/#dom-pointerevent-tiltx
+    fn TiltX(&self) -> i32 {
+        self.tilt_x.get()
+    }
+
+    // https://w3c.github.io/pointerevents/#dom-pointerevent-tilty
+    fn TiltY(&self) -> i32 {
+        self.tilt_y.get()
+    }
+
+    // https://w3c.github.io/pointerevents/#dom-pointerevent-twist
+    fn Twist(&self) -> i32 {
+        self.twist.get()
+    }
+
+    // https://w3c.github.io/pointerevents/#dom-pointerevent-pointertype
+    fn PointerType(&self) -> DOMString {
+        self.pointer_type.borrow().clone()
+    }
+
+    // https://w3c.github.io/pointerevents/#dom-pointerevent-isprimary
+    fn IsPrimary(&self) -> bool {
+        self.is_primary.get()
+    }
+
+    // https://dom.spec.whatwg.org/#dom-event-istrusted
+    fn IsTrusted(&self) -> bool {
+        self.mouseevent.IsTrusted()
+    }
+}
@@ -242,6 +242,15 @@ impl Promise {
         obj
     }
 
+    /// Adds reactions that do nothing, so that a rejection of the promise isn't reported
+    /// as unhandled.
+    pub fn mark_as_handled(&self) {
+        let global = self.global();
+        let realm = enter_realm(&*global);
+        let handler = PromiseNativeHandler::new(&global, None, None);
+        self.append_native_handler(&handler, InRealm::Entered(&realm));
+    }
+
     #[allow(unsafe_code)]
     pub fn append_native_handler(&self, handler: &PromiseNativeHandler, _comp: InRealm) {
         let _ais = AutoEntryScript::new(&*handler.global());
@@ -6,6 +6,7 @@ use crate::dom::bindings::root::Dom;
 use crate::dom::customelementregistry::{
     CustomElementDefinition, CustomElementReaction, CustomElementState,
 };
+use crate::dom::intersectionobserver::IntersectionObserverRegistration;
 use crate::dom::mutationobserver::RegisteredObserver;
 use crate::dom::node::UniqueId;
 use crate::dom::shadowroot::ShadowRoot;
@@ -50,4 +51,6 @@ pub struct ElementRareData {
     pub name_attribute: Option<Atom>,
     /// The client rect reported by layout.
     pub client_rect: Option<LayoutValue<Rect<i32>>>,
+    /// <https://w3c.github.io/IntersectionObserver/#dom-element-registeredintersectionobservers-slot>
+    pub registered_intersection_observers: Vec<IntersectionObserverRegistration>,
 }
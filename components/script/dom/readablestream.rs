@@ -2,36 +2,47 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
-use crate::dom::bindings::conversions::{ConversionBehavior, ConversionResult};
-use crate::dom::bindings::error::Error;
+use crate::dom::abortsignal::{AbortAlgorithm, AbortSignal};
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::ReadableStreamBinding::StreamPipeOptions;
+use crate::dom::bindings::conversions::{root_from_object, ConversionBehavior, ConversionResult};
+use crate::dom::bindings::error::{take_exception, throw_dom_exception, Error, Fallible};
 use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
 use crate::dom::bindings::root::DomRoot;
 use crate::dom::bindings::settings_stack::{AutoEntryScript, AutoIncumbentScript};
 use crate::dom::bindings::utils::get_dictionary_property;
 use crate::dom::globalscope::GlobalScope;
 use crate::dom::promise::Promise;
+use crate::dom::promisenativehandler::{Callback, PromiseNativeHandler};
+use crate::dom::writablestream::{WritableStream, WritableStreamState};
+use crate::dom::writablestreamdefaultwriter::WritableStreamDefaultWriter;
 use crate::js::conversions::FromJSValConvertible;
-use crate::realms::{enter_realm, InRealm};
+use crate::realms::{enter_realm, AlreadyInRealm, InRealm};
 use crate::script_runtime::JSContext as SafeJSContext;
 use dom_struct::dom_struct;
+use ipc_channel::ipc::IpcSender;
 use js::glue::{
     CreateReadableStreamUnderlyingSource, DeleteReadableStreamUnderlyingSource,
     ReadableStreamUnderlyingSourceTraps,
 };
-use js::jsapi::{HandleObject, HandleValue, Heap, JSContext, JSObject};
+use js::jsapi::{CallArgs, HandleObject, HandleValue, Heap, JSContext, JSObject, JSPROP_ENUMERATE};
 use js::jsapi::{
-    IsReadableStream, NewReadableExternalSourceStreamObject, ReadableStreamClose,
-    ReadableStreamDefaultReaderRead, ReadableStreamError, ReadableStreamGetReader,
-    ReadableStreamIsDisturbed, ReadableStreamIsLocked, ReadableStreamIsReadable,
-    ReadableStreamReaderMode, ReadableStreamReaderReleaseLock, ReadableStreamUnderlyingSource,
+    IsReadableStream, NewReadableExternalSourceStreamObject, ReadableStreamCancel,
+    ReadableStreamClose, ReadableStreamDefaultReaderRead, ReadableStreamError,
+    ReadableStreamGetReader, ReadableStreamIsDisturbed, ReadableStreamIsLocked,
+    ReadableStreamIsReadable, ReadableStreamReaderCancel, ReadableStreamReaderMode,
+    ReadableStreamReaderReleaseLock, ReadableStreamUnderlyingSource,
     ReadableStreamUpdateDataAvailableFromSource, UnwrapReadableStream,
 };
 use js::jsval::JSVal;
-use js::jsval::UndefinedValue;
+use js::jsval::{ObjectValue, UndefinedValue};
+use js::rust::wrappers::JS_DefineFunction;
+use js::rust::HandleObject as SafeHandleObject;
 use js::rust::HandleValue as SafeHandleValue;
-use js::rust::IntoHandle;
+use js::rust::{IntoHandle, MutableHandleValue};
+use net_traits::ResponseBodyControlMsg;
 use std::cell::{Cell, RefCell};
-use std::os::raw::c_void;
+use std::os::raw::{c_char, c_void};
 use std::ptr::{self, NonNull};
 use std::rc::Rc;
 use std::slice;
@@ -46,6 +57,11 @@ static UNDERLYING_SOURCE_TRAPS: ReadableStreamUnderlyingSourceTraps =
         finalize: Some(finalize),
     };
 
+// TODO: byte streams, with ReadableStreamBYOBReader and ReadableByteStreamController.
+// The engine's streams don't support `type: "bytes"` or `getReader({mode: "byob"})`,
+// so these need either an engine update or a ReadableStream implemented in Servo,
+// as WritableStream and TransformStream are.
+// https://streams.spec.whatwg.org/#byob-reader-class
 #[dom_struct]
 pub struct ReadableStream {
     reflector_: Reflector,
@@ -166,6 +182,15 @@ impl ReadableStream {
             .enqueue_chunk(cx, handle, bytes);
     }
 
+    /// Pauses the reading of the source of the stream from the network over `chan`
+    /// while too much of it is queued, and resumes it once it is read.
+    pub fn set_back_pressure_chan(&self, chan: IpcSender<ResponseBodyControlMsg>) {
+        self.external_underlying_source
+            .as_ref()
+            .expect("No external source to apply back-pressure to.")
+            .set_back_pressure_chan(chan);
+    }
+
     #[allow(unsafe_code)]
     pub fn error_native(&self, error: Error) {
         let global = self.global();
@@ -183,6 +208,65 @@ impl ReadableStream {
         }
     }
 
+    /// Error the stream with a JS value, unless it is already closed or errored.
+    #[allow(unsafe_code)]
+    pub fn error_if_readable(&self, error: SafeHandleValue) {
+        let global = self.global();
+        let _ar = enter_realm(&*global);
+        let cx = global.get_cx();
+
+        if !self.is_readable() {
+            return;
+        }
+        unsafe {
+            ReadableStreamError(*cx, self.js_stream.handle(), error.into_handle());
+        }
+    }
+
+    /// Is the stream neither closed nor errored?
+    #[allow(unsafe_code)]
+    pub fn is_readable(&self) -> bool {
+        let cx = self.global().get_cx();
+        let mut readable = false;
+
+        unsafe { ReadableStreamIsReadable(*cx, self.js_stream.handle(), &mut readable) && readable }
+    }
+
+    /// Cancel the stream, through the reader acquired with `start_reading` if there is one.
+    /// <https://streams.spec.whatwg.org/#readable-stream-cancel>
+    #[allow(unsafe_code)]
+    pub fn cancel(&self, reason: SafeHandleValue) -> Rc<Promise> {
+        let global = self.global();
+        let _ar = enter_realm(&*global);
+        let cx = global.get_cx();
+
+        unsafe {
+            if !self.has_reader.get() {
+                rooted!(in(*cx) let promise_obj = ReadableStreamCancel(
+                    *cx,
+                    self.js_stream.handle(),
+                    reason.into_handle(),
+                ));
+                if !promise_obj.is_null() {
+                    return Promise::new_with_js_promise(promise_obj.handle(), cx);
+                }
+            } else if ReadableStreamReaderCancel(*cx, self.js_reader.handle(), reason.into_handle())
+            {
+                // Note: cancelling through a reader doesn't give us the promise of the
+                // underlying source, so this is as far as we can wait.
+                let promise = Promise::new(&global);
+                promise.resolve_native(&());
+                return promise;
+            }
+
+            let promise = Promise::new(&global);
+            rooted!(in(*cx) let mut error = UndefinedValue());
+            take_exception(&global, Error::JSFailed, error.handle_mut());
+            promise.reject(cx, error.handle());
+            promise
+        }
+    }
+
     #[allow(unsafe_code)]
     pub fn close_native(&self) {
         let global = self.global();
@@ -214,12 +298,23 @@ impl ReadableStream {
 
     /// Acquires a reader and locks the stream,
     /// must be done before `read_a_chunk`.
-    #[allow(unsafe_code)]
     pub fn start_reading(&self) -> Result<(), ()> {
         if self.is_locked() || self.is_disturbed() {
             return Err(());
         }
 
+        self.acquire_reader();
+        Ok(())
+    }
+
+    /// Whether a reader was acquired natively, with `start_reading`.
+    pub fn has_native_reader(&self) -> bool {
+        self.has_reader.get()
+    }
+
+    /// Acquires a reader, even if the stream was already disturbed by script.
+    #[allow(unsafe_code)]
+    fn acquire_reader(&self) {
         let global = self.global();
         let _ar = enter_realm(&*global);
         let cx = global.get_cx();
@@ -236,7 +331,6 @@ impl ReadableStream {
         }
 
         self.has_reader.set(true);
-        Ok(())
     }
 
     /// Read a chunk from the stream,
@@ -314,6 +408,63 @@ impl ReadableStream {
 
         locked_or_disturbed
     }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-pipe-to>
+    #[allow(unsafe_code)]
+    pub fn pipe_to(&self, dest: &WritableStream, options: &StreamPipeOptions) -> Rc<Promise> {
+        let global = self.global();
+        let _ar = enter_realm(&*global);
+        let cx = global.get_cx();
+
+        // Steps 1-5.
+        let promise = Promise::new(&global);
+        if self.is_locked() || dest.is_locked() {
+            promise.reject_error(Error::Type(
+                "The source or destination is locked".to_owned(),
+            ));
+            return promise;
+        }
+
+        // Steps 6-10.
+        self.acquire_reader();
+        let writer = WritableStreamDefaultWriter::new(&global, dest)
+            .expect("The destination stream isn't locked");
+        let pipe = Rc::new(PipeTo {
+            source: DomRoot::from_ref(self),
+            dest: DomRoot::from_ref(dest),
+            writer,
+            signal: options.signal.clone(),
+            prevent_close: options.preventClose,
+            prevent_abort: options.preventAbort,
+            prevent_cancel: options.preventCancel,
+            promise: promise.clone(),
+            shutting_down: Cell::new(false),
+            reading: Cell::new(false),
+            pending_write: DomRefCell::new(None),
+            error: Heap::default(),
+            has_error: Cell::new(false),
+        });
+
+        // Step 14.
+        if let Some(ref signal) = options.signal {
+            if signal.aborted() {
+                pipe.abort(signal);
+                return promise;
+            }
+            signal.add_abort_algorithm(AbortAlgorithm::StreamPipe(pipe.clone()));
+        }
+
+        // Step 15.
+        pipe.react(
+            &pipe.writer.closed_promise(),
+            None,
+            Some(PipeReaction::DestErrored),
+        );
+        pipe.pipe_loop(cx);
+
+        // Step 16.
+        promise
+    }
 }
 
 #[allow(unsafe_code)]
@@ -346,12 +497,14 @@ unsafe extern "C" fn write_into_read_request_buffer(
 
 #[allow(unsafe_code)]
 unsafe extern "C" fn cancel(
-    _source: *const c_void,
+    source: *const c_void,
     _cx: *mut JSContext,
     _stream: HandleObject,
     _reason: HandleValue,
     _resolve_to: *mut JSVal,
 ) {
+    let source = &*(source as *const ExternalUnderlyingSourceController);
+    source.release_back_pressure();
 }
 
 #[allow(unsafe_code)]
@@ -379,6 +532,8 @@ pub enum ExternalUnderlyingSource {
     Blob(usize),
     /// A fetch response as underlying source.
     FetchResponse,
+    /// The body of a request handed to a service worker, read from its client.
+    FetchRequest,
 }
 
 #[derive(JSTraceable, MallocSizeOf)]
@@ -390,6 +545,43 @@ struct ExternalUnderlyingSourceController {
     closed: Cell<bool>,
     /// Does this stream contains all it's data in memory?
     in_memory: Cell<bool>,
+    /// Applies back-pressure to a source read from the network.
+    back_pressure: RefCell<Option<BackPressure>>,
+}
+
+/// The number of queued bytes above which the reading of a source from the network is paused.
+const BACK_PRESSURE_HIGH_WATER_MARK: usize = 1 << 20;
+
+/// Pauses and resumes the reading of a source from the network.
+#[derive(JSTraceable, MallocSizeOf)]
+struct BackPressure {
+    #[ignore_malloc_size_of = "Channels are hard"]
+    chan: IpcSender<ResponseBodyControlMsg>,
+    paused: Cell<bool>,
+}
+
+impl BackPressure {
+    fn update(&self, queued: usize) {
+        let paused = queued > BACK_PRESSURE_HIGH_WATER_MARK;
+        if paused == self.paused.replace(paused) {
+            return;
+        }
+        let _ = self.chan.send(if paused {
+            ResponseBodyControlMsg::Pause
+        } else {
+            ResponseBodyControlMsg::Resume
+        });
+    }
+}
+
+impl Drop for BackPressure {
+    /// A source that is no longer read, because the stream was closed, canceled or collected,
+    /// must not stay paused.
+    fn drop(&mut self) {
+        if self.paused.get() {
+            let _ = self.chan.send(ResponseBodyControlMsg::Resume);
+        }
+    }
 }
 
 impl ExternalUnderlyingSourceController {
@@ -397,12 +589,32 @@ impl ExternalUnderlyingSourceController {
         let (buffer, in_mem) = match source {
             ExternalUnderlyingSource::Blob(size) => (Vec::with_capacity(size), false),
             ExternalUnderlyingSource::Memory(size) => (Vec::with_capacity(size), true),
-            ExternalUnderlyingSource::FetchResponse => (vec![], false),
+            ExternalUnderlyingSource::FetchResponse | ExternalUnderlyingSource::FetchRequest => {
+                (vec![], false)
+            },
         };
         ExternalUnderlyingSourceController {
             buffer: RefCell::new(buffer),
             closed: Cell::new(false),
             in_memory: Cell::new(in_mem),
+            back_pressure: RefCell::new(None),
+        }
+    }
+
+    fn set_back_pressure_chan(&self, chan: IpcSender<ResponseBodyControlMsg>) {
+        *self.back_pressure.borrow_mut() = Some(BackPressure {
+            chan,
+            paused: Cell::new(false),
+        });
+    }
+
+    fn release_back_pressure(&self) {
+        self.back_pressure.borrow_mut().take();
+    }
+
+    fn update_back_pressure(&self) {
+        if let Some(ref back_pressure) = *self.back_pressure.borrow() {
+            back_pressure.update(self.buffer.borrow().len());
         }
     }
 
@@ -457,6 +669,7 @@ impl ExternalUnderlyingSourceController {
 
     fn close(&self, cx: SafeJSContext, stream: HandleObject) {
         self.closed.set(true);
+        self.release_back_pressure();
         self.maybe_close_js_stream(cx, stream);
     }
 
@@ -468,6 +681,7 @@ impl ExternalUnderlyingSourceController {
             buffer.len()
         };
         self.maybe_signal_available_bytes(cx, stream, available);
+        self.update_back_pressure();
     }
 
     #[allow(unsafe_code)]
@@ -498,6 +712,7 @@ impl ExternalUnderlyingSourceController {
         let length = dest.len();
         let chunk = self.get_chunk_with_length(length);
         dest.copy_from_slice(chunk.as_slice());
+        self.update_back_pressure();
     }
 }
 
@@ -538,3 +753,480 @@ pub fn get_read_promise_bytes(cx: SafeJSContext, v: &SafeHandleValue) -> Result<
         }
     }
 }
+
+#[allow(unsafe_code)]
+/// Get the `value` property of an object that a read promise resolved to.
+pub fn get_read_promise_value(
+    cx: SafeJSContext,
+    v: &SafeHandleValue,
+    rval: MutableHandleValue,
+) -> Result<(), Error> {
+    unsafe {
+        rooted!(in(*cx) let object = v.to_object());
+        match get_dictionary_property(*cx, object.handle(), "value", rval) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(Error::Type("Promise has no value property.".to_string())),
+            Err(()) => Err(Error::JSFailed),
+        }
+    }
+}
+
+type JSNativeFunction = unsafe extern "C" fn(*mut JSContext, u32, *mut JSVal) -> bool;
+
+/// The actions a pipe takes on its streams when shutting down.
+#[derive(Clone, Copy, JSTraceable, MallocSizeOf)]
+enum PipeAction {
+    AbortDest,
+    CancelSource,
+    CloseDest,
+    AbortDestAndCancelSource,
+}
+
+/// The state of a pipe from a readable stream to a writable stream.
+/// <https://streams.spec.whatwg.org/#readable-stream-pipe-to>
+#[derive(JSTraceable, MallocSizeOf)]
+pub struct PipeTo {
+    source: DomRoot<ReadableStream>,
+    dest: DomRoot<WritableStream>,
+    writer: DomRoot<WritableStreamDefaultWriter>,
+    signal: Option<DomRoot<AbortSignal>>,
+    prevent_close: bool,
+    prevent_abort: bool,
+    prevent_cancel: bool,
+    #[ignore_malloc_size_of = "Rc"]
+    promise: Rc<Promise>,
+    shutting_down: Cell<bool>,
+    /// Whether a read from the source is pending.
+    reading: Cell<bool>,
+    /// The promise of the last write to the destination, which completes after all others.
+    #[ignore_malloc_size_of = "Rc"]
+    pending_write: DomRefCell<Option<Rc<Promise>>>,
+    /// The error the pipe is finalized with, if `has_error` is set.
+    #[ignore_malloc_size_of = "Defined in rust-mozjs"]
+    error: Heap<JSVal>,
+    has_error: Cell<bool>,
+}
+
+impl PipeTo {
+    fn react(
+        self: &Rc<Self>,
+        promise: &Promise,
+        fulfilled: Option<PipeReaction>,
+        rejected: Option<PipeReaction>,
+    ) {
+        let global = self.source.global();
+        let handler = |reaction| {
+            Box::new(PipeReactionHandler {
+                pipe: self.clone(),
+                reaction,
+            }) as Box<dyn Callback>
+        };
+        let handler =
+            PromiseNativeHandler::new(&global, fulfilled.map(handler), rejected.map(handler));
+        let realm = enter_realm(&*global);
+        promise.append_native_handler(&handler, InRealm::Entered(&realm));
+    }
+
+    /// Waits for the destination to be ready, then reads a chunk from the source.
+    #[allow(unsafe_code)]
+    fn pipe_loop(self: &Rc<Self>, cx: SafeJSContext) {
+        if self.shutting_down.get() {
+            return;
+        }
+        // Closing must be propagated backward.
+        if self.dest.close_queued_or_in_flight() || self.dest.state() == WritableStreamState::Closed
+        {
+            rooted!(in(*cx) let mut error = UndefinedValue());
+            take_exception(
+                &self.source.global(),
+                Error::Type("The destination stream is closed".to_owned()),
+                error.handle_mut(),
+            );
+            if self.prevent_cancel {
+                self.shutdown(Some(error.handle()));
+            } else {
+                self.shutdown_with_action(PipeAction::CancelSource, Some(error.handle()));
+            }
+            return;
+        }
+        self.react(
+            &self.writer.ready_promise(),
+            Some(PipeReaction::DestReady),
+            None,
+        );
+    }
+
+    fn read(self: &Rc<Self>) {
+        if self.shutting_down.get() {
+            return;
+        }
+        self.reading.set(true);
+        let promise = self.source.read_a_chunk();
+        self.react(
+            &promise,
+            Some(PipeReaction::ReadFulfilled),
+            Some(PipeReaction::SourceErrored),
+        );
+    }
+
+    /// Writes a chunk that was read to the destination, or propagates the closing of the source
+    /// forward.
+    #[allow(unsafe_code)]
+    fn write(self: &Rc<Self>, cx: SafeJSContext, result: SafeHandleValue) {
+        self.reading.set(false);
+        if self.shutting_down.get() {
+            return;
+        }
+        rooted!(in(*cx) let mut chunk = UndefinedValue());
+        let read = get_read_promise_done(cx, &result).and_then(|done| {
+            if !done {
+                get_read_promise_value(cx, &result, chunk.handle_mut())?;
+            }
+            Ok(done)
+        });
+        match read {
+            Ok(false) => {
+                let promise = self.writer.write(cx, chunk.handle());
+                promise.mark_as_handled();
+                *self.pending_write.borrow_mut() = Some(promise);
+                self.pipe_loop(cx);
+            },
+            // Closing must be propagated forward.
+            Ok(true) if self.prevent_close => self.shutdown(None),
+            Ok(true) => self.shutdown_with_action(PipeAction::CloseDest, None),
+            Err(error) => {
+                rooted!(in(*cx) let mut error_value = UndefinedValue());
+                take_exception(&self.source.global(), error, error_value.handle_mut());
+                self.source_errored(error_value.handle());
+            },
+        }
+    }
+
+    /// Errors must be propagated forward.
+    fn source_errored(self: &Rc<Self>, error: SafeHandleValue) {
+        self.reading.set(false);
+        if self.prevent_abort {
+            self.shutdown(Some(error));
+        } else {
+            self.shutdown_with_action(PipeAction::AbortDest, Some(error));
+        }
+    }
+
+    /// Errors must be propagated backward.
+    fn dest_errored(self: &Rc<Self>, error: SafeHandleValue) {
+        if self.prevent_cancel {
+            self.shutdown(Some(error));
+        } else {
+            self.shutdown_with_action(PipeAction::CancelSource, Some(error));
+        }
+    }
+
+    /// The abort algorithm of the signal of the pipe.
+    #[allow(unsafe_code)]
+    pub fn abort(self: &Rc<Self>, signal: &AbortSignal) {
+        let cx = self.source.global().get_cx();
+        rooted!(in(*cx) let reason = signal.reason());
+        let action = match (self.prevent_abort, self.prevent_cancel) {
+            (true, true) => return self.shutdown(Some(reason.handle())),
+            (false, true) => PipeAction::AbortDest,
+            (true, false) => PipeAction::CancelSource,
+            (false, false) => PipeAction::AbortDestAndCancelSource,
+        };
+        self.shutdown_with_action(action, Some(reason.handle()));
+    }
+
+    fn set_error(&self, error: SafeHandleValue) {
+        self.error.set(error.get());
+        self.has_error.set(true);
+    }
+
+    /// Starts shutting down, and returns the pending write to wait for, if any.
+    fn start_shutdown(&self, error: Option<SafeHandleValue>) -> Option<Rc<Promise>> {
+        self.shutting_down.set(true);
+        if let Some(error) = error {
+            self.set_error(error);
+        }
+        if self.dest.state() == WritableStreamState::Writable &&
+            !self.dest.close_queued_or_in_flight()
+        {
+            return self.pending_write.borrow().clone();
+        }
+        None
+    }
+
+    /// <https://streams.spec.whatwg.org/#rs-pipeTo-shutdown-with-action>
+    fn shutdown_with_action(self: &Rc<Self>, action: PipeAction, error: Option<SafeHandleValue>) {
+        // Steps 1-2.
+        if self.shutting_down.get() {
+            return;
+        }
+        // Step 3.
+        match self.start_shutdown(error) {
+            Some(write) => {
+                let reaction = || Some(PipeReaction::WritesFinished(Some(action)));
+                self.react(&write, reaction(), reaction());
+            },
+            // Step 4.
+            None => self.perform_action(action),
+        }
+    }
+
+    /// Steps 4.1-4.3 of <https://streams.spec.whatwg.org/#rs-pipeTo-shutdown-with-action>.
+    #[allow(unsafe_code)]
+    fn perform_action(self: &Rc<Self>, action: PipeAction) {
+        let cx = self.source.global().get_cx();
+        rooted!(in(*cx) let error = self.error.get());
+        let promise = match action {
+            PipeAction::AbortDest => self.dest.abort(error.handle()),
+            PipeAction::CancelSource => self.source.cancel(error.handle()),
+            PipeAction::CloseDest => self.writer.close_with_error_propagation(),
+            PipeAction::AbortDestAndCancelSource => {
+                // Cancelling through our reader settles at once,
+                // so waiting for the abort waits for both.
+                self.source.cancel(error.handle()).mark_as_handled();
+                self.dest.abort(error.handle())
+            },
+        };
+        self.react(
+            &promise,
+            Some(PipeReaction::ActionFulfilled),
+            Some(PipeReaction::ActionRejected),
+        );
+    }
+
+    /// <https://streams.spec.whatwg.org/#rs-pipeTo-shutdown>
+    fn shutdown(self: &Rc<Self>, error: Option<SafeHandleValue>) {
+        // Steps 1-2.
+        if self.shutting_down.get() {
+            return;
+        }
+        // Step 3.
+        match self.start_shutdown(error) {
+            Some(write) => {
+                let reaction = || Some(PipeReaction::WritesFinished(None));
+                self.react(&write, reaction(), reaction());
+            },
+            // Step 4.
+            None => self.finalize(),
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#rs-pipeTo-finalize>
+    #[allow(unsafe_code)]
+    fn finalize(self: &Rc<Self>) {
+        // Steps 1-2.
+        self.writer.release();
+        // Note: a reader with a pending read can't be released, so the source stays locked.
+        if !self.reading.get() {
+            self.source.stop_reading();
+        }
+        // Step 3.
+        if let Some(ref signal) = self.signal {
+            signal.remove_stream_pipe(self);
+        }
+        // Steps 4-5.
+        if self.has_error.get() {
+            let global = self.source.global();
+            let _ar = enter_realm(&*global);
+            let cx = global.get_cx();
+            rooted!(in(*cx) let error = self.error.get());
+            self.promise.reject(cx, error.handle());
+        } else {
+            self.promise.resolve_native(&());
+        }
+    }
+}
+
+#[derive(JSTraceable, MallocSizeOf)]
+enum PipeReaction {
+    DestReady,
+    ReadFulfilled,
+    SourceErrored,
+    DestErrored,
+    WritesFinished(Option<PipeAction>),
+    ActionFulfilled,
+    ActionRejected,
+}
+
+#[derive(JSTraceable, MallocSizeOf)]
+struct PipeReactionHandler {
+    #[ignore_malloc_size_of = "Rc"]
+    pipe: Rc<PipeTo>,
+    reaction: PipeReaction,
+}
+
+impl Callback for PipeReactionHandler {
+    fn callback(&self, cx: SafeJSContext, v: SafeHandleValue, _realm: InRealm) {
+        let pipe = &self.pipe;
+        match self.reaction {
+            PipeReaction::DestReady => pipe.read(),
+            PipeReaction::ReadFulfilled => pipe.write(cx, v),
+            PipeReaction::SourceErrored => pipe.source_errored(v),
+            PipeReaction::DestErrored => pipe.dest_errored(v),
+            PipeReaction::WritesFinished(Some(action)) => pipe.perform_action(action),
+            PipeReaction::WritesFinished(None) => pipe.finalize(),
+            PipeReaction::ActionFulfilled => pipe.finalize(),
+            PipeReaction::ActionRejected => {
+                pipe.set_error(v);
+                pipe.finalize();
+            },
+        }
+    }
+}
+
+#[allow(unsafe_code)]
+unsafe fn source_from_this(
+    cx: *mut JSContext,
+    args: &CallArgs,
+    realm: InRealm,
+) -> Fallible<DomRoot<ReadableStream>> {
+    let this = args.thisv();
+    if !this.is_object() {
+        return Err(Error::Type(
+            "The this value is not a ReadableStream".to_owned(),
+        ));
+    }
+    ReadableStream::from_js(SafeJSContext::from_ptr(cx), this.to_object(), realm)
+        .map_err(|()| Error::Type("The this value is not a ReadableStream".to_owned()))
+}
+
+#[allow(unsafe_code)]
+unsafe fn writable_from_value(
+    cx: *mut JSContext,
+    value: SafeHandleValue,
+) -> Fallible<DomRoot<WritableStream>> {
+    if !value.is_object() {
+        return Err(Error::Type(
+            "The destination is not a WritableStream".to_owned(),
+        ));
+    }
+    root_from_object::<WritableStream>(value.to_object(), cx)
+        .map_err(|()| Error::Type("The destination is not a WritableStream".to_owned()))
+}
+
+#[allow(unsafe_code)]
+unsafe fn pipe_options(cx: *mut JSContext, value: SafeHandleValue) -> Fallible<StreamPipeOptions> {
+    match StreamPipeOptions::new(SafeJSContext::from_ptr(cx), value) {
+        Ok(ConversionResult::Success(options)) => Ok(options),
+        Ok(ConversionResult::Failure(error)) => Err(Error::Type(error.into_owned())),
+        Err(()) => Err(Error::JSFailed),
+    }
+}
+
+/// <https://streams.spec.whatwg.org/#rs-pipe-to>
+#[allow(unsafe_code)]
+unsafe extern "C" fn pipe_to(cx: *mut JSContext, argc: u32, vp: *mut JSVal) -> bool {
+    let args = CallArgs::from_vp(vp, argc);
+    let in_realm_proof = AlreadyInRealm::assert_for_cx(SafeJSContext::from_ptr(cx));
+    let global = GlobalScope::from_context(cx, InRealm::Already(&in_realm_proof));
+
+    let arguments =
+        source_from_this(cx, &args, InRealm::Already(&in_realm_proof)).and_then(|source| {
+            let dest = writable_from_value(cx, SafeHandleValue::from_raw(args.get(0)))?;
+            let options = pipe_options(cx, SafeHandleValue::from_raw(args.get(1)))?;
+            Ok((source, dest, options))
+        });
+    let promise = match arguments {
+        Ok((source, dest, options)) => source.pipe_to(&dest, &options),
+        Err(error) => {
+            let promise = Promise::new(&global);
+            rooted!(in(cx) let mut error_value = UndefinedValue());
+            take_exception(&global, error, error_value.handle_mut());
+            promise.reject(SafeJSContext::from_ptr(cx), error_value.handle());
+            promise
+        },
+    };
+    *args.rval() = ObjectValue(promise.reflector().get_jsobject().get());
+    true
+}
+
+/// <https://streams.spec.whatwg.org/#rs-pipe-through>
+#[allow(unsafe_code)]
+unsafe extern "C" fn pipe_through(cx: *mut JSContext, argc: u32, vp: *mut JSVal) -> bool {
+    let args = CallArgs::from_vp(vp, argc);
+    let in_realm_proof = AlreadyInRealm::assert_for_cx(SafeJSContext::from_ptr(cx));
+    let global = GlobalScope::from_context(cx, InRealm::Already(&in_realm_proof));
+
+    rooted!(in(cx) let mut readable = UndefinedValue());
+    let result = (|| {
+        let source = source_from_this(cx, &args, InRealm::Already(&in_realm_proof))?;
+        let transform = args.get(0);
+        if !transform.is_object() {
+            return Err(Error::Type("The transform is not an object".to_owned()));
+        }
+        rooted!(in(cx) let transform = transform.to_object());
+        rooted!(in(cx) let mut writable = UndefinedValue());
+        get_dictionary_property(cx, transform.handle(), "readable", readable.handle_mut())
+            .map_err(|()| Error::JSFailed)?;
+        get_dictionary_property(cx, transform.handle(), "writable", writable.handle_mut())
+            .map_err(|()| Error::JSFailed)?;
+        if !readable.is_object() || !IsReadableStream(readable.to_object()) {
+            return Err(Error::Type(
+                "The readable side is not a ReadableStream".to_owned(),
+            ));
+        }
+        let writable = writable_from_value(cx, writable.handle())?;
+        // Steps 1-2.
+        if source.is_locked() || writable.is_locked() {
+            return Err(Error::Type(
+                "The source or destination is locked".to_owned(),
+            ));
+        }
+        let options = pipe_options(cx, SafeHandleValue::from_raw(args.get(1)))?;
+        // Steps 3-4.
+        source.pipe_to(&writable, &options).mark_as_handled();
+        Ok(())
+    })();
+
+    match result {
+        // Step 5.
+        Ok(()) => {
+            *args.rval() = readable.get();
+            true
+        },
+        Err(error) => {
+            throw_dom_exception(SafeJSContext::from_ptr(cx), &global, error);
+            false
+        },
+    }
+}
+
+/// Defines `pipeTo` and `pipeThrough` on the engine-provided `ReadableStream.prototype` of a
+/// new global, since the engine has no `WritableStream` to pipe to.
+#[allow(unsafe_code)]
+pub unsafe fn define_pipe_methods(cx: SafeJSContext, global: SafeHandleObject) {
+    rooted!(in(*cx) let mut constructor = UndefinedValue());
+    rooted!(in(*cx) let mut prototype = UndefinedValue());
+    if !get_dictionary_property(*cx, global, "ReadableStream", constructor.handle_mut())
+        .unwrap_or(false) ||
+        !constructor.is_object()
+    {
+        return;
+    }
+    rooted!(in(*cx) let constructor = constructor.to_object());
+    if !get_dictionary_property(
+        *cx,
+        constructor.handle(),
+        "prototype",
+        prototype.handle_mut(),
+    )
+    .unwrap_or(false) ||
+        !prototype.is_object()
+    {
+        return;
+    }
+    rooted!(in(*cx) let prototype = prototype.to_object());
+    let methods: [(&[u8], JSNativeFunction); 2] =
+        [(b"pipeTo\0", pipe_to), (b"pipeThrough\0", pipe_through)];
+    for &(name, method) in methods.iter() {
+        assert!(!JS_DefineFunction(
+            *cx,
+            prototype.handle(),
+            name.as_ptr() as *const c_char,
+            Some(method),
+            1,
+            JSPROP_ENUMERATE as u32,
+        )
+        .is_null());
+    }
+}
@@ -4,6 +4,7 @@
 
 use crate::body::Extractable;
 use crate::body::{consume_body, BodyMixin, BodyType};
+use crate::dom::abortsignal::AbortSignal;
 use crate::dom::bindings::cell::DomRefCell;
 use crate::dom::bindings::codegen::Bindings::HeadersBinding::{HeadersInit, HeadersMethods};
 use crate::dom::bindings::codegen::Bindings::RequestBinding::ReferrerPolicy;
@@ -16,6 +17,7 @@ use crate::dom::bindings::codegen::Bindings::RequestBinding::RequestMethods;
 use crate::dom::bindings::codegen::Bindings::RequestBinding::RequestMode;
 use crate::dom::bindings::codegen::Bindings::RequestBinding::RequestRedirect;
 use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::refcounted::Trusted;
 use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
 use crate::dom::bindings::root::{DomRoot, MutNullableDom};
 use crate::dom::bindings::str::{ByteString, DOMString, USVString};
@@ -23,12 +25,16 @@ use crate::dom::bindings::trace::RootedTraceableBox;
 use crate::dom::globalscope::GlobalScope;
 use crate::dom::headers::{Guard, Headers};
 use crate::dom::promise::Promise;
-use crate::dom::readablestream::ReadableStream;
+use crate::dom::readablestream::{ExternalUnderlyingSource, ReadableStream};
 use crate::script_runtime::JSContext as SafeJSContext;
+use crate::task_source::TaskSource;
+use crate::task_source::TaskSourceName;
 use dom_struct::dom_struct;
-use http::header::{HeaderName, HeaderValue};
+use http::header::{HeaderMap, HeaderName, HeaderValue};
 use http::method::InvalidMethod;
 use http::Method as HttpMethod;
+use ipc_channel::ipc::{self, IpcSender};
+use ipc_channel::router::ROUTER;
 use js::jsapi::JSObject;
 use net_traits::request::CacheMode as NetTraitsRequestCache;
 use net_traits::request::CredentialsMode as NetTraitsRequestCredentials;
@@ -37,6 +43,7 @@ use net_traits::request::RedirectMode as NetTraitsRequestRedirect;
 use net_traits::request::Referrer as NetTraitsRequestReferrer;
 use net_traits::request::Request as NetTraitsRequest;
 use net_traits::request::RequestMode as NetTraitsRequestMode;
+use net_traits::request::{BodyChunkRequest, BodyChunkResponse};
 use net_traits::request::{Origin, Window};
 use net_traits::ReferrerPolicy as MsgReferrerPolicy;
 use servo_url::ServoUrl;
@@ -49,8 +56,13 @@ pub struct Request {
     reflector_: Reflector,
     request: DomRefCell<NetTraitsRequest>,
     body_stream: MutNullableDom<ReadableStream>,
+    /// The body of the request of a `FetchEvent`, which is only read from the client
+    /// once the worker uses it.
+    #[ignore_malloc_size_of = "Channels are hard"]
+    fetch_event_body: DomRefCell<Option<IpcSender<BodyChunkRequest>>>,
     headers: MutNullableDom<Headers>,
     mime_type: DomRefCell<Vec<u8>>,
+    signal: MutNullableDom<AbortSignal>,
 }
 
 impl Request {
@@ -59,8 +71,10 @@ impl Request {
             reflector_: Reflector::new(),
             request: DomRefCell::new(net_request_from_global(global, url)),
             body_stream: MutNullableDom::new(None),
+            fetch_event_body: DomRefCell::new(None),
             headers: Default::default(),
             mime_type: DomRefCell::new("".to_string().into_bytes()),
+            signal: MutNullableDom::new(None),
         }
     }
 
@@ -87,7 +101,8 @@ impl Request {
         // Step 4
         let base_url = global.api_base_url();
 
-        // Step 5 TODO: "Let signal be null."
+        // Step 5
+        let mut signal: Option<DomRoot<AbortSignal>> = None;
 
         match input {
             // Step 6
@@ -119,7 +134,8 @@ impl Request {
                 }
                 // Step 7.1
                 temporary_request = input_request.request.borrow().clone();
-                // Step 7.2 TODO: "Set signal to input's signal."
+                // Step 7.2
+                signal = Some(input_request.Signal());
             },
         }
 
@@ -298,12 +314,19 @@ impl Request {
             request.method = method;
         }
 
-        // Step 28 TODO: "If init["signal"] exists..."
+        // Step 28
+        if let Some(ref init_signal) = init.signal {
+            signal = init_signal.clone();
+        }
 
         // Step 29
         let r = Request::from_net_request(global, request);
 
-        // Step 30 TODO: "If signal is not null..."
+        // Step 30
+        if let Some(signal) = signal {
+            r.signal
+                .set(Some(&AbortSignal::new_dependent(global, &[signal])));
+        }
 
         // Step 31
         // "or_init" looks unclear here, but it always enters the block since r
@@ -459,6 +482,90 @@ impl Request {
         r
     }
 
+    /// The request object of a `FetchEvent`, for a request being fetched.
+    /// <https://w3c.github.io/ServiceWorker/#handle-fetch>
+    pub fn new_for_fetch_event(
+        global: &GlobalScope,
+        net_request: NetTraitsRequest,
+        body: Option<IpcSender<BodyChunkRequest>>,
+    ) -> DomRoot<Request> {
+        let headers = net_request.headers.clone();
+        let r = Request::from_net_request(global, net_request);
+        r.Headers().set_headers(headers);
+        r.Headers().set_guard(Guard::Immutable);
+        *r.mime_type.borrow_mut() = r.Headers().extract_mime_type();
+        *r.fetch_event_body.borrow_mut() = body;
+        r
+    }
+
+    /// Start reading the body of the request of a `FetchEvent` from its client, as a stream.
+    /// Once the worker reads it, the fetch can no longer fall back to the network.
+    fn stream_fetch_event_body(
+        &self,
+        body: IpcSender<BodyChunkRequest>,
+    ) -> DomRoot<ReadableStream> {
+        let global = self.global();
+        let stream = ReadableStream::new_with_external_underlying_source(
+            &global,
+            ExternalUnderlyingSource::FetchRequest,
+        );
+
+        let (chunk_chan, chunk_port) = ipc::channel().unwrap();
+        let _ = body.send(BodyChunkRequest::Connect(chunk_chan));
+        let _ = body.send(BodyChunkRequest::Chunk);
+
+        let trusted_stream = Trusted::new(&*stream);
+        let task_source = global.networking_task_source();
+        let canceller = global.task_canceller(TaskSourceName::Networking);
+        let mut body = Some(body);
+        ROUTER.add_route(
+            chunk_port.to_opaque(),
+            Box::new(move |message| {
+                let response: BodyChunkResponse = message.to().unwrap();
+                match response {
+                    BodyChunkResponse::Chunk(_) => {
+                        if let Some(ref body) = body {
+                            let _ = body.send(BodyChunkRequest::Chunk);
+                        }
+                    },
+                    // Dropping the channel to the body lets net remove its relay.
+                    BodyChunkResponse::Done | BodyChunkResponse::Error => body = None,
+                }
+                let stream = trusted_stream.clone();
+                let _ = task_source.queue_with_canceller(
+                    task!(read_fetch_event_body_chunk: move || {
+                        let stream = stream.root();
+                        match response {
+                            BodyChunkResponse::Chunk(bytes) => stream.enqueue_native(bytes),
+                            BodyChunkResponse::Done => stream.close_native(),
+                            BodyChunkResponse::Error => stream.error_native(Error::Type(
+                                "Reading the request body failed".to_string(),
+                            )),
+                        }
+                    }),
+                    &canceller,
+                );
+            }),
+        );
+
+        stream
+    }
+
+    /// The request object of an entry of a cache, as `Cache.keys` returns it.
+    /// <https://w3c.github.io/ServiceWorker/#dom-cache-keys>
+    pub fn new_for_cache_entry(
+        global: &GlobalScope,
+        url: ServoUrl,
+        headers: HeaderMap,
+    ) -> DomRoot<Request> {
+        let r = Request::new(global, url);
+        r.request.borrow_mut().headers = headers.clone();
+        r.Headers().set_headers(headers);
+        r.Headers().set_guard(Guard::Immutable);
+        *r.mime_type.borrow_mut() = r.Headers().extract_mime_type();
+        r
+    }
+
     fn clone_from(r: &Request) -> Fallible<DomRoot<Request>> {
         let req = r.request.borrow();
         let url = req.url();
@@ -472,6 +579,10 @@ impl Request {
         }
         *r_clone.request.borrow_mut() = req.clone();
         *r_clone.mime_type.borrow_mut() = mime_type;
+        r_clone.signal.set(Some(&AbortSignal::new_dependent(
+            &r.global(),
+            &[r.Signal()],
+        )));
         r_clone.Headers().copy_from_headers(r.Headers())?;
         r_clone.Headers().set_guard(headers_guard);
         Ok(r_clone)
@@ -614,6 +725,11 @@ impl RequestMethods for Request {
         DOMString::from_string(r.integrity_metadata.clone())
     }
 
+    // https://fetch.spec.whatwg.org/#dom-request-signal
+    fn Signal(&self) -> DomRoot<AbortSignal> {
+        self.signal.or_init(|| AbortSignal::new(&self.global()))
+    }
+
     /// <https://fetch.spec.whatwg.org/#dom-body-body>
     fn GetBody(&self, _cx: SafeJSContext) -> Option<NonNull<JSObject>> {
         self.body().map(|stream| stream.get_js_stream())
@@ -678,6 +794,11 @@ impl BodyMixin for Request {
     }
 
     fn body(&self) -> Option<DomRoot<ReadableStream>> {
+        let fetch_event_body = self.fetch_event_body.borrow_mut().take();
+        if let Some(body) = fetch_event_body {
+            self.body_stream
+                .set(Some(&*self.stream_fetch_event_body(body)));
+        }
         self.body_stream.get()
     }
 
@@ -0,0 +1,284 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::callback::ExceptionHandling;
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::ResizeObserverBinding::{
+    ResizeObserverBoxOptions, ResizeObserverCallback, ResizeObserverMethods, ResizeObserverOptions,
+};
+use crate::dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot};
+use crate::dom::domrectreadonly::DOMRectReadOnly;
+use crate::dom::element::Element;
+use crate::dom::node::{window_from_node, Node, ShadowIncluding};
+use crate::dom::resizeobserverentry::ResizeObserverEntry;
+use crate::dom::resizeobserversize::{ResizeObserverSize, ResizeObserverSizeImpl};
+use crate::dom::window::Window;
+use dom_struct::dom_struct;
+use script_layout_interface::rpc::BoxSizes;
+use std::rc::Rc;
+
+/// <https://drafts.csswg.org/resize-observer/#resize-observation-interface>
+#[derive(JSTraceable, MallocSizeOf)]
+#[unrooted_must_root_lint::must_root]
+struct ResizeObservation {
+    target: Dom<Element>,
+    observed_box: ResizeObserverBoxOptions,
+    last_reported_sizes: Vec<ResizeObserverSizeImpl>,
+}
+
+impl ResizeObservation {
+    fn new(target: &Element, observed_box: ResizeObserverBoxOptions) -> ResizeObservation {
+        ResizeObservation {
+            target: Dom::from_ref(target),
+            observed_box,
+            last_reported_sizes: vec![ResizeObserverSizeImpl::new(0., 0.)],
+        }
+    }
+
+    /// <https://drafts.csswg.org/resize-observer/#dom-resizeobservation-isactive>
+    fn is_active(&self) -> bool {
+        let box_sizes = box_sizes(&self.target);
+        let current_size = calculate_box_size(&self.target, box_sizes.as_ref(), self.observed_box);
+        self.last_reported_sizes
+            .first()
+            .map_or(true, |size| *size != current_size)
+    }
+}
+
+#[dom_struct]
+pub struct ResizeObserver {
+    reflector_: Reflector,
+    #[ignore_malloc_size_of = "can't measure Rc values"]
+    callback: Rc<ResizeObserverCallback>,
+    /// <https://drafts.csswg.org/resize-observer/#dom-resizeobserver-observationtargets-slot>
+    observation_targets: DomRefCell<Vec<ResizeObservation>>,
+    /// <https://drafts.csswg.org/resize-observer/#dom-resizeobserver-activetargets-slot>
+    active_targets: DomRefCell<Vec<Dom<Element>>>,
+    /// <https://drafts.csswg.org/resize-observer/#dom-resizeobserver-skippedtargets-slot>
+    skipped_targets: DomRefCell<Vec<Dom<Element>>>,
+}
+
+impl ResizeObserver {
+    fn new_inherited(callback: Rc<ResizeObserverCallback>) -> ResizeObserver {
+        ResizeObserver {
+            reflector_: Reflector::new(),
+            callback,
+            observation_targets: DomRefCell::new(vec![]),
+            active_targets: DomRefCell::new(vec![]),
+            skipped_targets: DomRefCell::new(vec![]),
+        }
+    }
+
+    fn new(window: &Window, callback: Rc<ResizeObserverCallback>) -> DomRoot<ResizeObserver> {
+        let observer = Box::new(ResizeObserver::new_inherited(callback));
+        reflect_dom_object(observer, window)
+    }
+
+    /// <https://drafts.csswg.org/resize-observer/#dom-resizeobserver-resizeobserver>
+    #[allow(non_snake_case)]
+    pub fn Constructor(
+        window: &Window,
+        callback: Rc<ResizeObserverCallback>,
+    ) -> DomRoot<ResizeObserver> {
+        ResizeObserver::new(window, callback)
+    }
+
+    pub fn has_observation_targets(&self) -> bool {
+        !self.observation_targets.borrow().is_empty()
+    }
+
+    pub fn has_active_observations(&self) -> bool {
+        !self.active_targets.borrow().is_empty()
+    }
+
+    pub fn has_skipped_observations(&self) -> bool {
+        !self.skipped_targets.borrow().is_empty()
+    }
+
+    /// <https://drafts.csswg.org/resize-observer/#gather-active-observations-h>
+    /// Steps 2.1-2.2, for a single observer.
+    pub fn gather_active_observations_at_depth(&self, depth: usize) {
+        // Step 2.1
+        let mut active_targets = self.active_targets.borrow_mut();
+        let mut skipped_targets = self.skipped_targets.borrow_mut();
+        active_targets.clear();
+        skipped_targets.clear();
+
+        // Step 2.2
+        for observation in self.observation_targets.borrow().iter() {
+            if !observation.is_active() {
+                continue;
+            }
+            let target = Dom::from_ref(&*observation.target);
+            if target_depth(&observation.target) > depth {
+                active_targets.push(target);
+            } else {
+                skipped_targets.push(target);
+            }
+        }
+    }
+
+    /// <https://drafts.csswg.org/resize-observer/#broadcast-active-observations>
+    /// Step 2, for a single observer. Returns the depth of the shallowest target
+    /// that was broadcast, if any.
+    pub fn broadcast_active_observations(&self) -> Option<usize> {
+        // Step 2.1
+        let active_targets: Vec<DomRoot<Element>> = self
+            .active_targets
+            .borrow_mut()
+            .drain(..)
+            .map(|target| DomRoot::from_ref(&*target))
+            .collect();
+        if active_targets.is_empty() {
+            return None;
+        }
+
+        // Steps 2.2-2.3
+        let window = self.global();
+        let window = window.as_window();
+        let mut entries = vec![];
+        let mut shallowest_target_depth = usize::max_value();
+        for target in active_targets {
+            let box_sizes = box_sizes(&target);
+            let entry = create_and_populate_entry(window, &target, box_sizes.as_ref());
+
+            let mut observations = self.observation_targets.borrow_mut();
+            if let Some(observation) = observations.iter_mut().find(|o| *o.target == *target) {
+                observation.last_reported_sizes = vec![calculate_box_size(
+                    &target,
+                    box_sizes.as_ref(),
+                    observation.observed_box,
+                )];
+            }
+
+            shallowest_target_depth = shallowest_target_depth.min(target_depth(&target));
+            entries.push(entry);
+        }
+
+        // Step 2.4
+        let _ = self
+            .callback
+            .Call_(self, entries, self, ExceptionHandling::Report);
+
+        // Step 2.5 was done when taking the active targets.
+        Some(shallowest_target_depth)
+    }
+}
+
+impl ResizeObserverMethods for ResizeObserver {
+    /// <https://drafts.csswg.org/resize-observer/#dom-resizeobserver-observe>
+    fn Observe(&self, target: &Element, options: &ResizeObserverOptions) {
+        // Step 1
+        self.Unobserve(target);
+
+        // Steps 2-3
+        self.observation_targets
+            .borrow_mut()
+            .push(ResizeObservation::new(target, options.box_));
+
+        self.global()
+            .as_window()
+            .Document()
+            .add_resize_observer(self);
+    }
+
+    /// <https://drafts.csswg.org/resize-observer/#dom-resizeobserver-unobserve>
+    fn Unobserve(&self, target: &Element) {
+        self.observation_targets
+            .borrow_mut()
+            .retain(|observation| *observation.target != *target);
+    }
+
+    /// <https://drafts.csswg.org/resize-observer/#dom-resizeobserver-disconnect>
+    fn Disconnect(&self) {
+        self.observation_targets.borrow_mut().clear();
+        self.active_targets.borrow_mut().clear();
+        self.skipped_targets.borrow_mut().clear();
+    }
+}
+
+/// The depth of `target` in the flat tree.
+fn target_depth(target: &Element) -> usize {
+    target
+        .upcast::<Node>()
+        .inclusive_ancestors(ShadowIncluding::Yes)
+        .count()
+}
+
+fn box_sizes(target: &Element) -> Option<BoxSizes> {
+    window_from_node(target).box_sizes_query(target.upcast())
+}
+
+/// <https://drafts.csswg.org/resize-observer/#calculate-box-size>
+///
+/// Targets without a box have a size of zero.
+fn calculate_box_size(
+    target: &Element,
+    box_sizes: Option<&BoxSizes>,
+    observed_box: ResizeObserverBoxOptions,
+) -> ResizeObserverSizeImpl {
+    let box_sizes = match box_sizes {
+        Some(box_sizes) => box_sizes,
+        None => return ResizeObserverSizeImpl::new(0., 0.),
+    };
+    let (width, height) = match observed_box {
+        ResizeObserverBoxOptions::Border_box => (
+            box_sizes.border_box.width.to_f64_px(),
+            box_sizes.border_box.height.to_f64_px(),
+        ),
+        ResizeObserverBoxOptions::Content_box => (
+            box_sizes.content_box.size.width.to_f64_px(),
+            box_sizes.content_box.size.height.to_f64_px(),
+        ),
+        ResizeObserverBoxOptions::Device_pixel_content_box => {
+            let device_pixel_ratio = window_from_node(target).device_pixel_ratio().get() as f64;
+            (
+                (box_sizes.content_box.size.width.to_f64_px() * device_pixel_ratio).round(),
+                (box_sizes.content_box.size.height.to_f64_px() * device_pixel_ratio).round(),
+            )
+        },
+    };
+    if box_sizes.vertical {
+        ResizeObserverSizeImpl::new(height, width)
+    } else {
+        ResizeObserverSizeImpl::new(width, height)
+    }
+}
+
+/// <https://drafts.csswg.org/resize-observer/#create-and-populate-a-resizeobserverentry>
+fn create_and_populate_entry(
+    window: &Window,
+    target: &Element,
+    box_sizes: Option<&BoxSizes>,
+) -> DomRoot<ResizeObserverEntry> {
+    let size = |observed_box| {
+        ResizeObserverSize::new(window, calculate_box_size(target, box_sizes, observed_box))
+    };
+    let border_box_size = size(ResizeObserverBoxOptions::Border_box);
+    let content_box_size = size(ResizeObserverBoxOptions::Content_box);
+    let device_pixel_content_box_size = size(ResizeObserverBoxOptions::Device_pixel_content_box);
+
+    let content_rect = match box_sizes {
+        Some(box_sizes) => DOMRectReadOnly::new(
+            window.upcast(),
+            box_sizes.content_box.origin.x.to_f64_px(),
+            box_sizes.content_box.origin.y.to_f64_px(),
+            box_sizes.content_box.size.width.to_f64_px(),
+            box_sizes.content_box.size.height.to_f64_px(),
+        ),
+        None => DOMRectReadOnly::new(window.upcast(), 0., 0., 0., 0.),
+    };
+
+    ResizeObserverEntry::new(
+        window,
+        target,
+        &content_rect,
+        &[&border_box_size],
+        &[&content_box_size],
+        &[&device_pixel_content_box_size],
+    )
+}
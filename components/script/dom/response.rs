@@ -26,7 +26,9 @@ use dom_struct::dom_struct;
 use http::header::HeaderMap as HyperHeaders;
 use hyper::StatusCode;
 use hyper_serde::Serde;
+use ipc_channel::ipc::IpcSender;
 use js::jsapi::JSObject;
+use net_traits::ResponseBodyControlMsg;
 use servo_url::ServoUrl;
 use std::ptr::NonNull;
 use std::rc::Rc;
@@ -449,6 +451,13 @@ impl Response {
         *self.stream_consumer.borrow_mut() = sc;
     }
 
+    /// Lets the body stream pause the reading of the body from the network over `chan`.
+    pub fn set_back_pressure_chan(&self, chan: IpcSender<ResponseBodyControlMsg>) {
+        if let Some(body) = self.body_stream.get() {
+            body.set_back_pressure_chan(chan);
+        }
+    }
+
     pub fn stream_chunk(&self, chunk: Vec<u8>) {
         // Note, are these two actually mutually exclusive?
         if let Some(stream_consumer) = self.stream_consumer.borrow_mut().as_ref() {
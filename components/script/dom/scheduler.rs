@@ -0,0 +1,266 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::abortsignal::{AbortAlgorithm, AbortSignal};
+use crate::dom::bindings::callback::ExceptionHandling;
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::SchedulerBinding::{
+    SchedulerMethods, SchedulerPostTaskCallback, SchedulerPostTaskOptions, TaskPriority,
+};
+use crate::dom::bindings::error::take_exception;
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::refcounted::Trusted;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot};
+use crate::dom::promise::Promise;
+use crate::dom::tasksignal::TaskSignal;
+use crate::dom::window::Window;
+use crate::realms::enter_realm;
+use crate::task_source::TaskSource;
+use crate::timers::OneshotTimerCallback;
+use dom_struct::dom_struct;
+use js::jsval::UndefinedValue;
+use script_traits::MsDuration;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// A task that was posted with `postTask()`, until it runs or is aborted.
+/// <https://wicg.github.io/scheduling-apis/#scheduler-task>
+#[derive(JSTraceable, MallocSizeOf)]
+#[unrooted_must_root_lint::must_root]
+struct PostedTask {
+    #[ignore_malloc_size_of = "Rc"]
+    callback: Rc<SchedulerPostTaskCallback>,
+    /// The promise returned by `postTask()`.
+    #[ignore_malloc_size_of = "Rc"]
+    promise: Rc<Promise>,
+    /// The signal whose priority the task follows, or `None` if it has a fixed priority.
+    priority_source: Option<Dom<TaskSignal>>,
+    priority: TaskPriority,
+    /// Whether the delay of the task elapsed, so that it is queued on a task source.
+    ready: bool,
+    /// Bumped each time the task is queued again, so that only the latest queued
+    /// runner of the task runs it.
+    generation: u32,
+}
+
+impl PostedTask {
+    fn priority(&self) -> TaskPriority {
+        match self.priority_source {
+            Some(ref signal) => signal.priority(),
+            None => self.priority,
+        }
+    }
+}
+
+// https://wicg.github.io/scheduling-apis/#sec-scheduler
+#[dom_struct]
+pub struct Scheduler {
+    reflector_: Reflector,
+    next_task_id: Cell<u64>,
+    /// The tasks that are waiting for their delay or for their task source, by id.
+    tasks: DomRefCell<HashMap<u64, PostedTask>>,
+}
+
+impl Scheduler {
+    fn new_inherited() -> Scheduler {
+        Scheduler {
+            reflector_: Reflector::new(),
+            next_task_id: Cell::new(0),
+            tasks: Default::default(),
+        }
+    }
+
+    pub fn new(window: &Window) -> DomRoot<Scheduler> {
+        reflect_dom_object(Box::new(Scheduler::new_inherited()), window)
+    }
+
+    /// Queues a task that runs the posted task of `id` on the task source of its priority.
+    /// User-blocking tasks go along with input events, and background tasks are throttled
+    /// along with idle callbacks so that they don't delay other tasks.
+    /// <https://wicg.github.io/scheduling-apis/#schedule-a-task-to-invoke-an-algorithm>
+    fn queue_task(&self, id: u64) {
+        let (priority, generation) = {
+            let mut tasks = self.tasks.borrow_mut();
+            let task = match tasks.get_mut(&id) {
+                Some(task) => task,
+                None => return,
+            };
+            task.ready = true;
+            task.generation += 1;
+            (task.priority(), task.generation)
+        };
+
+        let global = self.global();
+        let window = global
+            .downcast::<Window>()
+            .expect("Scheduler is only exposed to Window");
+        let task_manager = window.task_manager();
+        let scheduler = Trusted::new(self);
+        let task = task!(run_posted_task: move || {
+            scheduler.root().run_task(id, generation);
+        });
+        let _ = match priority {
+            TaskPriority::User_blocking => task_manager
+                .user_interaction_task_source()
+                .queue(task, &global),
+            TaskPriority::User_visible => task_manager
+                .dom_manipulation_task_source()
+                .queue(task, &global),
+            TaskPriority::Background => task_manager.idle_task_source().queue(task, &global),
+        };
+    }
+
+    /// Runs the posted task of `id`, unless it was aborted, or queued again since.
+    /// <https://wicg.github.io/scheduling-apis/#schedule-a-posttask-task> step 7.
+    #[allow(unrooted_must_root)]
+    fn run_task(&self, id: u64, generation: u32) {
+        let task = {
+            let mut tasks = self.tasks.borrow_mut();
+            match tasks.get(&id) {
+                Some(task) if task.generation == generation => {},
+                _ => return,
+            }
+            tasks.remove(&id).expect("The task is pending")
+        };
+
+        let global = self.global();
+        let _ac = enter_realm(&*global);
+        let cx = global.get_cx();
+        match task.callback.Call__(ExceptionHandling::Rethrow) {
+            Ok(value) => {
+                rooted!(in(*cx) let value = value);
+                task.promise.resolve(cx, value.handle());
+            },
+            Err(error) => {
+                rooted!(in(*cx) let mut error_value = UndefinedValue());
+                take_exception(&global, error, error_value.handle_mut());
+                task.promise.reject(cx, error_value.handle());
+            },
+        }
+    }
+
+    /// Moves the queued tasks that follow the priority of `signal` to the task source of
+    /// its new priority.
+    /// <https://wicg.github.io/scheduling-apis/#tasksignal-priority-change-algorithms>
+    pub fn priority_changed(&self, signal: &TaskSignal) {
+        let ids: Vec<u64> = self
+            .tasks
+            .borrow()
+            .iter()
+            .filter(|(_, task)| {
+                task.ready &&
+                    task.priority_source
+                        .as_ref()
+                        .map_or(false, |source| &**source == signal)
+            })
+            .map(|(id, _)| *id)
+            .collect();
+        for id in ids {
+            self.queue_task(id);
+        }
+    }
+
+    /// Removes the posted task of `id` and rejects its promise with the reason of
+    /// `signal`.
+    /// <https://wicg.github.io/scheduling-apis/#dom-scheduler-posttask> step 5.
+    #[allow(unrooted_must_root)]
+    pub fn abort_task(&self, id: u64, signal: &AbortSignal) {
+        let task = match self.tasks.borrow_mut().remove(&id) {
+            Some(task) => task,
+            // The task ran already.
+            None => return,
+        };
+        let global = self.global();
+        let _ac = enter_realm(&*global);
+        let cx = global.get_cx();
+        rooted!(in(*cx) let reason = signal.reason());
+        task.promise.reject(cx, reason.handle());
+    }
+}
+
+impl SchedulerMethods for Scheduler {
+    // https://wicg.github.io/scheduling-apis/#dom-scheduler-posttask
+    #[allow(unrooted_must_root)]
+    fn PostTask(
+        &self,
+        callback: Rc<SchedulerPostTaskCallback>,
+        options: &SchedulerPostTaskOptions,
+    ) -> Rc<Promise> {
+        let global = self.global();
+
+        // Step 1.
+        let promise = Promise::new(&global);
+
+        // Step 2.
+        let signal = options.signal.as_ref();
+        if let Some(signal) = signal {
+            if signal.aborted() {
+                let _ac = enter_realm(&*global);
+                let cx = global.get_cx();
+                rooted!(in(*cx) let reason = signal.reason());
+                promise.reject(cx, reason.handle());
+                return promise;
+            }
+        }
+
+        // Steps 3-4. An explicit priority wins over the one of a `TaskSignal`.
+        let priority_source = match options.priority {
+            Some(_) => None,
+            None => signal
+                .and_then(|signal| signal.downcast::<TaskSignal>())
+                .map(Dom::from_ref),
+        };
+        let id = self.next_task_id.get();
+        self.next_task_id.set(id + 1);
+        self.tasks.borrow_mut().insert(
+            id,
+            PostedTask {
+                callback,
+                promise: promise.clone(),
+                priority_source,
+                priority: options.priority.unwrap_or(TaskPriority::User_visible),
+                ready: false,
+                generation: 0,
+            },
+        );
+
+        // Step 5.
+        if let Some(signal) = signal {
+            signal.add_abort_algorithm(AbortAlgorithm::PostedTask {
+                scheduler: Dom::from_ref(self),
+                id,
+            });
+        }
+
+        // Steps 6-7.
+        if options.delay > 0 {
+            let callback = OneshotTimerCallback::SchedulerPostTaskDelay(SchedulerDelayCallback {
+                scheduler: Trusted::new(self),
+                id,
+            });
+            global.schedule_callback(callback, MsDuration::new(options.delay));
+        } else {
+            self.queue_task(id);
+        }
+
+        // Step 8.
+        promise
+    }
+}
+
+/// Queues a task that was posted with a `delay`, once the delay elapsed.
+#[derive(JSTraceable, MallocSizeOf)]
+pub struct SchedulerDelayCallback {
+    #[ignore_malloc_size_of = "Because it is non-owning"]
+    scheduler: Trusted<Scheduler>,
+    id: u64,
+}
+
+impl SchedulerDelayCallback {
+    pub fn invoke(self) {
+        self.scheduler.root().queue_task(self.id);
+    }
+}
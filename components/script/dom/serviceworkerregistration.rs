@@ -16,7 +16,7 @@ use crate::dom::workerglobalscope::prepare_workerscope_init;
 use devtools_traits::WorkerId;
 use dom_struct::dom_struct;
 use msg::constellation_msg::ServiceWorkerRegistrationId;
-use script_traits::{ScopeThings, WorkerScriptLoadOrigin};
+use script_traits::{NavigationPreloadState, ScopeThings, ScriptMsg, WorkerScriptLoadOrigin};
 use servo_url::ServoUrl;
 use std::cell::Cell;
 use uuid::Uuid;
@@ -85,8 +85,8 @@ impl ServiceWorkerRegistration {
     }
 
     pub fn set_navigation_preload_header_value(&self, value: ByteString) {
-        let mut header_value = self.navigation_preload_header_value.borrow_mut();
-        *header_value = Some(value);
+        *self.navigation_preload_header_value.borrow_mut() = Some(value);
+        self.send_navigation_preload_state();
     }
 
     pub fn get_navigation_preload_enabled(&self) -> bool {
@@ -94,7 +94,24 @@ impl ServiceWorkerRegistration {
     }
 
     pub fn set_navigation_preload_enabled(&self, flag: bool) {
-        self.navigation_preload_enabled.set(flag)
+        self.navigation_preload_enabled.set(flag);
+        self.send_navigation_preload_state();
+    }
+
+    /// Tells the service worker manager, which decides whether a navigation
+    /// is preloaded, about a change of the navigation preload state.
+    fn send_navigation_preload_state(&self) {
+        let header_value = self
+            .get_navigation_preload_header_value()
+            .map_or_else(|| b"true".to_vec(), |value| value.into());
+        let state = NavigationPreloadState {
+            enabled: self.get_navigation_preload_enabled(),
+            header_value,
+        };
+        let _ = self
+            .global()
+            .script_to_constellation_chan()
+            .send(ScriptMsg::SetNavigationPreload(self.scope.clone(), state));
     }
 
     pub fn get_uninstalling(&self) -> bool {
@@ -35,7 +35,8 @@ use crate::dom::text::Text;
 use crate::dom::virtualmethods::vtable_for;
 use crate::network_listener::PreInvoke;
 use crate::script_thread::ScriptThread;
-use content_security_policy::{self as csp, CspList};
+use crate::security_manager::reporting_endpoints_from_headers;
+use content_security_policy as csp;
 use dom_struct::dom_struct;
 use embedder_traits::resources::{self, Resource};
 use encoding_rs::Encoding;
@@ -47,8 +48,8 @@ use html5ever::{Attribute, ExpandedName, LocalName, QualName};
 use hyper_serde::Serde;
 use mime::{self, Mime};
 use msg::constellation_msg::PipelineId;
-use net_traits::{FetchMetadata, FetchResponseListener, Metadata, NetworkError};
-use net_traits::{ResourceFetchTiming, ResourceTimingType};
+use net_traits::{csp_list_from_headers, FetchMetadata, FetchResponseListener, Metadata};
+use net_traits::{NetworkError, ResourceFetchTiming, ResourceTimingType};
 use profile_traits::time::{
     profile, ProfilerCategory, TimerMetadata, TimerMetadataFrameType, TimerMetadataReflowType,
 };
@@ -754,27 +755,17 @@ impl FetchResponseListener for ParserContext {
 
         // https://www.w3.org/TR/CSP/#initialize-document-csp
         // TODO: Implement step 1 (local scheme special case)
-        let csp_list = metadata.as_ref().and_then(|m| {
-            let h = m.headers.as_ref()?;
-            let mut csp = h.get_all("content-security-policy").iter();
-            // This silently ignores the CSP if it contains invalid Unicode.
-            // We should probably report an error somewhere.
-            let c = csp.next().and_then(|c| c.to_str().ok())?;
-            let mut csp_list = CspList::parse(
-                c,
-                csp::PolicySource::Header,
-                csp::PolicyDisposition::Enforce,
-            );
-            for c in csp {
-                let c = c.to_str().ok()?;
-                csp_list.append(CspList::parse(
-                    c,
-                    csp::PolicySource::Header,
-                    csp::PolicyDisposition::Enforce,
-                ));
-            }
-            Some(csp_list)
-        });
+        let csp_list = metadata
+            .as_ref()
+            .and_then(|m| m.headers.as_ref())
+            .and_then(|h| csp_list_from_headers(h));
+
+        // https://w3c.github.io/reporting/#process-header
+        let reporting_endpoints = metadata
+            .as_ref()
+            .and_then(|m| m.headers.as_ref())
+            .map(|h| reporting_endpoints_from_headers(h, &self.url))
+            .unwrap_or_default();
 
         let parser = match ScriptThread::page_headers_available(&self.id, metadata) {
             Some(parser) => parser,
@@ -785,6 +776,7 @@ impl FetchResponseListener for ParserContext {
         }
 
         parser.document.set_csp_list(csp_list);
+        parser.document.set_reporting_endpoints(reporting_endpoints);
 
         self.parser = Some(Trusted::new(&*parser));
 
@@ -921,6 +913,10 @@ impl FetchResponseListener for ParserContext {
         &self.resource_timing
     }
 
+    // Navigation requests are not given a policy to check against, see
+    // `ScriptThread::handle_msg_from_constellation`.
+    fn process_csp_violations(&mut self, _violations: Vec<csp::Violation>) {}
+
     // store a PerformanceNavigationTiming entry in the globalscope's Performance buffer
     fn submit_resource_timing(&mut self) {
         let parser = match self.parser.as_ref() {
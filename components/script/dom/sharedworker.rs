@@ -0,0 +1,101 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::codegen::Bindings::MessageChannelBinding::MessageChannelMethods;
+use crate::dom::bindings::codegen::Bindings::SharedWorkerBinding::SharedWorkerMethods;
+use crate::dom::bindings::codegen::UnionTypes::StringOrWorkerOptions;
+use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject};
+use crate::dom::bindings::root::{Dom, DomRoot};
+use crate::dom::bindings::str::USVString;
+use crate::dom::bindings::structuredclone;
+use crate::dom::eventtarget::EventTarget;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::messagechannel::MessageChannel;
+use crate::dom::messageport::MessagePort;
+use dom_struct::dom_struct;
+use js::jsval::ObjectValue;
+use js::rust::{CustomAutoRooter, CustomAutoRooterGuard};
+use script_traits::ScriptMsg;
+
+// https://html.spec.whatwg.org/multipage/#sharedworker
+#[dom_struct]
+pub struct SharedWorker {
+    eventtarget: EventTarget,
+    port: Dom<MessagePort>,
+}
+
+impl SharedWorker {
+    fn new_inherited(port: &MessagePort) -> SharedWorker {
+        SharedWorker {
+            eventtarget: EventTarget::new_inherited(),
+            port: Dom::from_ref(port),
+        }
+    }
+
+    pub fn new(global: &GlobalScope, port: &MessagePort) -> DomRoot<SharedWorker> {
+        reflect_dom_object(Box::new(SharedWorker::new_inherited(port)), global)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-sharedworker
+    #[allow(non_snake_case)]
+    pub fn Constructor(
+        global: &GlobalScope,
+        script_url: USVString,
+        options: StringOrWorkerOptions,
+    ) -> Fallible<DomRoot<SharedWorker>> {
+        // Step 1.
+        let name = match options {
+            StringOrWorkerOptions::String(name) => String::from(name),
+            StringOrWorkerOptions::WorkerOptions(options) => String::from(options.name),
+        };
+
+        // Steps 2-4.
+        let worker_url = match global.api_base_url().join(&script_url) {
+            Ok(url) => url,
+            Err(_) => return Err(Error::Syntax),
+        };
+        let origin = global.origin().immutable().clone();
+        if worker_url.origin() != origin {
+            return Err(Error::Security);
+        }
+
+        // Steps 5-7, the inside port is entangled with the outside port,
+        // and transferred to the worker with the connection.
+        let channel = MessageChannel::new(global);
+        let outside_port = channel.Port1();
+        let inside_port = channel.Port2();
+        let worker = SharedWorker::new(global, &outside_port);
+
+        let cx = global.get_cx();
+        let inside_port_object = inside_port.reflector().get_jsobject().get();
+        rooted!(in(*cx) let message = ObjectValue(inside_port_object));
+        let mut rooted = CustomAutoRooter::new(vec![inside_port_object]);
+        let transfer = CustomAutoRooterGuard::new(*cx, &mut rooted);
+        let data = structuredclone::write(cx, message.handle(), Some(transfer))?;
+
+        // Step 8, the constellation finds or runs the worker matching
+        // the origin, url and name, and fires a connect event at it.
+        let _ = global
+            .script_to_constellation_chan()
+            .send(ScriptMsg::ConnectSharedWorker {
+                script_url: worker_url,
+                name,
+                origin,
+                data,
+            });
+
+        Ok(worker)
+    }
+}
+
+impl SharedWorkerMethods for SharedWorker {
+    // https://html.spec.whatwg.org/multipage/#dom-sharedworker-port
+    fn Port(&self) -> DomRoot<MessagePort> {
+        DomRoot::from_ref(&*self.port)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#handler-abstractworker-onerror
+    event_handler!(error, GetOnerror, SetOnerror);
+}
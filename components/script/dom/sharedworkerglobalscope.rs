@@ -0,0 +1,511 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::abstractworkerglobalscope::{run_worker_event_loop, WorkerEventLoopMethods};
+use crate::dom::bindings::codegen::Bindings::SharedWorkerGlobalScopeBinding;
+use crate::dom::bindings::codegen::Bindings::SharedWorkerGlobalScopeBinding::SharedWorkerGlobalScopeMethods;
+use crate::dom::bindings::codegen::Bindings::WorkerBinding::WorkerType;
+use crate::dom::bindings::codegen::UnionTypes::WindowProxyOrMessagePortOrServiceWorker;
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::root::{DomRoot, RootCollection, ThreadLocalStackRoots};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::bindings::structuredclone;
+use crate::dom::dedicatedworkerglobalscope::AutoWorkerReset;
+use crate::dom::event::Event;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::identityhub::Identities;
+use crate::dom::messageevent::MessageEvent;
+use crate::dom::messageport::MessagePort;
+use crate::dom::worker::TrustedWorkerAddress;
+use crate::dom::workerglobalscope::WorkerGlobalScope;
+use crate::fetch::load_whole_resource;
+use crate::realms::{enter_realm, AlreadyInRealm, InRealm};
+use crate::script_runtime::{
+    new_rt_and_cx, CommonScriptMsg, ContextForRequestInterrupt, JSContext as SafeJSContext,
+    Runtime, ScriptChan,
+};
+use crate::task_queue::{QueuedTask, QueuedTaskConversion, TaskQueue};
+use crate::task_source::networking::NetworkingTaskSource;
+use crate::task_source::TaskSourceName;
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use devtools_traits::DevtoolScriptControlMsg;
+use dom_struct::dom_struct;
+use ipc_channel::ipc::IpcReceiver;
+use ipc_channel::router::ROUTER;
+use js::conversions::ToJSValConvertible;
+use js::jsapi::{JSContext, JS_AddInterruptCallback};
+use js::jsval::UndefinedValue;
+use msg::constellation_msg::{PipelineId, SharedWorkerId};
+use net_traits::image_cache::ImageCache;
+use net_traits::request::{
+    CredentialsMode, Destination, ParserMetadata, Referrer, RequestBuilder, RequestMode,
+};
+use net_traits::IpcSend;
+use parking_lot::Mutex;
+use script_traits::{
+    ScriptMsg, SharedWorkerMsg, StructuredSerializedData, WorkerGlobalScopeInit,
+    WorkerScriptLoadOrigin,
+};
+use servo_rand::random;
+use servo_url::ServoUrl;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use style::thread_state::{self, ThreadState};
+
+/// Messages used to control the shared worker event loop
+pub enum SharedWorkerScriptMsg {
+    /// Standard message from a worker.
+    Common(CommonScriptMsg),
+    /// A new client connected to the worker, with the port of the connection.
+    Connect(StructuredSerializedData),
+    /// Wake-up call from the task queue.
+    WakeUp,
+}
+
+impl QueuedTaskConversion for SharedWorkerScriptMsg {
+    fn task_source_name(&self) -> Option<&TaskSourceName> {
+        let script_msg = match self {
+            SharedWorkerScriptMsg::Common(script_msg) => script_msg,
+            _ => return None,
+        };
+        match script_msg {
+            CommonScriptMsg::Task(_category, _boxed, _pipeline_id, task_source) => {
+                Some(&task_source)
+            },
+            _ => None,
+        }
+    }
+
+    fn pipeline_id(&self) -> Option<PipelineId> {
+        // Workers always return None, since the pipeline_id is only used to check for document activity,
+        // and this check does not apply to worker event-loops.
+        None
+    }
+
+    fn into_queued_task(self) -> Option<QueuedTask> {
+        let script_msg = match self {
+            SharedWorkerScriptMsg::Common(script_msg) => script_msg,
+            _ => return None,
+        };
+        let (category, boxed, pipeline_id, task_source) = match script_msg {
+            CommonScriptMsg::Task(category, boxed, pipeline_id, task_source) => {
+                (category, boxed, pipeline_id, task_source)
+            },
+            _ => return None,
+        };
+        Some((None, category, boxed, pipeline_id, task_source))
+    }
+
+    fn from_queued_task(queued_task: QueuedTask) -> Self {
+        let (_worker, category, boxed, pipeline_id, task_source) = queued_task;
+        let script_msg = CommonScriptMsg::Task(category, boxed, pipeline_id, task_source);
+        SharedWorkerScriptMsg::Common(script_msg)
+    }
+
+    fn inactive_msg() -> Self {
+        // Inactive is only relevant in the context of a browsing-context event-loop.
+        panic!("Workers should never receive messages marked as inactive");
+    }
+
+    fn wake_up_msg() -> Self {
+        SharedWorkerScriptMsg::WakeUp
+    }
+
+    fn is_wake_up(&self) -> bool {
+        match self {
+            SharedWorkerScriptMsg::WakeUp => true,
+            _ => false,
+        }
+    }
+}
+
+/// Messages sent from the constellation, when the worker has no clients left.
+pub enum SharedWorkerControlMsg {
+    /// Shutdown.
+    Exit,
+}
+
+pub enum MixedMessage {
+    FromSharedWorker(SharedWorkerScriptMsg),
+    FromDevtools(DevtoolScriptControlMsg),
+    FromControl(SharedWorkerControlMsg),
+}
+
+#[derive(Clone, JSTraceable)]
+pub struct SharedWorkerChan {
+    pub sender: Sender<SharedWorkerScriptMsg>,
+}
+
+impl ScriptChan for SharedWorkerChan {
+    fn send(&self, msg: CommonScriptMsg) -> Result<(), ()> {
+        self.sender
+            .send(SharedWorkerScriptMsg::Common(msg))
+            .map_err(|_| ())
+    }
+
+    fn clone(&self) -> Box<dyn ScriptChan + Send> {
+        Box::new(SharedWorkerChan {
+            sender: self.sender.clone(),
+        })
+    }
+}
+
+unsafe_no_jsmanaged_fields!(TaskQueue<SharedWorkerScriptMsg>);
+
+// https://html.spec.whatwg.org/multipage/#sharedworkerglobalscope
+#[dom_struct]
+pub struct SharedWorkerGlobalScope {
+    workerglobalscope: WorkerGlobalScope,
+
+    #[ignore_malloc_size_of = "Defined in std"]
+    task_queue: TaskQueue<SharedWorkerScriptMsg>,
+
+    #[ignore_malloc_size_of = "Defined in std"]
+    own_sender: Sender<SharedWorkerScriptMsg>,
+
+    /// A receiver of control messages,
+    /// currently only used to signal shutdown.
+    #[ignore_malloc_size_of = "Channels are hard"]
+    control_receiver: Receiver<SharedWorkerControlMsg>,
+
+    #[ignore_malloc_size_of = "Arc"]
+    image_cache: Arc<dyn ImageCache>,
+}
+
+impl WorkerEventLoopMethods for SharedWorkerGlobalScope {
+    type WorkerMsg = SharedWorkerScriptMsg;
+    type ControlMsg = SharedWorkerControlMsg;
+    type Event = MixedMessage;
+
+    fn task_queue(&self) -> &TaskQueue<SharedWorkerScriptMsg> {
+        &self.task_queue
+    }
+
+    fn handle_event(&self, event: MixedMessage) -> bool {
+        self.handle_mixed_message(event)
+    }
+
+    fn handle_worker_post_event(&self, _worker: &TrustedWorkerAddress) -> Option<AutoWorkerReset> {
+        None
+    }
+
+    fn from_control_msg(&self, msg: SharedWorkerControlMsg) -> MixedMessage {
+        MixedMessage::FromControl(msg)
+    }
+
+    fn from_worker_msg(&self, msg: SharedWorkerScriptMsg) -> MixedMessage {
+        MixedMessage::FromSharedWorker(msg)
+    }
+
+    fn from_devtools_msg(&self, msg: DevtoolScriptControlMsg) -> MixedMessage {
+        MixedMessage::FromDevtools(msg)
+    }
+
+    fn control_receiver(&self) -> &Receiver<SharedWorkerControlMsg> {
+        &self.control_receiver
+    }
+}
+
+impl SharedWorkerGlobalScope {
+    fn new_inherited(
+        init: WorkerGlobalScopeInit,
+        worker_name: DOMString,
+        worker_url: ServoUrl,
+        from_devtools_receiver: Receiver<DevtoolScriptControlMsg>,
+        runtime: Runtime,
+        own_sender: Sender<SharedWorkerScriptMsg>,
+        receiver: Receiver<SharedWorkerScriptMsg>,
+        control_receiver: Receiver<SharedWorkerControlMsg>,
+        closing: Arc<AtomicBool>,
+        image_cache: Arc<dyn ImageCache>,
+    ) -> SharedWorkerGlobalScope {
+        SharedWorkerGlobalScope {
+            workerglobalscope: WorkerGlobalScope::new_inherited(
+                init,
+                worker_name,
+                WorkerType::Classic,
+                worker_url,
+                runtime,
+                from_devtools_receiver,
+                closing,
+                Arc::new(Mutex::new(Identities::new())),
+            ),
+            task_queue: TaskQueue::new(receiver, own_sender.clone()),
+            own_sender,
+            control_receiver,
+            image_cache,
+        }
+    }
+
+    #[allow(unsafe_code)]
+    pub fn new(
+        init: WorkerGlobalScopeInit,
+        worker_name: DOMString,
+        worker_url: ServoUrl,
+        from_devtools_receiver: Receiver<DevtoolScriptControlMsg>,
+        runtime: Runtime,
+        own_sender: Sender<SharedWorkerScriptMsg>,
+        receiver: Receiver<SharedWorkerScriptMsg>,
+        control_receiver: Receiver<SharedWorkerControlMsg>,
+        closing: Arc<AtomicBool>,
+        image_cache: Arc<dyn ImageCache>,
+    ) -> DomRoot<SharedWorkerGlobalScope> {
+        let cx = runtime.cx();
+        let scope = Box::new(SharedWorkerGlobalScope::new_inherited(
+            init,
+            worker_name,
+            worker_url,
+            from_devtools_receiver,
+            runtime,
+            own_sender,
+            receiver,
+            control_receiver,
+            closing,
+            image_cache,
+        ));
+        unsafe { SharedWorkerGlobalScopeBinding::Wrap(SafeJSContext::from_ptr(cx), scope) }
+    }
+
+    #[allow(unsafe_code)]
+    // https://html.spec.whatwg.org/multipage/#run-a-worker
+    pub fn run_sharedworker_scope(
+        init: WorkerGlobalScopeInit,
+        worker_id: SharedWorkerId,
+        worker_url: ServoUrl,
+        worker_name: String,
+        worker_load_origin: WorkerScriptLoadOrigin,
+        referrer: Referrer,
+        constellation_receiver: IpcReceiver<SharedWorkerMsg>,
+        image_cache: Arc<dyn ImageCache>,
+    ) -> JoinHandle<()> {
+        let serialized_worker_url = worker_url.to_string();
+        let origin = init.origin.clone();
+        thread::Builder::new()
+            .name(format!("SharedWorker for {}", serialized_worker_url))
+            .spawn(move || {
+                thread_state::initialize(ThreadState::SCRIPT | ThreadState::IN_WORKER);
+
+                let roots = RootCollection::new();
+                let _stack_roots = ThreadLocalStackRoots::new(&roots);
+
+                let WorkerScriptLoadOrigin {
+                    referrer_url,
+                    referrer_policy,
+                    pipeline_id,
+                } = worker_load_origin;
+
+                let (own_sender, receiver) = unbounded();
+                let (control_sender, control_receiver) = unbounded();
+                let closing = Arc::new(AtomicBool::new(false));
+
+                // A shared worker outlives the document which started it,
+                // so its runtime has no parent.
+                let runtime = new_rt_and_cx(Some(NetworkingTaskSource(
+                    Box::new(SharedWorkerChan {
+                        sender: own_sender.clone(),
+                    }),
+                    pipeline_id,
+                )));
+
+                // Route the connections and the shutdown from the constellation to the
+                // event loop. The context is only available while the runtime is alive.
+                let context = Arc::new(Mutex::new(Some(ContextForRequestInterrupt::new(
+                    runtime.cx(),
+                ))));
+                let route_context = context.clone();
+                let route_sender = own_sender.clone();
+                let route_closing = closing.clone();
+                ROUTER.add_route(
+                    constellation_receiver.to_opaque(),
+                    Box::new(move |message| match message.to() {
+                        Ok(SharedWorkerMsg::Connect(data)) => {
+                            let _ = route_sender.send(SharedWorkerScriptMsg::Connect(data));
+                        },
+                        Ok(SharedWorkerMsg::Exit) => {
+                            route_closing.store(true, Ordering::SeqCst);
+                            let _ = control_sender.send(SharedWorkerControlMsg::Exit);
+                            if let Some(context) = route_context.lock().as_ref() {
+                                context.request_interrupt();
+                            }
+                        },
+                        Err(err) => warn!("Error receiving a SharedWorkerMsg: {:?}", err),
+                    }),
+                );
+
+                // Devtools are not supported for shared workers.
+                let (_devtools_sender, devtools_receiver) = unbounded();
+
+                let global = SharedWorkerGlobalScope::new(
+                    init,
+                    DOMString::from_string(worker_name),
+                    worker_url.clone(),
+                    devtools_receiver,
+                    runtime,
+                    own_sender,
+                    receiver,
+                    control_receiver,
+                    closing,
+                    image_cache,
+                );
+                let scope = global.upcast::<WorkerGlobalScope>();
+                let global_scope = global.upcast::<GlobalScope>();
+
+                let referrer = referrer_url
+                    .map(|url| Referrer::ReferrerUrl(url))
+                    .unwrap_or(referrer);
+
+                let request = RequestBuilder::new(worker_url, referrer)
+                    .destination(Destination::SharedWorker)
+                    .mode(RequestMode::SameOrigin)
+                    .credentials_mode(CredentialsMode::CredentialsSameOrigin)
+                    .parser_metadata(ParserMetadata::NotParserInserted)
+                    .use_url_credentials(true)
+                    .pipeline_id(Some(pipeline_id))
+                    .referrer_policy(referrer_policy)
+                    .origin(origin);
+
+                let source = match load_whole_resource(
+                    request,
+                    &global_scope.resource_threads().sender(),
+                    &global_scope,
+                ) {
+                    Err(_) => {
+                        println!("error loading script {}", serialized_worker_url);
+                        None
+                    },
+                    Ok((_metadata, bytes)) => Some(String::from_utf8_lossy(&bytes).into_owned()),
+                };
+
+                if let Some(source) = source {
+                    unsafe {
+                        // Handle interrupt requests
+                        JS_AddInterruptCallback(*scope.get_cx(), Some(interrupt_callback));
+                    }
+
+                    scope.execute_script(DOMString::from(source));
+
+                    let reporter_name = format!("shared-worker-reporter-{}", random::<u64>());
+                    global_scope.mem_profiler_chan().run_with_memory_reporting(
+                        || {
+                            // Run the responsible event loop specified by inside settings
+                            // until it is destroyed, which happens after the closing flag
+                            // is set to true, when the worker closes itself,
+                            // or after its last client went away.
+                            while !scope.is_closing() {
+                                run_worker_event_loop(&*global, None);
+                            }
+                        },
+                        reporter_name,
+                        scope.script_chan(),
+                        CommonScriptMsg::CollectReports,
+                    );
+                }
+
+                // Stop accepting connections.
+                *context.lock() = None;
+                let _ = global_scope
+                    .script_to_constellation_chan()
+                    .send(ScriptMsg::SharedWorkerClosed(worker_id));
+                scope.clear_js_runtime();
+            })
+            .expect("Thread spawning failed")
+    }
+
+    pub fn image_cache(&self) -> Arc<dyn ImageCache> {
+        self.image_cache.clone()
+    }
+
+    pub fn script_chan(&self) -> Box<dyn ScriptChan + Send> {
+        Box::new(SharedWorkerChan {
+            sender: self.own_sender.clone(),
+        })
+    }
+
+    fn handle_mixed_message(&self, msg: MixedMessage) -> bool {
+        match msg {
+            MixedMessage::FromDevtools(_) => {
+                debug!("got an unusable devtools control message inside the worker!")
+            },
+            MixedMessage::FromSharedWorker(msg) => {
+                self.handle_script_event(msg);
+            },
+            MixedMessage::FromControl(SharedWorkerControlMsg::Exit) => {
+                return false;
+            },
+        }
+        true
+    }
+
+    fn handle_script_event(&self, msg: SharedWorkerScriptMsg) {
+        match msg {
+            SharedWorkerScriptMsg::Connect(data) => {
+                let scope = self.upcast::<WorkerGlobalScope>();
+                let _ac = enter_realm(&*scope);
+                rooted!(in(*scope.get_cx()) let mut message = UndefinedValue());
+                match structuredclone::read(scope.upcast(), data, message.handle_mut()) {
+                    Ok(ports) => match ports.into_iter().next() {
+                        Some(port) => self.dispatch_connect(port),
+                        None => warn!("Shared worker connection without a port."),
+                    },
+                    Err(_) => warn!("Failed to read the port of a shared worker connection."),
+                }
+            },
+            SharedWorkerScriptMsg::Common(msg) => {
+                self.upcast::<WorkerGlobalScope>().process_event(msg);
+            },
+            SharedWorkerScriptMsg::WakeUp => {},
+        }
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-sharedworker>
+    /// Step 11.7, fire a connect event with the inside port.
+    #[allow(unsafe_code)]
+    fn dispatch_connect(&self, port: DomRoot<MessagePort>) {
+        let global = self.upcast::<GlobalScope>();
+        let cx = global.get_cx();
+        rooted!(in(*cx) let mut data = UndefinedValue());
+        unsafe { "".to_jsval(*cx, data.handle_mut()) };
+        let source = WindowProxyOrMessagePortOrServiceWorker::MessagePort(port.clone());
+        let event = MessageEvent::new(
+            global,
+            atom!("connect"),
+            false,
+            false,
+            data.handle(),
+            DOMString::new(),
+            Some(&source),
+            DOMString::new(),
+            vec![port],
+        );
+        event.upcast::<Event>().fire(self.upcast());
+    }
+}
+
+#[allow(unsafe_code)]
+unsafe extern "C" fn interrupt_callback(cx: *mut JSContext) -> bool {
+    let in_realm_proof = AlreadyInRealm::assert_for_cx(SafeJSContext::from_ptr(cx));
+    let global = GlobalScope::from_context(cx, InRealm::Already(&in_realm_proof));
+    let worker =
+        DomRoot::downcast::<WorkerGlobalScope>(global).expect("global is not a worker scope");
+    assert!(worker.is::<SharedWorkerGlobalScope>());
+
+    // A false response causes the script to terminate
+    !worker.is_closing()
+}
+
+impl SharedWorkerGlobalScopeMethods for SharedWorkerGlobalScope {
+    // https://html.spec.whatwg.org/multipage/#dom-sharedworkerglobalscope-name
+    fn Name(&self) -> DOMString {
+        self.upcast::<WorkerGlobalScope>().worker_name()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-sharedworkerglobalscope-close
+    fn Close(&self) {
+        self.upcast::<WorkerGlobalScope>().close();
+    }
+
+    // https://html.spec.whatwg.org/multipage/#handler-sharedworkerglobalscope-onconnect
+    event_handler!(connect, GetOnconnect, SetOnconnect);
+}
@@ -0,0 +1,2475 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::codegen::Bindings::CryptoKeyBinding::{KeyType, KeyUsage};
+use crate::dom::bindings::codegen::Bindings::SubtleCryptoBinding::{
+    AesCbcParams, AesDerivedKeyParams, AesGcmParams, AesKeyGenParams, Algorithm, CryptoKeyPair,
+    EcKeyGenParams, EcKeyImportParams, EcdhKeyDeriveParams, EcdsaParams, HkdfParams,
+    HmacImportParams, HmacKeyGenParams, JsonWebKey, KeyFormat, Pbkdf2Params, RsaHashedImportParams,
+    RsaHashedKeyGenParams, RsaOaepParams, RsaOtherPrimesInfo, RsaPssParams, SubtleCryptoMethods,
+};
+use crate::dom::bindings::codegen::UnionTypes::{
+    ArrayBufferViewOrArrayBuffer, BufferSourceOrJsonWebKey, ObjectOrString,
+};
+use crate::dom::bindings::conversions::{ConversionResult, FromJSValConvertible};
+use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::refcounted::TrustedPromise;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::DomRoot;
+use crate::dom::bindings::str::DOMString;
+use crate::dom::bindings::trace::RootedTraceableBox;
+use crate::dom::bindings::utils::set_dictionary_property;
+use crate::dom::cryptokey::{usage_intersection, CryptoKey, Handle, KeyData};
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::promise::Promise;
+use crate::realms::enter_realm;
+use crate::script_runtime::JSContext;
+use crate::task_source::{TaskSource, TaskSourceName};
+use dom_struct::dom_struct;
+use js::conversions::ToJSValConvertible;
+use js::jsapi::{JSObject, JS_NewPlainObject};
+use js::jsval::{ObjectValue, UndefinedValue};
+use js::rust::{HandleObject, HandleValue, MutableHandleObject};
+use js::typedarray::{ArrayBuffer, CreateWith, Uint8Array};
+use openssl::bn::{BigNum, BigNumContext, BigNumRef};
+use openssl::derive::Deriver;
+use openssl::ec::{EcGroup, EcKey, EcPoint, PointConversionForm};
+use openssl::ecdsa::EcdsaSig;
+use openssl::encrypt::{Decrypter, Encrypter};
+use openssl::error::ErrorStack;
+use openssl::hash::{self, MessageDigest};
+use openssl::memcmp;
+use openssl::nid::Nid;
+use openssl::pkcs5;
+use openssl::pkey::{HasPublic, Id, PKey, PKeyRef, Private, Public};
+use openssl::rand;
+use openssl::rsa::{Padding, Rsa};
+use openssl::sign::{RsaPssSaltlen, Signer, Verifier};
+use openssl::symm::{self, Cipher};
+use script_traits::serializable::KeyAlgorithmImpl;
+use std::ptr;
+use std::rc::Rc;
+
+/// The names of the supported algorithms.
+/// <https://w3c.github.io/webcrypto/#algorithm-overview>
+#[derive(Clone, Copy, Debug, JSTraceable, MallocSizeOf, PartialEq)]
+pub enum AlgorithmName {
+    RsassaPkcs1V15,
+    RsaPss,
+    RsaOaep,
+    Ecdsa,
+    Ecdh,
+    AesCbc,
+    AesGcm,
+    Hmac,
+    Hkdf,
+    Pbkdf2,
+    Sha1,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl AlgorithmName {
+    const ALL: [AlgorithmName; 14] = [
+        AlgorithmName::RsassaPkcs1V15,
+        AlgorithmName::RsaPss,
+        AlgorithmName::RsaOaep,
+        AlgorithmName::Ecdsa,
+        AlgorithmName::Ecdh,
+        AlgorithmName::AesCbc,
+        AlgorithmName::AesGcm,
+        AlgorithmName::Hmac,
+        AlgorithmName::Hkdf,
+        AlgorithmName::Pbkdf2,
+        AlgorithmName::Sha1,
+        AlgorithmName::Sha256,
+        AlgorithmName::Sha384,
+        AlgorithmName::Sha512,
+    ];
+
+    /// Algorithm names are matched ASCII case-insensitively.
+    /// <https://w3c.github.io/webcrypto/#algorithm-normalization-normalize-an-algorithm>
+    fn from_name(name: &str) -> Option<AlgorithmName> {
+        AlgorithmName::ALL
+            .iter()
+            .cloned()
+            .find(|algorithm| algorithm.as_str().eq_ignore_ascii_case(name))
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            AlgorithmName::RsassaPkcs1V15 => "RSASSA-PKCS1-v1_5",
+            AlgorithmName::RsaPss => "RSA-PSS",
+            AlgorithmName::RsaOaep => "RSA-OAEP",
+            AlgorithmName::Ecdsa => "ECDSA",
+            AlgorithmName::Ecdh => "ECDH",
+            AlgorithmName::AesCbc => "AES-CBC",
+            AlgorithmName::AesGcm => "AES-GCM",
+            AlgorithmName::Hmac => "HMAC",
+            AlgorithmName::Hkdf => "HKDF",
+            AlgorithmName::Pbkdf2 => "PBKDF2",
+            AlgorithmName::Sha1 => "SHA-1",
+            AlgorithmName::Sha256 => "SHA-256",
+            AlgorithmName::Sha384 => "SHA-384",
+            AlgorithmName::Sha512 => "SHA-512",
+        }
+    }
+}
+
+/// <https://w3c.github.io/webcrypto/#sha>
+#[derive(Clone, Copy, Debug, JSTraceable, MallocSizeOf, PartialEq)]
+pub enum HashAlgorithm {
+    Sha1,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl HashAlgorithm {
+    fn from_algorithm_name(name: AlgorithmName) -> Option<HashAlgorithm> {
+        match name {
+            AlgorithmName::Sha1 => Some(HashAlgorithm::Sha1),
+            AlgorithmName::Sha256 => Some(HashAlgorithm::Sha256),
+            AlgorithmName::Sha384 => Some(HashAlgorithm::Sha384),
+            AlgorithmName::Sha512 => Some(HashAlgorithm::Sha512),
+            _ => None,
+        }
+    }
+
+    fn algorithm_name(&self) -> AlgorithmName {
+        match *self {
+            HashAlgorithm::Sha1 => AlgorithmName::Sha1,
+            HashAlgorithm::Sha256 => AlgorithmName::Sha256,
+            HashAlgorithm::Sha384 => AlgorithmName::Sha384,
+            HashAlgorithm::Sha512 => AlgorithmName::Sha512,
+        }
+    }
+
+    fn message_digest(&self) -> MessageDigest {
+        match *self {
+            HashAlgorithm::Sha1 => MessageDigest::sha1(),
+            HashAlgorithm::Sha256 => MessageDigest::sha256(),
+            HashAlgorithm::Sha384 => MessageDigest::sha384(),
+            HashAlgorithm::Sha512 => MessageDigest::sha512(),
+        }
+    }
+
+    /// The block size in bits, which is the default length of HMAC keys.
+    fn block_size(&self) -> u32 {
+        match *self {
+            HashAlgorithm::Sha1 | HashAlgorithm::Sha256 => 512,
+            HashAlgorithm::Sha384 | HashAlgorithm::Sha512 => 1024,
+        }
+    }
+
+    /// The suffix of the JWK "alg" values using this hash, as in "HS256".
+    fn jwk_suffix(&self) -> &'static str {
+        match *self {
+            HashAlgorithm::Sha1 => "1",
+            HashAlgorithm::Sha256 => "256",
+            HashAlgorithm::Sha384 => "384",
+            HashAlgorithm::Sha512 => "512",
+        }
+    }
+}
+
+/// <https://w3c.github.io/webcrypto/#dfn-NamedCurve>
+#[derive(Clone, Copy, Debug, JSTraceable, MallocSizeOf, PartialEq)]
+pub enum NamedCurve {
+    P256,
+    P384,
+    P521,
+}
+
+impl NamedCurve {
+    /// Unlike algorithm names, curve names are case-sensitive.
+    fn from_name(name: &str) -> Option<NamedCurve> {
+        match name {
+            "P-256" => Some(NamedCurve::P256),
+            "P-384" => Some(NamedCurve::P384),
+            "P-521" => Some(NamedCurve::P521),
+            _ => None,
+        }
+    }
+
+    fn from_jwk_ecdsa_alg(alg: &str) -> Option<NamedCurve> {
+        match alg {
+            "ES256" => Some(NamedCurve::P256),
+            "ES384" => Some(NamedCurve::P384),
+            "ES512" => Some(NamedCurve::P521),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            NamedCurve::P256 => "P-256",
+            NamedCurve::P384 => "P-384",
+            NamedCurve::P521 => "P-521",
+        }
+    }
+
+    fn nid(&self) -> Nid {
+        match *self {
+            NamedCurve::P256 => Nid::X9_62_PRIME256V1,
+            NamedCurve::P384 => Nid::SECP384R1,
+            NamedCurve::P521 => Nid::SECP521R1,
+        }
+    }
+
+    fn group(&self) -> Fallible<EcGroup> {
+        EcGroup::from_curve_name(self.nid()).map_err(|_| Error::Operation)
+    }
+
+    fn jwk_ecdsa_alg(&self) -> &'static str {
+        match *self {
+            NamedCurve::P256 => "ES256",
+            NamedCurve::P384 => "ES384",
+            NamedCurve::P521 => "ES512",
+        }
+    }
+
+    /// The size in bytes of the coordinates of the points of the curve.
+    fn coordinate_size(&self) -> usize {
+        match *self {
+            NamedCurve::P256 => 32,
+            NamedCurve::P384 => 48,
+            NamedCurve::P521 => 66,
+        }
+    }
+}
+
+/// The algorithm of a key, reflected by its `algorithm` attribute.
+/// <https://w3c.github.io/webcrypto/#dfn-CryptoKey-slot-algorithm>
+#[derive(Clone, JSTraceable, MallocSizeOf)]
+pub enum KeyAlgorithm {
+    /// <https://w3c.github.io/webcrypto/#dfn-KeyAlgorithm>
+    Basic(AlgorithmName),
+    /// <https://w3c.github.io/webcrypto/#dfn-AesKeyAlgorithm>
+    Aes { name: AlgorithmName, length: u16 },
+    /// <https://w3c.github.io/webcrypto/#dfn-HmacKeyAlgorithm>
+    Hmac { hash: HashAlgorithm, length: u32 },
+    /// <https://w3c.github.io/webcrypto/#dfn-RsaHashedKeyAlgorithm>
+    RsaHashed {
+        name: AlgorithmName,
+        modulus_length: u32,
+        public_exponent: Vec<u8>,
+        hash: HashAlgorithm,
+    },
+    /// <https://w3c.github.io/webcrypto/#dfn-EcKeyAlgorithm>
+    Ec {
+        name: AlgorithmName,
+        curve: NamedCurve,
+    },
+}
+
+impl KeyAlgorithm {
+    pub fn name(&self) -> AlgorithmName {
+        match *self {
+            KeyAlgorithm::Basic(name) |
+            KeyAlgorithm::Aes { name, .. } |
+            KeyAlgorithm::RsaHashed { name, .. } |
+            KeyAlgorithm::Ec { name, .. } => name,
+            KeyAlgorithm::Hmac { .. } => AlgorithmName::Hmac,
+        }
+    }
+
+    fn hash(&self) -> Option<HashAlgorithm> {
+        match *self {
+            KeyAlgorithm::Hmac { hash, .. } | KeyAlgorithm::RsaHashed { hash, .. } => Some(hash),
+            _ => None,
+        }
+    }
+
+    fn curve(&self) -> Option<NamedCurve> {
+        match *self {
+            KeyAlgorithm::Ec { curve, .. } => Some(curve),
+            _ => None,
+        }
+    }
+
+    /// The members of this algorithm, as they are serialized.
+    pub fn to_impl(&self) -> KeyAlgorithmImpl {
+        let mut algorithm = KeyAlgorithmImpl {
+            name: self.name().as_str().to_owned(),
+            length: None,
+            hash: self
+                .hash()
+                .map(|hash| hash.algorithm_name().as_str().to_owned()),
+            modulus_length: None,
+            public_exponent: None,
+            named_curve: self.curve().map(|curve| curve.as_str().to_owned()),
+        };
+        match *self {
+            KeyAlgorithm::Aes { length, .. } => algorithm.length = Some(length as u32),
+            KeyAlgorithm::Hmac { length, .. } => algorithm.length = Some(length),
+            KeyAlgorithm::RsaHashed {
+                modulus_length,
+                ref public_exponent,
+                ..
+            } => {
+                algorithm.modulus_length = Some(modulus_length);
+                algorithm.public_exponent = Some(public_exponent.clone());
+            },
+            KeyAlgorithm::Basic(_) | KeyAlgorithm::Ec { .. } => {},
+        }
+        algorithm
+    }
+
+    /// The algorithm with the serialized members, if they describe a supported one.
+    pub fn from_impl(algorithm: &KeyAlgorithmImpl) -> Option<KeyAlgorithm> {
+        let name = AlgorithmName::from_name(&algorithm.name)?;
+        let hash = || {
+            algorithm
+                .hash
+                .as_ref()
+                .and_then(|hash| AlgorithmName::from_name(hash))
+                .and_then(HashAlgorithm::from_algorithm_name)
+        };
+        Some(match name {
+            AlgorithmName::AesCbc | AlgorithmName::AesGcm => KeyAlgorithm::Aes {
+                name,
+                length: algorithm.length? as u16,
+            },
+            AlgorithmName::Hmac => KeyAlgorithm::Hmac {
+                hash: hash()?,
+                length: algorithm.length?,
+            },
+            AlgorithmName::RsassaPkcs1V15 | AlgorithmName::RsaPss | AlgorithmName::RsaOaep => {
+                KeyAlgorithm::RsaHashed {
+                    name,
+                    modulus_length: algorithm.modulus_length?,
+                    public_exponent: algorithm.public_exponent.clone()?,
+                    hash: hash()?,
+                }
+            },
+            AlgorithmName::Ecdsa | AlgorithmName::Ecdh => KeyAlgorithm::Ec {
+                name,
+                curve: NamedCurve::from_name(algorithm.named_curve.as_ref()?)?,
+            },
+            _ => KeyAlgorithm::Basic(name),
+        })
+    }
+
+    /// Create the object reflecting this algorithm.
+    #[allow(unsafe_code)]
+    pub fn to_js_object(&self, cx: JSContext, mut rval: MutableHandleObject) {
+        unsafe {
+            rval.set(JS_NewPlainObject(*cx));
+            let object = rval.handle();
+            set_property(cx, object, "name", &DOMString::from(self.name().as_str()));
+            match *self {
+                KeyAlgorithm::Basic(_) => {},
+                KeyAlgorithm::Aes { length, .. } => set_property(cx, object, "length", &length),
+                KeyAlgorithm::Hmac { hash, length } => {
+                    set_hash_property(cx, object, hash);
+                    set_property(cx, object, "length", &length);
+                },
+                KeyAlgorithm::RsaHashed {
+                    modulus_length,
+                    ref public_exponent,
+                    hash,
+                    ..
+                } => {
+                    set_property(cx, object, "modulusLength", &modulus_length);
+                    rooted!(in(*cx) let mut exponent = ptr::null_mut::<JSObject>());
+                    assert!(Uint8Array::create(
+                        *cx,
+                        CreateWith::Slice(public_exponent),
+                        exponent.handle_mut()
+                    )
+                    .is_ok());
+                    set_property(cx, object, "publicExponent", &*exponent);
+                    set_hash_property(cx, object, hash);
+                },
+                KeyAlgorithm::Ec { curve, .. } => {
+                    set_property(cx, object, "namedCurve", &DOMString::from(curve.as_str()))
+                },
+            }
+        }
+    }
+}
+
+#[allow(unsafe_code)]
+unsafe fn set_property<T: ToJSValConvertible + ?Sized>(
+    cx: JSContext,
+    object: HandleObject,
+    name: &str,
+    value: &T,
+) {
+    rooted!(in(*cx) let mut property = UndefinedValue());
+    value.to_jsval(*cx, property.handle_mut());
+    let _ = set_dictionary_property(*cx, object, name, property.handle());
+}
+
+/// Set the `hash` member of an algorithm object, a `KeyAlgorithm` with the hash name.
+#[allow(unsafe_code)]
+unsafe fn set_hash_property(cx: JSContext, object: HandleObject, hash: HashAlgorithm) {
+    rooted!(in(*cx) let mut hash_object = ptr::null_mut::<JSObject>());
+    KeyAlgorithm::Basic(hash.algorithm_name()).to_js_object(cx, hash_object.handle_mut());
+    set_property(cx, object, "hash", &*hash_object);
+}
+
+/// The operations an algorithm is normalized for.
+/// <https://w3c.github.io/webcrypto/#supported-operation>
+#[derive(Clone, Copy, PartialEq)]
+enum Operation {
+    Encrypt,
+    Decrypt,
+    Sign,
+    Verify,
+    Digest,
+    GenerateKey,
+    DeriveBits,
+    ImportKey,
+    GetKeyLength,
+}
+
+/// The result of normalizing an algorithm, which unlike the dictionaries it
+/// comes from can be sent to the thread running the operation.
+enum NormalizedAlgorithm {
+    /// An algorithm without parameters.
+    Basic(AlgorithmName),
+    RsaHashedKeyGen {
+        name: AlgorithmName,
+        modulus_length: u32,
+        public_exponent: Vec<u8>,
+        hash: HashAlgorithm,
+    },
+    RsaHashedImport {
+        name: AlgorithmName,
+        hash: HashAlgorithm,
+    },
+    RsaPss {
+        salt_length: u32,
+    },
+    RsaOaep {
+        label: Option<Vec<u8>>,
+    },
+    Ecdsa {
+        hash: HashAlgorithm,
+    },
+    /// The parameters of ECDSA and ECDH key generation and import. The curve
+    /// is checked by the operations, which throw different errors.
+    EcKey {
+        name: AlgorithmName,
+        curve: String,
+    },
+    EcdhKeyDerive {
+        public: KeyData,
+    },
+    AesCbc {
+        iv: Vec<u8>,
+    },
+    AesGcm {
+        iv: Vec<u8>,
+        additional_data: Option<Vec<u8>>,
+        tag_length: Option<u8>,
+    },
+    /// The parameters of AES key generation, and of the key length of derived AES keys.
+    AesKey {
+        name: AlgorithmName,
+        length: u16,
+    },
+    /// The parameters of HMAC key generation and import.
+    Hmac {
+        hash: HashAlgorithm,
+        length: Option<u32>,
+    },
+    Hkdf {
+        hash: HashAlgorithm,
+        salt: Vec<u8>,
+        info: Vec<u8>,
+    },
+    Pbkdf2 {
+        hash: HashAlgorithm,
+        salt: Vec<u8>,
+        iterations: u32,
+    },
+}
+
+impl NormalizedAlgorithm {
+    fn name(&self) -> AlgorithmName {
+        match *self {
+            NormalizedAlgorithm::Basic(name) |
+            NormalizedAlgorithm::RsaHashedKeyGen { name, .. } |
+            NormalizedAlgorithm::RsaHashedImport { name, .. } |
+            NormalizedAlgorithm::EcKey { name, .. } |
+            NormalizedAlgorithm::AesKey { name, .. } => name,
+            NormalizedAlgorithm::RsaPss { .. } => AlgorithmName::RsaPss,
+            NormalizedAlgorithm::RsaOaep { .. } => AlgorithmName::RsaOaep,
+            NormalizedAlgorithm::Ecdsa { .. } => AlgorithmName::Ecdsa,
+            NormalizedAlgorithm::EcdhKeyDerive { .. } => AlgorithmName::Ecdh,
+            NormalizedAlgorithm::AesCbc { .. } => AlgorithmName::AesCbc,
+            NormalizedAlgorithm::AesGcm { .. } => AlgorithmName::AesGcm,
+            NormalizedAlgorithm::Hmac { .. } => AlgorithmName::Hmac,
+            NormalizedAlgorithm::Hkdf { .. } => AlgorithmName::Hkdf,
+            NormalizedAlgorithm::Pbkdf2 { .. } => AlgorithmName::Pbkdf2,
+        }
+    }
+}
+
+fn buffer_source_to_vec(source: &ArrayBufferViewOrArrayBuffer) -> Vec<u8> {
+    match *source {
+        ArrayBufferViewOrArrayBuffer::ArrayBufferView(ref view) => view.to_vec(),
+        ArrayBufferViewOrArrayBuffer::ArrayBuffer(ref buffer) => buffer.to_vec(),
+    }
+}
+
+/// Convert a value to the dictionary an algorithm is normalized to.
+#[allow(unsafe_code)]
+fn value_to_dictionary<T: FromJSValConvertible<Config = ()>>(
+    cx: JSContext,
+    value: HandleValue,
+) -> Fallible<T> {
+    match unsafe { T::from_jsval(*cx, value, ()) } {
+        Ok(ConversionResult::Success(dictionary)) => Ok(dictionary),
+        Ok(ConversionResult::Failure(error)) => Err(Error::Type(error.into_owned())),
+        Err(()) => Err(Error::JSFailed),
+    }
+}
+
+/// <https://w3c.github.io/webcrypto/#algorithm-normalization-normalize-an-algorithm>
+#[allow(unsafe_code)]
+fn normalize_algorithm(
+    cx: JSContext,
+    algorithm: &ObjectOrString,
+    operation: Operation,
+) -> Fallible<NormalizedAlgorithm> {
+    rooted!(in(*cx) let mut value = UndefinedValue());
+    match *algorithm {
+        ObjectOrString::Object(ref object) => value.set(ObjectValue(object.get())),
+        // A string is normalized as the dictionary {name: alg}.
+        ObjectOrString::String(ref name) => unsafe {
+            rooted!(in(*cx) let object = JS_NewPlainObject(*cx));
+            set_property(cx, object.handle(), "name", name);
+            value.set(ObjectValue(object.get()));
+        },
+    }
+    let value = value.handle();
+
+    let algorithm: Algorithm = value_to_dictionary(cx, value)?;
+    let name = AlgorithmName::from_name(&algorithm.name).ok_or(Error::NotSupported)?;
+
+    let normalized = match operation {
+        Operation::Encrypt | Operation::Decrypt => match name {
+            AlgorithmName::RsaOaep => {
+                let params: RootedTraceableBox<RsaOaepParams> = value_to_dictionary(cx, value)?;
+                NormalizedAlgorithm::RsaOaep {
+                    label: params.label.as_ref().map(buffer_source_to_vec),
+                }
+            },
+            AlgorithmName::AesCbc => {
+                let params: RootedTraceableBox<AesCbcParams> = value_to_dictionary(cx, value)?;
+                NormalizedAlgorithm::AesCbc {
+                    iv: buffer_source_to_vec(&params.iv),
+                }
+            },
+            AlgorithmName::AesGcm => {
+                let params: RootedTraceableBox<AesGcmParams> = value_to_dictionary(cx, value)?;
+                NormalizedAlgorithm::AesGcm {
+                    iv: buffer_source_to_vec(&params.iv),
+                    additional_data: params.additionalData.as_ref().map(buffer_source_to_vec),
+                    tag_length: params.tagLength,
+                }
+            },
+            _ => return Err(Error::NotSupported),
+        },
+        Operation::Sign | Operation::Verify => match name {
+            AlgorithmName::RsassaPkcs1V15 | AlgorithmName::Hmac => NormalizedAlgorithm::Basic(name),
+            AlgorithmName::RsaPss => {
+                let params: RsaPssParams = value_to_dictionary(cx, value)?;
+                NormalizedAlgorithm::RsaPss {
+                    salt_length: params.saltLength,
+                }
+            },
+            AlgorithmName::Ecdsa => {
+                let params: RootedTraceableBox<EcdsaParams> = value_to_dictionary(cx, value)?;
+                NormalizedAlgorithm::Ecdsa {
+                    hash: normalize_hash(cx, &params.hash)?,
+                }
+            },
+            _ => return Err(Error::NotSupported),
+        },
+        Operation::Digest => match name {
+            AlgorithmName::Sha1 |
+            AlgorithmName::Sha256 |
+            AlgorithmName::Sha384 |
+            AlgorithmName::Sha512 => NormalizedAlgorithm::Basic(name),
+            _ => return Err(Error::NotSupported),
+        },
+        Operation::GenerateKey => match name {
+            AlgorithmName::RsassaPkcs1V15 | AlgorithmName::RsaPss | AlgorithmName::RsaOaep => {
+                let params: RootedTraceableBox<RsaHashedKeyGenParams> =
+                    value_to_dictionary(cx, value)?;
+                NormalizedAlgorithm::RsaHashedKeyGen {
+                    name,
+                    modulus_length: params.parent.modulusLength,
+                    public_exponent: buffer_source_to_vec(&params.parent.publicExponent),
+                    hash: normalize_hash(cx, &params.hash)?,
+                }
+            },
+            AlgorithmName::Ecdsa | AlgorithmName::Ecdh => {
+                let params: EcKeyGenParams = value_to_dictionary(cx, value)?;
+                NormalizedAlgorithm::EcKey {
+                    name,
+                    curve: params.namedCurve.to_string(),
+                }
+            },
+            AlgorithmName::AesCbc | AlgorithmName::AesGcm => {
+                let params: AesKeyGenParams = value_to_dictionary(cx, value)?;
+                NormalizedAlgorithm::AesKey {
+                    name,
+                    length: params.length,
+                }
+            },
+            AlgorithmName::Hmac => {
+                let params: RootedTraceableBox<HmacKeyGenParams> = value_to_dictionary(cx, value)?;
+                NormalizedAlgorithm::Hmac {
+                    hash: normalize_hash(cx, &params.hash)?,
+                    length: params.length,
+                }
+            },
+            _ => return Err(Error::NotSupported),
+        },
+        Operation::DeriveBits => match name {
+            AlgorithmName::Ecdh => {
+                let params: EcdhKeyDeriveParams = value_to_dictionary(cx, value)?;
+                NormalizedAlgorithm::EcdhKeyDerive {
+                    public: params.public_.data().clone(),
+                }
+            },
+            AlgorithmName::Hkdf => {
+                let params: RootedTraceableBox<HkdfParams> = value_to_dictionary(cx, value)?;
+                NormalizedAlgorithm::Hkdf {
+                    hash: normalize_hash(cx, &params.hash)?,
+                    salt: buffer_source_to_vec(&params.salt),
+                    info: buffer_source_to_vec(&params.info),
+                }
+            },
+            AlgorithmName::Pbkdf2 => {
+                let params: RootedTraceableBox<Pbkdf2Params> = value_to_dictionary(cx, value)?;
+                NormalizedAlgorithm::Pbkdf2 {
+                    hash: normalize_hash(cx, &params.hash)?,
+                    salt: buffer_source_to_vec(&params.salt),
+                    iterations: params.iterations,
+                }
+            },
+            _ => return Err(Error::NotSupported),
+        },
+        Operation::ImportKey => match name {
+            AlgorithmName::RsassaPkcs1V15 | AlgorithmName::RsaPss | AlgorithmName::RsaOaep => {
+                let params: RootedTraceableBox<RsaHashedImportParams> =
+                    value_to_dictionary(cx, value)?;
+                NormalizedAlgorithm::RsaHashedImport {
+                    name,
+                    hash: normalize_hash(cx, &params.hash)?,
+                }
+            },
+            AlgorithmName::Ecdsa | AlgorithmName::Ecdh => {
+                let params: EcKeyImportParams = value_to_dictionary(cx, value)?;
+                NormalizedAlgorithm::EcKey {
+                    name,
+                    curve: params.namedCurve.to_string(),
+                }
+            },
+            AlgorithmName::Hmac => normalize_hmac_import_params(cx, value)?,
+            AlgorithmName::AesCbc |
+            AlgorithmName::AesGcm |
+            AlgorithmName::Hkdf |
+            AlgorithmName::Pbkdf2 => NormalizedAlgorithm::Basic(name),
+            _ => return Err(Error::NotSupported),
+        },
+        Operation::GetKeyLength => match name {
+            AlgorithmName::AesCbc | AlgorithmName::AesGcm => {
+                let params: AesDerivedKeyParams = value_to_dictionary(cx, value)?;
+                NormalizedAlgorithm::AesKey {
+                    name,
+                    length: params.length,
+                }
+            },
+            AlgorithmName::Hmac => normalize_hmac_import_params(cx, value)?,
+            AlgorithmName::Hkdf | AlgorithmName::Pbkdf2 => NormalizedAlgorithm::Basic(name),
+            _ => return Err(Error::NotSupported),
+        },
+    };
+    Ok(normalized)
+}
+
+fn normalize_hmac_import_params(
+    cx: JSContext,
+    value: HandleValue,
+) -> Fallible<NormalizedAlgorithm> {
+    let params: RootedTraceableBox<HmacImportParams> = value_to_dictionary(cx, value)?;
+    Ok(NormalizedAlgorithm::Hmac {
+        hash: normalize_hash(cx, &params.hash)?,
+        length: params.length,
+    })
+}
+
+/// Hash algorithm members are normalized for the "digest" operation.
+fn normalize_hash(cx: JSContext, hash: &ObjectOrString) -> Fallible<HashAlgorithm> {
+    match normalize_algorithm(cx, hash, Operation::Digest)? {
+        NormalizedAlgorithm::Basic(name) => {
+            HashAlgorithm::from_algorithm_name(name).ok_or(Error::NotSupported)
+        },
+        _ => Err(Error::NotSupported),
+    }
+}
+
+/// A JSON Web Key, as used by the "jwk" format, and serialized when wrapping keys.
+/// <https://tools.ietf.org/html/rfc7517>
+#[derive(Default, Deserialize, Serialize)]
+struct Jwk {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    kty: Option<String>,
+    #[serde(rename = "use", skip_serializing_if = "Option::is_none")]
+    use_: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    key_ops: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    alg: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ext: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    crv: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    x: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    y: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    d: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    n: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    e: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    p: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    q: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dp: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dq: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    qi: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    oth: Option<Vec<RsaOtherPrime>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    k: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct RsaOtherPrime {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    r: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    d: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    t: Option<String>,
+}
+
+fn to_string(value: &Option<DOMString>) -> Option<String> {
+    value.as_ref().map(|value| value.to_string())
+}
+
+fn to_dom_string(value: &Option<String>) -> Option<DOMString> {
+    value.as_ref().map(|value| DOMString::from(value.as_str()))
+}
+
+impl Jwk {
+    fn from_dictionary(jwk: &JsonWebKey) -> Jwk {
+        Jwk {
+            kty: to_string(&jwk.kty),
+            use_: to_string(&jwk.use_),
+            key_ops: jwk
+                .key_ops
+                .as_ref()
+                .map(|ops| ops.iter().map(|op| op.to_string()).collect()),
+            alg: to_string(&jwk.alg),
+            ext: jwk.ext,
+            crv: to_string(&jwk.crv),
+            x: to_string(&jwk.x),
+            y: to_string(&jwk.y),
+            d: to_string(&jwk.d),
+            n: to_string(&jwk.n),
+            e: to_string(&jwk.e),
+            p: to_string(&jwk.p),
+            q: to_string(&jwk.q),
+            dp: to_string(&jwk.dp),
+            dq: to_string(&jwk.dq),
+            qi: to_string(&jwk.qi),
+            oth: jwk.oth.as_ref().map(|primes| {
+                primes
+                    .iter()
+                    .map(|prime| RsaOtherPrime {
+                        r: to_string(&prime.r),
+                        d: to_string(&prime.d),
+                        t: to_string(&prime.t),
+                    })
+                    .collect()
+            }),
+            k: to_string(&jwk.k),
+        }
+    }
+
+    fn to_dictionary(&self) -> JsonWebKey {
+        JsonWebKey {
+            kty: to_dom_string(&self.kty),
+            use_: to_dom_string(&self.use_),
+            key_ops: self
+                .key_ops
+                .as_ref()
+                .map(|ops| ops.iter().map(|op| DOMString::from(op.as_str())).collect()),
+            alg: to_dom_string(&self.alg),
+            ext: self.ext,
+            crv: to_dom_string(&self.crv),
+            x: to_dom_string(&self.x),
+            y: to_dom_string(&self.y),
+            d: to_dom_string(&self.d),
+            n: to_dom_string(&self.n),
+            e: to_dom_string(&self.e),
+            p: to_dom_string(&self.p),
+            q: to_dom_string(&self.q),
+            dp: to_dom_string(&self.dp),
+            dq: to_dom_string(&self.dq),
+            qi: to_dom_string(&self.qi),
+            oth: self.oth.as_ref().map(|primes| {
+                primes
+                    .iter()
+                    .map(|prime| RsaOtherPrimesInfo {
+                        r: to_dom_string(&prime.r),
+                        d: to_dom_string(&prime.d),
+                        t: to_dom_string(&prime.t),
+                    })
+                    .collect()
+            }),
+            k: to_dom_string(&self.k),
+        }
+    }
+
+    /// The checks shared by the import of all the JWK key types: the "kty",
+    /// "use", "key_ops" and "ext" members.
+    fn check(&self, kty: &str, use_: &str, extractable: bool, usages: &[KeyUsage]) -> Fallible<()> {
+        if self.kty.as_ref().map(|kty| kty.as_str()) != Some(kty) {
+            return Err(Error::Data);
+        }
+        if let Some(ref key_use) = self.use_ {
+            if !usages.is_empty() && key_use != use_ {
+                return Err(Error::Data);
+            }
+        }
+        if let Some(ref key_ops) = self.key_ops {
+            if usages
+                .iter()
+                .any(|usage| !key_ops.iter().any(|op| op == usage.as_str()))
+            {
+                return Err(Error::Data);
+            }
+        }
+        if self.ext == Some(false) && extractable {
+            return Err(Error::Data);
+        }
+        Ok(())
+    }
+
+    /// The members shared by the export of all the JWK key types.
+    fn for_key(kty: &str, key: &KeyData) -> Jwk {
+        Jwk {
+            kty: Some(kty.to_owned()),
+            key_ops: Some(
+                key.usages
+                    .iter()
+                    .map(|usage| usage.as_str().to_owned())
+                    .collect(),
+            ),
+            ext: Some(key.extractable),
+            ..Jwk::default()
+        }
+    }
+}
+
+fn base64url_encode(data: &[u8]) -> String {
+    base64::encode_config(data, base64::URL_SAFE_NO_PAD)
+}
+
+/// Decode a required base64url encoded member of a JWK.
+fn base64url_decode(data: &Option<String>) -> Fallible<Vec<u8>> {
+    let data = data.as_ref().ok_or(Error::Data)?;
+    base64::decode_config(data, base64::URL_SAFE_NO_PAD).map_err(|_| Error::Data)
+}
+
+fn jwk_big_num(data: &Option<String>) -> Fallible<BigNum> {
+    BigNum::from_slice(&base64url_decode(data)?).map_err(|_| Error::Data)
+}
+
+/// The big-endian bytes of a number, left-padded with zeros to `size` bytes.
+fn padded_bytes(number: &BigNumRef, size: usize) -> Vec<u8> {
+    let bytes = number.to_vec();
+    let mut padded = vec![0; size.saturating_sub(bytes.len())];
+    padded.extend_from_slice(&bytes);
+    padded
+}
+
+/// The DER encoding of a private key as a PKCS #8 PrivateKeyInfo.
+fn private_key_to_pkcs8(key: &PKeyRef<Private>) -> Fallible<Vec<u8>> {
+    let pem = key
+        .private_key_to_pem_pkcs8()
+        .map_err(|_| Error::Operation)?;
+    let pem = String::from_utf8(pem).map_err(|_| Error::Operation)?;
+    let base64: String = pem
+        .lines()
+        .filter(|line| !line.starts_with("-----"))
+        .collect();
+    base64::decode(&base64).map_err(|_| Error::Operation)
+}
+
+/// The key data given to importKey.
+enum ImportData {
+    Bytes(Vec<u8>),
+    Jwk(Jwk),
+}
+
+/// The results of the operations, which are converted to JS values once back on the script thread.
+enum CryptoResult {
+    Bytes(Vec<u8>),
+    Boolean(bool),
+    Key(KeyData),
+    KeyPair { public: KeyData, private: KeyData },
+    Jwk(Jwk),
+}
+
+/// Throw a SyntaxError if a usage is not one of the usages allowed for a key.
+fn check_usages(usages: &[KeyUsage], allowed: &[KeyUsage]) -> Fallible<()> {
+    if usages.iter().any(|usage| !allowed.contains(usage)) {
+        return Err(Error::Syntax);
+    }
+    Ok(())
+}
+
+/// The checks the operations using a key make on its algorithm and usages.
+fn check_key(normalized: &NormalizedAlgorithm, key: &KeyData, usage: KeyUsage) -> Fallible<()> {
+    if normalized.name() != key.algorithm.name() || !key.has_usage(usage) {
+        return Err(Error::InvalidAccess);
+    }
+    Ok(())
+}
+
+fn secret(key: &KeyData) -> Fallible<&[u8]> {
+    match key.handle {
+        Handle::Secret(ref secret) => Ok(secret),
+        _ => Err(Error::InvalidAccess),
+    }
+}
+
+fn public_key(key: &KeyData) -> Fallible<&PKey<Public>> {
+    match key.handle {
+        Handle::Public(ref public) => Ok(public),
+        _ => Err(Error::InvalidAccess),
+    }
+}
+
+fn private_key(key: &KeyData) -> Fallible<&PKey<Private>> {
+    match key.handle {
+        Handle::Private(ref private) => Ok(private),
+        _ => Err(Error::InvalidAccess),
+    }
+}
+
+fn key_hash(key: &KeyData) -> Fallible<HashAlgorithm> {
+    key.algorithm.hash().ok_or(Error::Operation)
+}
+
+fn key_curve(key: &KeyData) -> Fallible<NamedCurve> {
+    key.algorithm.curve().ok_or(Error::Operation)
+}
+
+fn aes_cipher(key: &[u8], gcm: bool) -> Fallible<Cipher> {
+    match (key.len(), gcm) {
+        (16, false) => Ok(Cipher::aes_128_cbc()),
+        (24, false) => Ok(Cipher::aes_192_cbc()),
+        (32, false) => Ok(Cipher::aes_256_cbc()),
+        (16, true) => Ok(Cipher::aes_128_gcm()),
+        (24, true) => Ok(Cipher::aes_192_gcm()),
+        (32, true) => Ok(Cipher::aes_256_gcm()),
+        _ => Err(Error::Operation),
+    }
+}
+
+/// The length in bytes of an AES-GCM authentication tag.
+/// <https://w3c.github.io/webcrypto/#aes-gcm-operations>
+fn aes_gcm_tag_size(tag_length: Option<u8>) -> Fallible<usize> {
+    match tag_length.unwrap_or(128) {
+        length @ 32 |
+        length @ 64 |
+        length @ 96 |
+        length @ 104 |
+        length @ 112 |
+        length @ 120 |
+        length @ 128 => Ok(length as usize / 8),
+        _ => Err(Error::Operation),
+    }
+}
+
+fn rsa_oaep_encrypter<'a>(
+    key: &'a PKeyRef<Public>,
+    hash: HashAlgorithm,
+    label: &Option<Vec<u8>>,
+) -> Result<Encrypter<'a>, ErrorStack> {
+    let mut encrypter = Encrypter::new(key)?;
+    encrypter.set_rsa_padding(Padding::PKCS1_OAEP)?;
+    encrypter.set_rsa_oaep_md(hash.message_digest())?;
+    encrypter.set_rsa_mgf1_md(hash.message_digest())?;
+    if let Some(ref label) = *label {
+        encrypter.set_rsa_oaep_label(label)?;
+    }
+    Ok(encrypter)
+}
+
+fn rsa_oaep_decrypter<'a>(
+    key: &'a PKeyRef<Private>,
+    hash: HashAlgorithm,
+    label: &Option<Vec<u8>>,
+) -> Result<Decrypter<'a>, ErrorStack> {
+    let mut decrypter = Decrypter::new(key)?;
+    decrypter.set_rsa_padding(Padding::PKCS1_OAEP)?;
+    decrypter.set_rsa_oaep_md(hash.message_digest())?;
+    decrypter.set_rsa_mgf1_md(hash.message_digest())?;
+    if let Some(ref label) = *label {
+        decrypter.set_rsa_oaep_label(label)?;
+    }
+    Ok(decrypter)
+}
+
+/// The encrypt operation of the algorithms, which also wraps keys.
+fn encrypt(normalized: &NormalizedAlgorithm, key: &KeyData, data: &[u8]) -> Fallible<Vec<u8>> {
+    match *normalized {
+        // https://w3c.github.io/webcrypto/#rsa-oaep-operations
+        NormalizedAlgorithm::RsaOaep { ref label } => {
+            if key.key_type != KeyType::Public {
+                return Err(Error::InvalidAccess);
+            }
+            let encrypter = rsa_oaep_encrypter(public_key(key)?, key_hash(key)?, label)
+                .map_err(|_| Error::Operation)?;
+            let length = encrypter.encrypt_len(data).map_err(|_| Error::Operation)?;
+            let mut ciphertext = vec![0; length];
+            let length = encrypter
+                .encrypt(data, &mut ciphertext)
+                .map_err(|_| Error::Operation)?;
+            ciphertext.truncate(length);
+            Ok(ciphertext)
+        },
+        // https://w3c.github.io/webcrypto/#aes-cbc-operations
+        NormalizedAlgorithm::AesCbc { ref iv } => {
+            if iv.len() != 16 {
+                return Err(Error::Operation);
+            }
+            let secret = secret(key)?;
+            symm::encrypt(aes_cipher(secret, false)?, secret, Some(iv), data)
+                .map_err(|_| Error::Operation)
+        },
+        // https://w3c.github.io/webcrypto/#aes-gcm-operations
+        NormalizedAlgorithm::AesGcm {
+            ref iv,
+            ref additional_data,
+            tag_length,
+        } => {
+            let mut tag = vec![0; aes_gcm_tag_size(tag_length)?];
+            let secret = secret(key)?;
+            let additional_data = additional_data.as_ref().map_or(&[][..], |data| data);
+            let mut ciphertext = symm::encrypt_aead(
+                aes_cipher(secret, true)?,
+                secret,
+                Some(iv),
+                additional_data,
+                data,
+                &mut tag,
+            )
+            .map_err(|_| Error::Operation)?;
+            ciphertext.extend_from_slice(&tag);
+            Ok(ciphertext)
+        },
+        _ => Err(Error::NotSupported),
+    }
+}
+
+/// The decrypt operation of the algorithms, which also unwraps keys.
+fn decrypt(normalized: &NormalizedAlgorithm, key: &KeyData, data: &[u8]) -> Fallible<Vec<u8>> {
+    match *normalized {
+        // https://w3c.github.io/webcrypto/#rsa-oaep-operations
+        NormalizedAlgorithm::RsaOaep { ref label } => {
+            if key.key_type != KeyType::Private {
+                return Err(Error::InvalidAccess);
+            }
+            let decrypter = rsa_oaep_decrypter(private_key(key)?, key_hash(key)?, label)
+                .map_err(|_| Error::Operation)?;
+            let length = decrypter.decrypt_len(data).map_err(|_| Error::Operation)?;
+            let mut plaintext = vec![0; length];
+            let length = decrypter
+                .decrypt(data, &mut plaintext)
+                .map_err(|_| Error::Operation)?;
+            plaintext.truncate(length);
+            Ok(plaintext)
+        },
+        // https://w3c.github.io/webcrypto/#aes-cbc-operations
+        NormalizedAlgorithm::AesCbc { ref iv } => {
+            if iv.len() != 16 {
+                return Err(Error::Operation);
+            }
+            let secret = secret(key)?;
+            symm::decrypt(aes_cipher(secret, false)?, secret, Some(iv), data)
+                .map_err(|_| Error::Operation)
+        },
+        // https://w3c.github.io/webcrypto/#aes-gcm-operations
+        NormalizedAlgorithm::AesGcm {
+            ref iv,
+            ref additional_data,
+            tag_length,
+        } => {
+            let tag_size = aes_gcm_tag_size(tag_length)?;
+            if data.len() < tag_size {
+                return Err(Error::Operation);
+            }
+            let (ciphertext, tag) = data.split_at(data.len() - tag_size);
+            let secret = secret(key)?;
+            let additional_data = additional_data.as_ref().map_or(&[][..], |data| data);
+            symm::decrypt_aead(
+                aes_cipher(secret, true)?,
+                secret,
+                Some(iv),
+                additional_data,
+                ciphertext,
+                tag,
+            )
+            .map_err(|_| Error::Operation)
+        },
+        _ => Err(Error::NotSupported),
+    }
+}
+
+fn hmac(hash: HashAlgorithm, key: &[u8], data: &[u8]) -> Result<Vec<u8>, ErrorStack> {
+    let key = PKey::hmac(key)?;
+    let mut signer = Signer::new(hash.message_digest(), &key)?;
+    signer.update(data)?;
+    signer.sign_to_vec()
+}
+
+fn rsa_signer<'a>(
+    normalized: &NormalizedAlgorithm,
+    key: &'a PKeyRef<Private>,
+    hash: HashAlgorithm,
+) -> Result<Signer<'a>, ErrorStack> {
+    let mut signer = Signer::new(hash.message_digest(), key)?;
+    if let NormalizedAlgorithm::RsaPss { salt_length } = *normalized {
+        signer.set_rsa_padding(Padding::PKCS1_PSS)?;
+        signer.set_rsa_pss_saltlen(RsaPssSaltlen::custom(salt_length as i32))?;
+        signer.set_rsa_mgf1_md(hash.message_digest())?;
+    }
+    Ok(signer)
+}
+
+fn rsa_verifier<'a, T: HasPublic>(
+    normalized: &NormalizedAlgorithm,
+    key: &'a PKeyRef<T>,
+    hash: HashAlgorithm,
+) -> Result<Verifier<'a>, ErrorStack> {
+    let mut verifier = Verifier::new(hash.message_digest(), key)?;
+    if let NormalizedAlgorithm::RsaPss { salt_length } = *normalized {
+        verifier.set_rsa_padding(Padding::PKCS1_PSS)?;
+        verifier.set_rsa_pss_saltlen(RsaPssSaltlen::custom(salt_length as i32))?;
+        verifier.set_rsa_mgf1_md(hash.message_digest())?;
+    }
+    Ok(verifier)
+}
+
+/// The sign operation of the algorithms.
+fn sign(normalized: &NormalizedAlgorithm, key: &KeyData, data: &[u8]) -> Fallible<Vec<u8>> {
+    match *normalized {
+        // https://w3c.github.io/webcrypto/#rsassa-pkcs1-operations
+        // https://w3c.github.io/webcrypto/#rsa-pss-operations
+        NormalizedAlgorithm::Basic(AlgorithmName::RsassaPkcs1V15) |
+        NormalizedAlgorithm::RsaPss { .. } => {
+            if key.key_type != KeyType::Private {
+                return Err(Error::InvalidAccess);
+            }
+            let mut signer = rsa_signer(normalized, private_key(key)?, key_hash(key)?)
+                .map_err(|_| Error::Operation)?;
+            signer.update(data).map_err(|_| Error::Operation)?;
+            signer.sign_to_vec().map_err(|_| Error::Operation)
+        },
+        // https://w3c.github.io/webcrypto/#ecdsa-operations
+        NormalizedAlgorithm::Ecdsa { hash } => {
+            if key.key_type != KeyType::Private {
+                return Err(Error::InvalidAccess);
+            }
+            let size = key_curve(key)?.coordinate_size();
+            let mut signer = Signer::new(hash.message_digest(), private_key(key)?)
+                .map_err(|_| Error::Operation)?;
+            signer.update(data).map_err(|_| Error::Operation)?;
+            let der = signer.sign_to_vec().map_err(|_| Error::Operation)?;
+            // The signature is the concatenation of r and s, rather than their DER encoding.
+            let signature = EcdsaSig::from_der(&der).map_err(|_| Error::Operation)?;
+            let mut result = padded_bytes(signature.r(), size);
+            result.extend(padded_bytes(signature.s(), size));
+            Ok(result)
+        },
+        // https://w3c.github.io/webcrypto/#hmac-operations
+        NormalizedAlgorithm::Basic(AlgorithmName::Hmac) => {
+            hmac(key_hash(key)?, secret(key)?, data).map_err(|_| Error::Operation)
+        },
+        _ => Err(Error::NotSupported),
+    }
+}
+
+/// The verify operation of the algorithms.
+fn verify(
+    normalized: &NormalizedAlgorithm,
+    key: &KeyData,
+    signature: &[u8],
+    data: &[u8],
+) -> Fallible<bool> {
+    match *normalized {
+        // https://w3c.github.io/webcrypto/#rsassa-pkcs1-operations
+        // https://w3c.github.io/webcrypto/#rsa-pss-operations
+        NormalizedAlgorithm::Basic(AlgorithmName::RsassaPkcs1V15) |
+        NormalizedAlgorithm::RsaPss { .. } => {
+            if key.key_type != KeyType::Public {
+                return Err(Error::InvalidAccess);
+            }
+            let mut verifier = rsa_verifier(normalized, public_key(key)?, key_hash(key)?)
+                .map_err(|_| Error::Operation)?;
+            verifier.update(data).map_err(|_| Error::Operation)?;
+            Ok(verifier.verify(signature).unwrap_or(false))
+        },
+        // https://w3c.github.io/webcrypto/#ecdsa-operations
+        NormalizedAlgorithm::Ecdsa { hash } => {
+            if key.key_type != KeyType::Public {
+                return Err(Error::InvalidAccess);
+            }
+            let size = key_curve(key)?.coordinate_size();
+            if signature.len() != 2 * size {
+                return Ok(false);
+            }
+            let (r, s) = signature.split_at(size);
+            let der = BigNum::from_slice(r)
+                .and_then(|r| BigNum::from_slice(s).map(|s| (r, s)))
+                .and_then(|(r, s)| EcdsaSig::from_private_components(r, s))
+                .and_then(|signature| signature.to_der())
+                .map_err(|_| Error::Operation)?;
+            let mut verifier = Verifier::new(hash.message_digest(), public_key(key)?)
+                .map_err(|_| Error::Operation)?;
+            verifier.update(data).map_err(|_| Error::Operation)?;
+            Ok(verifier.verify(&der).unwrap_or(false))
+        },
+        // https://w3c.github.io/webcrypto/#hmac-operations
+        NormalizedAlgorithm::Basic(AlgorithmName::Hmac) => {
+            let mac = hmac(key_hash(key)?, secret(key)?, data).map_err(|_| Error::Operation)?;
+            Ok(mac.len() == signature.len() && memcmp::eq(&mac, signature))
+        },
+        _ => Err(Error::NotSupported),
+    }
+}
+
+/// The digest operation of the SHA algorithms.
+/// <https://w3c.github.io/webcrypto/#sha-operations>
+fn digest(normalized: &NormalizedAlgorithm, data: &[u8]) -> Fallible<Vec<u8>> {
+    let hash = match *normalized {
+        NormalizedAlgorithm::Basic(name) => {
+            HashAlgorithm::from_algorithm_name(name).ok_or(Error::NotSupported)?
+        },
+        _ => return Err(Error::NotSupported),
+    };
+    hash::hash(hash.message_digest(), data)
+        .map(|digest| digest.to_vec())
+        .map_err(|_| Error::Operation)
+}
+
+fn secret_key(
+    algorithm: KeyAlgorithm,
+    extractable: bool,
+    usages: Vec<KeyUsage>,
+    secret: Vec<u8>,
+) -> KeyData {
+    KeyData {
+        key_type: KeyType::Secret,
+        extractable,
+        algorithm,
+        usages,
+        handle: Handle::Secret(secret),
+    }
+}
+
+/// The generate key operation of the algorithms.
+fn generate_key(
+    normalized: &NormalizedAlgorithm,
+    extractable: bool,
+    usages: &[KeyUsage],
+) -> Fallible<CryptoResult> {
+    match *normalized {
+        // https://w3c.github.io/webcrypto/#rsassa-pkcs1-operations
+        // https://w3c.github.io/webcrypto/#rsa-pss-operations
+        // https://w3c.github.io/webcrypto/#rsa-oaep-operations
+        NormalizedAlgorithm::RsaHashedKeyGen {
+            name,
+            modulus_length,
+            ref public_exponent,
+            hash,
+        } => {
+            let (public_usages, private_usages) = rsa_usages(name);
+            check_usages(usages, &[public_usages, private_usages].concat())?;
+            let exponent = BigNum::from_slice(public_exponent).map_err(|_| Error::Operation)?;
+            let rsa =
+                Rsa::generate_with_e(modulus_length, &exponent).map_err(|_| Error::Operation)?;
+            let public = rsa_public_key(&rsa)?;
+            let private = PKey::from_rsa(rsa).map_err(|_| Error::Operation)?;
+            let algorithm = KeyAlgorithm::RsaHashed {
+                name,
+                modulus_length,
+                public_exponent: public_exponent.clone(),
+                hash,
+            };
+            Ok(CryptoResult::KeyPair {
+                public: KeyData {
+                    key_type: KeyType::Public,
+                    extractable: true,
+                    algorithm: algorithm.clone(),
+                    usages: usage_intersection(usages, public_usages),
+                    handle: Handle::Public(public),
+                },
+                private: KeyData {
+                    key_type: KeyType::Private,
+                    extractable,
+                    algorithm,
+                    usages: usage_intersection(usages, private_usages),
+                    handle: Handle::Private(private),
+                },
+            })
+        },
+        // https://w3c.github.io/webcrypto/#ecdsa-operations
+        // https://w3c.github.io/webcrypto/#ecdh-operations
+        NormalizedAlgorithm::EcKey { name, ref curve } => {
+            let (public_usages, private_usages) = ec_usages(name);
+            check_usages(usages, &[public_usages, private_usages].concat())?;
+            let curve = NamedCurve::from_name(curve).ok_or(Error::NotSupported)?;
+            let group = curve.group()?;
+            let ec = EcKey::generate(&group).map_err(|_| Error::Operation)?;
+            let public = EcKey::from_public_key(&group, ec.public_key())
+                .and_then(PKey::from_ec_key)
+                .map_err(|_| Error::Operation)?;
+            let private = PKey::from_ec_key(ec).map_err(|_| Error::Operation)?;
+            let algorithm = KeyAlgorithm::Ec { name, curve };
+            Ok(CryptoResult::KeyPair {
+                public: KeyData {
+                    key_type: KeyType::Public,
+                    extractable: true,
+                    algorithm: algorithm.clone(),
+                    usages: usage_intersection(usages, public_usages),
+                    handle: Handle::Public(public),
+                },
+                private: KeyData {
+                    key_type: KeyType::Private,
+                    extractable,
+                    algorithm,
+                    usages: usage_intersection(usages, private_usages),
+                    handle: Handle::Private(private),
+                },
+            })
+        },
+        // https://w3c.github.io/webcrypto/#aes-cbc-operations
+        // https://w3c.github.io/webcrypto/#aes-gcm-operations
+        NormalizedAlgorithm::AesKey { name, length } => {
+            check_usages(usages, AES_USAGES)?;
+            if length != 128 && length != 192 && length != 256 {
+                return Err(Error::Operation);
+            }
+            let mut secret = vec![0; length as usize / 8];
+            rand::rand_bytes(&mut secret).map_err(|_| Error::Operation)?;
+            Ok(CryptoResult::Key(secret_key(
+                KeyAlgorithm::Aes { name, length },
+                extractable,
+                usage_intersection(usages, usages),
+                secret,
+            )))
+        },
+        // https://w3c.github.io/webcrypto/#hmac-operations
+        NormalizedAlgorithm::Hmac { hash, length } => {
+            check_usages(usages, HMAC_USAGES)?;
+            let length = length.unwrap_or_else(|| hash.block_size());
+            if length == 0 {
+                return Err(Error::Operation);
+            }
+            let mut secret = vec![0; (length as usize + 7) / 8];
+            rand::rand_bytes(&mut secret).map_err(|_| Error::Operation)?;
+            Ok(CryptoResult::Key(secret_key(
+                KeyAlgorithm::Hmac { hash, length },
+                extractable,
+                usage_intersection(usages, usages),
+                secret,
+            )))
+        },
+        _ => Err(Error::NotSupported),
+    }
+}
+
+const AES_USAGES: &[KeyUsage] = &[
+    KeyUsage::Encrypt,
+    KeyUsage::Decrypt,
+    KeyUsage::WrapKey,
+    KeyUsage::UnwrapKey,
+];
+
+const HMAC_USAGES: &[KeyUsage] = &[KeyUsage::Sign, KeyUsage::Verify];
+
+const KDF_USAGES: &[KeyUsage] = &[KeyUsage::DeriveKey, KeyUsage::DeriveBits];
+
+/// The usages allowed for the public and private keys of an RSA algorithm.
+fn rsa_usages(name: AlgorithmName) -> (&'static [KeyUsage], &'static [KeyUsage]) {
+    match name {
+        AlgorithmName::RsaOaep => (
+            &[KeyUsage::Encrypt, KeyUsage::WrapKey],
+            &[KeyUsage::Decrypt, KeyUsage::UnwrapKey],
+        ),
+        _ => (&[KeyUsage::Verify], &[KeyUsage::Sign]),
+    }
+}
+
+/// The usages allowed for the public and private keys of an EC algorithm.
+fn ec_usages(name: AlgorithmName) -> (&'static [KeyUsage], &'static [KeyUsage]) {
+    match name {
+        AlgorithmName::Ecdh => (&[], KDF_USAGES),
+        _ => (&[KeyUsage::Verify], &[KeyUsage::Sign]),
+    }
+}
+
+fn rsa_public_key(rsa: &Rsa<Private>) -> Fallible<PKey<Public>> {
+    let n = rsa.n().to_owned().map_err(|_| Error::Operation)?;
+    let e = rsa.e().to_owned().map_err(|_| Error::Operation)?;
+    Rsa::from_public_components(n, e)
+        .and_then(PKey::from_rsa)
+        .map_err(|_| Error::Operation)
+}
+
+/// The import key operation of the algorithms.
+fn import_key(
+    format: KeyFormat,
+    data: ImportData,
+    normalized: &NormalizedAlgorithm,
+    extractable: bool,
+    usages: &[KeyUsage],
+) -> Fallible<KeyData> {
+    match *normalized {
+        NormalizedAlgorithm::RsaHashedImport { name, hash } => {
+            import_rsa_key(format, data, name, hash, extractable, usages)
+        },
+        NormalizedAlgorithm::EcKey { name, ref curve } => {
+            import_ec_key(format, data, name, curve, extractable, usages)
+        },
+        // https://w3c.github.io/webcrypto/#aes-cbc-operations
+        // https://w3c.github.io/webcrypto/#aes-gcm-operations
+        NormalizedAlgorithm::Basic(name @ AlgorithmName::AesCbc) |
+        NormalizedAlgorithm::Basic(name @ AlgorithmName::AesGcm) => {
+            check_usages(usages, AES_USAGES)?;
+            let secret = match (format, data) {
+                (KeyFormat::Raw, ImportData::Bytes(bytes)) => bytes,
+                (KeyFormat::Jwk, ImportData::Jwk(jwk)) => {
+                    jwk.check("oct", "enc", extractable, usages)?;
+                    let secret = base64url_decode(&jwk.k)?;
+                    if let Some(ref alg) = jwk.alg {
+                        let mode = if name == AlgorithmName::AesCbc {
+                            "CBC"
+                        } else {
+                            "GCM"
+                        };
+                        if *alg != format!("A{}{}", secret.len() * 8, mode) {
+                            return Err(Error::Data);
+                        }
+                    }
+                    secret
+                },
+                _ => return Err(Error::NotSupported),
+            };
+            if secret.len() != 16 && secret.len() != 24 && secret.len() != 32 {
+                return Err(Error::Data);
+            }
+            let length = secret.len() as u16 * 8;
+            Ok(secret_key(
+                KeyAlgorithm::Aes { name, length },
+                extractable,
+                usages.to_vec(),
+                secret,
+            ))
+        },
+        // https://w3c.github.io/webcrypto/#hmac-operations
+        NormalizedAlgorithm::Hmac { hash, length } => {
+            check_usages(usages, HMAC_USAGES)?;
+            let secret = match (format, data) {
+                (KeyFormat::Raw, ImportData::Bytes(bytes)) => bytes,
+                (KeyFormat::Jwk, ImportData::Jwk(jwk)) => {
+                    jwk.check("oct", "sig", extractable, usages)?;
+                    if let Some(ref alg) = jwk.alg {
+                        if *alg != format!("HS{}", hash.jwk_suffix()) {
+                            return Err(Error::Data);
+                        }
+                    }
+                    base64url_decode(&jwk.k)?
+                },
+                _ => return Err(Error::NotSupported),
+            };
+            let data_length = secret.len() as u32 * 8;
+            if data_length == 0 {
+                return Err(Error::Data);
+            }
+            let length = match length {
+                Some(length) if length > data_length || length <= data_length - 8 => {
+                    return Err(Error::Data);
+                },
+                Some(length) => length,
+                None => data_length,
+            };
+            Ok(secret_key(
+                KeyAlgorithm::Hmac { hash, length },
+                extractable,
+                usages.to_vec(),
+                secret,
+            ))
+        },
+        // https://w3c.github.io/webcrypto/#hkdf-operations
+        // https://w3c.github.io/webcrypto/#pbkdf2-operations
+        NormalizedAlgorithm::Basic(name @ AlgorithmName::Hkdf) |
+        NormalizedAlgorithm::Basic(name @ AlgorithmName::Pbkdf2) => {
+            let secret = match (format, data) {
+                (KeyFormat::Raw, ImportData::Bytes(bytes)) => bytes,
+                _ => return Err(Error::NotSupported),
+            };
+            check_usages(usages, KDF_USAGES)?;
+            if extractable {
+                return Err(Error::Syntax);
+            }
+            Ok(secret_key(
+                KeyAlgorithm::Basic(name),
+                false,
+                usages.to_vec(),
+                secret,
+            ))
+        },
+        _ => Err(Error::NotSupported),
+    }
+}
+
+/// <https://w3c.github.io/webcrypto/#rsassa-pkcs1-operations>
+/// <https://w3c.github.io/webcrypto/#rsa-pss-operations>
+/// <https://w3c.github.io/webcrypto/#rsa-oaep-operations>
+fn import_rsa_key(
+    format: KeyFormat,
+    data: ImportData,
+    name: AlgorithmName,
+    hash: HashAlgorithm,
+    extractable: bool,
+    usages: &[KeyUsage],
+) -> Fallible<KeyData> {
+    let (public_usages, private_usages) = rsa_usages(name);
+    let (key_type, handle) = match (format, data) {
+        (KeyFormat::Spki, ImportData::Bytes(bytes)) => {
+            check_usages(usages, public_usages)?;
+            let key = PKey::public_key_from_der(&bytes).map_err(|_| Error::Data)?;
+            if key.id() != Id::RSA {
+                return Err(Error::Data);
+            }
+            (KeyType::Public, Handle::Public(key))
+        },
+        (KeyFormat::Pkcs8, ImportData::Bytes(bytes)) => {
+            check_usages(usages, private_usages)?;
+            let key = PKey::private_key_from_pkcs8(&bytes).map_err(|_| Error::Data)?;
+            if key.id() != Id::RSA {
+                return Err(Error::Data);
+            }
+            (KeyType::Private, Handle::Private(key))
+        },
+        (KeyFormat::Jwk, ImportData::Jwk(jwk)) => {
+            let is_private = jwk.d.is_some();
+            check_usages(
+                usages,
+                if is_private {
+                    private_usages
+                } else {
+                    public_usages
+                },
+            )?;
+            let use_ = if name == AlgorithmName::RsaOaep {
+                "enc"
+            } else {
+                "sig"
+            };
+            jwk.check("RSA", use_, extractable, usages)?;
+            if let Some(ref alg) = jwk.alg {
+                let expected = match name {
+                    AlgorithmName::RsaOaep if hash == HashAlgorithm::Sha1 => "RSA-OAEP".to_owned(),
+                    AlgorithmName::RsaOaep => format!("RSA-OAEP-{}", hash.jwk_suffix()),
+                    AlgorithmName::RsaPss => format!("PS{}", hash.jwk_suffix()),
+                    _ => format!("RS{}", hash.jwk_suffix()),
+                };
+                if *alg != expected {
+                    return Err(Error::Data);
+                }
+            }
+            let n = jwk_big_num(&jwk.n)?;
+            let e = jwk_big_num(&jwk.e)?;
+            if is_private {
+                if jwk.oth.is_some() {
+                    return Err(Error::NotSupported);
+                }
+                let rsa = Rsa::from_private_components(
+                    n,
+                    e,
+                    jwk_big_num(&jwk.d)?,
+                    jwk_big_num(&jwk.p)?,
+                    jwk_big_num(&jwk.q)?,
+                    jwk_big_num(&jwk.dp)?,
+                    jwk_big_num(&jwk.dq)?,
+                    jwk_big_num(&jwk.qi)?,
+                )
+                .map_err(|_| Error::Data)?;
+                if !rsa.check_key().unwrap_or(false) {
+                    return Err(Error::Data);
+                }
+                let key = PKey::from_rsa(rsa).map_err(|_| Error::Data)?;
+                (KeyType::Private, Handle::Private(key))
+            } else {
+                let key = Rsa::from_public_components(n, e)
+                    .and_then(PKey::from_rsa)
+                    .map_err(|_| Error::Data)?;
+                (KeyType::Public, Handle::Public(key))
+            }
+        },
+        _ => return Err(Error::NotSupported),
+    };
+    let (modulus_length, public_exponent) = match handle {
+        Handle::Public(ref key) => key.rsa().map(|rsa| (rsa.n().num_bits(), rsa.e().to_vec())),
+        Handle::Private(ref key) => key.rsa().map(|rsa| (rsa.n().num_bits(), rsa.e().to_vec())),
+        Handle::Secret(_) => unreachable!(),
+    }
+    .map_err(|_| Error::Data)?;
+    Ok(KeyData {
+        key_type,
+        extractable,
+        algorithm: KeyAlgorithm::RsaHashed {
+            name,
+            modulus_length: modulus_length as u32,
+            public_exponent,
+            hash,
+        },
+        usages: usages.to_vec(),
+        handle,
+    })
+}
+
+/// <https://w3c.github.io/webcrypto/#ecdsa-operations>
+/// <https://w3c.github.io/webcrypto/#ecdh-operations>
+fn import_ec_key(
+    format: KeyFormat,
+    data: ImportData,
+    name: AlgorithmName,
+    curve: &str,
+    extractable: bool,
+    usages: &[KeyUsage],
+) -> Fallible<KeyData> {
+    let (public_usages, private_usages) = ec_usages(name);
+    let curve = NamedCurve::from_name(curve).ok_or(Error::Data)?;
+    let group = curve.group()?;
+    let (key_type, handle) = match (format, data) {
+        (KeyFormat::Spki, ImportData::Bytes(bytes)) => {
+            check_usages(usages, public_usages)?;
+            let key = PKey::public_key_from_der(&bytes).map_err(|_| Error::Data)?;
+            let ec = key.ec_key().map_err(|_| Error::Data)?;
+            if ec.group().curve_name() != Some(curve.nid()) {
+                return Err(Error::Data);
+            }
+            (KeyType::Public, Handle::Public(key))
+        },
+        (KeyFormat::Pkcs8, ImportData::Bytes(bytes)) => {
+            check_usages(usages, private_usages)?;
+            let key = PKey::private_key_from_pkcs8(&bytes).map_err(|_| Error::Data)?;
+            let ec = key.ec_key().map_err(|_| Error::Data)?;
+            if ec.group().curve_name() != Some(curve.nid()) || ec.check_key().is_err() {
+                return Err(Error::Data);
+            }
+            (KeyType::Private, Handle::Private(key))
+        },
+        (KeyFormat::Raw, ImportData::Bytes(bytes)) => {
+            check_usages(usages, public_usages)?;
+            let mut context = BigNumContext::new().map_err(|_| Error::Operation)?;
+            let point =
+                EcPoint::from_bytes(&group, &bytes, &mut context).map_err(|_| Error::Data)?;
+            let key = EcKey::from_public_key(&group, &point)
+                .and_then(PKey::from_ec_key)
+                .map_err(|_| Error::Data)?;
+            (KeyType::Public, Handle::Public(key))
+        },
+        (KeyFormat::Jwk, ImportData::Jwk(jwk)) => {
+            let is_private = jwk.d.is_some();
+            check_usages(
+                usages,
+                if is_private {
+                    private_usages
+                } else {
+                    public_usages
+                },
+            )?;
+            let use_ = if name == AlgorithmName::Ecdh {
+                "enc"
+            } else {
+                "sig"
+            };
+            jwk.check("EC", use_, extractable, usages)?;
+            if jwk.crv.as_ref().map(|crv| crv.as_str()) != Some(curve.as_str()) {
+                return Err(Error::Data);
+            }
+            if let Some(ref alg) = jwk.alg {
+                if name == AlgorithmName::Ecdsa &&
+                    NamedCurve::from_jwk_ecdsa_alg(alg) != Some(curve)
+                {
+                    return Err(Error::Data);
+                }
+            }
+            let size = curve.coordinate_size();
+            let x = base64url_decode(&jwk.x)?;
+            let y = base64url_decode(&jwk.y)?;
+            if x.len() != size || y.len() != size {
+                return Err(Error::Data);
+            }
+            let x = BigNum::from_slice(&x).map_err(|_| Error::Data)?;
+            let y = BigNum::from_slice(&y).map_err(|_| Error::Data)?;
+            let public = EcKey::from_public_key_affine_coordinates(&group, &x, &y)
+                .map_err(|_| Error::Data)?;
+            if is_private {
+                let d = base64url_decode(&jwk.d)?;
+                if d.len() != size {
+                    return Err(Error::Data);
+                }
+                let d = BigNum::from_slice(&d).map_err(|_| Error::Data)?;
+                let ec = EcKey::from_private_components(&group, &d, public.public_key())
+                    .map_err(|_| Error::Data)?;
+                if ec.check_key().is_err() {
+                    return Err(Error::Data);
+                }
+                let key = PKey::from_ec_key(ec).map_err(|_| Error::Data)?;
+                (KeyType::Private, Handle::Private(key))
+            } else {
+                let key = PKey::from_ec_key(public).map_err(|_| Error::Data)?;
+                (KeyType::Public, Handle::Public(key))
+            }
+        },
+        _ => return Err(Error::NotSupported),
+    };
+    Ok(KeyData {
+        key_type,
+        extractable,
+        algorithm: KeyAlgorithm::Ec { name, curve },
+        usages: usages.to_vec(),
+        handle,
+    })
+}
+
+/// The export key operation of the algorithms.
+fn export_key(format: KeyFormat, key: &KeyData) -> Fallible<CryptoResult> {
+    let name = key.algorithm.name();
+    if name == AlgorithmName::Hkdf || name == AlgorithmName::Pbkdf2 {
+        return Err(Error::NotSupported);
+    }
+    if !key.extractable {
+        return Err(Error::InvalidAccess);
+    }
+    match (format, &key.handle) {
+        (KeyFormat::Raw, &Handle::Secret(ref secret)) => Ok(CryptoResult::Bytes(secret.clone())),
+        (KeyFormat::Jwk, &Handle::Secret(ref secret)) => {
+            let mut jwk = Jwk::for_key("oct", key);
+            jwk.k = Some(base64url_encode(secret));
+            jwk.alg = Some(match key.algorithm {
+                KeyAlgorithm::Hmac { hash, .. } => format!("HS{}", hash.jwk_suffix()),
+                KeyAlgorithm::Aes { length, .. } if name == AlgorithmName::AesCbc => {
+                    format!("A{}CBC", length)
+                },
+                KeyAlgorithm::Aes { length, .. } => format!("A{}GCM", length),
+                _ => return Err(Error::NotSupported),
+            });
+            Ok(CryptoResult::Jwk(jwk))
+        },
+        (KeyFormat::Spki, &Handle::Public(ref public)) => public
+            .public_key_to_der()
+            .map(CryptoResult::Bytes)
+            .map_err(|_| Error::Operation),
+        (KeyFormat::Pkcs8, &Handle::Private(ref private)) => {
+            private_key_to_pkcs8(private).map(CryptoResult::Bytes)
+        },
+        (KeyFormat::Raw, &Handle::Public(ref public)) if public.id() == Id::EC => {
+            let ec = public.ec_key().map_err(|_| Error::Operation)?;
+            let mut context = BigNumContext::new().map_err(|_| Error::Operation)?;
+            ec.public_key()
+                .to_bytes(ec.group(), PointConversionForm::UNCOMPRESSED, &mut context)
+                .map(CryptoResult::Bytes)
+                .map_err(|_| Error::Operation)
+        },
+        (KeyFormat::Jwk, &Handle::Public(ref public)) => export_jwk(key, public, None),
+        (KeyFormat::Jwk, &Handle::Private(ref private)) => export_jwk(key, private, Some(private)),
+        (KeyFormat::Spki, _) | (KeyFormat::Pkcs8, _) => Err(Error::InvalidAccess),
+        (KeyFormat::Raw, &Handle::Private(_))
+            if name == AlgorithmName::Ecdsa || name == AlgorithmName::Ecdh =>
+        {
+            Err(Error::InvalidAccess)
+        },
+        _ => Err(Error::NotSupported),
+    }
+}
+
+/// Export an RSA or EC key as a JWK, with its private members if `private` is given.
+fn export_jwk<T: HasPublic>(
+    key: &KeyData,
+    public: &PKeyRef<T>,
+    private: Option<&PKeyRef<Private>>,
+) -> Fallible<CryptoResult> {
+    match key.algorithm {
+        KeyAlgorithm::RsaHashed { name, hash, .. } => {
+            let mut jwk = Jwk::for_key("RSA", key);
+            jwk.alg = Some(match name {
+                AlgorithmName::RsaOaep if hash == HashAlgorithm::Sha1 => "RSA-OAEP".to_owned(),
+                AlgorithmName::RsaOaep => format!("RSA-OAEP-{}", hash.jwk_suffix()),
+                AlgorithmName::RsaPss => format!("PS{}", hash.jwk_suffix()),
+                _ => format!("RS{}", hash.jwk_suffix()),
+            });
+            let rsa = public.rsa().map_err(|_| Error::Operation)?;
+            jwk.n = Some(base64url_encode(&rsa.n().to_vec()));
+            jwk.e = Some(base64url_encode(&rsa.e().to_vec()));
+            if let Some(private) = private {
+                let rsa = private.rsa().map_err(|_| Error::Operation)?;
+                let component = |number: Option<&BigNumRef>| {
+                    number
+                        .map(|number| base64url_encode(&number.to_vec()))
+                        .ok_or(Error::Operation)
+                };
+                jwk.d = Some(base64url_encode(&rsa.d().to_vec()));
+                jwk.p = Some(component(rsa.p())?);
+                jwk.q = Some(component(rsa.q())?);
+                jwk.dp = Some(component(rsa.dmp1())?);
+                jwk.dq = Some(component(rsa.dmq1())?);
+                jwk.qi = Some(component(rsa.iqmp())?);
+            }
+            Ok(CryptoResult::Jwk(jwk))
+        },
+        KeyAlgorithm::Ec { name, curve } => {
+            let mut jwk = Jwk::for_key("EC", key);
+            jwk.crv = Some(curve.as_str().to_owned());
+            if name == AlgorithmName::Ecdsa {
+                jwk.alg = Some(curve.jwk_ecdsa_alg().to_owned());
+            }
+            let size = curve.coordinate_size();
+            let ec = public.ec_key().map_err(|_| Error::Operation)?;
+            let mut context = BigNumContext::new().map_err(|_| Error::Operation)?;
+            let mut x = BigNum::new().map_err(|_| Error::Operation)?;
+            let mut y = BigNum::new().map_err(|_| Error::Operation)?;
+            ec.public_key()
+                .affine_coordinates_gfp(ec.group(), &mut x, &mut y, &mut context)
+                .map_err(|_| Error::Operation)?;
+            jwk.x = Some(base64url_encode(&padded_bytes(&x, size)));
+            jwk.y = Some(base64url_encode(&padded_bytes(&y, size)));
+            if let Some(private) = private {
+                let ec = private.ec_key().map_err(|_| Error::Operation)?;
+                jwk.d = Some(base64url_encode(&padded_bytes(ec.private_key(), size)));
+            }
+            Ok(CryptoResult::Jwk(jwk))
+        },
+        _ => Err(Error::NotSupported),
+    }
+}
+
+/// <https://tools.ietf.org/html/rfc5869>
+fn hkdf(
+    hash: HashAlgorithm,
+    key: &[u8],
+    salt: &[u8],
+    info: &[u8],
+    length: usize,
+) -> Result<Vec<u8>, ErrorStack> {
+    let hash_length = hash.message_digest().size();
+    // Extract, where an empty salt is a string of zeros as long as the hash.
+    let zeros;
+    let salt = if salt.is_empty() {
+        zeros = vec![0; hash_length];
+        &zeros
+    } else {
+        salt
+    };
+    let pseudorandom_key = hmac(hash, salt, key)?;
+    // Expand.
+    let mut output = Vec::with_capacity(length);
+    let mut block = vec![];
+    let blocks = (length + hash_length - 1) / hash_length;
+    for counter in 1..=blocks {
+        block.extend_from_slice(info);
+        block.push(counter as u8);
+        block = hmac(hash, &pseudorandom_key, &block)?;
+        output.extend_from_slice(&block);
+    }
+    output.truncate(length);
+    Ok(output)
+}
+
+/// The derive bits operation of the algorithms.
+fn derive_bits(
+    normalized: &NormalizedAlgorithm,
+    key: &KeyData,
+    length: Option<u32>,
+) -> Fallible<Vec<u8>> {
+    match *normalized {
+        // https://w3c.github.io/webcrypto/#ecdh-operations
+        NormalizedAlgorithm::EcdhKeyDerive { ref public } => {
+            if key.key_type != KeyType::Private || public.key_type != KeyType::Public {
+                return Err(Error::InvalidAccess);
+            }
+            if public.algorithm.name() != key.algorithm.name() ||
+                public.algorithm.curve() != key.algorithm.curve()
+            {
+                return Err(Error::InvalidAccess);
+            }
+            let mut deriver = Deriver::new(private_key(key)?).map_err(|_| Error::Operation)?;
+            deriver
+                .set_peer(public_key(public)?)
+                .map_err(|_| Error::Operation)?;
+            let mut secret = deriver.derive_to_vec().map_err(|_| Error::Operation)?;
+            let length = match length {
+                Some(length) => length as usize,
+                None => return Ok(secret),
+            };
+            if length > secret.len() * 8 {
+                return Err(Error::Operation);
+            }
+            // Keep the first length bits of the secret.
+            secret.truncate((length + 7) / 8);
+            if length % 8 != 0 {
+                if let Some(last) = secret.last_mut() {
+                    *last &= 0xff << (8 - length % 8);
+                }
+            }
+            Ok(secret)
+        },
+        // https://w3c.github.io/webcrypto/#hkdf-operations
+        NormalizedAlgorithm::Hkdf {
+            hash,
+            ref salt,
+            ref info,
+        } => {
+            let length = match length {
+                Some(length) if length % 8 == 0 => length as usize / 8,
+                _ => return Err(Error::Operation),
+            };
+            if length > 255 * hash.message_digest().size() {
+                return Err(Error::Operation);
+            }
+            hkdf(hash, secret(key)?, salt, info, length).map_err(|_| Error::Operation)
+        },
+        // https://w3c.github.io/webcrypto/#pbkdf2-operations
+        NormalizedAlgorithm::Pbkdf2 {
+            hash,
+            ref salt,
+            iterations,
+        } => {
+            let length = match length {
+                Some(length) if length % 8 == 0 => length as usize / 8,
+                _ => return Err(Error::Operation),
+            };
+            if iterations == 0 {
+                return Err(Error::Operation);
+            }
+            let mut bits = vec![0; length];
+            pkcs5::pbkdf2_hmac(
+                secret(key)?,
+                salt,
+                iterations as usize,
+                hash.message_digest(),
+                &mut bits,
+            )
+            .map_err(|_| Error::Operation)?;
+            Ok(bits)
+        },
+        _ => Err(Error::NotSupported),
+    }
+}
+
+/// The get key length operation of the algorithms, where `None` means the
+/// key derivation functions accept keys of any length.
+fn get_key_length(normalized: &NormalizedAlgorithm) -> Fallible<Option<u32>> {
+    match *normalized {
+        // https://w3c.github.io/webcrypto/#aes-cbc-operations
+        // https://w3c.github.io/webcrypto/#aes-gcm-operations
+        NormalizedAlgorithm::AesKey { length, .. } => {
+            if length != 128 && length != 192 && length != 256 {
+                return Err(Error::Operation);
+            }
+            Ok(Some(length as u32))
+        },
+        // https://w3c.github.io/webcrypto/#hmac-operations
+        NormalizedAlgorithm::Hmac { hash, length } => {
+            match length.unwrap_or_else(|| hash.block_size()) {
+                0 => Err(Error::Type(
+                    "The HMAC key length must not be zero.".to_owned(),
+                )),
+                length => Ok(Some(length)),
+            }
+        },
+        // https://w3c.github.io/webcrypto/#hkdf-operations
+        // https://w3c.github.io/webcrypto/#pbkdf2-operations
+        NormalizedAlgorithm::Basic(AlgorithmName::Hkdf) |
+        NormalizedAlgorithm::Basic(AlgorithmName::Pbkdf2) => Ok(None),
+        _ => Err(Error::NotSupported),
+    }
+}
+
+/// Check that a key given to importKey, deriveKey or unwrapKey can be used.
+/// <https://w3c.github.io/webcrypto/#SubtleCrypto-method-importKey>
+fn check_imported_key(key: &KeyData) -> Fallible<()> {
+    if key.key_type != KeyType::Public && key.usages.is_empty() {
+        return Err(Error::Syntax);
+    }
+    Ok(())
+}
+
+fn import_data(format: KeyFormat, bytes: Vec<u8>) -> Fallible<ImportData> {
+    if format != KeyFormat::Jwk {
+        return Ok(ImportData::Bytes(bytes));
+    }
+    serde_json::from_slice(&bytes)
+        .map(ImportData::Jwk)
+        .map_err(|_| Error::Data)
+}
+
+/// The number of threads the operations of all the `SubtleCrypto` objects of a process run on.
+const CRYPTO_THREADS: usize = 4;
+
+lazy_static! {
+    /// The threads operations run on in parallel, or `None` if they couldn't be spawned.
+    static ref CRYPTO_THREAD_POOL: Option<rayon::ThreadPool> = rayon::ThreadPoolBuilder::new()
+        .num_threads(CRYPTO_THREADS)
+        .thread_name(|index| format!("WebCrypto#{}", index))
+        .build()
+        .ok();
+}
+
+// https://w3c.github.io/webcrypto/#subtlecrypto-interface
+#[dom_struct]
+pub struct SubtleCrypto {
+    reflector_: Reflector,
+}
+
+impl SubtleCrypto {
+    fn new_inherited() -> SubtleCrypto {
+        SubtleCrypto {
+            reflector_: Reflector::new(),
+        }
+    }
+
+    pub fn new(global: &GlobalScope) -> DomRoot<SubtleCrypto> {
+        reflect_dom_object(Box::new(SubtleCrypto::new_inherited()), global)
+    }
+
+    /// Run the steps of an operation in parallel, then settle the promise with
+    /// their result in a task on the event loop of the global.
+    fn run_in_parallel<F>(&self, promise: &Rc<Promise>, steps: F)
+    where
+        F: FnOnce() -> Fallible<CryptoResult> + Send + 'static,
+    {
+        let global = self.global();
+        let task_source = global.dom_manipulation_task_source();
+        let canceller = global.task_canceller(TaskSourceName::DOMManipulation);
+        let pool = match *CRYPTO_THREAD_POOL {
+            Some(ref pool) => pool,
+            None => return promise.reject_error(Error::Operation),
+        };
+        let trusted_promise = TrustedPromise::new(promise.clone());
+        pool.spawn(move || {
+            let result = steps();
+            let _ = task_source.queue_with_canceller(
+                task!(settle_crypto_promise: move || {
+                    let promise = trusted_promise.root();
+                    settle_promise(&promise, result);
+                }),
+                &canceller,
+            );
+        });
+    }
+}
+
+#[allow(unsafe_code)]
+fn settle_promise(promise: &Promise, result: Fallible<CryptoResult>) {
+    let global = promise.global();
+    let cx = global.get_cx();
+    let _ac = enter_realm(promise);
+    match result {
+        Ok(CryptoResult::Bytes(bytes)) => {
+            rooted!(in(*cx) let mut array_buffer = ptr::null_mut::<JSObject>());
+            unsafe {
+                assert!(ArrayBuffer::create(
+                    *cx,
+                    CreateWith::Slice(&bytes),
+                    array_buffer.handle_mut()
+                )
+                .is_ok());
+            }
+            promise.resolve_native(&*array_buffer);
+        },
+        Ok(CryptoResult::Boolean(result)) => promise.resolve_native(&result),
+        Ok(CryptoResult::Key(key)) => promise.resolve_native(&CryptoKey::new(&global, key)),
+        Ok(CryptoResult::KeyPair { public, private }) => {
+            let pair = CryptoKeyPair {
+                publicKey: Some(CryptoKey::new(&global, public)),
+                privateKey: Some(CryptoKey::new(&global, private)),
+            };
+            promise.resolve_native(&pair);
+        },
+        Ok(CryptoResult::Jwk(jwk)) => promise.resolve_native(&jwk.to_dictionary()),
+        Err(error) => promise.reject_error(error),
+    }
+}
+
+impl SubtleCryptoMethods for SubtleCrypto {
+    // https://w3c.github.io/webcrypto/#dom-subtlecrypto-encrypt
+    fn Encrypt(
+        &self,
+        cx: JSContext,
+        algorithm: ObjectOrString,
+        key: &CryptoKey,
+        data: ArrayBufferViewOrArrayBuffer,
+    ) -> Rc<Promise> {
+        let promise = Promise::new(&self.global());
+        let data = buffer_source_to_vec(&data);
+        let normalized = match normalize_algorithm(cx, &algorithm, Operation::Encrypt) {
+            Ok(normalized) => normalized,
+            Err(error) => {
+                promise.reject_error(error);
+                return promise;
+            },
+        };
+        let key = key.data().clone();
+        self.run_in_parallel(&promise, move || {
+            check_key(&normalized, &key, KeyUsage::Encrypt)?;
+            encrypt(&normalized, &key, &data).map(CryptoResult::Bytes)
+        });
+        promise
+    }
+
+    // https://w3c.github.io/webcrypto/#dom-subtlecrypto-decrypt
+    fn Decrypt(
+        &self,
+        cx: JSContext,
+        algorithm: ObjectOrString,
+        key: &CryptoKey,
+        data: ArrayBufferViewOrArrayBuffer,
+    ) -> Rc<Promise> {
+        let promise = Promise::new(&self.global());
+        let data = buffer_source_to_vec(&data);
+        let normalized = match normalize_algorithm(cx, &algorithm, Operation::Decrypt) {
+            Ok(normalized) => normalized,
+            Err(error) => {
+                promise.reject_error(error);
+                return promise;
+            },
+        };
+        let key = key.data().clone();
+        self.run_in_parallel(&promise, move || {
+            check_key(&normalized, &key, KeyUsage::Decrypt)?;
+            decrypt(&normalized, &key, &data).map(CryptoResult::Bytes)
+        });
+        promise
+    }
+
+    // https://w3c.github.io/webcrypto/#dom-subtlecrypto-sign
+    fn Sign(
+        &self,
+        cx: JSContext,
+        algorithm: ObjectOrString,
+        key: &CryptoKey,
+        data: ArrayBufferViewOrArrayBuffer,
+    ) -> Rc<Promise> {
+        let promise = Promise::new(&self.global());
+        let data = buffer_source_to_vec(&data);
+        let normalized = match normalize_algorithm(cx, &algorithm, Operation::Sign) {
+            Ok(normalized) => normalized,
+            Err(error) => {
+                promise.reject_error(error);
+                return promise;
+            },
+        };
+        let key = key.data().clone();
+        self.run_in_parallel(&promise, move || {
+            check_key(&normalized, &key, KeyUsage::Sign)?;
+            sign(&normalized, &key, &data).map(CryptoResult::Bytes)
+        });
+        promise
+    }
+
+    // https://w3c.github.io/webcrypto/#dom-subtlecrypto-verify
+    fn Verify(
+        &self,
+        cx: JSContext,
+        algorithm: ObjectOrString,
+        key: &CryptoKey,
+        signature: ArrayBufferViewOrArrayBuffer,
+        data: ArrayBufferViewOrArrayBuffer,
+    ) -> Rc<Promise> {
+        let promise = Promise::new(&self.global());
+        let signature = buffer_source_to_vec(&signature);
+        let data = buffer_source_to_vec(&data);
+        let normalized = match normalize_algorithm(cx, &algorithm, Operation::Verify) {
+            Ok(normalized) => normalized,
+            Err(error) => {
+                promise.reject_error(error);
+                return promise;
+            },
+        };
+        let key = key.data().clone();
+        self.run_in_parallel(&promise, move || {
+            check_key(&normalized, &key, KeyUsage::Verify)?;
+            verify(&normalized, &key, &signature, &data).map(CryptoResult::Boolean)
+        });
+        promise
+    }
+
+    // https://w3c.github.io/webcrypto/#dom-subtlecrypto-digest
+    fn Digest(
+        &self,
+        cx: JSContext,
+        algorithm: ObjectOrString,
+        data: ArrayBufferViewOrArrayBuffer,
+    ) -> Rc<Promise> {
+        let promise = Promise::new(&self.global());
+        let data = buffer_source_to_vec(&data);
+        let normalized = match normalize_algorithm(cx, &algorithm, Operation::Digest) {
+            Ok(normalized) => normalized,
+            Err(error) => {
+                promise.reject_error(error);
+                return promise;
+            },
+        };
+        self.run_in_parallel(&promise, move || {
+            digest(&normalized, &data).map(CryptoResult::Bytes)
+        });
+        promise
+    }
+
+    // https://w3c.github.io/webcrypto/#dom-subtlecrypto-generatekey
+    fn GenerateKey(
+        &self,
+        cx: JSContext,
+        algorithm: ObjectOrString,
+        extractable: bool,
+        key_usages: Vec<KeyUsage>,
+    ) -> Rc<Promise> {
+        let promise = Promise::new(&self.global());
+        let normalized = match normalize_algorithm(cx, &algorithm, Operation::GenerateKey) {
+            Ok(normalized) => normalized,
+            Err(error) => {
+                promise.reject_error(error);
+                return promise;
+            },
+        };
+        self.run_in_parallel(&promise, move || {
+            let result = generate_key(&normalized, extractable, &key_usages)?;
+            match result {
+                CryptoResult::Key(ref key) => check_imported_key(key)?,
+                CryptoResult::KeyPair { ref private, .. } => check_imported_key(private)?,
+                _ => {},
+            }
+            Ok(result)
+        });
+        promise
+    }
+
+    // https://w3c.github.io/webcrypto/#dom-subtlecrypto-derivekey
+    fn DeriveKey(
+        &self,
+        cx: JSContext,
+        algorithm: ObjectOrString,
+        base_key: &CryptoKey,
+        derived_key_type: ObjectOrString,
+        extractable: bool,
+        key_usages: Vec<KeyUsage>,
+    ) -> Rc<Promise> {
+        let promise = Promise::new(&self.global());
+        let normalized =
+            normalize_algorithm(cx, &algorithm, Operation::DeriveBits).and_then(|normalized| {
+                let import = normalize_algorithm(cx, &derived_key_type, Operation::ImportKey)?;
+                let length = normalize_algorithm(cx, &derived_key_type, Operation::GetKeyLength)?;
+                Ok((normalized, import, length))
+            });
+        let (normalized, import, length) = match normalized {
+            Ok(normalized) => normalized,
+            Err(error) => {
+                promise.reject_error(error);
+                return promise;
+            },
+        };
+        let key = base_key.data().clone();
+        self.run_in_parallel(&promise, move || {
+            check_key(&normalized, &key, KeyUsage::DeriveKey)?;
+            let length = get_key_length(&length)?;
+            let secret = derive_bits(&normalized, &key, length)?;
+            let key = import_key(
+                KeyFormat::Raw,
+                ImportData::Bytes(secret),
+                &import,
+                extractable,
+                &key_usages,
+            )?;
+            check_imported_key(&key)?;
+            Ok(CryptoResult::Key(key))
+        });
+        promise
+    }
+
+    // https://w3c.github.io/webcrypto/#dom-subtlecrypto-derivebits
+    fn DeriveBits(
+        &self,
+        cx: JSContext,
+        algorithm: ObjectOrString,
+        base_key: &CryptoKey,
+        length: Option<u32>,
+    ) -> Rc<Promise> {
+        let promise = Promise::new(&self.global());
+        let normalized = match normalize_algorithm(cx, &algorithm, Operation::DeriveBits) {
+            Ok(normalized) => normalized,
+            Err(error) => {
+                promise.reject_error(error);
+                return promise;
+            },
+        };
+        let key = base_key.data().clone();
+        self.run_in_parallel(&promise, move || {
+            check_key(&normalized, &key, KeyUsage::DeriveBits)?;
+            derive_bits(&normalized, &key, length).map(CryptoResult::Bytes)
+        });
+        promise
+    }
+
+    // https://w3c.github.io/webcrypto/#dom-subtlecrypto-importkey
+    fn ImportKey(
+        &self,
+        cx: JSContext,
+        format: KeyFormat,
+        key_data: BufferSourceOrJsonWebKey,
+        algorithm: ObjectOrString,
+        extractable: bool,
+        key_usages: Vec<KeyUsage>,
+    ) -> Rc<Promise> {
+        let promise = Promise::new(&self.global());
+        let data = match (format, &key_data) {
+            (KeyFormat::Jwk, &BufferSourceOrJsonWebKey::JsonWebKey(ref jwk)) => {
+                Ok(ImportData::Jwk(Jwk::from_dictionary(jwk)))
+            },
+            (KeyFormat::Jwk, _) | (_, &BufferSourceOrJsonWebKey::JsonWebKey(_)) => Err(
+                Error::Type("The key data does not match the key format.".to_owned()),
+            ),
+            (_, &BufferSourceOrJsonWebKey::ArrayBufferView(ref view)) => {
+                Ok(ImportData::Bytes(view.to_vec()))
+            },
+            (_, &BufferSourceOrJsonWebKey::ArrayBuffer(ref buffer)) => {
+                Ok(ImportData::Bytes(buffer.to_vec()))
+            },
+        };
+        let normalized = normalize_algorithm(cx, &algorithm, Operation::ImportKey);
+        let (data, normalized) = match normalized.and_then(|normalized| Ok((data?, normalized))) {
+            Ok(result) => result,
+            Err(error) => {
+                promise.reject_error(error);
+                return promise;
+            },
+        };
+        self.run_in_parallel(&promise, move || {
+            let key = import_key(format, data, &normalized, extractable, &key_usages)?;
+            check_imported_key(&key)?;
+            Ok(CryptoResult::Key(key))
+        });
+        promise
+    }
+
+    // https://w3c.github.io/webcrypto/#dom-subtlecrypto-exportkey
+    fn ExportKey(&self, format: KeyFormat, key: &CryptoKey) -> Rc<Promise> {
+        let promise = Promise::new(&self.global());
+        let key = key.data().clone();
+        self.run_in_parallel(&promise, move || export_key(format, &key));
+        promise
+    }
+
+    // https://w3c.github.io/webcrypto/#dom-subtlecrypto-wrapkey
+    fn WrapKey(
+        &self,
+        cx: JSContext,
+        format: KeyFormat,
+        key: &CryptoKey,
+        wrapping_key: &CryptoKey,
+        wrap_algorithm: ObjectOrString,
+    ) -> Rc<Promise> {
+        let promise = Promise::new(&self.global());
+        // No supported algorithm has a "wrapKey" operation, so wrapping
+        // falls back to the "encrypt" operation.
+        let normalized = match normalize_algorithm(cx, &wrap_algorithm, Operation::Encrypt) {
+            Ok(normalized) => normalized,
+            Err(error) => {
+                promise.reject_error(error);
+                return promise;
+            },
+        };
+        let key = key.data().clone();
+        let wrapping_key = wrapping_key.data().clone();
+        self.run_in_parallel(&promise, move || {
+            check_key(&normalized, &wrapping_key, KeyUsage::WrapKey)?;
+            let bytes = match export_key(format, &key)? {
+                CryptoResult::Bytes(bytes) => bytes,
+                CryptoResult::Jwk(jwk) => serde_json::to_vec(&jwk).map_err(|_| Error::Operation)?,
+                _ => unreachable!(),
+            };
+            encrypt(&normalized, &wrapping_key, &bytes).map(CryptoResult::Bytes)
+        });
+        promise
+    }
+
+    // https://w3c.github.io/webcrypto/#dom-subtlecrypto-unwrapkey
+    fn UnwrapKey(
+        &self,
+        cx: JSContext,
+        format: KeyFormat,
+        wrapped_key: ArrayBufferViewOrArrayBuffer,
+        unwrapping_key: &CryptoKey,
+        unwrap_algorithm: ObjectOrString,
+        unwrapped_key_algorithm: ObjectOrString,
+        extractable: bool,
+        key_usages: Vec<KeyUsage>,
+    ) -> Rc<Promise> {
+        let promise = Promise::new(&self.global());
+        let wrapped_key = buffer_source_to_vec(&wrapped_key);
+        // No supported algorithm has an "unwrapKey" operation, so unwrapping
+        // falls back to the "decrypt" operation.
+        let normalized =
+            normalize_algorithm(cx, &unwrap_algorithm, Operation::Decrypt).and_then(|normalized| {
+                let import =
+                    normalize_algorithm(cx, &unwrapped_key_algorithm, Operation::ImportKey)?;
+                Ok((normalized, import))
+            });
+        let (normalized, import) = match normalized {
+            Ok(normalized) => normalized,
+            Err(error) => {
+                promise.reject_error(error);
+                return promise;
+            },
+        };
+        let unwrapping_key = unwrapping_key.data().clone();
+        self.run_in_parallel(&promise, move || {
+            check_key(&normalized, &unwrapping_key, KeyUsage::UnwrapKey)?;
+            let bytes = decrypt(&normalized, &unwrapping_key, &wrapped_key)?;
+            let data = import_data(format, bytes)?;
+            let key = import_key(format, data, &import, extractable, &key_usages)?;
+            check_imported_key(&key)?;
+            Ok(CryptoResult::Key(key))
+        });
+        promise
+    }
+}
@@ -0,0 +1,54 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::abortcontroller::AbortController;
+use crate::dom::bindings::codegen::Bindings::AbortControllerBinding::AbortControllerMethods;
+use crate::dom::bindings::codegen::Bindings::SchedulerBinding::TaskPriority;
+use crate::dom::bindings::codegen::Bindings::TaskControllerBinding::{
+    TaskControllerInit, TaskControllerMethods,
+};
+use crate::dom::bindings::error::ErrorResult;
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::reflector::reflect_dom_object;
+use crate::dom::bindings::root::DomRoot;
+use crate::dom::tasksignal::TaskSignal;
+use crate::dom::window::Window;
+use dom_struct::dom_struct;
+
+// https://wicg.github.io/scheduling-apis/#sec-task-controller
+#[dom_struct]
+pub struct TaskController {
+    abortcontroller: AbortController,
+}
+
+impl TaskController {
+    fn new_inherited(signal: &TaskSignal) -> TaskController {
+        TaskController {
+            abortcontroller: AbortController::new_inherited(signal.upcast()),
+        }
+    }
+
+    pub fn new(window: &Window, priority: TaskPriority) -> DomRoot<TaskController> {
+        let signal = TaskSignal::new(window, priority);
+        reflect_dom_object(Box::new(TaskController::new_inherited(&signal)), window)
+    }
+
+    // https://wicg.github.io/scheduling-apis/#dom-taskcontroller-taskcontroller
+    #[allow(non_snake_case)]
+    pub fn Constructor(window: &Window, init: &TaskControllerInit) -> DomRoot<TaskController> {
+        TaskController::new(window, init.priority)
+    }
+
+    fn signal(&self) -> DomRoot<TaskSignal> {
+        DomRoot::downcast(self.abortcontroller.Signal())
+            .expect("The signal of a TaskController is a TaskSignal")
+    }
+}
+
+impl TaskControllerMethods for TaskController {
+    // https://wicg.github.io/scheduling-apis/#dom-taskcontroller-setpriority
+    fn SetPriority(&self, priority: TaskPriority) -> ErrorResult {
+        self.signal().signal_priority_change(priority)
+    }
+}
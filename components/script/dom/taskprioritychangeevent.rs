@@ -0,0 +1,75 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::codegen::Bindings::EventBinding::EventMethods;
+use crate::dom::bindings::codegen::Bindings::SchedulerBinding::TaskPriority;
+use crate::dom::bindings::codegen::Bindings::TaskPriorityChangeEventBinding;
+use crate::dom::bindings::codegen::Bindings::TaskPriorityChangeEventBinding::TaskPriorityChangeEventMethods;
+use crate::dom::bindings::error::Fallible;
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::reflector::reflect_dom_object;
+use crate::dom::bindings::root::DomRoot;
+use crate::dom::bindings::str::DOMString;
+use crate::dom::event::Event;
+use crate::dom::window::Window;
+use dom_struct::dom_struct;
+use servo_atoms::Atom;
+
+// https://wicg.github.io/scheduling-apis/#sec-task-priority-change-event
+#[dom_struct]
+pub struct TaskPriorityChangeEvent {
+    event: Event,
+    previous_priority: TaskPriority,
+}
+
+impl TaskPriorityChangeEvent {
+    fn new_inherited(previous_priority: TaskPriority) -> TaskPriorityChangeEvent {
+        TaskPriorityChangeEvent {
+            event: Event::new_inherited(),
+            previous_priority,
+        }
+    }
+
+    pub fn new(
+        window: &Window,
+        type_: Atom,
+        bubbles: bool,
+        cancelable: bool,
+        previous_priority: TaskPriority,
+    ) -> DomRoot<TaskPriorityChangeEvent> {
+        let ev = reflect_dom_object(
+            Box::new(TaskPriorityChangeEvent::new_inherited(previous_priority)),
+            window,
+        );
+        ev.upcast::<Event>().init_event(type_, bubbles, cancelable);
+        ev
+    }
+
+    #[allow(non_snake_case)]
+    pub fn Constructor(
+        window: &Window,
+        type_: DOMString,
+        init: &TaskPriorityChangeEventBinding::TaskPriorityChangeEventInit,
+    ) -> Fallible<DomRoot<TaskPriorityChangeEvent>> {
+        Ok(TaskPriorityChangeEvent::new(
+            window,
+            Atom::from(type_),
+            init.parent.bubbles,
+            init.parent.cancelable,
+            init.previousPriority,
+        ))
+    }
+}
+
+impl TaskPriorityChangeEventMethods for TaskPriorityChangeEvent {
+    // https://wicg.github.io/scheduling-apis/#dom-taskprioritychangeevent-previouspriority
+    fn PreviousPriority(&self) -> TaskPriority {
+        self.previous_priority
+    }
+
+    // https://dom.spec.whatwg.org/#dom-event-istrusted
+    fn IsTrusted(&self) -> bool {
+        self.event.IsTrusted()
+    }
+}
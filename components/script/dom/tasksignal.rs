@@ -0,0 +1,95 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::abortsignal::AbortSignal;
+use crate::dom::bindings::codegen::Bindings::SchedulerBinding::TaskPriority;
+use crate::dom::bindings::codegen::Bindings::TaskSignalBinding::TaskSignalMethods;
+use crate::dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
+use crate::dom::bindings::error::{Error, ErrorResult};
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject};
+use crate::dom::bindings::root::DomRoot;
+use crate::dom::event::Event;
+use crate::dom::eventtarget::EventTarget;
+use crate::dom::taskprioritychangeevent::TaskPriorityChangeEvent;
+use crate::dom::window::Window;
+use dom_struct::dom_struct;
+use std::cell::Cell;
+
+// https://wicg.github.io/scheduling-apis/#sec-task-signal
+#[dom_struct]
+pub struct TaskSignal {
+    abortsignal: AbortSignal,
+    /// <https://wicg.github.io/scheduling-apis/#tasksignal-priority>
+    priority: Cell<TaskPriority>,
+    /// <https://wicg.github.io/scheduling-apis/#tasksignal-priority-changing>
+    priority_changing: Cell<bool>,
+}
+
+impl TaskSignal {
+    fn new_inherited(priority: TaskPriority) -> TaskSignal {
+        TaskSignal {
+            abortsignal: AbortSignal::new_inherited(),
+            priority: Cell::new(priority),
+            priority_changing: Cell::new(false),
+        }
+    }
+
+    pub fn new(window: &Window, priority: TaskPriority) -> DomRoot<TaskSignal> {
+        reflect_dom_object(Box::new(TaskSignal::new_inherited(priority)), window)
+    }
+
+    pub fn priority(&self) -> TaskPriority {
+        self.priority.get()
+    }
+
+    /// <https://wicg.github.io/scheduling-apis/#tasksignal-signal-priority-change>
+    pub fn signal_priority_change(&self, priority: TaskPriority) -> ErrorResult {
+        // Step 1.
+        if self.priority_changing.get() {
+            return Err(Error::NotAllowed);
+        }
+
+        // Step 2.
+        if self.priority.get() == priority {
+            return Ok(());
+        }
+
+        // Steps 3-5.
+        self.priority_changing.set(true);
+        let previous_priority = self.priority.replace(priority);
+
+        // Step 6. The scheduler of the window is the only one that can have tasks
+        // following this signal.
+        let global = self.global();
+        let window = global
+            .downcast::<Window>()
+            .expect("TaskSignal is only exposed to Window");
+        window.Scheduler().priority_changed(self);
+
+        // Step 7.
+        let event = TaskPriorityChangeEvent::new(
+            window,
+            atom!("prioritychange"),
+            false,
+            false,
+            previous_priority,
+        );
+        event.upcast::<Event>().fire(self.upcast::<EventTarget>());
+
+        // Step 8.
+        self.priority_changing.set(false);
+        Ok(())
+    }
+}
+
+impl TaskSignalMethods for TaskSignal {
+    // https://wicg.github.io/scheduling-apis/#dom-tasksignal-priority
+    fn Priority(&self) -> TaskPriority {
+        self.priority.get()
+    }
+
+    // https://wicg.github.io/scheduling-apis/#dom-tasksignal-onprioritychange
+    event_handler!(prioritychange, GetOnprioritychange, SetOnprioritychange);
+}
@@ -83,30 +83,10 @@ impl TextDecoder {
             options.ignoreBOM,
         ))
     }
-}
-
-impl TextDecoderMethods for TextDecoder {
-    // https://encoding.spec.whatwg.org/#dom-textdecoder-encoding
-    fn Encoding(&self) -> DOMString {
-        DOMString::from(self.encoding.name().to_ascii_lowercase())
-    }
 
-    // https://encoding.spec.whatwg.org/#dom-textdecoder-fatal
-    fn Fatal(&self) -> bool {
-        self.fatal
-    }
-
-    // https://encoding.spec.whatwg.org/#dom-textdecoder-ignorebom
-    fn IgnoreBOM(&self) -> bool {
-        self.ignoreBOM
-    }
-
-    // https://encoding.spec.whatwg.org/#dom-textdecoder-decode
-    fn Decode(
-        &self,
-        input: Option<ArrayBufferViewOrArrayBuffer>,
-        options: &TextDecodeOptions,
-    ) -> Fallible<USVString> {
+    /// Steps 1-5 of <https://encoding.spec.whatwg.org/#dom-textdecoder-decode>, also used by
+    /// <https://encoding.spec.whatwg.org/#decode-and-enqueue-a-chunk>.
+    pub fn decode_bytes(&self, input: &[u8], stream: bool) -> Fallible<String> {
         // Step 1.
         if !self.do_not_flush.get() {
             if self.ignoreBOM {
@@ -119,18 +99,10 @@ impl TextDecoderMethods for TextDecoder {
         }
 
         // Step 2.
-        self.do_not_flush.set(options.stream);
+        self.do_not_flush.set(stream);
 
         // Step 3.
-        match input {
-            Some(ArrayBufferViewOrArrayBuffer::ArrayBufferView(ref a)) => {
-                self.in_stream.borrow_mut().extend_from_slice(&a.to_vec());
-            },
-            Some(ArrayBufferViewOrArrayBuffer::ArrayBuffer(ref a)) => {
-                self.in_stream.borrow_mut().extend_from_slice(&a.to_vec());
-            },
-            None => {},
-        };
+        self.in_stream.borrow_mut().extend_from_slice(input);
 
         let mut decoder = self.decoder.borrow_mut();
         let (remaining, s) = {
@@ -147,7 +119,7 @@ impl TextDecoderMethods for TextDecoder {
                 match decoder.decode_to_string_without_replacement(
                     &in_stream,
                     &mut out_stream,
-                    !options.stream,
+                    !stream,
                 ) {
                     (DecoderResult::InputEmpty, read) => (in_stream.split_off(read), out_stream),
                     // Step 5.3.3.
@@ -159,12 +131,43 @@ impl TextDecoderMethods for TextDecoder {
                     String::with_capacity(decoder.max_utf8_buffer_length(in_stream.len()).unwrap());
                 // Step 5: Implemented by encoding_rs::Decoder.
                 let (_result, read, _replaced) =
-                    decoder.decode_to_string(&in_stream, &mut out_stream, !options.stream);
+                    decoder.decode_to_string(&in_stream, &mut out_stream, !stream);
                 (in_stream.split_off(read), out_stream)
             };
             (remaining, s)
         };
         self.in_stream.replace(remaining);
-        Ok(USVString(s))
+        Ok(s)
+    }
+}
+
+impl TextDecoderMethods for TextDecoder {
+    // https://encoding.spec.whatwg.org/#dom-textdecoder-encoding
+    fn Encoding(&self) -> DOMString {
+        DOMString::from(self.encoding.name().to_ascii_lowercase())
+    }
+
+    // https://encoding.spec.whatwg.org/#dom-textdecoder-fatal
+    fn Fatal(&self) -> bool {
+        self.fatal
+    }
+
+    // https://encoding.spec.whatwg.org/#dom-textdecoder-ignorebom
+    fn IgnoreBOM(&self) -> bool {
+        self.ignoreBOM
+    }
+
+    // https://encoding.spec.whatwg.org/#dom-textdecoder-decode
+    fn Decode(
+        &self,
+        input: Option<ArrayBufferViewOrArrayBuffer>,
+        options: &TextDecodeOptions,
+    ) -> Fallible<USVString> {
+        let input = match input {
+            Some(ArrayBufferViewOrArrayBuffer::ArrayBufferView(ref a)) => a.to_vec(),
+            Some(ArrayBufferViewOrArrayBuffer::ArrayBuffer(ref a)) => a.to_vec(),
+            None => vec![],
+        };
+        self.decode_bytes(&input, options.stream).map(USVString)
     }
 }
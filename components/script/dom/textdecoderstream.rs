@@ -0,0 +1,124 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::codegen::Bindings::TextDecoderBinding::{
+    TextDecoderMethods, TextDecoderOptions,
+};
+use crate::dom::bindings::codegen::Bindings::TextDecoderStreamBinding::TextDecoderStreamMethods;
+use crate::dom::bindings::error::Fallible;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot, MutNullableDom};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::textdecoder::TextDecoder;
+use crate::dom::transformstream::TransformStream;
+use crate::dom::transformstreamdefaultcontroller::{buffer_source_to_vec, TransformerType};
+use crate::dom::writablestream::WritableStream;
+use crate::script_runtime::JSContext;
+use dom_struct::dom_struct;
+use js::conversions::ToJSValConvertible;
+use js::jsapi::JSObject;
+use js::jsval::UndefinedValue;
+use js::rust::HandleValue;
+use std::ptr::NonNull;
+
+// https://encoding.spec.whatwg.org/#interface-textdecoderstream
+#[dom_struct]
+pub struct TextDecoderStream {
+    reflector_: Reflector,
+    /// The decoder holding the encoding, the error mode and the streaming state.
+    decoder: Dom<TextDecoder>,
+    /// <https://streams.spec.whatwg.org/#generictransformstream-transform>
+    transform: MutNullableDom<TransformStream>,
+}
+
+impl TextDecoderStream {
+    fn new_inherited(decoder: &TextDecoder) -> TextDecoderStream {
+        TextDecoderStream {
+            reflector_: Reflector::new(),
+            decoder: Dom::from_ref(decoder),
+            transform: Default::default(),
+        }
+    }
+
+    // https://encoding.spec.whatwg.org/#dom-textdecoderstream
+    #[allow(non_snake_case)]
+    pub fn Constructor(
+        global: &GlobalScope,
+        label: DOMString,
+        options: &TextDecoderOptions,
+    ) -> Fallible<DomRoot<TextDecoderStream>> {
+        // Steps 1-4.
+        let decoder = TextDecoder::Constructor(global, label, options)?;
+        let stream =
+            reflect_dom_object(Box::new(TextDecoderStream::new_inherited(&decoder)), global);
+        // Steps 5-9.
+        let transform = TransformStream::new_with_transformer(
+            global,
+            TransformerType::TextDecoder(Dom::from_ref(&*stream)),
+        )?;
+        stream.transform.set(Some(&transform));
+        Ok(stream)
+    }
+
+    fn transform(&self) -> DomRoot<TransformStream> {
+        self.transform.get().expect("The stream was constructed")
+    }
+
+    /// Enqueues `output` to the readable side, unless it is empty.
+    #[allow(unsafe_code)]
+    fn enqueue(&self, cx: JSContext, output: String) -> Fallible<()> {
+        if output.is_empty() {
+            return Ok(());
+        }
+        rooted!(in(*cx) let mut chunk = UndefinedValue());
+        unsafe { output.to_jsval(*cx, chunk.handle_mut()) };
+        self.transform().enqueue(chunk.handle())
+    }
+
+    /// <https://encoding.spec.whatwg.org/#decode-and-enqueue-a-chunk>
+    pub fn decode_and_enqueue_chunk(&self, cx: JSContext, chunk: HandleValue) -> Fallible<()> {
+        // Step 1.
+        let bytes = buffer_source_to_vec(cx, chunk)?;
+        // Steps 2-4.
+        let output = self.decoder.decode_bytes(&bytes, true)?;
+        // Step 5.
+        self.enqueue(cx, output)
+    }
+
+    /// <https://encoding.spec.whatwg.org/#flush-and-enqueue>
+    pub fn flush_and_enqueue(&self) -> Fallible<()> {
+        // Steps 1-3.
+        let output = self.decoder.decode_bytes(&[], false)?;
+        // Step 4.
+        self.enqueue(self.global().get_cx(), output)
+    }
+}
+
+impl TextDecoderStreamMethods for TextDecoderStream {
+    // https://encoding.spec.whatwg.org/#dom-textdecoder-encoding
+    fn Encoding(&self) -> DOMString {
+        self.decoder.Encoding()
+    }
+
+    // https://encoding.spec.whatwg.org/#dom-textdecoder-fatal
+    fn Fatal(&self) -> bool {
+        self.decoder.Fatal()
+    }
+
+    // https://encoding.spec.whatwg.org/#dom-textdecoder-ignorebom
+    fn IgnoreBOM(&self) -> bool {
+        self.decoder.IgnoreBOM()
+    }
+
+    // https://streams.spec.whatwg.org/#dom-generictransformstream-readable
+    fn Readable(&self, _cx: JSContext) -> NonNull<JSObject> {
+        self.transform().readable().get_js_stream()
+    }
+
+    // https://streams.spec.whatwg.org/#dom-generictransformstream-writable
+    fn Writable(&self) -> DomRoot<WritableStream> {
+        self.transform().writable()
+    }
+}
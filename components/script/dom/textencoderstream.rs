@@ -0,0 +1,146 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::codegen::Bindings::TextEncoderStreamBinding::TextEncoderStreamMethods;
+use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::reflector::{reflect_dom_object, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot, MutNullableDom};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::transformstream::TransformStream;
+use crate::dom::transformstreamdefaultcontroller::TransformerType;
+use crate::dom::writablestream::WritableStream;
+use crate::script_runtime::JSContext;
+use dom_struct::dom_struct;
+use js::jsapi::JS_StringHasLatin1Chars;
+use js::jsapi::{JSObject, JS_GetLatin1StringCharsAndLength, JS_GetTwoByteStringCharsAndLength};
+use js::rust::{HandleValue, ToString};
+use std::cell::Cell;
+use std::ptr::{self, NonNull};
+use std::slice;
+
+// https://encoding.spec.whatwg.org/#interface-textencoderstream
+#[dom_struct]
+pub struct TextEncoderStream {
+    reflector_: Reflector,
+    /// <https://encoding.spec.whatwg.org/#textencoderstream-pending-high-surrogate>
+    pending_high_surrogate: Cell<Option<u16>>,
+    /// <https://streams.spec.whatwg.org/#generictransformstream-transform>
+    transform: MutNullableDom<TransformStream>,
+}
+
+impl TextEncoderStream {
+    fn new_inherited() -> TextEncoderStream {
+        TextEncoderStream {
+            reflector_: Reflector::new(),
+            pending_high_surrogate: Cell::new(None),
+            transform: Default::default(),
+        }
+    }
+
+    // https://encoding.spec.whatwg.org/#dom-textencoderstream
+    #[allow(non_snake_case)]
+    pub fn Constructor(global: &GlobalScope) -> Fallible<DomRoot<TextEncoderStream>> {
+        let stream = reflect_dom_object(Box::new(TextEncoderStream::new_inherited()), global);
+        let transform = TransformStream::new_with_transformer(
+            global,
+            TransformerType::TextEncoder(Dom::from_ref(&*stream)),
+        )?;
+        stream.transform.set(Some(&transform));
+        Ok(stream)
+    }
+
+    fn transform(&self) -> DomRoot<TransformStream> {
+        self.transform.get().expect("The stream was constructed")
+    }
+
+    /// <https://encoding.spec.whatwg.org/#encode-and-enqueue-a-chunk>
+    #[allow(unsafe_code)]
+    pub fn encode_and_enqueue_chunk(&self, cx: JSContext, chunk: HandleValue) -> Fallible<()> {
+        // Step 1.
+        let units: Vec<u16> = unsafe {
+            rooted!(in(*cx) let string = ToString(*cx, chunk));
+            if string.is_null() {
+                return Err(Error::JSFailed);
+            }
+            let mut length = 0;
+            if JS_StringHasLatin1Chars(string.get()) {
+                let chars =
+                    JS_GetLatin1StringCharsAndLength(*cx, ptr::null(), string.get(), &mut length);
+                assert!(!chars.is_null());
+                slice::from_raw_parts(chars as *mut u8, length as usize)
+                    .iter()
+                    .map(|&unit| unit as u16)
+                    .collect()
+            } else {
+                let chars =
+                    JS_GetTwoByteStringCharsAndLength(*cx, ptr::null(), string.get(), &mut length);
+                assert!(!chars.is_null());
+                slice::from_raw_parts(chars, length as usize).to_vec()
+            }
+        };
+        // Steps 2-4.
+        let mut output = String::with_capacity(units.len());
+        let mut units = units.into_iter().peekable();
+        if let Some(high) = self.pending_high_surrogate.take() {
+            match units.peek() {
+                Some(&low) if (0xDC00..=0xDFFF).contains(&low) => {
+                    units.next();
+                    output.extend(
+                        std::char::decode_utf16(vec![high, low])
+                            .map(|item| item.unwrap_or(std::char::REPLACEMENT_CHARACTER)),
+                    );
+                },
+                Some(_) => output.push(std::char::REPLACEMENT_CHARACTER),
+                None => {
+                    self.pending_high_surrogate.set(Some(high));
+                    return Ok(());
+                },
+            }
+        }
+        let mut units: Vec<u16> = units.collect();
+        // A high surrogate at the end of the chunk may pair with the next chunk.
+        if let Some(&last) = units.last() {
+            if (0xD800..=0xDBFF).contains(&last) {
+                self.pending_high_surrogate.set(Some(last));
+                units.pop();
+            }
+        }
+        output.extend(
+            std::char::decode_utf16(units)
+                .map(|item| item.unwrap_or(std::char::REPLACEMENT_CHARACTER)),
+        );
+        // Step 5.
+        if output.is_empty() {
+            return Ok(());
+        }
+        self.transform().enqueue_bytes(output.as_bytes())
+    }
+
+    /// <https://encoding.spec.whatwg.org/#encode-and-flush>
+    pub fn encode_and_flush(&self) -> Fallible<()> {
+        if self.pending_high_surrogate.take().is_some() {
+            // The UTF-8 encoding of U+FFFD.
+            return self.transform().enqueue_bytes(&[0xEF, 0xBF, 0xBD]);
+        }
+        Ok(())
+    }
+}
+
+impl TextEncoderStreamMethods for TextEncoderStream {
+    // https://encoding.spec.whatwg.org/#dom-textencoder-encoding
+    fn Encoding(&self) -> DOMString {
+        DOMString::from("utf-8")
+    }
+
+    // https://streams.spec.whatwg.org/#dom-generictransformstream-readable
+    fn Readable(&self, _cx: JSContext) -> NonNull<JSObject> {
+        self.transform().readable().get_js_stream()
+    }
+
+    // https://streams.spec.whatwg.org/#dom-generictransformstream-writable
+    fn Writable(&self) -> DomRoot<WritableStream> {
+        self.transform().writable()
+    }
+}
@@ -0,0 +1,681 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::callback::{CallbackContainer, ExceptionHandling};
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::QueuingStrategyBinding::{
+    QueuingStrategy, QueuingStrategySize,
+};
+use crate::dom::bindings::codegen::Bindings::TransformStreamBinding::{
+    TransformStreamMethods, Transformer,
+};
+use crate::dom::bindings::conversions::{root_from_object, ConversionResult};
+use crate::dom::bindings::error::{take_exception, Error, Fallible};
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot, MutNullableDom};
+use crate::dom::bindings::utils::set_dictionary_property;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::promise::Promise;
+use crate::dom::promisenativehandler::{Callback, PromiseNativeHandler};
+use crate::dom::readablestream::ReadableStream;
+use crate::dom::transformstreamdefaultcontroller::{
+    TransformStreamDefaultController, TransformerType,
+};
+use crate::dom::writablestream::{
+    extract_high_water_mark, resolved_promise, WritableStream, WritableStreamState,
+};
+use crate::dom::writablestreamdefaultcontroller::UnderlyingSinkType;
+use crate::realms::{enter_realm, InRealm};
+use crate::script_runtime::JSContext;
+use dom_struct::dom_struct;
+use js::jsapi::{CallArgs, ExceptionStackBehavior, GetFunctionNativeReserved};
+use js::jsapi::{HandleValueArray, Heap, JSObject};
+use js::jsapi::{JSContext as RawJSContext, JS_ClearPendingException, JS_GetFunctionObject};
+use js::jsapi::{JS_NewPlainObject, NewFunctionWithReserved, SetFunctionNativeReserved};
+use js::jsval::{DoubleValue, JSVal, NullValue, ObjectValue, UndefinedValue};
+use js::rust::wrappers::{Construct1, JS_CallFunctionName, JS_GetProperty, JS_SetPendingException};
+use js::rust::HandleValue;
+use js::typedarray::{CreateWith, Uint8Array};
+use std::cell::Cell;
+use std::os::raw::c_char;
+use std::ptr::{self, NonNull};
+use std::rc::Rc;
+
+/// The reserved slot of the functions of the underlying source of the readable side, that
+/// holds the transform stream.
+const SLOT_TRANSFORM_STREAM: usize = 0;
+
+type SourceFunction = unsafe extern "C" fn(*mut RawJSContext, u32, *mut JSVal) -> bool;
+
+// https://streams.spec.whatwg.org/#ts-class
+#[dom_struct]
+pub struct TransformStream {
+    reflector_: Reflector,
+    /// <https://streams.spec.whatwg.org/#transformstream-backpressure>
+    backpressure: Cell<bool>,
+    /// <https://streams.spec.whatwg.org/#transformstream-backpressurechangepromise>
+    #[ignore_malloc_size_of = "Rc"]
+    backpressure_change_promise: DomRefCell<Option<Rc<Promise>>>,
+    /// <https://streams.spec.whatwg.org/#transformstream-controller>
+    controller: MutNullableDom<TransformStreamDefaultController>,
+    /// <https://streams.spec.whatwg.org/#transformstream-readable>
+    readable: MutNullableDom<ReadableStream>,
+    /// The `ReadableStreamDefaultController` of the readable side, that chunks are
+    /// enqueued with.
+    #[ignore_malloc_size_of = "Defined in rust-mozjs"]
+    readable_controller: Heap<*mut JSObject>,
+    /// <https://streams.spec.whatwg.org/#transformstream-writable>
+    writable: MutNullableDom<WritableStream>,
+    /// The promise the start algorithms of both sides return.
+    #[ignore_malloc_size_of = "Rc"]
+    start_promise: DomRefCell<Option<Rc<Promise>>>,
+}
+
+impl TransformStream {
+    fn new_inherited() -> TransformStream {
+        TransformStream {
+            reflector_: Reflector::new(),
+            backpressure: Cell::new(false),
+            backpressure_change_promise: Default::default(),
+            controller: Default::default(),
+            readable: Default::default(),
+            readable_controller: Heap::default(),
+            writable: Default::default(),
+            start_promise: Default::default(),
+        }
+    }
+
+    fn new(global: &GlobalScope) -> DomRoot<TransformStream> {
+        reflect_dom_object(Box::new(TransformStream::new_inherited()), global)
+    }
+
+    /// Creates a transform stream whose algorithms are implemented natively.
+    /// <https://streams.spec.whatwg.org/#transformstream-set-up>
+    #[allow(unrooted_must_root)]
+    pub fn new_with_transformer(
+        global: &GlobalScope,
+        transformer: TransformerType,
+    ) -> Fallible<DomRoot<TransformStream>> {
+        let stream = TransformStream::new(global);
+        stream.initialize(global, resolved_promise(global), 1., None, 0., None)?;
+        let controller = TransformStreamDefaultController::new(global, transformer);
+        stream.set_up_controller(&controller);
+        Ok(stream)
+    }
+
+    // https://streams.spec.whatwg.org/#ts-constructor
+    #[allow(non_snake_case, unsafe_code)]
+    pub fn Constructor(
+        cx: JSContext,
+        global: &GlobalScope,
+        transformer: Option<*mut JSObject>,
+        writable_strategy: &QueuingStrategy,
+        readable_strategy: &QueuingStrategy,
+    ) -> Fallible<DomRoot<TransformStream>> {
+        // Steps 1-2.
+        rooted!(in(*cx) let mut transformer_object = NullValue());
+        if let Some(object) = transformer {
+            transformer_object.set(ObjectValue(object));
+        }
+        let transformer_dict = match unsafe { Transformer::new(cx, transformer_object.handle()) } {
+            Ok(ConversionResult::Success(dict)) => dict,
+            Ok(ConversionResult::Failure(error)) => return Err(Error::Type(error.into_owned())),
+            Err(()) => return Err(Error::JSFailed),
+        };
+        // Steps 5-8.
+        let readable_high_water_mark = extract_high_water_mark(readable_strategy, 0.)?;
+        let writable_high_water_mark = extract_high_water_mark(writable_strategy, 1.)?;
+        // Steps 9-10.
+        let start_promise = Promise::new(global);
+        let stream = TransformStream::new(global);
+        stream.initialize(
+            global,
+            start_promise.clone(),
+            writable_high_water_mark,
+            writable_strategy.size.clone(),
+            readable_high_water_mark,
+            readable_strategy.size.clone(),
+        )?;
+        // Step 11.
+        let start = transformer_dict.start.clone();
+        let controller = TransformStreamDefaultController::new(
+            global,
+            TransformerType::new_js(transformer_dict),
+        );
+        if let Some(object) = transformer {
+            controller.set_transformer_object(object);
+        }
+        stream.set_up_controller(&controller);
+        // Step 12.
+        match start {
+            Some(start) => {
+                rooted!(in(*cx) let this = transformer.unwrap_or(ptr::null_mut()));
+                let result =
+                    start.Call_(&this.handle(), &*controller, ExceptionHandling::Rethrow)?;
+                rooted!(in(*cx) let result = result);
+                let _ac = enter_realm(global);
+                start_promise.resolve(cx, result.handle());
+            },
+            None => start_promise.resolve_native(&()),
+        }
+        Ok(stream)
+    }
+
+    /// <https://streams.spec.whatwg.org/#initialize-transform-stream>
+    fn initialize(
+        &self,
+        global: &GlobalScope,
+        start_promise: Rc<Promise>,
+        writable_high_water_mark: f64,
+        writable_size: Option<Rc<QueuingStrategySize>>,
+        readable_high_water_mark: f64,
+        readable_size: Option<Rc<QueuingStrategySize>>,
+    ) -> Fallible<()> {
+        // Step 1.
+        *self.start_promise.borrow_mut() = Some(start_promise);
+        // Steps 2-5.
+        let writable = WritableStream::new_with_sink(
+            global,
+            UnderlyingSinkType::Transform(Dom::from_ref(self)),
+            writable_high_water_mark,
+            writable_size,
+        )?;
+        self.writable.set(Some(&writable));
+        // Steps 6-9.
+        self.create_readable(global, readable_high_water_mark, readable_size)?;
+        // Steps 10-11.
+        *self.backpressure_change_promise.borrow_mut() = None;
+        self.backpressure.set(false);
+        self.set_backpressure(true);
+        Ok(())
+    }
+
+    /// Creates the readable side, by constructing an engine-provided `ReadableStream` whose
+    /// underlying source calls back into this stream.
+    #[allow(unsafe_code)]
+    fn create_readable(
+        &self,
+        global: &GlobalScope,
+        high_water_mark: f64,
+        size: Option<Rc<QueuingStrategySize>>,
+    ) -> Fallible<()> {
+        let realm = enter_realm(global);
+        let cx = global.get_cx();
+        unsafe {
+            rooted!(in(*cx) let source = JS_NewPlainObject(*cx));
+            let functions: [(&str, SourceFunction); 3] = [
+                ("start", source_start),
+                ("pull", source_pull),
+                ("cancel", source_cancel),
+            ];
+            for &(name, function) in functions.iter() {
+                rooted!(in(*cx) let function = ObjectValue(self.new_source_function(cx, function)));
+                set_dictionary_property(*cx, source.handle(), name, function.handle())
+                    .map_err(|_| Error::JSFailed)?;
+            }
+
+            rooted!(in(*cx) let strategy = JS_NewPlainObject(*cx));
+            rooted!(in(*cx) let high_water_mark = DoubleValue(high_water_mark));
+            set_dictionary_property(
+                *cx,
+                strategy.handle(),
+                "highWaterMark",
+                high_water_mark.handle(),
+            )
+            .map_err(|_| Error::JSFailed)?;
+            if let Some(size) = size {
+                rooted!(in(*cx) let size = ObjectValue(size.callback()));
+                set_dictionary_property(*cx, strategy.handle(), "size", size.handle())
+                    .map_err(|_| Error::JSFailed)?;
+            }
+
+            rooted!(in(*cx) let global_object = global.reflector().get_jsobject().get());
+            rooted!(in(*cx) let mut constructor = UndefinedValue());
+            if !JS_GetProperty(
+                *cx,
+                global_object.handle(),
+                b"ReadableStream\0".as_ptr() as *const c_char,
+                constructor.handle_mut(),
+            ) {
+                return Err(Error::JSFailed);
+            }
+            let args = [ObjectValue(source.get()), ObjectValue(strategy.get())];
+            let args = HandleValueArray::from_rooted_slice(&args);
+            rooted!(in(*cx) let mut readable = ptr::null_mut::<JSObject>());
+            if !Construct1(*cx, constructor.handle(), &args, readable.handle_mut()) {
+                return Err(Error::JSFailed);
+            }
+            let readable = ReadableStream::from_js(cx, readable.get(), InRealm::Entered(&realm))
+                .map_err(|_| Error::Type("Couldn't create the readable side".to_owned()))?;
+            self.readable.set(Some(&readable));
+        }
+        Ok(())
+    }
+
+    #[allow(unsafe_code)]
+    unsafe fn new_source_function(&self, cx: JSContext, function: SourceFunction) -> *mut JSObject {
+        let function = NewFunctionWithReserved(*cx, Some(function), 1, 0, ptr::null());
+        assert!(!function.is_null());
+        let object = JS_GetFunctionObject(function);
+        assert!(!object.is_null());
+        SetFunctionNativeReserved(
+            object,
+            SLOT_TRANSFORM_STREAM,
+            &ObjectValue(self.reflector().get_jsobject().get()),
+        );
+        object
+    }
+
+    /// <https://streams.spec.whatwg.org/#set-up-transform-stream-default-controller>
+    fn set_up_controller(&self, controller: &TransformStreamDefaultController) {
+        controller.set_stream(self);
+        self.controller.set(Some(controller));
+    }
+
+    pub fn start_promise(&self) -> Rc<Promise> {
+        self.start_promise
+            .borrow()
+            .clone()
+            .expect("The stream was initialized")
+    }
+
+    fn controller(&self) -> DomRoot<TransformStreamDefaultController> {
+        self.controller.get().expect("The controller was set up")
+    }
+
+    pub fn readable(&self) -> DomRoot<ReadableStream> {
+        self.readable.get().expect("The stream was initialized")
+    }
+
+    pub fn writable(&self) -> DomRoot<WritableStream> {
+        self.writable.get().expect("The stream was initialized")
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-default-controller-enqueue>
+    #[allow(unsafe_code)]
+    pub fn readable_enqueue(&self, chunk: HandleValue) -> Fallible<()> {
+        let cx = self.global().get_cx();
+        rooted!(in(*cx) let chunk = chunk.get());
+        let values = [chunk.get()];
+        let args = unsafe { HandleValueArray::from_rooted_slice(&values) };
+        if self.call_readable_controller(b"enqueue\0", &args) {
+            Ok(())
+        } else {
+            Err(Error::JSFailed)
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-default-controller-close>
+    pub fn readable_close(&self) -> Fallible<()> {
+        let args = HandleValueArray::new();
+        if self.call_readable_controller(b"close\0", &args) {
+            Ok(())
+        } else {
+            Err(Error::JSFailed)
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-default-controller-get-desired-size>
+    #[allow(unsafe_code)]
+    pub fn readable_desired_size(&self) -> Option<f64> {
+        let global = self.global();
+        let _ac = enter_realm(&*global);
+        let cx = global.get_cx();
+        rooted!(in(*cx) let controller = self.readable_controller.get());
+        rooted!(in(*cx) let mut desired_size = UndefinedValue());
+        unsafe {
+            if !JS_GetProperty(
+                *cx,
+                controller.handle(),
+                b"desiredSize\0".as_ptr() as *const c_char,
+                desired_size.handle_mut(),
+            ) {
+                JS_ClearPendingException(*cx);
+                return None;
+            }
+        }
+        if desired_size.is_number() {
+            Some(desired_size.to_number())
+        } else {
+            None
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-default-controller-has-backpressure>
+    fn readable_has_backpressure(&self) -> bool {
+        self.readable_desired_size().map_or(true, |size| size <= 0.)
+    }
+
+    #[allow(unsafe_code)]
+    fn call_readable_controller(&self, name: &[u8], args: &HandleValueArray) -> bool {
+        let global = self.global();
+        let _ac = enter_realm(&*global);
+        let cx = global.get_cx();
+        rooted!(in(*cx) let controller = self.readable_controller.get());
+        rooted!(in(*cx) let mut rval = UndefinedValue());
+        unsafe {
+            JS_CallFunctionName(
+                *cx,
+                controller.handle(),
+                name.as_ptr() as *const c_char,
+                args,
+                rval.handle_mut(),
+            )
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-error>
+    pub fn error(&self, error: HandleValue) {
+        self.readable().error_if_readable(error);
+        self.error_writable_and_unblock_write(error);
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-error-writable-and-unblock-write>
+    pub fn error_writable_and_unblock_write(&self, error: HandleValue) {
+        self.controller().clear_algorithms();
+        self.writable().controller().error_if_needed(error);
+        // https://streams.spec.whatwg.org/#transform-stream-unblock-write
+        if self.backpressure.get() {
+            self.set_backpressure(false);
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-set-backpressure>
+    pub fn set_backpressure(&self, backpressure: bool) {
+        // Step 1.
+        assert_ne!(self.backpressure.get(), backpressure);
+        // Steps 2-3.
+        let promise = Promise::new(&self.global());
+        let previous = self
+            .backpressure_change_promise
+            .borrow_mut()
+            .replace(promise);
+        if let Some(previous) = previous {
+            previous.resolve_native(&());
+        }
+        // Step 4.
+        self.backpressure.set(backpressure);
+    }
+
+    pub fn backpressure(&self) -> bool {
+        self.backpressure.get()
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-default-controller-enqueue>
+    pub fn enqueue(&self, chunk: HandleValue) -> Fallible<()> {
+        // Steps 1-3.
+        if self.readable_desired_size().is_none() || !self.readable().is_readable() {
+            return Err(Error::Type(
+                "The readable side can't be enqueued to".to_owned(),
+            ));
+        }
+        // Steps 4-5.
+        if let Err(error) = self.readable_enqueue(chunk) {
+            let global = self.global();
+            let cx = global.get_cx();
+            rooted!(in(*cx) let mut error_value = UndefinedValue());
+            take_exception(&global, error, error_value.handle_mut());
+            self.error_writable_and_unblock_write(error_value.handle());
+            return Err(self.throw(error_value.handle()));
+        }
+        // Steps 6-7.
+        let backpressure = self.readable_has_backpressure();
+        if backpressure != self.backpressure.get() {
+            assert!(backpressure);
+            self.set_backpressure(true);
+        }
+        Ok(())
+    }
+
+    /// Enqueues `bytes` as a `Uint8Array`, for transformers that output bytes.
+    #[allow(unsafe_code)]
+    pub fn enqueue_bytes(&self, bytes: &[u8]) -> Fallible<()> {
+        let cx = self.global().get_cx();
+        rooted!(in(*cx) let mut chunk = ptr::null_mut::<JSObject>());
+        unsafe {
+            Uint8Array::create(*cx, CreateWith::Slice(bytes), chunk.handle_mut())
+                .map_err(|_| Error::JSFailed)?;
+        }
+        rooted!(in(*cx) let chunk = ObjectValue(chunk.get()));
+        self.enqueue(chunk.handle())
+    }
+
+    /// Makes `error` the pending exception, for an `Error::JSFailed` to be thrown.
+    #[allow(unsafe_code)]
+    fn throw(&self, error: HandleValue) -> Error {
+        let global = self.global();
+        let _ac = enter_realm(&*global);
+        unsafe {
+            JS_SetPendingException(*global.get_cx(), error, ExceptionStackBehavior::Capture);
+        }
+        Error::JSFailed
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-default-controller-terminate>
+    #[allow(unsafe_code)]
+    pub fn terminate(&self) {
+        let global = self.global();
+        let _ac = enter_realm(&*global);
+        let cx = global.get_cx();
+        // Steps 1-3.
+        if self.readable_close().is_err() {
+            unsafe { JS_ClearPendingException(*cx) };
+        }
+        // Steps 4-5.
+        rooted!(in(*cx) let mut error = UndefinedValue());
+        take_exception(
+            &global,
+            Error::Type("The transform stream was terminated".to_owned()),
+            error.handle_mut(),
+        );
+        self.error_writable_and_unblock_write(error.handle());
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-default-controller-perform-transform>
+    fn perform_transform(&self, chunk: HandleValue) -> Rc<Promise> {
+        let global = self.global();
+        let transform_promise = self.controller().transform_algorithm(chunk);
+        let result = Promise::new(&global);
+        self.react(
+            &transform_promise,
+            TransformReaction::TransformFulfilled(result.clone()),
+            TransformReaction::TransformRejected(result.clone()),
+        );
+        result
+    }
+
+    fn react(&self, promise: &Promise, fulfilled: TransformReaction, rejected: TransformReaction) {
+        let global = self.global();
+        let handler = PromiseNativeHandler::new(
+            &global,
+            Some(Box::new(TransformReactionHandler {
+                stream: DomRoot::from_ref(self),
+                reaction: fulfilled,
+            })),
+            Some(Box::new(TransformReactionHandler {
+                stream: DomRoot::from_ref(self),
+                reaction: rejected,
+            })),
+        );
+        let realm = enter_realm(&*global);
+        promise.append_native_handler(&handler, InRealm::Entered(&realm));
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-default-sink-write-algorithm>
+    pub fn sink_write_algorithm(&self, chunk: HandleValue) -> Rc<Promise> {
+        // Step 1.
+        assert_eq!(self.writable().state(), WritableStreamState::Writable);
+        // Step 3.
+        if self.backpressure.get() {
+            let backpressure_change_promise = self
+                .backpressure_change_promise
+                .borrow()
+                .clone()
+                .expect("Backpressure was set");
+            let result = Promise::new(&self.global());
+            let chunk_value = Box::new(Heap::default());
+            chunk_value.set(chunk.get());
+            self.react(
+                &backpressure_change_promise,
+                TransformReaction::BackpressureChanged(result.clone(), chunk_value),
+                TransformReaction::Ignore,
+            );
+            return result;
+        }
+        // Step 4.
+        self.perform_transform(chunk)
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-default-sink-abort-algorithm>
+    pub fn sink_abort_algorithm(&self, reason: HandleValue) -> Rc<Promise> {
+        self.error(reason);
+        resolved_promise(&self.global())
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-default-sink-close-algorithm>
+    pub fn sink_close_algorithm(&self) -> Rc<Promise> {
+        let controller = self.controller();
+        let flush_promise = controller.flush_algorithm();
+        controller.clear_algorithms();
+        let result = Promise::new(&self.global());
+        self.react(
+            &flush_promise,
+            TransformReaction::FlushFulfilled(result.clone()),
+            TransformReaction::FlushRejected(result.clone()),
+        );
+        result
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-default-source-pull>
+    fn source_pull_algorithm(&self) -> Rc<Promise> {
+        assert!(self.backpressure.get());
+        self.set_backpressure(false);
+        self.backpressure_change_promise
+            .borrow()
+            .clone()
+            .expect("Backpressure was set")
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-default-source-cancel>
+    fn source_cancel_algorithm(&self, reason: HandleValue) -> Rc<Promise> {
+        self.error_writable_and_unblock_write(reason);
+        resolved_promise(&self.global())
+    }
+}
+
+impl TransformStreamMethods for TransformStream {
+    // https://streams.spec.whatwg.org/#ts-readable
+    fn Readable(&self, _cx: JSContext) -> NonNull<JSObject> {
+        self.readable().get_js_stream()
+    }
+
+    // https://streams.spec.whatwg.org/#ts-writable
+    fn Writable(&self) -> DomRoot<WritableStream> {
+        self.writable()
+    }
+}
+
+#[allow(unsafe_code)]
+unsafe fn stream_from_callee(cx: *mut RawJSContext, args: &CallArgs) -> DomRoot<TransformStream> {
+    rooted!(in(cx) let stream = *GetFunctionNativeReserved(args.callee(), SLOT_TRANSFORM_STREAM));
+    root_from_object::<TransformStream>(stream.to_object(), cx)
+        .expect("unexpected value for the transform stream of an underlying source function")
+}
+
+/// The start method of the underlying source of the readable side, which keeps its
+/// controller.
+#[allow(unsafe_code)]
+unsafe extern "C" fn source_start(cx: *mut RawJSContext, argc: u32, vp: *mut JSVal) -> bool {
+    let args = CallArgs::from_vp(vp, argc);
+    let stream = stream_from_callee(cx, &args);
+    let controller = args.get(0);
+    assert!(controller.is_object());
+    stream.readable_controller.set(controller.to_object());
+    let start_promise = stream.start_promise();
+    *args.rval() = ObjectValue(start_promise.reflector().get_jsobject().get());
+    true
+}
+
+/// The pull method of the underlying source of the readable side.
+#[allow(unsafe_code)]
+unsafe extern "C" fn source_pull(cx: *mut RawJSContext, argc: u32, vp: *mut JSVal) -> bool {
+    let args = CallArgs::from_vp(vp, argc);
+    let stream = stream_from_callee(cx, &args);
+    let promise = stream.source_pull_algorithm();
+    *args.rval() = ObjectValue(promise.reflector().get_jsobject().get());
+    true
+}
+
+/// The cancel method of the underlying source of the readable side.
+#[allow(unsafe_code)]
+unsafe extern "C" fn source_cancel(cx: *mut RawJSContext, argc: u32, vp: *mut JSVal) -> bool {
+    let args = CallArgs::from_vp(vp, argc);
+    let stream = stream_from_callee(cx, &args);
+    let promise = stream.source_cancel_algorithm(HandleValue::from_raw(args.get(0)));
+    *args.rval() = ObjectValue(promise.reflector().get_jsobject().get());
+    true
+}
+
+/// The reactions of a transform stream to the promises of its transformer.
+#[derive(JSTraceable, MallocSizeOf)]
+enum TransformReaction {
+    /// Step 3.1 of transform-stream-default-sink-write-algorithm.
+    BackpressureChanged(
+        #[ignore_malloc_size_of = "Rc"] Rc<Promise>,
+        #[ignore_malloc_size_of = "Defined in rust-mozjs"] Box<Heap<JSVal>>,
+    ),
+    /// Step 2 of transform-stream-default-controller-perform-transform.
+    TransformFulfilled(#[ignore_malloc_size_of = "Rc"] Rc<Promise>),
+    TransformRejected(#[ignore_malloc_size_of = "Rc"] Rc<Promise>),
+    /// Step 5 of transform-stream-default-sink-close-algorithm.
+    FlushFulfilled(#[ignore_malloc_size_of = "Rc"] Rc<Promise>),
+    FlushRejected(#[ignore_malloc_size_of = "Rc"] Rc<Promise>),
+    Ignore,
+}
+
+#[derive(JSTraceable, MallocSizeOf)]
+struct TransformReactionHandler {
+    stream: DomRoot<TransformStream>,
+    reaction: TransformReaction,
+}
+
+impl Callback for TransformReactionHandler {
+    #[allow(unsafe_code)]
+    fn callback(&self, cx: JSContext, v: HandleValue, _realm: InRealm) {
+        let stream = &self.stream;
+        match self.reaction {
+            TransformReaction::BackpressureChanged(ref result, ref chunk) => {
+                let writable = stream.writable();
+                if writable.state() == WritableStreamState::Erroring {
+                    rooted!(in(*cx) let stored_error = writable.stored_error());
+                    return result.reject(cx, stored_error.handle());
+                }
+                assert_eq!(writable.state(), WritableStreamState::Writable);
+                rooted!(in(*cx) let chunk = chunk.get());
+                let transformed = stream.perform_transform(chunk.handle());
+                rooted!(in(*cx) let transformed =
+                    ObjectValue(transformed.reflector().get_jsobject().get()));
+                result.resolve(cx, transformed.handle());
+            },
+            TransformReaction::TransformFulfilled(ref result) => result.resolve_native(&()),
+            TransformReaction::TransformRejected(ref result) => {
+                stream.error(v);
+                result.reject(cx, v);
+            },
+            TransformReaction::FlushFulfilled(ref result) => match stream.readable_close() {
+                Ok(()) => result.resolve_native(&()),
+                Err(error) => {
+                    rooted!(in(*cx) let mut error_value = UndefinedValue());
+                    take_exception(&stream.global(), error, error_value.handle_mut());
+                    result.reject(cx, error_value.handle());
+                },
+            },
+            TransformReaction::FlushRejected(ref result) => {
+                stream.error(v);
+                result.reject(cx, v);
+            },
+            TransformReaction::Ignore => {},
+        }
+    }
+}
@@ -0,0 +1,225 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::callback::ExceptionHandling;
+use crate::dom::bindings::codegen::Bindings::TransformStreamBinding::{
+    Transformer, TransformerFlushCallback, TransformerTransformCallback,
+};
+use crate::dom::bindings::codegen::Bindings::TransformStreamDefaultControllerBinding::TransformStreamDefaultControllerMethods;
+use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot, MutNullableDom};
+use crate::dom::compressionstream::CompressionStream;
+use crate::dom::decompressionstream::DecompressionStream;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::promise::Promise;
+use crate::dom::textdecoderstream::TextDecoderStream;
+use crate::dom::textencoderstream::TextEncoderStream;
+use crate::dom::transformstream::TransformStream;
+use crate::dom::writablestream::resolved_promise;
+use crate::dom::writablestreamdefaultcontroller::promise_from_callback_result;
+use crate::script_runtime::JSContext;
+use dom_struct::dom_struct;
+use js::jsapi::{Heap, JSObject};
+use js::jsval::UndefinedValue;
+use js::rust::HandleValue;
+use js::typedarray::{ArrayBuffer, ArrayBufferView};
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// The algorithms of a transform stream controller, given by its transformer.
+#[derive(JSTraceable, MallocSizeOf)]
+#[unrooted_must_root_lint::must_root]
+pub enum TransformerType {
+    /// A transformer given to the `TransformStream` constructor.
+    /// <https://streams.spec.whatwg.org/#set-up-transform-stream-default-controller-from-transformer>
+    Js {
+        #[ignore_malloc_size_of = "Defined in rust-mozjs"]
+        object: Heap<*mut JSObject>,
+        #[ignore_malloc_size_of = "Rc"]
+        transform: Option<Rc<TransformerTransformCallback>>,
+        #[ignore_malloc_size_of = "Rc"]
+        flush: Option<Rc<TransformerFlushCallback>>,
+    },
+    /// <https://encoding.spec.whatwg.org/#interface-textencoderstream>
+    TextEncoder(Dom<TextEncoderStream>),
+    /// <https://encoding.spec.whatwg.org/#interface-textdecoderstream>
+    TextDecoder(Dom<TextDecoderStream>),
+    /// <https://compression.spec.whatwg.org/#compressionstream>
+    Compression(Dom<CompressionStream>),
+    /// <https://compression.spec.whatwg.org/#decompressionstream>
+    Decompression(Dom<DecompressionStream>),
+}
+
+impl TransformerType {
+    pub fn new_js(transformer: Transformer) -> TransformerType {
+        TransformerType::Js {
+            object: Heap::default(),
+            transform: transformer.transform,
+            flush: transformer.flush,
+        }
+    }
+}
+
+// https://streams.spec.whatwg.org/#ts-default-controller-class
+#[dom_struct]
+pub struct TransformStreamDefaultController {
+    reflector_: Reflector,
+    transformer: TransformerType,
+    /// <https://streams.spec.whatwg.org/#transformstreamdefaultcontroller-stream>
+    stream: MutNullableDom<TransformStream>,
+    /// Set when the algorithms are cleared, after which the transformer is never called.
+    /// <https://streams.spec.whatwg.org/#transform-stream-default-controller-clear-algorithms>
+    algorithms_cleared: Cell<bool>,
+}
+
+impl TransformStreamDefaultController {
+    #[allow(unrooted_must_root)]
+    fn new_inherited(transformer: TransformerType) -> TransformStreamDefaultController {
+        TransformStreamDefaultController {
+            reflector_: Reflector::new(),
+            transformer,
+            stream: Default::default(),
+            algorithms_cleared: Cell::new(false),
+        }
+    }
+
+    #[allow(unrooted_must_root)]
+    pub fn new(
+        global: &GlobalScope,
+        transformer: TransformerType,
+    ) -> DomRoot<TransformStreamDefaultController> {
+        reflect_dom_object(
+            Box::new(TransformStreamDefaultController::new_inherited(transformer)),
+            global,
+        )
+    }
+
+    /// Sets the object the callbacks of a JS transformer are called on.
+    pub fn set_transformer_object(&self, transformer_object: *mut JSObject) {
+        if let TransformerType::Js { ref object, .. } = self.transformer {
+            object.set(transformer_object);
+        }
+    }
+
+    pub fn set_stream(&self, stream: &TransformStream) {
+        self.stream.set(Some(stream));
+    }
+
+    fn stream(&self) -> DomRoot<TransformStream> {
+        self.stream
+            .get()
+            .expect("The controller was set up with a stream")
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-default-controller-clear-algorithms>
+    pub fn clear_algorithms(&self) {
+        self.algorithms_cleared.set(true);
+    }
+
+    /// The transform algorithm.
+    #[allow(unsafe_code)]
+    pub fn transform_algorithm(&self, chunk: HandleValue) -> Rc<Promise> {
+        let global = self.global();
+        let cx = global.get_cx();
+        if self.algorithms_cleared.get() {
+            return resolved_promise(&global);
+        }
+        let result = match self.transformer {
+            TransformerType::Js {
+                ref object,
+                ref transform,
+                ..
+            } => match *transform {
+                Some(ref transform) => {
+                    rooted!(in(*cx) let this = object.get());
+                    let result =
+                        transform.Call_(&this.handle(), chunk, self, ExceptionHandling::Rethrow);
+                    return promise_from_callback_result(&global, result);
+                },
+                None => self.stream().enqueue(chunk),
+            },
+            TransformerType::TextEncoder(ref stream) => stream.encode_and_enqueue_chunk(cx, chunk),
+            TransformerType::TextDecoder(ref stream) => stream.decode_and_enqueue_chunk(cx, chunk),
+            TransformerType::Compression(ref stream) => {
+                stream.compress_and_enqueue_chunk(cx, chunk)
+            },
+            TransformerType::Decompression(ref stream) => {
+                stream.decompress_and_enqueue_chunk(cx, chunk)
+            },
+        };
+        promise_from_callback_result(&global, result.map(|()| UndefinedValue()))
+    }
+
+    /// The flush algorithm.
+    #[allow(unsafe_code)]
+    pub fn flush_algorithm(&self) -> Rc<Promise> {
+        let global = self.global();
+        let cx = global.get_cx();
+        if self.algorithms_cleared.get() {
+            return resolved_promise(&global);
+        }
+        let result = match self.transformer {
+            TransformerType::Js {
+                ref object,
+                ref flush,
+                ..
+            } => match *flush {
+                Some(ref flush) => {
+                    rooted!(in(*cx) let this = object.get());
+                    let result = flush.Call_(&this.handle(), self, ExceptionHandling::Rethrow);
+                    return promise_from_callback_result(&global, result);
+                },
+                None => Ok(()),
+            },
+            TransformerType::TextEncoder(ref stream) => stream.encode_and_flush(),
+            TransformerType::TextDecoder(ref stream) => stream.flush_and_enqueue(),
+            TransformerType::Compression(ref stream) => stream.compress_flush_and_enqueue(),
+            TransformerType::Decompression(ref stream) => stream.decompress_flush_and_enqueue(),
+        };
+        promise_from_callback_result(&global, result.map(|()| UndefinedValue()))
+    }
+}
+
+/// Copies the bytes of a chunk given to a transformer that takes a `BufferSource`.
+#[allow(unsafe_code)]
+pub fn buffer_source_to_vec(cx: JSContext, chunk: HandleValue) -> Fallible<Vec<u8>> {
+    if chunk.is_object() {
+        unsafe {
+            typedarray!(in(*cx) let buffer: ArrayBuffer = chunk.to_object());
+            if let Ok(buffer) = buffer {
+                return Ok(buffer.to_vec());
+            }
+            typedarray!(in(*cx) let view: ArrayBufferView = chunk.to_object());
+            if let Ok(view) = view {
+                return Ok(view.to_vec());
+            }
+        }
+    }
+    Err(Error::Type(
+        "The chunk is not an ArrayBuffer or ArrayBufferView".to_owned(),
+    ))
+}
+
+impl TransformStreamDefaultControllerMethods for TransformStreamDefaultController {
+    // https://streams.spec.whatwg.org/#ts-default-controller-desired-size
+    fn GetDesiredSize(&self) -> Option<f64> {
+        self.stream().readable_desired_size()
+    }
+
+    // https://streams.spec.whatwg.org/#ts-default-controller-enqueue
+    fn Enqueue(&self, _cx: JSContext, chunk: HandleValue) -> Fallible<()> {
+        self.stream().enqueue(chunk)
+    }
+
+    // https://streams.spec.whatwg.org/#ts-default-controller-error
+    fn Error(&self, _cx: JSContext, reason: HandleValue) {
+        self.stream().error(reason);
+    }
+
+    // https://streams.spec.whatwg.org/#ts-default-controller-terminate
+    fn Terminate(&self) {
+        self.stream().terminate();
+    }
+}
@@ -2,15 +2,20 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
+use crate::dom::bindings::callback::ExceptionHandling;
 use crate::dom::bindings::cell::{DomRefCell, Ref};
 use crate::dom::bindings::codegen::Bindings::DocumentBinding::{
     DocumentMethods, DocumentReadyState,
 };
 use crate::dom::bindings::codegen::Bindings::HistoryBinding::HistoryBinding::HistoryMethods;
+use crate::dom::bindings::codegen::Bindings::IdleDeadlineBinding::{
+    IdleRequestCallback, IdleRequestOptions,
+};
 use crate::dom::bindings::codegen::Bindings::ImageBitmapBinding::{
     ImageBitmapOptions, ImageBitmapSource,
 };
 use crate::dom::bindings::codegen::Bindings::MediaQueryListBinding::MediaQueryListBinding::MediaQueryListMethods;
+use crate::dom::bindings::codegen::Bindings::MessagePortBinding::StructuredSerializeOptions;
 use crate::dom::bindings::codegen::Bindings::RequestBinding::RequestInit;
 use crate::dom::bindings::codegen::Bindings::VoidFunctionBinding::VoidFunction;
 use crate::dom::bindings::codegen::Bindings::WindowBinding::{
@@ -30,6 +35,7 @@ use crate::dom::bindings::trace::{JSTraceable, RootedTraceableBox};
 use crate::dom::bindings::utils::{GlobalStaticData, WindowProxyHandler};
 use crate::dom::bindings::weakref::DOMTracker;
 use crate::dom::bluetooth::BluetoothExtraPermissionData;
+use crate::dom::cachestorage::CacheStorage;
 use crate::dom::crypto::Crypto;
 use crate::dom::cssstyledeclaration::{CSSModificationAccess, CSSStyleDeclaration, CSSStyleOwner};
 use crate::dom::customelementregistry::CustomElementRegistry;
@@ -40,7 +46,9 @@ use crate::dom::eventtarget::EventTarget;
 use crate::dom::globalscope::GlobalScope;
 use crate::dom::hashchangeevent::HashChangeEvent;
 use crate::dom::history::History;
+use crate::dom::idbfactory::IDBFactory;
 use crate::dom::identityhub::Identities;
+use crate::dom::idledeadline::{IdleCallbackTimeoutCallback, IdleDeadline, IdleRequest};
 use crate::dom::location::Location;
 use crate::dom::mediaquerylist::{MediaQueryList, MediaQueryListMatchState};
 use crate::dom::mediaquerylistevent::MediaQueryListEvent;
@@ -49,6 +57,7 @@ use crate::dom::navigator::Navigator;
 use crate::dom::node::{document_from_node, from_untrusted_node_address, Node, NodeDamage};
 use crate::dom::performance::Performance;
 use crate::dom::promise::Promise;
+use crate::dom::scheduler::Scheduler;
 use crate::dom::screen::Screen;
 use crate::dom::selection::Selection;
 use crate::dom::storage::Storage;
@@ -57,6 +66,7 @@ use crate::dom::webglrenderingcontext::WebGLCommandSender;
 use crate::dom::windowproxy::WindowProxy;
 use crate::dom::worklet::Worklet;
 use crate::dom::workletglobalscope::WorkletGlobalScopeType;
+use crate::editing;
 use crate::fetch;
 use crate::layout_image::fetch_image_for_layout;
 use crate::malloc_size_of::MallocSizeOf;
@@ -67,9 +77,10 @@ use crate::script_runtime::{
 };
 use crate::script_thread::{ImageCacheMsg, MainThreadScriptChan, MainThreadScriptMsg};
 use crate::script_thread::{ScriptThread, SendableMainThreadScriptChan};
+use crate::security_manager::SandboxingFlagSet;
 use crate::task_manager::TaskManager;
 use crate::task_source::{TaskSource, TaskSourceName};
-use crate::timers::{IsInterval, TimerCallback};
+use crate::timers::{IsInterval, OneshotTimerCallback, TimerCallback};
 use crate::webdriver_handlers::jsval_to_webdriver;
 use app_units::Au;
 use backtrace::Backtrace;
@@ -107,13 +118,17 @@ use profile_traits::ipc as ProfiledIpc;
 use profile_traits::mem::ProfilerChan as MemProfilerChan;
 use profile_traits::time::{ProfilerChan as TimeProfilerChan, ProfilerMsg};
 use script_layout_interface::message::{Msg, QueryMsg, Reflow, ReflowGoal, ScriptReflow};
+use script_layout_interface::rpc::{BoxSizes, BoxSizesResponse};
+use script_layout_interface::rpc::{CaretRectResponse, TextPositionResponse};
 use script_layout_interface::rpc::{ContentBoxResponse, ContentBoxesResponse, LayoutRPC};
 use script_layout_interface::rpc::{
     NodeScrollIdResponse, ResolvedStyleResponse, TextIndexResponse,
 };
 use script_layout_interface::{PendingImageState, TrustedNodeAddress};
 use script_traits::webdriver_msg::{WebDriverJSError, WebDriverJSResult};
-use script_traits::{ConstellationControlMsg, DocumentState, HistoryEntryReplacement, LoadData};
+use script_traits::{
+    ConstellationControlMsg, DocumentState, HistoryEntryReplacement, LoadData, MsDuration,
+};
 use script_traits::{
     ScriptMsg, ScriptToConstellationChan, ScrollState, StructuredSerializedData, TimerEventId,
 };
@@ -198,6 +213,8 @@ pub struct Window {
     history: MutNullableDom<History>,
     custom_element_registry: MutNullableDom<CustomElementRegistry>,
     performance: MutNullableDom<Performance>,
+    indexed_db: MutNullableDom<IDBFactory>,
+    caches: MutNullableDom<CacheStorage>,
     navigation_start: Cell<u64>,
     navigation_start_precise: Cell<u64>,
     screen: MutNullableDom<Screen>,
@@ -354,6 +371,23 @@ pub struct Window {
 
     /// https://dom.spec.whatwg.org/#window-current-event
     current_event: DomRefCell<Option<Dom<Event>>>,
+
+    /// <https://w3c.github.io/requestidlecallback/#dfn-idle-callback-identifier>
+    idle_callback_identifier: Cell<u32>,
+
+    /// <https://w3c.github.io/requestidlecallback/#dfn-list-of-idle-request-callbacks>
+    idle_request_callbacks: DomRefCell<Vec<IdleRequest>>,
+
+    /// <https://w3c.github.io/requestidlecallback/#dfn-list-of-runnable-idle-callbacks>
+    runnable_idle_callbacks: DomRefCell<Vec<IdleRequest>>,
+
+    /// <https://wicg.github.io/scheduling-apis/#dom-windoworworkerglobalscope-scheduler>
+    scheduler: MutNullableDom<Scheduler>,
+
+    /// Whether this window is a secure context, which is known before its document is set,
+    /// when the members that are only exposed in secure contexts are defined.
+    /// <https://html.spec.whatwg.org/multipage/#secure-context>
+    is_secure_context: bool,
 }
 
 impl Window {
@@ -639,6 +673,9 @@ impl WindowMethods for Window {
             stdout.flush().unwrap();
             stderr.flush().unwrap();
         }
+        if self.cannot_show_simple_dialogs() {
+            return;
+        }
         let (sender, receiver) =
             ProfiledIpc::channel(self.global().time_profiler_chan().clone()).unwrap();
         let prompt = PromptDefinition::Alert(s.to_string(), sender);
@@ -649,6 +686,9 @@ impl WindowMethods for Window {
 
     // https://html.spec.whatwg.org/multipage/#dom-confirm
     fn Confirm(&self, s: DOMString) -> bool {
+        if self.cannot_show_simple_dialogs() {
+            return false;
+        }
         let (sender, receiver) =
             ProfiledIpc::channel(self.global().time_profiler_chan().clone()).unwrap();
         let prompt = PromptDefinition::OkCancel(s.to_string(), sender);
@@ -659,6 +699,9 @@ impl WindowMethods for Window {
 
     // https://html.spec.whatwg.org/multipage/#dom-prompt
     fn Prompt(&self, message: DOMString, default: DOMString) -> Option<DOMString> {
+        if self.cannot_show_simple_dialogs() {
+            return None;
+        }
         let (sender, receiver) =
             ProfiledIpc::channel(self.global().time_profiler_chan().clone()).unwrap();
         let prompt = PromptDefinition::Input(message.to_string(), default.to_string(), sender);
@@ -667,6 +710,26 @@ impl WindowMethods for Window {
         receiver.recv().unwrap().map(|s| s.into())
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-print
+    fn Print(&self) {
+        // Step 1-2.
+        let document = self.Document();
+        if !document.is_fully_active() {
+            return;
+        }
+
+        // Step 4. There is no printing backend, so the printing steps only let the page
+        // prepare for and recover from printing.
+        // https://html.spec.whatwg.org/multipage/#printing-steps
+        self.upcast::<EventTarget>()
+            .fire_event(atom!("beforeprint"));
+
+        // Everything in the document is printed, not only what is near the viewport.
+        document.load_lazy_loading_elements();
+
+        self.upcast::<EventTarget>().fire_event(atom!("afterprint"));
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-window-stop
     fn Stop(&self) {
         // TODO: Cancel ongoing navigation.
@@ -911,6 +974,17 @@ impl WindowMethods for Window {
             .queue_function_as_microtask(callback);
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-structuredclone
+    fn StructuredClone(
+        &self,
+        cx: JSContext,
+        value: HandleValue,
+        options: RootedTraceableBox<StructuredSerializeOptions>,
+    ) -> Fallible<JSVal> {
+        self.upcast::<GlobalScope>()
+            .structured_clone(cx, value, options)
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-createimagebitmap
     fn CreateImageBitmap(
         &self,
@@ -1008,6 +1082,56 @@ impl WindowMethods for Window {
         doc.cancel_animation_frame(ident);
     }
 
+    /// <https://w3c.github.io/requestidlecallback/#the-requestidlecallback-method>
+    fn RequestIdleCallback(
+        &self,
+        callback: Rc<IdleRequestCallback>,
+        options: &IdleRequestOptions,
+    ) -> u32 {
+        // Steps 2-3.
+        let handle = self.idle_callback_identifier.get() + 1;
+        self.idle_callback_identifier.set(handle);
+
+        // Step 5. The timeout runs the callback straight from the timer task rather
+        // than from a throttled idle task, so that it can't be delayed any further.
+        let timeout = options
+            .timeout
+            .filter(|timeout| *timeout > 0)
+            .map(|timeout| {
+                let callback =
+                    OneshotTimerCallback::IdleCallbackTimeout(IdleCallbackTimeoutCallback {
+                        window: Trusted::new(self),
+                        handle,
+                    });
+                self.upcast::<GlobalScope>()
+                    .schedule_callback(callback, MsDuration::new(timeout.into()))
+            });
+
+        // Step 4.
+        self.idle_request_callbacks.borrow_mut().push(IdleRequest {
+            handle,
+            callback,
+            timeout,
+        });
+
+        // Step 6.
+        handle
+    }
+
+    /// <https://w3c.github.io/requestidlecallback/#the-cancelidlecallback-method>
+    fn CancelIdleCallback(&self, handle: u32) {
+        if let Some(request) = self.take_idle_request(handle) {
+            if let Some(timeout) = request.timeout {
+                self.upcast::<GlobalScope>().unschedule_callback(timeout);
+            }
+        }
+    }
+
+    /// <https://wicg.github.io/scheduling-apis/#dom-windoworworkerglobalscope-scheduler>
+    fn Scheduler(&self) -> DomRoot<Scheduler> {
+        self.scheduler.or_init(|| Scheduler::new(self))
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-window-postmessage
     fn PostMessage(
         &self,
@@ -1362,6 +1486,23 @@ impl WindowMethods for Window {
         USVString(self.origin().immutable().ascii_serialization())
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-crossoriginisolated
+    fn CrossOriginIsolated(&self) -> bool {
+        self.upcast::<GlobalScope>().cross_origin_isolated()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-windoworworkerglobalscope-indexeddb
+    fn IndexedDB(&self) -> DomRoot<IDBFactory> {
+        self.indexed_db
+            .or_init(|| IDBFactory::new(self.upcast::<GlobalScope>()))
+    }
+
+    // https://w3c.github.io/ServiceWorker/#self-caches
+    fn Caches(&self) -> DomRoot<CacheStorage> {
+        self.caches
+            .or_init(|| CacheStorage::new(self.upcast::<GlobalScope>()))
+    }
+
     // https://w3c.github.io/selection-api/#dom-window-getselection
     fn GetSelection(&self) -> Option<DomRoot<Selection>> {
         self.document.get().and_then(|d| d.GetSelection())
@@ -1394,6 +1535,89 @@ impl Window {
         current
     }
 
+    /// Whether `requestIdleCallback()` left callbacks for an idle period to run.
+    pub fn has_idle_callbacks(&self) -> bool {
+        !self.idle_request_callbacks.borrow().is_empty() ||
+            !self.runnable_idle_callbacks.borrow().is_empty()
+    }
+
+    /// Removes the idle callback of `handle` from whichever list it is in.
+    fn take_idle_request(&self, handle: u32) -> Option<IdleRequest> {
+        for list in &[&self.idle_request_callbacks, &self.runnable_idle_callbacks] {
+            let mut list = list.borrow_mut();
+            if let Some(index) = list.iter().position(|request| request.handle == handle) {
+                return Some(list.remove(index));
+            }
+        }
+        None
+    }
+
+    /// Starts an idle period that ends at `deadline`, in nanoseconds of
+    /// `time::precise_time_ns`.
+    /// <https://w3c.github.io/requestidlecallback/#start-an-idle-period-algorithm>
+    pub fn start_an_idle_period(&self, deadline: u64) {
+        // Steps 2-4.
+        let pending = mem::replace(&mut *self.idle_request_callbacks.borrow_mut(), vec![]);
+        self.runnable_idle_callbacks.borrow_mut().extend(pending);
+        if self.runnable_idle_callbacks.borrow().is_empty() {
+            return;
+        }
+
+        // Step 5.
+        self.queue_invoke_idle_callbacks(deadline);
+    }
+
+    fn queue_invoke_idle_callbacks(&self, deadline: u64) {
+        let window = Trusted::new(self);
+        let _ = self.task_manager.idle_task_source().queue(
+            task!(invoke_idle_callbacks: move || {
+                window.root().invoke_idle_callbacks(deadline);
+            }),
+            self.upcast(),
+        );
+    }
+
+    /// <https://w3c.github.io/requestidlecallback/#invoke-idle-callbacks-algorithm>
+    fn invoke_idle_callbacks(&self, deadline: u64) {
+        // Callbacks that are left when the deadline is reached wait for the next idle
+        // period that the script thread starts.
+        if time::precise_time_ns() >= deadline {
+            return;
+        }
+        if self.runnable_idle_callbacks.borrow().is_empty() {
+            return;
+        }
+
+        let request = self.runnable_idle_callbacks.borrow_mut().remove(0);
+        if let Some(timeout) = request.timeout {
+            self.upcast::<GlobalScope>().unschedule_callback(timeout);
+        }
+
+        let idle_deadline = IdleDeadline::new(self, deadline, false);
+        let _ = request
+            .callback
+            .Call__(&idle_deadline, ExceptionHandling::Report);
+
+        if !self.runnable_idle_callbacks.borrow().is_empty() {
+            self.queue_invoke_idle_callbacks(deadline);
+        }
+    }
+
+    /// <https://w3c.github.io/requestidlecallback/#invoke-idle-callback-timeout-algorithm>
+    pub fn invoke_idle_callback_timeout(&self, handle: u32) {
+        // Steps 1-2.
+        let request = match self.take_idle_request(handle) {
+            Some(request) => request,
+            None => return,
+        };
+
+        // Steps 3-4.
+        let idle_deadline = IdleDeadline::new(self, time::precise_time_ns(), true);
+        let _ = request
+            .callback
+            .Call__(&idle_deadline, ExceptionHandling::Report);
+    }
+
     /// https://html.spec.whatwg.org/multipage/#window-post-message-steps
     fn post_message_impl(
         &self,
@@ -1715,6 +1939,13 @@ impl Window {
             pending_restyles,
             animation_timeline_value: document.current_animation_timeline_value(),
             animations: document.animations().sets.clone(),
+            top_layer: document
+                .top_layer()
+                .iter()
+                .map(|element| element.upcast::<Node>().to_trusted_node_address())
+                .collect(),
+            caret: editing::caret(&document)
+                .map(|(node, offset)| (node.to_trusted_node_address(), offset)),
         };
 
         match self.layout_chan() {
@@ -1906,6 +2137,14 @@ impl Window {
         rects
     }
 
+    pub fn box_sizes_query(&self, node: &Node) -> Option<BoxSizes> {
+        if !self.layout_reflow(QueryMsg::BoxSizesQuery(node.to_opaque())) {
+            return None;
+        }
+        let BoxSizesResponse(box_sizes) = self.layout_rpc.box_sizes();
+        box_sizes
+    }
+
     pub fn client_rect_query(&self, node: &Node) -> UntypedRect<i32> {
         if !self.layout_reflow(QueryMsg::ClientRectQuery(node.to_opaque())) {
             return Rect::zero();
@@ -2004,6 +2243,35 @@ impl Window {
         self.layout_rpc.text_index()
     }
 
+    /// Returns the caret position in the text of `node` closest to `point`, in the
+    /// coordinates of the initial containing block, as a text node and an offset in bytes
+    /// in its text.
+    #[allow(unsafe_code)]
+    pub fn text_position_query(
+        &self,
+        node: &Node,
+        point: UntypedPoint2D<f32>,
+    ) -> Option<(DomRoot<Node>, usize)> {
+        if !self.layout_reflow(QueryMsg::TextPositionQuery(node.to_opaque(), point)) {
+            return None;
+        }
+        let TextPositionResponse(position) = self.layout_rpc.text_position();
+        let (address, offset) = position?;
+        let js_runtime = self.js_runtime.borrow();
+        let js_runtime = js_runtime.as_ref().unwrap();
+        let node = unsafe { from_untrusted_node_address(js_runtime.rt(), address) };
+        Some((node, offset))
+    }
+
+    /// Returns the rectangle of a caret at `offset`, in bytes, in the text of `node`.
+    pub fn caret_rect_query(&self, node: &Node, offset: usize) -> Option<UntypedRect<Au>> {
+        if !self.layout_reflow(QueryMsg::CaretRectQuery(node.to_opaque(), offset)) {
+            return None;
+        }
+        let CaretRectResponse(rect) = self.layout_rpc.caret_rect();
+        rect
+    }
+
     #[allow(unsafe_code)]
     pub fn init_window_proxy(&self, window_proxy: &WindowProxy) {
         assert!(self.window_proxy.get().is_none());
@@ -2031,9 +2299,15 @@ impl Window {
         &self,
         replace: HistoryEntryReplacement,
         force_reload: bool,
-        load_data: LoadData,
+        mut load_data: LoadData,
     ) {
         let doc = self.Document();
+        // The navigated document keeps the ancestors of the current one.
+        if !self.is_top_level() {
+            load_data.has_trustworthy_ancestor_origin = doc.has_trustworthy_ancestor_origin();
+            load_data.has_only_trustworthy_ancestor_origins =
+                doc.has_only_trustworthy_ancestor_origins();
+        }
         // TODO: Important re security. See https://github.com/servo/servo/issues/23373
         // Step 3: check that the source browsing-context is "allowed to navigate" this window.
         if !force_reload &&
@@ -2258,6 +2532,10 @@ impl Window {
         self.parent_info.is_none()
     }
 
+    pub fn is_secure_context(&self) -> bool {
+        self.is_secure_context
+    }
+
     /// Evaluate media query lists and report changes
     /// <https://drafts.csswg.org/cssom-view/#evaluate-media-queries-and-report-changes>
     pub fn evaluate_media_queries_and_report_changes(&self) {
@@ -2312,6 +2590,12 @@ impl Window {
         self.navigation_start_precise.set(time::precise_time_ns());
     }
 
+    /// <https://html.spec.whatwg.org/multipage/#cannot-show-simple-dialogs>
+    fn cannot_show_simple_dialogs(&self) -> bool {
+        self.Document()
+            .has_active_sandboxing_flag(SandboxingFlagSet::SANDBOXED_MODALS)
+    }
+
     pub fn send_to_embedder(&self, msg: EmbedderMsg) {
         self.send_to_constellation(ScriptMsg::ForwardToEmbedder(msg));
     }
@@ -2376,6 +2660,8 @@ impl Window {
         player_context: WindowGLContext,
         event_loop_waker: Option<Box<dyn EventLoopWaker>>,
         gpu_id_hub: Arc<ParkMutex<Identities>>,
+        cross_origin_isolated: bool,
+        is_secure_context: bool,
     ) -> DomRoot<Self> {
         let layout_rpc: Box<dyn LayoutRPC + Send> = {
             let (rpc_send, rpc_recv) = unbounded();
@@ -2400,6 +2686,7 @@ impl Window {
                 is_headless,
                 user_agent,
                 gpu_id_hub,
+                cross_origin_isolated,
             ),
             script_chan,
             task_manager,
@@ -2412,6 +2699,8 @@ impl Window {
             window_proxy: Default::default(),
             document: Default::default(),
             performance: Default::default(),
+            indexed_db: Default::default(),
+            caches: Default::default(),
             navigation_start: Cell::new(navigation_start),
             navigation_start_precise: Cell::new(navigation_start_precise),
             screen: Default::default(),
@@ -2461,6 +2750,11 @@ impl Window {
             visible: Cell::new(true),
             layout_marker: DomRefCell::new(Rc::new(Cell::new(true))),
             current_event: DomRefCell::new(None),
+            idle_callback_identifier: Cell::new(0),
+            idle_request_callbacks: Default::default(),
+            runnable_idle_callbacks: Default::default(),
+            scheduler: Default::default(),
+            is_secure_context,
         });
 
         unsafe { WindowBinding::Wrap(JSContext::from_ptr(runtime.cx()), win) }
@@ -2549,6 +2843,9 @@ fn debug_reflow_events(id: PipelineId, reflow_goal: &ReflowGoal, reason: &Reflow
             &QueryMsg::TextIndexQuery(..) => "\tTextIndexQuery",
             &QueryMsg::ElementInnerTextQuery(_) => "\tElementInnerTextQuery",
             &QueryMsg::InnerWindowDimensionsQuery(_) => "\tInnerWindowDimensionsQuery",
+            &QueryMsg::BoxSizesQuery(_n) => "\tBoxSizesQuery",
+            &QueryMsg::TextPositionQuery(..) => "\tTextPositionQuery",
+            &QueryMsg::CaretRectQuery(..) => "\tCaretRectQuery",
         },
     };
 
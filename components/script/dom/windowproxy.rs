@@ -3,6 +3,7 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
 use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
 use crate::dom::bindings::conversions::{root_from_handleobject, ToJSValConvertible};
 use crate::dom::bindings::error::{throw_dom_exception, Error, Fallible};
 use crate::dom::bindings::inheritance::Castable;
@@ -20,6 +21,7 @@ use crate::dom::window::Window;
 use crate::realms::{enter_realm, AlreadyInRealm, InRealm};
 use crate::script_runtime::JSContext as SafeJSContext;
 use crate::script_thread::ScriptThread;
+use crate::security_manager::SandboxingFlagSet;
 use dom_struct::dom_struct;
 use embedder_traits::EmbedderMsg;
 use indexmap::map::IndexMap;
@@ -287,6 +289,14 @@ impl WindowProxy {
             .and_then(|id| ScriptThread::find_document(id))
             .and_then(|doc| Some(DomRoot::from_ref(doc.window())))
             .unwrap();
+        // https://html.spec.whatwg.org/multipage/#sandboxed-auxiliary-navigation-browsing-context-flag
+        if window
+            .Document()
+            .has_active_sandboxing_flag(SandboxingFlagSet::SANDBOXED_AUXILIARY_NAVIGATION)
+        {
+            warn!("Blocked opening a popup from a sandboxed document");
+            return None;
+        }
         let msg = EmbedderMsg::AllowOpeningBrowser(chan);
         window.send_to_embedder(msg);
         if port.recv().unwrap() {
@@ -6,6 +6,7 @@ use crate::dom::bindings::cell::{DomRefCell, Ref};
 use crate::dom::bindings::codegen::Bindings::ImageBitmapBinding::{
     ImageBitmapOptions, ImageBitmapSource,
 };
+use crate::dom::bindings::codegen::Bindings::MessagePortBinding::StructuredSerializeOptions;
 use crate::dom::bindings::codegen::Bindings::RequestBinding::RequestInit;
 use crate::dom::bindings::codegen::Bindings::VoidFunctionBinding::VoidFunction;
 use crate::dom::bindings::codegen::Bindings::WorkerBinding::WorkerType;
@@ -18,13 +19,16 @@ use crate::dom::bindings::root::{DomRoot, MutNullableDom};
 use crate::dom::bindings::settings_stack::AutoEntryScript;
 use crate::dom::bindings::str::{DOMString, USVString};
 use crate::dom::bindings::trace::RootedTraceableBox;
+use crate::dom::cachestorage::CacheStorage;
 use crate::dom::crypto::Crypto;
 use crate::dom::dedicatedworkerglobalscope::DedicatedWorkerGlobalScope;
 use crate::dom::globalscope::GlobalScope;
+use crate::dom::idbfactory::IDBFactory;
 use crate::dom::identityhub::Identities;
 use crate::dom::performance::Performance;
 use crate::dom::promise::Promise;
 use crate::dom::serviceworkerglobalscope::ServiceWorkerGlobalScope;
+use crate::dom::sharedworkerglobalscope::SharedWorkerGlobalScope;
 use crate::dom::window::{base64_atob, base64_btoa};
 use crate::dom::workerlocation::WorkerLocation;
 use crate::dom::workernavigator::WorkerNavigator;
@@ -46,7 +50,7 @@ use crossbeam_channel::Receiver;
 use devtools_traits::{DevtoolScriptControlMsg, WorkerId};
 use dom_struct::dom_struct;
 use ipc_channel::ipc::IpcSender;
-use js::jsval::UndefinedValue;
+use js::jsval::{JSVal, UndefinedValue};
 use js::panic::maybe_resume_unwind;
 use js::rust::{HandleValue, ParentRuntime};
 use msg::constellation_msg::{PipelineId, PipelineNamespace};
@@ -82,6 +86,7 @@ pub fn prepare_workerscope_init(
         origin: global.origin().immutable().clone(),
         is_headless: global.is_headless(),
         user_agent: global.get_user_agent(),
+        cross_origin_isolated: global.cross_origin_isolated(),
     };
 
     init
@@ -116,6 +121,8 @@ pub struct WorkerGlobalScope {
 
     navigation_start_precise: u64,
     performance: MutNullableDom<Performance>,
+    indexed_db: MutNullableDom<IDBFactory>,
+    caches: MutNullableDom<CacheStorage>,
 }
 
 impl WorkerGlobalScope {
@@ -145,6 +152,7 @@ impl WorkerGlobalScope {
                 init.is_headless,
                 init.user_agent,
                 gpu_id_hub,
+                init.cross_origin_isolated,
             ),
             worker_id: init.worker_id,
             worker_name,
@@ -158,6 +166,8 @@ impl WorkerGlobalScope {
             from_devtools_receiver,
             navigation_start_precise: precise_time_ns(),
             performance: Default::default(),
+            indexed_db: Default::default(),
+            caches: Default::default(),
         }
     }
 
@@ -208,6 +218,11 @@ impl WorkerGlobalScope {
         self.worker_id.clone()
     }
 
+    /// <https://html.spec.whatwg.org/multipage/#concept-workerglobalscope-name>
+    pub fn worker_name(&self) -> DOMString {
+        self.worker_name.clone()
+    }
+
     pub fn task_canceller(&self) -> TaskCanceller {
         TaskCanceller {
             cancelled: self.closing.clone(),
@@ -365,6 +380,17 @@ impl WorkerGlobalScopeMethods for WorkerGlobalScope {
             .queue_function_as_microtask(callback);
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-structuredclone
+    fn StructuredClone(
+        &self,
+        cx: JSContext,
+        value: HandleValue,
+        options: RootedTraceableBox<StructuredSerializeOptions>,
+    ) -> Fallible<JSVal> {
+        self.upcast::<GlobalScope>()
+            .structured_clone(cx, value, options)
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-createimagebitmap
     fn CreateImageBitmap(
         &self,
@@ -405,6 +431,23 @@ impl WorkerGlobalScopeMethods for WorkerGlobalScope {
                 .ascii_serialization(),
         )
     }
+
+    // https://html.spec.whatwg.org/multipage/#dom-crossoriginisolated
+    fn CrossOriginIsolated(&self) -> bool {
+        self.upcast::<GlobalScope>().cross_origin_isolated()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-windoworworkerglobalscope-indexeddb
+    fn IndexedDB(&self) -> DomRoot<IDBFactory> {
+        self.indexed_db
+            .or_init(|| IDBFactory::new(self.upcast::<GlobalScope>()))
+    }
+
+    // https://w3c.github.io/ServiceWorker/#self-caches
+    fn Caches(&self) -> DomRoot<CacheStorage> {
+        self.caches
+            .or_init(|| CacheStorage::new(self.upcast::<GlobalScope>()))
+    }
 }
 
 impl WorkerGlobalScope {
@@ -440,12 +483,15 @@ impl WorkerGlobalScope {
     pub fn script_chan(&self) -> Box<dyn ScriptChan + Send> {
         let dedicated = self.downcast::<DedicatedWorkerGlobalScope>();
         let service_worker = self.downcast::<ServiceWorkerGlobalScope>();
+        let shared_worker = self.downcast::<SharedWorkerGlobalScope>();
         if let Some(dedicated) = dedicated {
             return dedicated.script_chan();
         } else if let Some(service_worker) = service_worker {
             return service_worker.script_chan();
+        } else if let Some(shared_worker) = shared_worker {
+            return shared_worker.script_chan();
         } else {
-            panic!("need to implement a sender for SharedWorker")
+            unreachable!()
         }
     }
 
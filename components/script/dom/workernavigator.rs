@@ -8,6 +8,7 @@ use crate::dom::bindings::root::{DomRoot, MutNullableDom};
 use crate::dom::bindings::str::DOMString;
 use crate::dom::bindings::utils::to_frozen_array;
 use crate::dom::gpu::GPU;
+use crate::dom::lockmanager::LockManager;
 use crate::dom::navigatorinfo;
 use crate::dom::permissions::Permissions;
 use crate::dom::workerglobalscope::WorkerGlobalScope;
@@ -105,6 +106,11 @@ impl WorkerNavigatorMethods for WorkerNavigator {
             .or_init(|| Permissions::new(&self.global()))
     }
 
+    // https://w3c.github.io/web-locks/#dom-navigatorlocks-locks
+    fn Locks(&self) -> DomRoot<LockManager> {
+        self.global().lock_manager()
+    }
+
     // https://gpuweb.github.io/gpuweb/#dom-navigator-gpu
     fn Gpu(&self) -> DomRoot<GPU> {
         self.gpu.or_init(|| GPU::new(&self.global()))
@@ -75,6 +75,7 @@ impl WorkletGlobalScope {
                 init.is_headless,
                 init.user_agent.clone(),
                 init.gpu_id_hub.clone(),
+                false,
             ),
             base_url,
             to_script_thread_sender: init.to_script_thread_sender.clone(),
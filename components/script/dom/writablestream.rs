@@ -0,0 +1,549 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::QueuingStrategyBinding::{
+    QueuingStrategy, QueuingStrategySize,
+};
+use crate::dom::bindings::codegen::Bindings::WritableStreamBinding::{
+    UnderlyingSink, WritableStreamMethods,
+};
+use crate::dom::bindings::conversions::ConversionResult;
+use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{DomRoot, MutNullableDom};
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::promise::Promise;
+use crate::dom::promisenativehandler::{Callback, PromiseNativeHandler};
+use crate::dom::writablestreamdefaultcontroller::{
+    UnderlyingSinkType, WritableStreamDefaultController,
+};
+use crate::dom::writablestreamdefaultwriter::WritableStreamDefaultWriter;
+use crate::realms::{enter_realm, InRealm};
+use crate::script_runtime::JSContext;
+use dom_struct::dom_struct;
+use js::jsapi::{Heap, JSObject};
+use js::jsval::{JSVal, NullValue, ObjectValue, UndefinedValue};
+use js::rust::HandleValue;
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+/// <https://streams.spec.whatwg.org/#writablestream-state>
+#[derive(Clone, Copy, Debug, JSTraceable, MallocSizeOf, PartialEq)]
+pub enum WritableStreamState {
+    Writable,
+    Closed,
+    Erroring,
+    Errored,
+}
+
+// https://streams.spec.whatwg.org/#ws-class
+#[dom_struct]
+pub struct WritableStream {
+    reflector_: Reflector,
+    /// <https://streams.spec.whatwg.org/#writablestream-state>
+    state: Cell<WritableStreamState>,
+    /// <https://streams.spec.whatwg.org/#writablestream-storederror>
+    #[ignore_malloc_size_of = "Defined in rust-mozjs"]
+    stored_error: Heap<JSVal>,
+    /// <https://streams.spec.whatwg.org/#writablestream-writer>
+    writer: MutNullableDom<WritableStreamDefaultWriter>,
+    /// <https://streams.spec.whatwg.org/#writablestream-controller>
+    controller: MutNullableDom<WritableStreamDefaultController>,
+    /// <https://streams.spec.whatwg.org/#writablestream-inflightwriterequest>
+    #[ignore_malloc_size_of = "Rc"]
+    in_flight_write_request: DomRefCell<Option<Rc<Promise>>>,
+    /// <https://streams.spec.whatwg.org/#writablestream-inflightcloserequest>
+    #[ignore_malloc_size_of = "Rc"]
+    in_flight_close_request: DomRefCell<Option<Rc<Promise>>>,
+    /// <https://streams.spec.whatwg.org/#writablestream-closerequest>
+    #[ignore_malloc_size_of = "Rc"]
+    close_request: DomRefCell<Option<Rc<Promise>>>,
+    /// The promise and the "was already erroring" flag of
+    /// <https://streams.spec.whatwg.org/#writablestream-pendingabortrequest>.
+    #[ignore_malloc_size_of = "Rc"]
+    pending_abort_request: DomRefCell<Option<(Rc<Promise>, bool)>>,
+    /// The reason of the pending abort request.
+    #[ignore_malloc_size_of = "Defined in rust-mozjs"]
+    pending_abort_reason: Heap<JSVal>,
+    /// <https://streams.spec.whatwg.org/#writablestream-writerequests>
+    #[ignore_malloc_size_of = "Rc"]
+    write_requests: DomRefCell<VecDeque<Rc<Promise>>>,
+    /// <https://streams.spec.whatwg.org/#writablestream-backpressure>
+    backpressure: Cell<bool>,
+}
+
+impl WritableStream {
+    fn new_inherited() -> WritableStream {
+        WritableStream {
+            reflector_: Reflector::new(),
+            state: Cell::new(WritableStreamState::Writable),
+            stored_error: Heap::default(),
+            writer: Default::default(),
+            controller: Default::default(),
+            in_flight_write_request: Default::default(),
+            in_flight_close_request: Default::default(),
+            close_request: Default::default(),
+            pending_abort_request: Default::default(),
+            pending_abort_reason: Heap::default(),
+            write_requests: Default::default(),
+            backpressure: Cell::new(false),
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#initialize-writable-stream>
+    pub fn new(global: &GlobalScope) -> DomRoot<WritableStream> {
+        reflect_dom_object(Box::new(WritableStream::new_inherited()), global)
+    }
+
+    /// <https://streams.spec.whatwg.org/#create-writable-stream>
+    #[allow(unrooted_must_root)]
+    pub fn new_with_sink(
+        global: &GlobalScope,
+        sink: UnderlyingSinkType,
+        high_water_mark: f64,
+        size: Option<Rc<QueuingStrategySize>>,
+    ) -> Fallible<DomRoot<WritableStream>> {
+        let stream = WritableStream::new(global);
+        let controller = WritableStreamDefaultController::new(global, sink);
+        controller.setup(&stream, high_water_mark, size)?;
+        Ok(stream)
+    }
+
+    // https://streams.spec.whatwg.org/#ws-constructor
+    #[allow(non_snake_case, unsafe_code)]
+    pub fn Constructor(
+        cx: JSContext,
+        global: &GlobalScope,
+        underlying_sink: Option<*mut JSObject>,
+        strategy: &QueuingStrategy,
+    ) -> Fallible<DomRoot<WritableStream>> {
+        // Steps 1-2.
+        rooted!(in(*cx) let mut sink_object = NullValue());
+        if let Some(object) = underlying_sink {
+            sink_object.set(ObjectValue(object));
+        }
+        let sink_dict = match unsafe { UnderlyingSink::new(cx, sink_object.handle()) } {
+            Ok(ConversionResult::Success(dict)) => dict,
+            Ok(ConversionResult::Failure(error)) => return Err(Error::Type(error.into_owned())),
+            Err(()) => return Err(Error::JSFailed),
+        };
+        // Steps 5-6.
+        let high_water_mark = extract_high_water_mark(strategy, 1.)?;
+        // Step 7.
+        let stream = WritableStream::new(global);
+        let controller =
+            WritableStreamDefaultController::new(global, UnderlyingSinkType::new_js(sink_dict));
+        if let Some(object) = underlying_sink {
+            controller.set_sink_object(object);
+        }
+        controller.setup(&stream, high_water_mark, strategy.size.clone())?;
+        Ok(stream)
+    }
+
+    pub fn state(&self) -> WritableStreamState {
+        self.state.get()
+    }
+
+    pub fn stored_error(&self) -> JSVal {
+        self.stored_error.get()
+    }
+
+    pub fn backpressure(&self) -> bool {
+        self.backpressure.get()
+    }
+
+    pub fn controller(&self) -> DomRoot<WritableStreamDefaultController> {
+        self.controller
+            .get()
+            .expect("A writable stream always has a controller once set up")
+    }
+
+    pub fn set_controller(&self, controller: &WritableStreamDefaultController) {
+        self.controller.set(Some(controller));
+    }
+
+    pub fn writer(&self) -> Option<DomRoot<WritableStreamDefaultWriter>> {
+        self.writer.get()
+    }
+
+    pub fn set_writer(&self, writer: Option<&WritableStreamDefaultWriter>) {
+        self.writer.set(writer);
+    }
+
+    /// <https://streams.spec.whatwg.org/#is-writable-stream-locked>
+    pub fn is_locked(&self) -> bool {
+        self.writer.get().is_some()
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-abort>
+    pub fn abort(&self, reason: HandleValue) -> Rc<Promise> {
+        let global = self.global();
+        let _ac = enter_realm(&*global);
+        let cx = global.get_cx();
+        // Step 1.
+        if let WritableStreamState::Closed | WritableStreamState::Errored = self.state.get() {
+            return resolved_promise(&global);
+        }
+        // Step 2.
+        self.controller().signal().signal_abort(reason);
+        // Steps 3-4.
+        if let WritableStreamState::Closed | WritableStreamState::Errored = self.state.get() {
+            return resolved_promise(&global);
+        }
+        // Step 5.
+        if let Some((ref promise, _)) = *self.pending_abort_request.borrow() {
+            return promise.clone();
+        }
+        // Steps 6-8.
+        let was_already_erroring = self.state.get() == WritableStreamState::Erroring;
+        rooted!(in(*cx) let mut reason = reason.get());
+        if was_already_erroring {
+            reason.set(UndefinedValue());
+        }
+        // Steps 9-10.
+        let promise = Promise::new(&global);
+        *self.pending_abort_request.borrow_mut() = Some((promise.clone(), was_already_erroring));
+        self.pending_abort_reason.set(reason.get());
+        // Step 11.
+        if !was_already_erroring {
+            self.start_erroring(reason.handle());
+        }
+        // Step 12.
+        promise
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-close>
+    pub fn close(&self) -> Rc<Promise> {
+        let global = self.global();
+        let _ac = enter_realm(&*global);
+        // Steps 1-2.
+        if let WritableStreamState::Closed | WritableStreamState::Errored = self.state.get() {
+            let promise = Promise::new(&global);
+            promise.reject_error(Error::Type("The stream is closed or errored".to_owned()));
+            return promise;
+        }
+        // Steps 3-4.
+        assert!(!self.close_queued_or_in_flight());
+        // Steps 5-6.
+        let promise = Promise::new(&global);
+        *self.close_request.borrow_mut() = Some(promise.clone());
+        // Steps 7-8.
+        if let Some(writer) = self.writer.get() {
+            if self.backpressure.get() && self.state.get() == WritableStreamState::Writable {
+                writer.resolve_ready_promise();
+            }
+        }
+        // Step 9.
+        self.controller().close();
+        // Step 10.
+        promise
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-add-write-request>
+    pub fn add_write_request(&self) -> Rc<Promise> {
+        assert!(self.is_locked());
+        assert_eq!(self.state.get(), WritableStreamState::Writable);
+        let promise = Promise::new(&self.global());
+        self.write_requests.borrow_mut().push_back(promise.clone());
+        promise
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-deal-with-rejection>
+    pub fn deal_with_rejection(&self, error: HandleValue) {
+        if self.state.get() == WritableStreamState::Writable {
+            return self.start_erroring(error);
+        }
+        assert_eq!(self.state.get(), WritableStreamState::Erroring);
+        self.finish_erroring();
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-start-erroring>
+    pub fn start_erroring(&self, reason: HandleValue) {
+        // Steps 1-4.
+        assert!(self.stored_error.get().is_undefined());
+        assert_eq!(self.state.get(), WritableStreamState::Writable);
+        let controller = self.controller();
+        // Steps 5-6.
+        self.state.set(WritableStreamState::Erroring);
+        self.stored_error.set(reason.get());
+        // Steps 7-8.
+        if let Some(writer) = self.writer.get() {
+            writer.ensure_ready_promise_rejected(reason);
+        }
+        // Step 9.
+        if !self.has_operation_marked_in_flight() && controller.started() {
+            self.finish_erroring();
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-finish-erroring>
+    pub fn finish_erroring(&self) {
+        let global = self.global();
+        let _ac = enter_realm(&*global);
+        let cx = global.get_cx();
+        // Steps 1-3.
+        assert_eq!(self.state.get(), WritableStreamState::Erroring);
+        assert!(!self.has_operation_marked_in_flight());
+        self.state.set(WritableStreamState::Errored);
+        // Step 4.
+        let controller = self.controller();
+        controller.error_steps();
+        // Steps 5-7.
+        rooted!(in(*cx) let stored_error = self.stored_error.get());
+        let write_requests: Vec<_> = self.write_requests.borrow_mut().drain(..).collect();
+        for request in write_requests {
+            request.reject(cx, stored_error.handle());
+        }
+        // Steps 8-9.
+        let (promise, was_already_erroring) = match self.pending_abort_request.borrow_mut().take() {
+            Some(request) => request,
+            None => return self.reject_close_and_closed_promise_if_needed(),
+        };
+        rooted!(in(*cx) let reason = self.pending_abort_reason.get());
+        self.pending_abort_reason.set(UndefinedValue());
+        // Step 11.
+        if was_already_erroring {
+            promise.reject(cx, stored_error.handle());
+            return self.reject_close_and_closed_promise_if_needed();
+        }
+        // Step 12.
+        let abort_promise = controller.abort_steps(reason.handle());
+        // Steps 13-14.
+        let handler = PromiseNativeHandler::new(
+            &global,
+            Some(Box::new(AbortRequestHandler {
+                stream: DomRoot::from_ref(self),
+                promise: promise.clone(),
+                fulfilled: true,
+            })),
+            Some(Box::new(AbortRequestHandler {
+                stream: DomRoot::from_ref(self),
+                promise,
+                fulfilled: false,
+            })),
+        );
+        let realm = enter_realm(&*global);
+        abort_promise.append_native_handler(&handler, InRealm::Entered(&realm));
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-finish-in-flight-write>
+    pub fn finish_in_flight_write(&self) {
+        let request = self
+            .in_flight_write_request
+            .borrow_mut()
+            .take()
+            .expect("No write request in flight");
+        request.resolve_native(&());
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-finish-in-flight-write-with-error>
+    pub fn finish_in_flight_write_with_error(&self, error: HandleValue) {
+        let request = self
+            .in_flight_write_request
+            .borrow_mut()
+            .take()
+            .expect("No write request in flight");
+        let _ac = enter_realm(&*request);
+        request.reject(self.global().get_cx(), error);
+        assert!(matches!(
+            self.state.get(),
+            WritableStreamState::Writable | WritableStreamState::Erroring
+        ));
+        self.deal_with_rejection(error);
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-finish-in-flight-close>
+    pub fn finish_in_flight_close(&self) {
+        // Steps 1-2.
+        let request = self
+            .in_flight_close_request
+            .borrow_mut()
+            .take()
+            .expect("No close request in flight");
+        request.resolve_native(&());
+        // Steps 3-5.
+        if self.state.get() == WritableStreamState::Erroring {
+            self.stored_error.set(UndefinedValue());
+            if let Some((promise, _)) = self.pending_abort_request.borrow_mut().take() {
+                promise.resolve_native(&());
+                self.pending_abort_reason.set(UndefinedValue());
+            }
+        }
+        // Step 6.
+        self.state.set(WritableStreamState::Closed);
+        // Step 7.
+        if let Some(writer) = self.writer.get() {
+            writer.resolve_closed_promise();
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-finish-in-flight-close-with-error>
+    pub fn finish_in_flight_close_with_error(&self, error: HandleValue) {
+        let global = self.global();
+        let _ac = enter_realm(&*global);
+        let cx = global.get_cx();
+        // Steps 1-2.
+        let request = self
+            .in_flight_close_request
+            .borrow_mut()
+            .take()
+            .expect("No close request in flight");
+        request.reject(cx, error);
+        // Steps 3-4.
+        assert!(matches!(
+            self.state.get(),
+            WritableStreamState::Writable | WritableStreamState::Erroring
+        ));
+        // Step 5.
+        if let Some((promise, _)) = self.pending_abort_request.borrow_mut().take() {
+            promise.reject(cx, error);
+            self.pending_abort_reason.set(UndefinedValue());
+        }
+        // Step 6.
+        self.deal_with_rejection(error);
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-close-queued-or-in-flight>
+    pub fn close_queued_or_in_flight(&self) -> bool {
+        self.close_request.borrow().is_some() || self.in_flight_close_request.borrow().is_some()
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-has-operation-marked-in-flight>
+    fn has_operation_marked_in_flight(&self) -> bool {
+        self.in_flight_write_request.borrow().is_some() ||
+            self.in_flight_close_request.borrow().is_some()
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-mark-close-request-in-flight>
+    pub fn mark_close_request_in_flight(&self) {
+        let request = self.close_request.borrow_mut().take();
+        assert!(request.is_some());
+        *self.in_flight_close_request.borrow_mut() = request;
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-mark-first-write-request-in-flight>
+    pub fn mark_first_write_request_in_flight(&self) {
+        let request = self.write_requests.borrow_mut().pop_front();
+        assert!(request.is_some());
+        *self.in_flight_write_request.borrow_mut() = request;
+    }
+
+    pub fn has_in_flight_write_request(&self) -> bool {
+        self.in_flight_write_request.borrow().is_some()
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-reject-close-and-closed-promise-if-needed>
+    fn reject_close_and_closed_promise_if_needed(&self) {
+        let global = self.global();
+        let _ac = enter_realm(&*global);
+        let cx = global.get_cx();
+        // Step 1.
+        assert_eq!(self.state.get(), WritableStreamState::Errored);
+        rooted!(in(*cx) let stored_error = self.stored_error.get());
+        // Step 2.
+        if let Some(request) = self.close_request.borrow_mut().take() {
+            assert!(self.in_flight_close_request.borrow().is_none());
+            request.reject(cx, stored_error.handle());
+        }
+        // Steps 3-4.
+        if let Some(writer) = self.writer.get() {
+            writer.reject_closed_promise(stored_error.handle());
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-update-backpressure>
+    pub fn update_backpressure(&self, backpressure: bool) {
+        // Steps 1-2.
+        assert_eq!(self.state.get(), WritableStreamState::Writable);
+        assert!(!self.close_queued_or_in_flight());
+        // Steps 3-4.
+        if let Some(writer) = self.writer.get() {
+            if backpressure != self.backpressure.get() {
+                if backpressure {
+                    writer.reset_ready_promise();
+                } else {
+                    writer.resolve_ready_promise();
+                }
+            }
+        }
+        // Step 5.
+        self.backpressure.set(backpressure);
+    }
+}
+
+impl WritableStreamMethods for WritableStream {
+    // https://streams.spec.whatwg.org/#ws-locked
+    fn Locked(&self) -> bool {
+        self.is_locked()
+    }
+
+    // https://streams.spec.whatwg.org/#ws-abort
+    fn Abort(&self, _cx: JSContext, reason: HandleValue) -> Rc<Promise> {
+        if self.is_locked() {
+            let promise = Promise::new(&self.global());
+            promise.reject_error(Error::Type("The stream is locked".to_owned()));
+            return promise;
+        }
+        self.abort(reason)
+    }
+
+    // https://streams.spec.whatwg.org/#ws-close
+    fn Close(&self) -> Rc<Promise> {
+        let error = if self.is_locked() {
+            "The stream is locked"
+        } else if self.close_queued_or_in_flight() {
+            "The stream is already closing"
+        } else {
+            return self.close();
+        };
+        let promise = Promise::new(&self.global());
+        promise.reject_error(Error::Type(error.to_owned()));
+        promise
+    }
+
+    // https://streams.spec.whatwg.org/#ws-get-writer
+    fn GetWriter(&self) -> Fallible<DomRoot<WritableStreamDefaultWriter>> {
+        WritableStreamDefaultWriter::new(&self.global(), self)
+    }
+}
+
+/// Returns a new promise resolved with undefined.
+pub fn resolved_promise(global: &GlobalScope) -> Rc<Promise> {
+    let promise = Promise::new(global);
+    promise.resolve_native(&());
+    promise
+}
+
+/// <https://streams.spec.whatwg.org/#validate-and-normalize-high-water-mark>
+pub fn extract_high_water_mark(strategy: &QueuingStrategy, default: f64) -> Fallible<f64> {
+    match strategy.highWaterMark {
+        None => Ok(default),
+        Some(high_water_mark) if high_water_mark.is_nan() || high_water_mark < 0. => Err(
+            Error::Range("The high water mark must be a non-negative number".to_owned()),
+        ),
+        Some(high_water_mark) => Ok(high_water_mark),
+    }
+}
+
+/// Steps 13-14 of <https://streams.spec.whatwg.org/#writable-stream-finish-erroring>.
+#[derive(JSTraceable, MallocSizeOf)]
+struct AbortRequestHandler {
+    stream: DomRoot<WritableStream>,
+    #[ignore_malloc_size_of = "Rc"]
+    promise: Rc<Promise>,
+    fulfilled: bool,
+}
+
+impl Callback for AbortRequestHandler {
+    fn callback(&self, cx: JSContext, v: HandleValue, _realm: InRealm) {
+        if self.fulfilled {
+            self.promise.resolve_native(&());
+        } else {
+            self.promise.reject(cx, v);
+        }
+        self.stream.reject_close_and_closed_promise_if_needed();
+    }
+}
@@ -0,0 +1,593 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::abortcontroller::AbortController;
+use crate::dom::abortsignal::AbortSignal;
+use crate::dom::bindings::callback::ExceptionHandling;
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::AbortControllerBinding::AbortControllerMethods;
+use crate::dom::bindings::codegen::Bindings::QueuingStrategyBinding::QueuingStrategySize;
+use crate::dom::bindings::codegen::Bindings::WritableStreamBinding::{
+    UnderlyingSink, UnderlyingSinkAbortCallback, UnderlyingSinkCloseCallback,
+    UnderlyingSinkStartCallback, UnderlyingSinkWriteCallback,
+};
+use crate::dom::bindings::codegen::Bindings::WritableStreamDefaultControllerBinding::WritableStreamDefaultControllerMethods;
+use crate::dom::bindings::error::{take_exception, Error, Fallible};
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot, MutNullableDom};
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::promise::Promise;
+use crate::dom::promisenativehandler::{Callback, PromiseNativeHandler};
+use crate::dom::transformstream::TransformStream;
+use crate::dom::writablestream::{resolved_promise, WritableStream, WritableStreamState};
+use crate::realms::{enter_realm, InRealm};
+use crate::script_runtime::JSContext;
+use dom_struct::dom_struct;
+use js::jsapi::{Heap, JSObject};
+use js::jsval::{JSVal, UndefinedValue};
+use js::rust::HandleValue;
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+/// The algorithms of a writable stream controller, given by its underlying sink.
+#[derive(JSTraceable, MallocSizeOf)]
+#[unrooted_must_root_lint::must_root]
+pub enum UnderlyingSinkType {
+    /// An underlying sink given to the `WritableStream` constructor.
+    /// <https://streams.spec.whatwg.org/#set-up-writable-stream-default-controller-from-underlying-sink>
+    Js {
+        #[ignore_malloc_size_of = "Defined in rust-mozjs"]
+        object: Heap<*mut JSObject>,
+        #[ignore_malloc_size_of = "Rc"]
+        start: Option<Rc<UnderlyingSinkStartCallback>>,
+        #[ignore_malloc_size_of = "Rc"]
+        write: Option<Rc<UnderlyingSinkWriteCallback>>,
+        #[ignore_malloc_size_of = "Rc"]
+        close: Option<Rc<UnderlyingSinkCloseCallback>>,
+        #[ignore_malloc_size_of = "Rc"]
+        abort: Option<Rc<UnderlyingSinkAbortCallback>>,
+    },
+    /// The writable side of a transform stream.
+    /// <https://streams.spec.whatwg.org/#initialize-transform-stream>
+    Transform(Dom<TransformStream>),
+}
+
+impl UnderlyingSinkType {
+    pub fn new_js(sink: UnderlyingSink) -> UnderlyingSinkType {
+        UnderlyingSinkType::Js {
+            object: Heap::default(),
+            start: sink.start,
+            write: sink.write,
+            close: sink.close,
+            abort: sink.abort,
+        }
+    }
+}
+
+/// A value in the queue of a controller.
+/// <https://streams.spec.whatwg.org/#value-with-size>
+#[derive(JSTraceable, MallocSizeOf)]
+enum QueueEntry {
+    Chunk(
+        #[ignore_malloc_size_of = "Defined in rust-mozjs"] Box<Heap<JSVal>>,
+        f64,
+    ),
+    /// <https://streams.spec.whatwg.org/#close-sentinel>
+    Close,
+}
+
+// https://streams.spec.whatwg.org/#ws-default-controller-class
+#[dom_struct]
+pub struct WritableStreamDefaultController {
+    reflector_: Reflector,
+    sink: UnderlyingSinkType,
+    /// <https://streams.spec.whatwg.org/#writablestreamdefaultcontroller-stream>
+    stream: MutNullableDom<WritableStream>,
+    /// <https://streams.spec.whatwg.org/#writablestreamdefaultcontroller-queue>
+    queue: DomRefCell<VecDeque<QueueEntry>>,
+    /// <https://streams.spec.whatwg.org/#writablestreamdefaultcontroller-queuetotalsize>
+    queue_total_size: Cell<f64>,
+    /// <https://streams.spec.whatwg.org/#writablestreamdefaultcontroller-started>
+    started: Cell<bool>,
+    /// <https://streams.spec.whatwg.org/#writablestreamdefaultcontroller-strategyhwm>
+    strategy_hwm: Cell<f64>,
+    /// <https://streams.spec.whatwg.org/#writablestreamdefaultcontroller-strategysizealgorithm>
+    #[ignore_malloc_size_of = "Rc"]
+    strategy_size: DomRefCell<Option<Rc<QueuingStrategySize>>>,
+    /// Set when the algorithms are cleared, after which the sink is never called.
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-clear-algorithms>
+    algorithms_cleared: Cell<bool>,
+    /// <https://streams.spec.whatwg.org/#writablestreamdefaultcontroller-abortcontroller>
+    abort_controller: Dom<AbortController>,
+}
+
+impl WritableStreamDefaultController {
+    #[allow(unrooted_must_root)]
+    fn new_inherited(
+        sink: UnderlyingSinkType,
+        abort_controller: &AbortController,
+    ) -> WritableStreamDefaultController {
+        WritableStreamDefaultController {
+            reflector_: Reflector::new(),
+            sink,
+            stream: Default::default(),
+            queue: Default::default(),
+            queue_total_size: Cell::new(0.),
+            started: Cell::new(false),
+            strategy_hwm: Cell::new(1.),
+            strategy_size: Default::default(),
+            algorithms_cleared: Cell::new(false),
+            abort_controller: Dom::from_ref(abort_controller),
+        }
+    }
+
+    #[allow(unrooted_must_root)]
+    pub fn new(
+        global: &GlobalScope,
+        sink: UnderlyingSinkType,
+    ) -> DomRoot<WritableStreamDefaultController> {
+        let abort_controller = AbortController::new(global);
+        reflect_dom_object(
+            Box::new(WritableStreamDefaultController::new_inherited(
+                sink,
+                &abort_controller,
+            )),
+            global,
+        )
+    }
+
+    /// Sets the object the callbacks of a JS underlying sink are called on.
+    pub fn set_sink_object(&self, sink_object: *mut JSObject) {
+        if let UnderlyingSinkType::Js { ref object, .. } = self.sink {
+            object.set(sink_object);
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#set-up-writable-stream-default-controller>
+    pub fn setup(
+        &self,
+        stream: &WritableStream,
+        high_water_mark: f64,
+        size: Option<Rc<QueuingStrategySize>>,
+    ) -> Fallible<()> {
+        let global = self.global();
+        // Steps 1-2.
+        self.stream.set(Some(stream));
+        stream.set_controller(self);
+        // Steps 3-10.
+        self.reset_queue();
+        self.strategy_hwm.set(high_water_mark);
+        *self.strategy_size.borrow_mut() = size;
+        // Steps 11-12.
+        stream.update_backpressure(self.get_backpressure());
+        // Steps 13-14.
+        let start_promise = self.start_algorithm()?;
+        // Steps 15-16.
+        self.react(
+            &global,
+            &start_promise,
+            ControllerReaction::StartFulfilled,
+            ControllerReaction::StartRejected,
+        );
+        Ok(())
+    }
+
+    pub fn started(&self) -> bool {
+        self.started.get()
+    }
+
+    pub fn signal(&self) -> DomRoot<AbortSignal> {
+        self.abort_controller.Signal()
+    }
+
+    fn stream(&self) -> DomRoot<WritableStream> {
+        self.stream
+            .get()
+            .expect("The controller was set up with a stream")
+    }
+
+    fn react(
+        &self,
+        global: &GlobalScope,
+        promise: &Promise,
+        fulfilled: ControllerReaction,
+        rejected: ControllerReaction,
+    ) {
+        let handler = PromiseNativeHandler::new(
+            global,
+            Some(Box::new(ControllerReactionHandler {
+                controller: DomRoot::from_ref(self),
+                reaction: fulfilled,
+            })),
+            Some(Box::new(ControllerReactionHandler {
+                controller: DomRoot::from_ref(self),
+                reaction: rejected,
+            })),
+        );
+        let realm = enter_realm(global);
+        promise.append_native_handler(&handler, InRealm::Entered(&realm));
+    }
+
+    /// The start algorithm, which returns a promise for its result.
+    #[allow(unsafe_code)]
+    fn start_algorithm(&self) -> Fallible<Rc<Promise>> {
+        let global = self.global();
+        let cx = global.get_cx();
+        match self.sink {
+            UnderlyingSinkType::Js {
+                ref object,
+                ref start,
+                ..
+            } => {
+                let result = match *start {
+                    Some(ref start) => {
+                        rooted!(in(*cx) let this = object.get());
+                        start.Call_(&this.handle(), self, ExceptionHandling::Rethrow)?
+                    },
+                    None => UndefinedValue(),
+                };
+                rooted!(in(*cx) let result = result);
+                Promise::new_resolved(&global, cx, result.handle())
+            },
+            UnderlyingSinkType::Transform(ref stream) => Ok(stream.start_promise()),
+        }
+    }
+
+    /// The write algorithm.
+    #[allow(unsafe_code)]
+    fn write_algorithm(&self, chunk: HandleValue) -> Rc<Promise> {
+        let global = self.global();
+        let cx = global.get_cx();
+        if self.algorithms_cleared.get() {
+            return resolved_promise(&global);
+        }
+        match self.sink {
+            UnderlyingSinkType::Js {
+                ref object,
+                ref write,
+                ..
+            } => match *write {
+                Some(ref write) => {
+                    rooted!(in(*cx) let this = object.get());
+                    let result =
+                        write.Call_(&this.handle(), chunk, self, ExceptionHandling::Rethrow);
+                    promise_from_callback_result(&global, result)
+                },
+                None => resolved_promise(&global),
+            },
+            UnderlyingSinkType::Transform(ref stream) => stream.sink_write_algorithm(chunk),
+        }
+    }
+
+    /// The close algorithm.
+    #[allow(unsafe_code)]
+    fn close_algorithm(&self) -> Rc<Promise> {
+        let global = self.global();
+        let cx = global.get_cx();
+        if self.algorithms_cleared.get() {
+            return resolved_promise(&global);
+        }
+        match self.sink {
+            UnderlyingSinkType::Js {
+                ref object,
+                ref close,
+                ..
+            } => match *close {
+                Some(ref close) => {
+                    rooted!(in(*cx) let this = object.get());
+                    let result = close.Call_(&this.handle(), ExceptionHandling::Rethrow);
+                    promise_from_callback_result(&global, result)
+                },
+                None => resolved_promise(&global),
+            },
+            UnderlyingSinkType::Transform(ref stream) => stream.sink_close_algorithm(),
+        }
+    }
+
+    /// The abort algorithm.
+    #[allow(unsafe_code)]
+    fn abort_algorithm(&self, reason: HandleValue) -> Rc<Promise> {
+        let global = self.global();
+        let cx = global.get_cx();
+        if self.algorithms_cleared.get() {
+            return resolved_promise(&global);
+        }
+        match self.sink {
+            UnderlyingSinkType::Js {
+                ref object,
+                ref abort,
+                ..
+            } => match *abort {
+                Some(ref abort) => {
+                    rooted!(in(*cx) let this = object.get());
+                    let result = abort.Call_(&this.handle(), reason, ExceptionHandling::Rethrow);
+                    promise_from_callback_result(&global, result)
+                },
+                None => resolved_promise(&global),
+            },
+            UnderlyingSinkType::Transform(ref stream) => stream.sink_abort_algorithm(reason),
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-clear-algorithms>
+    fn clear_algorithms(&self) {
+        self.algorithms_cleared.set(true);
+        *self.strategy_size.borrow_mut() = None;
+    }
+
+    /// <https://streams.spec.whatwg.org/#reset-queue>
+    fn reset_queue(&self) {
+        self.queue.borrow_mut().clear();
+        self.queue_total_size.set(0.);
+    }
+
+    /// <https://streams.spec.whatwg.org/#dequeue-value>
+    fn dequeue_value(&self) {
+        let entry = self.queue.borrow_mut().pop_front();
+        if let Some(QueueEntry::Chunk(_, size)) = entry {
+            // Rounding errors could make the total size negative.
+            self.queue_total_size
+                .set((self.queue_total_size.get() - size).max(0.));
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#ws-default-controller-private-abort>
+    pub fn abort_steps(&self, reason: HandleValue) -> Rc<Promise> {
+        let result = self.abort_algorithm(reason);
+        self.clear_algorithms();
+        result
+    }
+
+    /// <https://streams.spec.whatwg.org/#ws-default-controller-private-error>
+    pub fn error_steps(&self) {
+        self.reset_queue();
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-close>
+    pub fn close(&self) {
+        self.queue.borrow_mut().push_back(QueueEntry::Close);
+        self.advance_queue_if_needed();
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-get-chunk-size>
+    pub fn get_chunk_size(&self, chunk: HandleValue) -> f64 {
+        let size = match *self.strategy_size.borrow() {
+            Some(ref size) => size.clone(),
+            None => return 1.,
+        };
+        match size.Call__(chunk, ExceptionHandling::Rethrow) {
+            Ok(size) => size,
+            Err(error) => {
+                let cx = self.global().get_cx();
+                rooted!(in(*cx) let mut error_value = UndefinedValue());
+                take_exception(&self.global(), error, error_value.handle_mut());
+                self.error_if_needed(error_value.handle());
+                1.
+            },
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-get-desired-size>
+    pub fn get_desired_size(&self) -> f64 {
+        self.strategy_hwm.get() - self.queue_total_size.get()
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-get-backpressure>
+    fn get_backpressure(&self) -> bool {
+        self.get_desired_size() <= 0.
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-write>
+    pub fn write(&self, chunk: HandleValue, chunk_size: f64) {
+        let global = self.global();
+        // Step 1.
+        if !chunk_size.is_finite() || chunk_size < 0. {
+            let cx = global.get_cx();
+            rooted!(in(*cx) let mut error = UndefinedValue());
+            take_exception(
+                &global,
+                Error::Range("The chunk size must be a non-negative number".to_owned()),
+                error.handle_mut(),
+            );
+            return self.error_if_needed(error.handle());
+        }
+        self.queue
+            .borrow_mut()
+            .push_back(QueueEntry::Chunk(Box::new(Heap::default()), chunk_size));
+        if let Some(QueueEntry::Chunk(ref value, _)) = self.queue.borrow().back() {
+            value.set(chunk.get());
+        }
+        self.queue_total_size
+            .set(self.queue_total_size.get() + chunk_size);
+        // Steps 2-3.
+        let stream = self.stream();
+        if !stream.close_queued_or_in_flight() && stream.state() == WritableStreamState::Writable {
+            stream.update_backpressure(self.get_backpressure());
+        }
+        // Step 4.
+        self.advance_queue_if_needed();
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-advance-queue-if-needed>
+    fn advance_queue_if_needed(&self) {
+        // Steps 1-3.
+        let stream = self.stream();
+        if !self.started.get() || stream.has_in_flight_write_request() {
+            return;
+        }
+        // Steps 4-6.
+        let state = stream.state();
+        assert!(state != WritableStreamState::Closed && state != WritableStreamState::Errored);
+        if state == WritableStreamState::Erroring {
+            return stream.finish_erroring();
+        }
+        // Steps 7-10.
+        let cx = self.global().get_cx();
+        rooted!(in(*cx) let mut chunk = UndefinedValue());
+        let is_close = match self.queue.borrow().front() {
+            None => return,
+            Some(QueueEntry::Close) => true,
+            Some(QueueEntry::Chunk(ref value, _)) => {
+                chunk.set(value.get());
+                false
+            },
+        };
+        if is_close {
+            self.process_close();
+        } else {
+            self.process_write(chunk.handle());
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-error-if-needed>
+    pub fn error_if_needed(&self, error: HandleValue) {
+        if self.stream().state() == WritableStreamState::Writable {
+            self.error(error);
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-error>
+    fn error(&self, error: HandleValue) {
+        let stream = self.stream();
+        assert_eq!(stream.state(), WritableStreamState::Writable);
+        self.clear_algorithms();
+        stream.start_erroring(error);
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-process-close>
+    fn process_close(&self) {
+        let stream = self.stream();
+        stream.mark_close_request_in_flight();
+        self.dequeue_value();
+        assert!(self.queue.borrow().is_empty());
+        let sink_close_promise = self.close_algorithm();
+        self.clear_algorithms();
+        self.react(
+            &self.global(),
+            &sink_close_promise,
+            ControllerReaction::CloseFulfilled,
+            ControllerReaction::CloseRejected,
+        );
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-process-write>
+    fn process_write(&self, chunk: HandleValue) {
+        let stream = self.stream();
+        stream.mark_first_write_request_in_flight();
+        let sink_write_promise = self.write_algorithm(chunk);
+        self.react(
+            &self.global(),
+            &sink_write_promise,
+            ControllerReaction::WriteFulfilled,
+            ControllerReaction::WriteRejected,
+        );
+    }
+}
+
+impl WritableStreamDefaultControllerMethods for WritableStreamDefaultController {
+    // https://streams.spec.whatwg.org/#ws-default-controller-signal
+    fn Signal(&self) -> DomRoot<AbortSignal> {
+        self.signal()
+    }
+
+    // https://streams.spec.whatwg.org/#ws-default-controller-error
+    fn Error(&self, _cx: JSContext, e: HandleValue) {
+        if self.stream().state() != WritableStreamState::Writable {
+            return;
+        }
+        self.error(e);
+    }
+}
+
+/// Returns a promise resolved with the result of a callback, or rejected with its
+/// exception.
+/// <https://webidl.spec.whatwg.org/#invoke-a-callback-function>
+pub fn promise_from_callback_result(global: &GlobalScope, result: Fallible<JSVal>) -> Rc<Promise> {
+    let cx = global.get_cx();
+    match result {
+        Ok(value) => {
+            rooted!(in(*cx) let value = value);
+            Promise::new_resolved(global, cx, value.handle())
+                .expect("Resolving a promise with a value can't fail")
+        },
+        Err(error) => {
+            rooted!(in(*cx) let mut error_value = UndefinedValue());
+            take_exception(global, error, error_value.handle_mut());
+            let promise = Promise::new(global);
+            let _ac = enter_realm(global);
+            promise.reject(cx, error_value.handle());
+            promise
+        },
+    }
+}
+
+/// The reactions of a controller to the promises of its underlying sink.
+#[derive(JSTraceable, MallocSizeOf)]
+enum ControllerReaction {
+    StartFulfilled,
+    StartRejected,
+    WriteFulfilled,
+    WriteRejected,
+    CloseFulfilled,
+    CloseRejected,
+}
+
+#[derive(JSTraceable, MallocSizeOf)]
+struct ControllerReactionHandler {
+    controller: DomRoot<WritableStreamDefaultController>,
+    reaction: ControllerReaction,
+}
+
+impl Callback for ControllerReactionHandler {
+    fn callback(&self, _cx: JSContext, v: HandleValue, _realm: InRealm) {
+        let controller = &self.controller;
+        let stream = controller.stream();
+        match self.reaction {
+            // Step 15 of set-up-writable-stream-default-controller.
+            ControllerReaction::StartFulfilled => {
+                assert!(matches!(
+                    stream.state(),
+                    WritableStreamState::Writable | WritableStreamState::Erroring
+                ));
+                controller.started.set(true);
+                controller.advance_queue_if_needed();
+            },
+            // Step 16 of set-up-writable-stream-default-controller.
+            ControllerReaction::StartRejected => {
+                assert!(matches!(
+                    stream.state(),
+                    WritableStreamState::Writable | WritableStreamState::Erroring
+                ));
+                controller.started.set(true);
+                stream.deal_with_rejection(v);
+            },
+            // Step 4 of writable-stream-default-controller-process-write.
+            ControllerReaction::WriteFulfilled => {
+                stream.finish_in_flight_write();
+                let state = stream.state();
+                assert!(matches!(
+                    state,
+                    WritableStreamState::Writable | WritableStreamState::Erroring
+                ));
+                controller.dequeue_value();
+                if !stream.close_queued_or_in_flight() && state == WritableStreamState::Writable {
+                    stream.update_backpressure(controller.get_backpressure());
+                }
+                controller.advance_queue_if_needed();
+            },
+            // Step 5 of writable-stream-default-controller-process-write.
+            ControllerReaction::WriteRejected => {
+                if stream.state() == WritableStreamState::Writable {
+                    controller.clear_algorithms();
+                }
+                stream.finish_in_flight_write_with_error(v);
+            },
+            // Step 6 of writable-stream-default-controller-process-close.
+            ControllerReaction::CloseFulfilled => stream.finish_in_flight_close(),
+            // Step 7 of writable-stream-default-controller-process-close.
+            ControllerReaction::CloseRejected => stream.finish_in_flight_close_with_error(v),
+        }
+    }
+}
@@ -0,0 +1,303 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::WritableStreamDefaultWriterBinding::WritableStreamDefaultWriterMethods;
+use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{DomRoot, MutNullableDom};
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::promise::Promise;
+use crate::dom::writablestream::{resolved_promise, WritableStream, WritableStreamState};
+use crate::realms::enter_realm;
+use crate::script_runtime::JSContext;
+use dom_struct::dom_struct;
+use js::jsval::UndefinedValue;
+use js::rust::HandleValue;
+use std::rc::Rc;
+
+// https://streams.spec.whatwg.org/#default-writer-class
+#[dom_struct]
+pub struct WritableStreamDefaultWriter {
+    reflector_: Reflector,
+    /// <https://streams.spec.whatwg.org/#writablestreamdefaultwriter-stream>
+    stream: MutNullableDom<WritableStream>,
+    /// <https://streams.spec.whatwg.org/#writablestreamdefaultwriter-closedpromise>
+    #[ignore_malloc_size_of = "Rc"]
+    closed_promise: DomRefCell<Rc<Promise>>,
+    /// <https://streams.spec.whatwg.org/#writablestreamdefaultwriter-readypromise>
+    #[ignore_malloc_size_of = "Rc"]
+    ready_promise: DomRefCell<Rc<Promise>>,
+}
+
+impl WritableStreamDefaultWriter {
+    fn new_inherited(
+        stream: &WritableStream,
+        closed_promise: Rc<Promise>,
+        ready_promise: Rc<Promise>,
+    ) -> WritableStreamDefaultWriter {
+        WritableStreamDefaultWriter {
+            reflector_: Reflector::new(),
+            stream: MutNullableDom::new(Some(stream)),
+            closed_promise: DomRefCell::new(closed_promise),
+            ready_promise: DomRefCell::new(ready_promise),
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#set-up-writable-stream-default-writer>
+    pub fn new(
+        global: &GlobalScope,
+        stream: &WritableStream,
+    ) -> Fallible<DomRoot<WritableStreamDefaultWriter>> {
+        // Step 1.
+        if stream.is_locked() {
+            return Err(Error::Type("The stream is locked".to_owned()));
+        }
+        let _ac = enter_realm(global);
+        let cx = global.get_cx();
+        rooted!(in(*cx) let stored_error = stream.stored_error());
+        let rejected_promise = || {
+            let promise = Promise::new(global);
+            promise.reject(cx, stored_error.handle());
+            promise.mark_as_handled();
+            promise
+        };
+        // Steps 4-8.
+        let (ready_promise, closed_promise) = match stream.state() {
+            WritableStreamState::Writable => {
+                let ready_promise = if !stream.close_queued_or_in_flight() && stream.backpressure()
+                {
+                    Promise::new(global)
+                } else {
+                    resolved_promise(global)
+                };
+                (ready_promise, Promise::new(global))
+            },
+            WritableStreamState::Erroring => (rejected_promise(), Promise::new(global)),
+            WritableStreamState::Closed => (resolved_promise(global), resolved_promise(global)),
+            WritableStreamState::Errored => (rejected_promise(), rejected_promise()),
+        };
+        // Steps 2-3.
+        let writer = reflect_dom_object(
+            Box::new(WritableStreamDefaultWriter::new_inherited(
+                stream,
+                closed_promise,
+                ready_promise,
+            )),
+            global,
+        );
+        stream.set_writer(Some(&writer));
+        Ok(writer)
+    }
+
+    // https://streams.spec.whatwg.org/#default-writer-constructor
+    #[allow(non_snake_case)]
+    pub fn Constructor(
+        global: &GlobalScope,
+        stream: &WritableStream,
+    ) -> Fallible<DomRoot<WritableStreamDefaultWriter>> {
+        WritableStreamDefaultWriter::new(global, stream)
+    }
+
+    pub fn ready_promise(&self) -> Rc<Promise> {
+        self.ready_promise.borrow().clone()
+    }
+
+    pub fn closed_promise(&self) -> Rc<Promise> {
+        self.closed_promise.borrow().clone()
+    }
+
+    pub fn resolve_ready_promise(&self) {
+        self.ready_promise.borrow().resolve_native(&());
+    }
+
+    /// Replaces the ready promise with a pending one, when backpressure is applied.
+    pub fn reset_ready_promise(&self) {
+        *self.ready_promise.borrow_mut() = Promise::new(&self.global());
+    }
+
+    pub fn resolve_closed_promise(&self) {
+        self.closed_promise.borrow().resolve_native(&());
+    }
+
+    /// Steps 3-4 of
+    /// <https://streams.spec.whatwg.org/#writable-stream-reject-close-and-closed-promise-if-needed>.
+    pub fn reject_closed_promise(&self, error: HandleValue) {
+        let promise = self.closed_promise();
+        let _ac = enter_realm(&*promise);
+        promise.reject(self.global().get_cx(), error);
+        promise.mark_as_handled();
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-writer-ensure-closed-promise-rejected>
+    fn ensure_closed_promise_rejected(&self, error: HandleValue) {
+        if self.closed_promise.borrow().is_fulfilled() {
+            *self.closed_promise.borrow_mut() = Promise::new(&self.global());
+        }
+        self.reject_closed_promise(error);
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-writer-ensure-ready-promise-rejected>
+    pub fn ensure_ready_promise_rejected(&self, error: HandleValue) {
+        if self.ready_promise.borrow().is_fulfilled() {
+            *self.ready_promise.borrow_mut() = Promise::new(&self.global());
+        }
+        let promise = self.ready_promise();
+        let _ac = enter_realm(&*promise);
+        promise.reject(self.global().get_cx(), error);
+        promise.mark_as_handled();
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-writer-get-desired-size>
+    fn get_desired_size(&self, stream: &WritableStream) -> Option<f64> {
+        match stream.state() {
+            WritableStreamState::Errored | WritableStreamState::Erroring => None,
+            WritableStreamState::Closed => Some(0.),
+            WritableStreamState::Writable => Some(stream.controller().get_desired_size()),
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-writer-release>
+    #[allow(unsafe_code)]
+    pub fn release(&self) {
+        // Steps 1-3.
+        let stream = match self.stream.get() {
+            Some(stream) => stream,
+            None => return,
+        };
+        assert!(stream.writer().map_or(false, |writer| &*writer == self));
+        // Step 4.
+        let global = self.global();
+        let _ac = enter_realm(&*global);
+        let cx = global.get_cx();
+        rooted!(in(*cx) let mut released_error = UndefinedValue());
+        unsafe {
+            Error::Type("The writer was released".to_owned()).to_jsval(
+                *cx,
+                &global,
+                released_error.handle_mut(),
+            );
+        }
+        // Steps 5-6.
+        self.ensure_ready_promise_rejected(released_error.handle());
+        self.ensure_closed_promise_rejected(released_error.handle());
+        // Steps 7-8.
+        stream.set_writer(None);
+        self.stream.set(None);
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-writer-write>
+    pub fn write(&self, cx: JSContext, chunk: HandleValue) -> Rc<Promise> {
+        let global = self.global();
+        let _ac = enter_realm(&*global);
+        let rejected_promise = |error: Error| {
+            let promise = Promise::new(&global);
+            promise.reject_error(error);
+            promise
+        };
+        // Steps 1-4.
+        let stream = match self.stream.get() {
+            Some(stream) => stream,
+            None => return rejected_promise(Error::Type("The writer was released".to_owned())),
+        };
+        let chunk_size = stream.controller().get_chunk_size(chunk);
+        // Step 5.
+        if self.stream.get().map_or(true, |current| current != stream) {
+            return rejected_promise(Error::Type("The writer was released".to_owned()));
+        }
+        // Steps 6-9.
+        let state = stream.state();
+        if state == WritableStreamState::Errored || state == WritableStreamState::Erroring {
+            let promise = Promise::new(&global);
+            rooted!(in(*cx) let stored_error = stream.stored_error());
+            promise.reject(cx, stored_error.handle());
+            return promise;
+        }
+        if stream.close_queued_or_in_flight() || state == WritableStreamState::Closed {
+            return rejected_promise(Error::Type("The stream is closing or closed".to_owned()));
+        }
+        // Steps 10-13.
+        let promise = stream.add_write_request();
+        stream.controller().write(chunk, chunk_size);
+        promise
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-writer-close-with-error-propagation>
+    pub fn close_with_error_propagation(&self) -> Rc<Promise> {
+        let global = self.global();
+        let _ac = enter_realm(&*global);
+        let stream = match self.stream.get() {
+            Some(stream) => stream,
+            None => return resolved_promise(&global),
+        };
+        let state = stream.state();
+        if stream.close_queued_or_in_flight() || state == WritableStreamState::Closed {
+            return resolved_promise(&global);
+        }
+        if state == WritableStreamState::Errored {
+            let cx = global.get_cx();
+            let promise = Promise::new(&global);
+            rooted!(in(*cx) let stored_error = stream.stored_error());
+            promise.reject(cx, stored_error.handle());
+            return promise;
+        }
+        stream.close()
+    }
+}
+
+impl WritableStreamDefaultWriterMethods for WritableStreamDefaultWriter {
+    // https://streams.spec.whatwg.org/#default-writer-closed
+    fn Closed(&self) -> Rc<Promise> {
+        self.closed_promise()
+    }
+
+    // https://streams.spec.whatwg.org/#default-writer-desired-size
+    fn GetDesiredSize(&self) -> Fallible<Option<f64>> {
+        match self.stream.get() {
+            Some(stream) => Ok(self.get_desired_size(&stream)),
+            None => Err(Error::Type("The writer was released".to_owned())),
+        }
+    }
+
+    // https://streams.spec.whatwg.org/#default-writer-ready
+    fn Ready(&self) -> Rc<Promise> {
+        self.ready_promise()
+    }
+
+    // https://streams.spec.whatwg.org/#default-writer-abort
+    fn Abort(&self, _cx: JSContext, reason: HandleValue) -> Rc<Promise> {
+        match self.stream.get() {
+            Some(stream) => stream.abort(reason),
+            None => {
+                let promise = Promise::new(&self.global());
+                promise.reject_error(Error::Type("The writer was released".to_owned()));
+                promise
+            },
+        }
+    }
+
+    // https://streams.spec.whatwg.org/#default-writer-close
+    fn Close(&self) -> Rc<Promise> {
+        let error = match self.stream.get() {
+            Some(ref stream) if stream.close_queued_or_in_flight() => {
+                "The stream is already closing"
+            },
+            Some(stream) => return stream.close(),
+            None => "The writer was released",
+        };
+        let promise = Promise::new(&self.global());
+        promise.reject_error(Error::Type(error.to_owned()));
+        promise
+    }
+
+    // https://streams.spec.whatwg.org/#default-writer-release-lock
+    fn ReleaseLock(&self) {
+        self.release();
+    }
+
+    // https://streams.spec.whatwg.org/#default-writer-write
+    fn Write(&self, cx: JSContext, chunk: HandleValue) -> Rc<Promise> {
+        self.write(cx, chunk)
+    }
+}
@@ -38,6 +38,7 @@ use crate::script_runtime::JSContext;
 use crate::task_source::networking::NetworkingTaskSource;
 use crate::task_source::TaskSourceName;
 use crate::timers::{OneshotTimerCallback, OneshotTimerHandle};
+use content_security_policy as csp;
 use dom_struct::dom_struct;
 use encoding_rs::{Encoding, UTF_8};
 use euclid::Length;
@@ -274,6 +275,11 @@ impl XMLHttpRequest {
                 &self.resource_timing
             }
 
+            fn process_csp_violations(&mut self, violations: Vec<csp::Violation>) {
+                let global = &self.resource_timing_global();
+                global.report_csp_violations(violations, None);
+            }
+
             fn submit_resource_timing(&mut self) {
                 network_listener::submit_timing(self)
             }
@@ -312,7 +318,7 @@ impl XMLHttpRequest {
             .core_resource_thread()
             .send(Fetch(
                 init,
-                FetchChannels::ResponseMsg(action_sender, Some(cancellation_chan)),
+                FetchChannels::ResponseMsg(action_sender, Some(cancellation_chan), None),
             ))
             .unwrap();
     }
@@ -0,0 +1,1987 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Editing of the contents of editing hosts, the elements whose `contenteditable`
+//! attribute is in the true state and the documents in design mode, by the user and by
+//! `document.execCommand()`.
+//!
+//! <https://w3c.github.io/editing/docs/execCommand/>
+//! <https://w3c.github.io/input-events/>
+
+use crate::dom::bindings::codegen::Bindings::CharacterDataBinding::CharacterDataMethods;
+use crate::dom::bindings::codegen::Bindings::DocumentBinding::DocumentMethods;
+use crate::dom::bindings::codegen::Bindings::NodeBinding::NodeMethods;
+use crate::dom::bindings::codegen::Bindings::RangeBinding::RangeMethods;
+use crate::dom::bindings::codegen::Bindings::SelectionBinding::SelectionMethods;
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::root::{Dom, DomRoot};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::characterdata::CharacterData;
+use crate::dom::document::Document;
+use crate::dom::element::{CustomElementCreationMode, Element, ElementCreator};
+use crate::dom::event::{Event, EventStatus};
+use crate::dom::inputevent::InputEvent;
+use crate::dom::keyboardevent::KeyboardEvent;
+use crate::dom::node::{CloneChildrenFlag, Node, NodeDamage, ShadowIncluding};
+use crate::dom::range::{bp_position, Range};
+use crate::dom::selection::Selection;
+use crate::dom::text::Text;
+use crate::textinput::CMD_OR_CONTROL;
+use euclid::default::Point2D;
+use html5ever::{LocalName, QualName};
+use keyboard_types::{
+    CompositionEvent, CompositionState, Key, KeyState, Modifiers, ShortcutMatcher,
+};
+use std::cmp::Ordering;
+use std::iter;
+
+/// A change to the DOM made by an edit, which undoing the edit reverts.
+#[unrooted_must_root_lint::must_root]
+#[derive(JSTraceable, MallocSizeOf)]
+enum EditStep {
+    InsertData {
+        node: Dom<CharacterData>,
+        offset: u32,
+        data: DOMString,
+    },
+    DeleteData {
+        node: Dom<CharacterData>,
+        offset: u32,
+        data: DOMString,
+    },
+    InsertNode {
+        node: Dom<Node>,
+        parent: Dom<Node>,
+        child: Option<Dom<Node>>,
+    },
+    RemoveNode {
+        node: Dom<Node>,
+        parent: Dom<Node>,
+        child: Option<Dom<Node>>,
+    },
+}
+
+impl EditStep {
+    fn undo(&self) {
+        match *self {
+            EditStep::InsertData {
+                ref node,
+                offset,
+                ref data,
+            } => {
+                let _ = node.DeleteData(offset, utf16_len(data));
+            },
+            EditStep::DeleteData {
+                ref node,
+                offset,
+                ref data,
+            } => {
+                let _ = node.InsertData(offset, data.clone());
+            },
+            EditStep::InsertNode { ref node, .. } => node.remove_self(),
+            EditStep::RemoveNode {
+                ref node,
+                ref parent,
+                ref child,
+            } => {
+                let _ = parent.InsertBefore(node, child.as_deref());
+            },
+        }
+    }
+
+    fn redo(&self) {
+        match *self {
+            EditStep::InsertData {
+                ref node,
+                offset,
+                ref data,
+            } => {
+                let _ = node.InsertData(offset, data.clone());
+            },
+            EditStep::DeleteData {
+                ref node,
+                offset,
+                ref data,
+            } => {
+                let _ = node.DeleteData(offset, utf16_len(data));
+            },
+            EditStep::InsertNode {
+                ref node,
+                ref parent,
+                ref child,
+            } => {
+                let _ = parent.InsertBefore(node, child.as_deref());
+            },
+            EditStep::RemoveNode { ref node, .. } => node.remove_self(),
+        }
+    }
+}
+
+/// The selection before or after an edit, which undoing or redoing the edit restores.
+#[unrooted_must_root_lint::must_root]
+#[derive(JSTraceable, MallocSizeOf)]
+struct SavedSelection {
+    anchor: Dom<Node>,
+    anchor_offset: u32,
+    focus: Dom<Node>,
+    focus_offset: u32,
+}
+
+impl SavedSelection {
+    #[allow(unrooted_must_root)]
+    fn new(selection: &Selection) -> Option<SavedSelection> {
+        let anchor = selection.GetAnchorNode()?;
+        let focus = selection.GetFocusNode()?;
+        Some(SavedSelection {
+            anchor: Dom::from_ref(&*anchor),
+            anchor_offset: selection.AnchorOffset(),
+            focus: Dom::from_ref(&*focus),
+            focus_offset: selection.FocusOffset(),
+        })
+    }
+
+    fn restore(&self, selection: &Selection) {
+        let _ = selection.SetBaseAndExtent(
+            &self.anchor,
+            self.anchor_offset,
+            &self.focus,
+            self.focus_offset,
+        );
+    }
+}
+
+/// The changes to the DOM made by one edit, which are undone and redone together.
+#[unrooted_must_root_lint::must_root]
+#[derive(JSTraceable, MallocSizeOf)]
+struct EditTransaction {
+    steps: Vec<EditStep>,
+    selection_before: Option<SavedSelection>,
+    selection_after: Option<SavedSelection>,
+}
+
+impl EditTransaction {
+    fn undo(&self, selection: &Selection) {
+        for step in self.steps.iter().rev() {
+            step.undo();
+        }
+        if let Some(ref saved) = self.selection_before {
+            saved.restore(selection);
+        }
+    }
+
+    fn redo(&self, selection: &Selection) {
+        for step in self.steps.iter() {
+            step.redo();
+        }
+        if let Some(ref saved) = self.selection_after {
+            saved.restore(selection);
+        }
+    }
+}
+
+/// The text of an input method composition that is shown at the caret while it is
+/// being composed. It isn't part of the undo history, unlike the text it is committed as.
+#[unrooted_must_root_lint::must_root]
+#[derive(JSTraceable, MallocSizeOf)]
+struct CompositionPreview {
+    node: Dom<CharacterData>,
+    offset: u32,
+    length: u32,
+}
+
+/// The undo history of the editing hosts of a document, and the input method
+/// composition in progress in one of them.
+#[unrooted_must_root_lint::must_root]
+#[derive(Default, JSTraceable, MallocSizeOf)]
+pub struct EditingState {
+    undo_stack: Vec<EditTransaction>,
+    redo_stack: Vec<EditTransaction>,
+    /// The transaction that the edit being made records its changes in, if any.
+    current: Option<EditTransaction>,
+    composition: Option<CompositionPreview>,
+}
+
+impl EditingState {
+    fn begin(&mut self, selection: &Selection) {
+        self.current = Some(EditTransaction {
+            steps: vec![],
+            selection_before: SavedSelection::new(selection),
+            selection_after: None,
+        });
+    }
+
+    /// Ends the transaction of the edit being made, adding it to the undo history, and
+    /// returns whether the edit changed anything.
+    fn end(&mut self, selection: &Selection) -> bool {
+        let changed = self
+            .current
+            .as_ref()
+            .map_or(false, |transaction| !transaction.steps.is_empty());
+        if !changed {
+            self.current = None;
+            return false;
+        }
+        if let Some(ref mut transaction) = self.current {
+            transaction.selection_after = SavedSelection::new(selection);
+        }
+        self.undo_stack.extend(self.current.take());
+        self.redo_stack.clear();
+        true
+    }
+
+    fn record_insert_data(&mut self, node: &CharacterData, offset: u32, data: DOMString) {
+        if let Some(ref mut transaction) = self.current {
+            transaction.steps.push(EditStep::InsertData {
+                node: Dom::from_ref(node),
+                offset,
+                data,
+            });
+        }
+    }
+
+    fn record_delete_data(&mut self, node: &CharacterData, offset: u32, data: DOMString) {
+        if let Some(ref mut transaction) = self.current {
+            transaction.steps.push(EditStep::DeleteData {
+                node: Dom::from_ref(node),
+                offset,
+                data,
+            });
+        }
+    }
+
+    fn record_insert_node(&mut self, node: &Node, parent: &Node, child: Option<&Node>) {
+        if let Some(ref mut transaction) = self.current {
+            transaction.steps.push(EditStep::InsertNode {
+                node: Dom::from_ref(node),
+                parent: Dom::from_ref(parent),
+                child: child.map(Dom::from_ref),
+            });
+        }
+    }
+
+    fn record_remove_node(&mut self, node: &Node, parent: &Node, child: Option<&Node>) {
+        if let Some(ref mut transaction) = self.current {
+            transaction.steps.push(EditStep::RemoveNode {
+                node: Dom::from_ref(node),
+                parent: Dom::from_ref(parent),
+                child: child.map(Dom::from_ref),
+            });
+        }
+    }
+}
+
+/// Undoes the last edit of the undo history, and returns whether there was one.
+fn undo(document: &Document, selection: &Selection) -> bool {
+    let state = document.editing_state();
+    if state.borrow().undo_stack.is_empty() {
+        return false;
+    }
+    {
+        let mut state = state.borrow_mut();
+        let state = &mut *state;
+        state.redo_stack.extend(state.undo_stack.pop());
+    }
+    if let Some(transaction) = state.borrow().redo_stack.last() {
+        transaction.undo(selection);
+    }
+    true
+}
+
+/// Redoes the last undone edit, and returns whether there was one.
+fn redo(document: &Document, selection: &Selection) -> bool {
+    let state = document.editing_state();
+    if state.borrow().redo_stack.is_empty() {
+        return false;
+    }
+    {
+        let mut state = state.borrow_mut();
+        let state = &mut *state;
+        state.undo_stack.extend(state.redo_stack.pop());
+    }
+    if let Some(transaction) = state.borrow().undo_stack.last() {
+        transaction.redo(selection);
+    }
+    true
+}
+
+/// An edit that the user or `execCommand()` makes at the selection.
+enum Edit {
+    Bold,
+    Composition(DOMString),
+    CreateLink(DOMString),
+    Cut,
+    DeleteBackward,
+    DeleteForward,
+    InsertLineBreak,
+    InsertParagraph,
+    InsertText(DOMString),
+    Italic,
+    Paste(DOMString),
+    Redo,
+    Undo,
+}
+
+impl Edit {
+    /// <https://w3c.github.io/input-events/#interface-InputEvent-Attributes>
+    fn input_type(&self) -> &'static str {
+        match *self {
+            Edit::Bold => "formatBold",
+            Edit::Composition(_) => "insertFromComposition",
+            Edit::CreateLink(_) => "insertLink",
+            Edit::Cut => "deleteByCut",
+            Edit::DeleteBackward => "deleteContentBackward",
+            Edit::DeleteForward => "deleteContentForward",
+            Edit::InsertLineBreak => "insertLineBreak",
+            Edit::InsertParagraph => "insertParagraph",
+            Edit::InsertText(_) => "insertText",
+            Edit::Italic => "formatItalic",
+            Edit::Paste(_) => "insertFromPaste",
+            Edit::Redo => "historyRedo",
+            Edit::Undo => "historyUndo",
+        }
+    }
+
+    fn data(&self) -> Option<DOMString> {
+        match *self {
+            Edit::Composition(ref text) | Edit::InsertText(ref text) => Some(text.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// The commands of `execCommand()` that are supported.
+///
+/// <https://w3c.github.io/editing/docs/execCommand/#commands>
+#[derive(Clone, Copy, PartialEq)]
+enum Command {
+    Bold,
+    CreateLink,
+    Delete,
+    ForwardDelete,
+    InsertLineBreak,
+    InsertParagraph,
+    InsertText,
+    Italic,
+    Redo,
+    SelectAll,
+    Undo,
+}
+
+impl Command {
+    fn from_name(name: &str) -> Option<Command> {
+        Some(match &*name.to_ascii_lowercase() {
+            "bold" => Command::Bold,
+            "createlink" => Command::CreateLink,
+            "delete" => Command::Delete,
+            "forwarddelete" => Command::ForwardDelete,
+            "insertlinebreak" => Command::InsertLineBreak,
+            "insertparagraph" => Command::InsertParagraph,
+            "inserttext" => Command::InsertText,
+            "italic" => Command::Italic,
+            "redo" => Command::Redo,
+            "selectall" => Command::SelectAll,
+            "undo" => Command::Undo,
+            _ => return None,
+        })
+    }
+
+    fn style(&self) -> Option<InlineStyle> {
+        match *self {
+            Command::Bold => Some(InlineStyle::Bold),
+            Command::Italic => Some(InlineStyle::Italic),
+            _ => None,
+        }
+    }
+}
+
+/// The styles that the elements which `bold` and `italic` toggle give to text.
+#[derive(Clone, Copy)]
+enum InlineStyle {
+    Bold,
+    Italic,
+}
+
+impl InlineStyle {
+    /// The element that applying the style wraps text in.
+    fn element_name(&self) -> LocalName {
+        match *self {
+            InlineStyle::Bold => local_name!("b"),
+            InlineStyle::Italic => local_name!("i"),
+        }
+    }
+
+    fn is_applied_by(&self, element: &Element) -> bool {
+        if *element.namespace() != ns!(html) {
+            return false;
+        }
+        let name = element.local_name();
+        match *self {
+            InlineStyle::Bold => *name == local_name!("b") || *name == local_name!("strong"),
+            InlineStyle::Italic => *name == local_name!("i") || *name == local_name!("em"),
+        }
+    }
+}
+
+/// The ways that the caret moves along the lines that layout laid the text out in.
+#[derive(Clone, Copy)]
+enum LineMotion {
+    LineStart,
+    LineEnd,
+    PreviousLine,
+    NextLine,
+}
+
+/// What the default action of a `keydown` event in an editing host did.
+enum KeyAction {
+    Edit(Edit),
+    MoveCaret,
+    Nothing,
+}
+
+fn utf16_len(text: &str) -> u32 {
+    text.encode_utf16().count() as u32
+}
+
+fn utf16_offset_to_utf8(text: &str, offset: u32) -> usize {
+    let mut units = 0;
+    for (index, c) in text.char_indices() {
+        if units >= offset as usize {
+            return index;
+        }
+        units += c.len_utf16();
+    }
+    text.len()
+}
+
+fn utf8_offset_to_utf16(text: &str, offset: usize) -> u32 {
+    text.char_indices()
+        .take_while(|&(index, _)| index < offset)
+        .map(|(_, c)| c.len_utf16() as u32)
+        .sum()
+}
+
+/// The length in UTF-16 code units of the character of `text` before `offset`.
+fn char_len_before(text: &CharacterData, offset: u32) -> u32 {
+    let data = text.data();
+    let index = utf16_offset_to_utf8(&data, offset);
+    data[..index]
+        .chars()
+        .next_back()
+        .map_or(0, |c| c.len_utf16() as u32)
+}
+
+/// The length in UTF-16 code units of the character of `text` after `offset`.
+fn char_len_after(text: &CharacterData, offset: u32) -> u32 {
+    let data = text.data();
+    let index = utf16_offset_to_utf8(&data, offset);
+    data[index..]
+        .chars()
+        .next()
+        .map_or(0, |c| c.len_utf16() as u32)
+}
+
+/// Replaces the spaces of `text` that HTML would collapse with the text around it,
+/// given the characters before and after it, by no-break spaces so that they show.
+fn preserve_spaces(text: &str, before: Option<char>, after: Option<char>) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut previous = before;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let next = chars.peek().cloned().or(after);
+        let c = if c == ' ' && (previous.map_or(true, |p| p == ' ') || next.is_none()) {
+            '\u{a0}'
+        } else {
+            c
+        };
+        result.push(c);
+        previous = Some(c);
+    }
+    result
+}
+
+/// Whether `node` is an element that starts a line of its own, which the edits that
+/// split and join lines treat as a paragraph.
+fn is_block(node: &Node) -> bool {
+    let element = match node.downcast::<Element>() {
+        Some(element) => element,
+        None => return false,
+    };
+    if *element.namespace() != ns!(html) {
+        return false;
+    }
+    match *element.local_name() {
+        local_name!("address") |
+        local_name!("article") |
+        local_name!("aside") |
+        local_name!("blockquote") |
+        local_name!("dd") |
+        local_name!("details") |
+        local_name!("dialog") |
+        local_name!("div") |
+        local_name!("dl") |
+        local_name!("dt") |
+        local_name!("fieldset") |
+        local_name!("figcaption") |
+        local_name!("figure") |
+        local_name!("footer") |
+        local_name!("form") |
+        local_name!("h1") |
+        local_name!("h2") |
+        local_name!("h3") |
+        local_name!("h4") |
+        local_name!("h5") |
+        local_name!("h6") |
+        local_name!("header") |
+        local_name!("hgroup") |
+        local_name!("hr") |
+        local_name!("li") |
+        local_name!("main") |
+        local_name!("nav") |
+        local_name!("ol") |
+        local_name!("p") |
+        local_name!("pre") |
+        local_name!("section") |
+        local_name!("table") |
+        local_name!("ul") => true,
+        _ => false,
+    }
+}
+
+fn is_br(node: &Node) -> bool {
+    node.downcast::<Element>()
+        .map_or(false, |element| *element.local_name() == local_name!("br"))
+}
+
+/// Whether `node` is a non-empty text node or an element that the caret moves over as
+/// a whole, which are what deleting a character deletes from.
+fn is_leaf(node: &Node) -> bool {
+    if node.is::<Text>() {
+        return node.len() > 0;
+    }
+    node.downcast::<Element>()
+        .map_or(false, |element| match *element.local_name() {
+            local_name!("br") | local_name!("img") => true,
+            _ => false,
+        })
+}
+
+/// The leaves of `root`, in tree order.
+fn leaves(root: &Node) -> Vec<DomRoot<Node>> {
+    root.traverse_preorder(ShadowIncluding::No)
+        .filter(|node| is_leaf(node))
+        .collect()
+}
+
+/// The `<br>` that keeps the line of `block` from collapsing, if it has no other
+/// content.
+fn placeholder(block: &Node) -> Option<DomRoot<Node>> {
+    let leaves = leaves(block);
+    if leaves.len() == 1 && is_br(&leaves[0]) {
+        leaves.into_iter().next()
+    } else {
+        None
+    }
+}
+
+/// The last leaf of `root` that ends at or before the boundary point.
+fn leaf_before(root: &Node, node: &Node, offset: u32) -> Option<DomRoot<Node>> {
+    leaves(root)
+        .into_iter()
+        .rev()
+        .find(|leaf| bp_position(leaf, leaf.len(), node, offset) != Some(Ordering::Greater))
+}
+
+/// The first leaf of `root` that starts at or after the boundary point.
+fn leaf_after(root: &Node, node: &Node, offset: u32) -> Option<DomRoot<Node>> {
+    leaves(root)
+        .into_iter()
+        .find(|leaf| bp_position(leaf, 0, node, offset) != Some(Ordering::Less))
+}
+
+/// The boundary point at the start of `leaf`, which is in it if it is a text node.
+fn start_of_leaf(leaf: &Node) -> (DomRoot<Node>, u32) {
+    if leaf.is::<Text>() {
+        return (DomRoot::from_ref(leaf), 0);
+    }
+    (leaf.GetParentNode().unwrap(), leaf.index())
+}
+
+/// The boundary point at the end of `leaf`, which is in it if it is a text node.
+fn end_of_leaf(leaf: &Node) -> (DomRoot<Node>, u32) {
+    if leaf.is::<Text>() {
+        return (DomRoot::from_ref(leaf), leaf.len());
+    }
+    (leaf.GetParentNode().unwrap(), leaf.index() + 1)
+}
+
+/// The block that `node` is in, which is `host` for the content of the editing host
+/// that isn't in a block inside of it.
+fn block_of(node: &Node, host: &Node) -> DomRoot<Node> {
+    node.inclusive_ancestors(ShadowIncluding::No)
+        .find(|ancestor| &**ancestor == host || is_block(ancestor))
+        .unwrap_or_else(|| DomRoot::from_ref(host))
+}
+
+/// The inclusive ancestor of `node` that is a child of `ancestor`.
+fn child_of(ancestor: &Node, node: &Node) -> Option<DomRoot<Node>> {
+    node.inclusive_ancestors(ShadowIncluding::No).find(|child| {
+        child
+            .GetParentNode()
+            .map_or(false, |parent| &*parent == ancestor)
+    })
+}
+
+/// The element of `host` that gives `node` the style, if any.
+fn style_ancestor(host: &Node, node: &Node, style: InlineStyle) -> Option<DomRoot<Element>> {
+    node.inclusive_ancestors(ShadowIncluding::No)
+        .take_while(|ancestor| &**ancestor != host)
+        .filter_map(DomRoot::downcast::<Element>)
+        .find(|element| style.is_applied_by(element))
+}
+
+/// The node and offset in bytes in its text that layout knows the boundary point by: a
+/// text node next to it if there is one, and the element it is in otherwise.
+fn layout_position(node: &Node, offset: u32) -> (DomRoot<Node>, usize) {
+    if let Some(text) = node.downcast::<Text>() {
+        let offset = utf16_offset_to_utf8(&text.upcast::<CharacterData>().data(), offset);
+        return (DomRoot::from_ref(node), offset);
+    }
+    if offset > 0 {
+        if let Some(child) = node.children().nth(offset as usize - 1) {
+            if child.is::<Text>() {
+                let length = child.downcast::<CharacterData>().unwrap().data().len();
+                return (child, length);
+            }
+        }
+    }
+    match node.children().nth(offset as usize) {
+        Some(child) if child.is::<Text>() => (child, 0),
+        _ => (DomRoot::from_ref(node), 0),
+    }
+}
+
+/// The editing host that has focus, which the user edits.
+fn focused_editing_host(document: &Document) -> Option<DomRoot<Element>> {
+    document
+        .get_focused_element()
+        .or_else(|| document.GetDocumentElement())
+        .filter(|element| element.upcast::<Node>().is_editing_host())
+}
+
+/// The editing host that the selection is in, which `execCommand()` edits.
+fn selection_editing_host(document: &Document) -> Option<DomRoot<Element>> {
+    let range = document.GetSelection()?.GetRangeAt(0).ok()?;
+    let host = range.StartContainer().editing_host()?;
+    if !host
+        .upcast::<Node>()
+        .is_inclusive_ancestor_of(&range.EndContainer())
+    {
+        return None;
+    }
+    Some(host)
+}
+
+/// The position of the caret of the focused editing host if its selection is
+/// collapsed, as a node and an offset in bytes in its text, for layout to paint it.
+pub fn caret(document: &Document) -> Option<(DomRoot<Node>, usize)> {
+    let host = focused_editing_host(document)?;
+    let range = document.GetSelection()?.GetRangeAt(0).ok()?;
+    if !range.Collapsed() {
+        return None;
+    }
+    let node = range.StartContainer();
+    if !host.upcast::<Node>().is_inclusive_ancestor_of(&node) {
+        return None;
+    }
+    Some(layout_position(&node, range.StartOffset()))
+}
+
+/// Edits the contents of an editing host at its selection, recording the changes to
+/// the DOM in the undo history of the document while an edit is being made.
+struct Editor<'a> {
+    document: &'a Document,
+    host: &'a Node,
+    selection: &'a Selection,
+}
+
+impl<'a> Editor<'a> {
+    /// The range of the selection, if it is in the editing host.
+    fn range(&self) -> Option<DomRoot<Range>> {
+        let range = self.selection.GetRangeAt(0).ok()?;
+        if !self.host.is_inclusive_ancestor_of(&range.StartContainer()) ||
+            !self.host.is_inclusive_ancestor_of(&range.EndContainer())
+        {
+            return None;
+        }
+        Some(range)
+    }
+
+    /// The start of the selection, or of the editing host if the selection isn't in it.
+    fn start(&self) -> (DomRoot<Node>, u32) {
+        match self.range() {
+            Some(range) => (range.StartContainer(), range.StartOffset()),
+            None => (DomRoot::from_ref(self.host), 0),
+        }
+    }
+
+    /// The focus of the selection, or the start of the editing host if the selection
+    /// isn't in it.
+    fn focus(&self) -> (DomRoot<Node>, u32) {
+        match (self.range(), self.selection.GetFocusNode()) {
+            (Some(_), Some(focus)) => (focus, self.selection.FocusOffset()),
+            _ => (DomRoot::from_ref(self.host), 0),
+        }
+    }
+
+    fn collapse(&self, node: &Node, offset: u32) {
+        let _ = self.selection.Collapse(Some(node), offset);
+    }
+
+    /// Moves the selection into the editing host if it isn't in it, so that edits are
+    /// made in the editing host.
+    fn ensure_selection(&self) {
+        if self.range().is_none() {
+            self.collapse(self.host, 0);
+        }
+    }
+
+    fn select_all(&self) {
+        let _ = self.selection.SelectAllChildren(self.host);
+    }
+
+    fn create_element(&self, name: LocalName) -> DomRoot<Element> {
+        Element::create(
+            QualName::new(None, ns!(html), name),
+            None,
+            self.document,
+            ElementCreator::ScriptCreated,
+            CustomElementCreationMode::Synchronous,
+        )
+    }
+
+    fn insert_node(&self, node: &Node, parent: &Node, child: Option<&Node>) {
+        if parent.InsertBefore(node, child).is_ok() {
+            self.document
+                .editing_state()
+                .borrow_mut()
+                .record_insert_node(node, parent, child);
+        }
+    }
+
+    fn remove_node(&self, node: &Node) {
+        let parent = match node.GetParentNode() {
+            Some(parent) => parent,
+            None => return,
+        };
+        let child = node.GetNextSibling();
+        node.remove_self();
+        self.document
+            .editing_state()
+            .borrow_mut()
+            .record_remove_node(node, &parent, child.as_deref());
+    }
+
+    fn move_node(&self, node: &Node, parent: &Node, child: Option<&Node>) {
+        self.remove_node(node);
+        self.insert_node(node, parent, child);
+    }
+
+    fn insert_data(&self, text: &CharacterData, offset: u32, data: DOMString) {
+        if text.InsertData(offset, data.clone()).is_ok() {
+            self.document
+                .editing_state()
+                .borrow_mut()
+                .record_insert_data(text, offset, data);
+        }
+    }
+
+    fn delete_data(&self, text: &CharacterData, offset: u32, count: u32) {
+        let data = match text.SubstringData(offset, count) {
+            Ok(data) => data,
+            Err(_) => return,
+        };
+        if text.DeleteData(offset, count).is_ok() {
+            self.document
+                .editing_state()
+                .borrow_mut()
+                .record_delete_data(text, offset, data);
+        }
+    }
+
+    /// Splits the text node `node` at `offset`, and returns the new text node with the
+    /// text after `offset`.
+    fn split_text(&self, node: &Node, offset: u32) -> DomRoot<Node> {
+        let text = node.downcast::<CharacterData>().unwrap();
+        let count = text.Length() - offset;
+        let tail = Text::new(
+            text.SubstringData(offset, count).unwrap_or_default(),
+            self.document,
+        );
+        self.delete_data(text, offset, count);
+        if let Some(parent) = node.GetParentNode() {
+            self.insert_node(tail.upcast(), &parent, node.GetNextSibling().as_deref());
+        }
+        DomRoot::upcast(tail)
+    }
+
+    /// Inserts `new` at the boundary point, splitting the text node that it is in if
+    /// needed.
+    fn insert_node_at(&self, new: &Node, node: &Node, offset: u32) {
+        if !node.is::<Text>() {
+            let child = node.children().nth(offset as usize);
+            self.insert_node(new, node, child.as_deref());
+            return;
+        }
+        let parent = match node.GetParentNode() {
+            Some(parent) => parent,
+            None => return,
+        };
+        let child = if offset == 0 {
+            Some(DomRoot::from_ref(node))
+        } else if offset >= node.len() {
+            node.GetNextSibling()
+        } else {
+            Some(self.split_text(node, offset))
+        };
+        self.insert_node(new, &parent, child.as_deref());
+    }
+
+    /// The text node to insert text at the boundary point in and the offset in it,
+    /// which is a new text node if there is none at the boundary point.
+    fn text_node_at(&self, node: &Node, offset: u32) -> (DomRoot<Node>, u32) {
+        if node.is::<Text>() {
+            return (DomRoot::from_ref(node), offset);
+        }
+        if offset > 0 {
+            if let Some(child) = node.children().nth(offset as usize - 1) {
+                if child.is::<Text>() {
+                    let length = child.len();
+                    return (child, length);
+                }
+            }
+        }
+        if let Some(child) = node.children().nth(offset as usize) {
+            if child.is::<Text>() {
+                return (child, 0);
+            }
+        }
+        let text = Text::new(DOMString::new(), self.document);
+        self.insert_node_at(text.upcast(), node, offset);
+        (DomRoot::upcast(text), 0)
+    }
+
+    /// Gives `block` a `<br>` that keeps its line from collapsing if it has no content.
+    fn ensure_placeholder(&self, block: &Node) {
+        if block == self.host || !leaves(block).is_empty() {
+            return;
+        }
+        let br = self.create_element(local_name!("br"));
+        self.insert_node(br.upcast(), block, None);
+    }
+
+    /// Removes `node` if it has no content left.
+    fn remove_if_empty(&self, node: &Node) {
+        if node != self.host && leaves(node).is_empty() {
+            self.remove_node(node);
+        }
+    }
+
+    /// Splits the inclusive ancestors of the boundary point up to `ancestor` in two,
+    /// moving what is after the boundary point into shallow clones of them that are
+    /// inserted after them. Returns the clone of `ancestor`.
+    fn split_at(&self, ancestor: &Node, node: &Node, offset: u32) -> DomRoot<Node> {
+        let (mut node, mut offset) = match node.GetParentNode() {
+            Some(parent) if node.is::<Text>() => {
+                let index = node.index();
+                if offset == 0 {
+                    (parent, index)
+                } else {
+                    if offset < node.len() {
+                        self.split_text(node, offset);
+                    }
+                    (parent, index + 1)
+                }
+            },
+            _ => (DomRoot::from_ref(node), offset),
+        };
+        loop {
+            let clone = Node::clone(&node, None, CloneChildrenFlag::DoNotCloneChildren);
+            let following: Vec<DomRoot<Node>> = node.children().skip(offset as usize).collect();
+            for child in following {
+                self.move_node(&child, &clone, None);
+            }
+            let parent = match node.GetParentNode() {
+                Some(parent) => parent,
+                None => return clone,
+            };
+            self.insert_node(&clone, &parent, node.GetNextSibling().as_deref());
+            if &*node == ancestor {
+                return clone;
+            }
+            offset = node.index() + 1;
+            node = parent;
+        }
+    }
+
+    /// Wraps the line that the boundary point is in, which is content of the editing
+    /// host that isn't in a block, in a `<div>` so that it can be split. Returns the
+    /// `<div>` and the boundary point, which moves into it if it was in the editing host
+    /// itself.
+    fn wrap_line(&self, node: &Node, offset: u32) -> (DomRoot<Node>, DomRoot<Node>, u32) {
+        let index = if node == self.host {
+            offset
+        } else {
+            child_of(self.host, node).map_or(0, |child| child.index())
+        };
+        let children: Vec<DomRoot<Node>> = self.host.children().collect();
+        let mut start = index as usize;
+        while start > 0 && !is_block(&children[start - 1]) {
+            start -= 1;
+        }
+        let mut end = index as usize;
+        while end < children.len() && !is_block(&children[end]) {
+            end += 1;
+        }
+
+        let div = DomRoot::upcast::<Node>(self.create_element(local_name!("div")));
+        self.insert_node(&div, self.host, children.get(start).map(|child| &**child));
+        for child in &children[start..end] {
+            self.move_node(child, &div, None);
+        }
+        if node == self.host {
+            (div.clone(), div, offset - start as u32)
+        } else {
+            (div, DomRoot::from_ref(node), offset)
+        }
+    }
+
+    /// Joins the line at the end of `first_block` with the line at the start of
+    /// `second_block` by moving the content of the second line into the first, given
+    /// the last leaf of the first line and the first leaf of the second line if they
+    /// have any. Returns the boundary point where the lines meet.
+    fn join_lines(
+        &self,
+        first_block: &Node,
+        last: Option<&Node>,
+        second_block: &Node,
+        first: Option<&Node>,
+    ) -> (DomRoot<Node>, u32) {
+        // Where the content of the second line goes.
+        let child = match last {
+            Some(last) => child_of(first_block, last).and_then(|top| top.GetNextSibling()),
+            None => child_of(first_block, second_block),
+        };
+
+        // A `<br>` at the end of the first line would keep the lines apart.
+        let last = match last {
+            Some(br) if is_br(br) => {
+                self.remove_node(br);
+                None
+            },
+            last => last,
+        };
+
+        let line: Vec<DomRoot<Node>> = match first.and_then(|first| child_of(second_block, first)) {
+            Some(top) => iter::once(DomRoot::from_ref(&*top))
+                .chain(
+                    top.following_siblings()
+                        .take_while(|sibling| !is_block(sibling)),
+                )
+                .collect(),
+            None => vec![],
+        };
+        for node in &line {
+            self.move_node(node, first_block, child.as_deref());
+        }
+        self.remove_if_empty(second_block);
+        self.ensure_placeholder(first_block);
+
+        match (last, line.first()) {
+            (Some(last), _) => end_of_leaf(last),
+            (None, Some(node)) => (DomRoot::from_ref(first_block), node.index()),
+            (None, None) => (
+                DomRoot::from_ref(first_block),
+                child.map_or(first_block.len(), |child| child.index()),
+            ),
+        }
+    }
+
+    /// Deletes the contents of the selection, joining the lines at its start and end,
+    /// and returns whether it wasn't collapsed.
+    fn delete_selection(&self) -> bool {
+        let range = match self.range() {
+            Some(range) if !range.Collapsed() => range,
+            _ => return false,
+        };
+        let (start, start_offset) = (range.StartContainer(), range.StartOffset());
+        let (end, end_offset) = (range.EndContainer(), range.EndOffset());
+        let start_block = block_of(&start, self.host);
+        let end_block = block_of(&end, self.host);
+
+        if start == end {
+            match start.downcast::<CharacterData>() {
+                Some(text) => self.delete_data(text, start_offset, end_offset - start_offset),
+                None => {
+                    let children: Vec<DomRoot<Node>> = start
+                        .children()
+                        .skip(start_offset as usize)
+                        .take((end_offset - start_offset) as usize)
+                        .collect();
+                    for child in children {
+                        self.remove_node(&child);
+                    }
+                },
+            }
+            self.ensure_placeholder(&start_block);
+            self.collapse(&start, start_offset);
+            return true;
+        }
+
+        // Remove the nodes that the selection contains, and then the selected text of the
+        // text nodes at its ends.
+        let contained: Vec<DomRoot<Node>> = self
+            .host
+            .traverse_preorder(ShadowIncluding::No)
+            .filter(|node| {
+                range.contains(node) &&
+                    !node
+                        .GetParentNode()
+                        .map_or(false, |parent| range.contains(&parent))
+            })
+            .collect();
+        for node in contained {
+            self.remove_node(&node);
+        }
+        if let Some(text) = start.downcast::<CharacterData>() {
+            self.delete_data(text, start_offset, text.Length() - start_offset);
+        }
+        if let Some(text) = end.downcast::<CharacterData>() {
+            self.delete_data(text, 0, end_offset);
+        }
+
+        let start_offset = start_offset.min(start.len());
+        if start_block == end_block {
+            self.ensure_placeholder(&start_block);
+            self.collapse(&start, start_offset);
+            return true;
+        }
+        let last = leaf_before(&start_block, &start, start_offset);
+        let first = leaf_after(&end_block, &start, start_offset)
+            .filter(|leaf| block_of(leaf, self.host) == end_block);
+        let (node, offset) =
+            self.join_lines(&start_block, last.as_deref(), &end_block, first.as_deref());
+        self.collapse(&node, offset);
+        true
+    }
+
+    /// Deletes the character or other leaf before the caret, or joins its line with the
+    /// previous line at the start of a line.
+    fn delete_backward(&self) {
+        if self.delete_selection() {
+            return;
+        }
+        let (node, offset) = self.start();
+        let block = block_of(&node, self.host);
+        if let Some(text) = node.downcast::<Text>() {
+            if offset > 0 {
+                let text = text.upcast::<CharacterData>();
+                let length = char_len_before(text, offset);
+                self.delete_data(text, offset - length, length);
+                self.ensure_placeholder(&block);
+                self.collapse(&node, offset - length);
+                return;
+            }
+        }
+
+        let last = match leaf_before(self.host, &node, offset) {
+            Some(last) => last,
+            None => return,
+        };
+        let last_block = block_of(&last, self.host);
+        if last_block == block && placeholder(&block).as_ref() != Some(&last) {
+            let (node, offset) = match last.downcast::<CharacterData>() {
+                Some(text) => {
+                    let end = text.Length();
+                    let length = char_len_before(text, end);
+                    self.delete_data(text, end - length, length);
+                    (DomRoot::from_ref(&*last), end - length)
+                },
+                None => {
+                    let position = start_of_leaf(&last);
+                    self.remove_node(&last);
+                    position
+                },
+            };
+            self.ensure_placeholder(&block);
+            self.collapse(&node, offset);
+            return;
+        }
+
+        // The caret is at the start of a line, or after the placeholder of an empty one,
+        // which goes away with the line.
+        let (last, last_block, first) = if last_block == block {
+            let (parent, index) = start_of_leaf(&last);
+            match leaf_before(self.host, &parent, index) {
+                Some(previous) => {
+                    self.remove_node(&last);
+                    let previous_block = block_of(&previous, self.host);
+                    (previous, previous_block, None)
+                },
+                None => return,
+            }
+        } else {
+            let first = leaf_after(&block, &node, offset);
+            (last, last_block, first)
+        };
+        let (node, offset) = self.join_lines(&last_block, Some(&last), &block, first.as_deref());
+        self.collapse(&node, offset);
+    }
+
+    /// Deletes the character or other leaf after the caret, or joins its line with the
+    /// next line at the end of a line.
+    fn delete_forward(&self) {
+        if self.delete_selection() {
+            return;
+        }
+        let (node, offset) = self.start();
+        let block = block_of(&node, self.host);
+        if let Some(text) = node.downcast::<Text>() {
+            let text = text.upcast::<CharacterData>();
+            if offset < text.Length() {
+                let length = char_len_after(text, offset);
+                self.delete_data(text, offset, length);
+                self.ensure_placeholder(&block);
+                self.collapse(&node, offset);
+                return;
+            }
+        }
+
+        let first = match leaf_after(self.host, &node, offset) {
+            Some(first) => first,
+            None => return,
+        };
+        let first_block = block_of(&first, self.host);
+        if first_block == block && placeholder(&block).as_ref() != Some(&first) {
+            let position = start_of_leaf(&first);
+            match first.downcast::<CharacterData>() {
+                Some(text) => {
+                    let length = char_len_after(text, 0);
+                    self.delete_data(text, 0, length);
+                },
+                None => self.remove_node(&first),
+            }
+            self.ensure_placeholder(&block);
+            self.collapse(&position.0, position.1);
+            return;
+        }
+
+        // The caret is at the end of a line, or before the placeholder of an empty one.
+        let (last, first, first_block) = if first_block == block {
+            let (parent, index) = end_of_leaf(&first);
+            match leaf_after(self.host, &parent, index) {
+                Some(next) => {
+                    let next_block = block_of(&next, self.host);
+                    (Some(first), next, next_block)
+                },
+                None => return,
+            }
+        } else {
+            let last = leaf_before(&block, &node, offset);
+            (last, first, first_block)
+        };
+        let (node, offset) = self.join_lines(&block, last.as_deref(), &first_block, Some(&first));
+        self.collapse(&node, offset);
+    }
+
+    /// Replaces the selection with `text`.
+    fn insert_text(&self, text: &str) {
+        self.delete_selection();
+        if text.is_empty() {
+            return;
+        }
+        let (node, offset) = self.start();
+        let placeholder = placeholder(&block_of(&node, self.host));
+        let (node, offset) = self.text_node_at(&node, offset);
+        let data = {
+            let text_data = node.downcast::<CharacterData>().unwrap().data();
+            let index = utf16_offset_to_utf8(&text_data, offset);
+            preserve_spaces(
+                text,
+                text_data[..index].chars().next_back(),
+                text_data[index..].chars().next(),
+            )
+        };
+        let length = utf16_len(&data);
+        self.insert_data(
+            node.downcast::<CharacterData>().unwrap(),
+            offset,
+            DOMString::from(data),
+        );
+        if let Some(br) = placeholder {
+            self.remove_node(&br);
+        }
+        self.collapse(&node, offset + length);
+    }
+
+    /// Replaces the selection with the lines of `text` as paragraphs.
+    fn insert_lines(&self, text: &str) {
+        for (index, line) in text.split('\n').enumerate() {
+            if index > 0 {
+                self.insert_paragraph();
+            }
+            self.insert_text(line.trim_end_matches('\r'));
+        }
+    }
+
+    /// Replaces the selection with a paragraph break, splitting the block at the caret.
+    fn insert_paragraph(&self) {
+        self.delete_selection();
+        let (node, offset) = self.start();
+        let block = block_of(&node, self.host);
+        let (block, node, offset) = if &*block == self.host {
+            self.wrap_line(&node, offset)
+        } else {
+            (block, node, offset)
+        };
+        let new_block = self.split_at(&block, &node, offset);
+        self.ensure_placeholder(&block);
+        self.ensure_placeholder(&new_block);
+        let (node, offset) = match leaves(&new_block).first() {
+            Some(leaf) => start_of_leaf(leaf),
+            None => (new_block, 0),
+        };
+        self.collapse(&node, offset);
+    }
+
+    /// Replaces the selection with a `<br>`.
+    fn insert_line_break(&self) {
+        self.delete_selection();
+        let (node, offset) = self.start();
+        let block = block_of(&node, self.host);
+        let br = DomRoot::upcast::<Node>(self.create_element(local_name!("br")));
+        self.insert_node_at(&br, &node, offset);
+        let (parent, index) = end_of_leaf(&br);
+        // A line break doesn't start a new line at the end of a block without content
+        // after it.
+        if leaf_after(&block, &parent, index).is_none() {
+            let placeholder = self.create_element(local_name!("br"));
+            self.insert_node(
+                placeholder.upcast(),
+                &parent,
+                br.GetNextSibling().as_deref(),
+            );
+        }
+        self.collapse(&parent, index);
+    }
+
+    /// Splits the text nodes at the ends of the selection so that it contains whole text
+    /// nodes, selects them, and returns them.
+    fn select_text_nodes(&self) -> Vec<DomRoot<Node>> {
+        let range = match self.range() {
+            Some(range) => range,
+            None => return vec![],
+        };
+        let (start, start_offset) = (range.StartContainer(), range.StartOffset());
+        let (mut end, mut end_offset) = (range.EndContainer(), range.EndOffset());
+        let (start, start_offset) =
+            if start.is::<Text>() && start_offset > 0 && start_offset < start.len() {
+                let tail = self.split_text(&start, start_offset);
+                if start == end {
+                    end = tail.clone();
+                    end_offset -= start_offset;
+                }
+                (tail, 0)
+            } else {
+                (start, start_offset)
+            };
+        if end.is::<Text>() && end_offset > 0 && end_offset < end.len() {
+            self.split_text(&end, end_offset);
+        }
+
+        let texts: Vec<DomRoot<Node>> = leaves(self.host)
+            .into_iter()
+            .filter(|leaf| {
+                leaf.is::<Text>() &&
+                    bp_position(leaf, 0, &start, start_offset) != Some(Ordering::Less) &&
+                    bp_position(leaf, leaf.len(), &end, end_offset) != Some(Ordering::Greater)
+            })
+            .collect();
+        self.select_nodes(&texts);
+        texts
+    }
+
+    /// Selects the nodes from the start of the first to the end of the last.
+    fn select_nodes(&self, nodes: &[DomRoot<Node>]) {
+        if let (Some(first), Some(last)) = (nodes.first(), nodes.last()) {
+            let _ = self.selection.SetBaseAndExtent(first, 0, last, last.len());
+        }
+    }
+
+    /// Wraps `node` in a new element named `name`, with the `href`, or moves it into the
+    /// previous sibling instead if that is such an element.
+    fn wrap(&self, node: &Node, name: LocalName, href: Option<&DOMString>) {
+        let matches = |element: &Element| {
+            *element.local_name() == name &&
+                href.map_or(true, |href| {
+                    element.get_string_attribute(&local_name!("href")) == *href
+                })
+        };
+        if let Some(previous) = node.GetPreviousSibling() {
+            if previous.downcast::<Element>().map_or(false, matches) {
+                self.move_node(node, &previous, None);
+                return;
+            }
+        }
+        let parent = match node.GetParentNode() {
+            Some(parent) => parent,
+            None => return,
+        };
+        let element = self.create_element(name);
+        if let Some(href) = href {
+            element.set_string_attribute(&local_name!("href"), href.clone());
+        }
+        self.insert_node(element.upcast(), &parent, Some(node));
+        self.move_node(node, element.upcast(), None);
+    }
+
+    /// Moves `node` out of the element that gives it the style, splitting the element
+    /// around it.
+    fn remove_style(&self, node: &Node, style: InlineStyle) {
+        let element = match style_ancestor(self.host, node, style) {
+            Some(element) => element,
+            None => return,
+        };
+        let element = element.upcast::<Node>();
+        let parent = node.GetParentNode().unwrap();
+        let after = self.split_at(element, &parent, node.index() + 1);
+        let parent = node.GetParentNode().unwrap();
+        let middle = self.split_at(element, &parent, node.index());
+
+        let outer = middle.GetParentNode().unwrap();
+        let children: Vec<DomRoot<Node>> = middle.children().collect();
+        for child in children {
+            self.move_node(&child, &outer, Some(&middle));
+        }
+        self.remove_node(&middle);
+        self.remove_if_empty(&after);
+        self.remove_if_empty(element);
+    }
+
+    /// Applies the style to the selection, or removes it if all of the selection has it.
+    /// A collapsed selection moves into a new element with the style, or out of the
+    /// element that gives the style, so that the text typed next has the style or not.
+    fn toggle_style(&self, style: InlineStyle) {
+        let range = match self.range() {
+            Some(range) => range,
+            None => return,
+        };
+        if range.Collapsed() {
+            let (node, offset) = (range.StartContainer(), range.StartOffset());
+            match style_ancestor(self.host, &node, style) {
+                Some(element) => {
+                    let element = element.upcast::<Node>();
+                    let clone = self.split_at(element, &node, offset);
+                    self.remove_if_empty(&clone);
+                    if let Some(parent) = element.GetParentNode() {
+                        self.collapse(&parent, element.index() + 1);
+                    }
+                },
+                None => {
+                    let element = self.create_element(style.element_name());
+                    self.insert_node_at(element.upcast(), &node, offset);
+                    self.collapse(element.upcast(), 0);
+                },
+            }
+            return;
+        }
+
+        let texts = self.select_text_nodes();
+        if texts
+            .iter()
+            .all(|text| style_ancestor(self.host, text, style).is_some())
+        {
+            for text in &texts {
+                self.remove_style(text, style);
+            }
+        } else {
+            for text in &texts {
+                if style_ancestor(self.host, text, style).is_none() {
+                    self.wrap(text, style.element_name(), None);
+                }
+            }
+        }
+        self.select_nodes(&texts);
+    }
+
+    /// Makes the selection a link to `url`, or inserts a link with `url` as its text at
+    /// a collapsed selection.
+    fn create_link(&self, url: &DOMString) {
+        let range = match self.range() {
+            Some(range) => range,
+            None => return,
+        };
+        if range.Collapsed() {
+            let link = self.create_element(local_name!("a"));
+            link.set_string_attribute(&local_name!("href"), url.clone());
+            let text = Text::new(url.clone(), self.document);
+            let link = link.upcast::<Node>();
+            let _ = link.AppendChild(text.upcast());
+            self.insert_node_at(link, &range.StartContainer(), range.StartOffset());
+            let (parent, index) = end_of_leaf(link);
+            self.collapse(&parent, index);
+            return;
+        }
+
+        let texts = self.select_text_nodes();
+        for text in &texts {
+            let in_link = text
+                .inclusive_ancestors(ShadowIncluding::No)
+                .take_while(|ancestor| &**ancestor != self.host)
+                .filter_map(DomRoot::downcast::<Element>)
+                .any(|element| *element.local_name() == local_name!("a"));
+            if !in_link {
+                self.wrap(text, local_name!("a"), Some(url));
+            }
+        }
+        self.select_nodes(&texts);
+    }
+
+    /// The position before the boundary point that the caret moves to, skipping the
+    /// position at the end of the previous text node that looks the same as it.
+    fn position_before(&self, node: &Node, offset: u32) -> Option<(DomRoot<Node>, u32)> {
+        if let Some(text) = node.downcast::<Text>() {
+            if offset > 0 {
+                let length = char_len_before(text.upcast(), offset);
+                return Some((DomRoot::from_ref(node), offset - length));
+            }
+        }
+        let last = leaf_before(self.host, node, offset)?;
+        let same_line = block_of(&last, self.host) == block_of(node, self.host);
+        Some(match last.downcast::<CharacterData>() {
+            Some(text) if same_line => {
+                let end = text.Length();
+                (DomRoot::from_ref(&*last), end - char_len_before(text, end))
+            },
+            _ if last.is::<Text>() => end_of_leaf(&last),
+            _ => start_of_leaf(&last),
+        })
+    }
+
+    /// The position after the boundary point that the caret moves to, skipping the
+    /// position at the start of the next text node that looks the same as it.
+    fn position_after(&self, node: &Node, offset: u32) -> Option<(DomRoot<Node>, u32)> {
+        if let Some(text) = node.downcast::<Text>() {
+            if offset < node.len() {
+                let length = char_len_after(text.upcast(), offset);
+                return Some((DomRoot::from_ref(node), offset + length));
+            }
+        }
+        let first = leaf_after(self.host, node, offset)?;
+        let same_line = block_of(&first, self.host) == block_of(node, self.host);
+        Some(match first.downcast::<CharacterData>() {
+            Some(text) if same_line => (DomRoot::from_ref(&*first), char_len_after(text, 0)),
+            _ if first.is::<Text>() => start_of_leaf(&first),
+            _ => end_of_leaf(&first),
+        })
+    }
+
+    /// Moves the caret, or the focus of the selection if `extend` is true, by one
+    /// character.
+    fn move_horizontally(&self, forward: bool, extend: bool) {
+        let range = match self.range() {
+            Some(range) => range,
+            None => return,
+        };
+        if !extend && !range.Collapsed() {
+            if forward {
+                self.collapse(&range.EndContainer(), range.EndOffset());
+            } else {
+                self.collapse(&range.StartContainer(), range.StartOffset());
+            }
+            return;
+        }
+        let (node, offset) = self.focus();
+        let position = if forward {
+            self.position_after(&node, offset)
+        } else {
+            self.position_before(&node, offset)
+        };
+        if let Some((node, offset)) = position {
+            self.move_focus(&node, offset, extend);
+        }
+    }
+
+    /// Moves the caret, or the focus of the selection if `extend` is true, along the
+    /// lines that layout laid the contents of the editing host out in.
+    fn move_by_line(&self, motion: LineMotion, extend: bool) {
+        let window = self.document.window();
+        let (node, offset) = self.focus();
+        let (node, offset) = layout_position(&node, offset);
+        let rect = match window.caret_rect_query(&node, offset) {
+            Some(rect) => rect,
+            None => return,
+        };
+        let (x, y) = (rect.origin.x.to_f32_px(), rect.origin.y.to_f32_px());
+        let height = rect.size.height.to_f32_px();
+        let point = match motion {
+            LineMotion::LineStart => Point2D::new(f32::MIN, y + height / 2.),
+            LineMotion::LineEnd => Point2D::new(f32::MAX, y + height / 2.),
+            LineMotion::PreviousLine => Point2D::new(x, y - height / 2.),
+            LineMotion::NextLine => Point2D::new(x, y + height * 1.5),
+        };
+        if let Some((node, offset)) = window.text_position_query(self.host, point) {
+            let offset = node
+                .downcast::<CharacterData>()
+                .map_or(0, |text| utf8_offset_to_utf16(&text.data(), offset));
+            self.move_focus(&node, offset, extend);
+        }
+    }
+
+    fn move_focus(&self, node: &Node, offset: u32, extend: bool) {
+        if extend {
+            let _ = self.selection.Extend(node, offset);
+        } else {
+            self.collapse(node, offset);
+        }
+    }
+
+    /// Shows the text being composed at the caret, outside of the undo history.
+    fn show_composition(&self, data: &str) {
+        self.delete_selection();
+        if data.is_empty() {
+            return;
+        }
+        let (node, offset) = self.start();
+        let (node, offset) = self.text_node_at(&node, offset);
+        let text = node.downcast::<CharacterData>().unwrap();
+        let _ = text.InsertData(offset, DOMString::from(data));
+        let length = utf16_len(data);
+        self.collapse(&node, offset + length);
+        self.document.editing_state().borrow_mut().composition = Some(CompositionPreview {
+            node: Dom::from_ref(text),
+            offset,
+            length,
+        });
+    }
+
+    /// Removes the text being composed that was shown at the caret, if any.
+    fn hide_composition(&self) {
+        let state = self.document.editing_state();
+        if let Some(ref preview) = state.borrow().composition {
+            let _ = preview.node.DeleteData(preview.offset, preview.length);
+            self.collapse(preview.node.upcast(), preview.offset);
+        }
+        state.borrow_mut().composition = None;
+    }
+}
+
+/// Makes `edit` at the selection in `host`, and returns whether it changed anything.
+fn apply_edit(document: &Document, host: &Node, edit: &Edit) -> bool {
+    let selection = match document.GetSelection() {
+        Some(selection) => selection,
+        None => return false,
+    };
+    match *edit {
+        Edit::Undo => return undo(document, &selection),
+        Edit::Redo => return redo(document, &selection),
+        _ => {},
+    }
+
+    let editor = Editor {
+        document,
+        host,
+        selection: &selection,
+    };
+    editor.ensure_selection();
+    document.editing_state().borrow_mut().begin(&selection);
+    match *edit {
+        Edit::Bold => editor.toggle_style(InlineStyle::Bold),
+        Edit::CreateLink(ref url) => editor.create_link(url),
+        Edit::Cut => {
+            editor.delete_selection();
+        },
+        Edit::DeleteBackward => editor.delete_backward(),
+        Edit::DeleteForward => editor.delete_forward(),
+        Edit::InsertLineBreak => editor.insert_line_break(),
+        Edit::InsertParagraph => editor.insert_paragraph(),
+        Edit::Composition(ref text) | Edit::InsertText(ref text) => editor.insert_text(text),
+        Edit::Italic => editor.toggle_style(InlineStyle::Italic),
+        Edit::Paste(ref text) => editor.insert_lines(text),
+        Edit::Undo | Edit::Redo => unreachable!(),
+    }
+    document.editing_state().borrow_mut().end(&selection)
+}
+
+/// Fires an input event at `host`, and returns whether it wasn't canceled. Only
+/// `beforeinput` can be canceled.
+///
+/// <https://w3c.github.io/input-events/#events-inputevents>
+fn fire_input_event(
+    document: &Document,
+    host: &Node,
+    type_: &str,
+    input_type: &str,
+    data: Option<DOMString>,
+    is_composing: bool,
+) -> bool {
+    let window = document.window();
+    let event = InputEvent::new(
+        window,
+        DOMString::from(type_),
+        true,
+        type_ == "beforeinput",
+        Some(window),
+        0,
+        data,
+        is_composing,
+        DOMString::from(input_type),
+    );
+    let event = event.upcast::<Event>();
+    event.set_trusted(true);
+    event.fire(host.upcast()) == EventStatus::NotCanceled
+}
+
+/// Makes an edit of the user in `host`, firing `beforeinput` before it, which can cancel
+/// it, and `input` after it.
+fn perform_user_edit(document: &Document, host: &Node, edit: &Edit) {
+    if !fire_input_event(
+        document,
+        host,
+        "beforeinput",
+        edit.input_type(),
+        edit.data(),
+        false,
+    ) {
+        return;
+    }
+    // The listeners may have made `host` uneditable.
+    if !host.is_editing_host() {
+        return;
+    }
+    if apply_edit(document, host, edit) {
+        host.dirty(NodeDamage::OtherNodeDamage);
+        fire_input_event(
+            document,
+            host,
+            "input",
+            edit.input_type(),
+            edit.data(),
+            false,
+        );
+    }
+}
+
+/// Runs the default action of a `keydown` event in the focused editing host, and
+/// returns whether it had one.
+pub fn handle_keydown(document: &Document, event: &KeyboardEvent) -> bool {
+    let host = match focused_editing_host(document) {
+        Some(host) => host,
+        None => return false,
+    };
+    let selection = match document.GetSelection() {
+        Some(selection) => selection,
+        None => return false,
+    };
+    let host = host.upcast::<Node>();
+    let editor = Editor {
+        document,
+        host,
+        selection: &selection,
+    };
+    editor.ensure_selection();
+
+    let key = event.key();
+    let mut mods = event.modifiers();
+    let extend = mods.contains(Modifiers::SHIFT);
+    mods.remove(Modifiers::SHIFT);
+    let move_horizontally = |forward| {
+        editor.move_horizontally(forward, extend);
+        KeyAction::MoveCaret
+    };
+    let move_by_line = |motion| {
+        editor.move_by_line(motion, extend);
+        KeyAction::MoveCaret
+    };
+    let action = ShortcutMatcher::new(KeyState::Down, key.clone(), mods)
+        .shortcut(CMD_OR_CONTROL, 'A', || {
+            editor.select_all();
+            KeyAction::MoveCaret
+        })
+        .shortcut(CMD_OR_CONTROL, 'B', || KeyAction::Edit(Edit::Bold))
+        .shortcut(CMD_OR_CONTROL, 'I', || KeyAction::Edit(Edit::Italic))
+        .shortcut(CMD_OR_CONTROL, 'Y', || KeyAction::Edit(Edit::Redo))
+        .shortcut(CMD_OR_CONTROL, 'Z', || {
+            KeyAction::Edit(if extend { Edit::Redo } else { Edit::Undo })
+        })
+        .shortcut(Modifiers::empty(), Key::Backspace, || {
+            KeyAction::Edit(Edit::DeleteBackward)
+        })
+        .shortcut(Modifiers::empty(), Key::Delete, || {
+            KeyAction::Edit(Edit::DeleteForward)
+        })
+        .shortcut(Modifiers::empty(), Key::Enter, || {
+            KeyAction::Edit(if extend {
+                Edit::InsertLineBreak
+            } else {
+                Edit::InsertParagraph
+            })
+        })
+        .shortcut(Modifiers::empty(), Key::ArrowLeft, || {
+            move_horizontally(false)
+        })
+        .shortcut(Modifiers::empty(), Key::ArrowRight, || {
+            move_horizontally(true)
+        })
+        .shortcut(Modifiers::empty(), Key::ArrowUp, || {
+            move_by_line(LineMotion::PreviousLine)
+        })
+        .shortcut(Modifiers::empty(), Key::ArrowDown, || {
+            move_by_line(LineMotion::NextLine)
+        })
+        .shortcut(Modifiers::empty(), Key::Home, || {
+            move_by_line(LineMotion::LineStart)
+        })
+        .shortcut(Modifiers::empty(), Key::End, || {
+            move_by_line(LineMotion::LineEnd)
+        })
+        .otherwise(|| match key {
+            Key::Character(ref c) if !mods.intersects(Modifiers::CONTROL | Modifiers::META) => {
+                KeyAction::Edit(Edit::InsertText(DOMString::from(c.as_str())))
+            },
+            _ => KeyAction::Nothing,
+        })
+        .unwrap();
+
+    match action {
+        KeyAction::Edit(edit) => perform_user_edit(document, host, &edit),
+        KeyAction::MoveCaret => host.dirty(NodeDamage::OtherNodeDamage),
+        KeyAction::Nothing => return false,
+    }
+    true
+}
+
+/// Moves the caret to the text closest to where the user pressed the mouse button down
+/// in an editing host, given in client coordinates.
+pub fn handle_mousedown(document: &Document, target: &Element, client_point: Point2D<f32>) {
+    let host = match target.upcast::<Node>().editing_host() {
+        Some(host) => host,
+        None => return,
+    };
+    let selection = match document.GetSelection() {
+        Some(selection) => selection,
+        None => return,
+    };
+    let window = document.window();
+    let viewport = window.current_viewport();
+    let point = Point2D::new(
+        client_point.x + viewport.origin.x.to_f32_px(),
+        client_point.y + viewport.origin.y.to_f32_px(),
+    );
+    let host = host.upcast::<Node>();
+    let (node, offset) = match window.text_position_query(host, point) {
+        Some((node, offset)) => {
+            let offset = node
+                .downcast::<CharacterData>()
+                .map_or(0, |text| utf8_offset_to_utf16(&text.data(), offset));
+            (node, offset)
+        },
+        None => (DomRoot::from_ref(host), 0),
+    };
+    let _ = selection.Collapse(Some(&node), offset);
+    host.dirty(NodeDamage::OtherNodeDamage);
+}
+
+/// Shows the text of an input method composition in the focused editing host while it
+/// is being composed, and inserts it once it is committed.
+pub fn handle_composition_event(document: &Document, event: &CompositionEvent) {
+    let host = match focused_editing_host(document) {
+        Some(host) => host,
+        None => return,
+    };
+    let selection = match document.GetSelection() {
+        Some(selection) => selection,
+        None => return,
+    };
+    let host = host.upcast::<Node>();
+    let editor = Editor {
+        document,
+        host,
+        selection: &selection,
+    };
+    editor.ensure_selection();
+    editor.hide_composition();
+    match event.state {
+        CompositionState::Start => {},
+        CompositionState::Update => {
+            editor.show_composition(&event.data);
+            fire_input_event(
+                document,
+                host,
+                "input",
+                "insertCompositionText",
+                Some(DOMString::from(event.data.clone())),
+                true,
+            );
+        },
+        CompositionState::End => {
+            let edit = Edit::Composition(DOMString::from(event.data.clone()));
+            perform_user_edit(document, host, &edit);
+        },
+    }
+    host.dirty(NodeDamage::OtherNodeDamage);
+}
+
+/// Deletes the selection in the focused editing host, as the default action of a `cut`
+/// event.
+pub fn cut(document: &Document) {
+    if let Some(host) = focused_editing_host(document) {
+        perform_user_edit(document, host.upcast(), &Edit::Cut);
+    }
+}
+
+/// Replaces the selection in the focused editing host with `text`, as the default
+/// action of a `paste` event.
+pub fn paste(document: &Document, text: DOMString) {
+    if let Some(host) = focused_editing_host(document) {
+        perform_user_edit(document, host.upcast(), &Edit::Paste(text));
+    }
+}
+
+/// <https://w3c.github.io/editing/docs/execCommand/#execcommand()>
+pub fn exec_command(document: &Document, command: &str, value: DOMString) -> bool {
+    // Step 1 - 2.
+    let command = match Command::from_name(command) {
+        Some(command) => command,
+        None => return false,
+    };
+    if command == Command::SelectAll {
+        select_all(document);
+        return true;
+    }
+    let host = match selection_editing_host(document) {
+        Some(host) => host,
+        None => return false,
+    };
+    let host = host.upcast::<Node>();
+
+    // Step 3 - 6.
+    let edit = match command {
+        Command::Bold => Edit::Bold,
+        Command::CreateLink if value.is_empty() => return false,
+        Command::CreateLink => Edit::CreateLink(value),
+        Command::Delete => Edit::DeleteBackward,
+        Command::ForwardDelete => Edit::DeleteForward,
+        Command::InsertLineBreak => Edit::InsertLineBreak,
+        Command::InsertParagraph => Edit::InsertParagraph,
+        Command::InsertText => Edit::InsertText(value),
+        Command::Italic => Edit::Italic,
+        Command::Redo => Edit::Redo,
+        Command::SelectAll => unreachable!(),
+        Command::Undo => Edit::Undo,
+    };
+    if apply_edit(document, host, &edit) {
+        host.dirty(NodeDamage::OtherNodeDamage);
+        fire_input_event(
+            document,
+            host,
+            "input",
+            edit.input_type(),
+            edit.data(),
+            false,
+        );
+    }
+    true
+}
+
+/// Selects the contents of the editing host that the selection is in, or else of the
+/// body of the document.
+///
+/// <https://w3c.github.io/editing/docs/execCommand/#the-selectall-command>
+fn select_all(document: &Document) {
+    let root = selection_editing_host(document)
+        .or_else(|| document.GetBody().map(DomRoot::upcast))
+        .or_else(|| document.GetDocumentElement());
+    if let (Some(selection), Some(root)) = (document.GetSelection(), root) {
+        let _ = selection.SelectAllChildren(root.upcast());
+    }
+}
+
+/// Whether some and whether all of the selected text has the style, or the text at a
+/// collapsed selection.
+fn style_state(document: &Document, style: InlineStyle) -> (bool, bool) {
+    let host = match selection_editing_host(document) {
+        Some(host) => host,
+        None => return (false, false),
+    };
+    let host = host.upcast::<Node>();
+    let range = match document
+        .GetSelection()
+        .and_then(|selection| selection.GetRangeAt(0).ok())
+    {
+        Some(range) => range,
+        None => return (false, false),
+    };
+    let (start, start_offset) = (range.StartContainer(), range.StartOffset());
+    let (end, end_offset) = (range.EndContainer(), range.EndOffset());
+    if range.Collapsed() {
+        let (node, _) = layout_position(&start, start_offset);
+        let styled = style_ancestor(host, &node, style).is_some();
+        return (styled, styled);
+    }
+
+    let styled: Vec<bool> = leaves(host)
+        .into_iter()
+        .filter(|leaf| {
+            leaf.is::<Text>() &&
+                bp_position(leaf, leaf.len(), &start, start_offset) == Some(Ordering::Greater) &&
+                bp_position(leaf, 0, &end, end_offset) == Some(Ordering::Less)
+        })
+        .map(|text| style_ancestor(host, &text, style).is_some())
+        .collect();
+    (
+        styled.iter().any(|styled| *styled),
+        !styled.is_empty() && styled.iter().all(|styled| *styled),
+    )
+}
+
+/// <https://w3c.github.io/editing/docs/execCommand/#querycommandsupported()>
+pub fn query_command_supported(command: &str) -> bool {
+    Command::from_name(command).is_some()
+}
+
+/// <https://w3c.github.io/editing/docs/execCommand/#querycommandenabled()>
+pub fn query_command_enabled(document: &Document, command: &str) -> bool {
+    match Command::from_name(command) {
+        Some(Command::SelectAll) => true,
+        Some(_) => selection_editing_host(document).is_some(),
+        None => false,
+    }
+}
+
+/// <https://w3c.github.io/editing/docs/execCommand/#querycommandindeterm()>
+pub fn query_command_indeterm(document: &Document, command: &str) -> bool {
+    match Command::from_name(command).and_then(|command| command.style()) {
+        Some(style) => {
+            let (any, all) = style_state(document, style);
+            any && !all
+        },
+        None => false,
+    }
+}
+
+/// <https://w3c.github.io/editing/docs/execCommand/#querycommandstate()>
+pub fn query_command_state(document: &Document, command: &str) -> bool {
+    match Command::from_name(command).and_then(|command| command.style()) {
+        Some(style) => style_state(document, style).1,
+        None => false,
+    }
+}
+
+/// <https://w3c.github.io/editing/docs/execCommand/#querycommandvalue()>
+pub fn query_command_value(document: &Document, command: &str) -> DOMString {
+    match Command::from_name(command).and_then(|command| command.style()) {
+        Some(style) => DOMString::from(style_state(document, style).1.to_string()),
+        None => DOMString::new(),
+    }
+}
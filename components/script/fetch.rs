@@ -2,15 +2,18 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
+use crate::body::BodyMixin;
+use crate::dom::abortsignal::{AbortAlgorithm, AbortSignal};
 use crate::dom::bindings::codegen::Bindings::RequestBinding::RequestInfo;
 use crate::dom::bindings::codegen::Bindings::RequestBinding::RequestInit;
+use crate::dom::bindings::codegen::Bindings::RequestBinding::RequestMethods;
 use crate::dom::bindings::codegen::Bindings::ResponseBinding::ResponseBinding::ResponseMethods;
 use crate::dom::bindings::codegen::Bindings::ResponseBinding::ResponseType as DOMResponseType;
 use crate::dom::bindings::error::Error;
 use crate::dom::bindings::inheritance::Castable;
 use crate::dom::bindings::refcounted::{Trusted, TrustedPromise};
 use crate::dom::bindings::reflector::DomObject;
-use crate::dom::bindings::root::DomRoot;
+use crate::dom::bindings::root::{Dom, DomRoot};
 use crate::dom::bindings::trace::RootedTraceableBox;
 use crate::dom::globalscope::GlobalScope;
 use crate::dom::headers::Guard;
@@ -24,6 +27,7 @@ use crate::network_listener::{
 };
 use crate::realms::{enter_realm, InRealm};
 use crate::task_source::TaskSourceName;
+use content_security_policy as csp;
 use ipc_channel::ipc;
 use ipc_channel::router::ROUTER;
 use net_traits::request::{
@@ -37,12 +41,55 @@ use net_traits::{FetchMetadata, FilteredMetadata, Metadata};
 use net_traits::{ResourceFetchTiming, ResourceTimingType};
 use servo_url::ServoUrl;
 use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 
 struct FetchContext {
     fetch_promise: Option<TrustedPromise>,
     response_object: Trusted<Response>,
     resource_timing: ResourceFetchTiming,
+    /// Set once the fetch is aborted, after which what `net` sends is ignored.
+    aborted: Arc<AtomicBool>,
+}
+
+/// Aborts a call to `fetch()` when the signal of its request is aborted.
+#[derive(JSTraceable, MallocSizeOf)]
+#[unrooted_must_root_lint::must_root]
+pub struct FetchAbortAlgorithm {
+    #[ignore_malloc_size_of = "Rc"]
+    promise: Rc<Promise>,
+    response: Dom<Response>,
+    aborted: Arc<AtomicBool>,
+    #[ignore_malloc_size_of = "channels are hard"]
+    cancel_chan: ipc::IpcSender<()>,
+}
+
+impl FetchAbortAlgorithm {
+    #[allow(unrooted_must_root)]
+    pub fn abort(self, signal: &AbortSignal) {
+        self.aborted.store(true, Ordering::Release);
+        // The fetch may have completed already, in which case nobody is listening.
+        let _ = self.cancel_chan.send(());
+        abort_fetch(&self.promise, Some(&self.response), signal);
+    }
+}
+
+/// <https://fetch.spec.whatwg.org/#abort-fetch>
+fn abort_fetch(promise: &Promise, response: Option<&Response>, signal: &AbortSignal) {
+    let global = promise.global();
+    let _ac = enter_realm(&*global);
+    let cx = global.get_cx();
+    rooted!(in(*cx) let reason = signal.reason());
+    // Step 2.
+    if !promise.is_fulfilled() {
+        promise.reject(cx, reason.handle());
+    }
+    // Steps 3-5.
+    // TODO: cancel the body stream of the request.
+    // Step 6.
+    if let Some(body) = response.and_then(|response| response.body()) {
+        body.error_if_readable(reason.handle());
+    }
 }
 
 /// RAII fetch canceller object. By default initialized to not having a canceller
@@ -122,11 +169,14 @@ fn request_init_from_request(request: NetTraitsRequest) -> RequestBuilder {
         pipeline_id: request.pipeline_id,
         redirect_mode: request.redirect_mode,
         integrity_metadata: request.integrity_metadata.clone(),
+        cryptographic_nonce_metadata: request.cryptographic_nonce_metadata.clone(),
         url_list: vec![],
         parser_metadata: request.parser_metadata,
         initiator: request.initiator,
         csp_list: None,
         https_state: request.https_state,
+        has_trustworthy_ancestor_origin: request.has_trustworthy_ancestor_origin,
+        embedder_policy: request.embedder_policy,
     }
 }
 
@@ -138,25 +188,31 @@ pub fn Fetch(
     init: RootedTraceableBox<RequestInit>,
     comp: InRealm,
 ) -> Rc<Promise> {
-    let core_resource_thread = global.core_resource_thread();
-
     // Step 1
     let promise = Promise::new_in_current_realm(global, comp);
     let response = Response::new(global);
 
     // Step 2
-    let request = match Request::Constructor(global, input, init) {
+    let request_object = match Request::Constructor(global, input, init) {
         Err(e) => {
             response.error_stream(e.clone());
             promise.reject_error(e);
             return promise;
         },
-        Ok(r) => r.get_request(),
+        Ok(r) => r,
     };
+    let request = request_object.get_request();
+    let signal = request_object.Signal();
+    if signal.aborted() {
+        abort_fetch(&promise, None, &signal);
+        return promise;
+    }
     let timing_type = request.timing_type();
 
     let mut request_init = request_init_from_request(request);
     request_init.csp_list = global.get_csp_list().clone();
+    request_init.has_trustworthy_ancestor_origin = global.has_trustworthy_ancestor_origin();
+    request_init.embedder_policy = global.get_embedder_policy();
 
     // Step 3
     if global.downcast::<ServiceWorkerGlobalScope>().is_some() {
@@ -167,11 +223,65 @@ pub fn Fetch(
     response.Headers().set_guard(Guard::Immutable);
 
     // Step 5
+    let (cancel_sender, cancel_receiver) = ipc::channel().unwrap();
+    let aborted = Arc::new(AtomicBool::new(false));
+    signal.add_abort_algorithm(AbortAlgorithm::Fetch(FetchAbortAlgorithm {
+        promise: promise.clone(),
+        response: Dom::from_ref(&*response),
+        aborted: aborted.clone(),
+        cancel_chan: cancel_sender,
+    }));
+    start_fetch(
+        global,
+        &promise,
+        &response,
+        request_init,
+        timing_type,
+        aborted,
+        Some(cancel_receiver),
+    );
+
+    promise
+}
+
+/// The preload request of a navigation handled by a service worker, whose response
+/// the `preloadResponse` promise of the `FetchEvent` resolves to.
+/// <https://w3c.github.io/ServiceWorker/#handle-fetch>
+pub fn fetch_navigation_preload(global: &GlobalScope, request: RequestBuilder) -> Rc<Promise> {
+    let promise = Promise::new(global);
+    let response = Response::new(global);
+    response.Headers().set_guard(Guard::Immutable);
+    start_fetch(
+        global,
+        &promise,
+        &response,
+        request,
+        ResourceTimingType::Navigation,
+        Arc::new(AtomicBool::new(false)),
+        None,
+    );
+    promise
+}
+
+/// Step 5 of <https://fetch.spec.whatwg.org/#fetch-method>, fetching `request` from `net`
+/// and resolving `promise` with `response` once its metadata are received.
+fn start_fetch(
+    global: &GlobalScope,
+    promise: &Rc<Promise>,
+    response: &Response,
+    request: RequestBuilder,
+    timing_type: ResourceTimingType,
+    aborted: Arc<AtomicBool>,
+    cancel_receiver: Option<ipc::IpcReceiver<()>>,
+) {
     let (action_sender, action_receiver) = ipc::channel().unwrap();
+    let (body_control_sender, body_control_receiver) = ipc::channel().unwrap();
+    response.set_back_pressure_chan(body_control_sender);
     let fetch_context = Arc::new(Mutex::new(FetchContext {
         fetch_promise: Some(TrustedPromise::new(promise.clone())),
-        response_object: Trusted::new(&*response),
+        response_object: Trusted::new(response),
         resource_timing: ResourceFetchTiming::new(timing_type),
+        aborted: aborted,
     }));
     let listener = NetworkListener {
         context: fetch_context,
@@ -185,14 +295,13 @@ pub fn Fetch(
             listener.notify_fetch(message.to().unwrap());
         }),
     );
-    core_resource_thread
+    global
+        .core_resource_thread()
         .send(NetTraitsFetch(
-            request_init,
-            FetchChannels::ResponseMsg(action_sender, None),
+            request,
+            FetchChannels::ResponseMsg(action_sender, cancel_receiver, Some(body_control_receiver)),
         ))
         .unwrap();
-
-    promise
 }
 
 impl PreInvoke for FetchContext {}
@@ -208,6 +317,9 @@ impl FetchResponseListener for FetchContext {
 
     #[allow(unrooted_must_root)]
     fn process_response(&mut self, fetch_metadata: Result<FetchMetadata, NetworkError>) {
+        if self.aborted.load(Ordering::Acquire) {
+            return;
+        }
         let promise = self
             .fetch_promise
             .take()
@@ -261,11 +373,17 @@ impl FetchResponseListener for FetchContext {
     }
 
     fn process_response_chunk(&mut self, chunk: Vec<u8>) {
+        if self.aborted.load(Ordering::Acquire) {
+            return;
+        }
         let response = self.response_object.root();
         response.stream_chunk(chunk);
     }
 
     fn process_response_eof(&mut self, _response: Result<ResourceFetchTiming, NetworkError>) {
+        if self.aborted.load(Ordering::Acquire) {
+            return;
+        }
         let response = self.response_object.root();
         let _ac = enter_realm(&*response);
         response.finish();
@@ -281,6 +399,11 @@ impl FetchResponseListener for FetchContext {
         &self.resource_timing
     }
 
+    fn process_csp_violations(&mut self, violations: Vec<csp::Violation>) {
+        let global = &self.resource_timing_global();
+        global.report_csp_violations(violations, None);
+    }
+
     fn submit_resource_timing(&mut self) {
         // navigation submission is handled in servoparser/mod.rs
         match self.resource_timing.timing_type {
@@ -322,7 +445,7 @@ pub fn load_whole_resource(
     core_resource_thread
         .send(CoreResourceMsg::Fetch(
             request,
-            FetchChannels::ResponseMsg(action_sender, None),
+            FetchChannels::ResponseMsg(action_sender, None, None),
         ))
         .unwrap();
 
@@ -331,6 +454,9 @@ pub fn load_whole_resource(
     loop {
         match action_receiver.recv().unwrap() {
             FetchResponseMsg::ProcessRequestBody | FetchResponseMsg::ProcessRequestEOF => (),
+            FetchResponseMsg::ProcessCspViolations(violations) => {
+                global.report_csp_violations(violations, None)
+            },
             FetchResponseMsg::ProcessResponse(Ok(m)) => {
                 metadata = Some(match m {
                     FetchMetadata::Unfiltered(m) => m,
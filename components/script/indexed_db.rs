@@ -0,0 +1,563 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Conversions between JavaScript values and IndexedDB keys, the key path algorithms,
+//! and the messaging with the engine, shared by the `IDB*` interfaces.
+
+use crate::dom::bindings::codegen::Bindings::IDBCursorBinding::IDBCursorDirection;
+use crate::dom::bindings::codegen::UnionTypes::StringOrStringSequence;
+use crate::dom::bindings::conversions::ToJSValConvertible;
+use crate::dom::bindings::conversions::{jsstring_to_str, root_from_handlevalue};
+use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::refcounted::Trusted;
+use crate::dom::bindings::reflector::DomObject;
+use crate::dom::bindings::root::DomRoot;
+use crate::dom::bindings::str::DOMString;
+use crate::dom::bindings::structuredclone;
+use crate::dom::domexception::{DOMErrorName, DOMException};
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::idbkeyrange::IDBKeyRange;
+use crate::script_runtime::JSContext;
+use crate::task_source::{TaskSource, TaskSourceName};
+use ipc_channel::ipc::{IpcReceiver, IpcSender};
+use ipc_channel::router::ROUTER;
+use js::jsapi::{ClippedTime, DateGetMsecSinceEpoch, JSObject, JS_DefineElement};
+use js::jsapi::{JS_GetStringLength, JS_NewArrayObject1, JS_NewPlainObject};
+use js::jsapi::{NewDateObject, ObjectIsDate, JSPROP_ENUMERATE};
+use js::jsval::{DoubleValue, ObjectValue, UndefinedValue};
+use js::rust::jsapi_wrapped::{JS_GetArrayLength, JS_GetElement};
+use js::rust::wrappers::{JS_GetProperty, JS_HasOwnProperty, JS_IsArrayObject, JS_SetProperty};
+use js::rust::{HandleObject, HandleValue, MutableHandleValue};
+use js::typedarray::{ArrayBuffer, ArrayBufferView, CreateWith};
+use net_traits::indexeddb_thread::{IndexedDBCursorDirection, IndexedDBError};
+use net_traits::indexeddb_thread::{IndexedDBKeyRange, IndexedDBKeyType};
+use net_traits::indexeddb_thread::{IndexedDBResult, IndexedDBThreadMsg, KeyPath};
+use net_traits::storage_thread::StorageThreadMsg;
+use net_traits::IpcSend;
+use profile_traits::ipc;
+use script_traits::StructuredSerializedData;
+use serde::{Deserialize, Serialize};
+use std::ffi::CString;
+use std::ptr;
+
+/// <https://w3c.github.io/IndexedDB/#convert-a-value-to-a-key>
+pub fn convert_value_to_key(cx: JSContext, input: HandleValue) -> Fallible<IndexedDBKeyType> {
+    convert_value_to_key_with_seen(cx, input, &mut vec![])
+}
+
+#[allow(unsafe_code)]
+fn convert_value_to_key_with_seen(
+    cx: JSContext,
+    input: HandleValue,
+    seen: &mut Vec<*mut JSObject>,
+) -> Fallible<IndexedDBKeyType> {
+    if input.is_number() {
+        let number = input.to_number();
+        if number.is_nan() {
+            return Err(Error::Data);
+        }
+        return Ok(IndexedDBKeyType::Number(number));
+    }
+    if input.is_string() {
+        let string = unsafe { jsstring_to_str(*cx, input.to_string()) };
+        return Ok(IndexedDBKeyType::String(string.into()));
+    }
+    if !input.is_object() {
+        return Err(Error::Data);
+    }
+
+    rooted!(in(*cx) let object = input.to_object());
+    unsafe {
+        let mut is_date = false;
+        if !ObjectIsDate(*cx, object.handle().into(), &mut is_date) {
+            return Err(Error::JSFailed);
+        }
+        if is_date {
+            let mut milliseconds = 0.;
+            if !DateGetMsecSinceEpoch(*cx, object.handle().into(), &mut milliseconds) {
+                return Err(Error::JSFailed);
+            }
+            if milliseconds.is_nan() {
+                return Err(Error::Data);
+            }
+            return Ok(IndexedDBKeyType::Date(milliseconds));
+        }
+
+        typedarray!(in(*cx) let buffer: ArrayBuffer = object.get());
+        if let Ok(buffer) = buffer {
+            return Ok(IndexedDBKeyType::Binary(buffer.to_vec()));
+        }
+        typedarray!(in(*cx) let view: ArrayBufferView = object.get());
+        if let Ok(view) = view {
+            return Ok(IndexedDBKeyType::Binary(view.to_vec()));
+        }
+
+        let mut is_array = false;
+        if !JS_IsArrayObject(*cx, input, &mut is_array) {
+            return Err(Error::JSFailed);
+        }
+        if !is_array || seen.contains(&object.get()) {
+            return Err(Error::Data);
+        }
+        seen.push(object.get());
+        let mut length = 0;
+        if !JS_GetArrayLength(*cx, object.handle(), &mut length) {
+            return Err(Error::JSFailed);
+        }
+        let mut keys = Vec::with_capacity(length as usize);
+        for index in 0..length {
+            rooted!(in(*cx) let mut entry = UndefinedValue());
+            if !JS_GetElement(*cx, object.handle(), index, &mut entry.handle_mut()) {
+                return Err(Error::JSFailed);
+            }
+            keys.push(convert_value_to_key_with_seen(cx, entry.handle(), seen)?);
+        }
+        seen.pop();
+        Ok(IndexedDBKeyType::Array(keys))
+    }
+}
+
+/// <https://w3c.github.io/IndexedDB/#convert-a-value-to-a-multientry-key>
+#[allow(unsafe_code)]
+fn convert_value_to_multi_entry_key(
+    cx: JSContext,
+    input: HandleValue,
+) -> Fallible<Vec<IndexedDBKeyType>> {
+    let mut is_array = false;
+    if input.is_object() && unsafe { !JS_IsArrayObject(*cx, input, &mut is_array) } {
+        return Err(Error::JSFailed);
+    }
+    if !is_array {
+        return Ok(vec![convert_value_to_key(cx, input)?]);
+    }
+    rooted!(in(*cx) let object = input.to_object());
+    let mut length = 0;
+    if unsafe { !JS_GetArrayLength(*cx, object.handle(), &mut length) } {
+        return Err(Error::JSFailed);
+    }
+    let mut keys: Vec<IndexedDBKeyType> = vec![];
+    for index in 0..length {
+        rooted!(in(*cx) let mut entry = UndefinedValue());
+        if unsafe { !JS_GetElement(*cx, object.handle(), index, &mut entry.handle_mut()) } {
+            return Err(Error::JSFailed);
+        }
+        // Invalid and duplicate subkeys are skipped.
+        match convert_value_to_key_with_seen(cx, entry.handle(), &mut vec![object.get()]) {
+            Ok(key) => {
+                if !keys.contains(&key) {
+                    keys.push(key);
+                }
+            },
+            Err(Error::Data) => {},
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(keys)
+}
+
+/// <https://w3c.github.io/IndexedDB/#convert-a-key-to-a-value>
+#[allow(unsafe_code)]
+pub fn key_to_jsval(cx: JSContext, key: &IndexedDBKeyType, mut rval: MutableHandleValue) {
+    match *key {
+        IndexedDBKeyType::Number(number) => rval.set(DoubleValue(number)),
+        IndexedDBKeyType::Date(milliseconds) => unsafe {
+            let date = NewDateObject(*cx, ClippedTime { t: milliseconds });
+            rval.set(ObjectValue(date));
+        },
+        IndexedDBKeyType::String(ref string) => unsafe {
+            DOMString::from(string.clone()).to_jsval(*cx, rval);
+        },
+        IndexedDBKeyType::Binary(ref bytes) => unsafe {
+            rooted!(in(*cx) let mut buffer = ptr::null_mut::<JSObject>());
+            assert!(
+                ArrayBuffer::create(*cx, CreateWith::Slice(bytes), buffer.handle_mut()).is_ok()
+            );
+            rval.set(ObjectValue(buffer.get()));
+        },
+        IndexedDBKeyType::Array(ref keys) => {
+            new_array(cx, keys.len(), rval, |index, value| {
+                key_to_jsval(cx, &keys[index], value)
+            });
+        },
+    }
+}
+
+/// Creates an array of `length` elements, which `f` writes for each index.
+#[allow(unsafe_code)]
+pub fn new_array<F>(cx: JSContext, length: usize, mut rval: MutableHandleValue, mut f: F)
+where
+    F: FnMut(usize, MutableHandleValue),
+{
+    unsafe {
+        rooted!(in(*cx) let array = JS_NewArrayObject1(*cx, length));
+        for index in 0..length {
+            rooted!(in(*cx) let mut value = UndefinedValue());
+            f(index, value.handle_mut());
+            assert!(JS_DefineElement(
+                *cx,
+                array.handle().into(),
+                index as u32,
+                value.handle().into(),
+                JSPROP_ENUMERATE as u32,
+            ));
+        }
+        rval.set(ObjectValue(array.get()));
+    }
+}
+
+/// <https://w3c.github.io/IndexedDB/#convert-a-value-to-a-key-range>
+pub fn convert_value_to_key_range(
+    cx: JSContext,
+    input: HandleValue,
+    null_disallowed: bool,
+) -> Fallible<IndexedDBKeyRange> {
+    if let Ok(range) = root_from_handlevalue::<IDBKeyRange>(input, *cx) {
+        return Ok(range.inner().clone());
+    }
+    if input.is_undefined() || input.is_null() {
+        if null_disallowed {
+            return Err(Error::Data);
+        }
+        return Ok(IndexedDBKeyRange::default());
+    }
+    Ok(IndexedDBKeyRange::only(convert_value_to_key(cx, input)?))
+}
+
+/// <https://w3c.github.io/IndexedDB/#valid-key-path>
+pub fn key_path_from_idl(key_path: &StringOrStringSequence) -> Fallible<KeyPath> {
+    fn is_valid(path: &str) -> bool {
+        path.is_empty() ||
+            path.split('.').all(|identifier| {
+                let mut chars = identifier.chars();
+                match chars.next() {
+                    Some(c) if c.is_alphabetic() || c == '$' || c == '_' => {
+                        chars.all(|c| c.is_alphanumeric() || c == '$' || c == '_')
+                    },
+                    _ => false,
+                }
+            })
+    }
+    let key_path = match *key_path {
+        StringOrStringSequence::String(ref path) => KeyPath::String(path.to_string()),
+        StringOrStringSequence::StringSequence(ref paths) => {
+            KeyPath::Sequence(paths.iter().map(|path| path.to_string()).collect())
+        },
+    };
+    let valid = match key_path {
+        KeyPath::String(ref path) => is_valid(path),
+        KeyPath::Sequence(ref paths) => {
+            !paths.is_empty() && paths.iter().all(|path| is_valid(path))
+        },
+    };
+    if !valid {
+        return Err(Error::Syntax);
+    }
+    Ok(key_path)
+}
+
+pub fn cursor_direction_from_idl(direction: IDBCursorDirection) -> IndexedDBCursorDirection {
+    match direction {
+        IDBCursorDirection::Next => IndexedDBCursorDirection::Next,
+        IDBCursorDirection::Nextunique => IndexedDBCursorDirection::NextUnique,
+        IDBCursorDirection::Prev => IndexedDBCursorDirection::Prev,
+        IDBCursorDirection::Prevunique => IndexedDBCursorDirection::PrevUnique,
+    }
+}
+
+#[allow(unsafe_code)]
+pub fn key_path_to_jsval(cx: JSContext, key_path: &KeyPath, rval: MutableHandleValue) {
+    unsafe {
+        match *key_path {
+            KeyPath::String(ref path) => DOMString::from(path.clone()).to_jsval(*cx, rval),
+            KeyPath::Sequence(ref paths) => {
+                let paths: Vec<DOMString> = paths.iter().cloned().map(DOMString::from).collect();
+                paths.to_jsval(*cx, rval)
+            },
+        }
+    }
+}
+
+#[allow(unsafe_code)]
+fn has_own_property(cx: JSContext, object: HandleObject, name: &CString) -> Fallible<bool> {
+    let mut found = false;
+    if unsafe { !JS_HasOwnProperty(*cx, object, name.as_ptr(), &mut found) } {
+        return Err(Error::JSFailed);
+    }
+    Ok(found)
+}
+
+#[allow(unsafe_code)]
+fn get_property(
+    cx: JSContext,
+    object: HandleObject,
+    name: &CString,
+    rval: MutableHandleValue,
+) -> Fallible<()> {
+    if unsafe { !JS_GetProperty(*cx, object, name.as_ptr(), rval) } {
+        return Err(Error::JSFailed);
+    }
+    Ok(())
+}
+
+/// <https://w3c.github.io/IndexedDB/#evaluate-a-key-path-on-a-value>, returning whether the
+/// evaluation succeeded.
+pub fn evaluate_key_path(
+    cx: JSContext,
+    value: HandleValue,
+    key_path: &KeyPath,
+    rval: MutableHandleValue,
+) -> Fallible<bool> {
+    match *key_path {
+        KeyPath::String(ref path) => evaluate_string_key_path(cx, value, path, rval),
+        KeyPath::Sequence(ref paths) => {
+            let mut result = Ok(true);
+            new_array(cx, paths.len(), rval, |index, entry| {
+                if let Ok(true) = result {
+                    result = evaluate_string_key_path(cx, value, &paths[index], entry);
+                }
+            });
+            result
+        },
+    }
+}
+
+#[allow(unsafe_code)]
+fn evaluate_string_key_path(
+    cx: JSContext,
+    value: HandleValue,
+    path: &str,
+    mut rval: MutableHandleValue,
+) -> Fallible<bool> {
+    rooted!(in(*cx) let mut current = value.get());
+    if !path.is_empty() {
+        for identifier in path.split('.') {
+            if current.is_string() && identifier == "length" {
+                let length = unsafe { JS_GetStringLength(current.to_string()) };
+                current.set(DoubleValue(length as f64));
+                continue;
+            }
+            if !current.is_object() {
+                return Ok(false);
+            }
+            rooted!(in(*cx) let object = current.to_object());
+            let name = CString::new(identifier).unwrap();
+            if !has_own_property(cx, object.handle(), &name)? {
+                return Ok(false);
+            }
+            get_property(cx, object.handle(), &name, current.handle_mut())?;
+        }
+    }
+    rval.set(current.get());
+    Ok(true)
+}
+
+/// Evaluates a key path on a value and converts the result to a key. `Ok(None)` if the
+/// evaluation fails.
+pub fn extract_key(
+    cx: JSContext,
+    value: HandleValue,
+    key_path: &KeyPath,
+) -> Fallible<Option<IndexedDBKeyType>> {
+    rooted!(in(*cx) let mut key = UndefinedValue());
+    if !evaluate_key_path(cx, value, key_path, key.handle_mut())? {
+        return Ok(None);
+    }
+    convert_value_to_key(cx, key.handle()).map(Some)
+}
+
+/// The keys a value has in an index. A value whose index key is invalid is not in the index.
+/// <https://w3c.github.io/IndexedDB/#store-a-record-into-an-object-store>
+pub fn extract_index_keys(
+    cx: JSContext,
+    value: HandleValue,
+    key_path: &KeyPath,
+    multi_entry: bool,
+) -> Fallible<Vec<IndexedDBKeyType>> {
+    rooted!(in(*cx) let mut index_value = UndefinedValue());
+    if !evaluate_key_path(cx, value, key_path, index_value.handle_mut())? {
+        return Ok(vec![]);
+    }
+    let keys = match *key_path {
+        KeyPath::String(_) if multi_entry => {
+            convert_value_to_multi_entry_key(cx, index_value.handle())
+        },
+        _ => convert_value_to_key(cx, index_value.handle()).map(|key| vec![key]),
+    };
+    match keys {
+        Err(Error::Data) => Ok(vec![]),
+        keys => keys,
+    }
+}
+
+/// <https://w3c.github.io/IndexedDB/#check-that-a-key-could-be-injected-into-a-value>
+pub fn can_inject_key(cx: JSContext, value: HandleValue, path: &str) -> Fallible<bool> {
+    let mut identifiers: Vec<_> = path.split('.').collect();
+    identifiers.pop();
+    rooted!(in(*cx) let mut current = value.get());
+    for identifier in identifiers {
+        if !current.is_object() {
+            return Ok(false);
+        }
+        rooted!(in(*cx) let object = current.to_object());
+        let name = CString::new(identifier).unwrap();
+        if !has_own_property(cx, object.handle(), &name)? {
+            return Ok(true);
+        }
+        get_property(cx, object.handle(), &name, current.handle_mut())?;
+    }
+    Ok(current.is_object())
+}
+
+/// <https://w3c.github.io/IndexedDB/#inject-a-key-into-a-value-using-a-key-path>
+#[allow(unsafe_code)]
+pub fn inject_key(
+    cx: JSContext,
+    value: HandleValue,
+    key: &IndexedDBKeyType,
+    path: &str,
+) -> Fallible<()> {
+    let mut identifiers: Vec<_> = path.split('.').collect();
+    let last = identifiers.pop().unwrap_or("");
+    rooted!(in(*cx) let mut current = value.get());
+    for identifier in identifiers {
+        if !current.is_object() {
+            return Err(Error::Data);
+        }
+        rooted!(in(*cx) let object = current.to_object());
+        let name = CString::new(identifier).unwrap();
+        if !has_own_property(cx, object.handle(), &name)? {
+            rooted!(in(*cx) let new_object = ObjectValue(unsafe { JS_NewPlainObject(*cx) }));
+            if unsafe { !JS_SetProperty(*cx, object.handle(), name.as_ptr(), new_object.handle()) }
+            {
+                return Err(Error::JSFailed);
+            }
+        }
+        get_property(cx, object.handle(), &name, current.handle_mut())?;
+    }
+    if !current.is_object() {
+        return Err(Error::Data);
+    }
+    rooted!(in(*cx) let object = current.to_object());
+    rooted!(in(*cx) let mut key_value = UndefinedValue());
+    key_to_jsval(cx, key, key_value.handle_mut());
+    let name = CString::new(last).unwrap();
+    if unsafe { !JS_SetProperty(*cx, object.handle(), name.as_ptr(), key_value.handle()) } {
+        return Err(Error::JSFailed);
+    }
+    Ok(())
+}
+
+/// Sends a message to the IndexedDB engine of the storage thread and waits for its reply.
+pub fn send_message<T, F>(global: &GlobalScope, message: F) -> T
+where
+    T: for<'de> Deserialize<'de> + Serialize,
+    F: FnOnce(IpcSender<T>) -> IndexedDBThreadMsg,
+{
+    let (sender, receiver) = ipc::channel(global.time_profiler_chan().clone()).unwrap();
+    let storage_thread: IpcSender<StorageThreadMsg> = global.resource_threads().sender();
+    storage_thread
+        .send(StorageThreadMsg::IndexedDB(message(sender)))
+        .unwrap();
+    receiver.recv().unwrap()
+}
+
+/// Sends a message to the IndexedDB engine that has no reply.
+pub fn post_message(global: &GlobalScope, message: IndexedDBThreadMsg) {
+    let storage_thread: IpcSender<StorageThreadMsg> = global.resource_threads().sender();
+    let _ = storage_thread.send(StorageThreadMsg::IndexedDB(message));
+}
+
+/// Runs `on_event` with `object` in a task for each event the engine sends on `receiver`.
+pub fn route_events<T, E>(object: &T, receiver: IpcReceiver<E>, on_event: fn(&T, E))
+where
+    T: DomObject,
+    E: for<'de> Deserialize<'de> + Serialize + Send + 'static,
+{
+    let global = object.global();
+    let task_source = global.dom_manipulation_task_source();
+    let canceller = global.task_canceller(TaskSourceName::DOMManipulation);
+    let trusted = Trusted::new(object);
+    ROUTER.add_route(
+        receiver.to_opaque(),
+        Box::new(move |message| {
+            let event: E = match message.to() {
+                Ok(event) => event,
+                Err(_) => return,
+            };
+            let trusted = trusted.clone();
+            let _ = task_source.queue_with_canceller(
+                task!(indexeddb_event: move || {
+                    on_event(&trusted.root(), event);
+                }),
+                &canceller,
+            );
+        }),
+    );
+}
+
+/// Like `send_message`, for the operations which the engine may refuse.
+pub fn send_fallible_message<T, F>(global: &GlobalScope, message: F) -> Fallible<T>
+where
+    T: for<'de> Deserialize<'de> + Serialize,
+    F: FnOnce(IpcSender<IndexedDBResult<T>>) -> IndexedDBThreadMsg,
+{
+    send_message(global, message).map_err(|error| match error {
+        IndexedDBError::Constraint => Error::Constraint,
+        IndexedDBError::Data => Error::Data,
+        IndexedDBError::InvalidState => Error::InvalidState,
+        IndexedDBError::NotFound => Error::NotFound,
+        IndexedDBError::QuotaExceeded => Error::QuotaExceeded,
+        IndexedDBError::ReadOnly => Error::ReadOnly,
+        IndexedDBError::TransactionInactive => Error::TransactionInactive,
+    })
+}
+
+/// The `DOMException` a request or transaction reports for an asynchronous failure.
+pub fn error_to_dom_exception(global: &GlobalScope, error: Error) -> DomRoot<DOMException> {
+    let name = match error {
+        Error::Abort => DOMErrorName::AbortError,
+        Error::Constraint => DOMErrorName::ConstraintError,
+        Error::Data => DOMErrorName::DataError,
+        Error::DataClone => DOMErrorName::DataCloneError,
+        Error::InvalidState => DOMErrorName::InvalidStateError,
+        Error::NotFound => DOMErrorName::NotFoundError,
+        Error::QuotaExceeded => DOMErrorName::QuotaExceededError,
+        Error::ReadOnly => DOMErrorName::ReadOnlyError,
+        Error::Security => DOMErrorName::SecurityError,
+        Error::TransactionInactive => DOMErrorName::TransactionInactiveError,
+        Error::Version => DOMErrorName::VersionError,
+        _ => DOMErrorName::OperationError,
+    };
+    DOMException::new(global, name)
+}
+
+/// <https://html.spec.whatwg.org/multipage/#structuredserialize>
+///
+/// Blobs can't be stored yet, since their data lives in the file manager,
+/// and neither can crypto keys, whose key material is kept out of the serialized bytes.
+pub fn serialize(cx: JSContext, value: HandleValue) -> Fallible<Vec<u8>> {
+    let data = structuredclone::write(cx, value, None)?;
+    if data.blobs.is_some() || data.crypto_keys.is_some() || data.ports.is_some() {
+        return Err(Error::DataClone);
+    }
+    Ok(data.serialized)
+}
+
+/// <https://html.spec.whatwg.org/multipage/#structureddeserialize>
+pub fn deserialize(
+    global: &GlobalScope,
+    serialized: Vec<u8>,
+    rval: MutableHandleValue,
+) -> Fallible<()> {
+    let data = StructuredSerializedData {
+        serialized,
+        blobs: None,
+        crypto_keys: None,
+        ports: None,
+        image_bitmaps: None,
+    };
+    structuredclone::read(global, data, rval)
+        .map(|_| ())
+        .map_err(|_| Error::DataClone)
+}
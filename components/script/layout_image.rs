@@ -15,6 +15,7 @@ use crate::dom::globalscope::GlobalScope;
 use crate::dom::node::{document_from_node, Node};
 use crate::dom::performanceresourcetiming::InitiatorType;
 use crate::network_listener::{self, NetworkListener, PreInvoke, ResourceTimingListener};
+use content_security_policy as csp;
 use ipc_channel::ipc;
 use ipc_channel::router::ROUTER;
 use net_traits::image_cache::{ImageCache, PendingImageId};
@@ -36,6 +37,13 @@ impl FetchResponseListener for LayoutImageContext {
     fn process_request_body(&mut self) {}
     fn process_request_eof(&mut self) {}
     fn process_response(&mut self, metadata: Result<FetchMetadata, NetworkError>) {
+        if let Ok(ref metadata) = metadata {
+            let metadata = match *metadata {
+                FetchMetadata::Unfiltered(ref m) => m,
+                FetchMetadata::Filtered { ref unsafe_, .. } => unsafe_,
+            };
+            self.doc.root().note_subresource_response(metadata);
+        }
         self.cache
             .notify_pending_response(self.id, FetchResponseMsg::ProcessResponse(metadata));
     }
@@ -58,6 +66,11 @@ impl FetchResponseListener for LayoutImageContext {
         &self.resource_timing
     }
 
+    fn process_csp_violations(&mut self, violations: Vec<csp::Violation>) {
+        let global = &self.resource_timing_global();
+        global.report_csp_violations(violations, None);
+    }
+
     fn submit_resource_timing(&mut self) {
         network_listener::submit_timing(self)
     }
@@ -113,7 +126,8 @@ pub fn fetch_image_for_layout(
     let request = FetchRequestInit::new(url, document.global().get_referrer())
         .origin(document.origin().immutable().clone())
         .destination(Destination::Image)
-        .pipeline_id(Some(document.global().pipeline_id()));
+        .pipeline_id(Some(document.global().pipeline_id()))
+        .has_trustworthy_ancestor_origin(document.has_trustworthy_ancestor_origin());
 
     // Layout image loads do not delay the document load event.
     document
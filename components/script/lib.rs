@@ -65,12 +65,16 @@ pub mod document_loader;
 mod dom;
 #[warn(deprecated)]
 mod canvas_state;
+#[warn(deprecated)]
+mod editing;
 mod euclidext;
 #[warn(deprecated)]
 pub mod fetch;
 #[warn(deprecated)]
 mod image_listener;
 #[warn(deprecated)]
+mod indexed_db;
+#[warn(deprecated)]
 mod init;
 #[warn(deprecated)]
 mod layout_image;
@@ -90,6 +94,8 @@ pub mod script_runtime;
 #[allow(unsafe_code)]
 pub mod script_thread;
 #[warn(deprecated)]
+mod security_manager;
+#[warn(deprecated)]
 pub mod serviceworker_manager;
 #[warn(deprecated)]
 mod stylesheet_loader;
@@ -36,6 +36,7 @@ use crate::realms::{enter_realm, AlreadyInRealm, InRealm};
 use crate::script_runtime::JSContext as SafeJSContext;
 use crate::task::TaskBox;
 use crate::task_source::TaskSourceName;
+use content_security_policy as csp;
 use encoding_rs::UTF_8;
 use hyper_serde::Serde;
 use indexmap::IndexSet;
@@ -1183,6 +1184,11 @@ impl FetchResponseListener for ModuleContext {
         &self.resource_timing
     }
 
+    fn process_csp_violations(&mut self, violations: Vec<csp::Violation>) {
+        let global = &self.resource_timing_global();
+        global.report_csp_violations(violations, None);
+    }
+
     fn submit_resource_timing(&mut self) {
         network_listener::submit_timing(self)
     }
@@ -1642,6 +1648,7 @@ fn fetch_single_module_script(
         .origin(global.origin().immutable().clone())
         .parser_metadata(options.parser_metadata)
         .integrity_metadata(options.integrity_metadata.clone())
+        .cryptographic_nonce_metadata(options.cryptographic_nonce.clone())
         .credentials_mode(options.credentials_mode)
         .mode(mode);
 
@@ -1681,7 +1688,7 @@ fn fetch_single_module_script(
                 .sender()
                 .send(CoreResourceMsg::Fetch(
                     request,
-                    FetchChannels::ResponseMsg(action_sender, None),
+                    FetchChannels::ResponseMsg(action_sender, None, None),
                 ))
                 .unwrap();
         },
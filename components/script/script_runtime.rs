@@ -33,6 +33,7 @@ use crate::microtask::{EnqueuedPromiseCallback, Microtask, MicrotaskQueue};
 use crate::realms::{AlreadyInRealm, InRealm};
 use crate::script_module::EnsureModuleHooksInitialized;
 use crate::script_thread::trace_thread;
+use crate::security_manager;
 use crate::task::TaskBox;
 use crate::task_source::networking::NetworkingTaskSource;
 use crate::task_source::{TaskSource, TaskSourceName};
@@ -61,6 +62,7 @@ use js::jsapi::{
     JSJitCompilerOption, JS_SetOffthreadIonCompilationEnabled, JS_SetParallelParsingEnabled,
 };
 use js::jsapi::{JSObject, PromiseRejectionHandlingState, SetPreserveWrapperCallback};
+use js::jsapi::{JSSecurityCallbacks, JS_SetSecurityCallbacks};
 use js::jsapi::{SetJobQueue, SetProcessBuildIdOp, SetPromiseRejectionTrackerCallback};
 use js::jsval::UndefinedValue;
 use js::panic::wrap_panic;
@@ -183,6 +185,24 @@ unsafe extern "C" fn get_incumbent_global(_: *const c_void, _: *mut RawJSContext
     result
 }
 
+static SECURITY_CALLBACKS: JSSecurityCallbacks = JSSecurityCallbacks {
+    contentSecurityPolicyAllows: Some(content_security_policy_allows),
+    subsumes: None,
+};
+
+#[allow(unsafe_code)]
+/// <https://w3c.github.io/webappsec-csp/#can-compile-strings>
+unsafe extern "C" fn content_security_policy_allows(cx: *mut RawJSContext) -> bool {
+    let mut allowed = false;
+    let cx = JSContext::from_ptr(cx);
+    wrap_panic(&mut || {
+        let in_realm_proof = AlreadyInRealm::assert_for_cx(cx);
+        let global = GlobalScope::from_context(*cx, InRealm::Already(&in_realm_proof));
+        allowed = security_manager::is_js_evaluation_allowed(&global);
+    });
+    allowed
+}
+
 #[allow(unsafe_code)]
 unsafe extern "C" fn empty(extra: *const c_void) -> bool {
     let mut result = false;
@@ -479,6 +499,7 @@ unsafe fn new_rt_and_cx_with_parent(
         true
     }
     SetDOMCallbacks(cx, &DOM_CALLBACKS);
+    JS_SetSecurityCallbacks(cx, &SECURITY_CALLBACKS);
     SetPreserveWrapperCallback(cx, Some(empty_wrapper_callback));
     // Pre barriers aren't working correctly at the moment
     DisableIncrementalGC(cx);
@@ -575,7 +596,6 @@ unsafe fn new_rt_and_cx_with_parent(
     // TODO: handle js.throw_on_debugee_would_run (needs new Spidermonkey)
     // TODO: handle js.dump_stack_on_debugee_would_run (needs new Spidermonkey)
     cx_opts.set_werror_(pref!(js.werror.enabled));
-    // TODO: handle js.shared_memory.enabled
     JS_SetGCParameter(
         cx,
         JSGCParamKey::JSGC_MAX_BYTES,
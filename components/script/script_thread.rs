@@ -54,10 +54,12 @@ use crate::dom::performanceentry::PerformanceEntry;
 use crate::dom::performancepainttiming::PerformancePaintTiming;
 use crate::dom::serviceworker::TrustedServiceWorkerAddress;
 use crate::dom::servoparser::{ParserContext, ServoParser};
+use crate::dom::sharedworkerglobalscope::SharedWorkerGlobalScope;
 use crate::dom::uievent::UIEvent;
 use crate::dom::window::{ReflowReason, Window};
 use crate::dom::windowproxy::{CreatorBrowsingContextInfo, WindowProxy};
 use crate::dom::worker::TrustedWorkerAddress;
+use crate::dom::workerglobalscope::prepare_workerscope_init;
 use crate::dom::worklet::WorkletThreadPool;
 use crate::dom::workletglobalscope::WorkletGlobalScopeInit;
 use crate::fetch::FetchCanceller;
@@ -73,6 +75,7 @@ use crate::task_queue::{QueuedTask, QueuedTaskConversion, TaskQueue};
 use crate::task_source::dom_manipulation::DOMManipulationTaskSource;
 use crate::task_source::file_reading::FileReadingTaskSource;
 use crate::task_source::history_traversal::HistoryTraversalTaskSource;
+use crate::task_source::idle::IdleTaskSource;
 use crate::task_source::media_element::MediaElementTaskSource;
 use crate::task_source::networking::NetworkingTaskSource;
 use crate::task_source::performance_timeline::PerformanceTimelineTaskSource;
@@ -96,7 +99,7 @@ use euclid::Vector2D;
 use headers::ReferrerPolicy as ReferrerPolicyHeader;
 use headers::{HeaderMapExt, LastModified};
 use hyper_serde::Serde;
-use ipc_channel::ipc::{self, IpcSender};
+use ipc_channel::ipc::{self, IpcReceiver, IpcSender};
 use ipc_channel::router::ROUTER;
 use js::glue::GetWindowProxyClass;
 use js::jsapi::JS_SetWrapObjectCallbacks;
@@ -114,10 +117,12 @@ use msg::constellation_msg::{
 };
 use msg::constellation_msg::{BrowsingContextId, HistoryStateId, PipelineId};
 use msg::constellation_msg::{HangAnnotation, MonitoredComponentId, MonitoredComponentType};
-use msg::constellation_msg::{PipelineNamespace, TopLevelBrowsingContextId};
+use msg::constellation_msg::{PipelineNamespace, SharedWorkerId, TopLevelBrowsingContextId};
 use net_traits::image_cache::{ImageCache, PendingImageResponse};
 use net_traits::request::{CredentialsMode, Destination, RedirectMode, RequestBuilder};
 use net_traits::storage_thread::StorageType;
+use net_traits::CrossOriginOpenerPolicy;
+use net_traits::{embedder_policy_from_headers, opener_policy_from_headers};
 use net_traits::{FetchMetadata, FetchResponseListener, FetchResponseMsg};
 use net_traits::{
     Metadata, NetworkError, ReferrerPolicy, ResourceFetchTiming, ResourceThreads,
@@ -130,17 +135,17 @@ use profile_traits::time::{self as profile_time, profile, ProfilerCategory};
 use script_layout_interface::message::{self, LayoutThreadInit, Msg, ReflowGoal};
 use script_traits::webdriver_msg::WebDriverScriptCommand;
 use script_traits::CompositorEvent::{
-    CompositionEvent, IMEDismissedEvent, KeyboardEvent, MouseButtonEvent, MouseMoveEvent,
-    ResizeEvent, TouchEvent, WheelEvent,
+    CompositionEvent, DragEvent, IMEDismissedEvent, KeyboardEvent, MouseButtonEvent,
+    MouseMoveEvent, PenEvent, ResizeEvent, TouchEvent, WheelEvent,
 };
 use script_traits::{
     AnimationTickType, CompositorEvent, ConstellationControlMsg, DiscardBrowsingContext,
     DocumentActivity, EventResult, HistoryEntryReplacement, InitialScriptState, JsEvalResult,
     LayoutMsg, LoadData, LoadOrigin, MediaSessionActionType, MouseButton, MouseEventType,
     NewLayoutInfo, Painter, ProgressiveWebMetricType, ScriptMsg, ScriptThreadFactory,
-    ScriptToConstellationChan, StructuredSerializedData, TimerSchedulerMsg, TouchEventType,
-    TouchId, UntrustedNodeAddress, UpdatePipelineIdReason, WebrenderIpcSender, WheelDelta,
-    WindowSizeData, WindowSizeType,
+    ScriptToConstellationChan, SharedWorkerMsg, StructuredSerializedData, TimerSchedulerMsg,
+    TouchActions, TouchEventType, TouchId, UntrustedNodeAddress, UpdatePipelineIdReason,
+    WebrenderIpcSender, WheelDelta, WindowSizeData, WindowSizeType, WorkerScriptLoadOrigin,
 };
 use servo_atoms::Atom;
 use servo_config::opts;
@@ -171,6 +176,14 @@ pub type ImageCacheMsg = (PipelineId, PendingImageResponse);
 
 thread_local!(static SCRIPT_THREAD_ROOT: Cell<Option<*const ScriptThread>> = Cell::new(None));
 
+/// The longest an idle period may last, in nanoseconds, so that the event loop stays
+/// responsive to new tasks.
+/// <https://w3c.github.io/requestidlecallback/#why50>
+const MAX_IDLE_PERIOD_NS: u64 = 50_000_000;
+
+/// The time between two frames at 60 frames per second, in nanoseconds.
+const FRAME_DURATION_NS: u64 = 1_000_000_000 / 60;
+
 pub unsafe fn trace_thread(tr: *mut JSTracer) {
     SCRIPT_THREAD_ROOT.with(|root| {
         if let Some(script_thread) = root.get() {
@@ -216,6 +229,10 @@ struct InProgressLoad {
     canceller: FetchCanceller,
     /// Flag for sharing with the layout thread that is not yet created.
     layout_is_busy: Arc<AtomicBool>,
+    /// Whether any ancestor of the document being loaded has a potentially trustworthy origin.
+    has_trustworthy_ancestor_origin: bool,
+    /// Whether every ancestor of the document being loaded is a secure context.
+    has_only_trustworthy_ancestor_origins: bool,
 }
 
 impl InProgressLoad {
@@ -231,6 +248,8 @@ impl InProgressLoad {
         url: ServoUrl,
         origin: MutableOrigin,
         layout_is_busy: Arc<AtomicBool>,
+        has_trustworthy_ancestor_origin: bool,
+        has_only_trustworthy_ancestor_origins: bool,
     ) -> InProgressLoad {
         let current_time = get_time();
         let navigation_start_precise = precise_time_ns();
@@ -253,6 +272,8 @@ impl InProgressLoad {
             navigation_start_precise: navigation_start_precise,
             canceller: Default::default(),
             layout_is_busy: layout_is_busy,
+            has_trustworthy_ancestor_origin: has_trustworthy_ancestor_origin,
+            has_only_trustworthy_ancestor_origins: has_only_trustworthy_ancestor_origins,
         }
     }
 }
@@ -557,6 +578,8 @@ pub struct ScriptThread {
 
     history_traversal_task_sender: Sender<MainThreadScriptMsg>,
 
+    idle_task_sender: Box<dyn ScriptChan>,
+
     file_reading_task_sender: Box<dyn ScriptChan>,
 
     performance_timeline_task_sender: Box<dyn ScriptChan>,
@@ -692,6 +715,10 @@ pub struct ScriptThread {
 
     /// Receiver to receive commands from optional WebGPU server.
     webgpu_port: RefCell<Option<Receiver<WebGPUMsg>>>,
+
+    /// When animation frame callbacks last ran, in nanoseconds of `precise_time_ns`.
+    /// <https://html.spec.whatwg.org/multipage/#last-render-opportunity-time>
+    last_render_opportunity: Cell<Option<u64>>,
 }
 
 struct BHMExitSignal {
@@ -816,6 +843,8 @@ impl ScriptThreadFactory for ScriptThread {
                     load_data.url.clone(),
                     origin,
                     layout_is_busy,
+                    load_data.has_trustworthy_ancestor_origin,
+                    load_data.has_only_trustworthy_ancestor_origins,
                 );
                 script_thread.pre_page_load(new_load, load_data);
 
@@ -1341,6 +1370,7 @@ impl ScriptThread {
             networking_task_sender: boxed_script_sender.clone(),
             port_message_sender: boxed_script_sender.clone(),
             file_reading_task_sender: boxed_script_sender.clone(),
+            idle_task_sender: boxed_script_sender.clone(),
             performance_timeline_task_sender: boxed_script_sender.clone(),
             timer_task_sender: boxed_script_sender.clone(),
             remote_event_task_sender: boxed_script_sender.clone(),
@@ -1404,6 +1434,7 @@ impl ScriptThread {
             is_user_interacting: Cell::new(false),
             gpu_id_hub: Arc::new(Mutex::new(Identities::new())),
             webgpu_port: RefCell::new(None),
+            last_render_opportunity: Cell::new(None),
         }
     }
 
@@ -1689,11 +1720,54 @@ impl ScriptThread {
                 // minimize unnecessary work.
                 window.reflow(ReflowGoal::Full, ReflowReason::MissingExplicitReflow);
             }
+
+            // https://drafts.csswg.org/resize-observer/#html-event-loop
+            let mut depth = 0;
+            document.gather_active_resize_observations_at_depth(depth);
+            while document.has_active_resize_observations() {
+                depth = document.broadcast_active_resize_observations();
+                document.gather_active_resize_observations_at_depth(depth);
+            }
+            if document.has_skipped_resize_observations() {
+                document.deliver_resize_loop_error_notification();
+            }
+
+            // https://html.spec.whatwg.org/multipage/#event-loop-processing-model step 7.14
+            document.update_intersection_observations();
         }
 
+        self.start_idle_periods();
+
         true
     }
 
+    /// Starts an idle period for each window with idle callbacks, if this iteration of the
+    /// event loop left no tasks to run.
+    /// <https://html.spec.whatwg.org/multipage/#event-loop-processing-model> step 16.
+    fn start_idle_periods(&self) {
+        if self.task_queue.has_pending_tasks() || !self.control_port.is_empty() {
+            return;
+        }
+        let now = precise_time_ns();
+        for (_, document) in self.documents.borrow().iter() {
+            let window = document.window();
+            if !window.has_idle_callbacks() {
+                continue;
+            }
+            // An idle period that would run into the next frame is cut short, so that
+            // animation frame callbacks aren't delayed by idle callbacks.
+            let mut deadline = now + MAX_IDLE_PERIOD_NS;
+            if document.has_animation_frame_callbacks() {
+                if let Some(last_render_opportunity) = self.last_render_opportunity.get() {
+                    deadline = deadline.min(last_render_opportunity + FRAME_DURATION_NS);
+                }
+            }
+            if deadline > now {
+                window.start_an_idle_period(deadline);
+            }
+        }
+    }
+
     // Perform step 11.10 from https://html.spec.whatwg.org/multipage/#event-loops.
     fn update_animations_and_send_events(&self) {
         for (_, document) in self.documents.borrow().iter() {
@@ -1792,6 +1866,7 @@ impl ScriptThread {
                 UpdateHistoryState(id, ..) => Some(id),
                 RemoveHistoryStates(id, ..) => Some(id),
                 FocusIFrame(id, ..) => Some(id),
+                SetIFrameFullscreen(id, ..) => Some(id),
                 WebDriverScriptCommand(id, ..) => Some(id),
                 TickAllAnimations(id, ..) => Some(id),
                 WebFontLoaded(id) => Some(id),
@@ -1807,6 +1882,8 @@ impl ScriptThread {
                 ExitFullScreen(id, ..) => Some(id),
                 MediaSessionAction(..) => None,
                 SetWebGPUPort(..) => None,
+                DisownOpener(id) => Some(id),
+                SpawnSharedWorker { pipeline_id, .. } => Some(pipeline_id),
             },
             MixedMessage::FromDevtools(_) => None,
             MixedMessage::FromScript(ref inner_msg) => match *inner_msg {
@@ -1914,6 +1991,8 @@ impl ScriptThread {
                         self.handle_fetch_chunk(id, chunk)
                     },
                     FetchResponseMsg::ProcessResponseEOF(eof) => self.handle_fetch_eof(id, eof),
+                    // Navigation requests are not given a policy to check against.
+                    FetchResponseMsg::ProcessCspViolations(_) => {},
                     _ => unreachable!(),
                 };
             },
@@ -1990,6 +2069,11 @@ impl ScriptThread {
             ConstellationControlMsg::FocusIFrame(parent_pipeline_id, frame_id) => {
                 self.handle_focus_iframe_msg(parent_pipeline_id, frame_id)
             },
+            ConstellationControlMsg::SetIFrameFullscreen(
+                parent_pipeline_id,
+                frame_id,
+                fullscreen,
+            ) => self.handle_set_iframe_fullscreen_msg(parent_pipeline_id, frame_id, fullscreen),
             ConstellationControlMsg::WebDriverScriptCommand(pipeline_id, msg) => {
                 self.handle_webdriver_msg(pipeline_id, msg)
             },
@@ -1999,6 +2083,18 @@ impl ScriptThread {
             ConstellationControlMsg::WebFontLoaded(pipeline_id) => {
                 self.handle_web_font_loaded(pipeline_id)
             },
+            ConstellationControlMsg::DisownOpener(pipeline_id) => {
+                self.handle_disown_opener(pipeline_id)
+            },
+            ConstellationControlMsg::SpawnSharedWorker {
+                pipeline_id,
+                worker_id,
+                script_url,
+                name,
+                receiver,
+            } => {
+                self.handle_spawn_shared_worker(pipeline_id, worker_id, script_url, name, receiver)
+            },
             ConstellationControlMsg::DispatchIFrameLoadEvent {
                 target: browsing_context_id,
                 parent: parent_id,
@@ -2402,13 +2498,10 @@ impl ScriptThread {
         warn!("resize sent to nonexistent pipeline");
     }
 
-    // exit_fullscreen creates a new JS promise object, so we need to have entered a realm
     fn handle_exit_fullscreen(&self, id: PipelineId) {
         let document = self.documents.borrow().find_document(id);
         if let Some(document) = document {
-            let _ac = enter_realm(&*document);
-            document.exit_fullscreen();
-            return;
+            document.fully_exit_fullscreen();
         }
     }
 
@@ -2523,6 +2616,8 @@ impl ScriptThread {
             load_data.url.clone(),
             origin,
             layout_is_busy.clone(),
+            load_data.has_trustworthy_ancestor_origin,
+            load_data.has_only_trustworthy_ancestor_origins,
         );
         if load_data.url.as_str() == "about:blank" {
             self.start_page_load_about_blank(new_load, load_data.js_eval_result);
@@ -2623,6 +2718,26 @@ impl ScriptThread {
         }
     }
 
+    fn handle_set_iframe_fullscreen_msg(
+        &self,
+        parent_pipeline_id: PipelineId,
+        browsing_context_id: BrowsingContextId,
+        fullscreen: bool,
+    ) {
+        let doc = match self.documents.borrow().find_document(parent_pipeline_id) {
+            Some(doc) => doc,
+            None => {
+                return warn!(
+                    "Fullscreen iframe of closed pipeline {}.",
+                    parent_pipeline_id
+                )
+            },
+        };
+        if let Some(frame_element) = doc.find_iframe(browsing_context_id) {
+            doc.set_iframe_fullscreen(&frame_element, fullscreen);
+        }
+    }
+
     fn handle_post_message_msg(
         &self,
         pipeline_id: PipelineId,
@@ -2671,6 +2786,63 @@ impl ScriptThread {
         }
     }
 
+    /// <https://html.spec.whatwg.org/multipage/#dom-sharedworker>
+    fn handle_spawn_shared_worker(
+        &self,
+        pipeline_id: PipelineId,
+        worker_id: SharedWorkerId,
+        script_url: ServoUrl,
+        name: String,
+        receiver: IpcReceiver<SharedWorkerMsg>,
+    ) {
+        let window = match self.documents.borrow().find_window(pipeline_id) {
+            None => return warn!("Shared worker started by closed pipeline {}.", pipeline_id),
+            Some(window) => window,
+        };
+        let global = window.upcast::<GlobalScope>();
+        let worker_load_origin = WorkerScriptLoadOrigin {
+            referrer_url: None,
+            referrer_policy: None,
+            pipeline_id,
+        };
+
+        // The worker is not tracked by the window, since it keeps running
+        // for as long as any document is connected to it.
+        let _ = SharedWorkerGlobalScope::run_sharedworker_scope(
+            prepare_workerscope_init(global, None, None),
+            worker_id,
+            script_url,
+            name,
+            worker_load_origin,
+            global.get_referrer(),
+            receiver,
+            global.image_cache(),
+        );
+    }
+
+    fn handle_disown_opener(&self, pipeline_id: PipelineId) {
+        let mut incomplete_loads = self.incomplete_loads.borrow_mut();
+        let browsing_context_id = match incomplete_loads
+            .iter_mut()
+            .find(|load| load.pipeline_id == pipeline_id)
+        {
+            Some(load) => {
+                load.opener = None;
+                Some(load.browsing_context_id)
+            },
+            None => self
+                .documents
+                .borrow()
+                .find_window(pipeline_id)
+                .map(|window| window.window_proxy().browsing_context_id()),
+        };
+        if let Some(window_proxy) =
+            browsing_context_id.and_then(|id| ScriptThread::find_window_proxy(id))
+        {
+            window_proxy.disown();
+        }
+    }
+
     fn handle_unload_document(&self, pipeline_id: PipelineId) {
         let document = self.documents.borrow().find_document(pipeline_id);
         if let Some(document) = document {
@@ -2818,6 +2990,10 @@ impl ScriptThread {
         MediaElementTaskSource(self.media_element_task_sender.clone(), pipeline_id)
     }
 
+    pub fn idle_task_source(&self, pipeline_id: PipelineId) -> IdleTaskSource {
+        IdleTaskSource(self.idle_task_sender.clone(), pipeline_id)
+    }
+
     pub fn performance_timeline_task_source(
         &self,
         pipeline_id: PipelineId,
@@ -3000,6 +3176,7 @@ impl ScriptThread {
             None => return warn!("Message sent to closed pipeline {}.", id),
         };
         if tick_type.contains(AnimationTickType::REQUEST_ANIMATION_FRAME) {
+            self.last_render_opportunity.set(Some(precise_time_ns()));
             document.run_the_animation_frame_callbacks();
         }
         if tick_type.contains(AnimationTickType::CSS_ANIMATIONS_AND_TRANSITIONS) {
@@ -3241,6 +3418,7 @@ impl ScriptThread {
             self.dom_manipulation_task_source(incomplete.pipeline_id),
             self.file_reading_task_source(incomplete.pipeline_id),
             self.history_traversal_task_source(incomplete.pipeline_id),
+            self.idle_task_source(incomplete.pipeline_id),
             self.media_element_task_source(incomplete.pipeline_id),
             self.networking_task_source(incomplete.pipeline_id),
             self.performance_timeline_task_source(incomplete.pipeline_id)
@@ -3251,6 +3429,58 @@ impl ScriptThread {
             self.timer_task_source(incomplete.pipeline_id),
             self.websocket_task_source(incomplete.pipeline_id),
         );
+
+        let parent_window = incomplete
+            .parent_info
+            .and_then(|parent_id| self.documents.borrow().find_window(parent_id));
+        // https://html.spec.whatwg.org/multipage/#secure-context
+        let is_secure_context = final_url.is_potentially_trustworthy() &&
+            match parent_window {
+                Some(ref parent_window) => {
+                    parent_window.upcast::<GlobalScope>().is_secure_context()
+                },
+                // The parent lives in another script thread.
+                None => {
+                    incomplete.parent_info.is_none() ||
+                        incomplete.has_only_trustworthy_ancestor_origins
+                },
+            };
+        let (embedder_policy, opener_policy) = match metadata.headers {
+            Some(ref headers) => (
+                embedder_policy_from_headers(headers, is_secure_context),
+                opener_policy_from_headers(headers, is_secure_context),
+            ),
+            None => Default::default(),
+        };
+        // Documents with a local scheme inherit the embedder policy of their parent.
+        let embedder_policy = match parent_window {
+            Some(ref parent_window) if final_url.is_local_scheme() => {
+                parent_window.upcast::<GlobalScope>().get_embedder_policy()
+            },
+            _ => embedder_policy,
+        };
+        // https://html.spec.whatwg.org/multipage/#initialise-the-document-object
+        // Step 3.
+        let cross_origin_isolated = embedder_policy.is_compatible_with_cross_origin_isolation() &&
+            match parent_window {
+                // The "cross-origin-isolated" feature is only allowed for documents
+                // that are same origin with their parent by default.
+                Some(ref parent_window) => {
+                    parent_window
+                        .upcast::<GlobalScope>()
+                        .cross_origin_isolated() &&
+                        parent_window
+                            .upcast::<GlobalScope>()
+                            .origin()
+                            .same_origin(&origin)
+                },
+                None if incomplete.parent_info.is_some() => false,
+                None => {
+                    opener_policy == CrossOriginOpenerPolicy::SameOrigin &&
+                        final_url.is_potentially_trustworthy()
+                },
+            };
+
         // Create the window and document objects.
         let window = Window::new(
             self.js_runtime.clone(),
@@ -3290,7 +3520,12 @@ impl ScriptThread {
             self.player_context.clone(),
             self.event_loop_waker.as_ref().map(|w| (*w).clone_box()),
             self.gpu_id_hub.clone(),
+            cross_origin_isolated,
+            is_secure_context,
         );
+        window
+            .upcast::<GlobalScope>()
+            .set_embedder_policy(embedder_policy);
 
         // Initialize the browsing context for the window.
         let window_proxy = self.local_window_proxy(
@@ -3404,6 +3639,11 @@ impl ScriptThread {
         let parse_input = DOMString::new();
 
         document.set_https_state(metadata.https_state);
+        document.set_has_trustworthy_ancestor_origin(incomplete.has_trustworthy_ancestor_origin);
+        document.set_has_only_trustworthy_ancestor_origins(
+            incomplete.has_only_trustworthy_ancestor_origins,
+        );
+        document.report_security_state();
         document.set_navigation_start(incomplete.navigation_start_precise);
 
         if is_html_document == IsHTMLDocument::NonHTMLDocument {
@@ -3576,10 +3816,13 @@ impl ScriptThread {
                     node_address,
                 );
                 match (event_type, touch_result) {
-                    (TouchEventType::Down, TouchEventResult::Processed(handled)) => {
+                    (
+                        TouchEventType::Down,
+                        TouchEventResult::Processed(handled, allowed_touch_actions),
+                    ) => {
                         let result = if handled {
                             // TODO: Wait to see if preventDefault is called on the first touchmove event.
-                            EventResult::DefaultAllowed
+                            EventResult::DefaultAllowed(allowed_touch_actions)
                         } else {
                             EventResult::DefaultPrevented
                         };
@@ -3592,6 +3835,34 @@ impl ScriptThread {
                 }
             },
 
+            PenEvent(event_type, point, node_address, pen_state) => {
+                let document = match self.documents.borrow().find_document(pipeline_id) {
+                    Some(document) => document,
+                    None => return warn!("Message sent to closed pipeline {}.", pipeline_id),
+                };
+                document.handle_pen_event(
+                    self.js_runtime.rt(),
+                    event_type,
+                    point,
+                    node_address,
+                    pen_state,
+                );
+            },
+
+            DragEvent(event_type, point, node_address, data) => {
+                let document = match self.documents.borrow().find_document(pipeline_id) {
+                    Some(document) => document,
+                    None => return warn!("Message sent to closed pipeline {}.", pipeline_id),
+                };
+                document.handle_drag_event(
+                    self.js_runtime.rt(),
+                    event_type,
+                    point,
+                    node_address,
+                    data,
+                );
+            },
+
             WheelEvent(delta, point, node_address) => {
                 self.handle_wheel_event(pipeline_id, delta, point, node_address);
             },
@@ -3661,7 +3932,7 @@ impl ScriptThread {
             Some(document) => document,
             None => {
                 warn!("Message sent to closed pipeline {}.", pipeline_id);
-                return TouchEventResult::Processed(true);
+                return TouchEventResult::Processed(true, TouchActions::all());
             },
         };
         document.handle_touch_event(
@@ -3808,7 +4079,8 @@ impl ScriptThread {
             .headers(load_data.headers)
             .body(load_data.data)
             .redirect_mode(RedirectMode::Manual)
-            .origin(incomplete.origin.immutable().clone());
+            .origin(incomplete.origin.immutable().clone())
+            .has_trustworthy_ancestor_origin(incomplete.has_trustworthy_ancestor_origin);
 
         let context = ParserContext::new(id, load_data.url);
         self.incomplete_parser_contexts
@@ -0,0 +1,533 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! The script side of Content Security Policy: reporting violations
+//! (<https://w3c.github.io/webappsec-csp/#reporting>), checking string
+//! compilation, and the sandboxing flags set by the `sandbox` directive.
+
+use crate::body::Extractable;
+use crate::dom::bindings::codegen::Bindings::DocumentBinding::DocumentMethods;
+use crate::dom::bindings::codegen::Bindings::EventBinding::EventInit;
+use crate::dom::bindings::codegen::Bindings::SecurityPolicyViolationEventBinding::{
+    SecurityPolicyViolationEventDisposition, SecurityPolicyViolationEventInit,
+};
+use crate::dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::refcounted::Trusted;
+use crate::dom::bindings::reflector::DomObject;
+use crate::dom::bindings::str::{DOMString, USVString};
+use crate::dom::element::Element;
+use crate::dom::event::{Event, EventBubbles, EventCancelable};
+use crate::dom::eventtarget::EventTarget;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::node::{document_from_node, Node};
+use crate::dom::securitypolicyviolationevent::SecurityPolicyViolationEvent;
+use crate::dom::window::Window;
+use crate::realms::enter_realm;
+use crate::task_source::TaskSource;
+use content_security_policy as csp;
+use http::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
+use http::Method;
+use net_traits::request::{CredentialsMode, Destination, Referrer, RequestBuilder, RequestMode};
+use net_traits::{CoreResourceMsg, FetchChannels};
+use servo_url::ServoUrl;
+use std::collections::HashMap;
+
+bitflags! {
+    /// <https://html.spec.whatwg.org/multipage/#sandboxing-flag-set>
+    #[derive(JSTraceable, MallocSizeOf)]
+    pub struct SandboxingFlagSet: u16 {
+        const SANDBOXED_NAVIGATION = 1 << 0;
+        const SANDBOXED_AUXILIARY_NAVIGATION = 1 << 1;
+        const SANDBOXED_TOP_LEVEL_NAVIGATION = 1 << 2;
+        const SANDBOXED_PLUGINS = 1 << 3;
+        const SANDBOXED_ORIGIN = 1 << 4;
+        const SANDBOXED_FORMS = 1 << 5;
+        const SANDBOXED_POINTER_LOCK = 1 << 6;
+        const SANDBOXED_SCRIPTS = 1 << 7;
+        const SANDBOXED_AUTOMATIC_FEATURES = 1 << 8;
+        const SANDBOXED_DOCUMENT_DOMAIN = 1 << 9;
+        const SANDBOXED_MODALS = 1 << 10;
+        const SANDBOXED_ORIENTATION_LOCK = 1 << 11;
+        const SANDBOXED_PRESENTATION = 1 << 12;
+        const SANDBOXED_DOWNLOADS = 1 << 13;
+    }
+}
+
+/// Parse the `sandbox` directives of the enforced policies in `csp_list`.
+/// <https://w3c.github.io/webappsec-csp/#sandbox-init>
+pub fn sandboxing_flags_from_csp_list(csp_list: &csp::CspList) -> SandboxingFlagSet {
+    csp_list
+        .0
+        .iter()
+        .filter(|policy| matches!(policy.disposition, csp::PolicyDisposition::Enforce))
+        .flat_map(|policy| policy.directive_set.iter())
+        .filter(|directive| directive.name == "sandbox")
+        .fold(SandboxingFlagSet::empty(), |flags, directive| {
+            flags | parse_sandboxing_directive(&directive.value)
+        })
+}
+
+/// <https://html.spec.whatwg.org/multipage/#parse-a-sandboxing-directive>
+fn parse_sandboxing_directive(tokens: &[String]) -> SandboxingFlagSet {
+    let allows = |keyword: &str| {
+        tokens
+            .iter()
+            .any(|token| token.eq_ignore_ascii_case(keyword))
+    };
+
+    // Start from every flag being set, and remove the ones that are allowed.
+    let mut flags = SandboxingFlagSet::all();
+    if allows("allow-popups") {
+        flags.remove(SandboxingFlagSet::SANDBOXED_AUXILIARY_NAVIGATION);
+    }
+    if allows("allow-top-navigation") {
+        flags.remove(SandboxingFlagSet::SANDBOXED_TOP_LEVEL_NAVIGATION);
+    }
+    if allows("allow-same-origin") {
+        flags.remove(SandboxingFlagSet::SANDBOXED_ORIGIN);
+    }
+    if allows("allow-forms") {
+        flags.remove(SandboxingFlagSet::SANDBOXED_FORMS);
+    }
+    if allows("allow-pointer-lock") {
+        flags.remove(SandboxingFlagSet::SANDBOXED_POINTER_LOCK);
+    }
+    if allows("allow-scripts") {
+        flags.remove(SandboxingFlagSet::SANDBOXED_SCRIPTS);
+        flags.remove(SandboxingFlagSet::SANDBOXED_AUTOMATIC_FEATURES);
+    }
+    if allows("allow-modals") {
+        flags.remove(SandboxingFlagSet::SANDBOXED_MODALS);
+    }
+    if allows("allow-orientation-lock") {
+        flags.remove(SandboxingFlagSet::SANDBOXED_ORIENTATION_LOCK);
+    }
+    if allows("allow-presentation") {
+        flags.remove(SandboxingFlagSet::SANDBOXED_PRESENTATION);
+    }
+    if allows("allow-downloads") {
+        flags.remove(SandboxingFlagSet::SANDBOXED_DOWNLOADS);
+    }
+    flags
+}
+
+/// Parse a `Reporting-Endpoints` header into a map from endpoint name to URL.
+/// <https://w3c.github.io/reporting/#header>
+pub fn reporting_endpoints_from_headers(
+    headers: &HeaderMap,
+    base_url: &ServoUrl,
+) -> HashMap<String, ServoUrl> {
+    let mut endpoints = HashMap::new();
+    for value in headers.get_all("reporting-endpoints").iter() {
+        let value = match value.to_str() {
+            Ok(value) => value,
+            Err(_) => continue,
+        };
+        for member in value.split(',') {
+            let mut parts = member.splitn(2, '=');
+            let name = parts.next().unwrap_or("").trim();
+            let url = parts.next().unwrap_or("").trim().trim_matches('"');
+            if name.is_empty() {
+                continue;
+            }
+            // Only potentially trustworthy endpoints may receive reports.
+            match ServoUrl::parse_with_base(Some(base_url), url) {
+                Ok(url) if url.origin().is_potentially_trustworthy() => {
+                    endpoints.insert(name.to_owned(), url);
+                },
+                _ => warn!("Ignoring invalid reporting endpoint {}={}", name, url),
+            }
+        }
+    }
+    endpoints
+}
+
+/// The information about a violation that is exposed through
+/// `SecurityPolicyViolationEvent` and sent to the reporting endpoints.
+/// <https://w3c.github.io/webappsec-csp/#violation>
+#[derive(Clone, Debug)]
+pub struct CSPViolationReport {
+    document_url: String,
+    referrer: String,
+    blocked_url: String,
+    effective_directive: String,
+    original_policy: String,
+    source_file: String,
+    sample: String,
+    disposition: SecurityPolicyViolationEventDisposition,
+    status_code: u16,
+    line_number: u32,
+    column_number: u32,
+}
+
+/// <https://w3c.github.io/webappsec-csp/#deprecated-serialize-violation>
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct CSPReportUriViolationReportBody {
+    document_uri: String,
+    referrer: String,
+    blocked_uri: String,
+    effective_directive: String,
+    violated_directive: String,
+    original_policy: String,
+    disposition: &'static str,
+    status_code: u16,
+    script_sample: String,
+    source_file: String,
+    line_number: u32,
+    column_number: u32,
+}
+
+#[derive(Serialize)]
+struct CSPReportUriViolationReport {
+    #[serde(rename = "csp-report")]
+    csp_report: CSPReportUriViolationReportBody,
+}
+
+/// <https://w3c.github.io/webappsec-csp/#cspviolationreportbody>
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CSPViolationReportBody {
+    #[serde(rename = "documentURL")]
+    document_url: String,
+    referrer: String,
+    #[serde(rename = "blockedURL")]
+    blocked_url: String,
+    effective_directive: String,
+    original_policy: String,
+    source_file: String,
+    sample: String,
+    disposition: &'static str,
+    status_code: u16,
+    line_number: u32,
+    column_number: u32,
+}
+
+/// <https://w3c.github.io/reporting/#serialize-reports>
+#[derive(Serialize)]
+struct CSPViolationReportToReport {
+    #[serde(rename = "type")]
+    type_: &'static str,
+    age: u64,
+    url: String,
+    user_agent: String,
+    body: CSPViolationReportBody,
+}
+
+impl CSPViolationReport {
+    /// <https://w3c.github.io/webappsec-csp/#create-violation-for-global>
+    pub fn new(
+        global: &GlobalScope,
+        blocked_url: String,
+        effective_directive: &str,
+        policy: &csp::Policy,
+    ) -> CSPViolationReport {
+        let referrer = match global.downcast::<Window>() {
+            Some(window) => window.Document().Referrer().into(),
+            None => String::new(),
+        };
+        let disposition = match policy.disposition {
+            csp::PolicyDisposition::Enforce => SecurityPolicyViolationEventDisposition::Enforce,
+            csp::PolicyDisposition::Report => SecurityPolicyViolationEventDisposition::Report,
+        };
+        CSPViolationReport {
+            document_url: strip_url_for_use_in_reports(global.get_url()),
+            referrer,
+            blocked_url,
+            effective_directive: effective_directive.to_owned(),
+            original_policy: serialize_policy(policy),
+            // TODO: Fill in the location of the script that caused the violation.
+            source_file: String::new(),
+            sample: String::new(),
+            disposition,
+            // TODO: Use the status code of the response the global was created from.
+            status_code: 0,
+            line_number: 0,
+            column_number: 0,
+        }
+    }
+
+    /// Create a report for a violation found by the `content_security_policy` crate.
+    pub fn from_violation(global: &GlobalScope, violation: &csp::Violation) -> CSPViolationReport {
+        let blocked_url = match violation.resource {
+            csp::ViolationResource::Url(ref url) => {
+                strip_url_for_use_in_reports(ServoUrl::from_url(url.clone()))
+            },
+            csp::ViolationResource::Inline { .. } => "inline".to_owned(),
+            _ => "eval".to_owned(),
+        };
+        CSPViolationReport::new(
+            global,
+            blocked_url,
+            &violation.directive.name,
+            &violation.policy,
+        )
+    }
+
+    fn disposition_str(&self) -> &'static str {
+        match self.disposition {
+            SecurityPolicyViolationEventDisposition::Enforce => "enforce",
+            SecurityPolicyViolationEventDisposition::Report => "report",
+        }
+    }
+
+    fn to_event_init(&self) -> SecurityPolicyViolationEventInit {
+        SecurityPolicyViolationEventInit {
+            parent: EventInit {
+                bubbles: true,
+                cancelable: false,
+            },
+            documentURI: USVString(self.document_url.clone()),
+            referrer: USVString(self.referrer.clone()),
+            blockedURI: USVString(self.blocked_url.clone()),
+            violatedDirective: DOMString::from(self.effective_directive.clone()),
+            effectiveDirective: DOMString::from(self.effective_directive.clone()),
+            originalPolicy: DOMString::from(self.original_policy.clone()),
+            sourceFile: USVString(self.source_file.clone()),
+            sample: DOMString::from(self.sample.clone()),
+            disposition: self.disposition,
+            statusCode: self.status_code,
+            lineNumber: self.line_number,
+            columnNumber: self.column_number,
+        }
+    }
+
+    /// <https://w3c.github.io/webappsec-csp/#deprecated-serialize-violation>
+    fn serialize_for_report_uri(&self) -> Vec<u8> {
+        let report = CSPReportUriViolationReport {
+            csp_report: CSPReportUriViolationReportBody {
+                document_uri: self.document_url.clone(),
+                referrer: self.referrer.clone(),
+                blocked_uri: self.blocked_url.clone(),
+                effective_directive: self.effective_directive.clone(),
+                violated_directive: self.effective_directive.clone(),
+                original_policy: self.original_policy.clone(),
+                disposition: self.disposition_str(),
+                status_code: self.status_code,
+                script_sample: self.sample.clone(),
+                source_file: self.source_file.clone(),
+                line_number: self.line_number,
+                column_number: self.column_number,
+            },
+        };
+        serde_json::to_vec(&report).unwrap_or_default()
+    }
+
+    /// <https://w3c.github.io/reporting/#serialize-reports>
+    fn serialize_for_report_to(&self, user_agent: String) -> Vec<u8> {
+        let report = CSPViolationReportToReport {
+            type_: "csp-violation",
+            age: 0,
+            url: self.document_url.clone(),
+            user_agent,
+            body: CSPViolationReportBody {
+                document_url: self.document_url.clone(),
+                referrer: self.referrer.clone(),
+                blocked_url: self.blocked_url.clone(),
+                effective_directive: self.effective_directive.clone(),
+                original_policy: self.original_policy.clone(),
+                source_file: self.source_file.clone(),
+                sample: self.sample.clone(),
+                disposition: self.disposition_str(),
+                status_code: self.status_code,
+                line_number: self.line_number,
+                column_number: self.column_number,
+            },
+        };
+        serde_json::to_vec(&[report]).unwrap_or_default()
+    }
+}
+
+/// <https://w3c.github.io/webappsec-csp/#strip-url-for-use-in-reports>
+fn strip_url_for_use_in_reports(mut url: ServoUrl) -> String {
+    // Step 1.
+    if !matches!(url.scheme(), "http" | "https") {
+        return url.scheme().to_owned();
+    }
+    // Steps 2-4.
+    let url = url.as_mut_url();
+    url.set_fragment(None);
+    let _ = url.set_username("");
+    let _ = url.set_password(None);
+    url.to_string()
+}
+
+/// <https://w3c.github.io/webappsec-csp/#serialized-policy>
+fn serialize_policy(policy: &csp::Policy) -> String {
+    policy
+        .directive_set
+        .iter()
+        .map(|directive| {
+            if directive.value.is_empty() {
+                directive.name.clone()
+            } else {
+                format!("{} {}", directive.name, directive.value.join(" "))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// <https://w3c.github.io/webappsec-csp/#report-violation>
+pub fn report_csp_violation(
+    global: &GlobalScope,
+    report: CSPViolationReport,
+    policy: &csp::Policy,
+    element: Option<&Element>,
+) {
+    let message = format!(
+        "Content Security Policy: {} of {} violates the \"{}\" directive of the policy \"{}\"{}",
+        if report.disposition == SecurityPolicyViolationEventDisposition::Enforce {
+            "Blocked loading"
+        } else {
+            "Loading"
+        },
+        report.blocked_url,
+        report.effective_directive,
+        report.original_policy,
+        if report.disposition == SecurityPolicyViolationEventDisposition::Report {
+            " (report-only)"
+        } else {
+            ""
+        },
+    );
+    warn!("{}", message);
+    global.issue_page_warning(&message);
+
+    // Step 3. The event is fired at the element if it is still connected to
+    // the violating document, or otherwise at the document or worker global.
+    let target = match (element, global.downcast::<Window>()) {
+        (Some(element), Some(window))
+            if element.upcast::<Node>().is_connected() &&
+                document_from_node(element) == window.Document() =>
+        {
+            Trusted::new(element.upcast::<EventTarget>())
+        },
+        (_, Some(window)) => Trusted::new(window.Document().upcast::<EventTarget>()),
+        (_, None) => Trusted::new(global.upcast::<EventTarget>()),
+    };
+
+    // Step 4. Reports are sent to the `report-to` endpoint, falling back to
+    // the deprecated `report-uri` directive when there is none.
+    let report_to = policy
+        .directive_set
+        .iter()
+        .find(|directive| directive.name == "report-to")
+        .and_then(|directive| directive.value.first())
+        .and_then(|name| global.reporting_endpoint(name));
+    let report_uris: Vec<ServoUrl> = if report_to.is_some() {
+        vec![]
+    } else {
+        policy
+            .directive_set
+            .iter()
+            .filter(|directive| directive.name == "report-uri")
+            .flat_map(|directive| directive.value.iter())
+            .filter_map(|url| ServoUrl::parse_with_base(Some(&global.api_base_url()), url).ok())
+            .collect()
+    };
+
+    let _ = global.dom_manipulation_task_source().queue(
+        task!(report_csp_violation: move || {
+            let target = target.root();
+            let global = target.global();
+
+            // Step 3.1.
+            let event = SecurityPolicyViolationEvent::new(
+                &global,
+                atom!("securitypolicyviolation"),
+                EventBubbles::Bubbles,
+                EventCancelable::NotCancelable,
+                &report.to_event_init(),
+            );
+            event.upcast::<Event>().fire(&target);
+
+            // Step 3.2.
+            for url in report_uris {
+                send_report(&global, url, "application/csp-report", report.serialize_for_report_uri());
+            }
+
+            // Step 3.3.
+            if let Some(url) = report_to {
+                let user_agent = global.get_user_agent().into_owned();
+                send_report(
+                    &global,
+                    url,
+                    "application/reports+json",
+                    report.serialize_for_report_to(user_agent),
+                );
+            }
+        }),
+        global,
+    );
+}
+
+/// Send a serialized report to `url`, ignoring the response.
+/// <https://w3c.github.io/reporting/#try-delivery>
+fn send_report(global: &GlobalScope, url: ServoUrl, content_type: &'static str, body: Vec<u8>) {
+    let _ac = enter_realm(global);
+    let body = match body.extract(global) {
+        Ok(body) => body.into_net_request_body().0,
+        Err(_) => return warn!("Failed to extract the body of a CSP report"),
+    };
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+    let request = RequestBuilder::new(url, Referrer::NoReferrer)
+        .method(Method::POST)
+        .headers(headers)
+        .body(Some(body))
+        .mode(RequestMode::NoCors)
+        .credentials_mode(CredentialsMode::CredentialsSameOrigin)
+        .destination(Destination::Report)
+        .origin(global.origin().immutable().clone())
+        .pipeline_id(Some(global.pipeline_id()));
+    if let Err(e) = global
+        .core_resource_thread()
+        .send(CoreResourceMsg::Fetch(request, FetchChannels::Prefetch))
+    {
+        warn!("Failed to send CSP report ({})", e);
+    }
+}
+
+/// <https://w3c.github.io/webappsec-csp/#can-compile-strings>
+///
+/// Returns whether `eval` and friends may run in `global`, reporting a
+/// violation for every policy which does not allow `'unsafe-eval'`.
+pub fn is_js_evaluation_allowed(global: &GlobalScope) -> bool {
+    let csp_list = match global.get_csp_list() {
+        Some(csp_list) => csp_list,
+        None => return true,
+    };
+    let mut allowed = true;
+    for policy in &csp_list.0 {
+        let directive = policy
+            .directive_set
+            .iter()
+            .find(|directive| directive.name == "script-src")
+            .or_else(|| {
+                policy
+                    .directive_set
+                    .iter()
+                    .find(|directive| directive.name == "default-src")
+            });
+        let directive = match directive {
+            Some(directive) => directive,
+            None => continue,
+        };
+        if directive
+            .value
+            .iter()
+            .any(|source| source.eq_ignore_ascii_case("'unsafe-eval'"))
+        {
+            continue;
+        }
+        let report = CSPViolationReport::new(global, "eval".to_owned(), "script-src", policy);
+        report_csp_violation(global, report, policy, None);
+        if matches!(policy.disposition, csp::PolicyDisposition::Enforce) {
+            allowed = false;
+        }
+    }
+    allowed
+}
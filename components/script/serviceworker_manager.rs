@@ -14,14 +14,16 @@ use crate::dom::serviceworkerglobalscope::{
 use crate::dom::serviceworkerregistration::longest_prefix_match;
 use crate::script_runtime::ContextForRequestInterrupt;
 use crossbeam_channel::{unbounded, Receiver, RecvError, Sender};
+use http::Method;
 use ipc_channel::ipc::{self, IpcSender};
 use ipc_channel::router::ROUTER;
 use msg::constellation_msg::PipelineNamespace;
 use msg::constellation_msg::{ServiceWorkerId, ServiceWorkerRegistrationId};
+use net_traits::request::RequestMode;
 use net_traits::{CoreResourceMsg, CustomResponseMediator};
 use script_traits::{
-    DOMMessage, Job, JobError, JobResult, JobResultValue, JobType, SWManagerMsg, SWManagerSenders,
-    ScopeThings, ServiceWorkerManagerFactory, ServiceWorkerMsg,
+    DOMMessage, Job, JobError, JobResult, JobResultValue, JobType, NavigationPreloadState,
+    SWManagerMsg, SWManagerSenders, ScopeThings, ServiceWorkerManagerFactory, ServiceWorkerMsg,
 };
 use servo_config::pref;
 use servo_url::ImmutableOrigin;
@@ -137,6 +139,8 @@ struct ServiceWorkerRegistration {
     context: Option<ContextForRequestInterrupt>,
     /// The closing flag for the worker.
     closing: Option<Arc<AtomicBool>>,
+    /// The navigation preload enabled flag and header value.
+    navigation_preload: NavigationPreloadState,
 }
 
 impl ServiceWorkerRegistration {
@@ -150,6 +154,10 @@ impl ServiceWorkerRegistration {
             control_sender: None,
             context: None,
             closing: None,
+            navigation_preload: NavigationPreloadState {
+                enabled: false,
+                header_value: b"true".to_vec(),
+            },
         }
     }
 
@@ -187,6 +195,15 @@ impl ServiceWorkerRegistration {
         None
     }
 
+    /// Roughly <https://w3c.github.io/ServiceWorker/#install> from step 13 on, and
+    /// <https://w3c.github.io/ServiceWorker/#activation-algorithm>, making the installing
+    /// worker handle the fetches of the clients in scope.
+    fn activate(&mut self) {
+        if let Some(worker) = self.installing_worker.take() {
+            self.update_registration_state(RegistrationUpdateTarget::Active, worker);
+        }
+    }
+
     /// <https://w3c.github.io/ServiceWorker/#update-registration-state>
     fn update_registration_state(
         &mut self,
@@ -271,6 +288,14 @@ impl ServiceWorkerManager {
             if let Some(scope) = self.get_matching_scope(&mediator.load_url) {
                 if let Some(registration) = self.registrations.get(&scope) {
                     if let Some(ref worker) = registration.active_worker {
+                        let mut mediator = mediator;
+                        if registration.navigation_preload.enabled &&
+                            mediator.request.mode == RequestMode::Navigate &&
+                            mediator.request.method == Method::GET
+                        {
+                            mediator.navigation_preload =
+                                Some(registration.navigation_preload.header_value.clone());
+                        }
                         worker.send_message(ServiceWorkerScriptMsg::Response(mediator));
                         return true;
                     }
@@ -300,6 +325,11 @@ impl ServiceWorkerManager {
                     }
                 }
             },
+            ServiceWorkerMsg::SetNavigationPreload(scope_url, state) => {
+                if let Some(registration) = self.registrations.get_mut(&scope_url) {
+                    registration.navigation_preload = state;
+                }
+            },
             ServiceWorkerMsg::ScheduleJob(job) => match job.job_type {
                 JobType::Register => {
                     self.handle_register_job(job);
@@ -430,6 +460,10 @@ impl ServiceWorkerManager {
                     active_worker: registration.active_worker.as_ref().map(|worker| worker.id),
                 },
             ));
+
+            // The worker dispatches install and activate to itself once its script ran,
+            // and only handles fetches after that.
+            registration.activate();
         } else {
             // Step 2
             let _ = job
@@ -7,6 +7,7 @@ use crate::task::TaskCanceller;
 use crate::task_source::dom_manipulation::DOMManipulationTaskSource;
 use crate::task_source::file_reading::FileReadingTaskSource;
 use crate::task_source::history_traversal::HistoryTraversalTaskSource;
+use crate::task_source::idle::IdleTaskSource;
 use crate::task_source::media_element::MediaElementTaskSource;
 use crate::task_source::networking::NetworkingTaskSource;
 use crate::task_source::performance_timeline::PerformanceTimelineTaskSource;
@@ -43,6 +44,8 @@ pub struct TaskManager {
     #[ignore_malloc_size_of = "task sources are hard"]
     history_traversal_task_source: HistoryTraversalTaskSource,
     #[ignore_malloc_size_of = "task sources are hard"]
+    idle_task_source: IdleTaskSource,
+    #[ignore_malloc_size_of = "task sources are hard"]
     media_element_task_source: MediaElementTaskSource,
     #[ignore_malloc_size_of = "task sources are hard"]
     networking_task_source: NetworkingTaskSource,
@@ -65,6 +68,7 @@ impl TaskManager {
         dom_manipulation_task_source: DOMManipulationTaskSource,
         file_reading_task_source: FileReadingTaskSource,
         history_traversal_task_source: HistoryTraversalTaskSource,
+        idle_task_source: IdleTaskSource,
         media_element_task_source: MediaElementTaskSource,
         networking_task_source: NetworkingTaskSource,
         performance_timeline_task_source: PerformanceTimelineTaskSource,
@@ -78,6 +82,7 @@ impl TaskManager {
             dom_manipulation_task_source,
             file_reading_task_source,
             history_traversal_task_source,
+            idle_task_source,
             media_element_task_source,
             networking_task_source,
             performance_timeline_task_source,
@@ -138,6 +143,14 @@ impl TaskManager {
         HistoryTraversal
     );
 
+    task_source_functions!(
+        self,
+        idle_task_source_with_canceller,
+        idle_task_source,
+        IdleTaskSource,
+        Idle
+    );
+
     task_source_functions!(
         self,
         performance_timeline_task_source_with_canceller,
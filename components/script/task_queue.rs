@@ -125,7 +125,7 @@ impl<T: QueuedTaskConversion> TaskQueue<T> {
                     None => return false,
                 };
                 match task_source {
-                    TaskSourceName::PerformanceTimeline => return true,
+                    TaskSourceName::PerformanceTimeline | TaskSourceName::Idle => return true,
                     _ => {
                         // A task that will not be throttled, start counting "business"
                         self.taken_task_counter
@@ -173,6 +173,17 @@ impl<T: QueuedTaskConversion> TaskQueue<T> {
         &self.port
     }
 
+    /// Whether there are tasks left to run, including throttled ones and ones that were
+    /// sent but not yet taken.
+    pub fn has_pending_tasks(&self) -> bool {
+        !self.msg_queue.borrow().is_empty() ||
+            !self.port.is_empty() ||
+            self.throttled
+                .borrow()
+                .values()
+                .any(|queue| !queue.is_empty())
+    }
+
     /// Take a message from the front of the queue, without waiting if empty.
     pub fn recv(&self) -> Result<T, ()> {
         self.msg_queue.borrow_mut().pop_front().ok_or(())
@@ -0,0 +1,46 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+// Tasks from this task source are throttled by the task queue of the script
+// thread, so that they only run when the event loop isn't busy with other tasks.
+
+use crate::script_runtime::{CommonScriptMsg, ScriptChan, ScriptThreadEventCategory};
+use crate::task::{TaskCanceller, TaskOnce};
+use crate::task_source::{TaskSource, TaskSourceName};
+use msg::constellation_msg::PipelineId;
+use std::fmt;
+use std::result::Result;
+
+#[derive(JSTraceable)]
+/// https://w3c.github.io/requestidlecallback/#dfn-idle-task-task-source
+pub struct IdleTaskSource(pub Box<dyn ScriptChan + Send + 'static>, pub PipelineId);
+
+impl Clone for IdleTaskSource {
+    fn clone(&self) -> IdleTaskSource {
+        IdleTaskSource(self.0.clone(), self.1.clone())
+    }
+}
+
+impl fmt::Debug for IdleTaskSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "IdleTaskSource(...)")
+    }
+}
+
+impl TaskSource for IdleTaskSource {
+    const NAME: TaskSourceName = TaskSourceName::Idle;
+
+    fn queue_with_canceller<T>(&self, task: T, canceller: &TaskCanceller) -> Result<(), ()>
+    where
+        T: TaskOnce + 'static,
+    {
+        let msg = CommonScriptMsg::Task(
+            ScriptThreadEventCategory::ScriptEvent,
+            Box::new(canceller.wrap_task(task)),
+            Some(self.1),
+            Self::NAME,
+        );
+        self.0.send(msg).map_err(|_| ())
+    }
+}
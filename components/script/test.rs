@@ -60,6 +60,11 @@ pub mod size_of {
     }
 }
 
+pub mod mixed_content {
+    pub use crate::dom::document::degrades_security_state;
+    pub use net_traits::response::HttpsState;
+}
+
 pub mod srcset {
     pub use crate::dom::htmlimageelement::{parse_a_srcset_attribute, Descriptor, ImageSource};
 }
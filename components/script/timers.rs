@@ -2,6 +2,7 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
+use crate::dom::abortsignal::AbortSignalTimeoutCallback;
 use crate::dom::bindings::callback::ExceptionHandling::Report;
 use crate::dom::bindings::cell::DomRefCell;
 use crate::dom::bindings::codegen::Bindings::FunctionBinding::Function;
@@ -10,6 +11,8 @@ use crate::dom::bindings::str::DOMString;
 use crate::dom::document::FakeRequestAnimationFrameCallback;
 use crate::dom::eventsource::EventSourceTimeoutCallback;
 use crate::dom::globalscope::GlobalScope;
+use crate::dom::idledeadline::IdleCallbackTimeoutCallback;
+use crate::dom::scheduler::SchedulerDelayCallback;
 use crate::dom::testbinding::TestBindingCallback;
 use crate::dom::xmlhttprequest::XHRTimeoutCallback;
 use crate::script_module::ScriptFetchOptions;
@@ -78,6 +81,9 @@ pub enum OneshotTimerCallback {
     JsTimer(JsTimerTask),
     TestBindingCallback(TestBindingCallback),
     FakeRequestAnimationFrame(FakeRequestAnimationFrameCallback),
+    AbortSignalTimeout(AbortSignalTimeoutCallback),
+    IdleCallbackTimeout(IdleCallbackTimeoutCallback),
+    SchedulerPostTaskDelay(SchedulerDelayCallback),
 }
 
 impl OneshotTimerCallback {
@@ -88,6 +94,9 @@ impl OneshotTimerCallback {
             OneshotTimerCallback::JsTimer(task) => task.invoke(this, js_timers),
             OneshotTimerCallback::TestBindingCallback(callback) => callback.invoke(),
             OneshotTimerCallback::FakeRequestAnimationFrame(callback) => callback.invoke(),
+            OneshotTimerCallback::AbortSignalTimeout(callback) => callback.invoke(),
+            OneshotTimerCallback::IdleCallbackTimeout(callback) => callback.invoke(),
+            OneshotTimerCallback::SchedulerPostTaskDelay(callback) => callback.invoke(),
         }
     }
 }
@@ -117,6 +117,9 @@ pub enum QueryMsg {
     ElementInnerTextQuery(TrustedNodeAddress),
     ResolvedFontStyleQuery(TrustedNodeAddress, PropertyId, String),
     InnerWindowDimensionsQuery(BrowsingContextId),
+    BoxSizesQuery(OpaqueNode),
+    TextPositionQuery(OpaqueNode, Point2D<f32>),
+    CaretRectQuery(OpaqueNode, usize),
 }
 
 /// Any query to perform with this reflow.
@@ -140,6 +143,9 @@ impl ReflowGoal {
                 QueryMsg::ElementInnerTextQuery(_) => true,
                 QueryMsg::ContentBoxQuery(_) |
                 QueryMsg::ContentBoxesQuery(_) |
+                QueryMsg::BoxSizesQuery(_) |
+                QueryMsg::TextPositionQuery(..) |
+                QueryMsg::CaretRectQuery(..) |
                 QueryMsg::ClientRectQuery(_) |
                 QueryMsg::NodeScrollGeometryQuery(_) |
                 QueryMsg::NodeScrollIdQuery(_) |
@@ -162,6 +168,9 @@ impl ReflowGoal {
                 QueryMsg::ElementInnerTextQuery(_) => true,
                 QueryMsg::ContentBoxQuery(_) |
                 QueryMsg::ContentBoxesQuery(_) |
+                QueryMsg::BoxSizesQuery(_) |
+                QueryMsg::TextPositionQuery(..) |
+                QueryMsg::CaretRectQuery(..) |
                 QueryMsg::ClientRectQuery(_) |
                 QueryMsg::NodeScrollGeometryQuery(_) |
                 QueryMsg::NodeScrollIdQuery(_) |
@@ -214,6 +223,11 @@ pub struct ScriptReflow {
     pub animation_timeline_value: f64,
     /// The set of animations for this document.
     pub animations: DocumentAnimationSet,
+    /// The elements of the document's top layer, in top layer order.
+    pub top_layer: Vec<TrustedNodeAddress>,
+    /// The caret of the focused editing host, as a node and an offset in bytes in its
+    /// text, if it should be painted.
+    pub caret: Option<(TrustedNodeAddress, usize)>,
 }
 
 pub struct LayoutThreadInit {
@@ -3,7 +3,7 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
 use app_units::Au;
-use euclid::default::Rect;
+use euclid::default::{Rect, Size2D as UntypedSize2D};
 use euclid::Size2D;
 use script_traits::UntrustedNodeAddress;
 use servo_arc::Arc;
@@ -42,12 +42,36 @@ pub trait LayoutRPC {
     fn element_inner_text(&self) -> String;
     /// Get the dimensions of an iframe's inner window.
     fn inner_window_dimensions(&self) -> Option<Size2D<f32, CSSPixel>>;
+    /// Requests the sizes of the boxes of a node, as observed by `ResizeObserver`.
+    fn box_sizes(&self) -> BoxSizesResponse;
+    /// Requests the caret position in the text of an element closest to a point.
+    fn text_position(&self) -> TextPositionResponse;
+    /// Requests the rectangle of a caret in the text of a node.
+    fn caret_rect(&self) -> CaretRectResponse;
 }
 
 pub struct ContentBoxResponse(pub Option<Rect<Au>>);
 
 pub struct ContentBoxesResponse(pub Vec<Rect<Au>>);
 
+/// The sizes of the principal box of a node.
+#[derive(Clone, Copy, Debug)]
+pub struct BoxSizes {
+    /// The content box, relative to the padding box.
+    pub content_box: Rect<Au>,
+    /// The size of the border box.
+    pub border_box: UntypedSize2D<Au>,
+    /// Whether the inline axis of the box is vertical.
+    pub vertical: bool,
+}
+
+pub struct BoxSizesResponse(pub Option<BoxSizes>);
+
+/// A caret position, as a node and an offset in bytes in its text.
+pub struct TextPositionResponse(pub Option<(UntrustedNodeAddress, usize)>);
+
+pub struct CaretRectResponse(pub Option<Rect<Au>>);
+
 pub struct NodeGeometryResponse {
     pub client_rect: Rect<i32>,
 }
@@ -23,8 +23,8 @@ pub mod serializable;
 pub mod transferable;
 pub mod webdriver_msg;
 
-use crate::serializable::{BlobData, BlobImpl};
-use crate::transferable::MessagePortImpl;
+use crate::serializable::{BlobData, BlobImpl, CryptoKeyImpl};
+use crate::transferable::{ImageBitmapImpl, MessagePortImpl};
 use crate::webdriver_msg::{LoadStatus, WebDriverScriptCommand};
 use bluetooth_traits::BluetoothRequest;
 use canvas_traits::webgl::WebGLPipeline;
@@ -44,7 +44,7 @@ use log::warn;
 use media::WindowGLContext;
 use msg::constellation_msg::BackgroundHangMonitorRegister;
 use msg::constellation_msg::{
-    BlobId, BrowsingContextId, HistoryStateId, MessagePortId, PipelineId,
+    BlobId, BrowsingContextId, HistoryStateId, MessagePortId, PipelineId, SharedWorkerId,
 };
 use msg::constellation_msg::{PipelineNamespaceId, TopLevelBrowsingContextId};
 use net_traits::image::base::Image;
@@ -62,6 +62,7 @@ use servo_url::ServoUrl;
 use std::borrow::Cow;
 use std::collections::{HashMap, VecDeque};
 use std::fmt;
+use std::path::PathBuf;
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 use style_traits::CSSPixel;
@@ -78,11 +79,13 @@ use webrender_api::{BuiltDisplayListDescriptor, HitTestFlags, HitTestResult};
 
 pub use crate::script_msg::{
     DOMMessage, HistoryEntryReplacement, Job, JobError, JobResult, JobResultValue, JobType,
-    SWManagerMsg, SWManagerSenders, ScopeThings, ServiceWorkerMsg,
+    NavigationPreloadState, SWManagerMsg, SWManagerSenders, ScopeThings, ServiceWorkerMsg,
+    SharedWorkerMsg,
 };
 pub use crate::script_msg::{
     EventResult, IFrameSize, IFrameSizeMsg, LayoutMsg, LogEntry, ScriptMsg,
 };
+pub use crate::script_msg::{LockInfo, LockManagerMsg, LockManagerSnapshot, LockMode, LockRequest};
 
 /// The address of a node. Layout sends these back. They must be validated via
 /// `from_untrusted_node_address` before they can be used, because we do not trust layout.
@@ -182,6 +185,13 @@ pub struct LoadData {
 
     /// The source to use instead of a network response for a srcdoc document.
     pub srcdoc: String,
+    /// Whether any ancestor of the browsing context being navigated has a
+    /// potentially trustworthy origin, used for mixed content checks.
+    pub has_trustworthy_ancestor_origin: bool,
+    /// Whether every ancestor of the browsing context being navigated is a secure
+    /// context, which the navigated document needs to be one itself.
+    /// <https://html.spec.whatwg.org/multipage/#secure-context>
+    pub has_only_trustworthy_ancestor_origins: bool,
 }
 
 /// The result of evaluating a javascript scheme url.
@@ -214,6 +224,8 @@ impl LoadData {
             referrer: referrer,
             referrer_policy: referrer_policy,
             srcdoc: "".to_string(),
+            has_trustworthy_ancestor_origin: false,
+            has_only_trustworthy_ancestor_origins: true,
         }
     }
 }
@@ -368,6 +380,10 @@ pub enum ConstellationControlMsg {
     /// Set an iframe to be focused. Used when an element in an iframe gains focus.
     /// PipelineId is for the parent, BrowsingContextId is for the nested browsing context
     FocusIFrame(PipelineId, BrowsingContextId),
+    /// Makes an iframe enter or leave fullscreen, because the document of its nested
+    /// browsing context entered fullscreen or left it completely.
+    /// PipelineId is for the parent, BrowsingContextId is for the nested browsing context
+    SetIFrameFullscreen(PipelineId, BrowsingContextId, bool),
     /// Passes a webdriver command to the script thread for execution
     WebDriverScriptCommand(PipelineId, WebDriverScriptCommand),
     /// Notifies script thread that all animations are done
@@ -404,6 +420,25 @@ pub enum ConstellationControlMsg {
     MediaSessionAction(PipelineId, MediaSessionActionType),
     /// Notifies script thread that WebGPU server has started
     SetWebGPUPort(IpcReceiver<WebGPUMsg>),
+    /// Notifies the script thread that the browsing context of the given pipeline
+    /// has been moved into a new browsing context group and has lost its opener.
+    /// https://html.spec.whatwg.org/multipage/#disowned-its-opener
+    DisownOpener(PipelineId),
+    /// Start a new shared worker in the event loop of the given pipeline,
+    /// with a channel on which it will receive its connections.
+    /// https://html.spec.whatwg.org/multipage/#run-a-worker
+    SpawnSharedWorker {
+        /// The pipeline whose window creates the worker's outside settings.
+        pipeline_id: PipelineId,
+        /// The id of the new worker.
+        worker_id: SharedWorkerId,
+        /// The url of the worker script.
+        script_url: ServoUrl,
+        /// The name given to the worker.
+        name: String,
+        /// The receiver of messages from the constellation to the worker.
+        receiver: IpcReceiver<SharedWorkerMsg>,
+    },
 }
 
 impl fmt::Debug for ConstellationControlMsg {
@@ -431,6 +466,7 @@ impl fmt::Debug for ConstellationControlMsg {
             UpdateHistoryState(..) => "UpdateHistoryState",
             RemoveHistoryStates(..) => "RemoveHistoryStates",
             FocusIFrame(..) => "FocusIFrame",
+            SetIFrameFullscreen(..) => "SetIFrameFullscreen",
             WebDriverScriptCommand(..) => "WebDriverScriptCommand",
             TickAllAnimations(..) => "TickAllAnimations",
             WebFontLoaded(..) => "WebFontLoaded",
@@ -442,6 +478,8 @@ impl fmt::Debug for ConstellationControlMsg {
             ExitFullScreen(..) => "ExitFullScreen",
             MediaSessionAction(..) => "MediaSessionAction",
             SetWebGPUPort(..) => "SetWebGPUPort",
+            DisownOpener(..) => "DisownOpener",
+            SpawnSharedWorker { .. } => "SpawnSharedWorker",
         };
         write!(formatter, "ConstellationControlMsg::{}", variant)
     }
@@ -489,6 +527,62 @@ pub enum TouchEventType {
 #[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct TouchId(pub i32);
 
+bitflags! {
+    #[derive(Deserialize, Serialize)]
+    /// The default touch behaviors that the `touch-action` property of a touch's target
+    /// allows the compositor to perform.
+    ///
+    /// <https://w3c.github.io/pointerevents/#determining-supported-touch-behavior>
+    pub struct TouchActions: u8 {
+        /// Horizontal panning.
+        const PAN_X = 0b001;
+        /// Vertical panning.
+        const PAN_Y = 0b010;
+        /// Pinch zooming.
+        const PINCH_ZOOM = 0b100;
+    }
+}
+
+/// The state of a pen (stylus) that is touching or hovering over the screen.
+///
+/// <https://w3c.github.io/pointerevents/#pointerevent-interface>
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct PenState {
+    /// The normalized pressure of the pen tip, in the range [0, 1].
+    pub pressure: f32,
+    /// The angle between the Y-Z plane and the plane containing the pen axis and
+    /// the Y axis, in degrees in the range [-90, 90].
+    pub tilt_x: i32,
+    /// The angle between the X-Z plane and the plane containing the pen axis and
+    /// the X axis, in degrees in the range [-90, 90].
+    pub tilt_y: i32,
+    /// The clockwise rotation of the pen about its own major axis, in degrees in
+    /// the range [0, 359].
+    pub twist: i32,
+}
+
+/// The type of a drag event from the host system.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, MallocSizeOf, PartialEq, Serialize)]
+pub enum DragEventType {
+    /// A drag entered the window, or moved onto a new document.
+    Enter,
+    /// A drag moved over the window.
+    Over,
+    /// A drag left the window, or was cancelled.
+    Leave,
+    /// The dragged data was dropped onto the window.
+    Drop,
+}
+
+/// The data carried by a drag from the host system.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct DragData {
+    /// The paths of the files being dragged.
+    pub files: Vec<PathBuf>,
+    /// The plain text being dragged, if any.
+    pub text: Option<String>,
+}
+
 /// The mouse button involved in the event.
 #[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 pub enum MouseButton {
@@ -564,6 +658,21 @@ pub enum CompositorEvent {
         Point2D<f32>,
         Option<UntrustedNodeAddress>,
     ),
+    /// A pen event was generated with a location and the state of the pen. Pens that
+    /// hover over the screen generate `Move` events with a pressure of zero.
+    PenEvent(
+        TouchEventType,
+        Point2D<f32>,
+        Option<UntrustedNodeAddress>,
+        PenState,
+    ),
+    /// Data from the host system was dragged over, or dropped onto, a location.
+    DragEvent(
+        DragEventType,
+        Point2D<f32>,
+        Option<UntrustedNodeAddress>,
+        DragData,
+    ),
     /// A wheel event was generated with a delta in the X, Y, and/or Z directions
     WheelEvent(WheelDelta, Point2D<f32>, Option<UntrustedNodeAddress>),
     /// A key was pressed.
@@ -872,6 +981,8 @@ pub struct WorkerGlobalScopeInit {
     pub is_headless: bool,
     /// An optional string allowing the user agnet to be set for testing.
     pub user_agent: Cow<'static, str>,
+    /// Whether the worker's owner has the cross-origin isolated capability
+    pub cross_origin_isolated: bool,
 }
 
 /// Common entities representing a network load origin
@@ -961,8 +1072,12 @@ pub struct StructuredSerializedData {
     pub serialized: Vec<u8>,
     /// Serialized in a structured callback,
     pub blobs: Option<HashMap<BlobId, BlobImpl>>,
+    /// Serialized crypto keys, keyed by the order in which they were serialized.
+    pub crypto_keys: Option<HashMap<u32, CryptoKeyImpl>>,
     /// Transferred objects.
     pub ports: Option<HashMap<MessagePortId, MessagePortImpl>>,
+    /// Transferred image bitmaps, keyed by the order in which they were transferred.
+    pub image_bitmaps: Option<HashMap<u64, ImageBitmapImpl>>,
 }
 
 impl StructuredSerializedData {
@@ -998,11 +1113,19 @@ impl StructuredSerializedData {
             warn!("Attempt to broadcast structured serialized data including ports(should never happen).");
         }
 
+        if self.image_bitmaps.is_some() {
+            // Not panicking only because this is called from the constellation.
+            warn!("Attempt to broadcast structured serialized data including transferred image bitmaps(should never happen).");
+        }
+
         StructuredSerializedData {
             serialized,
             blobs,
+            crypto_keys: self.crypto_keys.clone(),
             // Ports cannot be broadcast.
             ports: None,
+            // Neither can other transferred objects.
+            image_bitmaps: None,
         }
     }
 }
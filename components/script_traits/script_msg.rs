@@ -12,6 +12,7 @@ use crate::LoadData;
 use crate::MessagePortMsg;
 use crate::PortMessageTask;
 use crate::StructuredSerializedData;
+use crate::TouchActions;
 use crate::WindowSizeType;
 use crate::WorkerGlobalScopeInit;
 use crate::WorkerScriptLoadOrigin;
@@ -26,8 +27,8 @@ use msg::constellation_msg::{
     BroadcastChannelRouterId, BrowsingContextId, MessagePortId, MessagePortRouterId, PipelineId,
     TopLevelBrowsingContextId,
 };
-use msg::constellation_msg::{HistoryStateId, TraversalDirection};
-use msg::constellation_msg::{ServiceWorkerId, ServiceWorkerRegistrationId};
+use msg::constellation_msg::{HistoryStateId, LockClientId, TraversalDirection};
+use msg::constellation_msg::{ServiceWorkerId, ServiceWorkerRegistrationId, SharedWorkerId};
 use net_traits::request::RequestBuilder;
 use net_traits::storage_thread::StorageType;
 use net_traits::CoreResourceMsg;
@@ -86,8 +87,8 @@ impl fmt::Debug for LayoutMsg {
 /// Whether a DOM event was prevented by web content
 #[derive(Debug, Deserialize, Serialize)]
 pub enum EventResult {
-    /// Allowed by web content
-    DefaultAllowed,
+    /// Allowed by web content, with the touch behaviors permitted by the target's `touch-action`
+    DefaultAllowed(TouchActions),
     /// Prevented by web content
     DefaultPrevented,
 }
@@ -181,6 +182,12 @@ pub enum ScriptMsg {
     ),
     /// Notifies the constellation that this frame has received focus.
     Focus,
+    /// Notifies the constellation that the document of this frame entered fullscreen, or
+    /// left it completely, so that the iframe it is in does the same in its parent.
+    FullscreenChanged(bool),
+    /// Exits fullscreen in the given browsing context and its descendants, or in the
+    /// whole browser of this frame if none is given.
+    ExitFullscreen(Option<BrowsingContextId>),
     /// Get the top-level browsing context info for a given browsing context.
     GetTopForBrowsingContext(
         BrowsingContextId,
@@ -265,6 +272,34 @@ pub enum ScriptMsg {
     ForwardDOMMessage(DOMMessage, ServoUrl),
     /// https://w3c.github.io/ServiceWorker/#schedule-job-algorithm.
     ScheduleJob(Job),
+    /// Update the navigation preload state of the service worker registration with this scope.
+    SetNavigationPreload(ServoUrl, NavigationPreloadState),
+    /// Connect to the shared worker matching the origin, url and name,
+    /// starting one if none is running.
+    /// https://html.spec.whatwg.org/multipage/#dom-sharedworker
+    ConnectSharedWorker {
+        /// The url of the worker script.
+        script_url: ServoUrl,
+        /// The name of the worker.
+        name: String,
+        /// The origin of the document connecting to the worker.
+        origin: ImmutableOrigin,
+        /// The serialized port to hand to the worker in a `connect` event.
+        data: StructuredSerializedData,
+    },
+    /// A shared worker has closed, and should not receive new connections.
+    SharedWorkerClosed(SharedWorkerId),
+    /// A new lock client, with the sender used to answer its requests.
+    /// https://w3c.github.io/web-locks/#lock-manager
+    NewLockClient(LockClientId, IpcSender<LockManagerMsg>, ImmutableOrigin),
+    /// A lock client went away, release its locks and abort its pending requests.
+    RemoveLockClient(LockClientId),
+    /// https://w3c.github.io/web-locks/#request-a-lock
+    RequestLock(LockRequest),
+    /// Release a held lock, or abort a pending request, of a lock client.
+    ReleaseLock(LockClientId, u64),
+    /// https://w3c.github.io/web-locks/#snapshot-the-lock-state
+    QueryLocks(LockClientId),
     /// Get Window Informations size and position
     GetClientWindow(IpcSender<(DeviceIntSize, DeviceIntPoint)>),
     /// Get the screen size (pixel)
@@ -310,6 +345,8 @@ impl fmt::Debug for ScriptMsg {
             ChangeRunningAnimationsState(..) => "ChangeRunningAnimationsState",
             CreateCanvasPaintThread(..) => "CreateCanvasPaintThread",
             Focus => "Focus",
+            FullscreenChanged(..) => "FullscreenChanged",
+            ExitFullscreen(..) => "ExitFullscreen",
             GetBrowsingContextInfo(..) => "GetBrowsingContextInfo",
             GetTopForBrowsingContext(..) => "GetParentBrowsingContext",
             GetChildBrowsingContextId(..) => "GetChildBrowsingContextId",
@@ -337,6 +374,14 @@ impl fmt::Debug for ScriptMsg {
             PipelineExited => "PipelineExited",
             ForwardDOMMessage(..) => "ForwardDOMMessage",
             ScheduleJob(..) => "ScheduleJob",
+            SetNavigationPreload(..) => "SetNavigationPreload",
+            ConnectSharedWorker { .. } => "ConnectSharedWorker",
+            SharedWorkerClosed(..) => "SharedWorkerClosed",
+            NewLockClient(..) => "NewLockClient",
+            RemoveLockClient(..) => "RemoveLockClient",
+            RequestLock(..) => "RequestLock",
+            ReleaseLock(..) => "ReleaseLock",
+            QueryLocks(..) => "QueryLocks",
             GetClientWindow(..) => "GetClientWindow",
             GetScreenSize(..) => "GetScreenSize",
             GetScreenAvailSize(..) => "GetScreenAvailSize",
@@ -395,10 +440,89 @@ pub enum ServiceWorkerMsg {
     ForwardDOMMessage(DOMMessage, ServoUrl),
     /// https://w3c.github.io/ServiceWorker/#schedule-job-algorithm
     ScheduleJob(Job),
+    /// Update the navigation preload state of the registration with this scope.
+    SetNavigationPreload(ServoUrl, NavigationPreloadState),
     /// Exit the service worker manager
     Exit,
 }
 
+/// The navigation preload state of a service worker registration.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct NavigationPreloadState {
+    /// https://w3c.github.io/ServiceWorker/#service-worker-registration-navigation-preload-enabled-flag
+    pub enabled: bool,
+    /// https://w3c.github.io/ServiceWorker/#service-worker-registration-navigation-preload-header-value
+    pub header_value: Vec<u8>,
+}
+
+/// Messages sent from the constellation to a shared worker.
+#[derive(Debug, Deserialize, Serialize)]
+pub enum SharedWorkerMsg {
+    /// A new client connected, with the serialized port of its connection.
+    Connect(StructuredSerializedData),
+    /// The last client went away, shut the worker down.
+    Exit,
+}
+
+/// https://w3c.github.io/web-locks/#enumdef-lockmode
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum LockMode {
+    /// Any number of clients can hold a shared lock at once.
+    Shared,
+    /// Only one client can hold an exclusive lock at once.
+    Exclusive,
+}
+
+/// A request for a lock, sent by a lock client to the constellation.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LockRequest {
+    /// The client making the request.
+    pub client_id: LockClientId,
+    /// The id of the request, unique within the client.
+    pub id: u64,
+    /// The name of the requested resource.
+    pub name: String,
+    /// The mode of the requested lock.
+    pub mode: LockMode,
+    /// Only grant the lock if it is available right away.
+    pub if_available: bool,
+    /// Release any held locks with the same name, and grant this one first.
+    pub steal: bool,
+}
+
+/// https://w3c.github.io/web-locks/#dictdef-lockinfo
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LockInfo {
+    /// The name of the resource.
+    pub name: String,
+    /// The mode of the lock.
+    pub mode: LockMode,
+    /// The id of the client holding or requesting the lock.
+    pub client_id: String,
+}
+
+/// https://w3c.github.io/web-locks/#dictdef-lockmanagersnapshot
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct LockManagerSnapshot {
+    /// The held locks.
+    pub held: Vec<LockInfo>,
+    /// The pending requests.
+    pub pending: Vec<LockInfo>,
+}
+
+/// Messages sent from the constellation to a lock client.
+#[derive(Debug, Deserialize, Serialize)]
+pub enum LockManagerMsg {
+    /// The request with this id was granted.
+    Granted(u64),
+    /// The `ifAvailable` request with this id could not be granted.
+    NotGranted(u64),
+    /// The lock held by the request with this id was stolen.
+    Stolen(u64),
+    /// The answer to the oldest query of the client.
+    Snapshot(LockManagerSnapshot),
+}
+
 #[derive(Debug, Deserialize, PartialEq, Serialize)]
 /// https://w3c.github.io/ServiceWorker/#dfn-job-type
 pub enum JobType {
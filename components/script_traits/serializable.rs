@@ -146,3 +146,39 @@ impl BlobImpl {
         &mut self.blob_data
     }
 }
+
+/// The internal slots of a serialized CryptoKey.
+/// <https://w3c.github.io/webcrypto/#cryptokey-interface-clone>
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, Serialize)]
+pub struct CryptoKeyImpl {
+    /// The type of the key, "secret", "public" or "private".
+    pub key_type: String,
+    /// Whether the key material may be exported.
+    pub extractable: bool,
+    /// The algorithm the key is used with.
+    pub algorithm: KeyAlgorithmImpl,
+    /// The operations the key may be used for.
+    pub usages: Vec<String>,
+    /// The key material: the bytes of a secret key,
+    /// or the DER encoding of a public or private key.
+    pub handle: Vec<u8>,
+}
+
+/// The members of the algorithm of a serialized CryptoKey,
+/// which depend on the kind of the algorithm.
+/// <https://w3c.github.io/webcrypto/#dfn-KeyAlgorithm>
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, Serialize)]
+pub struct KeyAlgorithmImpl {
+    /// The name of the algorithm.
+    pub name: String,
+    /// The length in bits of AES and HMAC keys.
+    pub length: Option<u32>,
+    /// The name of the hash of HMAC and RSA keys.
+    pub hash: Option<String>,
+    /// The length in bits of the modulus of RSA keys.
+    pub modulus_length: Option<u32>,
+    /// The public exponent of RSA keys.
+    pub public_exponent: Option<Vec<u8>>,
+    /// The name of the curve of EC keys.
+    pub named_curve: Option<String>,
+}
@@ -158,3 +158,18 @@ impl MessagePortImpl {
         self.state = MessagePortState::Detached;
     }
 }
+
+#[derive(Debug, Deserialize, MallocSizeOf, Serialize)]
+/// The bitmap data of a transferred ImageBitmap,
+/// moved out of the source object as part of its transfer steps.
+/// <https://html.spec.whatwg.org/multipage/#the-imagebitmap-interface:transfer-steps>
+pub struct ImageBitmapImpl {
+    /// The width of the bitmap.
+    pub width: u32,
+    /// The height of the bitmap.
+    pub height: u32,
+    /// The RGBA pixels of the bitmap.
+    pub bitmap_data: Vec<u8>,
+    /// <https://html.spec.whatwg.org/multipage/#concept-imagebitmap-origin-clean>
+    pub origin_clean: bool,
+}
@@ -585,6 +585,13 @@ where
                 }
             },
 
+            WindowEvent::DownloadAction(id, action) => {
+                let msg = ConstellationMsg::DownloadAction(id, action);
+                if let Err(e) = self.constellation_chan.send(msg) {
+                    warn!("Sending download action to constellation failed ({:?}).", e);
+                }
+            },
+
             WindowEvent::MouseWindowEventClass(mouse_window_event) => {
                 self.compositor
                     .on_mouse_window_event_class(mouse_window_event);
@@ -599,6 +606,15 @@ where
                     .on_touch_event(event_type, identifier, location);
             },
 
+            WindowEvent::Pen(event_type, location, pen_state) => {
+                self.compositor
+                    .on_pen_event(event_type, location, pen_state);
+            },
+
+            WindowEvent::Drag(event_type, location, data) => {
+                self.compositor.on_drag_event(event_type, location, data);
+            },
+
             WindowEvent::Wheel(delta, location) => {
                 self.compositor.on_wheel_event(delta, location);
             },
@@ -682,8 +698,8 @@ where
                 self.compositor.capture_webrender();
             },
 
-            WindowEvent::NewBrowser(url, browser_id) => {
-                let msg = ConstellationMsg::NewBrowser(url, browser_id);
+            WindowEvent::NewBrowser(url, browser_id, session_id) => {
+                let msg = ConstellationMsg::NewBrowser(url, browser_id, session_id);
                 if let Err(e) = self.constellation_chan.send(msg) {
                     warn!(
                         "Sending NewBrowser message to constellation failed ({:?}).",
@@ -47,6 +47,7 @@ pub enum PseudoElement {
     // APPLIES_TO_PLACEHOLDER properties expect!
 
     // Non-eager pseudos.
+    Backdrop,
     DetailsSummary,
     DetailsContent,
     ServoText,
@@ -78,6 +79,7 @@ impl ToCss for PseudoElement {
             After => "::after",
             Before => "::before",
             Selection => "::selection",
+            Backdrop => "::backdrop",
             DetailsSummary => "::-servo-details-summary",
             DetailsContent => "::-servo-details-content",
             ServoText => "::-servo-text",
@@ -220,7 +222,9 @@ impl PseudoElement {
             PseudoElement::After | PseudoElement::Before | PseudoElement::Selection => {
                 PseudoElementCascadeType::Eager
             },
-            PseudoElement::DetailsSummary => PseudoElementCascadeType::Lazy,
+            PseudoElement::Backdrop | PseudoElement::DetailsSummary => {
+                PseudoElementCascadeType::Lazy
+            },
             PseudoElement::DetailsContent |
             PseudoElement::ServoText |
             PseudoElement::ServoInputText |
@@ -287,6 +291,7 @@ pub enum NonTSPseudoClass {
     Indeterminate,
     Lang(Lang),
     Link,
+    Modal,
     PlaceholderShown,
     ReadWrite,
     ReadOnly,
@@ -348,6 +353,7 @@ impl ToCss for NonTSPseudoClass {
             Hover => ":hover",
             Indeterminate => ":indeterminate",
             Link => ":link",
+            Modal => ":modal",
             PlaceholderShown => ":placeholder-shown",
             ReadWrite => ":read-write",
             ReadOnly => ":read-only",
@@ -368,6 +374,7 @@ impl NonTSPseudoClass {
             Active => ElementState::IN_ACTIVE_STATE,
             Focus => ElementState::IN_FOCUS_STATE,
             Fullscreen => ElementState::IN_FULLSCREEN_STATE,
+            Modal => ElementState::IN_MODAL_DIALOG_STATE,
             Hover => ElementState::IN_HOVER_STATE,
             Defined => ElementState::IN_DEFINED_STATE,
             Enabled => ElementState::IN_ENABLED_STATE,
@@ -437,6 +444,7 @@ impl<'a, 'i> ::selectors::Parser<'i> for SelectorParser<'a> {
             "hover" => Hover,
             "indeterminate" => Indeterminate,
             "link" => Link,
+            "modal" => Modal,
             "placeholder-shown" => PlaceholderShown,
             "read-write" => ReadWrite,
             "read-only" => ReadOnly,
@@ -482,6 +490,7 @@ impl<'a, 'i> ::selectors::Parser<'i> for SelectorParser<'a> {
             "before" => Before,
             "after" => After,
             "selection" => Selection,
+            "backdrop" => Backdrop,
             "-servo-details-summary" => {
                 if !self.in_user_agent_stylesheet() {
                     return Err(location.new_custom_error(SelectorParseErrorKind::UnexpectedIdent(name.clone())))
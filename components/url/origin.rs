@@ -79,6 +79,28 @@ impl ImmutableOrigin {
     pub fn ascii_serialization(&self) -> String {
         self.clone().into_url_origin().ascii_serialization()
     }
+
+    /// <https://w3c.github.io/webappsec-secure-contexts/#is-origin-trustworthy>
+    pub fn is_potentially_trustworthy(&self) -> bool {
+        // Step 1
+        let (scheme, host) = match *self {
+            ImmutableOrigin::Opaque(_) => return false,
+            ImmutableOrigin::Tuple(ref scheme, ref host, _) => (scheme, host),
+        };
+
+        // Step 3
+        if scheme == "https" || scheme == "wss" {
+            return true;
+        }
+
+        match *host {
+            // Step 4
+            Host::Ipv4(ip_addr) => ip_addr.is_loopback(),
+            Host::Ipv6(ip_addr) => ip_addr.is_loopback(),
+            // Step 5
+            Host::Domain(ref domain) => domain == "localhost" || domain.ends_with(".localhost"),
+        }
+    }
 }
 
 /// Opaque identifier for URLs that have file or other schemes
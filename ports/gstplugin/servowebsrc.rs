@@ -74,6 +74,7 @@ use servo::compositing::windowing::WindowEvent;
 use servo::compositing::windowing::WindowMethods;
 use servo::embedder_traits::EmbedderProxy;
 use servo::embedder_traits::EventLoopWaker;
+use servo::msg::constellation_msg::BrowsingSessionId;
 use servo::msg::constellation_msg::TopLevelBrowsingContextId;
 use servo::servo_config::prefs::add_user_prefs;
 use servo::servo_config::prefs::read_prefs_map;
@@ -220,7 +221,11 @@ impl ServoThread {
         let mut servo = Servo::new(embedder, window, None);
 
         let id = TopLevelBrowsingContextId::new();
-        servo.handle_events(vec![WindowEvent::NewBrowser(url, id)]);
+        servo.handle_events(vec![WindowEvent::NewBrowser(
+            url,
+            id,
+            BrowsingSessionId::Default,
+        )]);
 
         let swap_chain = match webxr_mode {
             None => Some(webrender_swap_chain),
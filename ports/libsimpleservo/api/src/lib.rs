@@ -24,11 +24,11 @@ use servo::compositing::windowing::{
 use servo::config::prefs::pref_map;
 use servo::embedder_traits::resources::{self, Resource, ResourceReaderMethods};
 use servo::embedder_traits::{
-    EmbedderMsg, EmbedderProxy, MediaSessionEvent, PromptDefinition, PromptOrigin,
+    ClipboardData, EmbedderMsg, EmbedderProxy, MediaSessionEvent, PromptDefinition, PromptOrigin,
 };
 use servo::euclid::{Point2D, Rect, Scale, Size2D, Vector2D};
 use servo::keyboard_types::{Key, KeyState, KeyboardEvent};
-use servo::msg::constellation_msg::TraversalDirection;
+use servo::msg::constellation_msg::{BrowsingSessionId, TraversalDirection};
 use servo::script_traits::{TouchEventType, TouchId};
 use servo::servo_config::{opts, pref};
 use servo::servo_url::ServoUrl;
@@ -310,7 +310,11 @@ pub fn init(
             context_menu_sender: None,
         };
         let browser_id = BrowserId::new();
-        let _ = servo_glue.process_event(WindowEvent::NewBrowser(url, browser_id));
+        let _ = servo_glue.process_event(WindowEvent::NewBrowser(
+            url,
+            browser_id,
+            BrowsingSessionId::Default,
+        ));
         *s.borrow_mut() = Some(servo_glue);
     });
 
@@ -702,10 +706,13 @@ impl ServoGlue {
                 },
                 EmbedderMsg::GetClipboardContents(sender) => {
                     let contents = self.callbacks.host_callbacks.get_clipboard_contents();
-                    let _ = sender.send(contents.unwrap_or("".to_owned()));
+                    let _ = sender.send(contents.map(ClipboardData::from_text).unwrap_or_default());
                 },
-                EmbedderMsg::SetClipboardContents(text) => {
-                    self.callbacks.host_callbacks.set_clipboard_contents(text);
+                EmbedderMsg::SetClipboardContents(data) => {
+                    // Only text can be exchanged with the host clipboard.
+                    if let Some(text) = data.text() {
+                        self.callbacks.host_callbacks.set_clipboard_contents(text);
+                    }
                 },
                 EmbedderMsg::CloseBrowser => {
                     // TODO: close the appropriate "tab".
@@ -783,8 +790,16 @@ impl ServoGlue {
                 EmbedderMsg::Panic(reason, backtrace) => {
                     self.callbacks.host_callbacks.on_panic(reason, backtrace);
                 },
+                EmbedderMsg::SelectDownloadPath(_, sender) => {
+                    // Downloads are not supported by this embedding yet.
+                    let _ = sender.send(None);
+                },
                 EmbedderMsg::Status(..) |
                 EmbedderMsg::SelectFiles(..) |
+                EmbedderMsg::DownloadStarted(..) |
+                EmbedderMsg::DownloadProgress(..) |
+                EmbedderMsg::DownloadStatusChanged(..) |
+                EmbedderMsg::SecurityStateChanged(..) |
                 EmbedderMsg::MoveTo(..) |
                 EmbedderMsg::ResizeTo(..) |
                 EmbedderMsg::Keyboard(..) |
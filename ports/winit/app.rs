@@ -12,6 +12,7 @@ use crate::{headed_window, headless_window};
 use winit::WindowId;
 use servo::compositing::windowing::WindowEvent;
 use servo::config::opts::{self, parse_url_or_filename};
+use servo::msg::constellation_msg::BrowsingSessionId;
 use servo::servo_config::pref;
 use servo::servo_url::ServoUrl;
 use servo::{BrowserId, Servo};
@@ -80,7 +81,11 @@ impl App {
 
         let mut servo = Servo::new(embedder, window.clone(), user_agent);
         let browser_id = BrowserId::new();
-        servo.handle_events(vec![WindowEvent::NewBrowser(get_default_url(), browser_id)]);
+        servo.handle_events(vec![WindowEvent::NewBrowser(
+            get_default_url(),
+            browser_id,
+            BrowsingSessionId::Default,
+        )]);
         servo.setup_logging();
 
         register_window(window);
@@ -8,7 +8,7 @@ use euclid::{Point2D, Vector2D};
 use keyboard_types::{Key, KeyboardEvent, Modifiers, ShortcutMatcher};
 use servo::compositing::windowing::{WebRenderDebugOption, WindowEvent};
 use servo::embedder_traits::{
-    ContextMenuResult, EmbedderMsg, FilterPattern, PermissionRequest, PromptDefinition, PromptOrigin, PromptResult,
+    ClipboardData, ContextMenuResult, EmbedderMsg, FilterPattern, PermissionRequest, PromptDefinition, PromptOrigin, PromptResult,
     PermissionPrompt,
 };
 use servo::msg::constellation_msg::TopLevelBrowsingContextId as BrowserId;
@@ -24,6 +24,7 @@ use std::env;
 use std::fs::File;
 use std::io::Write;
 use std::mem;
+use std::path::PathBuf;
 use std::rc::Rc;
 use std::thread;
 use std::time::Duration;
@@ -426,12 +427,13 @@ where
                         },
                         None => "".to_owned(),
                     };
-                    if let Err(e) = sender.send(contents) {
+                    if let Err(e) = sender.send(ClipboardData::from_text(contents)) {
                         warn!("Failed to send clipboard ({})", e);
                     }
                 }
-                EmbedderMsg::SetClipboardContents(text) => {
-                    if let Some(ref mut ctx) = self.clipboard_ctx {
+                EmbedderMsg::SetClipboardContents(data) => {
+                    // The clipboard crate only supports text.
+                    if let (Some(ctx), Some(text)) = (self.clipboard_ctx.as_mut(), data.text()) {
                         if let Err(e) = ctx.set_contents(text) {
                             warn!("Error setting clipboard contents ({})", e);
                         }
@@ -524,6 +526,27 @@ where
                 EmbedderMsg::ShowContextMenu(sender, ..) => {
                     let _ = sender.send(ContextMenuResult::Ignored);
                 }
+                EmbedderMsg::SelectDownloadPath(filename, sender) => {
+                    let path = if opts::get().headless {
+                        None
+                    } else {
+                        get_download_path(filename)
+                    };
+                    if let Err(e) = sender.send(path) {
+                        let reason = format!("Failed to send SelectDownloadPath response: {}", e);
+                        self.event_queue.push(WindowEvent::SendError(None, reason));
+                    };
+                },
+                EmbedderMsg::DownloadStarted(_id, url, path) => {
+                    info!("Downloading {} to {}", url, path.display());
+                },
+                EmbedderMsg::DownloadProgress(..) => {},
+                EmbedderMsg::DownloadStatusChanged(id, status) => {
+                    debug!("Download {:?} is now {:?}", id, status);
+                },
+                EmbedderMsg::SecurityStateChanged(state) => {
+                    debug!("Security state is now {:?}", state);
+                },
             }
         }
     }
@@ -631,6 +654,18 @@ fn get_selected_files(patterns: Vec<FilterPattern>, multiple_files: bool) -> Opt
         .expect("Thread spawning failed")
 }
 
+fn get_download_path(filename: String) -> Option<PathBuf> {
+    thread::Builder::new()
+        .name("Save file".to_owned())
+        .spawn(move || {
+            tinyfiledialogs::save_file_dialog("Save file", &tiny_dialog_escape(&filename))
+                .map(PathBuf::from)
+        })
+        .unwrap()
+        .join()
+        .expect("Thread spawning failed")
+}
+
 fn sanitize_url(request: &str) -> Option<ServoUrl> {
     let request = request.trim();
     ServoUrl::parse(&request)
@@ -0,0 +1,27 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use script::test::mixed_content::{degrades_security_state, HttpsState};
+use servo_url::ServoUrl;
+
+#[test]
+fn test_insecure_subresource_of_secure_document() {
+    let url = ServoUrl::parse("http://example.com/image.png").unwrap();
+    assert!(degrades_security_state(HttpsState::Modern, &url));
+}
+
+#[test]
+fn test_trustworthy_subresource_of_secure_document() {
+    let url = ServoUrl::parse("https://example.com/image.png").unwrap();
+    assert!(!degrades_security_state(HttpsState::Modern, &url));
+    let url = ServoUrl::parse("http://localhost/image.png").unwrap();
+    assert!(!degrades_security_state(HttpsState::Modern, &url));
+}
+
+#[test]
+fn test_subresource_of_localhost_document() {
+    // http://localhost is potentially trustworthy, but isn't delivered securely.
+    let url = ServoUrl::parse("http://example.com/image.png").unwrap();
+    assert!(!degrades_security_state(HttpsState::None, &url));
+}
@@ -7,12 +7,15 @@ importScripts("interfaces.js");
 
 // IMPORTANT: Do not change the list below without review from a DOM peer!
 test_interfaces([
+  "AbortController",
+  "AbortSignal",
   "Blob",
   "BroadcastChannel",
   "ByteLengthQueuingStrategy",
   "CanvasGradient",
   "CanvasPattern",
   "CloseEvent",
+  "CompressionStream",
   "CountQueuingStrategy",
   "DOMMatrix",
   "DOMMatrixReadOnly",
@@ -23,6 +26,7 @@ test_interfaces([
   "DOMRectReadOnly",
   "DOMStringList",
   "CustomEvent",
+  "DecompressionStream",
   "DedicatedWorkerGlobalScope",
   "DOMException",
   "ErrorEvent",
@@ -36,6 +40,17 @@ test_interfaces([
   "FormData",
   "Headers",
   "History",
+  "IDBCursor",
+  "IDBCursorWithValue",
+  "IDBDatabase",
+  "IDBFactory",
+  "IDBIndex",
+  "IDBKeyRange",
+  "IDBObjectStore",
+  "IDBOpenDBRequest",
+  "IDBRequest",
+  "IDBTransaction",
+  "IDBVersionChangeEvent",
   "ImageData",
   "MessageChannel",
   "MessageEvent",
@@ -53,8 +68,13 @@ test_interfaces([
   "ReadableStream",
   "Request",
   "Response",
+  "SecurityPolicyViolationEvent",
   "TextDecoder",
+  "TextDecoderStream",
   "TextEncoder",
+  "TextEncoderStream",
+  "TransformStream",
+  "TransformStreamDefaultController",
   "URL",
   "URLSearchParams",
   "WebSocket",
@@ -62,6 +82,9 @@ test_interfaces([
   "WorkerGlobalScope",
   "WorkerLocation",
   "WorkerNavigator",
+  "WritableStream",
+  "WritableStreamDefaultController",
+  "WritableStreamDefaultWriter",
   "XMLHttpRequest",
   "XMLHttpRequestEventTarget",
   "XMLHttpRequestUpload",